@@ -0,0 +1,75 @@
+// Throughput of the UART codec. Run with `cargo bench`; criterion reports the
+// time per iteration and the frames per second it amounts to.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+use ti154::frame::MTFrame;
+use ti154::parser::Parser;
+use ti154::subsystem::mac::sreq::DataReq;
+use ti154::subsystem::MTFramePayload;
+use ti154::types::{Address, AddressMode, PanId, Security, ShortAddress, TxOption};
+
+// Frames in the stream fed to the parser.
+const STREAM_FRAMES: u64 = 100;
+
+fn data_req(length: usize) -> MTFrame {
+    DataReq {
+        dest_address: Address::Addr16Bit(ShortAddress { address: 0x0001 }),
+        dest_pan_id: PanId(0xabcd),
+        src_address_mode: AddressMode::Addr16Bit,
+        handle: 0x01,
+        tx_option: TxOption::ACK,
+        channel: 0,
+        power: 0,
+        security: Security::none(),
+        include_fh_ies: 0,
+        data_length: length as u16,
+        ie_length: 0,
+        data_payload: (0..length).map(|x| x as u8).collect(),
+        ie_payload: Vec::new(),
+    }
+    .into_mt_frame()
+}
+
+fn codec(c: &mut Criterion) {
+    let frame = data_req(100);
+    let bytes = frame.encode_to_uart_transport_frame();
+
+    let mut group = c.benchmark_group("codec");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("frame_check_sequence", |b| {
+        b.iter(|| MTFrame::compute_frame_check_sequence(black_box(&bytes[1..bytes.len() - 1])))
+    });
+    group.bench_function("encode", |b| {
+        b.iter(|| black_box(&frame).encode_to_uart_transport_frame())
+    });
+    group.bench_function("decode_payload", |b| {
+        b.iter(|| MTFramePayload::try_from_frame(black_box(&frame)).unwrap())
+    });
+    group.finish();
+}
+
+fn parser(c: &mut Criterion) {
+    let bytes = data_req(100).encode_to_uart_transport_frame();
+    let stream: Vec<u8> = (0..STREAM_FRAMES)
+        .flat_map(|_| bytes.iter().copied())
+        .collect();
+
+    let mut group = c.benchmark_group("parser");
+    group.throughput(Throughput::Elements(STREAM_FRAMES));
+    let mut parser = Parser::new();
+    group.bench_function("feed", |b| {
+        b.iter(|| {
+            for byte in black_box(&stream) {
+                black_box(parser.feed(*byte));
+            }
+        })
+    });
+    group.bench_function("feed_slice", |b| {
+        b.iter(|| parser.feed_slice(black_box(&stream)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, codec, parser);
+criterion_main!(benches);
@@ -0,0 +1,14 @@
+// Every command decoder, selected by the command code in the frame header.
+// Whatever decodes has to encode back to a frame without panicking.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ti154::subsystem::decode_any;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(payload) = decode_any(data) {
+        payload
+            .into_mt_frame()
+            .encode_to_uart_transport_frame();
+    }
+});
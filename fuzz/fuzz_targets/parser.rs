@@ -0,0 +1,20 @@
+// The UART transport parser and the reassembly of extended frames, fed one
+// byte at a time and in slices, which have to agree.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ti154::parser::Parser;
+use ti154::subsystem::MTFramePayload;
+
+fuzz_target!(|data: &[u8]| {
+    let mut by_byte = Parser::new();
+    let frames: Vec<_> = data.iter().filter_map(|byte| by_byte.feed(*byte)).collect();
+
+    let mut by_slice = Parser::new();
+    let sliced = by_slice.feed_slice(data);
+    assert_eq!(format!("{:?}", frames), format!("{:?}", sliced));
+
+    for frame in frames.into_iter().flatten() {
+        let _ = MTFramePayload::try_from_frame(&frame);
+    }
+});
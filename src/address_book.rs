@@ -0,0 +1,137 @@
+use crate::error::Error;
+use crate::reader::Reader;
+use crate::subsystem::MTFramePayload;
+use crate::types::{Address, AssociationStatus, ExtendedAddress, ShortAddress};
+use crate::writer::BufMut;
+use std::collections::HashMap;
+
+const NO_SHORT_ADDRESS: u16 = 0xfffe;
+const BROADCAST_SHORT_ADDRESS: u16 = 0xffff;
+
+#[derive(Debug, Default)]
+pub struct AddressBook {
+    short_addresses: HashMap<ExtendedAddress, ShortAddress>,
+    extended_addresses: HashMap<ShortAddress, ExtendedAddress>,
+}
+
+impl AddressBook {
+    pub fn new() -> AddressBook {
+        AddressBook::default()
+    }
+
+    pub fn insert(&mut self, extended_address: ExtendedAddress, short_address: ShortAddress) {
+        self.remove(&extended_address);
+
+        if short_address.address == NO_SHORT_ADDRESS
+            || short_address.address == BROADCAST_SHORT_ADDRESS
+        {
+            return;
+        }
+
+        if let Some(previous) = self.extended_addresses.remove(&short_address) {
+            self.short_addresses.remove(&previous);
+        }
+
+        self.short_addresses.insert(extended_address, short_address);
+        self.extended_addresses
+            .insert(short_address, extended_address);
+    }
+
+    pub fn remove(&mut self, extended_address: &ExtendedAddress) -> Option<ShortAddress> {
+        let short_address = self.short_addresses.remove(extended_address)?;
+        self.extended_addresses.remove(&short_address);
+        Some(short_address)
+    }
+
+    pub fn clear(&mut self) {
+        self.short_addresses.clear();
+        self.extended_addresses.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.short_addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.short_addresses.is_empty()
+    }
+
+    pub fn short_address(&self, extended_address: &ExtendedAddress) -> Option<ShortAddress> {
+        self.short_addresses.get(extended_address).copied()
+    }
+
+    pub fn extended_address(&self, short_address: &ShortAddress) -> Option<ExtendedAddress> {
+        self.extended_addresses.get(short_address).copied()
+    }
+
+    /// Mappings ordered by short address.
+    pub fn entries(&self) -> Vec<(ExtendedAddress, ShortAddress)> {
+        let mut entries: Vec<_> = self
+            .short_addresses
+            .iter()
+            .map(|(extended_address, short_address)| (*extended_address, *short_address))
+            .collect();
+        entries.sort_by_key(|(_, short_address)| short_address.address);
+        entries
+    }
+
+    /// Serializes the book for persistence, read back with `try_decode`.
+    pub fn encode(&self) -> Vec<u8> {
+        let entries = self.entries();
+        let mut buffer = Vec::new();
+        buffer.put_u16_le(entries.len() as u16);
+        for (extended_address, short_address) in entries {
+            extended_address.encode_into(&mut buffer);
+            short_address.encode_into(&mut buffer);
+        }
+        buffer
+    }
+
+    pub fn try_decode(buffer: &[u8]) -> Result<AddressBook, Error> {
+        let mut cursor = Reader::new(buffer);
+        let count = cursor.field("count", Reader::read_u16_le)?;
+        let mut book = AddressBook::new();
+        for _ in 0..count {
+            let extended_address = cursor.field("extended_address", ExtendedAddress::try_decode)?;
+            let short_address = cursor.field("short_address", ShortAddress::try_decode)?;
+            book.insert(extended_address, short_address);
+        }
+        Ok(book)
+    }
+
+    /// Returns the short address of the device when it is known, falling back to
+    /// extended addressing otherwise.
+    pub fn resolve(&self, extended_address: &ExtendedAddress) -> Address {
+        match self.short_address(extended_address) {
+            Some(short_address) => Address::Addr16Bit(short_address),
+            None => Address::Addr64Bit(*extended_address),
+        }
+    }
+
+    /// Updates the book from device-table and association traffic. Frames that
+    /// carry no address mapping are ignored, which includes associate and data
+    /// indications as they only name one address.
+    pub fn observe(&mut self, payload: &MTFramePayload) {
+        use MTFramePayload::*;
+        match payload {
+            #[cfg(feature = "security-tables")]
+            MAC_AddDeviceReq_SREQ(x) => self.insert(x.ext_addr, x.short_addr),
+            #[cfg(feature = "security-tables")]
+            MAC_DeleteDeviceReq_SREQ(x) => {
+                self.remove(&x.ext_addr);
+            }
+            #[cfg(feature = "security-tables")]
+            MAC_DeleteAllDevicesReq_SREQ(_) => self.clear(),
+            MAC_AssociateRsp_SREQ(x) if x.assoc_status == AssociationStatus::Successful => {
+                self.insert(x.extended_address, x.assoc_short_address)
+            }
+            MAC_OrphanRsp_SREQ(x) if x.associated_member => {
+                self.insert(x.extended_address, x.assoc_short_address)
+            }
+            MAC_DisassociateInd_AREQ(x) => {
+                self.remove(&x.extended_address);
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::subsystem::mac::areq::AssociateInd;
+use crate::subsystem::mac::sreq::AssociateRsp;
+use crate::types::{AssociationStatus, ShortAddress};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Copy, Clone)]
+pub struct AdmissionConfig {
+    // Indications that can be admitted back to back.
+    pub burst: u32,
+    // Time needed to earn one more admission.
+    pub refill_interval: Duration,
+    // Indications kept waiting before new ones are answered with PANAtCapacity.
+    pub max_deferred: usize,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        AdmissionConfig {
+            burst: 4,
+            refill_interval: Duration::from_millis(250),
+            max_deferred: 16,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct AdmissionStats {
+    pub admitted: u64,
+    pub deferred: u64,
+    pub rejected: u64,
+    pub released: u64,
+}
+
+#[derive(Debug)]
+pub enum Admission {
+    Admitted(AssociateInd),
+    Deferred,
+    Rejected(AssociateRsp),
+}
+
+#[derive(Debug)]
+pub struct AdmissionControl {
+    config: AdmissionConfig,
+    tokens: u32,
+    last_refill: Option<Instant>,
+    deferred: VecDeque<AssociateInd>,
+    stats: AdmissionStats,
+}
+
+impl AdmissionControl {
+    pub fn new(config: AdmissionConfig) -> AdmissionControl {
+        AdmissionControl {
+            config,
+            tokens: config.burst,
+            last_refill: None,
+            deferred: VecDeque::new(),
+            stats: AdmissionStats::default(),
+        }
+    }
+
+    pub fn on_associate_ind(&mut self, indication: AssociateInd, now: Instant) -> Admission {
+        self.refill(now);
+
+        if self.deferred.is_empty() && self.tokens > 0 {
+            self.tokens -= 1;
+            self.stats.admitted += 1;
+            return Admission::Admitted(indication);
+        }
+
+        if self.deferred.len() < self.config.max_deferred {
+            self.deferred.push_back(indication);
+            self.stats.deferred += 1;
+            return Admission::Deferred;
+        }
+
+        self.stats.rejected += 1;
+        Admission::Rejected(at_capacity_response(&indication))
+    }
+
+    /// Releases the oldest deferred indication once the rate allows it.
+    pub fn poll(&mut self, now: Instant) -> Option<AssociateInd> {
+        self.refill(now);
+
+        if self.tokens == 0 {
+            return None;
+        }
+
+        let indication = self.deferred.pop_front()?;
+        self.tokens -= 1;
+        self.stats.released += 1;
+        Some(indication)
+    }
+
+    pub fn deferred(&self) -> usize {
+        self.deferred.len()
+    }
+
+    pub fn stats(&self) -> AdmissionStats {
+        self.stats
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let last_refill = *self.last_refill.get_or_insert(now);
+        let interval = self.config.refill_interval;
+        if interval == Duration::from_secs(0) {
+            self.tokens = self.config.burst;
+            return;
+        }
+
+        let earned = now.saturating_duration_since(last_refill).as_nanos() / interval.as_nanos();
+        if earned == 0 {
+            return;
+        }
+
+        self.tokens = (self.tokens as u128 + earned).min(self.config.burst as u128) as u32;
+        self.last_refill = Some(last_refill + interval * earned.min(u32::MAX as u128) as u32);
+    }
+}
+
+pub fn at_capacity_response(indication: &AssociateInd) -> AssociateRsp {
+    AssociateRsp {
+        extended_address: indication.extended_address,
+        assoc_short_address: ShortAddress { address: 0xffff },
+        assoc_status: AssociationStatus::PANAtCapacity,
+        security: indication.security,
+    }
+}
@@ -0,0 +1,82 @@
+use crate::diff::{diff_bytes, diff_fields, FieldChange};
+use crate::frame::CommandCode;
+use crate::subsystem::MTFramePayload;
+use crate::types::{CommandType, MTSubsystem};
+use std::ops::Range;
+use std::sync::RwLock;
+
+#[derive(Debug, Clone)]
+pub struct AuditMismatch {
+    pub subsystem: MTSubsystem,
+    pub cmd_type: CommandType,
+    pub id: u8,
+    pub original: Vec<u8>,
+    pub reencoded: Vec<u8>,
+    pub byte_ranges: Vec<Range<usize>>,
+    // Fields whose bytes differ, with `left` from the original and `right`
+    // from the re-encoded payload.
+    pub fields: Vec<FieldChange>,
+}
+
+pub type AuditHook = fn(&AuditMismatch);
+
+static HOOK: RwLock<Option<AuditHook>> = RwLock::new(None);
+
+/// Installs the hook mismatches are passed to. Without one they are
+/// dropped, `check` returns them to the caller instead.
+pub fn set_hook(hook: AuditHook) {
+    if let Ok(mut current) = HOOK.write() {
+        *current = Some(hook);
+    }
+}
+
+/// Re-encodes `payload` and compares it against the bytes it was decoded
+/// from. With the `audit` feature this runs on every decoded payload.
+pub fn check(
+    subsystem: &MTSubsystem,
+    cmd_type: &CommandType,
+    id: u8,
+    original: &[u8],
+    payload: &MTFramePayload,
+) -> Option<AuditMismatch> {
+    let mut reencoded = Vec::new();
+    payload.encode_into(&mut reencoded);
+
+    if reencoded == original {
+        return None;
+    }
+
+    let command = CommandCode {
+        is_extended: false,
+        cmd_type: *cmd_type,
+        subsystem: *subsystem,
+        id,
+    };
+    let fields = diff_fields(&command, original, &reencoded);
+
+    Some(AuditMismatch {
+        subsystem: *subsystem,
+        cmd_type: *cmd_type,
+        id,
+        original: original.to_vec(),
+        byte_ranges: diff_bytes(original, &reencoded),
+        reencoded,
+        fields,
+    })
+}
+
+pub fn audit(
+    subsystem: &MTSubsystem,
+    cmd_type: &CommandType,
+    id: u8,
+    original: &[u8],
+    payload: &MTFramePayload,
+) {
+    let hook = match HOOK.read().ok().and_then(|hook| *hook) {
+        Some(hook) => hook,
+        None => return,
+    };
+    if let Some(mismatch) = check(subsystem, cmd_type, id, original, payload) {
+        hook(&mismatch);
+    }
+}
@@ -0,0 +1,619 @@
+use crate::error::{Error, FhError};
+use crate::frame::{CommandCode, MTFrame};
+#[cfg(feature = "fh")]
+use crate::pib_config::PibConfig;
+#[cfg(feature = "fh")]
+use crate::reader::Reader;
+#[cfg(feature = "fh")]
+#[cfg(feature = "app")]
+use crate::subsystem::app;
+use crate::subsystem::mac::{areq, sreq, srsp};
+#[cfg(feature = "sys")]
+use crate::subsystem::sys;
+#[cfg(feature = "app")]
+use crate::types::APPCommandId;
+#[cfg(all(feature = "fh", feature = "wisun-ie"))]
+use crate::types::PhyId;
+use crate::types::{
+    Address, CommandType, MACCommandId, MACPIBAttributeId, MTSubsystem, PanId, Status,
+};
+#[cfg(feature = "fh")]
+use crate::types::{ChannelFunction, FHPIBAttributeId, FhSchedule};
+#[cfg(feature = "sys")]
+use crate::types::{ResetReason, SYSCommandId};
+#[cfg(feature = "sys")]
+use alloc::boxed::Box;
+use alloc::collections::btree_map::Entry;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+pub trait Client {
+    /// Sends a SREQ and blocks until the matching SRSP is received.
+    fn request(&mut self, frame: MTFrame) -> Result<MTFrame, Error>;
+
+    /// Like `request`, but gives up with `Error::Timeout` once `timeout`
+    /// elapses. Clients that cannot bound the wait fall back to `request`.
+    fn request_with_timeout(
+        &mut self,
+        frame: MTFrame,
+        timeout: Duration,
+    ) -> Result<MTFrame, Error> {
+        let _ = timeout;
+        self.request(frame)
+    }
+}
+
+pub fn get_mac_pib<C: Client>(
+    client: &mut C,
+    attribute_id: MACPIBAttributeId,
+) -> Result<[u8; 16], Error> {
+    let frame = client.request(sreq::GetReq { attribute_id }.into_mt_frame())?;
+    expect_response(&frame, MTSubsystem::MAC, MACCommandId::GetReq as u8)?;
+    let response = srsp::GetReq::try_decode(&frame.payload)?.check()?;
+    Ok(response.data)
+}
+
+pub fn set_mac_pib<C: Client>(
+    client: &mut C,
+    attribute_id: MACPIBAttributeId,
+    attribute_value: [u8; 16],
+) -> Result<(), Error> {
+    let request = sreq::SetReq {
+        attribute_id,
+        attribute_value,
+    };
+    let frame = client.request(request.into_mt_frame())?;
+    expect_response(&frame, MTSubsystem::MAC, MACCommandId::SetReq as u8)?;
+    srsp::SetReq::try_decode(&frame.payload)?.check()?;
+    Ok(())
+}
+
+/// Moves the running PAN to `pan_id`, e.g. after a PAN ID conflict.
+pub fn update_pan_id<C: Client>(client: &mut C, pan_id: PanId) -> Result<(), Error> {
+    let frame = client.request(sreq::UpdatePANIdReq { pan_id }.into_mt_frame())?;
+    expect_response(&frame, MTSubsystem::MAC, MACCommandId::UpdatePANIdReq as u8)?;
+    srsp::UpdatePANIdReq::try_decode(&frame.payload)?.check()?;
+    Ok(())
+}
+
+pub fn data_req<C: Client>(client: &mut C, request: sreq::DataReq) -> Result<(), Error> {
+    let frame = client.request(request.into_mt_frame())?;
+    expect_response(&frame, MTSubsystem::MAC, MACCommandId::DataReq as u8)?;
+    srsp::DataReq::try_decode(&frame.payload)?.check()?;
+    Ok(())
+}
+
+/// Enables the frequency hopping engine. Call `fh_start` once the FH PIB
+/// attributes are configured.
+#[cfg(feature = "fh")]
+pub fn fh_enable<C: Client>(client: &mut C) -> Result<(), Error> {
+    let frame = client.request(sreq::FHEnableReq {}.into_mt_frame())?;
+    expect_response(&frame, MTSubsystem::MAC, MACCommandId::FHEnableReq as u8)?;
+    srsp::FHEnableReq::try_decode(&frame.payload)?.check()?;
+    Ok(())
+}
+
+#[cfg(feature = "fh")]
+pub fn fh_start<C: Client>(client: &mut C) -> Result<(), Error> {
+    let frame = client.request(sreq::FHStartReq {}.into_mt_frame())?;
+    expect_response(&frame, MTSubsystem::MAC, MACCommandId::FHStartReq as u8)?;
+    srsp::FHStartReq::try_decode(&frame.payload)?.check()?;
+    Ok(())
+}
+
+#[cfg(feature = "fh")]
+pub fn get_fh_pib<C: Client>(
+    client: &mut C,
+    attribute_id: FHPIBAttributeId,
+) -> Result<srsp::FHGetReq, Error> {
+    let frame = client.request(sreq::FHGetReq { attribute_id }.into_mt_frame())?;
+    expect_response(&frame, MTSubsystem::MAC, MACCommandId::FHGetReq as u8)?;
+    srsp::FHGetReq::try_decode(&frame.payload)?.check()
+}
+
+/// Reads the unicast and broadcast schedule attributes. Fixed channels are
+/// only reported for schedules using `ChannelFunction::Fixed`.
+#[cfg(feature = "fh")]
+pub fn read_schedule<C: Client>(client: &mut C) -> Result<FhSchedule, Error> {
+    let mut read =
+        |attribute_id| -> Result<Vec<u8>, Error> { Ok(get_fh_pib(client, attribute_id)?.data) };
+    let uc_dwell_interval = read(FHPIBAttributeId::UCDwellInterval)?;
+    let uc_channel_function = read(FHPIBAttributeId::UCChannelFunction)?;
+    let uc_fixed_channel = read(FHPIBAttributeId::UCFixedChannel)?;
+    let bc_interval = read(FHPIBAttributeId::BCInterval)?;
+    let bc_dwell_interval = read(FHPIBAttributeId::BCDwellInterval)?;
+    let bc_channel_function = read(FHPIBAttributeId::BCChannelFunction)?;
+    let bc_fixed_channel = read(FHPIBAttributeId::BCFixedChannel)?;
+
+    let uc_channel_function = Reader::new(&uc_channel_function[..])
+        .field("uc_channel_function", ChannelFunction::try_decode)?;
+    let bc_channel_function = Reader::new(&bc_channel_function[..])
+        .field("bc_channel_function", ChannelFunction::try_decode)?;
+    let fixed_channel = |function, data: &[u8], name| -> Result<Option<u16>, Error> {
+        match function {
+            ChannelFunction::Fixed => Ok(Some(Reader::new(data).field(name, Reader::read_u16_le)?)),
+            _ => Ok(None),
+        }
+    };
+
+    Ok(FhSchedule {
+        uc_dwell_interval: Reader::new(&uc_dwell_interval[..])
+            .field("uc_dwell_interval", Reader::read_u8)?,
+        uc_channel_function,
+        uc_fixed_channel: fixed_channel(
+            uc_channel_function,
+            &uc_fixed_channel,
+            "uc_fixed_channel",
+        )?,
+        bc_interval: Reader::new(&bc_interval[..]).field("bc_interval", Reader::read_u32_le)?,
+        bc_dwell_interval: Reader::new(&bc_dwell_interval[..])
+            .field("bc_dwell_interval", Reader::read_u8)?,
+        bc_channel_function,
+        bc_fixed_channel: fixed_channel(
+            bc_channel_function,
+            &bc_fixed_channel,
+            "bc_fixed_channel",
+        )?,
+    })
+}
+
+/// Validates `schedule` and writes it, failures name the attribute with
+/// `Error::PibWriteFailed`.
+#[cfg(feature = "fh")]
+pub fn write_schedule<C: Client>(client: &mut C, schedule: &FhSchedule) -> Result<(), Error> {
+    schedule.validate()?;
+    PibConfig::new().fh_schedule(schedule).apply(client)
+}
+
+/// Sends a Wi-SUN async request after validating its channel list against
+/// the current PHY and the unicast excluded channels.
+#[cfg(all(feature = "fh", feature = "wisun-ie"))]
+pub fn ws_async<C: Client>(client: &mut C, request: sreq::WSAsyncReq) -> Result<(), Error> {
+    let phy_id = get_mac_pib(client, MACPIBAttributeId::PhyCurrentDescriptorId)?;
+    let excluded = get_fh_pib(client, FHPIBAttributeId::UCExcludedChannels)?.channel_mask()?;
+    request.validate(PhyId::from_u8(phy_id[0]), &excluded)?;
+
+    let frame = client.request(request.into_mt_frame())?;
+    expect_response(&frame, MTSubsystem::MAC, MACCommandId::WSAsyncReq as u8)?;
+    srsp::WSAsyncReq::try_decode(&frame.payload)?.check()?;
+    Ok(())
+}
+
+/// Passes an APP message to the application code on the coprocessor.
+/// Answers, if any, come back as `APP_AppMsgInd_AREQ`.
+#[cfg(feature = "app")]
+pub fn app_msg<C: Client>(client: &mut C, request: app::sreq::AppMsgReq) -> Result<(), Error> {
+    let frame = client.request(request.into_mt_frame())?;
+    expect_response(&frame, MTSubsystem::APP, APPCommandId::AppMsg as u8)?;
+    app::srsp::AppMsgReq::try_decode(&frame.payload)?.check()?;
+    Ok(())
+}
+
+/// Remembers the destination of each data request by MSDU handle, so a
+/// failed confirm can name the device it was meant for.
+#[derive(Debug, Default, Clone)]
+pub struct PendingData {
+    destinations: BTreeMap<u8, Address>,
+}
+
+impl PendingData {
+    pub fn new() -> PendingData {
+        PendingData::default()
+    }
+
+    pub fn sent(&mut self, request: &sreq::DataReq) {
+        self.destinations
+            .insert(request.handle, request.dest_address);
+    }
+
+    pub fn len(&self) -> usize {
+        self.destinations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.destinations.is_empty()
+    }
+
+    /// Forgets the request and returns its destination. FHAPI statuses turn
+    /// into `Error::Fh`, any other failure into `Error::CommandFailed`.
+    pub fn confirm(&mut self, confirm: &areq::DataCnf) -> Result<Option<Address>, Error> {
+        let destination = self.destinations.remove(&confirm.handle);
+        if let Err(status) = confirm.status.into_result() {
+            return Err(destination
+                .and_then(|destination| FhError::from_status(status, destination))
+                .map_or(Error::CommandFailed(status), Error::Fh));
+        }
+        Ok(destination)
+    }
+}
+
+/// Hands out MSDU handles that no unconfirmed request uses, each tied to a
+/// context returned once the data confirm arrives.
+#[derive(Debug, Clone)]
+pub struct HandleAllocator<T> {
+    next: u8,
+    pending: BTreeMap<u8, T>,
+}
+
+impl<T> Default for HandleAllocator<T> {
+    fn default() -> Self {
+        HandleAllocator {
+            next: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> HandleAllocator<T> {
+    pub fn new() -> HandleAllocator<T> {
+        HandleAllocator::default()
+    }
+
+    /// Fails with `Error::TableFull` while all 256 handles wait for their
+    /// confirm.
+    pub fn allocate(&mut self, context: T) -> Result<u8, Error> {
+        for _ in 0..=u8::MAX {
+            let handle = self.next;
+            self.next = self.next.wrapping_add(1);
+            if let Entry::Vacant(entry) = self.pending.entry(handle) {
+                entry.insert(context);
+                return Ok(handle);
+            }
+        }
+        Err(Error::TableFull)
+    }
+
+    /// Frees `handle`, e.g. when the data request itself was rejected.
+    pub fn release(&mut self, handle: u8) -> Option<T> {
+        self.pending.remove(&handle)
+    }
+
+    pub fn confirm(&mut self, confirm: &areq::DataCnf) -> Option<T> {
+        self.release(confirm.handle)
+    }
+
+    pub fn get(&self, handle: u8) -> Option<&T> {
+        self.pending.get(&handle)
+    }
+
+    pub fn get_mut(&mut self, handle: u8) -> Option<&mut T> {
+        self.pending.get_mut(&handle)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u8, &T)> {
+        self.pending
+            .iter()
+            .map(|(handle, context)| (*handle, context))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+pub fn expect_response(frame: &MTFrame, subsystem: MTSubsystem, id: u8) -> Result<(), Error> {
+    if frame.header.command.is(CommandType::SRSP, subsystem, id) {
+        Ok(())
+    } else {
+        Err(Error::UnexpectedResponse(frame.clone()))
+    }
+}
+
+/// Responses and confirms reporting the outcome of a request.
+pub trait Confirm: Sized {
+    fn status(&self) -> Status;
+
+    /// Turns a non-success status into `Error::CommandFailed`.
+    fn check(self) -> Result<Self, Error> {
+        match self.status().into_result() {
+            Ok(()) => Ok(self),
+            Err(status) => Err(Error::CommandFailed(status)),
+        }
+    }
+}
+
+macro_rules! impl_confirm {
+    ($($(#[$attr:meta])* $name:path),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            impl Confirm for $name {
+                fn status(&self) -> Status {
+                    self.status
+                }
+            }
+        )*
+    };
+}
+
+impl_confirm!(
+    areq::DataCnf,
+    areq::PurgeCnf,
+    areq::AssociateCnf,
+    areq::DisassociateCnf,
+    areq::PollCnf,
+    areq::ScanCnf,
+    areq::StartCnf,
+    #[cfg(feature = "wisun-ie")]
+    areq::WSAsyncCnf,
+    srsp::Init,
+    srsp::DataReq,
+    srsp::PurgeReq,
+    srsp::AssociateReq,
+    srsp::AssociateRsp,
+    srsp::DisassociateReq,
+    srsp::GetReq,
+    srsp::SetReq,
+    #[cfg(feature = "security-tables")]
+    srsp::SecurityGetReq,
+    #[cfg(feature = "security-tables")]
+    srsp::SecuritySetReq,
+    srsp::UpdatePANIdReq,
+    #[cfg(feature = "security-tables")]
+    srsp::AddDeviceReq,
+    #[cfg(feature = "security-tables")]
+    srsp::DeleteDeviceReq,
+    #[cfg(feature = "security-tables")]
+    srsp::DeleteAllDevicesReq,
+    #[cfg(feature = "security-tables")]
+    srsp::DeleteKeyReq,
+    #[cfg(feature = "security-tables")]
+    srsp::ReadKeyReq,
+    #[cfg(feature = "security-tables")]
+    srsp::WriteKeyReq,
+    srsp::OrphanRsp,
+    srsp::PollReq,
+    srsp::ResetReq,
+    srsp::ScanReq,
+    srsp::StartReq,
+    srsp::SyncReq,
+    srsp::SetRxGainReq,
+    #[cfg(feature = "wisun-ie")]
+    srsp::WSAsyncReq,
+    #[cfg(feature = "fh")]
+    srsp::FHEnableReq,
+    #[cfg(feature = "fh")]
+    srsp::FHStartReq,
+    #[cfg(feature = "fh")]
+    srsp::FHGetReq,
+    #[cfg(feature = "fh")]
+    srsp::FHSetReq,
+    srsp::SrcMatchEnableReq,
+    srsp::SrcMatchAddEntryReq,
+    srsp::SrcMatchDeleteEntryReq,
+    srsp::SrcMatchAckAllPendingReq,
+    #[cfg(feature = "app")]
+    app::srsp::AppMsgReq,
+);
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub timeout: Duration,
+    // Attempts in total, including the first one.
+    pub attempts: u32,
+    pub backoff: Duration,
+    pub backoff_factor: u32,
+    pub max_backoff: Duration,
+    // Commands needing a different timeout, e.g. scans or resets.
+    pub timeouts: Vec<(MTSubsystem, u8, Duration)>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            timeout: Duration::from_millis(500),
+            attempts: 3,
+            backoff: Duration::from_millis(50),
+            backoff_factor: 2,
+            max_backoff: Duration::from_secs(1),
+            timeouts: Vec::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn timeout_for(&self, command: &CommandCode) -> Duration {
+        self.timeouts
+            .iter()
+            .find(|(subsystem, id, _)| *subsystem == command.subsystem && *id == command.id)
+            .map_or(self.timeout, |(_, _, timeout)| *timeout)
+    }
+
+    /// Wait before attempt `attempt + 1`, `attempt` counting from 1.
+    pub fn backoff_after(&self, attempt: u32) -> Duration {
+        let factor = self
+            .backoff_factor
+            .checked_pow(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+/// Sends every request with the policy's timeout and repeats it when the
+/// SRSP does not arrive. Other errors are returned right away. A lost SRSP
+/// does not mean the coprocessor missed the request, so only wrap clients
+/// whose requests are safe to repeat.
+pub struct Retrying<C> {
+    client: C,
+    policy: RetryPolicy,
+    sleep: fn(Duration),
+}
+
+impl<C: Client> Retrying<C> {
+    #[cfg(feature = "std")]
+    pub fn new(client: C, policy: RetryPolicy) -> Retrying<C> {
+        Retrying::with_sleep(client, policy, std::thread::sleep)
+    }
+
+    pub fn with_sleep(client: C, policy: RetryPolicy, sleep: fn(Duration)) -> Retrying<C> {
+        Retrying {
+            client,
+            policy,
+            sleep,
+        }
+    }
+
+    pub fn client(&mut self) -> &mut C {
+        &mut self.client
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    pub fn into_inner(self) -> C {
+        self.client
+    }
+}
+
+impl<C: Client> Client for Retrying<C> {
+    fn request(&mut self, frame: MTFrame) -> Result<MTFrame, Error> {
+        let timeout = self.policy.timeout_for(&frame.header.command);
+        self.request_with_timeout(frame, timeout)
+    }
+
+    fn request_with_timeout(
+        &mut self,
+        frame: MTFrame,
+        timeout: Duration,
+    ) -> Result<MTFrame, Error> {
+        let mut attempt = 1;
+        loop {
+            match self.client.request_with_timeout(frame.clone(), timeout) {
+                Err(Error::Timeout { .. }) if attempt < self.policy.attempts => {
+                    (self.sleep)(self.policy.backoff_after(attempt));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Routine run by `ResetAware` to bring the coprocessor back to a known
+/// state after it resets.
+#[cfg(feature = "sys")]
+pub type Reinit<C> = Box<dyn FnMut(&mut C, &sys::areq::ResetInd) -> Result<(), Error>>;
+
+/// Watches for SYS_RESET_IND. A reset arriving instead of the SRSP fails the
+/// request in flight with `Error::DeviceReset`, as its response is lost with
+/// the coprocessor state. Indications received outside a request are passed
+/// to `handle`.
+#[cfg(feature = "sys")]
+pub struct ResetAware<C> {
+    client: C,
+    reinit: Option<Reinit<C>>,
+    last_reset: Option<ResetReason>,
+    resets: u32,
+}
+
+#[cfg(feature = "sys")]
+impl<C: Client> ResetAware<C> {
+    pub fn new(client: C) -> ResetAware<C> {
+        ResetAware {
+            client,
+            reinit: None,
+            last_reset: None,
+            resets: 0,
+        }
+    }
+
+    pub fn with_reinit<F>(client: C, reinit: F) -> ResetAware<C>
+    where
+        F: FnMut(&mut C, &sys::areq::ResetInd) -> Result<(), Error> + 'static,
+    {
+        ResetAware {
+            reinit: Some(Box::new(reinit)),
+            ..ResetAware::new(client)
+        }
+    }
+
+    pub fn client(&mut self) -> &mut C {
+        &mut self.client
+    }
+
+    pub fn into_inner(self) -> C {
+        self.client
+    }
+
+    /// Number of resets seen so far.
+    pub fn resets(&self) -> u32 {
+        self.resets
+    }
+
+    /// Reason of the last reset not yet taken.
+    pub fn take_reset(&mut self) -> Option<ResetReason> {
+        self.last_reset.take()
+    }
+
+    /// Returns the reset indication carried by `frame`, if any, after running
+    /// the reinitialization routine.
+    pub fn handle(&mut self, frame: &MTFrame) -> Result<Option<sys::areq::ResetInd>, Error> {
+        if !is_reset_ind(frame) {
+            return Ok(None);
+        }
+        let indication = sys::areq::ResetInd::try_decode(&frame.payload)?;
+        self.reset(&indication)?;
+        Ok(Some(indication))
+    }
+
+    fn reset(&mut self, indication: &sys::areq::ResetInd) -> Result<(), Error> {
+        self.resets += 1;
+        self.last_reset = Some(indication.reason);
+        match self.reinit.as_mut() {
+            Some(reinit) => reinit(&mut self.client, indication),
+            None => Ok(()),
+        }
+    }
+
+    fn check(&mut self, result: Result<MTFrame, Error>) -> Result<MTFrame, Error> {
+        let frame = match result {
+            Ok(frame) if is_reset_ind(&frame) => frame,
+            Err(Error::UnexpectedResponse(frame)) if is_reset_ind(&frame) => frame,
+            result => return result,
+        };
+        let indication = sys::areq::ResetInd::try_decode(&frame.payload)?;
+        self.reset(&indication)?;
+        Err(Error::DeviceReset(indication.reason))
+    }
+}
+
+#[cfg(feature = "sys")]
+impl<C: Client> Client for ResetAware<C> {
+    fn request(&mut self, frame: MTFrame) -> Result<MTFrame, Error> {
+        let result = self.client.request(frame);
+        self.check(result)
+    }
+
+    fn request_with_timeout(
+        &mut self,
+        frame: MTFrame,
+        timeout: Duration,
+    ) -> Result<MTFrame, Error> {
+        let result = self.client.request_with_timeout(frame, timeout);
+        self.check(result)
+    }
+}
+
+/// Returns the capabilities reported by SYS_PING, the cheapest way to check
+/// a port has a coprocessor behind it.
+#[cfg(feature = "sys")]
+pub fn ping<C: Client>(client: &mut C) -> Result<u16, Error> {
+    let frame = client.request(sys::sreq::PingReq {}.into_mt_frame())?;
+    expect_response(&frame, MTSubsystem::SYS, SYSCommandId::PingReq as u8)?;
+    Ok(sys::srsp::PingReq::try_decode(&frame.payload)?.capabilities)
+}
+
+#[cfg(feature = "sys")]
+fn is_reset_ind(frame: &MTFrame) -> bool {
+    frame
+        .header
+        .command
+        .is(CommandType::AREQ, MTSubsystem::SYS, SYSCommandId::ResetInd)
+}
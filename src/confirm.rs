@@ -0,0 +1,153 @@
+use crate::client::{self, Client, HandleAllocator};
+use crate::error::Error;
+use crate::frame::MTFrame;
+use crate::subsystem::mac::{areq, sreq};
+use crate::types::{CommandType, MACCommandId, MTSubsystem};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+enum Slot {
+    Waiting(Option<Waker>),
+    Confirmed(areq::DataCnf),
+    // The future was dropped, the confirm only frees the handle.
+    Abandoned,
+}
+
+/// Matches data confirms to the requests sent with `send_data` by MSDU
+/// handle. Frames read from the coprocessor have to be passed to `handle`,
+/// clones share the pending requests.
+#[derive(Clone, Default)]
+pub struct Confirms {
+    handles: Arc<Mutex<HandleAllocator<Slot>>>,
+}
+
+impl Confirms {
+    pub fn new() -> Confirms {
+        Confirms::default()
+    }
+
+    /// Sends `request` with a free handle in place of its own. The returned
+    /// future resolves with the data confirm, not with the SRSP, which only
+    /// says the request was queued.
+    pub fn send_data<C: Client>(
+        &self,
+        client: &mut C,
+        mut request: sreq::DataReq,
+    ) -> Result<DataConfirm, Error> {
+        let handle = self.lock().allocate(Slot::Waiting(None))?;
+        request.handle = handle;
+        if let Err(error) = client::data_req(client, request) {
+            self.lock().release(handle);
+            return Err(error);
+        }
+        Ok(DataConfirm {
+            handle,
+            confirms: self.clone(),
+            completed: false,
+        })
+    }
+
+    /// Completes the request `frame` confirms. Returns false for any other
+    /// frame, including confirms of requests sent another way.
+    pub fn handle(&self, frame: &MTFrame) -> Result<bool, Error> {
+        let command = &frame.header.command;
+        if !command.is(
+            CommandType::AREQ,
+            MTSubsystem::MAC,
+            MACCommandId::DataCnf as u8,
+        ) {
+            return Ok(false);
+        }
+        let confirm = areq::DataCnf::try_decode(&frame.payload)?;
+        let mut handles = self.lock();
+        let handle = confirm.handle;
+        let waker = match handles.get_mut(handle) {
+            Some(slot @ Slot::Waiting(_)) => {
+                match std::mem::replace(slot, Slot::Confirmed(confirm)) {
+                    Slot::Waiting(waker) => waker,
+                    _ => None,
+                }
+            }
+            Some(Slot::Abandoned) => {
+                handles.release(handle);
+                return Ok(true);
+            }
+            // Repeated confirms keep the first one.
+            Some(Slot::Confirmed(_)) | None => return Ok(false),
+        };
+        drop(handles);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(true)
+    }
+
+    /// Requests waiting for their confirm.
+    pub fn pending(&self) -> usize {
+        self.lock()
+            .iter()
+            .filter(|(_, slot)| matches!(slot, Slot::Waiting(_)))
+            .count()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HandleAllocator<Slot>> {
+        self.handles
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+/// Data confirm of a request sent with `Confirms::send_data`.
+pub struct DataConfirm {
+    handle: u8,
+    confirms: Confirms,
+    // The confirm was returned and the handle released, it may belong to
+    // another request by now.
+    completed: bool,
+}
+
+impl DataConfirm {
+    pub fn handle(&self) -> u8 {
+        self.handle
+    }
+}
+
+impl Future for DataConfirm {
+    type Output = areq::DataCnf;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<areq::DataCnf> {
+        if self.completed {
+            return Poll::Pending;
+        }
+        let this = &mut *self;
+        let mut handles = this.confirms.lock();
+        if let Some(Slot::Confirmed(_)) = handles.get(this.handle) {
+            if let Some(Slot::Confirmed(confirm)) = handles.release(this.handle) {
+                this.completed = true;
+                return Poll::Ready(confirm);
+            }
+        }
+        if let Some(slot) = handles.get_mut(this.handle) {
+            *slot = Slot::Waiting(Some(cx.waker().clone()));
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for DataConfirm {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        let mut handles = self.confirms.lock();
+        match handles.get_mut(self.handle) {
+            Some(slot @ Slot::Waiting(_)) => *slot = Slot::Abandoned,
+            Some(Slot::Confirmed(_)) => {
+                handles.release(self.handle);
+            }
+            _ => (),
+        }
+    }
+}
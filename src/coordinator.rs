@@ -0,0 +1,352 @@
+use crate::address_book::AddressBook;
+use crate::client::{self, Client, Confirm, HandleAllocator};
+use crate::error::{Error, FhError};
+use crate::frame::MTFrame;
+use crate::subsystem::mac::{areq, sreq, srsp};
+use crate::subsystem::MTFramePayload;
+use crate::types::*;
+
+#[derive(Debug, Clone)]
+pub struct CoordinatorConfig {
+    pub pan_id: PanId,
+    pub short_address: ShortAddress,
+    pub logical_channel: u8,
+    pub channel_page: u8,
+    pub phy_id: PhyId,
+    // Short addresses handed out to joining devices count up from this one.
+    pub first_device_address: u16,
+    // Extra attributes written after the reset, before the PAN is started.
+    pub pib: Vec<(MACPIBAttributeId, [u8; 16])>,
+}
+
+impl Default for CoordinatorConfig {
+    fn default() -> Self {
+        CoordinatorConfig {
+            pan_id: PanId(0x1234),
+            short_address: ShortAddress::from(0x0000),
+            logical_channel: 0,
+            channel_page: 9,
+            phy_id: PhyId::STD_US_915_PHY_1,
+            first_device_address: 0x0001,
+            pib: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum JoinDecision {
+    Accept,
+    Reject(AssociationStatus),
+}
+
+#[derive(Debug)]
+pub enum CoordinatorEvent {
+    Started,
+    Joined {
+        extended_address: ExtendedAddress,
+        short_address: ShortAddress,
+    },
+    Rejected {
+        extended_address: ExtendedAddress,
+        status: AssociationStatus,
+    },
+    Left(ExtendedAddress),
+    // Another PAN uses the same identifier, see `Coordinator::update_pan_id`.
+    PanIdConflict(PanId),
+    Data(areq::DataInd),
+    DataCnf(areq::DataCnf),
+    // A data request failed in the frequency hopping layer, e.g. because the
+    // destination is not a neighbor yet.
+    FhError {
+        handle: u8,
+        error: FhError,
+    },
+    // Anything the coordinator does not act on itself.
+    Other(MTFramePayload),
+}
+
+/// Runs a non-beacon PAN: resets and configures the coprocessor, starts the
+/// network, answers association requests through `policy` and turns the
+/// asynchronous frames into events.
+pub struct Coordinator<C, P> {
+    client: C,
+    config: CoordinatorConfig,
+    policy: P,
+    address_book: AddressBook,
+    next_address: u16,
+    // Destination of every data request waiting for its confirm.
+    handles: HandleAllocator<Address>,
+}
+
+impl<C, P> Coordinator<C, P>
+where
+    C: Client,
+    P: FnMut(&areq::AssociateInd) -> JoinDecision,
+{
+    pub fn new(client: C, config: CoordinatorConfig, policy: P) -> Coordinator<C, P> {
+        Coordinator {
+            client,
+            next_address: config.first_device_address,
+            config,
+            policy,
+            address_book: AddressBook::new(),
+            handles: HandleAllocator::new(),
+        }
+    }
+
+    pub fn client(&mut self) -> &mut C {
+        &mut self.client
+    }
+
+    pub fn address_book(&self) -> &AddressBook {
+        &self.address_book
+    }
+
+    /// Resets the MAC, writes the PIB and requests the PAN start. The start
+    /// confirm arrives later as `CoordinatorEvent::Started`. A
+    /// `first_device_address` of 0xfffe or above fails with
+    /// `InvalidShortAddress` before anything is sent.
+    pub fn start(&mut self) -> Result<(), Error> {
+        let first = ShortAddress::from(self.config.first_device_address);
+        if first.u16() >= 0xfffe {
+            return Err(Error::InvalidShortAddress(first));
+        }
+
+        let frame = self
+            .client
+            .request(sreq::ResetReq { set_default: true }.into_mt_frame())?;
+        client::expect_response(&frame, MTSubsystem::MAC, MACCommandId::ResetReq as u8)?;
+        srsp::ResetReq::try_decode(&frame.payload)?.check()?;
+
+        let mut pib = vec![
+            (
+                MACPIBAttributeId::ShortAddress,
+                self.config.short_address.u16(),
+            ),
+            (MACPIBAttributeId::PANId, self.config.pan_id.u16()),
+            (MACPIBAttributeId::RxOnWhenIdle, 1),
+            (MACPIBAttributeId::AssociationPermit, 1),
+        ]
+        .into_iter()
+        .map(|(attribute_id, value)| {
+            let mut attribute_value = [0x00; 16];
+            attribute_value[..2].copy_from_slice(&value.to_le_bytes());
+            (attribute_id, attribute_value)
+        })
+        .collect::<Vec<_>>();
+        pib.extend(self.config.pib.iter().cloned());
+
+        for (attribute_id, attribute_value) in pib {
+            client::set_mac_pib(&mut self.client, attribute_id, attribute_value)?;
+        }
+
+        self.request_start(false)
+    }
+
+    /// Handles an asynchronous frame from the coprocessor.
+    pub fn handle(&mut self, frame: &MTFrame) -> Result<CoordinatorEvent, Error> {
+        use MTFramePayload::*;
+        let event = match MTFramePayload::try_from_frame(frame)? {
+            MAC_StartCnf_AREQ(x) => {
+                x.check()?;
+                CoordinatorEvent::Started
+            }
+            MAC_AssociateInd_AREQ(x) => self.on_associate_ind(x)?,
+            MAC_DisassociateInd_AREQ(x) => {
+                self.address_book.remove(&x.extended_address);
+                CoordinatorEvent::Left(x.extended_address)
+            }
+            MAC_SyncLossInd_AREQ(x) if x.status == Status::PANIdConflict => {
+                CoordinatorEvent::PanIdConflict(x.pan_id)
+            }
+            MAC_DataInd_AREQ(x) => CoordinatorEvent::Data(x),
+            MAC_DataCnf_AREQ(x) => {
+                let destination = self.handles.confirm(&x);
+                let error = match (x.status.into_result(), destination) {
+                    (Err(status), Some(destination)) => FhError::from_status(status, destination),
+                    _ => None,
+                };
+                match error {
+                    Some(error) => CoordinatorEvent::FhError {
+                        handle: x.handle,
+                        error,
+                    },
+                    None => CoordinatorEvent::DataCnf(x),
+                }
+            }
+            payload => CoordinatorEvent::Other(payload),
+        };
+        Ok(event)
+    }
+
+    pub fn events<'a, I>(
+        &'a mut self,
+        frames: I,
+    ) -> impl Iterator<Item = Result<CoordinatorEvent, Error>> + 'a
+    where
+        I: IntoIterator<Item = MTFrame>,
+        I::IntoIter: 'a,
+    {
+        frames.into_iter().map(move |frame| self.handle(&frame))
+    }
+
+    /// Only the data indications out of `frames`, the other events are still
+    /// handled on the way.
+    pub fn data_indications<'a, I>(
+        &'a mut self,
+        frames: I,
+    ) -> impl Iterator<Item = Result<areq::DataInd, Error>> + 'a
+    where
+        I: IntoIterator<Item = MTFrame>,
+        I::IntoIter: 'a,
+    {
+        self.events(frames).filter_map(|event| match event {
+            Ok(CoordinatorEvent::Data(indication)) => Some(Ok(indication)),
+            Ok(_) => None,
+            Err(error) => Some(Err(error)),
+        })
+    }
+
+    /// Moves the running PAN to `pan_id`. Later data requests use it too.
+    pub fn update_pan_id(&mut self, pan_id: PanId) -> Result<(), Error> {
+        client::update_pan_id(&mut self.client, pan_id)?;
+        self.config.pan_id = pan_id;
+        Ok(())
+    }
+
+    /// Answers a `CoordinatorEvent::PanIdConflict`: asks `choose` for a new
+    /// identifier given the conflicting one, moves the PAN to it and
+    /// broadcasts a coordinator realignment so the joined devices follow.
+    pub fn resolve_pan_id_conflict<F>(&mut self, choose: F) -> Result<PanId, Error>
+    where
+        F: FnOnce(PanId) -> PanId,
+    {
+        let current = self.config.pan_id;
+        let pan_id = choose(current);
+        if !pan_id.is_valid() || pan_id == current {
+            return Err(Error::InvalidPanId(pan_id));
+        }
+
+        self.update_pan_id(pan_id)?;
+        self.request_start(true)?;
+        Ok(pan_id)
+    }
+
+    /// Queues `data` for `destination` and returns the MSDU handle the data
+    /// confirm will carry.
+    pub fn send(&mut self, destination: Address, data: Vec<u8>) -> Result<u8, Error> {
+        let handle = self.handles.allocate(destination)?;
+        let request = sreq::DataReq {
+            dest_address: destination,
+            dest_pan_id: self.config.pan_id,
+            src_address_mode: AddressMode::Addr16Bit,
+            handle,
+            tx_option: TxOption::ACK,
+            channel: 0,
+            power: 0,
+            security: Security::none(),
+            include_fh_ies: 0,
+            data_length: data.len() as u16,
+            ie_length: 0,
+            data_payload: data,
+            ie_payload: Vec::new(),
+        };
+        if let Err(error) = client::data_req(&mut self.client, request) {
+            self.handles.release(handle);
+            return Err(error);
+        }
+        Ok(handle)
+    }
+
+    fn request_start(&mut self, coord_realignment: bool) -> Result<(), Error> {
+        let request = sreq::StartReq {
+            start_time: 0,
+            pan_id: self.config.pan_id,
+            logical_channel: self.config.logical_channel,
+            channel_page: self.config.channel_page,
+            phy_id: self.config.phy_id,
+            beacon_order: 15,
+            super_frame_order: 15,
+            pan_coordinator: true,
+            battery_life_ext: false,
+            coord_realignment,
+            realign_security: Security::none(),
+            beacon_security: Security::none(),
+            start_fh: false,
+            enh_beacon_order: 0,
+            ofs_time_slot: 0,
+            non_beacon_order: 0,
+            num_ies: 0,
+            ie_id_list: Vec::new(),
+        };
+        let frame = self.client.request(request.into_mt_frame())?;
+        client::expect_response(&frame, MTSubsystem::MAC, MACCommandId::StartReq as u8)?;
+        srsp::StartReq::try_decode(&frame.payload)?.check()?;
+        Ok(())
+    }
+
+    fn on_associate_ind(
+        &mut self,
+        indication: areq::AssociateInd,
+    ) -> Result<CoordinatorEvent, Error> {
+        let extended_address = indication.extended_address;
+        let decision = (self.policy)(&indication);
+
+        let (short_address, status) = match decision {
+            JoinDecision::Accept => match self.allocate(&extended_address) {
+                Ok(short_address) => (short_address, AssociationStatus::Successful),
+                Err(Error::TableFull) => {
+                    (ShortAddress::from(0xffff), AssociationStatus::PANAtCapacity)
+                }
+                Err(error) => return Err(error),
+            },
+            JoinDecision::Reject(status) => (ShortAddress::from(0xffff), status),
+        };
+
+        let response = sreq::AssociateRsp {
+            extended_address,
+            assoc_short_address: short_address,
+            assoc_status: status,
+            security: Security::none(),
+        };
+        let frame = self.client.request(response.into_mt_frame())?;
+        client::expect_response(&frame, MTSubsystem::MAC, MACCommandId::AssociateRsp as u8)?;
+        srsp::AssociateRsp::try_decode(&frame.payload)?.check()?;
+
+        if status != AssociationStatus::Successful {
+            return Ok(CoordinatorEvent::Rejected {
+                extended_address,
+                status,
+            });
+        }
+
+        self.address_book.insert(extended_address, short_address);
+        Ok(CoordinatorEvent::Joined {
+            extended_address,
+            short_address,
+        })
+    }
+
+    // Rejoining devices keep their address. 0xfffe and 0xffff are reserved,
+    // so addresses run from `first_device_address` to 0xfffd. Fails with
+    // `TableFull` once a whole cycle finds none free.
+    fn allocate(&mut self, extended_address: &ExtendedAddress) -> Result<ShortAddress, Error> {
+        if let Some(short_address) = self.address_book.short_address(extended_address) {
+            return Ok(short_address);
+        }
+        let first = self.config.first_device_address;
+        for _ in first..0xfffe {
+            let candidate = ShortAddress::from(self.next_address);
+            self.next_address = match self.next_address.wrapping_add(1) {
+                0xfffe => first,
+                next => next,
+            };
+            if candidate != self.config.short_address
+                && self.address_book.extended_address(&candidate).is_none()
+            {
+                return Ok(candidate);
+            }
+        }
+        Err(Error::TableFull)
+    }
+}
@@ -0,0 +1,191 @@
+use crate::diff::fields;
+use crate::frame::{CommandCode, MTExtendedHeader, MTFrame};
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
+use crate::subsystem::MTFramePayload;
+use crate::types::MTSubsystem;
+use alloc::format;
+use alloc::string::String;
+use core::fmt::Write;
+
+const START_OF_FRAME: u8 = 0xfe;
+const BYTES_PER_LINE: usize = 8;
+
+/// Renders a raw MT frame, with or without the UART start of frame and FCS,
+/// as one line per field: offset, bytes, field name and, for the header,
+/// decoded value. The decoded payload follows its fields.
+///
+/// Payload field boundaries come from the decoders themselves, so fields
+/// decoded without a name, such as a trailing data buffer, are listed as
+/// `...`.
+pub fn annotate(frame_bytes: &[u8]) -> String {
+    let mut output = String::new();
+    let is_transport = frame_bytes.first() == Some(&START_OF_FRAME)
+        && frame_bytes.len() >= 5
+        && frame_bytes.len() == frame_bytes[1] as usize + 5;
+
+    let (start, mt_bytes) = if is_transport {
+        line(&mut output, 0, &frame_bytes[..1], "SOF", "");
+        (1, &frame_bytes[1..frame_bytes.len() - 1])
+    } else {
+        (0, frame_bytes)
+    };
+
+    annotate_mt_frame(&mut output, start, mt_bytes);
+
+    if is_transport {
+        let fcs = frame_bytes[frame_bytes.len() - 1];
+        let expected = MTFrame::compute_frame_check_sequence(mt_bytes);
+        let value = if fcs == expected {
+            String::from("ok")
+        } else {
+            format!("expected 0x{:02x}", expected)
+        };
+        line(&mut output, frame_bytes.len() - 1, &[fcs], "FCS", &value);
+    }
+
+    output
+}
+
+fn annotate_mt_frame(output: &mut String, start: usize, bytes: &[u8]) {
+    if bytes.len() < 3 {
+        line(output, start, bytes, "...", "truncated header");
+        return;
+    }
+
+    line(
+        output,
+        start,
+        &bytes[..1],
+        "length",
+        &format!("{}", bytes[0]),
+    );
+    let command = match CommandCode::from_cmd(bytes[1], bytes[2]) {
+        Ok(command) => command,
+        Err(error) => {
+            line(output, start + 1, &bytes[1..], "...", &format!("{}", error));
+            return;
+        }
+    };
+    line(
+        output,
+        start + 1,
+        &bytes[1..2],
+        "cmd0",
+        &format!("{:?} {:?}", command.cmd_type, command.subsystem),
+    );
+    line(
+        output,
+        start + 2,
+        &bytes[2..3],
+        "cmd1",
+        &command_name(&command).unwrap_or_else(|| format!("0x{:02x}", command.id)),
+    );
+
+    let mut cursor = Reader::new(bytes);
+    let frame = match MTFrame::try_decode(&mut cursor) {
+        Ok(frame) => frame,
+        Err(error) => {
+            line(output, start + 3, &bytes[3..], "...", &format!("{}", error));
+            return;
+        }
+    };
+
+    let mut offset = start + 3;
+    if let Some(extended_header) = &frame.extended_header {
+        let size = extended_header.size();
+        line(
+            output,
+            offset,
+            &bytes[3..3 + size],
+            "extended header",
+            &format!("{:?}", extended_header),
+        );
+        offset += size;
+        if let MTExtendedHeader::V2 { .. } = extended_header {
+            // Blocks only decode once joined.
+            line(output, offset, &frame.payload, "block", "");
+            return;
+        }
+    }
+
+    annotate_payload(output, offset, &command, &frame.payload);
+}
+
+fn annotate_payload(output: &mut String, start: usize, command: &CommandCode, payload: &[u8]) {
+    let decoded = MTFramePayload::try_decode_unaudited(
+        &command.subsystem,
+        &command.cmd_type,
+        command.id,
+        payload,
+        DecodeOptions::default(),
+    );
+
+    let mut position = 0;
+    for (field, range) in fields(command, payload) {
+        if range.start > position {
+            line(
+                output,
+                start + position,
+                &payload[position..range.start],
+                "...",
+                "",
+            );
+        }
+        line(
+            output,
+            start + range.start,
+            &payload[range.clone()],
+            &field,
+            "",
+        );
+        position = range.end;
+    }
+    if position < payload.len() {
+        line(output, start + position, &payload[position..], "...", "");
+    }
+
+    match decoded {
+        Ok(decoded) => {
+            let _ = writeln!(output, "decoded: {:?}", decoded);
+        }
+        Err(error) => {
+            let _ = writeln!(output, "error: {}", error);
+        }
+    }
+}
+
+pub(crate) fn command_name(command: &CommandCode) -> Option<String> {
+    let id = command.command_id();
+    match command.subsystem {
+        MTSubsystem::MAC => id.mac().map(|id| format!("{:?}", id)),
+        MTSubsystem::SYS => id.sys().map(|id| format!("{:?}", id)),
+        MTSubsystem::UTIL => id.util().map(|id| format!("{:?}", id)),
+        MTSubsystem::RPC => id.rpc().map(|id| format!("{:?}", id)),
+        MTSubsystem::APP => id.app().map(|id| format!("{:?}", id)),
+        _ => None,
+    }
+}
+
+fn line(output: &mut String, offset: usize, bytes: &[u8], name: &str, value: &str) {
+    let mut chunks = bytes.chunks(BYTES_PER_LINE);
+    let first = chunks.next().unwrap_or(&[]);
+    let text = format!("{:04x}  {:<24} {} {}", offset, hex(first), name, value);
+    let _ = writeln!(output, "{}", text.trim_end());
+    for (index, chunk) in chunks.enumerate() {
+        let text = format!(
+            "{:04x}  {}",
+            offset + (index + 1) * BYTES_PER_LINE,
+            hex(chunk)
+        );
+        let _ = writeln!(output, "{}", text.trim_end());
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut output = String::new();
+    for byte in bytes {
+        let _ = write!(output, "{:02x} ", byte);
+    }
+    output
+}
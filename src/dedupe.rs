@@ -0,0 +1,78 @@
+use crate::subsystem::mac::areq::DataInd;
+use crate::subsystem::MTFramePayload;
+use crate::types::Address;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Drops data indications repeating the source address and DSN of one
+/// received less than `window` ago, as when the acknowledgement of a frame
+/// is lost and the sender retries it.
+#[derive(Debug)]
+pub struct DuplicateFilter {
+    window: Duration,
+    seen: HashMap<(Address, u8), Instant>,
+    // The same keys in the order they were received, for expiry.
+    order: VecDeque<(Instant, Address, u8)>,
+    duplicates: u64,
+}
+
+impl DuplicateFilter {
+    pub fn new(window: Duration) -> DuplicateFilter {
+        DuplicateFilter {
+            window,
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            duplicates: 0,
+        }
+    }
+
+    /// Number of indications dropped so far.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+    }
+
+    /// Records `indication` and returns whether it repeats a recent one.
+    pub fn is_duplicate(&mut self, indication: &DataInd, now: Instant) -> bool {
+        self.expire(now);
+
+        let key = (indication.src_address, indication.dsn);
+        if self.seen.contains_key(&key) {
+            self.duplicates += 1;
+            return true;
+        }
+        self.seen.insert(key, now);
+        self.order.push_back((now, key.0, key.1));
+        false
+    }
+
+    /// Passes anything but duplicate data indications through.
+    pub fn filter(&mut self, payload: MTFramePayload, now: Instant) -> Option<MTFramePayload> {
+        match &payload {
+            MTFramePayload::MAC_DataInd_AREQ(x) if self.is_duplicate(x, now) => None,
+            _ => Some(payload),
+        }
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some((received, address, dsn)) = self.order.front().copied() {
+            if now.saturating_duration_since(received) < self.window {
+                break;
+            }
+            self.order.pop_front();
+            self.seen.remove(&(address, dsn));
+        }
+    }
+}
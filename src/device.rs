@@ -0,0 +1,249 @@
+use crate::client::{self, Client, Confirm, HandleAllocator};
+use crate::error::Error;
+use crate::frame::MTFrame;
+use crate::subsystem::mac::{areq, sreq, srsp};
+use crate::subsystem::MTFramePayload;
+use crate::types::*;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct DeviceConfig {
+    pub channels: ChannelMask,
+    pub channel_page: u8,
+    pub phy_id: PhyId,
+    pub scan_duration: u8,
+    pub max_results: u8,
+    pub capabilities: CapabilityInfo,
+    // Sleepy devices leave the receiver off and fetch indirect data with a
+    // poll request every `poll_interval`.
+    pub poll_interval: Option<Duration>,
+}
+
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        DeviceConfig {
+            channels: ChannelMask::all(),
+            channel_page: 9,
+            phy_id: PhyId::STD_US_915_PHY_1,
+            scan_duration: 5,
+            max_results: 8,
+            capabilities: CapabilityInfo::ALLOCATE_ADDRESS,
+            poll_interval: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DeviceEvent {
+    Beacon(PanDescriptor),
+    // The scan found no PAN the selector accepted.
+    NoNetwork,
+    Associating(PanDescriptor),
+    Associated(ShortAddress),
+    Left,
+    Data(areq::DataInd),
+    DataCnf(areq::DataCnf),
+    // A poll confirm, `true` when the coordinator has more data queued.
+    Polled(bool),
+    Other(MTFramePayload),
+}
+
+/// Joins a PAN as an end node: runs an active scan, lets `select` pick one
+/// of the beacons heard, associates and polls the coordinator for indirect
+/// data.
+pub struct Device<C, S> {
+    client: C,
+    config: DeviceConfig,
+    select: S,
+    candidates: Vec<PanDescriptor>,
+    coordinator: Option<PanDescriptor>,
+    short_address: Option<ShortAddress>,
+    next_poll: Option<Instant>,
+    handles: HandleAllocator<()>,
+}
+
+impl<C, S> Device<C, S>
+where
+    C: Client,
+    S: FnMut(&[PanDescriptor]) -> Option<usize>,
+{
+    pub fn new(client: C, config: DeviceConfig, select: S) -> Device<C, S> {
+        Device {
+            client,
+            config,
+            select,
+            candidates: Vec::new(),
+            coordinator: None,
+            short_address: None,
+            next_poll: None,
+            handles: HandleAllocator::new(),
+        }
+    }
+
+    pub fn client(&mut self) -> &mut C {
+        &mut self.client
+    }
+
+    pub fn coordinator(&self) -> Option<&PanDescriptor> {
+        self.coordinator.as_ref()
+    }
+
+    pub fn short_address(&self) -> Option<ShortAddress> {
+        self.short_address
+    }
+
+    /// Starts an active scan. Beacons and the scan confirm arrive later
+    /// through `handle`, which associates once the selector picks a PAN.
+    pub fn scan(&mut self) -> Result<(), Error> {
+        self.candidates.clear();
+        let request = sreq::ScanReq {
+            scan_type: ScanType::Active,
+            scan_duration: self.config.scan_duration,
+            channel_page: self.config.channel_page,
+            phy_id: self.config.phy_id,
+            max_results: self.config.max_results,
+            permit_join: PermitJoin::OnlyIfPermitJoinIsEnabled,
+            link_quality: 0,
+            rsp_filter: 0,
+            mpm_scan: MPMScan::Disabled,
+            mpm_type: MPMType::NBPAN,
+            mpm_duration: 0,
+            security: Security::none(),
+            channels: self.config.channels,
+        };
+        let frame = self.client.request(request.into_mt_frame())?;
+        client::expect_response(&frame, MTSubsystem::MAC, MACCommandId::ScanReq as u8)?;
+        srsp::ScanReq::try_decode(&frame.payload)?.check()?;
+        Ok(())
+    }
+
+    /// Handles an asynchronous frame from the coprocessor.
+    pub fn handle(&mut self, frame: &MTFrame, now: Instant) -> Result<DeviceEvent, Error> {
+        use MTFramePayload::*;
+        let event = match MTFramePayload::try_from_frame(frame)? {
+            MAC_BeaconNotifyInd_AREQ(areq::BeaconNotifyInd::StandardFrame(x)) => {
+                self.candidates.push(x.pan_descriptor);
+                DeviceEvent::Beacon(x.pan_descriptor)
+            }
+            MAC_ScanCnf_AREQ(x) => {
+                // The scan ends with NoBeacon when nothing answered, leave
+                // that to the selector instead of failing.
+                if x.status != Status::NoBeacon {
+                    x.check()?;
+                }
+                self.on_scan_cnf()?
+            }
+            MAC_AssociateCnf_AREQ(x) => {
+                let x = x.check()?;
+                self.short_address = Some(x.short_address);
+                self.next_poll = self.config.poll_interval.map(|interval| now + interval);
+                DeviceEvent::Associated(x.short_address)
+            }
+            MAC_DisassociateInd_AREQ(_) => {
+                self.coordinator = None;
+                self.short_address = None;
+                self.next_poll = None;
+                DeviceEvent::Left
+            }
+            MAC_PollCnf_AREQ(x) => {
+                let frame_pending = x.frame_pending;
+                if x.status != Status::NoData {
+                    x.check()?;
+                }
+                // Drain whatever the coordinator still holds right away.
+                if frame_pending {
+                    self.poll()?;
+                }
+                DeviceEvent::Polled(frame_pending)
+            }
+            MAC_DataInd_AREQ(x) => DeviceEvent::Data(x),
+            MAC_DataCnf_AREQ(x) => {
+                self.handles.confirm(&x);
+                DeviceEvent::DataCnf(x)
+            }
+            payload => DeviceEvent::Other(payload),
+        };
+        Ok(event)
+    }
+
+    /// Sends a poll request when the configured interval has elapsed and
+    /// returns whether it did.
+    pub fn poll_if_due(&mut self, now: Instant) -> Result<bool, Error> {
+        match (self.next_poll, self.config.poll_interval) {
+            (Some(next_poll), Some(interval)) if now >= next_poll => {
+                self.next_poll = Some(now + interval);
+                self.poll()?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    pub fn poll(&mut self) -> Result<(), Error> {
+        let coordinator = self.coordinator.as_ref().ok_or(Error::NotAssociated)?;
+        let request = sreq::PollReq {
+            coord_address: coordinator.coord_address,
+            coord_pan_id: coordinator.pan_id,
+            security: Security::none(),
+        };
+        let frame = self.client.request(request.into_mt_frame())?;
+        client::expect_response(&frame, MTSubsystem::MAC, MACCommandId::PollReq as u8)?;
+        srsp::PollReq::try_decode(&frame.payload)?.check()?;
+        Ok(())
+    }
+
+    /// Queues `data` for the coordinator and returns the MSDU handle the data
+    /// confirm will carry.
+    pub fn send(&mut self, data: Vec<u8>) -> Result<u8, Error> {
+        if self.short_address.is_none() {
+            return Err(Error::NotAssociated);
+        }
+        let coordinator = self.coordinator.as_ref().ok_or(Error::NotAssociated)?;
+        let handle = self.handles.allocate(())?;
+        let request = sreq::DataReq {
+            dest_address: coordinator.coord_address,
+            dest_pan_id: coordinator.pan_id,
+            src_address_mode: AddressMode::Addr16Bit,
+            handle,
+            tx_option: TxOption::ACK,
+            channel: 0,
+            power: 0,
+            security: Security::none(),
+            include_fh_ies: 0,
+            data_length: data.len() as u16,
+            ie_length: 0,
+            data_payload: data,
+            ie_payload: Vec::new(),
+        };
+        if let Err(error) = client::data_req(&mut self.client, request) {
+            self.handles.release(handle);
+            return Err(error);
+        }
+        Ok(handle)
+    }
+
+    fn on_scan_cnf(&mut self) -> Result<DeviceEvent, Error> {
+        let candidates = core::mem::take(&mut self.candidates);
+        let selected = (self.select)(&candidates).and_then(|index| candidates.get(index));
+        let pan = match selected {
+            Some(pan) => *pan,
+            None => return Ok(DeviceEvent::NoNetwork),
+        };
+
+        let request = sreq::AssociateReq {
+            logical_channel: pan.logical_channel,
+            channel_page: pan.channel_page,
+            phy_id: self.config.phy_id.u8(),
+            coord_address: pan.coord_address,
+            coord_pan_id: pan.pan_id,
+            capability_info: self.config.capabilities,
+            security: Security::none(),
+        };
+        let frame = self.client.request(request.into_mt_frame())?;
+        client::expect_response(&frame, MTSubsystem::MAC, MACCommandId::AssociateReq as u8)?;
+        srsp::AssociateReq::try_decode(&frame.payload)?.check()?;
+
+        self.coordinator = Some(pan);
+        Ok(DeviceEvent::Associating(pan))
+    }
+}
@@ -0,0 +1,141 @@
+use crate::error::Error;
+use crate::frame::CommandCode;
+use crate::reader::DecodeOptions;
+use crate::subsystem::MTFramePayload;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::Range;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FieldChange {
+    pub path: String,
+    // Bytes of the field on each side, `None` where the field is missing.
+    pub left: Option<Vec<u8>>,
+    pub right: Option<Vec<u8>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FrameDiff {
+    pub fields: Vec<FieldChange>,
+    pub byte_ranges: Vec<Range<usize>>,
+}
+
+impl FrameDiff {
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.byte_ranges.is_empty()
+    }
+}
+
+/// Compares two payloads of the same command. Returns `None` when the
+/// payloads hold different commands.
+pub fn diff_frames(a: &MTFramePayload, b: &MTFramePayload) -> Option<FrameDiff> {
+    if mem::discriminant(a) != mem::discriminant(b) {
+        return None;
+    }
+
+    let left = a.clone().into_mt_frame();
+    let right = b.clone().into_mt_frame();
+    let fields = diff_fields(&left.header.command, &left.payload, &right.payload);
+
+    Some(FrameDiff {
+        fields,
+        byte_ranges: diff_bytes(&left.payload, &right.payload),
+    })
+}
+
+// Fields whose bytes differ between two encoded payloads of `command`, each
+// split into fields on its own.
+pub(crate) fn diff_fields(command: &CommandCode, left: &[u8], right: &[u8]) -> Vec<FieldChange> {
+    let bytes = |payload: &[u8]| -> BTreeMap<String, Vec<u8>> {
+        fields(command, payload)
+            .into_iter()
+            .map(|(path, range)| (path, payload[range].to_vec()))
+            .collect()
+    };
+    let left = bytes(left);
+    let right = bytes(right);
+
+    let mut paths: Vec<&String> = left.keys().chain(right.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    paths
+        .into_iter()
+        .filter(|path| left.get(*path) != right.get(*path))
+        .map(|path| FieldChange {
+            path: path.clone(),
+            left: left.get(path).cloned(),
+            right: right.get(path).cloned(),
+        })
+        .collect()
+}
+
+pub fn diff_bytes(a: &[u8], b: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let length = a.len().max(b.len());
+
+    for index in 0..length {
+        if a.get(index) == b.get(index) {
+            continue;
+        }
+
+        match ranges.last_mut() {
+            Some(range) if range.end == index => range.end += 1,
+            _ => ranges.push(index..index + 1),
+        }
+    }
+
+    ranges
+}
+
+/// Splits an encoded payload of `command` into its named fields, in the
+/// order they are decoded.
+///
+/// Every prefix of the payload is decoded. A prefix cut inside a field fails
+/// with that field's name and start offset, as tagged by the decoder, and the
+/// longest failing prefix gives its end. Fields that cannot fail when cut,
+/// such as a trailing data buffer, are not listed. A name read more than once,
+/// as for list elements, is followed by the index of each read.
+pub(crate) fn fields(command: &CommandCode, payload: &[u8]) -> Vec<(String, Range<usize>)> {
+    let mut fields: Vec<(&'static str, Range<usize>)> = Vec::new();
+    for length in 0..payload.len() {
+        let result = MTFramePayload::try_decode_unaudited(
+            &command.subsystem,
+            &command.cmd_type,
+            command.id,
+            &payload[..length],
+            DecodeOptions::lenient(),
+        );
+        if let Err(Error::Decode { field, offset, .. }) = result {
+            match fields.last_mut() {
+                Some((name, range)) if *name == field && range.start == offset => {
+                    range.end = length + 1
+                }
+                _ => fields.push((field, offset..length + 1)),
+            }
+        }
+    }
+
+    let mut reads: BTreeMap<&'static str, usize> = BTreeMap::new();
+    for (name, _) in fields.iter() {
+        *reads.entry(name).or_default() += 1;
+    }
+    let mut index: BTreeMap<&'static str, usize> = BTreeMap::new();
+    fields
+        .into_iter()
+        .map(|(name, range)| {
+            let path = if reads[name] > 1 {
+                let next = index.entry(name).or_default();
+                *next += 1;
+                format!("{}.{}", name, *next - 1)
+            } else {
+                name.to_string()
+            };
+            (path, range)
+        })
+        .collect()
+}
@@ -0,0 +1,166 @@
+use crate::error::Error;
+use crate::frame::MTFrame;
+#[cfg(feature = "app")]
+use crate::subsystem::app;
+#[cfg(feature = "mac")]
+use crate::subsystem::mac;
+#[cfg(feature = "sys")]
+use crate::subsystem::sys;
+#[cfg(feature = "util")]
+use crate::subsystem::util;
+use crate::subsystem::MTFramePayload;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// Asynchronous payloads a handler can subscribe to.
+pub trait Indication {
+    fn from_payload(payload: &MTFramePayload) -> Option<&Self>;
+}
+
+macro_rules! impl_indication {
+    ($($(#[$attr:meta])* $variant:ident => $name:path),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            impl Indication for $name {
+                fn from_payload(payload: &MTFramePayload) -> Option<&Self> {
+                    match payload {
+                        MTFramePayload::$variant(x) => Some(x),
+                        #[allow(unreachable_patterns)]
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_indication!(
+    #[cfg(feature = "mac")]
+    MAC_DataCnf_AREQ => mac::areq::DataCnf,
+    #[cfg(feature = "mac")]
+    MAC_DataInd_AREQ => mac::areq::DataInd,
+    #[cfg(feature = "mac")]
+    MAC_PurgeCnf_AREQ => mac::areq::PurgeCnf,
+    #[cfg(feature = "wisun-ie")]
+    MAC_WSAsyncInd_AREQ => mac::areq::WSAsyncInd,
+    #[cfg(feature = "mac")]
+    MAC_SyncLossInd_AREQ => mac::areq::SyncLossInd,
+    #[cfg(feature = "mac")]
+    MAC_AssociateInd_AREQ => mac::areq::AssociateInd,
+    #[cfg(feature = "mac")]
+    MAC_AssociateCnf_AREQ => mac::areq::AssociateCnf,
+    #[cfg(feature = "mac")]
+    MAC_BeaconNotifyInd_AREQ => mac::areq::BeaconNotifyInd,
+    #[cfg(feature = "mac")]
+    MAC_DisassociateInd_AREQ => mac::areq::DisassociateInd,
+    #[cfg(feature = "mac")]
+    MAC_DisassociateCnf_AREQ => mac::areq::DisassociateCnf,
+    #[cfg(feature = "mac")]
+    MAC_OrphanInd_AREQ => mac::areq::OrphanInd,
+    #[cfg(feature = "mac")]
+    MAC_PollCnf_AREQ => mac::areq::PollCnf,
+    #[cfg(feature = "mac")]
+    MAC_PollInd_AREQ => mac::areq::PollInd,
+    #[cfg(feature = "mac")]
+    MAC_ScanCnf_AREQ => mac::areq::ScanCnf,
+    #[cfg(feature = "mac")]
+    MAC_CommStatusInd_AREQ => mac::areq::CommStatusInd,
+    #[cfg(feature = "mac")]
+    MAC_StartCnf_AREQ => mac::areq::StartCnf,
+    #[cfg(feature = "wisun-ie")]
+    MAC_WSAsyncCnf_AREQ => mac::areq::WSAsyncCnf,
+    #[cfg(feature = "sys")]
+    SYS_ResetInd_AREQ => sys::areq::ResetInd,
+    #[cfg(feature = "util")]
+    UTIL_Loopback_AREQ => util::areq::Loopback,
+    #[cfg(feature = "app")]
+    APP_AppMsgInd_AREQ => app::areq::AppMsgInd,
+);
+
+/// Handle returned by `Dispatcher::subscribe`, pass it back to
+/// `unsubscribe` to remove the handler.
+#[derive(Debug)]
+pub struct Subscription<T: ?Sized> {
+    id: usize,
+    indication: PhantomData<fn(&T)>,
+}
+
+type Handler<'a> = Box<dyn FnMut(&MTFramePayload) -> bool + 'a>;
+
+/// Fans asynchronous payloads out to the handlers subscribed to their type,
+/// in subscription order.
+#[derive(Default)]
+pub struct Dispatcher<'a> {
+    handlers: Vec<(usize, Handler<'a>)>,
+    next_id: usize,
+}
+
+impl<'a> Dispatcher<'a> {
+    pub fn new() -> Dispatcher<'a> {
+        Dispatcher::default()
+    }
+
+    pub fn subscribe<T, F>(&mut self, mut handler: F) -> Subscription<T>
+    where
+        T: Indication,
+        F: FnMut(&T) + 'a,
+    {
+        self.insert(Box::new(move |payload| match T::from_payload(payload) {
+            Some(indication) => {
+                handler(indication);
+                true
+            }
+            None => false,
+        }))
+    }
+
+    /// Subscribes to every payload, whatever its type.
+    pub fn subscribe_all<F>(&mut self, mut handler: F) -> Subscription<MTFramePayload>
+    where
+        F: FnMut(&MTFramePayload) + 'a,
+    {
+        self.insert(Box::new(move |payload| {
+            handler(payload);
+            true
+        }))
+    }
+
+    pub fn unsubscribe<T: ?Sized>(&mut self, subscription: Subscription<T>) -> bool {
+        let count = self.handlers.len();
+        self.handlers.retain(|(id, _)| *id != subscription.id);
+        self.handlers.len() != count
+    }
+
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Returns how many handlers received `payload`.
+    pub fn dispatch(&mut self, payload: &MTFramePayload) -> usize {
+        self.handlers
+            .iter_mut()
+            .map(|(_, handler)| handler(payload))
+            .filter(|handled| *handled)
+            .count()
+    }
+
+    pub fn dispatch_frame(&mut self, frame: &MTFrame) -> Result<usize, Error> {
+        let payload = MTFramePayload::try_from_frame(frame)?;
+        Ok(self.dispatch(&payload))
+    }
+
+    fn insert<T: ?Sized>(&mut self, handler: Handler<'a>) -> Subscription<T> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.handlers.push((id, handler));
+        Subscription {
+            id,
+            indication: PhantomData,
+        }
+    }
+}
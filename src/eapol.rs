@@ -0,0 +1,184 @@
+use crate::client::{self, Client, Confirm};
+use crate::error::Error;
+use crate::ie::{HeaderIe, WhIe, WISUN_HEADER_IE};
+use crate::reader::Reader;
+use crate::subsystem::mac::{areq, sreq, srsp};
+use crate::subsystem::MTFramePayload;
+use crate::types::*;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// An EAPOL PDU exchanged with `peer`, as carried by Wi-SUN frames of type
+/// EAPOL.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EapolFrame {
+    pub peer: ExtendedAddress,
+    // The EAPOL authenticator named by the EA-IE, set by border routers and
+    // relays.
+    pub authenticator: Option<ExtendedAddress>,
+    pub pdu: Vec<u8>,
+}
+
+impl EapolFrame {
+    pub fn from_async_ind(indication: &areq::WSAsyncInd) -> Option<EapolFrame> {
+        if indication.frame_type != WiSUNAsyncFrameType::EAPOL {
+            return None;
+        }
+        EapolFrame::new(
+            indication.src_address,
+            &indication.ie_payload,
+            &indication.data_payload,
+        )
+    }
+
+    /// Data indications qualify when their IEs start with a UTT-IE of frame
+    /// type EAPOL.
+    pub fn from_data_ind(indication: &areq::DataInd) -> Option<EapolFrame> {
+        let is_eapol = header_ies(&indication.ie_payload).iter().any(|ie| {
+            matches!(ie, WhIe::UnicastTiming { frame_type, .. }
+                if *frame_type == WiSUNAsyncFrameType::EAPOL.raw())
+        });
+        if !is_eapol {
+            return None;
+        }
+        EapolFrame::new(
+            indication.src_address,
+            &indication.ie_payload,
+            &indication.data_payload,
+        )
+    }
+
+    /// A unicast data request to `peer` with a UTT-IE of frame type EAPOL
+    /// and the EA-IE when `authenticator` is set.
+    pub fn into_data_req(self, pan_id: PanId, handle: u8) -> Result<sreq::DataReq, Error> {
+        let mut header_ies = vec![WhIe::UnicastTiming {
+            frame_type: WiSUNAsyncFrameType::EAPOL.raw(),
+            ufsi: 0,
+        }
+        .to_header_ie()];
+        if let Some(authenticator) = self.authenticator {
+            header_ies.push(WhIe::EapolAuthenticator(authenticator).to_header_ie());
+        }
+
+        let mut request = sreq::DataReq {
+            dest_address: Address::Addr64Bit(self.peer),
+            dest_pan_id: pan_id,
+            src_address_mode: AddressMode::Addr64Bit,
+            handle,
+            tx_option: TxOption::ACK,
+            channel: 0,
+            power: 0,
+            security: Security::none(),
+            include_fh_ies: 0,
+            data_length: self.pdu.len() as u16,
+            ie_length: 0,
+            data_payload: self.pdu,
+            ie_payload: Vec::new(),
+        };
+        request.set_ies(&header_ies, &[])?;
+        request.set_fh_ies(FHIncludeIe::US);
+        Ok(request)
+    }
+
+    // EAPOL runs before a short address is assigned, anything else is not
+    // a join attempt.
+    fn new(source: Address, ie_payload: &[u8], pdu: &[u8]) -> Option<EapolFrame> {
+        let peer = match source {
+            Address::Addr64Bit(address) => address,
+            _ => return None,
+        };
+        let authenticator = header_ies(ie_payload).into_iter().find_map(|ie| match ie {
+            WhIe::EapolAuthenticator(address) => Some(address),
+            _ => None,
+        });
+        Some(EapolFrame {
+            peer,
+            authenticator,
+            pdu: pdu.to_vec(),
+        })
+    }
+}
+
+/// Implemented by the 802.1X side, e.g. a supplicant on a node or a
+/// RADIUS relay on a border router, which runs the key establishment.
+pub trait Authenticator {
+    /// Takes a received PDU and returns the PDUs to send in reply.
+    fn receive(&mut self, frame: EapolFrame) -> Vec<EapolFrame>;
+}
+
+/// Moves EAPOL PDUs between the coprocessor and an `Authenticator`.
+pub struct EapolTransport<C> {
+    client: C,
+    pan_id: PanId,
+    next_handle: u8,
+}
+
+impl<C: Client> EapolTransport<C> {
+    pub fn new(client: C, pan_id: PanId) -> EapolTransport<C> {
+        EapolTransport {
+            client,
+            pan_id,
+            next_handle: 0,
+        }
+    }
+
+    pub fn client(&mut self) -> &mut C {
+        &mut self.client
+    }
+
+    pub fn set_pan_id(&mut self, pan_id: PanId) {
+        self.pan_id = pan_id;
+    }
+
+    /// Queues `frame` and returns the MSDU handle the data confirm will carry.
+    pub fn send(&mut self, frame: EapolFrame) -> Result<u8, Error> {
+        let handle = self.next_handle;
+        self.next_handle = self.next_handle.wrapping_add(1);
+
+        let request = frame.into_data_req(self.pan_id, handle)?;
+        let frame = self.client.request(request.into_mt_frame())?;
+        client::expect_response(&frame, MTSubsystem::MAC, MACCommandId::DataReq as u8)?;
+        srsp::DataReq::try_decode(&frame.payload)?.check()?;
+        Ok(handle)
+    }
+
+    /// Passes EAPOL frames to `authenticator` and sends its replies. Returns
+    /// `false` for anything else, which is left to the caller.
+    pub fn handle<A: Authenticator>(
+        &mut self,
+        payload: &MTFramePayload,
+        authenticator: &mut A,
+    ) -> Result<bool, Error> {
+        let frame = match payload {
+            MTFramePayload::MAC_WSAsyncInd_AREQ(x) => EapolFrame::from_async_ind(x),
+            MTFramePayload::MAC_DataInd_AREQ(x) => EapolFrame::from_data_ind(x),
+            _ => None,
+        };
+        let frame = match frame {
+            Some(frame) => frame,
+            None => return Ok(false),
+        };
+
+        for reply in authenticator.receive(frame) {
+            self.send(reply)?;
+        }
+        Ok(true)
+    }
+}
+
+// Wi-SUN header IEs at the start of an IE payload, up to the first one
+// that is not a Wi-SUN header IE or does not decode.
+fn header_ies(ie_payload: &[u8]) -> Vec<WhIe> {
+    let mut cursor = Reader::new(ie_payload);
+    let mut ies = Vec::new();
+    while cursor.remaining() > 0 {
+        match HeaderIe::try_decode(&mut cursor) {
+            Ok(ie) if ie.element_id == WISUN_HEADER_IE => match WhIe::try_from_header_ie(&ie) {
+                Ok(ie) => ies.push(ie),
+                Err(_) => break,
+            },
+            _ => break,
+        }
+    }
+    ies
+}
@@ -1,3 +1,14 @@
+use crate::frame::{CommandCode, MTFrame};
+use crate::subsystem::length::PayloadLength;
+use crate::types::{
+    Address, ExtendedAddress, MTExtendedHeaderStatus, PanId, PibAttribute, ResetReason,
+    ShortAddress, Status, WiSUNAsyncFrameType,
+};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
 #[derive(Debug)]
 pub enum Error {
     InvalidStartOfFrame(u8),
@@ -9,18 +20,352 @@ pub enum Error {
     InvalidErrorCode(u8),
     InvalidAddressMode(u8),
     InvalidTxOption(u8),
+    InvalidCapabilityInfo(u8),
     InvalidSecurityLevel(u8),
     InvalidKeyIdMode(u8),
     InvalidFrameType(u8),
     InvalidAssociationStatus(u8),
     InvalidDisassociationReason(u8),
+    InvalidCommEventReason(u8),
     InvalidMACPIBAttributeId(u8),
     InvalidFHPIBAttributeId(u16),
     InvalidSecurityPIBAttributeId(u8),
     InvalidScanType(u8),
-    InvalidPhyId(u8),
+    InvalidPermitJoin(u8),
+    InvalidMPMScan(u8),
+    InvalidMPMType(u8),
+    InvalidWiSUNAsyncOperation(u8),
+    // Enums without a dedicated variant.
+    InvalidEnumValue {
+        type_name: &'static str,
+        value: u32,
+    },
     InvalidStatus(u8),
     InvalidBeaconType(u8),
+    InvalidExtendedAddress(String),
+    InvalidNetName(Vec<u8>),
+    InvalidSecurityBundle,
+    // A fragment whose header is truncated or points past the payload.
+    InvalidFragment,
+    // A payload too large to fragment, or to fit the MTU at all.
+    PayloadTooLarge(usize),
+    // Not an IPv6 packet, or an IPHC header that does not decode.
+    InvalidIpv6Packet,
+    InvalidPanId(PanId),
+    // A first device address that leaves no room before the reserved 0xfffe.
+    InvalidShortAddress(ShortAddress),
+    // An `FhSchedule` whose fields contradict each other.
+    InvalidFhSchedule(&'static str),
+    TableFull,
+    NoChannels,
+    ChannelOutOfRange(u8),
+    ExcludedChannel(u8),
+    UnsupportedAsyncFrameType(WiSUNAsyncFrameType),
+    Fragmentation(MTExtendedHeaderStatus),
+    NotAssociated,
     NotEnoughBytes,
+    // Bytes left over after a value decoded from a slice of its own.
+    TrailingBytes(usize),
+    CommandFailed(Status),
+    UnexpectedResponse(MTFrame),
     NotImplemented,
+    // The link to the coprocessor failed, e.g. a TCP bridge went away.
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+    // A replayed session got another request than the one recorded.
+    ReplayMismatch {
+        expected: Vec<u8>,
+        actual: Vec<u8>,
+    },
+    // The coprocessor reset while the request was in flight.
+    DeviceReset(ResetReason),
+    // A data request failed in the frequency hopping layer, see `FhError`.
+    Fh(FhError),
+    // A PIB write of a bulk configuration failed, `kind` says why.
+    PibWriteFailed {
+        attribute: PibAttribute,
+        kind: Box<Error>,
+    },
+    // No response arrived for the request in time.
+    Timeout {
+        command: CommandCode,
+    },
+    // The payload size does not match the command layout, as when host and
+    // firmware disagree on a version.
+    LengthMismatch {
+        command: CommandCode,
+        expected: PayloadLength,
+        actual: usize,
+    },
+    // A field of a command payload failed to decode. `offset` is where the
+    // field starts within the payload and `kind` is the underlying error.
+    Decode {
+        command: Option<CommandCode>,
+        field: &'static str,
+        offset: usize,
+        kind: Box<Error>,
+    },
+}
+
+impl Error {
+    pub(crate) fn field(field: &'static str, offset: usize, kind: Error) -> Error {
+        Error::Decode {
+            command: None,
+            field,
+            offset,
+            kind: Box::new(kind),
+        }
+    }
+
+    // Called by the payload dispatch, which is the first to know the command.
+    pub(crate) fn with_command(self, command: CommandCode) -> Error {
+        match self {
+            Error::Decode {
+                command: None,
+                field,
+                offset,
+                kind,
+            } => Error::Decode {
+                command: Some(command),
+                field,
+                offset,
+                kind,
+            },
+            error => error,
+        }
+    }
+
+    /// The error behind any decode context, e.g. `InvalidStatus(0x99)`.
+    pub fn kind(&self) -> &Error {
+        match self {
+            Error::Decode { kind, .. } | Error::PibWriteFailed { kind, .. } => kind.kind(),
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidStartOfFrame(value) => {
+                write!(f, "invalid start of frame byte 0x{:02x}", value)
+            }
+            Error::InvalidFrameCheckSequence(frame) => {
+                write!(f, "invalid frame check sequence in {:02x?}", frame)
+            }
+            Error::InvalidCommandType(value) => write!(f, "invalid command type 0x{:02x}", value),
+            Error::InvalidSubsystem(value) => write!(f, "invalid subsystem 0x{:02x}", value),
+            Error::InvalidCommandId(value) => write!(f, "invalid command id 0x{:02x}", value),
+            Error::InvalidExtendedHeaderStatus(value) => {
+                write!(f, "invalid extended header status 0x{:02x}", value)
+            }
+            Error::InvalidErrorCode(value) => write!(f, "invalid error code 0x{:02x}", value),
+            Error::InvalidAddressMode(value) => write!(f, "invalid address mode 0x{:02x}", value),
+            Error::InvalidTxOption(value) => write!(f, "invalid tx options 0x{:02x}", value),
+            Error::InvalidCapabilityInfo(value) => {
+                write!(f, "invalid capability info 0x{:02x}", value)
+            }
+            Error::InvalidSecurityLevel(value) => {
+                write!(f, "invalid security level 0x{:02x}", value)
+            }
+            Error::InvalidKeyIdMode(value) => write!(f, "invalid key id mode 0x{:02x}", value),
+            Error::InvalidFrameType(value) => write!(f, "invalid frame type 0x{:02x}", value),
+            Error::InvalidAssociationStatus(value) => {
+                write!(f, "invalid association status 0x{:02x}", value)
+            }
+            Error::InvalidDisassociationReason(value) => {
+                write!(f, "invalid disassociation reason 0x{:02x}", value)
+            }
+            Error::InvalidCommEventReason(value) => {
+                write!(f, "invalid comm event reason 0x{:02x}", value)
+            }
+            Error::InvalidMACPIBAttributeId(value) => {
+                write!(f, "invalid MAC PIB attribute id 0x{:02x}", value)
+            }
+            Error::InvalidFHPIBAttributeId(value) => {
+                write!(f, "invalid FH PIB attribute id 0x{:04x}", value)
+            }
+            Error::InvalidSecurityPIBAttributeId(value) => {
+                write!(f, "invalid security PIB attribute id 0x{:02x}", value)
+            }
+            Error::InvalidScanType(value) => write!(f, "invalid scan type 0x{:02x}", value),
+            Error::InvalidPermitJoin(value) => write!(f, "invalid permit join 0x{:02x}", value),
+            Error::InvalidMPMScan(value) => write!(f, "invalid MPM scan 0x{:02x}", value),
+            Error::InvalidMPMType(value) => write!(f, "invalid MPM type 0x{:02x}", value),
+            Error::InvalidWiSUNAsyncOperation(value) => {
+                write!(f, "invalid Wi-SUN async operation 0x{:02x}", value)
+            }
+            Error::InvalidEnumValue { type_name, value } => {
+                write!(f, "invalid {} value 0x{:02x}", type_name, value)
+            }
+            Error::InvalidStatus(value) => write!(f, "invalid status 0x{:02x}", value),
+            Error::InvalidBeaconType(value) => write!(f, "invalid beacon type 0x{:02x}", value),
+            Error::InvalidExtendedAddress(address) => {
+                write!(f, "invalid extended address \"{}\"", address)
+            }
+            Error::InvalidNetName(name) => write!(f, "invalid network name {:02x?}", name),
+            Error::InvalidSecurityBundle => write!(f, "invalid security bundle"),
+            Error::InvalidFragment => write!(f, "invalid fragment"),
+            Error::PayloadTooLarge(size) => write!(f, "payload of {} bytes is too large", size),
+            Error::InvalidIpv6Packet => write!(f, "invalid IPv6 packet"),
+            Error::ReplayMismatch { expected, actual } => write!(
+                f,
+                "replay expected request {:02x?}, got {:02x?}",
+                expected, actual
+            ),
+            #[cfg(feature = "std")]
+            Error::Io(kind) => write!(f, "i/o error: {}", kind),
+            Error::InvalidPanId(pan_id) => write!(f, "invalid PAN ID {}", pan_id),
+            Error::InvalidShortAddress(short_address) => {
+                write!(f, "invalid short address {}", short_address)
+            }
+            Error::InvalidFhSchedule(reason) => write!(f, "invalid FH schedule: {}", reason),
+            Error::TableFull => write!(f, "table full"),
+            Error::NoChannels => write!(f, "no channels enabled"),
+            Error::ChannelOutOfRange(channel) => {
+                write!(f, "channel {} is out of the PHY's range", channel)
+            }
+            Error::ExcludedChannel(channel) => write!(f, "channel {} is excluded", channel),
+            Error::UnsupportedAsyncFrameType(frame_type) => {
+                write!(f, "{:?} frames are not sent asynchronously", frame_type)
+            }
+            Error::Fragmentation(status) => write!(f, "fragmentation failed: {:?}", status),
+            Error::NotAssociated => write!(f, "not associated"),
+            Error::NotEnoughBytes => write!(f, "not enough bytes"),
+            Error::TrailingBytes(count) => write!(f, "{} bytes left after the value", count),
+            Error::CommandFailed(status) => write!(
+                f,
+                "command failed with status {:?} ({})",
+                status,
+                status.description()
+            ),
+            Error::UnexpectedResponse(frame) => write!(
+                f,
+                "unexpected response {:?} {:?} 0x{:02x}",
+                frame.header.command.subsystem,
+                frame.header.command.cmd_type,
+                frame.header.command.id
+            ),
+            Error::NotImplemented => write!(f, "not implemented"),
+            Error::DeviceReset(reason) => write!(f, "device reset: {:?}", reason),
+            Error::Fh(error) => write!(f, "{}", error),
+            Error::PibWriteFailed { attribute, kind } => {
+                write!(f, "setting {} failed: {}", attribute, kind)
+            }
+            Error::Timeout { command } => write!(
+                f,
+                "{:?} {:?} 0x{:02x} timed out",
+                command.subsystem, command.cmd_type, command.id
+            ),
+            Error::LengthMismatch {
+                command,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{:?} {:?} 0x{:02x} expects {} but has {}",
+                command.subsystem, command.cmd_type, command.id, expected, actual
+            ),
+            Error::Decode {
+                command,
+                field,
+                offset,
+                kind,
+            } => {
+                if let Some(command) = command {
+                    write!(
+                        f,
+                        "{:?} {:?} 0x{:02x}: ",
+                        command.subsystem, command.cmd_type, command.id
+                    )?;
+                }
+                write!(f, "{} at offset {}: {}", field, offset, kind)
+            }
+        }
+    }
+}
+
+/// An FHAPI status (0x61 to 0x6C) from a data confirm, together with the
+/// destination of the failed request where it tells the application what to
+/// do next.
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FhError {
+    // The destination is not in the neighbor table yet, it has to be
+    // discovered before it can be reached.
+    NoNeighbor(ExtendedAddress),
+    // The neighbor entry of the destination timed out.
+    ExpiredNode(ExtendedAddress),
+    Other {
+        status: Status,
+        destination: Address,
+    },
+}
+
+impl FhError {
+    /// `None` unless `status` is in the FHAPI range.
+    pub fn from_status(status: Status, destination: Address) -> Option<FhError> {
+        if !status.is_fh_error() {
+            return None;
+        }
+        let error = match (status, destination) {
+            (Status::FHAPINoEntryInTheNeighbor, Address::Addr64Bit(address)) => {
+                FhError::NoNeighbor(address)
+            }
+            (Status::FHAPIExpiredNode, Address::Addr64Bit(address)) => {
+                FhError::ExpiredNode(address)
+            }
+            (status, destination) => FhError::Other {
+                status,
+                destination,
+            },
+        };
+        Some(error)
+    }
+
+    pub fn status(&self) -> Status {
+        match self {
+            FhError::NoNeighbor(_) => Status::FHAPINoEntryInTheNeighbor,
+            FhError::ExpiredNode(_) => Status::FHAPIExpiredNode,
+            FhError::Other { status, .. } => *status,
+        }
+    }
+
+    pub fn destination(&self) -> Address {
+        match self {
+            FhError::NoNeighbor(address) | FhError::ExpiredNode(address) => {
+                Address::Addr64Bit(*address)
+            }
+            FhError::Other { destination, .. } => *destination,
+        }
+    }
+}
+
+impl fmt::Display for FhError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FhError::NoNeighbor(address) => write!(f, "{} is not a neighbor", address),
+            FhError::ExpiredNode(address) => write!(f, "neighbor {} expired", address),
+            FhError::Other {
+                status,
+                destination,
+            } => write!(f, "sending to {} failed with {:?}", destination, status),
+        }
+    }
+}
+
+// `Decode` and `PibWriteFailed` already print the error they wrap, which
+// `kind` returns, so it is not given as the source as well.
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+#[cfg(not(feature = "std"))]
+impl core::error::Error for Error {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error.kind())
+    }
 }
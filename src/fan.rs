@@ -0,0 +1,578 @@
+use crate::client::{self, Client, Confirm};
+use crate::error::Error;
+use crate::frame::MTFrame;
+use crate::ie::{PayloadIe, WpIe, WISUN_PAYLOAD_IE_GROUP};
+use crate::pib_config::PibConfig;
+use crate::subsystem::mac::{areq, sreq, srsp};
+use crate::subsystem::MTFramePayload;
+use crate::types::*;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct FanConfig {
+    // Only advertisements carrying this NETNAME-IE are considered.
+    pub net_name: NetName,
+    pub channels: ChannelMask,
+    // Time spent collecting PAN advertisements after a solicit.
+    pub discovery_period: Duration,
+    // Time to wait for the PAN configuration after a solicit.
+    pub config_timeout: Duration,
+    // Unanswered configuration solicits before discovery starts over.
+    pub max_config_attempts: u32,
+    // Written between enabling and starting frequency hopping, e.g. the
+    // unicast schedule.
+    pub pib: PibConfig,
+}
+
+impl FanConfig {
+    pub fn new(net_name: NetName) -> FanConfig {
+        FanConfig {
+            net_name,
+            channels: ChannelMask::all(),
+            discovery_period: Duration::from_secs(15),
+            config_timeout: Duration::from_secs(15),
+            max_config_attempts: 3,
+            pib: PibConfig::new(),
+        }
+    }
+}
+
+/// A PAN heard during discovery.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PanCandidate {
+    pub address: Address,
+    pub pan_id: PanId,
+    pub link_quality: Lqi,
+    pub rssi: Rssi,
+    pub pan_size: Option<u16>,
+    pub routing_cost: Option<u16>,
+}
+
+impl PanCandidate {
+    // Lower routing cost first, the stronger signal breaks ties.
+    fn is_better_than(&self, other: &PanCandidate) -> bool {
+        let cost = |x: &PanCandidate| x.routing_cost.unwrap_or(u16::MAX);
+        (cost(self), -i16::from(self.rssi.0)) < (cost(other), -i16::from(other.rssi.0))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum FanState {
+    Idle,
+    // Soliciting and collecting PAN advertisements.
+    Discovery,
+    // Soliciting the PAN configuration of the chosen PAN.
+    ConfigSolicit {
+        pan: PanCandidate,
+        attempts: u32,
+    },
+    // The PAN configuration arrived, the node can start the EAPOL
+    // authentication with the border router.
+    AuthenticationReady {
+        pan: PanCandidate,
+        pan_version: Option<u16>,
+    },
+}
+
+#[derive(Debug)]
+pub enum FanEvent {
+    StateChanged { from: FanState, to: FanState },
+    PanHeard(PanCandidate),
+    // Anything the join state machine does not act on itself.
+    Other(MTFramePayload),
+}
+
+/// Runs the node side of the Wi-SUN FAN join up to the point where
+/// authentication can start: PAN discovery, PAN configuration solicitation
+/// and adoption of the chosen PAN. Advertisements and configurations arrive
+/// through `handle`, timeouts are driven by `poll`.
+///
+/// Solicits are repeated at fixed intervals rather than with the Trickle
+/// timers of the specification.
+pub struct Fan<C> {
+    client: C,
+    config: FanConfig,
+    state: FanState,
+    candidates: Vec<PanCandidate>,
+    deadline: Option<Instant>,
+}
+
+impl<C: Client> Fan<C> {
+    pub fn new(client: C, config: FanConfig) -> Fan<C> {
+        Fan {
+            client,
+            config,
+            state: FanState::Idle,
+            candidates: Vec::new(),
+            deadline: None,
+        }
+    }
+
+    pub fn client(&mut self) -> &mut C {
+        &mut self.client
+    }
+
+    pub fn state(&self) -> &FanState {
+        &self.state
+    }
+
+    pub fn candidates(&self) -> &[PanCandidate] {
+        &self.candidates
+    }
+
+    /// Enables frequency hopping, writes the network name and `config.pib`,
+    /// starts hopping and sends the first PAN advertisement solicit.
+    pub fn start(&mut self, now: Instant) -> Result<FanEvent, Error> {
+        client::fh_enable(&mut self.client)?;
+        PibConfig::new()
+            .net_name(&self.config.net_name)
+            .apply(&mut self.client)?;
+        self.config.pib.apply(&mut self.client)?;
+        client::fh_start(&mut self.client)?;
+        self.discover(now)
+    }
+
+    /// Handles an asynchronous frame from the coprocessor.
+    pub fn handle(&mut self, frame: &MTFrame) -> Result<FanEvent, Error> {
+        use MTFramePayload::*;
+        let payload = MTFramePayload::try_from_frame(frame)?;
+        let indication = match payload {
+            MAC_WSAsyncInd_AREQ(x) => x,
+            MAC_WSAsyncCnf_AREQ(x) => return Ok(FanEvent::Other(MAC_WSAsyncCnf_AREQ(x.check()?))),
+            payload => return Ok(FanEvent::Other(payload)),
+        };
+
+        match (&self.state, indication.frame_type) {
+            (FanState::Discovery, WiSUNAsyncFrameType::PANAdvert) => {
+                match self.on_pan_advert(&indication) {
+                    Some(candidate) => Ok(FanEvent::PanHeard(candidate)),
+                    None => Ok(FanEvent::Other(MAC_WSAsyncInd_AREQ(indication))),
+                }
+            }
+            (FanState::ConfigSolicit { pan, .. }, WiSUNAsyncFrameType::PANConfig)
+                if pan.address == indication.src_address =>
+            {
+                let pan = pan.clone();
+                let pan_version = wp_ies(&indication).into_iter().find_map(|ie| match ie {
+                    WpIe::PanVersion(version) => Some(version),
+                    _ => None,
+                });
+                PibConfig::new()
+                    .pan_id(pan.pan_id)
+                    .apply(&mut self.client)?;
+                self.deadline = None;
+                Ok(self.transition(FanState::AuthenticationReady { pan, pan_version }))
+            }
+            _ => Ok(FanEvent::Other(MAC_WSAsyncInd_AREQ(indication))),
+        }
+    }
+
+    /// Moves on once the current discovery period or configuration wait
+    /// is over. Returns `None` while waiting.
+    pub fn poll(&mut self, now: Instant) -> Result<Option<FanEvent>, Error> {
+        match self.deadline {
+            Some(deadline) if now >= deadline => (),
+            _ => return Ok(None),
+        }
+
+        match self.state.clone() {
+            FanState::Discovery => {
+                let best = self.candidates.iter().fold(None, |best, x| match best {
+                    Some(best) if !x.is_better_than(best) => Some(best),
+                    _ => Some(x),
+                });
+                match best.cloned() {
+                    Some(pan) => self.solicit_config(pan, 1, now).map(Some),
+                    None => {
+                        self.solicit(WiSUNAsyncFrameType::PANAdvertSOL)?;
+                        self.deadline = Some(now + self.config.discovery_period);
+                        Ok(None)
+                    }
+                }
+            }
+            FanState::ConfigSolicit { pan, attempts } => {
+                if attempts < self.config.max_config_attempts {
+                    return self.solicit_config(pan, attempts + 1, now).map(Some);
+                }
+                self.candidates.retain(|x| x.address != pan.address);
+                self.discover(now).map(Some)
+            }
+            FanState::Idle | FanState::AuthenticationReady { .. } => {
+                self.deadline = None;
+                Ok(None)
+            }
+        }
+    }
+
+    fn discover(&mut self, now: Instant) -> Result<FanEvent, Error> {
+        self.solicit(WiSUNAsyncFrameType::PANAdvertSOL)?;
+        self.deadline = Some(now + self.config.discovery_period);
+        Ok(self.transition(FanState::Discovery))
+    }
+
+    fn solicit_config(
+        &mut self,
+        pan: PanCandidate,
+        attempts: u32,
+        now: Instant,
+    ) -> Result<FanEvent, Error> {
+        self.solicit(WiSUNAsyncFrameType::PANConfigSOL)?;
+        self.deadline = Some(now + self.config.config_timeout);
+        Ok(self.transition(FanState::ConfigSolicit { pan, attempts }))
+    }
+
+    fn solicit(&mut self, frame_type: WiSUNAsyncFrameType) -> Result<(), Error> {
+        send_async(&mut self.client, frame_type, self.config.channels)
+    }
+
+    fn transition(&mut self, to: FanState) -> FanEvent {
+        let from = core::mem::replace(&mut self.state, to.clone());
+        FanEvent::StateChanged { from, to }
+    }
+
+    // Advertisements for another network are ignored, repeated ones
+    // refresh the candidate.
+    fn on_pan_advert(&mut self, indication: &areq::WSAsyncInd) -> Option<PanCandidate> {
+        let ies = wp_ies(indication);
+        let net_name = self.config.net_name.as_str().as_bytes();
+        if !ies
+            .iter()
+            .any(|ie| matches!(ie, WpIe::NetName(name) if name.as_slice() == net_name))
+        {
+            return None;
+        }
+
+        let (pan_size, routing_cost) = ies
+            .iter()
+            .find_map(|ie| match ie {
+                WpIe::Pan {
+                    pan_size,
+                    routing_cost,
+                    ..
+                } => Some((Some(*pan_size), Some(*routing_cost))),
+                _ => None,
+            })
+            .unwrap_or((None, None));
+        let candidate = PanCandidate {
+            address: indication.src_address,
+            pan_id: indication.src_pan_id,
+            link_quality: indication.link_quality,
+            rssi: indication.rssi,
+            pan_size,
+            routing_cost,
+        };
+        self.candidates.retain(|x| x.address != candidate.address);
+        self.candidates.push(candidate.clone());
+        Some(candidate)
+    }
+}
+
+fn send_async<C: Client>(
+    client: &mut C,
+    frame_type: WiSUNAsyncFrameType,
+    channels: ChannelMask,
+) -> Result<(), Error> {
+    let request = sreq::WSAsyncReq {
+        operation: WiSUNAsyncOperation::Start,
+        frame_type,
+        security: Security::none(),
+        channels,
+    };
+    let frame = client.request(request.into_mt_frame())?;
+    client::expect_response(&frame, MTSubsystem::MAC, MACCommandId::WSAsyncReq as u8)?;
+    srsp::WSAsyncReq::try_decode(&frame.payload)?.check()?;
+    Ok(())
+}
+
+// Sub-IEs of every WP-IE in the indication, malformed ones are skipped.
+fn wp_ies(indication: &areq::WSAsyncInd) -> Vec<WpIe> {
+    PayloadIe::try_decode_list(&indication.ie_payload)
+        .unwrap_or_default()
+        .iter()
+        .filter(|ie| ie.group_id == WISUN_PAYLOAD_IE_GROUP)
+        .filter_map(|ie| ie.wp_ies().ok())
+        .flatten()
+        .collect()
+}
+
+/// Interval bounds and redundancy constant of a Trickle timer (RFC 6206).
+/// The default matches the Wi-SUN discovery timers, DISC_IMIN of 15 s
+/// doubled up to twice and DISC_K of 1.
+#[derive(Debug, Copy, Clone)]
+pub struct TrickleConfig {
+    pub imin: Duration,
+    pub doublings: u32,
+    pub k: u32,
+}
+
+impl Default for TrickleConfig {
+    fn default() -> Self {
+        TrickleConfig {
+            imin: Duration::from_secs(15),
+            doublings: 2,
+            k: 1,
+        }
+    }
+}
+
+/// A Trickle timer: transmits once per interval at a random point in its
+/// second half unless `k` consistent transmissions were heard first, and
+/// doubles the interval each time up to `imin * 2^doublings`.
+#[derive(Debug, Clone)]
+pub struct Trickle {
+    config: TrickleConfig,
+    interval: Duration,
+    interval_start: Instant,
+    transmit_at: Option<Instant>,
+    counter: u32,
+    // xorshift state for picking the transmission time.
+    state: u64,
+}
+
+impl Trickle {
+    pub fn new(config: TrickleConfig, seed: u64, now: Instant) -> Trickle {
+        let mut trickle = Trickle {
+            config,
+            interval: config.imin,
+            interval_start: now,
+            transmit_at: None,
+            counter: 0,
+            state: (seed ^ 0x9e37_79b9_7f4a_7c15) | 1,
+        };
+        trickle.begin_interval(now);
+        trickle
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// A neighbor sent the same information.
+    pub fn hear_consistent(&mut self) {
+        self.counter = self.counter.saturating_add(1);
+    }
+
+    /// Something is out of date, start over with the shortest interval.
+    pub fn reset(&mut self, now: Instant) {
+        if self.interval != self.config.imin {
+            self.interval = self.config.imin;
+            self.begin_interval(now);
+        }
+    }
+
+    /// Whether a transmission is due at `now`.
+    pub fn poll(&mut self, now: Instant) -> bool {
+        let mut transmit = false;
+        if let Some(transmit_at) = self.transmit_at {
+            if now >= transmit_at {
+                self.transmit_at = None;
+                transmit = self.counter < self.config.k;
+            }
+        }
+        if now >= self.interval_start + self.interval {
+            let imax = self.config.imin * 2u32.pow(self.config.doublings);
+            self.interval = (self.interval * 2).min(imax);
+            self.begin_interval(now);
+        }
+        transmit
+    }
+
+    fn begin_interval(&mut self, now: Instant) {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let random = self.state.wrapping_mul(0x2545_f491_4f6c_dd1d);
+
+        let half = (self.interval.as_nanos() / 2).max(1) as u64;
+        self.interval_start = now;
+        self.transmit_at = Some(now + Duration::from_nanos(half + random % half));
+        self.counter = 0;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BorderRouterConfig {
+    pub net_name: NetName,
+    pub channels: ChannelMask,
+    pub pan_size: u16,
+    pub pan_advert: TrickleConfig,
+    pub pan_config: TrickleConfig,
+    // Seeds the Trickle timers, use something that differs between border
+    // routers such as the extended address.
+    pub seed: u64,
+    // Written between enabling and starting frequency hopping, e.g. the
+    // schedules.
+    pub pib: PibConfig,
+}
+
+impl BorderRouterConfig {
+    pub fn new(net_name: NetName) -> BorderRouterConfig {
+        BorderRouterConfig {
+            net_name,
+            channels: ChannelMask::all(),
+            pan_size: 0,
+            pan_advert: TrickleConfig::default(),
+            pan_config: TrickleConfig::default(),
+            seed: 0,
+            pib: PibConfig::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BorderRouterEvent {
+    // A node asked for PAN advertisements or the PAN configuration, the
+    // matching Trickle timer was reset.
+    Solicited {
+        frame_type: WiSUNAsyncFrameType,
+        from: Address,
+    },
+    // Anything the scheduler does not act on itself.
+    Other(MTFramePayload),
+}
+
+/// Coordinator side counterpart of `Fan`: sends PAN advertisements and PAN
+/// configurations on their Trickle timers, keeps the PAN version and pushes
+/// GTK hashes into the FH PIB.
+pub struct BorderRouter<C> {
+    client: C,
+    config: BorderRouterConfig,
+    pan_version: u16,
+    gtk_hashes: [GtkHash; 4],
+    pan_advert: Trickle,
+    pan_config: Trickle,
+}
+
+impl<C: Client> BorderRouter<C> {
+    pub fn new(client: C, config: BorderRouterConfig, now: Instant) -> BorderRouter<C> {
+        let pan_advert = Trickle::new(config.pan_advert, config.seed, now);
+        let pan_config = Trickle::new(config.pan_config, !config.seed, now);
+        BorderRouter {
+            client,
+            config,
+            pan_version: 0,
+            gtk_hashes: [GtkHash::default(); 4],
+            pan_advert,
+            pan_config,
+        }
+    }
+
+    pub fn client(&mut self) -> &mut C {
+        &mut self.client
+    }
+
+    pub fn pan_version(&self) -> u16 {
+        self.pan_version
+    }
+
+    pub fn gtk_hashes(&self) -> &[GtkHash; 4] {
+        &self.gtk_hashes
+    }
+
+    /// Enables frequency hopping, writes the network name, PAN size, routing
+    /// cost, PAN version and `config.pib`, and starts hopping.
+    pub fn start(&mut self) -> Result<(), Error> {
+        client::fh_enable(&mut self.client)?;
+        PibConfig::new()
+            .net_name(&self.config.net_name)
+            .fh(
+                FHPIBAttributeId::PANSize,
+                self.config.pan_size.to_le_bytes().to_vec(),
+            )
+            .fh(FHPIBAttributeId::RoutingCost, vec![0x00, 0x00])
+            .fh(
+                FHPIBAttributeId::PANVersion,
+                self.pan_version.to_le_bytes().to_vec(),
+            )
+            .apply(&mut self.client)?;
+        self.config.pib.apply(&mut self.client)?;
+        client::fh_start(&mut self.client)
+    }
+
+    /// Bumps and writes the PAN version, which makes nodes fetch the PAN
+    /// configuration again, and restarts its Trickle timer.
+    pub fn increment_pan_version(&mut self, now: Instant) -> Result<u16, Error> {
+        let pan_version = self.pan_version.wrapping_add(1);
+        PibConfig::new()
+            .fh(
+                FHPIBAttributeId::PANVersion,
+                pan_version.to_le_bytes().to_vec(),
+            )
+            .apply(&mut self.client)?;
+        self.pan_version = pan_version;
+        self.pan_config.reset(now);
+        Ok(pan_version)
+    }
+
+    /// Writes the hash of GTK `index` and announces it with a new PAN
+    /// version. Indexes past 3 fail with `InvalidParameter`.
+    pub fn set_gtk_hash(&mut self, index: u8, hash: GtkHash, now: Instant) -> Result<u16, Error> {
+        let request = sreq::FHSetReq::gtk_hash(index, hash)
+            .ok_or(Error::CommandFailed(Status::InvalidParameter))?;
+        PibConfig::new()
+            .fh(request.attribute_id, request.data)
+            .apply(&mut self.client)?;
+        self.gtk_hashes[usize::from(index)] = hash;
+        self.increment_pan_version(now)
+    }
+
+    /// Handles an asynchronous frame from the coprocessor. Solicits reset the
+    /// matching timer, advertisements and configurations of the same PAN
+    /// count as consistent.
+    pub fn handle(&mut self, frame: &MTFrame, now: Instant) -> Result<BorderRouterEvent, Error> {
+        use MTFramePayload::*;
+        let indication = match MTFramePayload::try_from_frame(frame)? {
+            MAC_WSAsyncInd_AREQ(x) => x,
+            MAC_WSAsyncCnf_AREQ(x) => {
+                return Ok(BorderRouterEvent::Other(MAC_WSAsyncCnf_AREQ(x.check()?)))
+            }
+            payload => return Ok(BorderRouterEvent::Other(payload)),
+        };
+
+        let frame_type = indication.frame_type;
+        match frame_type {
+            WiSUNAsyncFrameType::PANAdvertSOL => self.pan_advert.reset(now),
+            WiSUNAsyncFrameType::PANConfigSOL => self.pan_config.reset(now),
+            WiSUNAsyncFrameType::PANAdvert => {
+                let net_name = self.config.net_name.as_str().as_bytes();
+                let same_network = wp_ies(&indication)
+                    .iter()
+                    .any(|ie| matches!(ie, WpIe::NetName(name) if name.as_slice() == net_name));
+                if same_network {
+                    self.pan_advert.hear_consistent();
+                }
+                return Ok(BorderRouterEvent::Other(MAC_WSAsyncInd_AREQ(indication)));
+            }
+            WiSUNAsyncFrameType::PANConfig => {
+                if wp_ies(&indication).contains(&WpIe::PanVersion(self.pan_version)) {
+                    self.pan_config.hear_consistent();
+                }
+                return Ok(BorderRouterEvent::Other(MAC_WSAsyncInd_AREQ(indication)));
+            }
+            _ => return Ok(BorderRouterEvent::Other(MAC_WSAsyncInd_AREQ(indication))),
+        }
+        Ok(BorderRouterEvent::Solicited {
+            frame_type,
+            from: indication.src_address,
+        })
+    }
+
+    /// Sends whatever the Trickle timers say is due and returns the frame
+    /// types sent.
+    pub fn poll(&mut self, now: Instant) -> Result<Vec<WiSUNAsyncFrameType>, Error> {
+        let mut sent = Vec::new();
+        if self.pan_advert.poll(now) {
+            self.send(WiSUNAsyncFrameType::PANAdvert)?;
+            sent.push(WiSUNAsyncFrameType::PANAdvert);
+        }
+        if self.pan_config.poll(now) {
+            self.send(WiSUNAsyncFrameType::PANConfig)?;
+            sent.push(WiSUNAsyncFrameType::PANConfig);
+        }
+        Ok(sent)
+    }
+
+    fn send(&mut self, frame_type: WiSUNAsyncFrameType) -> Result<(), Error> {
+        send_async(&mut self.client, frame_type, self.config.channels)
+    }
+}
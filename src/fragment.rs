@@ -0,0 +1,214 @@
+use crate::error::Error;
+use crate::subsystem::mac::areq::DataInd;
+use crate::types::Address;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+// Fragment headers as in RFC 4944: the first fragment carries the datagram
+// size and tag, the following ones add the offset in units of 8 bytes.
+pub(crate) const FRAG1: u8 = 0xc0;
+pub(crate) const FRAGN: u8 = 0xe0;
+pub(crate) const DISPATCH_MASK: u8 = 0xf8;
+pub(crate) const FRAG1_HEADER_SIZE: usize = 4;
+pub(crate) const FRAGN_HEADER_SIZE: usize = 5;
+
+/// Largest payload the 11-bit datagram size can describe.
+pub const MAX_PAYLOAD_SIZE: usize = 0x07ff;
+
+pub(crate) fn is_fragment(payload: &[u8]) -> bool {
+    payload
+        .first()
+        .is_some_and(|byte| byte & DISPATCH_MASK == FRAG1 || byte & DISPATCH_MASK == FRAGN)
+}
+
+pub(crate) fn frag1_header(size: u16, tag: u16) -> Vec<u8> {
+    let mut header = vec![FRAG1 | (size >> 8) as u8, size as u8];
+    header.extend(tag.to_be_bytes().iter());
+    header
+}
+
+// `offset` is in bytes and has to be a multiple of 8.
+pub(crate) fn fragn_header(size: u16, tag: u16, offset: usize) -> Vec<u8> {
+    let mut header = vec![FRAGN | (size >> 8) as u8, size as u8];
+    header.extend(tag.to_be_bytes().iter());
+    header.push((offset / 8) as u8);
+    header
+}
+
+/// Splits payloads into MSDUs of at most `mtu` bytes.
+#[derive(Debug)]
+pub struct Fragmenter {
+    mtu: usize,
+    next_tag: u16,
+}
+
+impl Fragmenter {
+    pub fn new(mtu: usize) -> Fragmenter {
+        Fragmenter { mtu, next_tag: 0 }
+    }
+
+    /// Returns the MSDUs to send in order. A payload that fits is sent as it
+    /// is, unless its first byte would read as a fragment header.
+    pub fn split(&mut self, payload: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
+        if payload.len() <= self.mtu && !is_fragment(payload) {
+            return Ok(vec![payload.to_vec()]);
+        }
+        if payload.len() > MAX_PAYLOAD_SIZE {
+            return Err(Error::PayloadTooLarge(payload.len()));
+        }
+
+        // All fragments but the last carry a multiple of 8 bytes.
+        let next_size = self.mtu.saturating_sub(FRAGN_HEADER_SIZE) / 8 * 8;
+        let first_size = match self.mtu.saturating_sub(FRAG1_HEADER_SIZE) {
+            room if room >= payload.len() => payload.len(),
+            room if room >= 8 && next_size > 0 => room / 8 * 8,
+            _ => return Err(Error::PayloadTooLarge(payload.len())),
+        };
+
+        let tag = self.next_tag;
+        self.next_tag = self.next_tag.wrapping_add(1);
+        let size = payload.len() as u16;
+
+        let mut first = frag1_header(size, tag);
+        first.extend(payload[..first_size].iter());
+
+        let mut fragments = vec![first];
+        let mut offset = first_size;
+        while offset < payload.len() {
+            let end = (offset + next_size).min(payload.len());
+            let mut fragment = fragn_header(size, tag, offset);
+            fragment.extend(payload[offset..end].iter());
+            fragments.push(fragment);
+            offset = end;
+        }
+        Ok(fragments)
+    }
+}
+
+#[derive(Debug)]
+struct Partial {
+    started: Instant,
+    data: Vec<u8>,
+    // Byte ranges received so far, by start. They never overlap, so their
+    // lengths add up to `received`.
+    ranges: BTreeMap<usize, usize>,
+    received: usize,
+}
+
+/// Puts fragmented payloads back together. Fragments of a payload that is
+/// not complete within `timeout` are dropped by `poll`.
+#[derive(Debug)]
+pub struct Reassembler {
+    timeout: Duration,
+    partial: HashMap<(Address, u16), Partial>,
+}
+
+impl Reassembler {
+    pub fn new(timeout: Duration) -> Reassembler {
+        Reassembler {
+            timeout,
+            partial: HashMap::new(),
+        }
+    }
+
+    /// Number of payloads waiting for fragments.
+    pub fn len(&self) -> usize {
+        self.partial.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.partial.is_empty()
+    }
+
+    pub fn on_data_ind(
+        &mut self,
+        indication: &DataInd,
+        now: Instant,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.receive(indication.src_address, &indication.data_payload, now)
+    }
+
+    /// Returns the payload once `msdu` completes it. MSDUs without a fragment
+    /// header are returned as they are.
+    pub fn receive(
+        &mut self,
+        source: Address,
+        msdu: &[u8],
+        now: Instant,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        if !is_fragment(msdu) {
+            return Ok(Some(msdu.to_vec()));
+        }
+
+        let header_size = match msdu[0] & DISPATCH_MASK {
+            FRAG1 => FRAG1_HEADER_SIZE,
+            _ => FRAGN_HEADER_SIZE,
+        };
+        if msdu.len() < header_size {
+            return Err(Error::InvalidFragment);
+        }
+        let size = usize::from(u16::from_be_bytes([msdu[0] & 0x07, msdu[1]]));
+        let tag = u16::from_be_bytes([msdu[2], msdu[3]]);
+        let offset = match header_size {
+            FRAG1_HEADER_SIZE => 0,
+            _ => usize::from(msdu[4]) * 8,
+        };
+        let data = &msdu[header_size..];
+        if offset + data.len() > size {
+            return Err(Error::InvalidFragment);
+        }
+
+        let key = (source, tag);
+        // A new size under the same tag means the sender started over.
+        if self
+            .partial
+            .get(&key)
+            .is_some_and(|partial| partial.data.len() != size)
+        {
+            self.partial.remove(&key);
+        }
+        let partial = self.partial.entry(key).or_insert_with(|| Partial {
+            started: now,
+            data: vec![0x00; size],
+            ranges: BTreeMap::new(),
+            received: 0,
+        });
+        let end = offset + data.len();
+        // Repeated fragments are ignored, fragments overlapping others drop
+        // the payload as RFC 4944 asks.
+        if partial.ranges.get(&offset) != Some(&end) {
+            if partial
+                .ranges
+                .range(..end.max(offset + 1))
+                .next_back()
+                .is_some_and(|(_, last)| *last > offset)
+            {
+                self.partial.remove(&key);
+                return Err(Error::InvalidFragment);
+            }
+            partial.data[offset..end].copy_from_slice(data);
+            partial.ranges.insert(offset, end);
+            partial.received += data.len();
+        }
+
+        if partial.received < size {
+            return Ok(None);
+        }
+        Ok(self.partial.remove(&key).map(|partial| partial.data))
+    }
+
+    /// Drops the payloads that timed out and returns their source and tag.
+    pub fn poll(&mut self, now: Instant) -> Vec<(Address, u16)> {
+        let timeout = self.timeout;
+        let expired: Vec<_> = self
+            .partial
+            .iter()
+            .filter(|(_, partial)| now.saturating_duration_since(partial.started) >= timeout)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in expired.iter() {
+            self.partial.remove(key);
+        }
+        expired
+    }
+}
@@ -1,10 +1,15 @@
 use crate::error::Error;
-use crate::types::{CommandType, MTExtendedHeaderStatus, MTSubsystem};
-use bytes::{Buf, BufMut};
+use crate::reader::Reader;
+use crate::types::{CommandId, CommandType, MTExtendedHeaderStatus, MTSubsystem};
+use crate::writer::BufMut;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
 use num_traits::FromPrimitive;
-use std::io::{Cursor, Read};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MTFrame {
     pub header: MTHeader,
     pub extended_header: Option<MTExtendedHeader>,
@@ -12,19 +17,60 @@ pub struct MTFrame {
 }
 
 impl MTFrame {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let header = MTHeader::try_decode(Read::by_ref(cursor))?;
+    // The length byte covers the 4 byte V2 header as well.
+    pub const MAX_BLOCK_PAYLOAD: usize = 0xff - 4;
+
+    /// Frame of `command` carrying `payload`, with the length taken from the
+    /// payload. Payloads the one byte length field cannot count get a V2
+    /// extended header, as a frame joined by `Reassembler` has, and are
+    /// split with `into_blocks` when sent.
+    pub fn new(command: CommandCode, payload: Vec<u8>) -> MTFrame {
+        if let Ok(length) = u8::try_from(payload.len()) {
+            return MTFrame {
+                header: MTHeader {
+                    length,
+                    command: CommandCode {
+                        is_extended: false,
+                        ..command
+                    },
+                },
+                extended_header: None,
+                payload,
+            };
+        }
+
+        // Past a u16 the payload cannot be described at all, `into_blocks`
+        // refuses it.
+        let packet_length = u16::try_from(payload.len()).unwrap_or(u16::MAX);
+        MTFrame {
+            header: MTHeader {
+                // The length of a first block.
+                length: u8::MAX,
+                command: CommandCode {
+                    is_extended: true,
+                    ..command
+                },
+            },
+            extended_header: Some(MTExtendedHeader::V2 {
+                stack_id: 0,
+                block: 0,
+                packet_length,
+            }),
+            payload,
+        }
+    }
+
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let header = MTHeader::try_decode(cursor)?;
 
         let extended_header = if header.has_extension() {
-            Some(MTExtendedHeader::try_decode(Read::by_ref(cursor))?)
+            Some(MTExtendedHeader::try_decode(cursor)?)
         } else {
             None
         };
 
         let mut payload = Vec::new();
-        cursor
-            .read_to_end(&mut payload)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.read_to_end(&mut payload)?;
 
         Ok(MTFrame {
             header,
@@ -43,6 +89,14 @@ impl MTFrame {
         buffer.extend(self.payload.iter());
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip_all,
+            fields(command = ?self.header.command, length = self.payload.len())
+        )
+    )]
     pub fn encode_to_uart_transport_frame(&self) -> Vec<u8> {
         const START_OF_FRAME: u8 = 0xfe;
         let mut buffer = Vec::new();
@@ -53,12 +107,166 @@ impl MTFrame {
         buffer
     }
 
+    /// XOR of all bytes, taken eight at a time.
     pub fn compute_frame_check_sequence(mt_frame_bytes: &[u8]) -> u8 {
-        mt_frame_bytes.iter().fold(0, |acc, x| acc ^ x)
+        let mut chunks = mt_frame_bytes.chunks_exact(8);
+        let wide = chunks.by_ref().fold(0u64, |acc, chunk| {
+            let mut word = [0x00; 8];
+            word.copy_from_slice(chunk);
+            acc ^ u64::from_ne_bytes(word)
+        });
+        let fcs = wide.to_ne_bytes().iter().fold(0, |acc, x| acc ^ x);
+        chunks.remainder().iter().fold(fcs, |acc, x| acc ^ x)
+    }
+
+    /// Splits a frame whose payload does not fit the one byte length field,
+    /// as built by `MTFrame::new`, into blocks carrying a V2 extended header
+    /// with the full packet length. Frames that fit are returned unchanged.
+    /// Payloads whose length or block count does not fit the header fail
+    /// with `PayloadTooLarge`.
+    pub fn into_blocks(self, stack_id: u8) -> Result<Vec<MTFrame>, Error> {
+        let extended_header_size = self.extended_header.as_ref().map_or(0, |x| x.size());
+        if self.payload.len() + extended_header_size <= usize::from(u8::MAX) {
+            return Ok(vec![self]);
+        }
+
+        let size = self.payload.len();
+        let packet_length = u16::try_from(size).map_err(|_| Error::PayloadTooLarge(size))?;
+        if size.div_ceil(MTFrame::MAX_BLOCK_PAYLOAD) > usize::from(u8::MAX) + 1 {
+            return Err(Error::PayloadTooLarge(size));
+        }
+        Ok(self
+            .payload
+            .chunks(MTFrame::MAX_BLOCK_PAYLOAD)
+            .zip(0..=u8::MAX)
+            .map(|(payload, block)| {
+                let extended_header = MTExtendedHeader::V2 {
+                    stack_id,
+                    block,
+                    packet_length,
+                };
+                MTFrame {
+                    header: MTHeader {
+                        length: (extended_header.size() + payload.len()) as u8,
+                        command: CommandCode {
+                            is_extended: true,
+                            ..self.header.command.clone()
+                        },
+                    },
+                    extended_header: Some(extended_header),
+                    payload: payload.to_vec(),
+                }
+            })
+            .collect())
+    }
+
+    /// The V3 frame acknowledging this block, sent back for each block of an
+    /// extended frame.
+    pub fn block_ack(&self, status: MTExtendedHeaderStatus) -> Option<MTFrame> {
+        let (stack_id, block) = match self.extended_header {
+            Some(MTExtendedHeader::V2 {
+                stack_id, block, ..
+            }) => (stack_id, block),
+            _ => return None,
+        };
+        let extended_header = MTExtendedHeader::V3 {
+            stack_id,
+            block,
+            status,
+        };
+        Some(MTFrame {
+            header: MTHeader {
+                length: extended_header.size() as u8,
+                command: self.header.command.clone(),
+            },
+            extended_header: Some(extended_header),
+            payload: Vec::new(),
+        })
     }
 }
 
-#[derive(Debug, Clone)]
+/// Joins the blocks of extended frames back into a single frame. The joined
+/// frame keeps the V2 header of its first block, whose `packet_length` is the
+/// size of the full payload.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone)]
+pub struct Reassembler {
+    pending: Option<MTFrame>,
+    next_block: u8,
+}
+
+impl Reassembler {
+    pub fn new() -> Reassembler {
+        Reassembler::default()
+    }
+
+    /// Returns the frame once complete. Frames without a V2 header are
+    /// passed through.
+    pub fn push(&mut self, frame: MTFrame) -> Result<Option<MTFrame>, Error> {
+        let (block, packet_length) = match frame.extended_header {
+            Some(MTExtendedHeader::V2 {
+                block,
+                packet_length,
+                ..
+            }) => (block, packet_length),
+            _ => return Ok(Some(frame)),
+        };
+
+        let mut pending = match self.pending.take() {
+            _ if block == 0 => {
+                self.next_block = 0;
+                MTFrame {
+                    payload: Vec::with_capacity(usize::from(packet_length)),
+                    ..frame.clone()
+                }
+            }
+            Some(pending)
+                if block == self.next_block && pending.header.command == frame.header.command =>
+            {
+                pending
+            }
+            _ => {
+                return Err(Error::Fragmentation(
+                    MTExtendedHeaderStatus::BlockOutOfOrder,
+                ))
+            }
+        };
+
+        match pending.extended_header {
+            Some(MTExtendedHeader::V2 {
+                packet_length: expected,
+                ..
+            }) if expected == packet_length => {}
+            _ => {
+                return Err(Error::Fragmentation(
+                    MTExtendedHeaderStatus::BlockLengthChanged,
+                ))
+            }
+        }
+
+        pending.payload.extend_from_slice(&frame.payload);
+        if pending.payload.len() > usize::from(packet_length) {
+            return Err(Error::Fragmentation(
+                MTExtendedHeaderStatus::BlockLengthChanged,
+            ));
+        }
+        if pending.payload.len() == usize::from(packet_length) {
+            return Ok(Some(pending));
+        }
+
+        self.next_block = block.wrapping_add(1);
+        self.pending = Some(pending);
+        Ok(None)
+    }
+
+    pub fn reset(&mut self) {
+        self.pending = None;
+        self.next_block = 0;
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MTHeader {
     pub length: u8,
     pub command: CommandCode,
@@ -66,15 +274,15 @@ pub struct MTHeader {
 
 impl MTHeader {
     pub fn size() -> usize {
-        return 3;
+        3
     }
 
     pub fn has_extension(&self) -> bool {
         self.command.is_extended
     }
 
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let length = cursor.get_u8();
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let length = cursor.read_u8()?;
         let command = CommandCode::try_decode(cursor)?;
         Ok(MTHeader { length, command })
     }
@@ -85,7 +293,9 @@ impl MTHeader {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CommandCode {
     pub is_extended: bool,
     pub cmd_type: CommandType,
@@ -94,17 +304,22 @@ pub struct CommandCode {
 }
 
 impl CommandCode {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let type_and_subsystem = cursor.get_u8();
-        let id = cursor.get_u8();
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let cmd0 = cursor.read_u8()?;
+        let cmd1 = cursor.read_u8()?;
+        CommandCode::from_cmd(cmd0, cmd1)
+    }
 
-        let is_extended = (type_and_subsystem & 0x80) != 0;
+    /// Splits the CMD0 byte into the extension flag, command type and
+    /// subsystem. CMD1 is the command id.
+    pub fn from_cmd(cmd0: u8, cmd1: u8) -> Result<Self, Error> {
+        let is_extended = (cmd0 & 0x80) != 0;
 
-        let cmd_type = 0x03 & (type_and_subsystem >> 5);
+        let cmd_type = 0x03 & (cmd0 >> 5);
         let cmd_type =
             FromPrimitive::from_u8(cmd_type).ok_or(Error::InvalidCommandType(cmd_type))?;
 
-        let subsystem = type_and_subsystem & 0x1F;
+        let subsystem = cmd0 & 0x1F;
         let subsystem =
             FromPrimitive::from_u8(subsystem).ok_or(Error::InvalidSubsystem(subsystem))?;
 
@@ -112,25 +327,42 @@ impl CommandCode {
             is_extended,
             cmd_type,
             subsystem,
-            id,
+            id: cmd1,
         })
     }
 
+    pub fn cmd0(&self) -> u8 {
+        let value = ((self.cmd_type as u8) << 5) | (self.subsystem as u8);
+        if self.is_extended {
+            0x80 | value
+        } else {
+            value
+        }
+    }
+
+    pub fn command_id(&self) -> CommandId {
+        CommandId(self.id)
+    }
+
+    /// Whether this is the `cmd_type` frame of command `id` in `subsystem`.
+    pub fn is(
+        &self,
+        cmd_type: CommandType,
+        subsystem: MTSubsystem,
+        id: impl Into<CommandId>,
+    ) -> bool {
+        self.cmd_type == cmd_type && self.subsystem == subsystem && self.command_id() == id.into()
+    }
+
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
-        let type_and_subsystem = {
-            let value = ((self.cmd_type as u8) << 5) | (self.subsystem as u8);
-            if self.is_extended {
-                0x80 | value
-            } else {
-                value
-            }
-        };
-        buffer.put_u8(type_and_subsystem);
+        buffer.put_u8(self.cmd0());
         buffer.put_u8(self.id);
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MTExtendedHeader {
     V1 {
         stack_id: u8,
@@ -153,8 +385,25 @@ pub enum MTExtendedHeader {
 }
 
 impl MTExtendedHeader {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let version_and_stack_id = cursor.get_u8();
+    pub fn size(&self) -> usize {
+        match self {
+            MTExtendedHeader::V1 { .. } => 1,
+            MTExtendedHeader::V2 { .. } => 4,
+            MTExtendedHeader::V3 { .. } | MTExtendedHeader::V4 { .. } => 3,
+        }
+    }
+
+    pub fn stack_id(&self) -> u8 {
+        match *self {
+            MTExtendedHeader::V1 { stack_id }
+            | MTExtendedHeader::V2 { stack_id, .. }
+            | MTExtendedHeader::V3 { stack_id, .. }
+            | MTExtendedHeader::V4 { stack_id, .. } => stack_id,
+        }
+    }
+
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let version_and_stack_id = cursor.read_u8()?;
         let version = (version_and_stack_id & 0xf8) >> 3;
         let stack_id = version_and_stack_id & 0x07;
 
@@ -162,10 +411,10 @@ impl MTExtendedHeader {
             return Ok(MTExtendedHeader::V1 { stack_id });
         }
 
-        let block = cursor.get_u8();
+        let block = cursor.read_u8()?;
 
         if version == 2 {
-            let packet_length = cursor.get_u16_le();
+            let packet_length = cursor.read_u16_le()?;
             return Ok(MTExtendedHeader::V2 {
                 stack_id,
                 block,
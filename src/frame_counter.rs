@@ -0,0 +1,154 @@
+use crate::client::{self, Client, Confirm};
+use crate::error::Error;
+use crate::reader::Reader;
+use crate::subsystem::mac::{sreq, srsp};
+use crate::types::{MACCommandId, MTSubsystem, SecurityPIBAttributeId};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct FrameCounterConfig {
+    // Time between two reads of the frame counter.
+    pub interval: Duration,
+    // Warn once fewer frames than this are left before the counter wraps.
+    pub margin: u32,
+    // Warn once the counter is expected to wrap within this time at the
+    // current rate.
+    pub horizon: Duration,
+}
+
+impl Default for FrameCounterConfig {
+    fn default() -> Self {
+        FrameCounterConfig {
+            interval: Duration::from_secs(3600),
+            margin: 1 << 24,
+            horizon: Duration::from_secs(7 * 24 * 3600),
+        }
+    }
+}
+
+/// Raised once when the counter crosses the margin or the horizon, and again
+/// only after it went back down, i.e. after the key was rotated.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct RolloverWarning {
+    pub frame_counter: u32,
+    pub remaining: u32,
+    pub time_left: Option<Duration>,
+}
+
+/// Tracks the outgoing MAC frame counter and its rate so keys can be
+/// rotated before the nonces run out.
+#[derive(Debug)]
+pub struct FrameCounterMonitor {
+    config: FrameCounterConfig,
+    last: Option<(Instant, u32)>,
+    // Frames per second between the last two reads.
+    rate: Option<f64>,
+    next_read: Option<Instant>,
+    warned: bool,
+}
+
+impl FrameCounterMonitor {
+    pub fn new(config: FrameCounterConfig) -> FrameCounterMonitor {
+        FrameCounterMonitor {
+            config,
+            last: None,
+            rate: None,
+            next_read: None,
+            warned: false,
+        }
+    }
+
+    pub fn frame_counter(&self) -> Option<u32> {
+        self.last.map(|(_, frame_counter)| frame_counter)
+    }
+
+    pub fn rate(&self) -> Option<f64> {
+        self.rate
+    }
+
+    pub fn remaining(&self) -> Option<u32> {
+        self.frame_counter()
+            .map(|frame_counter| u32::MAX - frame_counter)
+    }
+
+    pub fn time_to_rollover(&self) -> Option<Duration> {
+        let remaining = self.remaining()?;
+        match self.rate {
+            Some(rate) if rate > 0.0 => {
+                Duration::try_from_secs_f64(f64::from(remaining) / rate).ok()
+            }
+            _ => None,
+        }
+    }
+
+    pub fn request(&self) -> sreq::SecurityGetReq {
+        sreq::SecurityGetReq {
+            attribute_id: SecurityPIBAttributeId::FrameCounter,
+            index1: 0,
+            index2: 0,
+        }
+    }
+
+    /// Takes the response to `request`, read at `now`.
+    pub fn observe(
+        &mut self,
+        response: &srsp::SecurityGetReq,
+        now: Instant,
+    ) -> Result<Option<RolloverWarning>, Error> {
+        response
+            .status
+            .into_result()
+            .map_err(Error::CommandFailed)?;
+        let mut cursor = Reader::new(response.data.as_slice());
+        let frame_counter = cursor.field("frame_counter", Reader::read_u32_le)?;
+        Ok(self.update(frame_counter, now))
+    }
+
+    /// Reads the counter if `interval` elapsed since the last read, the first
+    /// call always reads.
+    pub fn poll<C: Client>(
+        &mut self,
+        client: &mut C,
+        now: Instant,
+    ) -> Result<Option<RolloverWarning>, Error> {
+        match self.next_read {
+            Some(next_read) if now < next_read => return Ok(None),
+            _ => self.next_read = Some(now + self.config.interval),
+        }
+
+        let frame = client.request(self.request().into_mt_frame())?;
+        let id = MACCommandId::SecurityGetReq as u8;
+        client::expect_response(&frame, MTSubsystem::MAC, id)?;
+        let response = srsp::SecurityGetReq::try_decode(&frame.payload)?.check()?;
+        self.observe(&response, now)
+    }
+
+    fn update(&mut self, frame_counter: u32, now: Instant) -> Option<RolloverWarning> {
+        match self.last {
+            Some((then, previous)) if frame_counter >= previous => {
+                let elapsed = now.saturating_duration_since(then).as_secs_f64();
+                if elapsed > 0.0 {
+                    self.rate = Some(f64::from(frame_counter - previous) / elapsed);
+                }
+            }
+            // A lower counter means a new key, the old rate still applies.
+            Some(_) => self.warned = false,
+            None => (),
+        }
+        self.last = Some((now, frame_counter));
+
+        let remaining = u32::MAX - frame_counter;
+        let time_left = self.time_to_rollover();
+        let near = remaining <= self.config.margin
+            || time_left.is_some_and(|time_left| time_left <= self.config.horizon);
+        if !near || self.warned {
+            return None;
+        }
+        self.warned = true;
+        Some(RolloverWarning {
+            frame_counter,
+            remaining,
+            time_left,
+        })
+    }
+}
@@ -0,0 +1,337 @@
+#[cfg(feature = "mac")]
+use crate::client::Client;
+use crate::debug;
+use crate::error::Error;
+use crate::frame::{CommandCode, MTFrame};
+use crate::subsystem::MTFramePayload;
+use crate::types::MTSubsystem;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Direction {
+    Tx,
+    Rx,
+}
+
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    pub timestamp: Instant,
+    pub direction: Direction,
+    pub bytes: Vec<u8>,
+}
+
+/// Keeps the last frames exchanged with the device so they can be dumped
+/// once something goes wrong, without capturing the whole session.
+#[derive(Debug)]
+pub struct FrameHistory {
+    capacity: usize,
+    frames: VecDeque<CapturedFrame>,
+}
+
+impl FrameHistory {
+    pub fn new(capacity: usize) -> FrameHistory {
+        FrameHistory {
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Keeps every frame, to capture a whole session.
+    pub fn unbounded() -> FrameHistory {
+        FrameHistory {
+            capacity: usize::MAX,
+            frames: VecDeque::new(),
+        }
+    }
+
+    /// Bytes are kept as given, so input the parser rejected can be recorded too.
+    pub fn record(&mut self, direction: Direction, bytes: &[u8], now: Instant) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(CapturedFrame {
+            timestamp: now,
+            direction,
+            bytes: bytes.to_vec(),
+        });
+    }
+
+    pub fn frame_sent(&mut self, frame: &MTFrame, now: Instant) {
+        self.record(Direction::Tx, &frame.encode_to_uart_transport_frame(), now);
+    }
+
+    pub fn frame_received(&mut self, frame: &MTFrame, now: Instant) {
+        self.record(Direction::Rx, &frame.encode_to_uart_transport_frame(), now);
+    }
+
+    pub fn frames(&self) -> impl Iterator<Item = &CapturedFrame> {
+        self.frames.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Writes one line per frame: seconds since the oldest frame, direction
+    /// and the bytes in hex.
+    pub fn dump<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let start = match self.frames.front() {
+            Some(frame) => frame.timestamp,
+            None => return Ok(()),
+        };
+
+        for frame in &self.frames {
+            let offset = frame.timestamp.saturating_duration_since(start);
+            let direction = match frame.direction {
+                Direction::Tx => "TX",
+                Direction::Rx => "RX",
+            };
+            write!(writer, "{:>12} {}", format_offset(offset), direction)?;
+            for byte in &frame.bytes {
+                write!(writer, " {:02x}", byte)?;
+            }
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn dump_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.dump(&mut writer)?;
+        writer.flush()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Sent(MTFrame),
+    Received(MTFrame),
+    // A request failed, the error is kept as displayed.
+    Failed { command: CommandCode, error: String },
+}
+
+impl Event {
+    pub fn command(&self) -> &CommandCode {
+        match self {
+            Event::Sent(frame) | Event::Received(frame) => &frame.header.command,
+            Event::Failed { command, .. } => command,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoggedEvent {
+    pub timestamp: Instant,
+    pub event: Event,
+}
+
+/// Keeps the last decoded frames and request failures of a client, see
+/// `Logged`. Unlike `FrameHistory` the frames can be queried by command and
+/// are dumped decoded.
+#[derive(Debug)]
+pub struct EventLog {
+    capacity: usize,
+    events: VecDeque<LoggedEvent>,
+}
+
+impl EventLog {
+    pub fn new(capacity: usize) -> EventLog {
+        EventLog {
+            capacity,
+            events: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn record(&mut self, event: Event, now: Instant) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(LoggedEvent {
+            timestamp: now,
+            event,
+        });
+    }
+
+    pub fn frame_sent(&mut self, frame: &MTFrame, now: Instant) {
+        self.record(Event::Sent(frame.clone()), now);
+    }
+
+    pub fn frame_received(&mut self, frame: &MTFrame, now: Instant) {
+        self.record(Event::Received(frame.clone()), now);
+    }
+
+    pub fn failed(&mut self, command: &CommandCode, error: &Error, now: Instant) {
+        let event = Event::Failed {
+            command: command.clone(),
+            error: error.to_string(),
+        };
+        self.record(event, now);
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = &LoggedEvent> {
+        self.events.iter()
+    }
+
+    /// Events recorded at or after `instant`.
+    pub fn since(&self, instant: Instant) -> impl Iterator<Item = &LoggedEvent> {
+        self.events.iter().filter(move |x| x.timestamp >= instant)
+    }
+
+    /// Events of one command, whatever its type, e.g. the requests, responses
+    /// and failures of MAC_DATA_REQ.
+    pub fn command(&self, subsystem: MTSubsystem, id: u8) -> impl Iterator<Item = &LoggedEvent> {
+        self.events.iter().filter(move |x| {
+            let command = x.event.command();
+            command.subsystem == subsystem && command.id == id
+        })
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &LoggedEvent> {
+        self.events
+            .iter()
+            .filter(|x| matches!(x.event, Event::Failed { .. }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Writes one line per event: seconds since the oldest event, TX, RX or
+    /// ERR, the command and the decoded payload or the error.
+    pub fn dump<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let start = match self.events.front() {
+            Some(event) => event.timestamp,
+            None => return Ok(()),
+        };
+
+        for event in &self.events {
+            let offset = event.timestamp.saturating_duration_since(start);
+            let (direction, details) = match &event.event {
+                Event::Sent(frame) => ("TX", describe(frame)),
+                Event::Received(frame) => ("RX", describe(frame)),
+                Event::Failed { error, .. } => ("ERR", error.clone()),
+            };
+            let command = event.event.command();
+            writeln!(
+                writer,
+                "{:>12} {:<3} {:?} {:?} {} {}",
+                format_offset(offset),
+                direction,
+                command.subsystem,
+                command.cmd_type,
+                debug::command_name(command).unwrap_or_else(|| format!("0x{:02x}", command.id)),
+                details
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn describe(frame: &MTFrame) -> String {
+    match MTFramePayload::try_from_frame(frame) {
+        Ok(payload) => format!("{:?}", payload),
+        Err(_) => format!("{:02x?}", frame.payload),
+    }
+}
+
+/// Logs every request made through `client`, its response and its failure
+/// in an `EventLog` of the last `capacity` events. Frames the application
+/// reads outside requests, such as indications, are added with `received`.
+#[cfg(feature = "mac")]
+pub struct Logged<C> {
+    client: C,
+    history: EventLog,
+}
+
+#[cfg(feature = "mac")]
+impl<C: Client> Logged<C> {
+    pub fn new(client: C, capacity: usize) -> Logged<C> {
+        Logged {
+            client,
+            history: EventLog::new(capacity),
+        }
+    }
+
+    pub fn client(&mut self) -> &mut C {
+        &mut self.client
+    }
+
+    pub fn history(&self) -> &EventLog {
+        &self.history
+    }
+
+    pub fn into_inner(self) -> C {
+        self.client
+    }
+
+    pub fn received(&mut self, frame: &MTFrame) {
+        self.history.frame_received(frame, Instant::now());
+    }
+
+    fn record(&mut self, command: &CommandCode, result: &Result<MTFrame, Error>) {
+        match result {
+            Ok(frame) => self.received(frame),
+            Err(error) => {
+                if let Error::UnexpectedResponse(frame) = error {
+                    self.received(frame);
+                }
+                self.history.failed(command, error, Instant::now());
+            }
+        }
+    }
+}
+
+#[cfg(feature = "mac")]
+impl<C: Client> Client for Logged<C> {
+    fn request(&mut self, frame: MTFrame) -> Result<MTFrame, Error> {
+        let command = frame.header.command.clone();
+        self.history.frame_sent(&frame, Instant::now());
+        let result = self.client.request(frame);
+        self.record(&command, &result);
+        result
+    }
+
+    fn request_with_timeout(
+        &mut self,
+        frame: MTFrame,
+        timeout: Duration,
+    ) -> Result<MTFrame, Error> {
+        let command = frame.header.command.clone();
+        self.history.frame_sent(&frame, Instant::now());
+        let result = self.client.request_with_timeout(frame, timeout);
+        self.record(&command, &result);
+        result
+    }
+}
+
+fn format_offset(offset: Duration) -> String {
+    format!("{}.{:06}", offset.as_secs(), offset.subsec_micros())
+}
@@ -0,0 +1,436 @@
+use crate::error::Error;
+use crate::reader::Reader;
+use crate::types::ExtendedAddress;
+use crate::writer::BufMut;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+// IEEE 802.15.4 information elements as carried in the IE payloads of data
+// requests and indications, plus the Wi-SUN FAN sub-IEs nested in them.
+
+pub const WISUN_HEADER_IE: u8 = 0x2a;
+pub const WISUN_PAYLOAD_IE_GROUP: u8 = 0x04;
+
+fn read_u24_le(cursor: &mut Reader) -> Result<u32, Error> {
+    let bytes = cursor.read_bytes(3)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0x00]))
+}
+
+fn put_u24_le(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&value.to_le_bytes()[..3]);
+}
+
+// Length field of a descriptor holding `content`, or `PayloadTooLarge` when
+// the content needs more than `max` bytes.
+fn length(content: &[u8], max: u16) -> Result<u16, Error> {
+    match u16::try_from(content.len()) {
+        Ok(length) if length <= max => Ok(length),
+        _ => Err(Error::PayloadTooLarge(content.len())),
+    }
+}
+
+fn try_decode_list<T>(
+    buffer: &[u8],
+    decode: impl Fn(&mut Reader) -> Result<T, Error>,
+) -> Result<Vec<T>, Error> {
+    let mut cursor = Reader::new(buffer);
+    let mut list = Vec::new();
+    while cursor.remaining() > 0 {
+        list.push(decode(&mut cursor)?);
+    }
+    Ok(list)
+}
+
+// Descriptor: length in bits 0-6, element id in bits 7-14, type bit 15 clear.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeaderIe {
+    pub element_id: u8,
+    pub content: Vec<u8>,
+}
+
+impl HeaderIe {
+    // Header termination IE separating header IEs from payload IEs.
+    pub const HT1: u8 = 0x7e;
+
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let descriptor = cursor.read_u16_le()?;
+        let length = (descriptor & 0x7f) as usize;
+        let element_id = ((descriptor >> 7) & 0xff) as u8;
+        let content = cursor.read_bytes(length)?.to_vec();
+        Ok(HeaderIe {
+            element_id,
+            content,
+        })
+    }
+
+    pub fn try_decode_list(buffer: &[u8]) -> Result<Vec<Self>, Error> {
+        try_decode_list(buffer, HeaderIe::try_decode)
+    }
+
+    /// Fails with `PayloadTooLarge` when the content is longer than the 127
+    /// bytes the descriptor can count.
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) -> Result<(), Error> {
+        let length = length(&self.content, 0x7f)?;
+        buffer.put_u16_le(length | u16::from(self.element_id) << 7);
+        buffer.extend(self.content.iter());
+        Ok(())
+    }
+}
+
+// Descriptor: length in bits 0-10, group id in bits 11-14, type bit 15 set.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PayloadIe {
+    pub group_id: u8,
+    pub content: Vec<u8>,
+}
+
+impl PayloadIe {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let descriptor = cursor.read_u16_le()?;
+        let length = (descriptor & 0x07ff) as usize;
+        let group_id = ((descriptor >> 11) & 0x0f) as u8;
+        let content = cursor.read_bytes(length)?.to_vec();
+        Ok(PayloadIe { group_id, content })
+    }
+
+    pub fn try_decode_list(buffer: &[u8]) -> Result<Vec<Self>, Error> {
+        try_decode_list(buffer, PayloadIe::try_decode)
+    }
+
+    /// Fails with `PayloadTooLarge` when the content is longer than the 2047
+    /// bytes the descriptor can count.
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) -> Result<(), Error> {
+        let length = length(&self.content, 0x07ff)?;
+        buffer.put_u16_le(0x8000 | u16::from(self.group_id & 0x0f) << 11 | length);
+        buffer.extend(self.content.iter());
+        Ok(())
+    }
+
+    /// Builds a WP-IE holding the given Wi-SUN sub-IEs. Fails with
+    /// `PayloadTooLarge` when a sub-IE does not fit its descriptor.
+    pub fn wisun(ies: &[WpIe]) -> Result<PayloadIe, Error> {
+        let mut content = Vec::new();
+        for ie in ies {
+            ie.to_nested_ie().encode_into(&mut content)?;
+        }
+        Ok(PayloadIe {
+            group_id: WISUN_PAYLOAD_IE_GROUP,
+            content,
+        })
+    }
+
+    pub fn nested_ies(&self) -> Result<Vec<NestedIe>, Error> {
+        NestedIe::try_decode_list(&self.content)
+    }
+
+    pub fn wp_ies(&self) -> Result<Vec<WpIe>, Error> {
+        self.nested_ies()?
+            .iter()
+            .map(WpIe::try_from_nested_ie)
+            .collect()
+    }
+}
+
+// Short descriptors hold the length in bits 0-7 and the sub-id in bits 8-14,
+// long ones the length in bits 0-10 and the sub-id in bits 11-14. Bit 15
+// tells them apart.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NestedIe {
+    pub sub_id: u8,
+    pub long: bool,
+    pub content: Vec<u8>,
+}
+
+impl NestedIe {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let descriptor = cursor.read_u16_le()?;
+        let long = descriptor & 0x8000 != 0;
+        let (length, sub_id) = if long {
+            (descriptor & 0x07ff, (descriptor >> 11) & 0x0f)
+        } else {
+            (descriptor & 0x00ff, (descriptor >> 8) & 0x7f)
+        };
+        let content = cursor.read_bytes(length as usize)?.to_vec();
+        Ok(NestedIe {
+            sub_id: sub_id as u8,
+            long,
+            content,
+        })
+    }
+
+    pub fn try_decode_list(buffer: &[u8]) -> Result<Vec<Self>, Error> {
+        try_decode_list(buffer, NestedIe::try_decode)
+    }
+
+    /// Fails with `PayloadTooLarge` when the content is longer than the
+    /// descriptor can count: 2047 bytes for the long form, 255 for the short
+    /// one. The form is not switched, sub-ids mean different IEs in each.
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) -> Result<(), Error> {
+        let descriptor = if self.long {
+            0x8000 | u16::from(self.sub_id & 0x0f) << 11 | length(&self.content, 0x07ff)?
+        } else {
+            u16::from(self.sub_id & 0x7f) << 8 | length(&self.content, 0x00ff)?
+        };
+        buffer.put_u16_le(descriptor);
+        buffer.extend(self.content.iter());
+        Ok(())
+    }
+}
+
+// Sub-IEs of the Wi-SUN header IE, identified by the first content byte.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WhIe {
+    UnicastTiming {
+        frame_type: u8,
+        ufsi: u32,
+    },
+    BroadcastTiming {
+        slot_number: u16,
+        interval_offset: u32,
+    },
+    Rsl(u8),
+    EapolAuthenticator(ExtendedAddress),
+    Other {
+        sub_id: u8,
+        content: Vec<u8>,
+    },
+}
+
+impl WhIe {
+    pub const UTT: u8 = 0x01;
+    pub const BT: u8 = 0x02;
+    pub const RSL: u8 = 0x04;
+    pub const EA: u8 = 0x09;
+
+    pub fn try_from_header_ie(ie: &HeaderIe) -> Result<Self, Error> {
+        let mut cursor = Reader::new(&ie.content[..]);
+        let sub_id = cursor.read_u8()?;
+        let wh_ie = match sub_id {
+            WhIe::UTT => WhIe::UnicastTiming {
+                frame_type: cursor.read_u8()?,
+                ufsi: read_u24_le(&mut cursor)?,
+            },
+            WhIe::BT => WhIe::BroadcastTiming {
+                slot_number: cursor.read_u16_le()?,
+                interval_offset: read_u24_le(&mut cursor)?,
+            },
+            WhIe::RSL => WhIe::Rsl(cursor.read_u8()?),
+            WhIe::EA => WhIe::EapolAuthenticator(ExtendedAddress::try_decode(&mut cursor)?),
+            _ => {
+                let mut content = Vec::new();
+                cursor.read_to_end(&mut content)?;
+                WhIe::Other { sub_id, content }
+            }
+        };
+        Ok(wh_ie)
+    }
+
+    pub fn to_header_ie(&self) -> HeaderIe {
+        let mut content = Vec::new();
+        match self {
+            WhIe::UnicastTiming { frame_type, ufsi } => {
+                content.put_u8(WhIe::UTT);
+                content.put_u8(*frame_type);
+                put_u24_le(&mut content, *ufsi);
+            }
+            WhIe::BroadcastTiming {
+                slot_number,
+                interval_offset,
+            } => {
+                content.put_u8(WhIe::BT);
+                content.put_u16_le(*slot_number);
+                put_u24_le(&mut content, *interval_offset);
+            }
+            WhIe::Rsl(rsl) => {
+                content.put_u8(WhIe::RSL);
+                content.put_u8(*rsl);
+            }
+            WhIe::EapolAuthenticator(address) => {
+                content.put_u8(WhIe::EA);
+                address.encode_into(&mut content);
+            }
+            WhIe::Other {
+                sub_id,
+                content: data,
+            } => {
+                content.put_u8(*sub_id);
+                content.extend(data.iter());
+            }
+        }
+        HeaderIe {
+            element_id: WISUN_HEADER_IE,
+            content,
+        }
+    }
+}
+
+// Fixed part of the unicast and broadcast schedules. The channel plan and
+// channel function fields that follow depend on `channel_info` and are
+// kept as raw bytes.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Schedule {
+    pub dwell_interval: u8,
+    pub clock_drift: u8,
+    pub timing_accuracy: u8,
+    pub channel_info: u8,
+    pub channel_data: Vec<u8>,
+}
+
+impl Schedule {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let dwell_interval = cursor.read_u8()?;
+        let clock_drift = cursor.read_u8()?;
+        let timing_accuracy = cursor.read_u8()?;
+        let channel_info = cursor.read_u8()?;
+        let mut channel_data = Vec::new();
+        cursor.read_to_end(&mut channel_data)?;
+        Ok(Schedule {
+            dwell_interval,
+            clock_drift,
+            timing_accuracy,
+            channel_info,
+            channel_data,
+        })
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u8(self.dwell_interval);
+        buffer.put_u8(self.clock_drift);
+        buffer.put_u8(self.timing_accuracy);
+        buffer.put_u8(self.channel_info);
+        buffer.extend(self.channel_data.iter());
+    }
+}
+
+// Sub-IEs nested in the Wi-SUN payload IE. US, BS and VP use the long
+// descriptor format, the others the short one.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WpIe {
+    UnicastSchedule(Schedule),
+    BroadcastSchedule {
+        broadcast_interval: u32,
+        schedule_id: u16,
+        schedule: Schedule,
+    },
+    Pan {
+        pan_size: u16,
+        routing_cost: u16,
+        use_parent_bs_ie: bool,
+        routing_method: bool,
+        fan_tps_version: u8,
+    },
+    NetName(Vec<u8>),
+    PanVersion(u16),
+    GtkHash([[u8; 8]; 4]),
+    Other(NestedIe),
+}
+
+impl WpIe {
+    pub const US: u8 = 0x01;
+    pub const BS: u8 = 0x02;
+    pub const PAN: u8 = 0x04;
+    pub const NETNAME: u8 = 0x05;
+    pub const PANVER: u8 = 0x06;
+    pub const GTKHASH: u8 = 0x07;
+
+    pub fn try_from_nested_ie(ie: &NestedIe) -> Result<Self, Error> {
+        let mut cursor = Reader::new(&ie.content[..]);
+        let wp_ie = match (ie.long, ie.sub_id) {
+            (true, WpIe::US) => WpIe::UnicastSchedule(Schedule::try_decode(&mut cursor)?),
+            (true, WpIe::BS) => WpIe::BroadcastSchedule {
+                broadcast_interval: cursor.read_u32_le()?,
+                schedule_id: cursor.read_u16_le()?,
+                schedule: Schedule::try_decode(&mut cursor)?,
+            },
+            (false, WpIe::PAN) => {
+                let pan_size = cursor.read_u16_le()?;
+                let routing_cost = cursor.read_u16_le()?;
+                let flags = cursor.read_u8()?;
+                WpIe::Pan {
+                    pan_size,
+                    routing_cost,
+                    use_parent_bs_ie: flags & 0x01 != 0,
+                    routing_method: flags & 0x02 != 0,
+                    fan_tps_version: flags >> 5,
+                }
+            }
+            (false, WpIe::NETNAME) => WpIe::NetName(ie.content.clone()),
+            (false, WpIe::PANVER) => WpIe::PanVersion(cursor.read_u16_le()?),
+            (false, WpIe::GTKHASH) => {
+                let mut hashes = [[0x00; 8]; 4];
+                for hash in hashes.iter_mut() {
+                    cursor.read_exact(hash)?;
+                }
+                WpIe::GtkHash(hashes)
+            }
+            _ => WpIe::Other(ie.clone()),
+        };
+        Ok(wp_ie)
+    }
+
+    pub fn to_nested_ie(&self) -> NestedIe {
+        let mut content = Vec::new();
+        let (long, sub_id) = match self {
+            WpIe::UnicastSchedule(schedule) => {
+                schedule.encode_into(&mut content);
+                (true, WpIe::US)
+            }
+            WpIe::BroadcastSchedule {
+                broadcast_interval,
+                schedule_id,
+                schedule,
+            } => {
+                content.put_u32_le(*broadcast_interval);
+                content.put_u16_le(*schedule_id);
+                schedule.encode_into(&mut content);
+                (true, WpIe::BS)
+            }
+            WpIe::Pan {
+                pan_size,
+                routing_cost,
+                use_parent_bs_ie,
+                routing_method,
+                fan_tps_version,
+            } => {
+                content.put_u16_le(*pan_size);
+                content.put_u16_le(*routing_cost);
+                content.put_u8(
+                    u8::from(*use_parent_bs_ie)
+                        | u8::from(*routing_method) << 1
+                        | (fan_tps_version & 0x07) << 5,
+                );
+                (false, WpIe::PAN)
+            }
+            WpIe::NetName(name) => {
+                content.extend(name.iter());
+                (false, WpIe::NETNAME)
+            }
+            WpIe::PanVersion(version) => {
+                content.put_u16_le(*version);
+                (false, WpIe::PANVER)
+            }
+            WpIe::GtkHash(hashes) => {
+                hashes.iter().for_each(|hash| content.extend(hash.iter()));
+                (false, WpIe::GTKHASH)
+            }
+            WpIe::Other(ie) => return ie.clone(),
+        };
+        NestedIe {
+            sub_id,
+            long,
+            content,
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use crate::subsystem::mac::{areq, sreq};
+use crate::types::{Address, Status, TxOption};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+/// An indirect data request held by the coprocessor until its destination
+/// polls for it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct IndirectData {
+    pub handle: u8,
+    pub destination: Address,
+    pub data: Vec<u8>,
+}
+
+pub type ExpiryCallback = Box<dyn FnMut(Status, &IndirectData)>;
+
+/// Tracks the MSDU handles of indirect data requests until they are
+/// confirmed or purged, so the depth of each destination's queue is known.
+/// Requests dropped by the coprocessor, i.e. confirmed with
+/// `TransactionExpired` or `TransactionOverflow`, go to the expiry callback,
+/// which decides whether to send them again.
+#[derive(Default)]
+pub struct IndirectQueue {
+    queued: BTreeMap<u8, IndirectData>,
+    on_expiry: Option<ExpiryCallback>,
+}
+
+impl IndirectQueue {
+    pub fn new() -> IndirectQueue {
+        IndirectQueue::default()
+    }
+
+    pub fn on_expiry<F: FnMut(Status, &IndirectData) + 'static>(&mut self, callback: F) {
+        self.on_expiry = Some(Box::new(callback));
+    }
+
+    /// Records `request` if it is sent indirectly, returns whether it was.
+    pub fn sent(&mut self, request: &sreq::DataReq) -> bool {
+        if !request.tx_option.contains(TxOption::INDIRECT) {
+            return false;
+        }
+        self.queued.insert(
+            request.handle,
+            IndirectData {
+                handle: request.handle,
+                destination: request.dest_address,
+                data: request.data_payload.clone(),
+            },
+        );
+        true
+    }
+
+    /// Forgets the request `confirm` refers to and returns it, or `None` for
+    /// handles that were not queued indirectly.
+    pub fn confirm(&mut self, confirm: &areq::DataCnf) -> Option<IndirectData> {
+        let data = self.queued.remove(&confirm.handle)?;
+        if matches!(
+            confirm.status,
+            Status::TransactionExpired | Status::TransactionOverflow
+        ) {
+            if let Some(callback) = self.on_expiry.as_mut() {
+                callback(confirm.status, &data);
+            }
+        }
+        Some(data)
+    }
+
+    /// Forgets a request removed with a purge request.
+    pub fn purged(&mut self, confirm: &areq::PurgeCnf) -> Option<IndirectData> {
+        match confirm.status {
+            Status::Success => self.queued.remove(&confirm.handle),
+            _ => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queued.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+
+    pub fn depth(&self, destination: &Address) -> usize {
+        self.queued(destination).count()
+    }
+
+    pub fn queued<'a>(
+        &'a self,
+        destination: &'a Address,
+    ) -> impl Iterator<Item = &'a IndirectData> + 'a {
+        self.queued
+            .values()
+            .filter(move |data| data.destination == *destination)
+    }
+
+    /// Purge requests for everything queued to `destination`, e.g. once it
+    /// left the network.
+    pub fn purge_requests(&self, destination: &Address) -> Vec<sreq::PurgeReq> {
+        self.queued(destination)
+            .map(|data| sreq::PurgeReq {
+                handle: data.handle,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,151 @@
+use crate::subsystem::mac::areq::DataInd;
+use crate::subsystem::mac::sreq::DataReq;
+use crate::types::{Address, AddressMode, PanId, Security, TxOption};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct KeepAliveConfig {
+    // Silence after which a device is probed.
+    pub interval: Duration,
+    // Time a probe waits for an echo before it counts as missed.
+    pub timeout: Duration,
+    // Missed probes before the link is reported down.
+    pub max_missed: u32,
+    pub pan_id: PanId,
+    // Handle used for every probe, keep it out of the range used by the application.
+    pub handle: u8,
+    pub payload: Vec<u8>,
+}
+
+impl Default for KeepAliveConfig {
+    fn default() -> Self {
+        KeepAliveConfig {
+            interval: Duration::from_secs(300),
+            timeout: Duration::from_secs(10),
+            max_missed: 3,
+            pan_id: PanId::BROADCAST,
+            handle: 0xff,
+            payload: vec![0x00],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum KeepAliveEvent {
+    Probe(DataReq),
+    LinkDown(Address),
+    LinkUp(Address),
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct DeviceLiveness {
+    pub last_seen: Option<Instant>,
+    pub missed: u32,
+    pub link_down: bool,
+}
+
+#[derive(Debug)]
+struct Tracked {
+    liveness: DeviceLiveness,
+    next_probe: Instant,
+    probe_sent: Option<Instant>,
+}
+
+/// Probes devices that stay silent and reports the ones that stop answering.
+/// The application is expected to echo the probe payload back.
+#[derive(Debug)]
+pub struct KeepAlive {
+    config: KeepAliveConfig,
+    devices: HashMap<Address, Tracked>,
+}
+
+impl KeepAlive {
+    pub fn new(config: KeepAliveConfig) -> KeepAlive {
+        KeepAlive {
+            config,
+            devices: HashMap::new(),
+        }
+    }
+
+    pub fn track(&mut self, address: Address, now: Instant) {
+        let next_probe = now + self.config.interval;
+        self.devices.entry(address).or_insert(Tracked {
+            liveness: DeviceLiveness {
+                last_seen: None,
+                missed: 0,
+                link_down: false,
+            },
+            next_probe,
+            probe_sent: None,
+        });
+    }
+
+    pub fn untrack(&mut self, address: &Address) {
+        self.devices.remove(address);
+    }
+
+    pub fn liveness(&self, address: &Address) -> Option<DeviceLiveness> {
+        self.devices.get(address).map(|device| device.liveness)
+    }
+
+    /// Any frame from a device proves it alive, not only the echo.
+    pub fn on_data_ind(&mut self, indication: &DataInd, now: Instant) -> Option<KeepAliveEvent> {
+        let interval = self.config.interval;
+        let device = self.devices.get_mut(&indication.src_address)?;
+        device.liveness.last_seen = Some(now);
+        device.liveness.missed = 0;
+        device.probe_sent = None;
+        device.next_probe = now + interval;
+
+        if device.liveness.link_down {
+            device.liveness.link_down = false;
+            return Some(KeepAliveEvent::LinkUp(indication.src_address));
+        }
+        None
+    }
+
+    pub fn poll(&mut self, now: Instant) -> Vec<KeepAliveEvent> {
+        let mut events = Vec::new();
+
+        for (address, device) in self.devices.iter_mut() {
+            if let Some(probe_sent) = device.probe_sent {
+                if now < probe_sent + self.config.timeout {
+                    continue;
+                }
+                device.probe_sent = None;
+                device.liveness.missed += 1;
+                if device.liveness.missed >= self.config.max_missed && !device.liveness.link_down {
+                    device.liveness.link_down = true;
+                    events.push(KeepAliveEvent::LinkDown(*address));
+                }
+            }
+
+            if now >= device.next_probe {
+                device.probe_sent = Some(now);
+                device.next_probe = now + self.config.interval;
+                events.push(KeepAliveEvent::Probe(probe(&self.config, *address)));
+            }
+        }
+
+        events
+    }
+}
+
+fn probe(config: &KeepAliveConfig, dest_address: Address) -> DataReq {
+    DataReq {
+        dest_address,
+        dest_pan_id: config.pan_id,
+        src_address_mode: AddressMode::Addr16Bit,
+        handle: config.handle,
+        tx_option: TxOption::ACK,
+        channel: 0,
+        power: 0,
+        security: Security::none(),
+        include_fh_ies: 0,
+        data_length: config.payload.len() as u16,
+        ie_length: 0,
+        data_payload: config.payload.clone(),
+        ie_payload: Vec::new(),
+    }
+}
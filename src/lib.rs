@@ -1,24 +1,143 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
 #[macro_use]
 extern crate bitflags;
+extern crate alloc;
 
+#[cfg(all(feature = "std", feature = "mac"))]
+pub mod address_book;
+#[cfg(all(feature = "std", feature = "mac"))]
+pub mod admission;
+#[cfg(feature = "std")]
+pub mod audit;
+#[cfg(feature = "mac")]
+pub mod client;
+#[cfg(all(feature = "std", feature = "mac"))]
+pub mod confirm;
+#[cfg(feature = "coordinator")]
+pub mod coordinator;
+pub mod debug;
+#[cfg(all(feature = "std", feature = "mac"))]
+pub mod dedupe;
+#[cfg(feature = "device")]
+pub mod device;
+pub mod diff;
+pub mod dispatch;
+#[cfg(all(feature = "fh", feature = "wisun-ie"))]
+pub mod eapol;
 pub mod error;
+#[cfg(feature = "fan")]
+pub mod fan;
+#[cfg(all(feature = "std", feature = "mac"))]
+pub mod fragment;
 pub mod frame;
+#[cfg(all(feature = "std", feature = "security-tables"))]
+pub mod frame_counter;
+#[cfg(feature = "std")]
+pub mod history;
+#[cfg(feature = "mac")]
+pub mod ie;
+#[cfg(feature = "mac")]
+pub mod indirect;
+#[cfg(all(feature = "std", feature = "mac"))]
+pub mod keepalive;
+#[cfg(all(
+    feature = "std",
+    feature = "mac",
+    any(feature = "util", feature = "sys")
+))]
+pub mod link_probe;
+#[cfg(all(feature = "std", feature = "mac"))]
+pub mod metrics;
+#[cfg(all(feature = "std", feature = "mac"))]
+pub mod pacing;
 pub mod parser;
+#[cfg(feature = "mac")]
+pub mod pib_config;
+#[cfg(feature = "mac")]
+pub mod pib_override;
+#[cfg(feature = "std")]
+pub mod queue;
+mod reader;
+#[cfg(all(feature = "std", feature = "mac"))]
+pub mod replay;
+#[cfg(feature = "sbl")]
+pub mod sbl;
+#[cfg(all(feature = "std", feature = "mac"))]
+pub mod scheduler;
+#[cfg(feature = "security-tables")]
+pub mod security;
+#[cfg(feature = "sim")]
+pub mod sim;
+#[cfg(feature = "sixlowpan")]
+pub mod sixlowpan;
+#[cfg(feature = "mac")]
+pub mod sniffer;
+pub mod stats;
 pub mod subsystem;
+#[cfg(all(feature = "std", feature = "mac"))]
+pub mod tcp;
+#[cfg(any(
+    feature = "test-util",
+    all(
+        test,
+        feature = "std",
+        feature = "mac",
+        feature = "sys",
+        feature = "util",
+        feature = "app",
+        feature = "fh",
+        feature = "wisun-ie",
+        feature = "security-tables",
+        feature = "coordinator",
+        feature = "device",
+        feature = "fan"
+    )
+))]
+pub mod test_util;
+#[cfg(all(feature = "std", feature = "mac"))]
+pub mod tpc;
+#[cfg(feature = "std")]
+pub mod transport;
 pub mod types;
+pub mod watchdog;
+mod writer;
+
+pub use reader::{DecodeOptions, Reader};
 
-#[cfg(test)]
+// The tests cover every subsystem and need the default features.
+#[cfg(all(
+    test,
+    feature = "std",
+    feature = "mac",
+    feature = "sys",
+    feature = "util",
+    feature = "app",
+    feature = "fh",
+    feature = "wisun-ie",
+    feature = "security-tables",
+    feature = "coordinator",
+    feature = "device",
+    feature = "fan"
+))]
 mod tests {
-    use crate::{frame, subsystem, types};
-    use std::io::Cursor;
+    use crate::{
+        address_book, admission, audit, client, confirm, coordinator, debug, dedupe, device, diff,
+        dispatch, eapol, error, fan, fragment, frame, frame_counter, history, ie, indirect,
+        keepalive, link_probe, metrics, pacing, pib_config, pib_override, queue, replay, scheduler,
+        security, sniffer, stats, subsystem, tcp, test_util, tpc, transport, types, watchdog,
+    };
+    use crate::{DecodeOptions, Reader};
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
 
     #[test]
     fn decode_mt_header_1() {
         let data = [0x00, 0x01, 0x02];
-        let mut cursor = Cursor::new(&data[..]);
+        let mut cursor = Reader::new(&data[..]);
         let header = frame::MTHeader::try_decode(&mut cursor).unwrap();
         assert_eq!(header.length, 0);
-        assert_eq!(header.has_extension(), false);
+        assert!(!header.has_extension());
         assert_eq!(header.command.cmd_type, types::CommandType::POLL);
         assert_eq!(header.command.subsystem, types::MTSubsystem::SYS);
         assert_eq!(header.command.id, 2);
@@ -27,10 +146,10 @@ mod tests {
     #[test]
     fn decode_mt_header_2() {
         let data = [0xFF, 0x81, 0x0A];
-        let mut cursor = Cursor::new(&data[..]);
+        let mut cursor = Reader::new(&data[..]);
         let header = frame::MTHeader::try_decode(&mut cursor).unwrap();
         assert_eq!(header.length, 255);
-        assert_eq!(header.has_extension(), true);
+        assert!(header.has_extension());
         assert_eq!(header.command.cmd_type, types::CommandType::POLL);
         assert_eq!(header.command.subsystem, types::MTSubsystem::SYS);
         assert_eq!(header.command.id, 10);
@@ -39,10 +158,10 @@ mod tests {
     #[test]
     fn decode_sys_reset_ind() {
         let data = [0x6, 0x41, 0x80, 0x0, 0x3, 0x1, 0x2, 0x2, 0x0];
-        let mut cursor = Cursor::new(&data[..]);
+        let mut cursor = Reader::new(&data[..]);
         let frame = frame::MTFrame::try_decode(&mut cursor).unwrap();
         assert_eq!(frame.header.length, 0x06);
-        assert_eq!(frame.header.has_extension(), false);
+        assert!(!frame.header.has_extension());
         assert_eq!(frame.header.command.cmd_type, types::CommandType::AREQ);
         assert_eq!(frame.header.command.subsystem, types::MTSubsystem::SYS);
         assert_eq!(frame.header.command.id, types::SYSCommandId::ResetInd as u8);
@@ -66,10 +185,10 @@ mod tests {
     #[test]
     fn decode_mac_reset_req_srsp() {
         let data = [0x1, 0x62, 0x1, 0x0];
-        let mut cursor = Cursor::new(&data[..]);
+        let mut cursor = Reader::new(&data[..]);
         let frame = frame::MTFrame::try_decode(&mut cursor).unwrap();
         assert_eq!(frame.header.length, 0x01);
-        assert_eq!(frame.header.has_extension(), false);
+        assert!(!frame.header.has_extension());
         assert_eq!(frame.header.command.cmd_type, types::CommandType::SRSP);
         assert_eq!(frame.header.command.subsystem, types::MTSubsystem::MAC);
         assert_eq!(frame.header.command.id, types::MACCommandId::ResetReq as u8);
@@ -100,7 +219,7 @@ mod tests {
             0xbb, 0x1, 0xba, 0x1, 0xb9, 0x1, 0xb7,
         ];
 
-        let mut cursor = Cursor::new(&data[..]);
+        let mut cursor = Reader::new(&data[..]);
         let frame1 = frame::MTFrame::try_decode(&mut cursor).unwrap();
 
         // Second Fragment
@@ -119,14 +238,14 @@ mod tests {
             0x1, 0xac, 0x1, 0xad, 0x1, 0xaf, 0x1,
         ];
 
-        let mut cursor = Cursor::new(&data[..]);
+        let mut cursor = Reader::new(&data[..]);
         let frame2 = frame::MTFrame::try_decode(&mut cursor).unwrap();
 
         let data: Vec<u8> = frame1
             .payload
             .iter()
             .chain(frame2.payload.iter())
-            .map(|x| *x)
+            .copied()
             .collect();
 
         assert_eq!(data.len(), 351);
@@ -134,15 +253,18 @@ mod tests {
         if let Ok(payload) = subsystem::mac::areq::DataInd::try_decode(&data[..]) {
             assert_eq!(payload.timestamp, 1512743);
             assert_eq!(payload.timestamp2, 4);
-            assert_eq!(payload.src_pan_id, 0xfffa);
-            assert_eq!(payload.dest_pan_id, 0xfffa);
-            assert_eq!(payload.link_quality, 0xc9);
+            assert_eq!(payload.src_pan_id, types::PanId(0xfffa));
+            assert_eq!(payload.dest_pan_id, types::PanId(0xfffa));
+            assert_eq!(payload.link_quality, types::Lqi(0xc9));
             assert_eq!(payload.correlation, 0x00);
-            assert_eq!(payload.rssi, -23);
+            assert_eq!(payload.rssi, types::Rssi(-23));
             assert_eq!(payload.dsn, 0x09);
-            assert_eq!(payload.security_level, types::SecurityLevel::NoSecurity);
-            assert_eq!(payload.key_id_mode, types::KeyIdMode::NotUsed);
-            assert_eq!(payload.key_index, 0x00);
+            assert_eq!(
+                payload.security.security_level,
+                types::SecurityLevel::NoSecurity
+            );
+            assert_eq!(payload.security.key_id_mode, types::KeyIdMode::NotUsed);
+            assert_eq!(payload.security.key_index, 0x00);
             assert_eq!(payload.frame_counter, 0x00);
             assert_eq!(payload.data_length, 0x012c);
             assert_eq!(payload.ie_length, 0x0000);
@@ -151,6 +273,10 @@ mod tests {
         } else {
             panic!("Invalid payload.");
         }
+
+        for end in 0..data.len() {
+            assert!(subsystem::mac::areq::DataInd::try_decode(&data[..end]).is_err());
+        }
     }
 
     #[test]
@@ -178,6 +304,38 @@ mod tests {
         decode_encode_test(&data);
     }
 
+    #[test]
+    fn decode_mac_associate_ind_capabilities() {
+        let mut payload = [
+            0x80, 0xbd, 0xc9, 0xb, 0x0, 0x4b, 0x12, 0x0, 0x8e, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+            0x0, 0x0, 0x0, 0x0,
+        ];
+        let indication = subsystem::mac::areq::AssociateInd::try_decode(&payload).unwrap();
+        assert_eq!(
+            indication.capabilities,
+            types::CapabilityInfo::FFD
+                | types::CapabilityInfo::MAINS_POWERED
+                | types::CapabilityInfo::RX_ON_WHEN_IDLE
+                | types::CapabilityInfo::ALLOCATE_ADDRESS
+        );
+
+        payload[8] = 0x10;
+        let error = subsystem::mac::areq::AssociateInd::try_decode(&payload).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            error::Error::InvalidCapabilityInfo(0x10)
+        ));
+        assert!(matches!(
+            error,
+            error::Error::Decode {
+                command: None,
+                field: "capabilities",
+                offset: 8,
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn decode_encode_mac_associate_rsp_sreq() {
         let data = [
@@ -187,13 +345,5863 @@ mod tests {
         decode_encode_test(&data);
     }
 
-    fn decode_encode_test(data: &[u8]) {
-        let mut cursor = Cursor::new(&data[..]);
+    #[test]
+    fn decode_encode_mac_disassociate_req_sreq() {
+        let data = [
+            0x18, 0x22, 0x7, 0x3, 0x80, 0xbd, 0xc9, 0xb, 0x0, 0x4b, 0x12, 0x0, 0xbb, 0xaa, 0x1,
+            0x1, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+        ];
+        let mut cursor = Reader::new(&data[..]);
+        let frame = frame::MTFrame::try_decode(&mut cursor).unwrap();
+
+        if let Ok(payload) = subsystem::mac::sreq::DisassociateReq::try_decode(&frame.payload) {
+            assert_eq!(payload.device_pan_id, types::PanId(0xaabb));
+            assert_eq!(
+                payload.disassociate_reason,
+                types::DisassociateReason::CoorWishesDevLeave
+            );
+            assert!(payload.tx_indirect);
+        } else {
+            panic!("Invalid payload.");
+        }
+
+        decode_encode_test(&data);
+    }
+
+    #[test]
+    fn decode_encode_mac_disassociate_ind() {
+        let data = [
+            0x14, 0x42, 0x86, 0x80, 0xbd, 0xc9, 0xb, 0x0, 0x4b, 0x12, 0x0, 0x2, 0x0, 0x0, 0x0, 0x0,
+            0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+        ];
+        decode_encode_test(&data);
+    }
+
+    #[test]
+    fn decode_encode_mac_disassociate_cnf() {
+        let data = [
+            0xc, 0x42, 0x87, 0x0, 0x2, 0xb1, 0xac, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0xbb, 0xaa,
+        ];
+        let mut cursor = Reader::new(&data[..]);
+        let frame = frame::MTFrame::try_decode(&mut cursor).unwrap();
+
+        if let Ok(payload) = subsystem::mac::areq::DisassociateCnf::try_decode(&frame.payload) {
+            assert_eq!(payload.status, types::Status::Success);
+            assert_eq!(
+                payload.device_addr,
+                types::Address::Addr16Bit(types::ShortAddress { address: 0xacb1 })
+            );
+            assert_eq!(payload.device_pan_id, types::PanId(0xaabb));
+        } else {
+            panic!("Invalid payload.");
+        }
+
+        decode_encode_test(&data);
+    }
+
+    #[test]
+    fn decode_mac_orphan_ind_encode_orphan_rsp_sreq() {
+        let data = [
+            0x13, 0x42, 0x8a, 0x80, 0xbd, 0xc9, 0xb, 0x0, 0x4b, 0x12, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+            0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+        ];
+        let mut cursor = Reader::new(&data[..]);
         let frame = frame::MTFrame::try_decode(&mut cursor).unwrap();
+        let orphan = subsystem::mac::areq::OrphanInd::try_decode(&frame.payload).unwrap();
+        assert_eq!(
+            orphan.extended_address.address,
+            [0x00, 0x12, 0x4b, 0x00, 0x0b, 0xc9, 0xbd, 0x80]
+        );
+        decode_encode_test(&data);
+
+        let response = subsystem::mac::sreq::OrphanRsp {
+            extended_address: orphan.extended_address,
+            assoc_short_address: types::ShortAddress { address: 0xacb1 },
+            associated_member: true,
+            security: orphan.security,
+        };
 
         let mut buffer = Vec::new();
-        frame.encode_into(&mut buffer);
+        response.into_mt_frame().encode_into(&mut buffer);
+        assert_eq!(
+            buffer,
+            [
+                0x16, 0x22, 0x51, 0x80, 0xbd, 0xc9, 0xb, 0x0, 0x4b, 0x12, 0x0, 0xb1, 0xac, 0x1,
+                0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0
+            ]
+        );
+        decode_encode_test(&buffer);
+    }
 
-        assert_eq!(buffer, data);
+    #[test]
+    fn decode_encode_mac_beacon_notify_ind() {
+        let data = [
+            0x33, 0x42, 0x83, 0x0, 0x5, 0x10, 0x20, 0x30, 0x40, 0x2, 0xbb, 0xaa, 0x0, 0x0, 0x0,
+            0x0, 0x0, 0x0, 0xcd, 0xab, 0xff, 0xcf, 0xb, 0x0, 0x0, 0xe0, 0x0, 0x0, 0x0, 0x0, 0x0,
+            0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x1, 0x1, 0x3, 0x34, 0x12, 0x80, 0xbd, 0xc9, 0xb,
+            0x0, 0x4b, 0x12, 0x0, 0x1, 0x2, 0x3,
+        ];
+        let mut cursor = Reader::new(&data[..]);
+        let frame = frame::MTFrame::try_decode(&mut cursor).unwrap();
+
+        use subsystem::mac::areq::BeaconNotifyInd;
+        if let Ok(BeaconNotifyInd::StandardFrame(beacon)) =
+            BeaconNotifyInd::try_decode(&frame.payload)
+        {
+            assert_eq!(beacon.bsn, 0x05);
+            assert_eq!(beacon.pan_descriptor.timestamp, 0x40302010);
+            assert_eq!(
+                beacon.pan_descriptor.coord_address,
+                types::Address::Addr16Bit(types::ShortAddress { address: 0xaabb })
+            );
+            assert_eq!(beacon.pan_descriptor.pan_id, types::PanId(0xabcd));
+            assert_eq!(
+                beacon.pan_descriptor.superframe_spec,
+                types::SuperframeSpec {
+                    beacon_order: 15,
+                    superframe_order: 15,
+                    final_cap_slot: 15,
+                    battery_life_extension: false,
+                    pan_coordinator: true,
+                    association_permit: true,
+                }
+            );
+            assert_eq!(beacon.pan_descriptor.logical_channel, 0x0b);
+            assert_eq!(beacon.pan_descriptor.link_quality, 0xe0);
+            assert_eq!(
+                beacon.short_addr_list,
+                [types::ShortAddress { address: 0x1234 }]
+            );
+            assert_eq!(
+                beacon.ext_addr_list[0].address,
+                [0x00, 0x12, 0x4b, 0x00, 0x0b, 0xc9, 0xbd, 0x80]
+            );
+            assert_eq!(beacon.sdu, [0x01, 0x02, 0x03]);
+
+            let mut buffer = Vec::new();
+            BeaconNotifyInd::StandardFrame(beacon)
+                .into_mt_frame()
+                .encode_into(&mut buffer);
+            assert_eq!(buffer, data);
+        } else {
+            panic!("Invalid payload.");
+        }
+
+        let data = [
+            0xa, 0x42, 0x83, 0x1, 0x6, 0xf, 0xf, 0xf, 0xe, 0x0, 0x0, 0xff, 0x3f,
+        ];
+        let mut cursor = Reader::new(&data[..]);
+        let frame = frame::MTFrame::try_decode(&mut cursor).unwrap();
+
+        if let Ok(BeaconNotifyInd::EnhancedFrame(beacon)) =
+            BeaconNotifyInd::try_decode(&frame.payload)
+        {
+            assert_eq!(beacon.bsn, 0x06);
+            assert_eq!(beacon.coexistence.enh_beacon_order, 0x0e);
+            assert_eq!(beacon.coexistence.non_beacon_order, 0x3fff);
+            assert!(!beacon.coexistence.is_beacon_enabled());
+
+            let mut buffer = Vec::new();
+            BeaconNotifyInd::EnhancedFrame(beacon)
+                .into_mt_frame()
+                .encode_into(&mut buffer);
+            assert_eq!(buffer, data);
+        } else {
+            panic!("Invalid payload.");
+        }
+    }
+
+    #[test]
+    fn decode_encode_mac_comm_status_ind() {
+        let data = [
+            0x21, 0x42, 0x8d, 0xf3, 0x3, 0x80, 0xbd, 0xc9, 0xb, 0x0, 0x4b, 0x12, 0x0, 0x2, 0x0,
+            0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0xbb, 0xaa, 0x2, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+            0x0, 0x5, 0x1, 0x3,
+        ];
+        let mut cursor = Reader::new(&data[..]);
+        let frame = frame::MTFrame::try_decode(&mut cursor).unwrap();
+
+        if let Ok(payload) = subsystem::mac::areq::CommStatusInd::try_decode(&frame.payload) {
+            assert_eq!(payload.status, types::Status::UnavailableKey);
+            assert_eq!(
+                payload.dst_addr,
+                types::Address::Addr16Bit(types::ShortAddress { address: 0x0000 })
+            );
+            assert_eq!(payload.device_pan_id, types::PanId(0xaabb));
+            assert_eq!(payload.reason, types::CommEventReason::RxSecure);
+            assert_eq!(
+                payload.security.security_level,
+                types::SecurityLevel::AESEncryptionMIC32
+            );
+            assert_eq!(
+                payload.security.key_id_mode,
+                types::KeyIdMode::Key1ByteIndex
+            );
+            assert_eq!(payload.security.key_index, 0x03);
+        } else {
+            panic!("Invalid payload.");
+        }
+
+        decode_encode_test(&data);
+
+        let mut payload = data[3..].to_vec();
+        payload[21] = 0x7;
+        let error = subsystem::mac::areq::CommStatusInd::try_decode(&payload).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            error::Error::InvalidCommEventReason(0x07)
+        ));
+
+        // Through the dispatch the error also names the command.
+        let error = subsystem::MTFramePayload::try_decode(
+            &types::MTSubsystem::MAC,
+            &types::CommandType::AREQ,
+            types::MACCommandId::CommStatusInd as u8,
+            &payload,
+        )
+        .unwrap_err();
+        match error {
+            error::Error::Decode {
+                command: Some(command),
+                field,
+                offset,
+                kind,
+            } => {
+                assert_eq!(command.id, types::MACCommandId::CommStatusInd as u8);
+                assert_eq!(field, "reason");
+                assert_eq!(offset, 21);
+                assert!(matches!(*kind, error::Error::InvalidCommEventReason(0x07)));
+            }
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+
+    #[test]
+    fn encode_mac_src_match_requests() {
+        use subsystem::mac::sreq;
+
+        let request = sreq::SrcMatchAddEntryReq {
+            address: types::Address::Addr16Bit(types::ShortAddress { address: 0xacb1 }),
+            pan_id: types::PanId(0xaabb),
+        };
+        let mut buffer = Vec::new();
+        request.into_mt_frame().encode_into(&mut buffer);
+        assert_eq!(
+            buffer,
+            [0xb, 0x22, 0x46, 0x2, 0xb1, 0xac, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0xbb, 0xaa]
+        );
+        decode_encode_test(&buffer);
+
+        let request = sreq::SrcMatchEnableReq {
+            address_type: types::AddressMode::Addr64Bit,
+            num_entries: 5,
+        };
+        let mut buffer = Vec::new();
+        request.into_mt_frame().encode_into(&mut buffer);
+        assert_eq!(buffer, [0x2, 0x22, 0x45, 0x3, 0x5]);
+
+        decode_encode_test(&[0x1, 0x22, 0x49, 0x1]);
+        decode_encode_test(&[0x1, 0x62, 0x47, 0x0]);
+    }
+
+    #[test]
+    fn encode_mac_active2_scan_req() {
+        let request = subsystem::mac::sreq::ScanReq {
+            scan_type: types::ScanType::Active2,
+            scan_duration: 5,
+            channel_page: 9,
+            phy_id: types::PhyId::STD_US_915_PHY_1,
+            max_results: 8,
+            permit_join: types::PermitJoin::OnlyIfPermitJoinIsEnabled,
+            link_quality: 0x40,
+            rsp_filter: 50,
+            mpm_scan: types::MPMScan::Enabled,
+            mpm_type: types::MPMType::NBPAN,
+            mpm_duration: 0x0102,
+            security: types::Security::none(),
+            channels: [11, 12, 13].iter().copied().collect(),
+        };
+
+        let mut buffer = Vec::new();
+        request.into_mt_frame().encode_into(&mut buffer);
+        assert_eq!(buffer[0] as usize, buffer.len() - 3);
+        assert_eq!(
+            buffer[..16],
+            [0x28, 0x22, 0xc, 0x5, 0x5, 0x9, 0x1, 0x8, 0x1, 0x40, 0x32, 0x1, 0x2, 0x2, 0x1, 0x0]
+        );
+        decode_encode_test(&buffer);
+    }
+
+    #[test]
+    fn channel_mask_bits() {
+        let mut mask = types::ChannelMask::new();
+        mask.enable(0).enable_range(8..=10).enable(135).enable(200);
+        assert_eq!(mask.channels().collect::<Vec<_>>(), [0, 8, 9, 10, 135]);
+        assert_eq!(mask.count(), 5);
+        assert!(mask.is_enabled(9));
+        assert!(!mask.is_enabled(200));
+
+        mask.disable(9);
+        let mut buffer = Vec::new();
+        mask.encode_into(&mut buffer);
+        assert_eq!(
+            buffer,
+            [
+                0x1, 0x5, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+                0x80
+            ]
+        );
+
+        let decoded = types::ChannelMask::try_decode(&mut Reader::new(&buffer[..])).unwrap();
+        assert_eq!(decoded, mask);
+        assert_eq!(
+            types::ChannelMask::all().count(),
+            types::ChannelMask::CHANNELS
+        );
+    }
+
+    #[test]
+    fn address_book_resolves_associated_devices() {
+        let data = [
+            0x16, 0x22, 0x50, 0x80, 0xbd, 0xc9, 0xb, 0x0, 0x4b, 0x12, 0x0, 0xb1, 0xac, 0x0, 0x0,
+            0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+        ];
+        let mut cursor = Reader::new(&data[..]);
+        let frame = frame::MTFrame::try_decode(&mut cursor).unwrap();
+        let payload = subsystem::MTFramePayload::try_decode(
+            &frame.header.command.subsystem,
+            &frame.header.command.cmd_type,
+            frame.header.command.id,
+            &frame.payload,
+        )
+        .unwrap();
+
+        let extended_address = types::ExtendedAddress {
+            address: [0x00, 0x12, 0x4b, 0x00, 0x0b, 0xc9, 0xbd, 0x80],
+        };
+        let short_address = types::ShortAddress { address: 0xacb1 };
+
+        let mut book = address_book::AddressBook::new();
+        assert_eq!(
+            book.resolve(&extended_address),
+            types::Address::Addr64Bit(extended_address)
+        );
+
+        book.observe(&payload);
+        assert_eq!(
+            book.resolve(&extended_address),
+            types::Address::Addr16Bit(short_address)
+        );
+        assert_eq!(
+            book.extended_address(&short_address),
+            Some(extended_address)
+        );
+
+        book.remove(&extended_address);
+        assert!(book.is_empty());
+        assert_eq!(book.extended_address(&short_address), None);
+    }
+
+    #[test]
+    fn latency_histogram_percentiles() {
+        let mut histogram = metrics::LatencyHistogram::new();
+        assert!(histogram.value_at_percentile(50.0).is_none());
+
+        for micros in 1..=1000 {
+            histogram.record(Duration::from_micros(micros));
+        }
+
+        assert_eq!(histogram.count(), 1000);
+        assert_eq!(histogram.min(), Some(Duration::from_micros(1)));
+        assert_eq!(histogram.max(), Some(Duration::from_micros(1000)));
+        assert_eq!(histogram.mean(), Some(Duration::from_micros(500)));
+
+        let median = histogram.value_at_percentile(50.0).unwrap().as_micros();
+        assert!((500..530).contains(&median), "median was {}", median);
+        assert_eq!(
+            histogram.value_at_percentile(100.0),
+            Some(Duration::from_micros(1000))
+        );
+    }
+
+    #[test]
+    fn metrics_record_response_latency() {
+        let request = subsystem::mac::sreq::ResetReq { set_default: true }.into_mt_frame();
+        let data = [0x1, 0x62, 0x1, 0x0];
+        let response = frame::MTFrame::try_decode(&mut Reader::new(&data[..])).unwrap();
+
+        let sent_at = Instant::now();
+        let mut metrics = metrics::Metrics::new();
+        metrics.frame_sent(&request, sent_at);
+        metrics.frame_received(&response, sent_at + Duration::from_millis(3));
+
+        let histogram = metrics
+            .response_latency(types::MTSubsystem::MAC, types::MACCommandId::ResetReq as u8)
+            .unwrap();
+        assert_eq!(histogram.count(), 1);
+        assert_eq!(histogram.max(), Some(Duration::from_millis(3)));
+        assert_eq!(metrics.data_confirm_latency().count(), 0);
+    }
+
+    #[test]
+    fn watchdog_escalates_recovery_actions() {
+        struct Board {
+            hard_resets: u32,
+            reflashes: u32,
+        }
+
+        impl watchdog::BoardControl for Board {
+            type Error = ();
+
+            fn hard_reset(&mut self) -> Result<(), ()> {
+                self.hard_resets += 1;
+                Ok(())
+            }
+
+            fn reflash(&mut self) -> Result<(), ()> {
+                self.reflashes += 1;
+                Ok(())
+            }
+        }
+
+        use watchdog::RecoveryAction::*;
+        let mut board = Board {
+            hard_resets: 0,
+            reflashes: 0,
+        };
+        let mut watchdog = watchdog::Watchdog::new(watchdog::WatchdogConfig {
+            retries: 2,
+            soft_resets: 1,
+            hard_resets: 1,
+        });
+
+        let steps: Vec<_> = (0..6)
+            .map(|_| watchdog.recover(&mut board).unwrap())
+            .collect();
+        let actions: Vec<_> = steps.iter().map(|step| step.action).collect();
+        assert_eq!(
+            actions,
+            [Retry, Retry, SoftReset, HardReset, Reflash, Retry]
+        );
+        let escalations: Vec<_> = steps.iter().map(|step| step.escalated).collect();
+        assert_eq!(escalations, [false, false, true, true, true, false]);
+        assert_eq!(board.hard_resets, 1);
+        assert_eq!(board.reflashes, 1);
+
+        watchdog.report_success();
+        assert_eq!(watchdog.report_failure().action, Retry);
+    }
+
+    #[test]
+    fn diff_decoded_frames() {
+        let decode = |data: &[u8]| {
+            let frame = frame::MTFrame::try_decode(&mut Reader::new(data)).unwrap();
+            subsystem::MTFramePayload::try_decode(
+                &frame.header.command.subsystem,
+                &frame.header.command.cmd_type,
+                frame.header.command.id,
+                &frame.payload,
+            )
+            .unwrap()
+        };
+
+        let a = decode(&[
+            0x10, 0x42, 0x84, 0x0, 0x1, 0x27, 0x15, 0x17, 0x0, 0x4, 0x0, 0x0, 0xc9, 0x0, 0xe9, 0x0,
+            0x0, 0x0, 0x0,
+        ]);
+        let b = decode(&[
+            0x10, 0x42, 0x84, 0xe9, 0x1, 0x27, 0x15, 0x17, 0x0, 0x4, 0x0, 0x2, 0xc9, 0x0, 0xe9,
+            0x0, 0x0, 0x0, 0x0,
+        ]);
+
+        let changes = diff::diff_frames(&a, &b).unwrap();
+        assert_eq!(
+            changes.fields,
+            [
+                diff::FieldChange {
+                    path: "retries".to_string(),
+                    left: Some(vec![0x00]),
+                    right: Some(vec![0x02]),
+                },
+                diff::FieldChange {
+                    path: "status".to_string(),
+                    left: Some(vec![0x00]),
+                    right: Some(vec![0xe9]),
+                },
+            ]
+        );
+        assert_eq!(changes.byte_ranges, [0..1, 8..9]);
+        assert!(diff::diff_frames(&a, &a).unwrap().is_empty());
+
+        let c = decode(&[0x1, 0x62, 0x1, 0x0]);
+        assert!(diff::diff_frames(&a, &c).is_none());
+
+        // Fields read more than once, like list elements, carry their index.
+        use subsystem::mac::areq::{BeaconNotifyInd, StandardBeaconFrame};
+        let beacon = |last: u16| {
+            subsystem::MTFramePayload::MAC_BeaconNotifyInd_AREQ(BeaconNotifyInd::StandardFrame(
+                StandardBeaconFrame {
+                    bsn: 0,
+                    pan_descriptor: types::PanDescriptor {
+                        timestamp: 0,
+                        coord_address: types::Address::Addr16Bit(types::ShortAddress::from(0)),
+                        pan_id: types::PanId(0x1234),
+                        superframe_spec: types::SuperframeSpec::from_u16(0xc0ff),
+                        logical_channel: 3,
+                        channel_page: 9,
+                        gts_permit: false,
+                        link_quality: 0xff,
+                        security_failure: false,
+                        security: types::Security::none(),
+                    },
+                    short_addr_list: vec![
+                        types::ShortAddress::from(0x0001),
+                        types::ShortAddress::from(last),
+                    ],
+                    ext_addr_list: Vec::new(),
+                    sdu: Vec::new(),
+                },
+            ))
+        };
+        let changes = diff::diff_frames(&beacon(0x0002), &beacon(0x0003)).unwrap();
+        assert_eq!(
+            changes.fields,
+            [diff::FieldChange {
+                path: "short_addr_list.1".to_string(),
+                left: Some(vec![0x02, 0x00]),
+                right: Some(vec![0x03, 0x00]),
+            }]
+        );
+    }
+
+    struct MockClient {
+        pib: HashMap<u8, [u8; 16]>,
+        failing_attribute: Option<types::MACPIBAttributeId>,
+    }
+
+    impl client::Client for MockClient {
+        fn request(&mut self, frame: frame::MTFrame) -> Result<frame::MTFrame, error::Error> {
+            use subsystem::mac::{sreq, srsp};
+            let id = frame.header.command.id;
+            if id == types::MACCommandId::GetReq as u8 {
+                let request = sreq::GetReq::try_decode(&frame.payload)?;
+                let data = self.pib[&(request.attribute_id.raw())];
+                let status = types::Status::Success;
+                Ok(srsp::GetReq { status, data }.into_mt_frame())
+            } else if id == types::MACCommandId::SetReq as u8 {
+                let request = sreq::SetReq::try_decode(&frame.payload)?;
+                let status = if self.failing_attribute == Some(request.attribute_id) {
+                    types::Status::UnsupportedAttribute
+                } else {
+                    self.pib
+                        .insert(request.attribute_id.raw(), request.attribute_value);
+                    types::Status::Success
+                };
+                Ok(srsp::SetReq { status }.into_mt_frame())
+            } else {
+                Err(error::Error::NotImplemented)
+            }
+        }
+    }
+
+    #[test]
+    fn pib_override_restores_previous_values() {
+        use types::MACPIBAttributeId::{PhyTransmitPowerSigned, PromiscuousMode};
+        let mut pib = HashMap::new();
+        pib.insert(PromiscuousMode.raw(), [0x00; 16]);
+        pib.insert(PhyTransmitPowerSigned.raw(), [0x05; 16]);
+        let mut client = MockClient {
+            pib,
+            failing_attribute: None,
+        };
+
+        {
+            let mut guard = pib_override::PibOverride::new(
+                &mut client,
+                &[
+                    (PromiscuousMode, [0x01; 16]),
+                    (PhyTransmitPowerSigned, [0x0e; 16]),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                client::get_mac_pib(guard.client(), PromiscuousMode).unwrap(),
+                [0x01; 16]
+            );
+            assert_eq!(
+                client::get_mac_pib(guard.client(), PhyTransmitPowerSigned).unwrap(),
+                [0x0e; 16]
+            );
+        }
+
+        assert_eq!(client.pib[&(PromiscuousMode.raw())], [0x00; 16]);
+        assert_eq!(client.pib[&(PhyTransmitPowerSigned.raw())], [0x05; 16]);
+
+        client.failing_attribute = Some(PhyTransmitPowerSigned);
+        let result = pib_override::PibOverride::new(
+            &mut client,
+            &[
+                (PromiscuousMode, [0x01; 16]),
+                (PhyTransmitPowerSigned, [0x0e; 16]),
+            ],
+        );
+        assert!(matches!(
+            result,
+            Err(error::Error::CommandFailed(
+                types::Status::UnsupportedAttribute
+            ))
+        ));
+        drop(result);
+        assert_eq!(client.pib[&(PromiscuousMode.raw())], [0x00; 16]);
+    }
+
+    #[test]
+    fn admission_control_defers_and_rejects_joins() {
+        let data = [
+            0x80, 0xbd, 0xc9, 0xb, 0x0, 0x4b, 0x12, 0x0, 0x80, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+            0x0, 0x0, 0x0, 0x0,
+        ];
+        let indication = || subsystem::mac::areq::AssociateInd::try_decode(&data).unwrap();
+
+        let mut control = admission::AdmissionControl::new(admission::AdmissionConfig {
+            burst: 1,
+            refill_interval: Duration::from_millis(100),
+            max_deferred: 1,
+        });
+        let now = Instant::now();
+
+        use admission::Admission;
+        assert!(matches!(
+            control.on_associate_ind(indication(), now),
+            Admission::Admitted(_)
+        ));
+        assert!(matches!(
+            control.on_associate_ind(indication(), now),
+            Admission::Deferred
+        ));
+        if let Admission::Rejected(response) = control.on_associate_ind(indication(), now) {
+            assert_eq!(
+                response.assoc_status,
+                types::AssociationStatus::PANAtCapacity
+            );
+            assert_eq!(response.extended_address, indication().extended_address);
+        } else {
+            panic!("Join was not rejected.");
+        }
+
+        assert!(control.poll(now + Duration::from_millis(50)).is_none());
+        assert!(control.poll(now + Duration::from_millis(100)).is_some());
+        assert_eq!(control.deferred(), 0);
+        assert_eq!(
+            control.stats(),
+            admission::AdmissionStats {
+                admitted: 1,
+                deferred: 1,
+                rejected: 1,
+                released: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn pacer_spaces_data_requests() {
+        let timing = pacing::PhyTiming::for_phy(types::PhyId::STD_US_915_PHY_1).unwrap();
+        assert_eq!(timing.inter_frame_spacing, Duration::from_micros(800));
+        assert_eq!(timing.air_time(75), Duration::from_millis(16));
+
+        let now = Instant::now();
+        let mut pacer = pacing::Pacer::new(timing);
+        assert!(pacer.is_ready(now));
+
+        pacer.on_transmit(75, now);
+        assert_eq!(pacer.delay(now), Duration::from_micros(16_800));
+        pacer.on_transmit(75, now);
+        assert_eq!(pacer.delay(now), Duration::from_micros(33_600));
+        assert!(pacer.is_ready(now + Duration::from_micros(33_600)));
+    }
+
+    #[test]
+    fn transmit_power_follows_feedback() {
+        use subsystem::mac::{areq, sreq};
+        use types::{Address, ShortAddress, Status};
+        let destination = Address::Addr16Bit(ShortAddress { address: 0x0001 });
+        let mut request = sreq::DataReq {
+            dest_address: destination,
+            dest_pan_id: types::PanId(0xabcd),
+            src_address_mode: types::AddressMode::Addr16Bit,
+            handle: 7,
+            tx_option: types::TxOption::ACK,
+            channel: 0,
+            power: 0,
+            security: types::Security::none(),
+            include_fh_ies: 0,
+            data_length: 0,
+            ie_length: 0,
+            data_payload: Vec::new(),
+            ie_payload: Vec::new(),
+        };
+        let confirm = |status, rssi: i8| areq::DataCnf {
+            status,
+            handle: 7,
+            timestamp: 0,
+            timestamp2: 0,
+            retries: 0,
+            link_quality: types::Lqi(0),
+            correlation: 0,
+            rssi: types::Rssi(rssi),
+            frame_counter: 0,
+        };
+
+        let mut tpc = tpc::TransmitPowerControl::new(tpc::TpcConfig {
+            min_power: 0,
+            max_power: 10,
+            initial_power: 10,
+            target_rssi: -80,
+            hysteresis: 5,
+            step: 4,
+        });
+
+        let report = tpc.apply(&mut request);
+        assert!(request.tx_option.contains(types::TxOption::PWR_CHAN));
+        assert_eq!(request.power, 10);
+        assert_eq!(report.power, 10);
+
+        // A strong link lowers the power down to the minimum.
+        let report = tpc.on_data_cnf(&confirm(Status::Success, -40)).unwrap();
+        assert_eq!(report.status, Some(Status::Success));
+        assert_eq!(tpc.power(&destination), 6);
+        tpc.apply(&mut request);
+        tpc.on_data_cnf(&confirm(Status::Success, -40));
+        tpc.apply(&mut request);
+        tpc.on_data_cnf(&confirm(Status::Success, -40));
+        assert_eq!(tpc.power(&destination), 0);
+
+        // Within the hysteresis nothing changes, a missing ack raises it again.
+        tpc.apply(&mut request);
+        tpc.on_data_cnf(&confirm(Status::Success, -84));
+        assert_eq!(tpc.power(&destination), 0);
+        tpc.apply(&mut request);
+        tpc.on_data_cnf(&confirm(Status::NoAck, 0));
+        assert_eq!(tpc.power(&destination), 4);
+        assert!(tpc.on_data_cnf(&confirm(Status::Success, -40)).is_none());
+    }
+
+    #[test]
+    fn security_tables_export_import() {
+        struct XorCipher(u8);
+
+        impl security::BundleCipher for XorCipher {
+            fn encrypt(&mut self, plaintext: &[u8]) -> Vec<u8> {
+                plaintext.iter().map(|x| x ^ self.0).collect()
+            }
+
+            fn decrypt(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, error::Error> {
+                Ok(self.encrypt(ciphertext))
+            }
+        }
+
+        use subsystem::mac::sreq;
+        use subsystem::MTFramePayload::*;
+        let mut manager = security::SecurityManager::new();
+        manager.observe(&MAC_WriteKeyReq_SREQ(sreq::WriteKeyReq {
+            new: true,
+            index: 0,
+            key: [0xab; 16],
+            frame_counter: 10,
+            data_size: 1,
+            lookup_data: [0x01; 9],
+        }));
+        manager.observe(&MAC_AddDeviceReq_SREQ(sreq::AddDeviceReq {
+            pan_id: types::PanId(0xaabb),
+            short_addr: types::ShortAddress { address: 0xacb1 },
+            ext_addr: types::ExtendedAddress {
+                address: [0x00, 0x12, 0x4b, 0x00, 0x0b, 0xc9, 0xbd, 0x80],
+            },
+            frame_counter: 7,
+            exempt: false,
+            unique: false,
+            duplicate: false,
+            data_size: 1,
+            lookup_data: [0x02; 9],
+        }));
+        manager.set_frame_counter(1000);
+        manager.set_key_frame_counter(0, 20);
+
+        let bundle = manager.export(&mut XorCipher(0x5a)).unwrap();
+        assert!(!bundle.starts_with(b"TI154SEC"));
+        assert!(matches!(
+            security::SecurityManager::import(&bundle, &mut XorCipher(0x00)),
+            Err(error::Error::InvalidSecurityBundle)
+        ));
+
+        let imported = security::SecurityManager::import(&bundle, &mut XorCipher(0x5a)).unwrap();
+        assert_eq!(imported.frame_counter(), Some(1000));
+        assert_eq!(imported.keys().next().unwrap().frame_counter, 20);
+        assert_eq!(imported.devices().count(), 1);
+
+        let encode = |manager: &security::SecurityManager| {
+            let mut buffer = Vec::new();
+            for frame in manager.restore_frames() {
+                frame.encode_into(&mut buffer);
+            }
+            buffer
+        };
+        assert_eq!(encode(&imported), encode(&manager));
+        assert_eq!(manager.restore_frames().len(), 3);
+
+        // Record lengths are u16s in the bundle.
+        manager.observe(&MAC_SecuritySetReq_SREQ(sreq::SecuritySetReq {
+            attribute_id: types::SecurityPIBAttributeId::SecurityLevelEntry,
+            index1: 0,
+            index2: 0,
+            attribute_value: vec![0x00; 0x10000],
+        }));
+        assert!(matches!(
+            manager.export(&mut XorCipher(0x5a)),
+            Err(error::Error::PayloadTooLarge(0x10005))
+        ));
+    }
+
+    #[test]
+    fn audit_reports_lossy_decoding() {
+        use types::{CommandType, MTSubsystem};
+        let id = types::MACCommandId::PollInd as u8;
+
+        let data = [
+            0x2, 0xb1, 0xac, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0xfa, 0xff, 0x0,
+        ];
+        let payload =
+            subsystem::MTFramePayload::try_decode(&MTSubsystem::MAC, &CommandType::AREQ, id, &data)
+                .unwrap();
+        assert!(audit::check(&MTSubsystem::MAC, &CommandType::AREQ, id, &data, &payload).is_none());
+
+        // Padding after a short address is not kept by the decoder.
+        let data = [
+            0x2, 0xb1, 0xac, 0x0, 0x0, 0x77, 0x0, 0x0, 0x0, 0xfa, 0xff, 0x0,
+        ];
+        let payload =
+            subsystem::MTFramePayload::try_decode(&MTSubsystem::MAC, &CommandType::AREQ, id, &data)
+                .unwrap();
+        let mismatch =
+            audit::check(&MTSubsystem::MAC, &CommandType::AREQ, id, &data, &payload).unwrap();
+        assert_eq!(mismatch.byte_ranges.first(), Some(&(5..6)));
+        assert_eq!(
+            mismatch.fields,
+            [diff::FieldChange {
+                path: "dev_addr".to_string(),
+                left: Some(data[..9].to_vec()),
+                right: Some(mismatch.reencoded[..9].to_vec()),
+            }]
+        );
+    }
+
+    #[test]
+    fn keepalive_reports_silent_devices() {
+        use keepalive::KeepAliveEvent;
+        use types::{Address, ShortAddress};
+        let device = Address::Addr16Bit(ShortAddress { address: 0x0001 });
+        let indication = subsystem::mac::areq::DataInd {
+            src_address: device,
+            dest_address: Address::Addr16Bit(ShortAddress { address: 0x0000 }),
+            timestamp: 0,
+            timestamp2: 0,
+            src_pan_id: types::PanId(0xabcd),
+            dest_pan_id: types::PanId(0xabcd),
+            link_quality: types::Lqi(0),
+            correlation: 0,
+            rssi: types::Rssi(-60),
+            dsn: 0,
+            security: types::Security::none(),
+            frame_counter: 0,
+            data_length: 1,
+            ie_length: 0,
+            data_payload: vec![0x00],
+            ie_payload: Vec::new(),
+        };
+
+        let mut keepalive = keepalive::KeepAlive::new(keepalive::KeepAliveConfig {
+            interval: Duration::from_secs(60),
+            timeout: Duration::from_secs(5),
+            max_missed: 2,
+            pan_id: types::PanId(0xabcd),
+            handle: 0xf0,
+            payload: vec![0x00],
+        });
+        let now = Instant::now();
+        keepalive.track(device, now);
+        assert!(keepalive.poll(now).is_empty());
+
+        let at = |seconds| now + Duration::from_secs(seconds);
+        match keepalive.poll(at(60)).as_slice() {
+            [KeepAliveEvent::Probe(request)] => {
+                assert_eq!(request.dest_address, device);
+                assert_eq!(request.handle, 0xf0);
+            }
+            events => panic!("Unexpected events {:?}", events),
+        }
+        assert!(keepalive.poll(at(64)).is_empty());
+        assert_eq!(keepalive.liveness(&device).unwrap().missed, 0);
+
+        // The first probe times out, the second one only after the next interval.
+        assert!(keepalive.poll(at(65)).is_empty());
+        assert_eq!(keepalive.liveness(&device).unwrap().missed, 1);
+        assert!(matches!(
+            keepalive.poll(at(120)).as_slice(),
+            [KeepAliveEvent::Probe(_)]
+        ));
+        match keepalive.poll(at(125)).as_slice() {
+            [KeepAliveEvent::LinkDown(address)] => assert_eq!(*address, device),
+            events => panic!("Unexpected events {:?}", events),
+        }
+
+        assert!(matches!(
+            keepalive.on_data_ind(&indication, at(130)),
+            Some(KeepAliveEvent::LinkUp(_))
+        ));
+        let liveness = keepalive.liveness(&device).unwrap();
+        assert_eq!(liveness.last_seen, Some(at(130)));
+        assert_eq!(liveness.missed, 0);
+        assert!(!liveness.link_down);
+        assert!(keepalive.poll(at(180)).is_empty());
+    }
+
+    #[test]
+    fn address_conversions() {
+        use types::{Address, ExtendedAddress, ShortAddress};
+        let short = ShortAddress::from(0xabcd);
+        assert_eq!(short.u16(), 0xabcd);
+        assert_eq!(u16::from(short), 0xabcd);
+        assert_eq!(short.to_string(), "ab:cd");
+        assert_eq!(format!("{:x}", short), "abcd");
+
+        let extended = ExtendedAddress::from(0x0012_4b00_0bc9_bd80);
+        assert_eq!(
+            extended.address,
+            [0x00, 0x12, 0x4b, 0x00, 0x0b, 0xc9, 0xbd, 0x80]
+        );
+        assert_eq!(extended.u64(), 0x0012_4b00_0bc9_bd80);
+        assert_eq!(extended.to_string(), "00:12:4b:00:0b:c9:bd:80");
+        assert_eq!(format!("{:016x}", extended), "00124b000bc9bd80");
+
+        let mut buffer = Vec::new();
+        extended.encode_into(&mut buffer);
+        assert_eq!(buffer, [0x80, 0xbd, 0xc9, 0x0b, 0x00, 0x4b, 0x12, 0x00]);
+
+        assert_eq!(Address::from(short), Address::Addr16Bit(short));
+        assert_eq!(Address::from(extended).to_string(), extended.to_string());
+    }
+
+    #[test]
+    fn frame_history_keeps_last_frames() {
+        let data = [0x0, 0x41, 0x0];
+        let mut cursor = Reader::new(&data[..]);
+        let frame = frame::MTFrame::try_decode(&mut cursor).unwrap();
+
+        let now = Instant::now();
+        let mut history = history::FrameHistory::new(2);
+        history.record(history::Direction::Rx, &[0xaa], now);
+        history.frame_sent(&frame, now + Duration::from_millis(5));
+        history.record(
+            history::Direction::Rx,
+            &[0xfe, 0x0, 0x61, 0x0, 0x61],
+            now + Duration::from_millis(1_250),
+        );
+        assert_eq!(history.len(), 2);
+
+        let mut output = Vec::new();
+        history.dump(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "    0.000000 TX fe 00 41 00 41\n    1.245000 RX fe 00 61 00 61\n"
+        );
+    }
+
+    #[test]
+    fn extended_address_from_str() {
+        use types::ExtendedAddress;
+        let expected = ExtendedAddress {
+            address: [0x00, 0x12, 0x4b, 0x00, 0x0b, 0xc9, 0xbd, 0x80],
+        };
+        for text in &[
+            "00:12:4B:00:0B:C9:BD:80",
+            "00:12:4b:00:0b:c9:bd:80",
+            "00124B000BC9BD80",
+            "0x00124b000bc9bd80",
+        ] {
+            assert_eq!(text.parse::<ExtendedAddress>().unwrap(), expected);
+        }
+        assert_eq!(
+            expected.to_string().parse::<ExtendedAddress>().unwrap(),
+            expected
+        );
+
+        for text in &[
+            "",
+            "00:12:4b:00:0b:c9:bd",
+            "0:12:4b:00:0b:c9:bd:80:0",
+            "00124b000bc9bd8",
+            "00124b000bc9bdzz",
+            "+0124b000bc9bd80",
+        ] {
+            assert!(matches!(
+                text.parse::<ExtendedAddress>(),
+                Err(error::Error::InvalidExtendedAddress(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn decode_payload_from_frame() {
+        use subsystem::MTFramePayload;
+        let frame = watchdog::soft_reset_frame();
+        match MTFramePayload::try_from_frame(&frame) {
+            Ok(MTFramePayload::SYS_ResetReq_AREQ(request)) => {
+                assert_eq!(request.reset_type, types::ResetType::Soft)
+            }
+            payload => panic!("Unexpected payload {:?}", payload),
+        }
+
+        let data = [0x0, 0x41, 0xff];
+        let mut cursor = Reader::new(&data[..]);
+        let frame = frame::MTFrame::try_decode(&mut cursor).unwrap();
+        assert!(matches!(
+            MTFramePayload::try_from_frame(&frame),
+            Err(error::Error::InvalidCommandId(0xff))
+        ));
+    }
+
+    #[test]
+    fn decode_borrowed_mac_data_ind() {
+        use subsystem::mac::areq::{DataInd, DataIndRef};
+        let data = [
+            0x2, 0xb1, 0xac, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x2, 0xbb, 0xaa, 0x0, 0x0, 0x0, 0x0,
+            0x0, 0x0, 0x27, 0x15, 0x17, 0x0, 0x4, 0x0, 0xfa, 0xff, 0xfa, 0xff, 0xc9, 0x0, 0xe9,
+            0x9, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x3,
+            0x0, 0x2, 0x0, 0xa, 0xb, 0xc, 0xd, 0xe,
+        ];
+
+        let indication = DataIndRef::try_decode(&data).unwrap();
+        assert_eq!(indication.data_payload, [0xa, 0xb, 0xc]);
+        assert_eq!(indication.ie_payload, [0xd, 0xe]);
+        assert!(std::ptr::eq(indication.data_payload, &data[51..54]));
+        assert!(DataIndRef::try_decode(&data[..55]).is_err());
+
+        let owned = DataInd::try_decode(&data).unwrap();
+        assert_eq!(owned.to_ref(), indication);
+        assert_eq!(indication.into_owned().encode(), data);
+    }
+
+    #[test]
+    fn decode_encode_mac_data_ind_without_source() {
+        use types::{Address, ShortAddress};
+        let indication = subsystem::mac::areq::DataInd {
+            src_address: Address::None,
+            dest_address: Address::Addr16Bit(ShortAddress { address: 0x0000 }),
+            timestamp: 0x01020304,
+            timestamp2: 0x0506,
+            src_pan_id: types::PanId(0xabcd),
+            dest_pan_id: types::PanId(0xabcd),
+            link_quality: types::Lqi(0x80),
+            correlation: 0,
+            rssi: types::Rssi(-60),
+            dsn: 0x11,
+            security: types::Security::none(),
+            frame_counter: 0,
+            data_length: 2,
+            ie_length: 0,
+            data_payload: vec![0x01, 0x02],
+            ie_payload: Vec::new(),
+        };
+        let data = indication.encode();
+        assert_eq!(data[..9], [0x00; 9]);
+
+        let decoded = subsystem::mac::areq::DataInd::try_decode(&data).unwrap();
+        assert_eq!(decoded.src_address, Address::None);
+        assert_eq!(decoded.dest_address, indication.dest_address);
+        assert_eq!(decoded.data_payload, [0x01, 0x02]);
+        assert_eq!(decoded.encode(), data);
+        assert_eq!(Address::None.to_string(), "none");
+    }
+
+    #[test]
+    fn cursor_reads_within_bounds() {
+        let data = [0x01, 0x02, 0x03, 0x04, 0x05];
+        let mut cursor = Reader::new(&data[..]);
+        assert_eq!(cursor.read_u16_le().unwrap(), 0x0201);
+        assert_eq!(cursor.position(), 2);
+
+        let mut bytes = [0x00; 4];
+        assert!(matches!(
+            cursor.read_exact(&mut bytes),
+            Err(error::Error::NotEnoughBytes)
+        ));
+        assert_eq!(cursor.position(), 2);
+
+        let mut rest = Vec::new();
+        assert_eq!(cursor.read_to_end(&mut rest).unwrap(), 3);
+        assert_eq!(rest, [0x03, 0x04, 0x05]);
+        assert_eq!(cursor.remaining(), 0);
+    }
+
+    #[test]
+    fn security_none_encoding() {
+        let mut buffer = Vec::new();
+        types::Security::none().encode_into(&mut buffer);
+        assert_eq!(buffer, [0x00; 11]);
+
+        let data = [0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x8, 0x5, 0x1, 0x3];
+        let mut cursor = Reader::new(&data[..]);
+        let security = types::Security::try_decode(&mut cursor).unwrap();
+        assert_eq!(
+            security.key_source.key,
+            [0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x8]
+        );
+        assert_eq!(
+            security.security_level,
+            types::SecurityLevel::AESEncryptionMIC32
+        );
+        assert_eq!(security.key_id_mode, types::KeyIdMode::Key1ByteIndex);
+        assert_eq!(security.key_index, 0x03);
+
+        let mut buffer = Vec::new();
+        security.encode_into(&mut buffer);
+        assert_eq!(buffer, data);
+    }
+
+    #[test]
+    fn superframe_spec_fields() {
+        let spec = types::SuperframeSpec::from_u16(0x9a37);
+        assert_eq!(spec.beacon_order, 7);
+        assert_eq!(spec.superframe_order, 3);
+        assert_eq!(spec.final_cap_slot, 10);
+        assert!(spec.battery_life_extension);
+        assert!(!spec.pan_coordinator);
+        assert!(spec.association_permit);
+        assert!(spec.is_beacon_enabled());
+        assert_eq!(spec.to_u16(), 0x9a37);
+    }
+
+    #[test]
+    fn decode_truncated_frames() {
+        let frames: [&[u8]; 8] = [
+            // SYS_RESET_IND
+            &[0x6, 0x41, 0x80, 0x0, 0x3, 0x1, 0x2, 0x2, 0x0],
+            // MAC_RESET_REQ SRSP
+            &[0x1, 0x62, 0x1, 0x0],
+            // MAC_START_REQ
+            &[
+                0x2a, 0x22, 0x3, 0x0, 0x0, 0x0, 0x0, 0xff, 0xff, 0x0, 0x9, 0x1, 0xf, 0xf, 0x1, 0x0,
+                0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+                0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x1, 0xf, 0xa, 0xff, 0x3f, 0x0,
+            ],
+            // MAC_ASSOCIATE_IND
+            &[
+                0x14, 0x42, 0x81, 0x80, 0xbd, 0xc9, 0xb, 0x0, 0x4b, 0x12, 0x0, 0x80, 0x0, 0x0, 0x0,
+                0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+            ],
+            // MAC_ASSOCIATE_RSP
+            &[
+                0x16, 0x22, 0x50, 0x80, 0xbd, 0xc9, 0xb, 0x0, 0x4b, 0x12, 0x0, 0xb1, 0xac, 0x0,
+                0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+            ],
+            // MAC_DATA_CNF
+            &[
+                0x10, 0x42, 0x84, 0x0, 0x1, 0x27, 0x15, 0x17, 0x0, 0x4, 0x0, 0x0, 0xc9, 0x0, 0xe9,
+                0x0, 0x0, 0x0, 0x0,
+            ],
+            // MAC_POLL_IND
+            &[
+                0xc, 0x42, 0x91, 0x2, 0xb1, 0xac, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0xfa, 0xff, 0x0,
+            ],
+            // UTIL_GET_EXT_ADDR SRSP
+            &[
+                0x9, 0x67, 0xee, 0x0, 0x80, 0xbd, 0xc9, 0xb, 0x0, 0x4b, 0x12, 0x0,
+            ],
+        ];
+
+        for data in frames.iter() {
+            decode_encode_test(data);
+            truncation_test(data);
+        }
+    }
+
+    fn truncation_test(data: &[u8]) {
+        for end in 0..data.len() {
+            let mut cursor = Reader::new(&data[..end]);
+            let result = frame::MTFrame::try_decode(&mut cursor).and_then(|frame| {
+                subsystem::MTFramePayload::try_decode(
+                    &frame.header.command.subsystem,
+                    &frame.header.command.cmd_type,
+                    frame.header.command.id,
+                    &frame.payload,
+                )
+            });
+            assert!(
+                result.is_err(),
+                "{:x?} decoded after truncation",
+                &data[..end]
+            );
+        }
+    }
+
+    fn decode_encode_test(data: &[u8]) {
+        let mut cursor = Reader::new(data);
+        let frame = frame::MTFrame::try_decode(&mut cursor).unwrap();
+
+        let mut buffer = Vec::new();
+        frame.encode_into(&mut buffer);
+
+        assert_eq!(buffer, data);
+
+        let payload = subsystem::MTFramePayload::try_from_frame(&frame).unwrap();
+        let mut buffer = Vec::new();
+        payload.into_mt_frame().encode_into(&mut buffer);
+
+        assert_eq!(buffer, data);
+    }
+
+    #[test]
+    fn round_trip_arbitrary_payloads() {
+        use test_util::Arbitrary;
+
+        let mut rng = test_util::Rng::new(0x154);
+        for _ in 0..5000 {
+            test_util::assert_round_trip(subsystem::MTFramePayload::arbitrary(&mut rng));
+        }
+    }
+
+    #[test]
+    fn decode_invalid_enum_values() {
+        let data = [0x07];
+        assert!(matches!(
+            types::PermitJoin::try_decode(&mut Reader::new(&data[..])),
+            Err(error::Error::InvalidPermitJoin(0x07))
+        ));
+        assert!(matches!(
+            types::MPMScan::try_decode(&mut Reader::new(&data[..])),
+            Err(error::Error::InvalidMPMScan(0x07))
+        ));
+        assert!(matches!(
+            types::MPMType::try_decode(&mut Reader::new(&data[..])),
+            Err(error::Error::InvalidMPMType(0x07))
+        ));
+        assert!(matches!(
+            types::WiSUNAsyncOperation::try_decode(&mut Reader::new(&data[..])),
+            Err(error::Error::InvalidWiSUNAsyncOperation(0x07))
+        ));
+
+        let data = [0xff];
+        assert!(matches!(
+            types::ResetReason::try_decode(&mut Reader::new(&data[..])),
+            Err(error::Error::InvalidEnumValue {
+                type_name: "ResetReason",
+                value: 0xff
+            })
+        ));
+    }
+
+    #[test]
+    fn display_errors() {
+        let decode = |payload: &[u8]| {
+            subsystem::MTFramePayload::try_decode(
+                &types::MTSubsystem::UTIL,
+                &types::CommandType::SRSP,
+                types::UTILCommandId::CallbackSubCmd as u8,
+                payload,
+            )
+            .unwrap_err()
+        };
+        assert_eq!(
+            decode(&[0x00, 0x99]).to_string(),
+            "UTIL SRSP 0x06 expects 5 bytes but has 2"
+        );
+
+        // A field error gets the command from the payload dispatch.
+        let error = subsystem::util::srsp::CallbackSubCmd::try_decode(&[0x00, 0x99])
+            .unwrap_err()
+            .with_command(frame::CommandCode {
+                is_extended: false,
+                cmd_type: types::CommandType::SRSP,
+                subsystem: types::MTSubsystem::UTIL,
+                id: types::UTILCommandId::CallbackSubCmd as u8,
+            });
+        assert_eq!(
+            error.to_string(),
+            "UTIL SRSP 0x06: enables at offset 1: not enough bytes"
+        );
+
+        // The cause is in the message already, error chains would print it
+        // twice.
+        assert!(matches!(error.kind(), error::Error::NotEnoughBytes));
+        let error: &dyn std::error::Error = &error;
+        assert!(error.source().is_none());
+        assert_eq!(
+            error::Error::InvalidStatus(0x99).to_string(),
+            "invalid status 0x99"
+        );
+    }
+
+    #[test]
+    fn status_helpers() {
+        use client::Confirm;
+        use types::Status;
+
+        assert!(Status::Success.is_success());
+        assert_eq!(Status::NoAck.into_result(), Err(Status::NoAck));
+        assert!(Status::UnavailableKey.is_security_error());
+        assert!(!Status::NoAck.is_security_error());
+        assert!(Status::FHAPIError.is_fh_error());
+        assert!(Status::FHAPIExpiredNode.is_fh_error());
+        assert!(!Status::CounterError.is_fh_error());
+
+        let confirm = subsystem::mac::areq::PurgeCnf {
+            status: Status::InvalidHandle,
+            handle: 0x01,
+        };
+        assert!(matches!(
+            confirm.check(),
+            Err(error::Error::CommandFailed(Status::InvalidHandle))
+        ));
+        let confirm = subsystem::mac::areq::PurgeCnf {
+            status: Status::Success,
+            handle: 0x01,
+        };
+        assert_eq!(confirm.check().unwrap().handle, 0x01);
+    }
+
+    #[test]
+    fn client_fh_enable_and_start() {
+        use subsystem::mac::srsp;
+        use types::{MACCommandId, MTSubsystem};
+
+        let mut fh_client = test_util::ScriptedClient::new();
+        client::fh_enable(&mut fh_client).unwrap();
+        client::fh_start(&mut fh_client).unwrap();
+        assert_eq!(
+            fh_client.command_ids(),
+            [
+                MACCommandId::FHEnableReq as u8,
+                MACCommandId::FHStartReq as u8
+            ]
+        );
+
+        fh_client.respond(MTSubsystem::MAC, MACCommandId::FHStartReq as u8, |_| {
+            let status = types::Status::FHAPIError;
+            Ok(srsp::FHStartReq { status }.into_mt_frame())
+        });
+        assert!(matches!(
+            client::fh_start(&mut fh_client),
+            Err(error::Error::CommandFailed(types::Status::FHAPIError))
+        ));
+    }
+
+    #[test]
+    fn wisun_information_elements() {
+        use ie::{HeaderIe, PayloadIe, Schedule, WhIe, WpIe};
+
+        // UTT-IE of a PAN advertisement solicit, UFSI 0x123456.
+        let data = [0x05, 0x15, 0x01, 0x01, 0x56, 0x34, 0x12];
+        let header_ies = HeaderIe::try_decode_list(&data).unwrap();
+        assert_eq!(header_ies.len(), 1);
+        assert_eq!(header_ies[0].element_id, ie::WISUN_HEADER_IE);
+        let wh_ie = WhIe::try_from_header_ie(&header_ies[0]).unwrap();
+        assert_eq!(
+            wh_ie,
+            WhIe::UnicastTiming {
+                frame_type: 0x01,
+                ufsi: 0x123456
+            }
+        );
+        let mut buffer = Vec::new();
+        wh_ie.to_header_ie().encode_into(&mut buffer).unwrap();
+        assert_eq!(buffer, data);
+
+        let wp_ies = vec![
+            WpIe::UnicastSchedule(Schedule {
+                dwell_interval: 0xff,
+                clock_drift: 0xff,
+                timing_accuracy: 0x0a,
+                channel_info: 0x02,
+                channel_data: vec![0x01, 0x00],
+            }),
+            WpIe::Pan {
+                pan_size: 10,
+                routing_cost: 0x0100,
+                use_parent_bs_ie: true,
+                routing_method: true,
+                fan_tps_version: 1,
+            },
+            WpIe::NetName(b"ti154".to_vec()),
+            WpIe::PanVersion(7),
+        ];
+        let payload_ies = [PayloadIe::wisun(&wp_ies).unwrap()];
+        let mut buffer = Vec::new();
+        payload_ies[0].encode_into(&mut buffer).unwrap();
+        assert_eq!(&buffer[..4], [0x1a, 0xa0, 0x06, 0x88]);
+
+        let mut request = subsystem::mac::sreq::DataReq {
+            dest_address: types::Address::Addr16Bit(types::ShortAddress { address: 0x0001 }),
+            dest_pan_id: types::PanId(0xabcd),
+            src_address_mode: types::AddressMode::Addr64Bit,
+            handle: 0x01,
+            tx_option: types::TxOption::ACK,
+            channel: 0,
+            power: 0,
+            security: types::Security::none(),
+            include_fh_ies: 0,
+            data_length: 0,
+            ie_length: 0,
+            data_payload: Vec::new(),
+            ie_payload: Vec::new(),
+        };
+        request.set_payload_ies(&payload_ies).unwrap();
+        assert_eq!(request.ie_length as usize, buffer.len());
+
+        let decoded = PayloadIe::try_decode_list(&request.ie_payload).unwrap();
+        assert_eq!(decoded, payload_ies);
+        assert_eq!(decoded[0].wp_ies().unwrap(), wp_ies);
+    }
+
+    #[test]
+    fn data_req_header_ies() {
+        use ie::{HeaderIe, PayloadIe, WhIe, WpIe};
+        use types::FHIncludeIe;
+
+        let mut request = subsystem::mac::sreq::DataReq {
+            dest_address: types::Address::Addr64Bit(types::ExtendedAddress::from(
+                0x00124b000a0bc9bd,
+            )),
+            dest_pan_id: types::PanId(0xabcd),
+            src_address_mode: types::AddressMode::Addr64Bit,
+            handle: 0x02,
+            tx_option: types::TxOption::ACK,
+            channel: 0,
+            power: 0,
+            security: types::Security::none(),
+            include_fh_ies: 0,
+            data_length: 0,
+            ie_length: 0,
+            data_payload: Vec::new(),
+            ie_payload: Vec::new(),
+        };
+        request.set_fh_ies(FHIncludeIe::UTT | FHIncludeIe::BT | FHIncludeIe::US);
+        assert_eq!(request.include_fh_ies, 0x0001_000a);
+        assert_eq!(
+            request.fh_ies(),
+            FHIncludeIe::UTT | FHIncludeIe::BT | FHIncludeIe::US
+        );
+
+        let ea = WhIe::EapolAuthenticator(types::ExtendedAddress::from(0x0102030405060708));
+        request
+            .set_ies(
+                &[ea.to_header_ie()],
+                &[PayloadIe::wisun(&[WpIe::PanVersion(3)]).unwrap()],
+            )
+            .unwrap();
+        assert_eq!(
+            request.ie_payload,
+            [
+                0x09, 0x15, 0x09, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x00, 0x3f, 0x04,
+                0xa0, 0x02, 0x06, 0x03, 0x00
+            ]
+        );
+        assert_eq!(request.ie_length, 19);
+
+        request.set_ies(&[], &[]).unwrap();
+        request.push_raw_ies(&[0x02, 0x15, 0x04, 0x80]);
+        assert_eq!(request.ie_length, 4);
+        let header_ies = HeaderIe::try_decode_list(&request.ie_payload).unwrap();
+        assert_eq!(
+            WhIe::try_from_header_ie(&header_ies[0]).unwrap(),
+            WhIe::Rsl(0x80)
+        );
+
+        let frame = request.into_mt_frame();
+        assert_eq!(frame.header.length as usize, frame.payload.len());
+    }
+
+    #[test]
+    fn ie_lengths_at_the_descriptor_limits() {
+        use ie::{HeaderIe, NestedIe, PayloadIe, WpIe};
+        use subsystem::mac::sreq;
+        use test_util::{Arbitrary, Rng};
+
+        let header = |length| HeaderIe {
+            element_id: ie::WISUN_HEADER_IE,
+            content: vec![0xa5; length],
+        };
+        let mut buffer = Vec::new();
+        header(127).encode_into(&mut buffer).unwrap();
+        assert_eq!(HeaderIe::try_decode_list(&buffer).unwrap(), [header(127)]);
+        assert!(matches!(
+            header(128).encode_into(&mut Vec::new()),
+            Err(error::Error::PayloadTooLarge(128))
+        ));
+
+        let payload = |length| PayloadIe {
+            group_id: ie::WISUN_PAYLOAD_IE_GROUP,
+            content: vec![0x5a; length],
+        };
+        let mut buffer = Vec::new();
+        payload(2047).encode_into(&mut buffer).unwrap();
+        assert_eq!(
+            PayloadIe::try_decode_list(&buffer).unwrap(),
+            [payload(2047)]
+        );
+        assert!(matches!(
+            payload(2048).encode_into(&mut Vec::new()),
+            Err(error::Error::PayloadTooLarge(2048))
+        ));
+
+        for (long, limit) in [(false, 255), (true, 2047)] {
+            let nested = |length| NestedIe {
+                sub_id: WpIe::NETNAME,
+                long,
+                content: vec![0x3c; length],
+            };
+            let mut buffer = Vec::new();
+            nested(limit).encode_into(&mut buffer).unwrap();
+            assert_eq!(NestedIe::try_decode_list(&buffer).unwrap(), [nested(limit)]);
+            assert!(matches!(
+                nested(limit + 1).encode_into(&mut Vec::new()),
+                Err(error::Error::PayloadTooLarge(x)) if x == limit + 1
+            ));
+        }
+        assert!(matches!(
+            PayloadIe::wisun(&[WpIe::NetName(vec![0x00; 256])]),
+            Err(error::Error::PayloadTooLarge(256))
+        ));
+
+        let mut request = sreq::DataReq::arbitrary(&mut Rng::new(0x302));
+        let before = request.clone();
+        assert!(request.set_ies(&[header(128)], &[]).is_err());
+        assert_eq!(request, before);
+    }
+
+    #[test]
+    fn fh_net_name_and_gtk_hash() {
+        use subsystem::mac::{sreq, srsp};
+        use types::{FHPIBAttributeId, GtkHash, NetName};
+
+        let name = NetName::new("wisun-fan").unwrap();
+        let request = sreq::FHSetReq::net_name(&name);
+        assert_eq!(request.attribute_id, FHPIBAttributeId::NetName);
+        assert_eq!(request.data.len(), NetName::SIZE);
+        assert_eq!(&request.data[..9], b"wisun-fan");
+        assert!(request.data[9..].iter().all(|byte| *byte == 0x00));
+
+        let response = srsp::FHGetReq {
+            status: types::Status::Success,
+            data: request.data,
+        };
+        assert_eq!(response.net_name().unwrap(), name);
+        assert_eq!(name.to_string(), "wisun-fan");
+        assert!(NetName::new("a-network-name-longer-than-32-bytes").is_err());
+
+        let hash = GtkHash([0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
+        let request = sreq::FHSetReq::gtk_hash(2, hash).unwrap();
+        assert_eq!(request.attribute_id, FHPIBAttributeId::GTK2Hash);
+        assert!(sreq::FHSetReq::gtk_hash(4, hash).is_none());
+        let response = srsp::FHGetReq {
+            status: types::Status::Success,
+            data: request.data,
+        };
+        assert_eq!(response.gtk_hash().unwrap(), hash);
+    }
+
+    #[test]
+    fn security_device_table() {
+        use security::{DeviceEntry, DeviceTable};
+        use types::SecurityPIBAttributeId::{DeviceEntry as Entry, DeviceTableEntries};
+
+        let device = |address: u16| DeviceEntry {
+            pan_id: types::PanId(0xabcd),
+            short_address: types::ShortAddress::from(address),
+            ext_address: types::ExtendedAddress::from(u64::from(address)),
+            frame_counter: 0,
+            exempt: false,
+        };
+
+        let mut table = DeviceTable::new(2);
+        let requests = table.insert(device(1)).unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].attribute_id, Entry);
+        assert_eq!(requests[0].index1, 0);
+        assert_eq!(
+            requests[0].attribute_value,
+            [
+                0xcd, 0xab, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00
+            ]
+        );
+        assert_eq!(requests[1].attribute_id, DeviceTableEntries);
+        assert_eq!(requests[1].attribute_value, [0x01, 0x00]);
+
+        table.insert(device(2)).unwrap();
+        assert!(matches!(
+            table.insert(device(3)),
+            Err(error::Error::TableFull)
+        ));
+
+        // Updating a known device rewrites its own index only.
+        let mut updated = device(2);
+        updated.frame_counter = 10;
+        let requests = table.insert(updated).unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].index1, 1);
+        assert_eq!(table.get(1).unwrap().frame_counter, 10);
+
+        // Removing the first device moves the last one into its index.
+        let removal = table.remove(&device(1).ext_address).unwrap();
+        assert_eq!(removal.index, 0);
+        assert_eq!(removal.moved_from, Some(1));
+        let requests = removal.requests;
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].index1, 0);
+        let mut cursor = Reader::new(&requests[0].attribute_value[..]);
+        assert_eq!(DeviceEntry::try_decode(&mut cursor).unwrap(), updated);
+        assert_eq!(requests[1].attribute_value, [0x01, 0x00]);
+        assert_eq!(table.index_of(&updated.ext_address), Some(0));
+        assert!(table.remove(&device(1).ext_address).is_none());
+
+        assert_eq!(table.restore_requests().len(), 2);
+        assert_eq!(table.clear()[0].attribute_value, [0x00, 0x00]);
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn security_key_table() {
+        use security::{KeyDescriptor, KeyLookup, KeyTable, KeyUsage};
+        use types::SecurityPIBAttributeId::{
+            KeyEntry, KeyIdLookupEntry, KeyIdUsageEntry, KeyTableEntries,
+        };
+
+        let key = |byte: u8| KeyDescriptor {
+            key: [byte; 16],
+            frame_counter: 0,
+            lookups: vec![KeyLookup {
+                data: [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, byte],
+                size: 0x01,
+            }],
+            devices: Vec::new(),
+            usages: vec![KeyUsage {
+                frame_type: 0x01,
+                command_frame_id: 0x00,
+            }],
+        };
+
+        let mut table = KeyTable::new(4);
+        let requests = table.install(1, key(0xaa)).unwrap();
+        let ids: Vec<_> = requests.iter().map(|x| x.attribute_id).collect();
+        assert_eq!(
+            ids,
+            [KeyEntry, KeyIdLookupEntry, KeyIdUsageEntry, KeyTableEntries]
+        );
+        assert!(requests[..3].iter().all(|x| x.index1 == 1));
+        assert_eq!(requests[0].attribute_value.len(), 20);
+        assert_eq!(requests[1].attribute_value[8..], [0xaa, 0x01]);
+        assert_eq!(requests[2].attribute_value, [0x01, 0x00]);
+        assert_eq!(requests[3].attribute_value, [0x02, 0x00]);
+
+        // Rotating replaces the key in place.
+        table.install(1, key(0xbb)).unwrap();
+        assert_eq!(table.get(1).unwrap().key, [0xbb; 16]);
+        assert_eq!(table.keys().count(), 1);
+        assert!(matches!(
+            table.install(4, key(0xcc)),
+            Err(error::Error::TableFull)
+        ));
+        // Descriptors past index2 u16::MAX cannot be written.
+        let mut many = key(0xcc);
+        many.usages = vec![many.usages[0]; 0x10001];
+        assert!(matches!(
+            table.install(2, many),
+            Err(error::Error::TableFull)
+        ));
+        assert!(table.get(2).is_none());
+
+        let request = table.frame_counter_request(1);
+        assert_eq!((request.attribute_id, request.index1), (KeyEntry, 1));
+        let mut data = vec![0xbb; 16];
+        data.extend([0x00, 0x00, 0x00, 0xf0]);
+        let response = subsystem::mac::srsp::SecurityGetReq {
+            status: types::Status::Success,
+            index1: 1,
+            index2: 0,
+            data,
+        };
+        assert_eq!(
+            table.update_frame_counter(1, &response).unwrap(),
+            0xf000_0000
+        );
+        assert_eq!(table.get(1).unwrap().frame_counter, 0xf000_0000);
+        assert_eq!(table.restore_requests().len(), 4);
+    }
+
+    #[test]
+    fn coordinator_start_join_and_data() {
+        use coordinator::{Coordinator, CoordinatorConfig, CoordinatorEvent, JoinDecision};
+        use subsystem::mac::areq;
+        use types::{MACCommandId, Status};
+
+        let blocked = types::ExtendedAddress::from(0xbad);
+        let mut coordinator = Coordinator::new(
+            test_util::ScriptedClient::new(),
+            CoordinatorConfig::default(),
+            |indication: &areq::AssociateInd| {
+                if indication.extended_address == blocked {
+                    JoinDecision::Reject(types::AssociationStatus::PANAccessDenied)
+                } else {
+                    JoinDecision::Accept
+                }
+            },
+        );
+        coordinator.start().unwrap();
+        let ids = coordinator.client().command_ids();
+        assert_eq!(ids.first(), Some(&(MACCommandId::ResetReq as u8)));
+        assert_eq!(ids.last(), Some(&(MACCommandId::StartReq as u8)));
+        assert_eq!(ids.len(), 6);
+
+        let associate = |address: u64| {
+            areq::AssociateInd {
+                extended_address: types::ExtendedAddress::from(address),
+                capabilities: types::CapabilityInfo::ALLOCATE_ADDRESS,
+                security: types::Security::none(),
+            }
+            .into_mt_frame()
+        };
+        let data = areq::DataInd {
+            src_address: types::Address::Addr16Bit(types::ShortAddress::from(0x0001)),
+            dest_address: types::Address::Addr16Bit(types::ShortAddress::from(0x0000)),
+            timestamp: 0,
+            timestamp2: 0,
+            src_pan_id: types::PanId(0x1234),
+            dest_pan_id: types::PanId(0x1234),
+            link_quality: types::Lqi(0xff),
+            correlation: 0,
+            rssi: types::Rssi(-40),
+            dsn: 0,
+            security: types::Security::none(),
+            frame_counter: 0,
+            data_length: 2,
+            ie_length: 0,
+            data_payload: vec![0x68, 0x69],
+            ie_payload: Vec::new(),
+        };
+        let frames = vec![
+            areq::StartCnf {
+                status: Status::Success,
+            }
+            .into_mt_frame(),
+            associate(0x1),
+            associate(0xbad),
+            associate(0x1),
+            data.into_mt_frame(),
+        ];
+
+        let events: Vec<_> = coordinator
+            .events(frames)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(matches!(events[0], CoordinatorEvent::Started));
+        assert!(matches!(
+            events[1],
+            CoordinatorEvent::Joined { short_address, .. } if short_address.u16() == 0x0001
+        ));
+        assert!(matches!(
+            events[2],
+            CoordinatorEvent::Rejected {
+                status: types::AssociationStatus::PANAccessDenied,
+                ..
+            }
+        ));
+        // A rejoining device keeps its address.
+        assert!(matches!(
+            events[3],
+            CoordinatorEvent::Joined { short_address, .. } if short_address.u16() == 0x0001
+        ));
+        match &events[4] {
+            CoordinatorEvent::Data(indication) => assert_eq!(indication.data_payload, b"hi"),
+            event => panic!("unexpected event {:?}", event),
+        }
+        assert_eq!(coordinator.address_book().len(), 1);
+
+        let handle = coordinator
+            .send(
+                types::Address::Addr16Bit(types::ShortAddress::from(0x0001)),
+                vec![0x01],
+            )
+            .unwrap();
+        assert_eq!(handle, 0);
+        let request = coordinator.client().requests().last().unwrap();
+        assert_eq!(request.header.command.id, MACCommandId::DataReq as u8);
+    }
+
+    #[test]
+    fn coordinator_rejects_joins_once_addresses_run_out() {
+        use coordinator::{Coordinator, CoordinatorConfig, CoordinatorEvent, JoinDecision};
+        use subsystem::mac::{areq, sreq};
+        use types::AssociationStatus;
+
+        let config = |first_device_address| CoordinatorConfig {
+            first_device_address,
+            ..CoordinatorConfig::default()
+        };
+        let accept = |_: &areq::AssociateInd| JoinDecision::Accept;
+
+        let mut coordinator =
+            Coordinator::new(test_util::ScriptedClient::new(), config(0xfffe), accept);
+        assert!(matches!(
+            coordinator.start(),
+            Err(error::Error::InvalidShortAddress(x)) if x.u16() == 0xfffe
+        ));
+        assert!(coordinator.client().requests().is_empty());
+
+        // Only 0xfffc and 0xfffd are left before the reserved addresses.
+        let mut coordinator =
+            Coordinator::new(test_util::ScriptedClient::new(), config(0xfffc), accept);
+        coordinator.start().unwrap();
+        let frames: Vec<_> = (1..=3)
+            .map(|address| {
+                areq::AssociateInd {
+                    extended_address: types::ExtendedAddress::from(address),
+                    capabilities: types::CapabilityInfo::ALLOCATE_ADDRESS,
+                    security: types::Security::none(),
+                }
+                .into_mt_frame()
+            })
+            .collect();
+        let events: Vec<_> = coordinator
+            .events(frames)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(matches!(
+            events[0],
+            CoordinatorEvent::Joined { short_address, .. } if short_address.u16() == 0xfffc
+        ));
+        assert!(matches!(
+            events[1],
+            CoordinatorEvent::Joined { short_address, .. } if short_address.u16() == 0xfffd
+        ));
+        assert!(matches!(
+            events[2],
+            CoordinatorEvent::Rejected {
+                status: AssociationStatus::PANAtCapacity,
+                ..
+            }
+        ));
+        let responses = coordinator.client().decoded(
+            types::MTSubsystem::MAC,
+            types::MACCommandId::AssociateRsp as u8,
+            sreq::AssociateRsp::try_decode,
+        );
+        let response = responses.last().unwrap();
+        assert_eq!(response.assoc_short_address.u16(), 0xffff);
+        assert_eq!(coordinator.address_book().len(), 2);
+    }
+
+    #[test]
+    fn device_scan_associate_and_poll() {
+        use device::{Device, DeviceConfig, DeviceEvent};
+        use subsystem::mac::areq;
+        use types::{MACCommandId, Status};
+
+        let beacon = |pan_id: u16, link_quality: u8| {
+            areq::BeaconNotifyInd::StandardFrame(areq::StandardBeaconFrame {
+                bsn: 0,
+                pan_descriptor: types::PanDescriptor {
+                    timestamp: 0,
+                    coord_address: types::Address::Addr16Bit(types::ShortAddress::from(0x0000)),
+                    pan_id: types::PanId(pan_id),
+                    superframe_spec: types::SuperframeSpec::from_u16(0xc0ff),
+                    logical_channel: 3,
+                    channel_page: 9,
+                    gts_permit: false,
+                    link_quality,
+                    security_failure: false,
+                    security: types::Security::none(),
+                },
+                short_addr_list: Vec::new(),
+                ext_addr_list: Vec::new(),
+                sdu: Vec::new(),
+            })
+            .into_mt_frame()
+        };
+        let scan_cnf = areq::ScanCnf {
+            status: Status::Success,
+            scan_type: types::ScanType::Active,
+            channel_page: 9,
+            phy_id: types::PhyId::STD_US_915_PHY_1,
+            unscanned_channels: types::ChannelMask::new(),
+            result_list_count: 0,
+            result_list: Vec::new(),
+        }
+        .into_mt_frame();
+
+        let config = DeviceConfig {
+            poll_interval: Some(Duration::from_secs(5)),
+            ..DeviceConfig::default()
+        };
+        // Pick the beacon with the best link quality.
+        let mut device = Device::new(test_util::ScriptedClient::new(), config, |pans| {
+            (0..pans.len()).max_by_key(|&i| pans[i].link_quality)
+        });
+        assert!(matches!(
+            device.send(vec![0x01]),
+            Err(error::Error::NotAssociated)
+        ));
+
+        let now = Instant::now();
+        device.scan().unwrap();
+        for frame in [beacon(0x1111, 0x40), beacon(0x2222, 0xc0)] {
+            assert!(matches!(
+                device.handle(&frame, now).unwrap(),
+                DeviceEvent::Beacon(_)
+            ));
+        }
+        match device.handle(&scan_cnf, now).unwrap() {
+            DeviceEvent::Associating(pan) => assert_eq!(pan.pan_id, types::PanId(0x2222)),
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        let associate_cnf = areq::AssociateCnf {
+            status: Status::Success,
+            short_address: types::ShortAddress::from(0x0007),
+            security: types::Security::none(),
+        }
+        .into_mt_frame();
+        assert!(matches!(
+            device.handle(&associate_cnf, now).unwrap(),
+            DeviceEvent::Associated(_)
+        ));
+        assert_eq!(device.short_address().unwrap().u16(), 0x0007);
+
+        assert!(!device.poll_if_due(now + Duration::from_secs(1)).unwrap());
+        assert!(device.poll_if_due(now + Duration::from_secs(5)).unwrap());
+        let poll_cnf = |frame_pending| {
+            areq::PollCnf {
+                status: Status::NoData,
+                frame_pending,
+            }
+            .into_mt_frame()
+        };
+        // A pending frame triggers another poll straight away.
+        device.handle(&poll_cnf(true), now).unwrap();
+        device.handle(&poll_cnf(false), now).unwrap();
+        assert_eq!(device.send(vec![0x01]).unwrap(), 0);
+
+        let ids = |ids: &[MACCommandId]| ids.iter().map(|&x| x as u8).collect::<Vec<_>>();
+        assert_eq!(
+            device.client().command_ids(),
+            ids(&[
+                MACCommandId::ScanReq,
+                MACCommandId::AssociateReq,
+                MACCommandId::PollReq,
+                MACCommandId::PollReq,
+                MACCommandId::DataReq,
+            ])
+        );
+    }
+
+    #[test]
+    fn sniffer_promiscuous_stream() {
+        use sniffer::{Sniffer, SnifferConfig};
+        use subsystem::mac::{areq, sreq};
+        use types::{MACCommandId, MACPIBAttributeId, MTSubsystem, Status};
+
+        let writes = |client: &test_util::ScriptedClient| {
+            client
+                .decoded(
+                    MTSubsystem::MAC,
+                    MACCommandId::SetReq as u8,
+                    sreq::SetReq::try_decode,
+                )
+                .into_iter()
+                .map(|x| (x.attribute_id, x.attribute_value[0]))
+                .collect::<Vec<_>>()
+        };
+
+        let config = SnifferConfig {
+            logical_channel: 11,
+            channel_page: 9,
+            phy_id: types::PhyId::STD_US_915_PHY_1,
+        };
+        let mut sniffer = Sniffer::new(test_util::ScriptedClient::new(), config);
+        sniffer.start().unwrap();
+        assert_eq!(
+            writes(sniffer.client()).last(),
+            Some(&(MACPIBAttributeId::PromiscuousMode, 1))
+        );
+        assert!(writes(sniffer.client()).contains(&(MACPIBAttributeId::LogicalChannel, 11)));
+
+        let indication = |rssi: i8| areq::DataInd {
+            src_address: types::Address::Addr16Bit(types::ShortAddress::from(0x0002)),
+            dest_address: types::Address::Addr16Bit(types::ShortAddress::from(0xffff)),
+            timestamp: 0x1000,
+            timestamp2: 0x20,
+            src_pan_id: types::PanId(0x1234),
+            dest_pan_id: types::PanId(0x1234),
+            link_quality: types::Lqi(0x80),
+            correlation: 0,
+            rssi: types::Rssi(rssi),
+            dsn: 7,
+            security: types::Security::none(),
+            frame_counter: 0,
+            data_length: 1,
+            ie_length: 0,
+            data_payload: vec![0xaa],
+            ie_payload: Vec::new(),
+        };
+        let frames = vec![
+            indication(-50).into_mt_frame(),
+            areq::PollCnf {
+                status: Status::NoData,
+                frame_pending: false,
+            }
+            .into_mt_frame(),
+            indication(-70).into_mt_frame(),
+        ];
+        let sniffed: Vec<_> = sniffer.frames(frames).collect::<Result<_, _>>().unwrap();
+        assert_eq!(sniffed.len(), 2);
+        assert_eq!(sniffed[0].rssi, types::Rssi(-50));
+        assert_eq!(sniffed[1].rssi, types::Rssi(-70));
+        assert_eq!(sniffed[0].logical_channel, 11);
+        assert_eq!(sniffed[0].timestamp, 0x1000);
+        assert_eq!(sniffed[0].payload, vec![0xaa]);
+
+        sniffer.set_channel(12).unwrap();
+        sniffer.stop().unwrap();
+        assert_eq!(
+            writes(sniffer.client())[5..],
+            [
+                (MACPIBAttributeId::LogicalChannel, 12),
+                (MACPIBAttributeId::PromiscuousMode, 0),
+                (MACPIBAttributeId::RxOnWhenIdle, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn link_quality_and_rssi() {
+        use types::{Lqi, Rssi};
+
+        let rssi = Rssi::try_decode(&mut Reader::new(&[0xc4][..])).unwrap();
+        assert_eq!(rssi.dbm(), -60);
+        assert_eq!(rssi.to_string(), "-60 dBm");
+        let mut buffer = Vec::new();
+        rssi.encode_into(&mut buffer);
+        assert_eq!(buffer, [0xc4]);
+        assert!(Rssi(-40) > rssi);
+
+        assert_eq!(Lqi(0xff).percent(), 100);
+        assert_eq!(Lqi(0x00).to_rssi(), Rssi(Lqi::MIN_DBM));
+        assert_eq!(Lqi(0xff).to_rssi(), Rssi(Lqi::MAX_DBM));
+        assert_eq!(Lqi::from_rssi(Rssi(-128)), Lqi(0x00));
+        assert_eq!(Lqi::from_rssi(Rssi(0)), Lqi(0xff));
+        assert_eq!(Lqi::from_rssi(Lqi(0x80).to_rssi()).value(), 0x7f);
+        assert_eq!(Lqi(0x80).to_string(), "LQI 128");
+
+        assert_eq!(Lqi::correlation_quality(30), 0);
+        assert_eq!(Lqi::correlation_quality(80), 50);
+        assert_eq!(Lqi::correlation_quality(127), 100);
+    }
+
+    #[test]
+    fn decode_unnamed_phy_ids() {
+        use types::PhyId;
+
+        let phy_id = PhyId::try_decode(&mut Reader::new(&[0x03][..])).unwrap();
+        assert_eq!(phy_id, PhyId::STD_ETSI_863_PHY_3);
+        assert!(phy_id.is_standard());
+
+        let phy_id = PhyId::try_decode(&mut Reader::new(&[0x8c][..])).unwrap();
+        assert_eq!(phy_id, PhyId::Other(0x8c));
+        assert!(phy_id.is_generic());
+        let mut buffer = Vec::new();
+        phy_id.encode_into(&mut buffer);
+        assert_eq!(buffer, [0x8c]);
+
+        assert!(PhyId::GENERIC_US_LRM_915_PHY_129.is_long_range());
+        for value in 0..=u8::MAX {
+            assert_eq!(u8::from(PhyId::from(value)), value);
+        }
+    }
+
+    #[test]
+    fn decode_unknown_enum_values() {
+        use std::convert::TryFrom;
+        use subsystem::mac::srsp;
+        use types::{MACPIBAttributeId, Status};
+
+        assert_eq!(Status::from_raw(0xe4), Status::SecurityError);
+        assert_eq!(Status::from_raw(0x42), Status::Unknown(0x42));
+        assert_eq!(Status::Unknown(0x42).raw(), 0x42);
+        assert_eq!(MACPIBAttributeId::from_raw(0x53).raw(), 0x53);
+
+        // A SetReq response carrying a status newer firmware introduced is
+        // refused unless the caller opts in.
+        assert!(matches!(
+            srsp::SetReq::try_decode(&[0x42]).unwrap_err().kind(),
+            error::Error::InvalidStatus(0x42)
+        ));
+        assert!(matches!(
+            Status::try_from(0x42),
+            Err(error::Error::InvalidStatus(0x42))
+        ));
+
+        let response = srsp::SetReq::try_decode_with(&[0x42], DecodeOptions::lenient()).unwrap();
+        assert_eq!(response.status, Status::Unknown(0x42));
+        assert!(!response.status.is_success());
+        assert_eq!(response.encode(), [0x42]);
+
+        // The option reaches the field decoders through the payload dispatch.
+        let frame = response.into_mt_frame();
+        assert!(subsystem::MTFramePayload::try_from_frame(&frame).is_err());
+        assert!(matches!(
+            subsystem::MTFramePayload::try_from_frame_with(&frame, DecodeOptions::lenient()),
+            Ok(subsystem::MTFramePayload::MAC_SetReq_SRSP(srsp::SetReq {
+                status: Status::Unknown(0x42)
+            }))
+        ));
+    }
+
+    #[test]
+    fn typed_command_code() {
+        use frame::CommandCode;
+        use types::{CommandId, CommandType, MACCommandId, MTSubsystem, SYSCommandId};
+
+        // AREQ MAC DataInd.
+        let command = CommandCode::from_cmd(0x42, 0x85).unwrap();
+        assert_eq!(command.cmd_type, CommandType::AREQ);
+        assert_eq!(command.subsystem, MTSubsystem::MAC);
+        assert_eq!(command.command_id().mac(), Some(MACCommandId::DataInd));
+        assert!(command.is(CommandType::AREQ, MTSubsystem::MAC, MACCommandId::DataInd));
+        assert!(!command.is(CommandType::SRSP, MTSubsystem::MAC, MACCommandId::DataInd));
+        assert_eq!(command.cmd0(), 0x42);
+
+        // Id 0x00 is ResetReq in SYS and MTCommandError in RPC.
+        let command = CommandCode::from_cmd(0x21, 0x00).unwrap();
+        assert!(command.is(CommandType::SREQ, MTSubsystem::SYS, SYSCommandId::ResetReq));
+        assert_eq!(command.command_id(), CommandId(0x00));
+
+        // Subsystems without decoders still parse in the header.
+        let command = CommandCode::from_cmd(0x48, 0x01).unwrap();
+        assert_eq!(command.subsystem, MTSubsystem::DEBUG);
+        assert!(matches!(
+            CommandCode::from_cmd(0x5f, 0x01),
+            Err(error::Error::InvalidSubsystem(0x1f))
+        ));
+        assert!(matches!(
+            subsystem::MTFramePayload::try_decode(
+                &command.subsystem,
+                &command.cmd_type,
+                command.id,
+                &[]
+            ),
+            Err(error::Error::NotImplemented)
+        ));
+    }
+
+    #[test]
+    fn extended_frame_blocks() {
+        use frame::{CommandCode, MTExtendedHeader, MTFrame, MTHeader};
+        use types::{CommandType, MTExtendedHeaderStatus, MTSubsystem};
+
+        let payload: Vec<u8> = (0..600).map(|x| x as u8).collect();
+        let frame = MTFrame {
+            header: MTHeader {
+                length: 0,
+                command: CommandCode {
+                    is_extended: false,
+                    cmd_type: CommandType::AREQ,
+                    subsystem: MTSubsystem::MAC,
+                    id: types::MACCommandId::SecurityGetReq as u8,
+                },
+            },
+            extended_header: None,
+            payload: payload.clone(),
+        };
+        // 256 blocks of 251 bytes is the most the one byte block number
+        // allows, below the limit of the length field.
+        let oversized = |size: usize| MTFrame {
+            payload: vec![0x00; size],
+            ..frame.clone()
+        };
+        assert_eq!(oversized(256 * 251).into_blocks(0).unwrap().len(), 256);
+        assert!(matches!(
+            oversized(256 * 251 + 1).into_blocks(0),
+            Err(error::Error::PayloadTooLarge(64257))
+        ));
+        assert!(matches!(
+            oversized(0x10000).into_blocks(0),
+            Err(error::Error::PayloadTooLarge(0x10000))
+        ));
+
+        let blocks = frame.into_blocks(0).unwrap();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].header.length, 0xff);
+        assert!(matches!(
+            blocks[2].extended_header,
+            Some(MTExtendedHeader::V2 {
+                block: 2,
+                packet_length: 600,
+                ..
+            })
+        ));
+
+        let mut parser = crate::parser::Parser::new();
+        let mut frames = Vec::new();
+        for block in &blocks {
+            for byte in block.encode_to_uart_transport_frame() {
+                if let Some(frame) = parser.feed(byte) {
+                    frames.push(frame.unwrap());
+                }
+            }
+        }
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].payload, payload);
+        assert!(frames[0].header.command.is_extended);
+
+        let ack = blocks[1]
+            .block_ack(MTExtendedHeaderStatus::Success)
+            .unwrap();
+        let mut buffer = Vec::new();
+        ack.encode_into(&mut buffer);
+        assert_eq!(buffer, [0x03, 0xc2, 0x30, 0x18, 0x01, 0x00]);
+
+        // Skipping a block fails the whole packet.
+        let mut reassembler = frame::Reassembler::new();
+        assert!(reassembler.push(blocks[0].clone()).unwrap().is_none());
+        assert!(matches!(
+            reassembler.push(blocks[2].clone()),
+            Err(error::Error::Fragmentation(
+                MTExtendedHeaderStatus::BlockOutOfOrder
+            ))
+        ));
+    }
+
+    #[test]
+    fn retry_policy_on_timeouts() {
+        use client::{Client, RetryPolicy, Retrying};
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use subsystem::mac::{sreq, srsp};
+        use types::{MACCommandId, MTSubsystem, Status};
+
+        static SLEPT_MS: AtomicU64 = AtomicU64::new(0);
+        fn sleep(duration: Duration) {
+            SLEPT_MS.fetch_add(duration.as_millis() as u64, Ordering::SeqCst);
+        }
+
+        // Drops the first `drops` responses.
+        struct LossyClient {
+            drops: usize,
+            timeouts: Vec<Duration>,
+        }
+
+        impl Client for LossyClient {
+            fn request(&mut self, _: frame::MTFrame) -> Result<frame::MTFrame, error::Error> {
+                unreachable!()
+            }
+
+            fn request_with_timeout(
+                &mut self,
+                frame: frame::MTFrame,
+                timeout: Duration,
+            ) -> Result<frame::MTFrame, error::Error> {
+                self.timeouts.push(timeout);
+                if self.drops > 0 {
+                    self.drops -= 1;
+                    return Err(error::Error::Timeout {
+                        command: frame.header.command,
+                    });
+                }
+                Ok(srsp::ResetReq {
+                    status: Status::Success,
+                }
+                .into_mt_frame())
+            }
+        }
+
+        let policy = RetryPolicy {
+            timeouts: vec![(
+                MTSubsystem::MAC,
+                MACCommandId::ResetReq as u8,
+                Duration::from_secs(2),
+            )],
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.backoff_after(1), Duration::from_millis(50));
+        assert_eq!(policy.backoff_after(2), Duration::from_millis(100));
+        assert_eq!(policy.backoff_after(40), Duration::from_secs(1));
+
+        let lossy = LossyClient {
+            drops: 2,
+            timeouts: Vec::new(),
+        };
+        let mut client = Retrying::with_sleep(lossy, policy, sleep);
+        let request = || sreq::ResetReq { set_default: true }.into_mt_frame();
+        client.request(request()).unwrap();
+        assert_eq!(client.client().timeouts, vec![Duration::from_secs(2); 3]);
+        assert_eq!(SLEPT_MS.load(Ordering::SeqCst), 150);
+
+        client.client().drops = 3;
+        let error = client.request(request()).unwrap_err();
+        assert!(matches!(error, error::Error::Timeout { .. }));
+        assert_eq!(error.to_string(), "MAC SREQ 0x01 timed out");
+    }
+
+    #[test]
+    fn dispatch_indications_to_subscribers() {
+        use dispatch::Dispatcher;
+        use std::cell::RefCell;
+        use subsystem::mac::areq;
+
+        let data = RefCell::new(Vec::new());
+        let joins = RefCell::new(Vec::new());
+        let mut everything = 0;
+        let mut dispatcher = Dispatcher::new();
+        let data_subscription = dispatcher.subscribe(|indication: &areq::DataInd| {
+            data.borrow_mut().push(indication.data_payload.clone())
+        });
+        dispatcher.subscribe(|indication: &areq::AssociateInd| {
+            joins.borrow_mut().push(indication.extended_address)
+        });
+        dispatcher.subscribe_all(|_| everything += 1);
+
+        let associate = areq::AssociateInd {
+            extended_address: types::ExtendedAddress::from(0x1),
+            capabilities: types::CapabilityInfo::ALLOCATE_ADDRESS,
+            security: types::Security::none(),
+        }
+        .into_mt_frame();
+        let indication = areq::DataInd {
+            src_address: types::Address::Addr16Bit(types::ShortAddress::from(0x0001)),
+            dest_address: types::Address::Addr16Bit(types::ShortAddress::from(0x0000)),
+            timestamp: 0,
+            timestamp2: 0,
+            src_pan_id: types::PanId(0x1234),
+            dest_pan_id: types::PanId(0x1234),
+            link_quality: types::Lqi(0xff),
+            correlation: 0,
+            rssi: types::Rssi(-40),
+            dsn: 0,
+            security: types::Security::none(),
+            frame_counter: 0,
+            data_length: 1,
+            ie_length: 0,
+            data_payload: vec![0x2a],
+            ie_payload: Vec::new(),
+        }
+        .into_mt_frame();
+
+        assert_eq!(dispatcher.dispatch_frame(&associate).unwrap(), 2);
+        assert_eq!(dispatcher.dispatch_frame(&indication).unwrap(), 2);
+        assert!(dispatcher.unsubscribe(data_subscription));
+        assert_eq!(dispatcher.dispatch_frame(&indication).unwrap(), 1);
+        assert_eq!(dispatcher.len(), 2);
+        drop(dispatcher);
+
+        assert_eq!(data.into_inner(), vec![vec![0x2a]]);
+        assert_eq!(joins.into_inner(), vec![types::ExtendedAddress::from(0x1)]);
+        assert_eq!(everything, 3);
+    }
+
+    #[test]
+    fn reset_ind_fails_request_in_flight() {
+        use subsystem::mac::{sreq, srsp};
+        use subsystem::sys::areq::ResetInd;
+
+        struct ResettingClient {
+            reset_pending: bool,
+            reinitialized: u32,
+        }
+
+        impl client::Client for ResettingClient {
+            fn request(&mut self, _: frame::MTFrame) -> Result<frame::MTFrame, error::Error> {
+                if self.reset_pending {
+                    self.reset_pending = false;
+                    return Ok(ResetInd {
+                        reason: types::ResetReason::MACAssert,
+                        transport: types::TransportProtocolRevision::ExtendedRPCFrame,
+                        product: types::ProductIdCode::TI154Stack,
+                        major: 2,
+                        minor: 0,
+                        maint: 0,
+                    }
+                    .into_mt_frame());
+                }
+                let status = types::Status::Success;
+                Ok(srsp::SetReq { status }.into_mt_frame())
+            }
+        }
+
+        let inner = ResettingClient {
+            reset_pending: true,
+            reinitialized: 0,
+        };
+        let mut client = client::ResetAware::with_reinit(inner, |client, _| {
+            client.reinitialized += 1;
+            Ok(())
+        });
+        let request = || {
+            sreq::SetReq {
+                attribute_id: types::MACPIBAttributeId::RxOnWhenIdle,
+                attribute_value: [0x00; 16],
+            }
+            .into_mt_frame()
+        };
+
+        let error = client::Client::request(&mut client, request()).unwrap_err();
+        assert!(matches!(
+            error,
+            error::Error::DeviceReset(types::ResetReason::MACAssert)
+        ));
+        assert_eq!(error.to_string(), "device reset: MACAssert");
+        assert_eq!(client.client().reinitialized, 1);
+        assert!(client::Client::request(&mut client, request()).is_ok());
+
+        let data = [0x6, 0x41, 0x80, 0x0, 0x3, 0x1, 0x2, 0x2, 0x0];
+        let frame = frame::MTFrame::try_decode(&mut Reader::new(&data[..])).unwrap();
+        assert_eq!(
+            client
+                .handle(&frame)
+                .unwrap()
+                .map(|indication| indication.reason),
+            Some(types::ResetReason::Hardware)
+        );
+        assert_eq!(client.resets(), 2);
+        assert_eq!(client.client().reinitialized, 2);
+        assert_eq!(client.take_reset(), Some(types::ResetReason::Hardware));
+        assert_eq!(client.take_reset(), None);
+    }
+
+    #[test]
+    fn stats_count_frames_and_link_errors() {
+        let mut stats = stats::Stats::new();
+        let mut parser = crate::parser::Parser::new();
+        let request = subsystem::mac::sreq::ResetReq { set_default: true }.into_mt_frame();
+        stats.frame_sent(&request);
+
+        let valid = [0xfe, 0x6, 0x41, 0x80, 0x0, 0x3, 0x1, 0x2, 0x2, 0x0, 0xc5];
+        let mut corrupted = valid;
+        corrupted[10] ^= 0xff;
+        let bytes = [0x00, 0x11, 0x22]
+            .iter()
+            .chain(valid.iter())
+            .chain(corrupted.iter())
+            .chain([0x33].iter());
+        for byte in bytes {
+            if let Some(result) = parser.feed(*byte) {
+                stats.record(&result);
+            }
+        }
+        let before = stats.snapshot();
+        stats.error(&error::Error::Timeout {
+            command: request.header.command,
+        });
+        stats.error(&error::Error::NotEnoughBytes);
+        stats.error(&error::Error::CommandFailed(types::Status::NoBeacon));
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.frames_sent, 1);
+        assert_eq!(snapshot.sent(types::MTSubsystem::MAC), 1);
+        assert_eq!(snapshot.frames_received, 1);
+        assert_eq!(snapshot.received(types::MTSubsystem::SYS), 1);
+        assert_eq!(snapshot.checksum_failures, 1);
+        assert_eq!(snapshot.resyncs, 2);
+        assert_eq!(snapshot.discarded_bytes, 4);
+        assert_eq!(snapshot.timeouts, 1);
+        assert_eq!(snapshot.decode_errors, 1);
+
+        let delta = snapshot.since(&before);
+        assert_eq!(delta.frames_received, 0);
+        assert_eq!(delta.timeouts, 1);
+        stats.reset();
+        assert_eq!(stats.snapshot(), stats::Snapshot::default());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing_reports_spans_and_decode_failures() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+
+        struct Message(String);
+
+        impl Visit for Message {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "message" {
+                    self.0 = format!("{:?}", value);
+                }
+            }
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes) -> Id {
+                let mut lines = self.0.lock().unwrap();
+                lines.push(format!("span {}", span.metadata().name()));
+                Id::from_u64(lines.len() as u64)
+            }
+
+            fn record(&self, _: &Id, _: &Record) {}
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, event: &Event) {
+                let mut message = Message(String::new());
+                event.record(&mut message);
+                let line = format!("{} {}", event.metadata().level(), message.0);
+                self.0.lock().unwrap().push(line);
+            }
+
+            fn enter(&self, _: &Id) {}
+
+            fn exit(&self, _: &Id) {}
+        }
+
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Recorder(lines.clone()), || {
+            let frame = subsystem::mac::sreq::ResetReq { set_default: true }.into_mt_frame();
+            let bytes = frame.encode_to_uart_transport_frame();
+            let mut parser = crate::parser::Parser::new();
+            assert!(parser.feed_slice(&bytes)[0].is_ok());
+
+            let mut corrupt = bytes.clone();
+            *corrupt.last_mut().unwrap() ^= 0xff;
+            assert!(parser.feed_slice(&corrupt)[0].is_err());
+
+            // A MAC reset SRSP with its status byte missing.
+            let payload = subsystem::MTFramePayload::try_decode(
+                &types::MTSubsystem::MAC,
+                &types::CommandType::SRSP,
+                0x01,
+                &[],
+            );
+            assert!(payload.is_err());
+        });
+
+        let lines = lines.lock().unwrap();
+        assert!(lines.contains(&"span encode_to_uart_transport_frame".to_string()));
+        assert!(lines.contains(&"span try_decode_with".to_string()));
+        assert!(lines.contains(&"TRACE frame received".to_string()));
+        let failures = lines
+            .iter()
+            .filter(|line| line.starts_with("DEBUG decode failed: ["))
+            .count();
+        assert_eq!(failures, 2);
+    }
+
+    #[test]
+    fn annotate_frame_fields() {
+        let request = subsystem::mac::sreq::SetReq {
+            attribute_id: types::MACPIBAttributeId::RxOnWhenIdle,
+            attribute_value: [0x01; 16],
+        }
+        .into_mt_frame();
+        let mut bytes = request.encode_to_uart_transport_frame();
+        let lines: Vec<String> = debug::annotate(&bytes).lines().map(String::from).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "0000  fe                       SOF",
+                "0001  11                       length 17",
+                "0002  22                       cmd0 SREQ MAC",
+                "0003  09                       cmd1 SetReq",
+                "0004  52                       attribute_id",
+                "0005  01 01 01 01 01 01 01 01  attribute_value",
+                "000d  01 01 01 01 01 01 01 01",
+                "decoded: MAC_SetReq_SREQ(SetReq { attribute_id: RxOnWhenIdle, \
+                 attribute_value: [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1] })",
+                "0015  68                       FCS ok",
+            ]
+        );
+
+        let fcs = bytes.len() - 1;
+        bytes[fcs] = 0x00;
+        assert!(debug::annotate(&bytes).ends_with("FCS expected 0x68\n"));
+        assert!(debug::annotate(&bytes[1..fcs]).starts_with("0000  11"));
+    }
+
+    #[test]
+    fn ws_async_channels_are_validated() {
+        use subsystem::mac::{sreq, srsp};
+        use types::{ChannelMask, MACCommandId, MTSubsystem, PhyId, WiSUNAsyncFrameType};
+
+        let request = |frame_type, channels: ChannelMask| sreq::WSAsyncReq {
+            operation: types::WiSUNAsyncOperation::Start,
+            frame_type,
+            security: types::Security::none(),
+            channels,
+        };
+        let mut excluded = ChannelMask::new();
+        excluded.enable(5);
+        let mut client = test_util::ScriptedClient::new();
+        client.respond(MTSubsystem::MAC, MACCommandId::GetReq as u8, |_| {
+            let mut data = [0x00; 16];
+            data[0] = PhyId::STD_ETSI_863_PHY_3.u8();
+            let status = types::Status::Success;
+            Ok(srsp::GetReq { status, data }.into_mt_frame())
+        });
+        client.respond(MTSubsystem::MAC, MACCommandId::FHGetReq as u8, move |_| {
+            let data = excluded.to_bytes().to_vec();
+            let status = types::Status::Success;
+            Ok(srsp::FHGetReq { status, data }.into_mt_frame())
+        });
+        let sent = |client: &test_util::ScriptedClient| {
+            client
+                .command_ids()
+                .into_iter()
+                .filter(|&x| x == MACCommandId::WSAsyncReq as u8)
+                .count()
+        };
+
+        let mut channels = ChannelMask::new();
+        channels.enable_range(0..=4);
+        client::ws_async(
+            &mut client,
+            request(WiSUNAsyncFrameType::PANAdvert, channels),
+        )
+        .unwrap();
+        assert_eq!(sent(&client), 1);
+
+        let phy = PhyId::STD_ETSI_863_PHY_3;
+        let with = |channel| {
+            let mut mask = channels;
+            mask.enable(channel);
+            mask
+        };
+        assert!(matches!(
+            request(WiSUNAsyncFrameType::PANConfig, with(34)).validate(phy, &excluded),
+            Err(error::Error::ChannelOutOfRange(34))
+        ));
+        assert!(matches!(
+            request(WiSUNAsyncFrameType::PANConfig, with(5)).validate(phy, &excluded),
+            Err(error::Error::ExcludedChannel(5))
+        ));
+        assert!(matches!(
+            request(WiSUNAsyncFrameType::Data, channels).validate(phy, &excluded),
+            Err(error::Error::UnsupportedAsyncFrameType(
+                WiSUNAsyncFrameType::Data
+            ))
+        ));
+        assert!(matches!(
+            client::ws_async(
+                &mut client,
+                request(WiSUNAsyncFrameType::PANAdvert, ChannelMask::new())
+            ),
+            Err(error::Error::NoChannels)
+        ));
+        assert_eq!(sent(&client), 1);
+        assert_eq!(PhyId::Other(0x10).channel_count(), None);
+    }
+
+    #[test]
+    fn energy_detect_scan_levels() {
+        use subsystem::mac::areq::ScanCnf;
+        use types::{Channel, ChannelMask, EnergyLevel};
+
+        let mut requested = ChannelMask::new();
+        requested.enable_range(11..=15);
+        let mut unscanned = ChannelMask::new();
+        unscanned.enable(13);
+        let confirm = ScanCnf {
+            status: types::Status::Success,
+            scan_type: types::ScanType::EnergyDetect,
+            channel_page: 0,
+            phy_id: types::PhyId::STD_US_915_PHY_1,
+            unscanned_channels: unscanned,
+            result_list_count: 4,
+            result_list: vec![0x40, 0x10, 0x80, 0x10],
+        };
+
+        assert_eq!(
+            confirm.energy_levels(&requested).unwrap(),
+            vec![
+                (Channel(11), EnergyLevel(0x40)),
+                (Channel(12), EnergyLevel(0x10)),
+                (Channel(14), EnergyLevel(0x80)),
+                (Channel(15), EnergyLevel(0x10)),
+            ]
+        );
+        assert_eq!(
+            confirm.best_channels(&requested, 3).unwrap(),
+            vec![Channel(12), Channel(15), Channel(11)]
+        );
+
+        let active = ScanCnf {
+            scan_type: types::ScanType::Active,
+            ..confirm
+        };
+        assert!(active.energy_levels(&requested).is_none());
+    }
+
+    #[test]
+    fn channel_center_frequencies() {
+        use types::{Channel, PhyId};
+
+        assert_eq!(
+            Channel(0).center_frequency(PhyId::STD_US_915_PHY_1),
+            Some(902_200_000)
+        );
+        assert_eq!(
+            Channel(128).center_frequency(PhyId::GENERIC_US_LRM_915_PHY_129),
+            Some(927_800_000)
+        );
+        assert_eq!(
+            Channel(33).center_frequency(PhyId::STD_ETSI_863_PHY_3),
+            Some(869_725_000)
+        );
+        assert_eq!(
+            Channel(16).center_frequency(PhyId::GENERIC_ETSI_863_PHY_133),
+            Some(869_625_000)
+        );
+        assert_eq!(
+            Channel(7).center_frequency(PhyId::GENERIC_CHINA_433_PHY_128),
+            None
+        );
+        assert_eq!(Channel(0).center_frequency(PhyId::Other(0x10)), None);
+        assert_eq!(Channel::from(11).to_string(), "channel 11");
+    }
+
+    #[test]
+    fn pan_id_constants_and_display() {
+        use types::PanId;
+
+        assert!(PanId::BROADCAST.is_broadcast());
+        assert!(!PanId::BROADCAST.is_valid());
+        assert!(PanId(0xabcd).is_valid());
+        assert_eq!(PanId::from(0x00ab).to_string(), "0x00ab");
+        assert_eq!(u16::from(PanId(0xabcd)), 0xabcd);
+
+        let mut buffer = Vec::new();
+        PanId(0xabcd).encode_into(&mut buffer);
+        assert_eq!(buffer, [0xcd, 0xab]);
+        let decoded = PanId::try_decode(&mut Reader::new(&buffer[..])).unwrap();
+        assert_eq!(decoded, PanId(0xabcd));
+    }
+
+    #[test]
+    fn pib_config_reports_failing_attribute() {
+        use pib_config::PibConfig;
+        use types::{MACPIBAttributeId, PanId, PibAttribute};
+
+        let config = PibConfig::new()
+            .pan_id(PanId(0x1234))
+            .channel(3)
+            .rx_on_idle(true)
+            .association_permit(true);
+        assert_eq!(config.len(), 4);
+
+        let mut client = MockClient {
+            pib: HashMap::new(),
+            failing_attribute: Some(MACPIBAttributeId::RxOnWhenIdle),
+        };
+        let error = config.apply(&mut client).unwrap_err();
+        assert!(matches!(
+            error,
+            error::Error::PibWriteFailed {
+                attribute: PibAttribute::Mac(MACPIBAttributeId::RxOnWhenIdle),
+                ..
+            }
+        ));
+        assert!(matches!(
+            error.kind(),
+            error::Error::CommandFailed(types::Status::UnsupportedAttribute)
+        ));
+        assert_eq!(
+            error.to_string(),
+            "setting MAC PIB RxOnWhenIdle failed: command failed with status UnsupportedAttribute (PIB attribute not supported)"
+        );
+        assert_eq!(
+            client.pib[&MACPIBAttributeId::PANId.raw()][..2],
+            [0x34, 0x12]
+        );
+        assert_eq!(client.pib[&MACPIBAttributeId::LogicalChannel.raw()][0], 3);
+        assert!(!client
+            .pib
+            .contains_key(&MACPIBAttributeId::AssociationPermit.raw()));
+
+        client.failing_attribute = None;
+        config.apply(&mut client).unwrap();
+        assert_eq!(
+            client.pib[&MACPIBAttributeId::AssociationPermit.raw()][0],
+            1
+        );
+    }
+
+    #[test]
+    fn coordinator_resolves_pan_id_conflict() {
+        use coordinator::{Coordinator, CoordinatorConfig, CoordinatorEvent, JoinDecision};
+        use subsystem::mac::{areq, sreq};
+        use types::{PanId, Status};
+
+        let mut coordinator = Coordinator::new(
+            test_util::ScriptedClient::new(),
+            CoordinatorConfig::default(),
+            |_: &areq::AssociateInd| JoinDecision::Accept,
+        );
+        let conflict = areq::SyncLossInd {
+            status: Status::PANIdConflict,
+            pan_id: PanId(0x1234),
+            logical_channel: 0,
+            channel_page: 9,
+            phy_id: types::PhyId::STD_US_915_PHY_1,
+            security: types::Security::none(),
+        }
+        .into_mt_frame();
+        assert!(matches!(
+            coordinator.handle(&conflict).unwrap(),
+            CoordinatorEvent::PanIdConflict(PanId(0x1234))
+        ));
+
+        coordinator.update_pan_id(PanId(0x4321)).unwrap();
+        let request = &coordinator.client().requests()[0];
+        let request = sreq::UpdatePANIdReq::try_decode(&request.payload).unwrap();
+        assert_eq!(request.pan_id, PanId(0x4321));
+
+        let address = types::Address::Addr16Bit(types::ShortAddress::from(0x0001));
+        coordinator.send(address, vec![0x01]).unwrap();
+        let request = coordinator.client().requests().last().unwrap();
+        let request = sreq::DataReq::try_decode(&request.payload).unwrap();
+        assert_eq!(request.dest_pan_id, PanId(0x4321));
+    }
+
+    #[test]
+    fn coordinator_realigns_after_pan_id_conflict() {
+        use coordinator::{Coordinator, CoordinatorConfig, JoinDecision};
+        use subsystem::mac::{areq, sreq};
+        use types::{MACCommandId, PanId};
+
+        let mut coordinator = Coordinator::new(
+            test_util::ScriptedClient::new(),
+            CoordinatorConfig::default(),
+            |_: &areq::AssociateInd| JoinDecision::Accept,
+        );
+        assert!(matches!(
+            coordinator.resolve_pan_id_conflict(|current| current),
+            Err(error::Error::InvalidPanId(PanId(0x1234)))
+        ));
+        assert!(coordinator
+            .resolve_pan_id_conflict(|_| PanId::BROADCAST)
+            .is_err());
+        assert!(coordinator.client().requests().is_empty());
+
+        let pan_id = coordinator
+            .resolve_pan_id_conflict(|current| PanId(current.u16() + 1))
+            .unwrap();
+        assert_eq!(pan_id, PanId(0x1235));
+
+        let requests = coordinator.client().requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].header.command.id,
+            MACCommandId::UpdatePANIdReq as u8
+        );
+        let start = sreq::StartReq::try_decode(&requests[1].payload).unwrap();
+        assert!(start.coord_realignment);
+        assert_eq!(start.pan_id, PanId(0x1235));
+    }
+
+    #[test]
+    fn app_msg_round_trip() {
+        use subsystem::app::{areq, sreq};
+        use types::*;
+
+        let request = sreq::AppMsgReq::new(
+            0x08,
+            ShortAddress::from(0x0001),
+            0x02,
+            0x0006,
+            vec![0xde, 0xad],
+        );
+        let bytes = request.into_mt_frame().encode_to_uart_transport_frame();
+        assert_eq!(
+            bytes,
+            [0xfe, 0x09, 0x29, 0x00, 0x08, 0x01, 0x00, 0x02, 0x06, 0x00, 0x02, 0xde, 0xad, 0x5c]
+        );
+
+        let mut cursor = Reader::new(&bytes[1..bytes.len() - 1]);
+        let frame = frame::MTFrame::try_decode(&mut cursor).unwrap();
+        let request = match subsystem::MTFramePayload::try_from_frame(&frame).unwrap() {
+            subsystem::MTFramePayload::APP_AppMsgReq_SREQ(x) => x,
+            payload => panic!("unexpected {:?}", payload),
+        };
+        assert_eq!(request.dest_address, ShortAddress::from(0x0001));
+        assert_eq!(request.cluster_id, 0x0006);
+        assert_eq!(request.data, vec![0xde, 0xad]);
+
+        let indication = [0x08, 0x01, 0x00, 0x02, 0x06, 0x00, 0x01, 0x7f];
+        let payload = subsystem::MTFramePayload::try_decode(
+            &MTSubsystem::APP,
+            &CommandType::AREQ,
+            APPCommandId::AppMsg as u8,
+            &indication,
+        )
+        .unwrap();
+        match payload {
+            subsystem::MTFramePayload::APP_AppMsgInd_AREQ(areq::AppMsgInd {
+                src_endpoint,
+                data,
+                ..
+            }) => {
+                assert_eq!(src_endpoint, 0x02);
+                assert_eq!(data, vec![0x7f]);
+            }
+            payload => panic!("unexpected {:?}", payload),
+        }
+    }
+
+    #[test]
+    fn link_probe_measures_round_trips() {
+        use client::Client;
+        use link_probe::{LinkProbe, LinkProbeConfig, ProbeMethod};
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use subsystem::{sys, util};
+
+        // Echoes loopbacks, with a corrupted payload once `corrupt` is set.
+        #[derive(Default)]
+        struct EchoClient {
+            corrupt: bool,
+        }
+
+        impl Client for EchoClient {
+            fn request(&mut self, frame: frame::MTFrame) -> Result<frame::MTFrame, error::Error> {
+                if frame.header.command.subsystem == types::MTSubsystem::SYS {
+                    return Ok(sys::srsp::PingReq { capabilities: 0 }.into_mt_frame());
+                }
+                let request = util::sreq::Loopback::try_decode(&frame.payload)?;
+                let mut data = request.data;
+                if self.corrupt {
+                    data[0] ^= 0xff;
+                }
+                Ok(util::srsp::Loopback {
+                    repeats: request.repeats,
+                    interval: request.interval,
+                    data,
+                }
+                .into_mt_frame())
+            }
+        }
+
+        let failures = Rc::new(RefCell::new(Vec::new()));
+        let mut probe = LinkProbe::new(LinkProbeConfig {
+            max_failures: 2,
+            ..LinkProbeConfig::default()
+        });
+        let seen = failures.clone();
+        probe.on_failure(move |_, health| seen.borrow_mut().push(health.consecutive_failures));
+
+        let mut client = EchoClient::default();
+        let start = Instant::now();
+        assert!(probe.poll(&mut client, start).unwrap().is_ok());
+        assert!(probe
+            .poll(&mut client, start + Duration::from_secs(1))
+            .is_none());
+        assert!(probe.health().last_rtt.is_some());
+
+        client.corrupt = true;
+        for _ in 0..2 {
+            assert!(matches!(
+                probe.probe(&mut client),
+                Err(error::Error::UnexpectedResponse(_))
+            ));
+        }
+        assert!(!probe.is_up());
+        assert_eq!(*failures.borrow(), vec![1, 2]);
+
+        let mut ping = LinkProbe::new(LinkProbeConfig {
+            method: ProbeMethod::Ping,
+            ..LinkProbeConfig::default()
+        });
+        assert!(ping.probe(&mut client).is_ok());
+        assert_eq!(ping.health().probes, 1);
+        assert!(ping.is_up());
+    }
+
+    #[test]
+    fn frame_counter_warns_before_rollover() {
+        use frame_counter::{FrameCounterConfig, FrameCounterMonitor};
+        use subsystem::mac::srsp;
+        use types::Status;
+
+        fn response(frame_counter: u32) -> srsp::SecurityGetReq {
+            srsp::SecurityGetReq {
+                status: Status::Success,
+                index1: 0,
+                index2: 0,
+                data: frame_counter.to_le_bytes().to_vec(),
+            }
+        }
+
+        let mut monitor = FrameCounterMonitor::new(FrameCounterConfig {
+            interval: Duration::from_secs(60),
+            margin: 1000,
+            horizon: Duration::from_secs(3600),
+        });
+        let start = Instant::now();
+        assert_eq!(monitor.observe(&response(0), start).unwrap(), None);
+        assert_eq!(monitor.time_to_rollover(), None);
+
+        // 100 frames per second leave about 497 days.
+        let now = start + Duration::from_secs(60);
+        assert_eq!(monitor.observe(&response(6000), now).unwrap(), None);
+        assert_eq!(monitor.rate(), Some(100.0));
+        assert!(monitor.time_to_rollover().unwrap() > Duration::from_secs(400 * 24 * 3600));
+
+        // Inside the horizon, warned once.
+        let counter = u32::MAX - 300_000;
+        let now = now + Duration::from_secs(60);
+        let warning = monitor.observe(&response(counter), now).unwrap().unwrap();
+        assert_eq!(warning.remaining, 300_000);
+        assert!(warning.time_left.unwrap() < Duration::from_secs(3600));
+        let now = now + Duration::from_secs(60);
+        assert_eq!(monitor.observe(&response(counter + 10), now).unwrap(), None);
+
+        // After rotation the counter restarts and the warning can fire again.
+        let now = now + Duration::from_secs(60);
+        assert_eq!(monitor.observe(&response(5), now).unwrap(), None);
+        let now = now + Duration::from_secs(60);
+        assert!(monitor
+            .observe(&response(u32::MAX - 10), now)
+            .unwrap()
+            .is_some());
+
+        let mut failed = response(0);
+        failed.status = Status::Unsupported;
+        assert!(matches!(
+            monitor.observe(&failed, now),
+            Err(error::Error::CommandFailed(Status::Unsupported))
+        ));
+    }
+
+    #[test]
+    fn fh_data_confirm_names_the_destination() {
+        use coordinator::{Coordinator, CoordinatorConfig, CoordinatorEvent, JoinDecision};
+        use error::FhError;
+        use subsystem::mac::areq;
+        use types::{Address, ExtendedAddress, Lqi, Rssi, ShortAddress, Status};
+
+        let confirm = |status, handle| {
+            areq::DataCnf {
+                status,
+                handle,
+                timestamp: 0,
+                timestamp2: 0,
+                retries: 0,
+                link_quality: Lqi(0),
+                correlation: 0,
+                rssi: Rssi(0),
+                frame_counter: 0,
+            }
+            .into_mt_frame()
+        };
+
+        let mut coordinator = Coordinator::new(
+            test_util::ScriptedClient::new(),
+            CoordinatorConfig::default(),
+            |_: &areq::AssociateInd| JoinDecision::Accept,
+        );
+        let neighbor = ExtendedAddress::from(0x0012_4b00_0000_0001);
+        let handle = coordinator
+            .send(Address::Addr64Bit(neighbor), vec![0x01])
+            .unwrap();
+        match coordinator
+            .handle(&confirm(Status::FHAPINoEntryInTheNeighbor, handle))
+            .unwrap()
+        {
+            CoordinatorEvent::FhError { handle: x, error } => {
+                assert_eq!(x, handle);
+                assert_eq!(error, FhError::NoNeighbor(neighbor));
+                assert_eq!(error.status(), Status::FHAPINoEntryInTheNeighbor);
+            }
+            event => panic!("unexpected {:?}", event),
+        }
+
+        // Short destinations and other failures keep their status.
+        let short = Address::Addr16Bit(ShortAddress::from(0x0002));
+        let handle = coordinator.send(short, vec![0x02]).unwrap();
+        match coordinator
+            .handle(&confirm(Status::FHAPINoEntryInTheNeighbor, handle))
+            .unwrap()
+        {
+            CoordinatorEvent::FhError { error, .. } => assert_eq!(error.destination(), short),
+            event => panic!("unexpected {:?}", event),
+        }
+        let handle = coordinator.send(short, vec![0x03]).unwrap();
+        assert!(matches!(
+            coordinator.handle(&confirm(Status::NoAck, handle)).unwrap(),
+            CoordinatorEvent::DataCnf(_)
+        ));
+        assert_eq!(FhError::from_status(Status::NoAck, short), None);
+
+        let mut pending = client::PendingData::new();
+        assert!(matches!(
+            pending.confirm(
+                &areq::DataCnf::try_decode(&confirm(Status::FHAPIExpiredNode, 7).payload).unwrap()
+            ),
+            Err(error::Error::CommandFailed(Status::FHAPIExpiredNode))
+        ));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn fan_join_state_machine() {
+        use fan::{Fan, FanConfig, FanEvent, FanState};
+        use ie::{PayloadIe, WpIe};
+        use subsystem::mac::{areq, sreq};
+        use types::*;
+
+        let indication = |frame_type, address: u16, net_name: &str, routing_cost: u16| {
+            let mut ie_payload = Vec::new();
+            PayloadIe::wisun(&[
+                WpIe::NetName(net_name.as_bytes().to_vec()),
+                WpIe::Pan {
+                    pan_size: 10,
+                    routing_cost,
+                    use_parent_bs_ie: true,
+                    routing_method: true,
+                    fan_tps_version: 1,
+                },
+                WpIe::PanVersion(7),
+            ])
+            .unwrap()
+            .encode_into(&mut ie_payload)
+            .unwrap();
+            areq::WSAsyncInd {
+                src_address: Address::Addr16Bit(ShortAddress::from(address)),
+                dest_address: Address::None,
+                timestamp: 0,
+                timestamp2: 0,
+                src_pan_id: PanId(address),
+                dest_pan_id: PanId::BROADCAST,
+                link_quality: Lqi(0xff),
+                correlation: 0,
+                rssi: Rssi(-60),
+                dsn: 0,
+                security: Security::none(),
+                frame_counter: 0,
+                frame_type,
+                data_length: 0,
+                ie_length: ie_payload.len() as u16,
+                data_payload: Vec::new(),
+                ie_payload,
+            }
+            .into_mt_frame()
+        };
+
+        let config = FanConfig::new(NetName::new("wisun").unwrap());
+        let period = config.discovery_period;
+        let mut fan = Fan::new(test_util::ScriptedClient::new(), config);
+        let start = Instant::now();
+        assert!(matches!(
+            fan.start(start).unwrap(),
+            FanEvent::StateChanged {
+                from: FanState::Idle,
+                to: FanState::Discovery
+            }
+        ));
+
+        // Nothing heard yet, the solicit is repeated.
+        assert!(fan.poll(start + period).unwrap().is_none());
+        let now = start + period;
+
+        let advert = WiSUNAsyncFrameType::PANAdvert;
+        assert!(matches!(
+            fan.handle(&indication(advert, 0x0001, "other", 0)).unwrap(),
+            FanEvent::Other(_)
+        ));
+        assert!(matches!(
+            fan.handle(&indication(advert, 0x0002, "wisun", 300))
+                .unwrap(),
+            FanEvent::PanHeard(_)
+        ));
+        fan.handle(&indication(advert, 0x0003, "wisun", 100))
+            .unwrap();
+        assert_eq!(fan.candidates().len(), 2);
+
+        // The cheapest route wins.
+        let now = now + period;
+        fan.poll(now).unwrap().unwrap();
+        let pan = match fan.state() {
+            FanState::ConfigSolicit { pan, attempts: 1 } => pan.clone(),
+            state => panic!("unexpected {:?}", state),
+        };
+        assert_eq!(pan.pan_id, PanId(0x0003));
+        assert_eq!(pan.routing_cost, Some(100));
+
+        // Configurations from other PANs are not taken.
+        let config_frame = WiSUNAsyncFrameType::PANConfig;
+        assert!(matches!(
+            fan.handle(&indication(config_frame, 0x0002, "wisun", 300))
+                .unwrap(),
+            FanEvent::Other(_)
+        ));
+        match fan
+            .handle(&indication(config_frame, 0x0003, "wisun", 100))
+            .unwrap()
+        {
+            FanEvent::StateChanged {
+                to: FanState::AuthenticationReady { pan_version, .. },
+                ..
+            } => assert_eq!(pan_version, Some(7)),
+            event => panic!("unexpected {:?}", event),
+        }
+        let set = fan.client().decoded(
+            MTSubsystem::MAC,
+            MACCommandId::SetReq as u8,
+            sreq::SetReq::try_decode,
+        );
+        assert_eq!(set.last().unwrap().attribute_value[..2], [0x03, 0x00]);
+        assert!(fan.poll(now + period).unwrap().is_none());
+        assert_eq!(
+            fan.client()
+                .decoded(
+                    MTSubsystem::MAC,
+                    MACCommandId::WSAsyncReq as u8,
+                    sreq::WSAsyncReq::try_decode,
+                )
+                .into_iter()
+                .map(|x| x.frame_type)
+                .collect::<Vec<_>>(),
+            vec![
+                WiSUNAsyncFrameType::PANAdvertSOL,
+                WiSUNAsyncFrameType::PANAdvertSOL,
+                WiSUNAsyncFrameType::PANConfigSOL
+            ]
+        );
+    }
+
+    #[test]
+    fn fan_border_router_trickle() {
+        use fan::{BorderRouter, BorderRouterConfig, BorderRouterEvent, Trickle, TrickleConfig};
+        use subsystem::mac::{areq, sreq};
+        use types::*;
+
+        // Without consistent transmissions heard the timer fires once per
+        // interval, and the interval doubles up to imin * 4.
+        let config = TrickleConfig::default();
+        let start = Instant::now();
+        let second = Duration::from_secs(1);
+        let mut trickle = Trickle::new(config, 1, start);
+        let fired = (1..=105)
+            .filter(|x| trickle.poll(start + second * *x))
+            .count();
+        assert_eq!(fired, 3);
+        assert_eq!(trickle.interval(), Duration::from_secs(60));
+
+        let now = start + second * 105;
+        trickle.reset(now);
+        assert_eq!(trickle.interval(), config.imin);
+        trickle.hear_consistent();
+        assert!(!(1..=15).any(|x| trickle.poll(now + second * x)));
+
+        let fh_writes = |client: &test_util::ScriptedClient| {
+            client
+                .decoded(
+                    MTSubsystem::MAC,
+                    MACCommandId::FHSetReq as u8,
+                    sreq::FHSetReq::try_decode,
+                )
+                .into_iter()
+                .map(|x| (x.attribute_id, x.data))
+                .collect::<Vec<_>>()
+        };
+
+        let mut router = BorderRouter::new(
+            test_util::ScriptedClient::new(),
+            BorderRouterConfig::new(NetName::new("wisun").unwrap()),
+            start,
+        );
+        router.start().unwrap();
+        let attributes: Vec<_> = fh_writes(router.client())
+            .into_iter()
+            .map(|(attribute_id, _)| attribute_id)
+            .collect();
+        assert_eq!(
+            attributes,
+            vec![
+                FHPIBAttributeId::NetName,
+                FHPIBAttributeId::PANSize,
+                FHPIBAttributeId::RoutingCost,
+                FHPIBAttributeId::PANVersion
+            ]
+        );
+
+        let sent: Vec<_> = (1..=15)
+            .flat_map(|x| router.poll(start + second * x).unwrap())
+            .collect();
+        assert_eq!(
+            sent,
+            vec![
+                WiSUNAsyncFrameType::PANAdvert,
+                WiSUNAsyncFrameType::PANConfig
+            ]
+        );
+
+        // A new GTK is written and announced with the next PAN version.
+        let written = fh_writes(router.client()).len();
+        let hash = GtkHash([0x11; 8]);
+        assert_eq!(router.set_gtk_hash(1, hash, start).unwrap(), 1);
+        assert_eq!(
+            fh_writes(router.client())[written..],
+            [
+                (FHPIBAttributeId::GTK1Hash, vec![0x11; 8]),
+                (FHPIBAttributeId::PANVersion, vec![0x01, 0x00])
+            ]
+        );
+        assert_eq!(router.gtk_hashes()[1], hash);
+        assert!(router.set_gtk_hash(4, hash, start).is_err());
+
+        let solicit = areq::WSAsyncInd {
+            src_address: Address::Addr16Bit(ShortAddress::from(0x0002)),
+            dest_address: Address::None,
+            timestamp: 0,
+            timestamp2: 0,
+            src_pan_id: PanId::BROADCAST,
+            dest_pan_id: PanId::BROADCAST,
+            link_quality: Lqi(0xff),
+            correlation: 0,
+            rssi: Rssi(-60),
+            dsn: 0,
+            security: Security::none(),
+            frame_counter: 0,
+            frame_type: WiSUNAsyncFrameType::PANConfigSOL,
+            data_length: 0,
+            ie_length: 0,
+            data_payload: Vec::new(),
+            ie_payload: Vec::new(),
+        };
+        assert!(matches!(
+            router.handle(&solicit.into_mt_frame(), start).unwrap(),
+            BorderRouterEvent::Solicited {
+                frame_type: WiSUNAsyncFrameType::PANConfigSOL,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn eapol_transport_round_trip() {
+        use eapol::{Authenticator, EapolFrame, EapolTransport};
+        use subsystem::mac::{areq, sreq};
+        use subsystem::MTFramePayload;
+        use types::*;
+
+        // Answers every PDU with its reverse.
+        struct Reverse(Vec<EapolFrame>);
+
+        impl Authenticator for Reverse {
+            fn receive(&mut self, frame: EapolFrame) -> Vec<EapolFrame> {
+                self.0.push(frame.clone());
+                let mut pdu = frame.pdu;
+                pdu.reverse();
+                vec![EapolFrame { pdu, ..frame }]
+            }
+        }
+
+        let node = ExtendedAddress::from(0x0012_4b00_0000_0001);
+        let border_router = ExtendedAddress::from(0x0012_4b00_0000_00ff);
+        let request = EapolFrame {
+            peer: node,
+            authenticator: Some(border_router),
+            pdu: vec![0x02, 0x00, 0x00, 0x05],
+        }
+        .into_data_req(PanId(0x1234), 7)
+        .unwrap();
+        assert_eq!(request.dest_address, Address::Addr64Bit(node));
+        assert_eq!(request.fh_ies(), FHIncludeIe::US);
+
+        let data_ind = |ie_payload: Vec<u8>| areq::DataInd {
+            src_address: Address::Addr64Bit(node),
+            dest_address: Address::Addr64Bit(border_router),
+            timestamp: 0,
+            timestamp2: 0,
+            src_pan_id: PanId(0x1234),
+            dest_pan_id: PanId(0x1234),
+            link_quality: Lqi(0xff),
+            correlation: 0,
+            rssi: Rssi(-50),
+            dsn: 0,
+            security: Security::none(),
+            frame_counter: 0,
+            data_length: request.data_length,
+            ie_length: ie_payload.len() as u16,
+            data_payload: request.data_payload.clone(),
+            ie_payload,
+        };
+        let indication = data_ind(request.ie_payload.clone());
+        let frame = EapolFrame::from_data_ind(&indication).unwrap();
+        assert_eq!(frame.peer, node);
+        assert_eq!(frame.authenticator, Some(border_router));
+        assert_eq!(frame.pdu, vec![0x02, 0x00, 0x00, 0x05]);
+
+        let plain = data_ind(Vec::new());
+        assert_eq!(EapolFrame::from_data_ind(&plain), None);
+
+        let mut transport = EapolTransport::new(test_util::ScriptedClient::new(), PanId(0x1234));
+        let mut authenticator = Reverse(Vec::new());
+        assert!(transport
+            .handle(
+                &MTFramePayload::MAC_DataInd_AREQ(indication),
+                &mut authenticator
+            )
+            .unwrap());
+        assert!(!transport
+            .handle(&MTFramePayload::MAC_DataInd_AREQ(plain), &mut authenticator)
+            .unwrap());
+        assert_eq!(authenticator.0.len(), 1);
+
+        let sent = transport.client().decoded(
+            MTSubsystem::MAC,
+            MACCommandId::DataReq as u8,
+            sreq::DataReq::try_decode,
+        );
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].data_payload, vec![0x05, 0x00, 0x00, 0x02]);
+        assert_eq!(sent[0].dest_address, Address::Addr64Bit(node));
+    }
+
+    #[test]
+    fn fh_schedule_read_and_write() {
+        use subsystem::mac::{sreq, srsp};
+        use types::{ChannelFunction, FHPIBAttributeId, FhSchedule, MACCommandId, Status};
+
+        #[derive(Default)]
+        struct PibClient {
+            pib: HashMap<u16, Vec<u8>>,
+            writes: Vec<FHPIBAttributeId>,
+        }
+
+        impl client::Client for PibClient {
+            fn request(&mut self, frame: frame::MTFrame) -> Result<frame::MTFrame, error::Error> {
+                if frame.header.command.id == MACCommandId::FHSetReq as u8 {
+                    let request = sreq::FHSetReq::try_decode(&frame.payload)?;
+                    self.writes.push(request.attribute_id);
+                    self.pib.insert(request.attribute_id.raw(), request.data);
+                    let status = Status::Success;
+                    return Ok(srsp::FHSetReq { status }.into_mt_frame());
+                }
+                let request = sreq::FHGetReq::try_decode(&frame.payload)?;
+                Ok(srsp::FHGetReq {
+                    status: Status::Success,
+                    data: self.pib[&request.attribute_id.raw()].clone(),
+                }
+                .into_mt_frame())
+            }
+        }
+
+        let hopping = FhSchedule {
+            uc_dwell_interval: 255,
+            uc_channel_function: ChannelFunction::DH1CF,
+            uc_fixed_channel: None,
+            bc_interval: 1020,
+            bc_dwell_interval: 255,
+            bc_channel_function: ChannelFunction::DH1CF,
+            bc_fixed_channel: None,
+        };
+        let mut pib_client = PibClient::default();
+        client::write_schedule(&mut pib_client, &hopping).unwrap();
+        assert_eq!(pib_client.writes.len(), 5);
+        pib_client
+            .pib
+            .insert(FHPIBAttributeId::UCFixedChannel.raw(), vec![0x00, 0x00]);
+        pib_client
+            .pib
+            .insert(FHPIBAttributeId::BCFixedChannel.raw(), vec![0x00, 0x00]);
+        assert_eq!(client::read_schedule(&mut pib_client).unwrap(), hopping);
+
+        // Fixed channels go out before the channel functions.
+        let fixed = FhSchedule {
+            uc_channel_function: ChannelFunction::Fixed,
+            uc_fixed_channel: Some(12),
+            ..hopping
+        };
+        pib_client.writes.clear();
+        client::write_schedule(&mut pib_client, &fixed).unwrap();
+        assert_eq!(
+            pib_client.writes[3..],
+            [
+                FHPIBAttributeId::UCFixedChannel,
+                FHPIBAttributeId::UCChannelFunction,
+                FHPIBAttributeId::BCChannelFunction,
+            ]
+        );
+        assert_eq!(client::read_schedule(&mut pib_client).unwrap(), fixed);
+
+        let invalid = [
+            FhSchedule {
+                uc_fixed_channel: None,
+                ..fixed
+            },
+            FhSchedule {
+                bc_fixed_channel: Some(3),
+                ..hopping
+            },
+            FhSchedule {
+                uc_dwell_interval: 0,
+                ..hopping
+            },
+            FhSchedule {
+                bc_interval: 100,
+                ..hopping
+            },
+        ];
+        pib_client.writes.clear();
+        for schedule in invalid.iter() {
+            assert!(matches!(
+                client::write_schedule(&mut pib_client, schedule),
+                Err(error::Error::InvalidFhSchedule(_))
+            ));
+        }
+        assert!(pib_client.writes.is_empty());
+
+        // Broadcast disabled, only the unicast schedule matters.
+        let unicast_only = FhSchedule {
+            bc_interval: 0,
+            bc_dwell_interval: 0,
+            ..hopping
+        };
+        assert!(unicast_only.validate().is_ok());
+    }
+
+    #[test]
+    fn address_book_persists() {
+        let mut book = address_book::AddressBook::new();
+        book.insert(
+            types::ExtendedAddress::from(0x0012_4b00_0000_0002),
+            types::ShortAddress::from(0x0002),
+        );
+        book.insert(
+            types::ExtendedAddress::from(0x0012_4b00_0000_0001),
+            types::ShortAddress::from(0x0001),
+        );
+
+        let encoded = book.encode();
+        assert_eq!(encoded.len(), 2 + 2 * 10);
+        assert_eq!(&encoded[..4], &[0x02, 0x00, 0x01, 0x00]);
+
+        let restored = address_book::AddressBook::try_decode(&encoded).unwrap();
+        assert_eq!(restored.entries(), book.entries());
+        assert_eq!(
+            restored.extended_address(&types::ShortAddress::from(0x0002)),
+            Some(types::ExtendedAddress::from(0x0012_4b00_0000_0002))
+        );
+
+        assert!(matches!(
+            address_book::AddressBook::try_decode(&encoded[..encoded.len() - 1]),
+            Err(error::Error::Decode {
+                field: "short_address",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn indirect_queue_tracks_expiry() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use subsystem::mac::{areq, sreq};
+        use types::*;
+
+        let sleepy = Address::Addr16Bit(ShortAddress::from(0x0001));
+        let other = Address::Addr16Bit(ShortAddress::from(0x0002));
+        let request = |handle, destination, tx_option| sreq::DataReq {
+            dest_address: destination,
+            dest_pan_id: PanId(0x1234),
+            src_address_mode: AddressMode::Addr16Bit,
+            handle,
+            tx_option,
+            channel: 0,
+            power: 0,
+            security: Security::none(),
+            include_fh_ies: 0,
+            data_length: 1,
+            ie_length: 0,
+            data_payload: vec![handle],
+            ie_payload: Vec::new(),
+        };
+        let confirm = |handle, status| areq::DataCnf {
+            status,
+            handle,
+            timestamp: 0,
+            timestamp2: 0,
+            retries: 0,
+            link_quality: Lqi(0),
+            correlation: 0,
+            rssi: Rssi(0),
+            frame_counter: 0,
+        };
+
+        let expired = Rc::new(RefCell::new(Vec::new()));
+        let mut queue = indirect::IndirectQueue::new();
+        let seen = expired.clone();
+        queue.on_expiry(move |status, data| seen.borrow_mut().push((status, data.handle)));
+
+        let indirect = TxOption::ACK | TxOption::INDIRECT;
+        assert!(queue.sent(&request(1, sleepy, indirect)));
+        assert!(queue.sent(&request(2, sleepy, indirect)));
+        assert!(queue.sent(&request(3, sleepy, indirect)));
+        assert!(queue.sent(&request(4, other, indirect)));
+        assert!(!queue.sent(&request(5, other, TxOption::ACK)));
+        assert_eq!(queue.depth(&sleepy), 3);
+        assert_eq!(queue.depth(&other), 1);
+
+        assert_eq!(queue.confirm(&confirm(5, Status::Success)), None);
+        let data = queue.confirm(&confirm(1, Status::Success)).unwrap();
+        assert_eq!(data.data, vec![1]);
+        queue.confirm(&confirm(2, Status::TransactionExpired));
+        queue.confirm(&confirm(4, Status::TransactionOverflow));
+        assert_eq!(
+            *expired.borrow(),
+            [
+                (Status::TransactionExpired, 2),
+                (Status::TransactionOverflow, 4)
+            ]
+        );
+        assert_eq!(queue.depth(&sleepy), 1);
+
+        let purges = queue.purge_requests(&sleepy);
+        assert_eq!(purges.len(), 1);
+        assert_eq!(purges[0].handle, 3);
+        let purged = areq::PurgeCnf {
+            status: Status::InvalidHandle,
+            handle: 3,
+        };
+        assert_eq!(queue.purged(&purged), None);
+        let purged = areq::PurgeCnf {
+            status: Status::Success,
+            handle: 3,
+        };
+        assert_eq!(queue.purged(&purged).unwrap().handle, 3);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn handle_allocator_skips_unconfirmed_handles() {
+        use subsystem::mac::areq;
+
+        let mut handles = client::HandleAllocator::new();
+        for context in 0..=255u16 {
+            assert_eq!(handles.allocate(context).unwrap() as u16, context);
+        }
+        assert!(matches!(
+            handles.allocate(256),
+            Err(error::Error::TableFull)
+        ));
+
+        let confirm = areq::DataCnf {
+            status: types::Status::Success,
+            handle: 0x10,
+            timestamp: 0,
+            timestamp2: 0,
+            retries: 0,
+            link_quality: types::Lqi(0),
+            correlation: 0,
+            rssi: types::Rssi(0),
+            frame_counter: 0,
+        };
+        assert_eq!(handles.confirm(&confirm), Some(0x10));
+        assert_eq!(handles.confirm(&confirm), None);
+        assert_eq!(handles.release(0x20), Some(0x20));
+
+        // Only the freed handles come back, in order.
+        assert_eq!(handles.allocate(1000).unwrap(), 0x10);
+        assert_eq!(handles.get(0x10), Some(&1000));
+        assert_eq!(handles.allocate(1001).unwrap(), 0x20);
+        assert_eq!(handles.len(), 256);
+    }
+
+    #[test]
+    fn duplicate_filter_drops_retries() {
+        use subsystem::mac::areq;
+        use subsystem::MTFramePayload;
+        use types::*;
+
+        let indication = |source: u16, dsn| areq::DataInd {
+            src_address: Address::Addr16Bit(ShortAddress::from(source)),
+            dest_address: Address::Addr16Bit(ShortAddress::from(0x0000)),
+            timestamp: 0,
+            timestamp2: 0,
+            src_pan_id: PanId(0x1234),
+            dest_pan_id: PanId(0x1234),
+            link_quality: Lqi(0xff),
+            correlation: 0,
+            rssi: Rssi(-40),
+            dsn,
+            security: Security::none(),
+            frame_counter: 0,
+            data_length: 1,
+            ie_length: 0,
+            data_payload: vec![dsn],
+            ie_payload: Vec::new(),
+        };
+
+        let start = Instant::now();
+        let mut filter = dedupe::DuplicateFilter::new(Duration::from_secs(2));
+        assert!(!filter.is_duplicate(&indication(1, 7), start));
+        assert!(!filter.is_duplicate(&indication(2, 7), start));
+        assert!(!filter.is_duplicate(&indication(1, 8), start));
+        assert!(filter.is_duplicate(&indication(1, 7), start + Duration::from_secs(1)));
+        assert_eq!(filter.duplicates(), 1);
+        assert_eq!(filter.len(), 3);
+
+        // Past the window the DSN may legitimately come around again.
+        let later = start + Duration::from_secs(3);
+        assert!(!filter.is_duplicate(&indication(1, 7), later));
+        assert_eq!(filter.len(), 1);
+
+        let payload = MTFramePayload::MAC_DataInd_AREQ(indication(1, 7));
+        assert!(filter.filter(payload, later).is_none());
+        let payload = MTFramePayload::MAC_DataInd_AREQ(indication(1, 9));
+        assert!(filter.filter(payload, later).is_some());
+        assert_eq!(filter.duplicates(), 2);
+    }
+
+    #[test]
+    fn fragment_and_reassemble() {
+        use fragment::{Fragmenter, Reassembler};
+        use types::{Address, ShortAddress};
+
+        let payload: Vec<u8> = (0..100).collect();
+        let mut fragmenter = Fragmenter::new(40);
+        let fragments = fragmenter.split(&payload).unwrap();
+        // 32 bytes after the first header, 32 after each following one.
+        assert_eq!(
+            fragments.iter().map(Vec::len).collect::<Vec<_>>(),
+            [36, 37, 37, 9]
+        );
+        assert_eq!(&fragments[0][..4], &[0xc0, 100, 0x00, 0x00]);
+        assert_eq!(&fragments[1][..5], &[0xe0, 100, 0x00, 0x00, 4]);
+        assert!(fragments.iter().all(|fragment| fragment.len() <= 40));
+
+        // Small payloads go out untouched, unless they look like a fragment.
+        assert_eq!(fragmenter.split(&[0x01, 0x02]).unwrap(), [vec![0x01, 0x02]]);
+        assert_eq!(
+            fragmenter.split(&[0xc1]).unwrap(),
+            [vec![0xc0, 0x01, 0x00, 0x01, 0xc1]]
+        );
+        assert!(matches!(
+            fragmenter.split(&[0x00; 3000]),
+            Err(error::Error::PayloadTooLarge(3000))
+        ));
+
+        let start = Instant::now();
+        let source = Address::Addr16Bit(ShortAddress::from(0x0001));
+        let other = Address::Addr16Bit(ShortAddress::from(0x0002));
+        let mut reassembler = Reassembler::new(Duration::from_secs(5));
+
+        // Out of order and with a repeated fragment.
+        for fragment in [&fragments[2], &fragments[0], &fragments[2], &fragments[3]] {
+            assert_eq!(reassembler.receive(source, fragment, start).unwrap(), None);
+        }
+        assert_eq!(
+            reassembler.receive(source, &fragments[1], start).unwrap(),
+            Some(payload.clone())
+        );
+        assert!(reassembler.is_empty());
+
+        assert_eq!(
+            reassembler.receive(other, &[0x01, 0x02], start).unwrap(),
+            Some(vec![0x01, 0x02])
+        );
+        assert!(matches!(
+            reassembler.receive(other, &[0xe0, 0x04, 0x00, 0x00, 0x01, 0xff], start),
+            Err(error::Error::InvalidFragment)
+        ));
+
+        // The missing fragment never comes.
+        reassembler.receive(other, &fragments[0], start).unwrap();
+        assert!(reassembler.poll(start + Duration::from_secs(1)).is_empty());
+        assert_eq!(
+            reassembler.poll(start + Duration::from_secs(5)),
+            [(other, 0x0000)]
+        );
+        assert!(reassembler.is_empty());
+    }
+
+    #[cfg(feature = "sixlowpan")]
+    #[test]
+    fn sixlowpan_compress_and_fragment() {
+        use crate::sixlowpan::{self, SixLowPan};
+        use types::{Address, ExtendedAddress, ShortAddress};
+
+        let node = Address::Addr64Bit(ExtendedAddress::from(0x0012_4b00_0000_0001));
+        let router = Address::Addr16Bit(ShortAddress::from(0x0001));
+        let packet = |payload: &[u8], destination: [u8; 16]| {
+            let mut packet = vec![0x60, 0x00, 0x00, 0x00];
+            packet.extend((payload.len() as u16).to_be_bytes().iter());
+            // UDP, hop limit 64.
+            packet.extend([17, 64].iter());
+            packet.extend([0xfe, 0x80, 0, 0, 0, 0, 0, 0].iter());
+            packet.extend([0x02, 0x12, 0x4b, 0x00, 0x00, 0x00, 0x00, 0x01].iter());
+            packet.extend(destination.iter());
+            packet.extend(payload.iter());
+            packet
+        };
+        let to_router = [
+            0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xfe, 0, 0x00, 0x01,
+        ];
+        let all_nodes = [0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01];
+
+        // Both addresses come from the link layer, only the next header and
+        // the payload are left.
+        let small = packet(&[0xaa, 0xbb], to_router);
+        let compressed = sixlowpan::compress(&small, &node, &router).unwrap();
+        assert_eq!(compressed, [0x7a, 0x33, 17, 0xaa, 0xbb]);
+        assert_eq!(
+            sixlowpan::decompress(&compressed, &node, &router).unwrap(),
+            small
+        );
+
+        let multicast = packet(&[0x01], all_nodes);
+        let compressed = sixlowpan::compress(&multicast, &node, &Address::None).unwrap();
+        assert_eq!(compressed, [0x7a, 0x3b, 17, 0x01, 0x01]);
+        assert_eq!(
+            sixlowpan::decompress(&compressed, &node, &Address::None).unwrap(),
+            multicast
+        );
+
+        // Compressed next headers are not supported.
+        assert!(matches!(
+            sixlowpan::decompress(&[0x7e, 0x33, 0xf0], &node, &router),
+            Err(error::Error::NotImplemented)
+        ));
+
+        let payload: Vec<u8> = (0..200).map(|x| x as u8).collect();
+        let large = packet(&payload, to_router);
+        let mut adaptation = SixLowPan::new(80, Duration::from_secs(5));
+        let fragments = adaptation.send(&large, &node, &router).unwrap();
+        assert!(fragments.len() > 1);
+        assert!(fragments.iter().all(|fragment| fragment.len() <= 80));
+        // The datagram size is the uncompressed packet size.
+        assert_eq!(&fragments[0][..2], &[0xc0, 240]);
+
+        let now = Instant::now();
+        let mut receiver = SixLowPan::new(80, Duration::from_secs(5));
+        let (last, rest) = fragments.split_last().unwrap();
+        for fragment in rest.iter().rev() {
+            assert_eq!(
+                receiver.receive(&node, &router, fragment, now).unwrap(),
+                None
+            );
+        }
+        assert_eq!(
+            receiver.receive(&node, &router, last, now).unwrap(),
+            Some(large)
+        );
+
+        let uncompressed = [&[0x41][..], &small[..]].concat();
+        assert_eq!(
+            receiver
+                .receive(&node, &router, &uncompressed, now)
+                .unwrap(),
+            Some(small)
+        );
+    }
+
+    #[test]
+    fn frame_writer_batches_writes() {
+        use std::io::{self, IoSlice, Write};
+        use subsystem::sys::sreq;
+        use transport::{FlushPolicy, FrameWriter, WriteMode};
+
+        // Accepts at most `limit` bytes per call, like a full serial buffer.
+        #[derive(Default)]
+        struct Port {
+            bytes: Vec<u8>,
+            calls: usize,
+            limit: usize,
+        }
+
+        impl Write for Port {
+            fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+                self.calls += 1;
+                let count = buffer.len().min(self.limit);
+                self.bytes.extend_from_slice(&buffer[..count]);
+                Ok(count)
+            }
+
+            fn write_vectored(&mut self, buffers: &[IoSlice<'_>]) -> io::Result<usize> {
+                self.calls += 1;
+                let mut count = 0;
+                for buffer in buffers {
+                    let take = buffer.len().min(self.limit - count);
+                    self.bytes.extend_from_slice(&buffer[..take]);
+                    count += take;
+                }
+                Ok(count)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let frame = sreq::PingReq {}.into_mt_frame();
+        let encoded = frame.encode_to_uart_transport_frame();
+        let expected = encoded.repeat(4);
+
+        let port = Port {
+            limit: usize::MAX,
+            ..Port::default()
+        };
+        let mut writer = FrameWriter::new(port, FlushPolicy::Frames(3));
+        for _ in 0..4 {
+            writer.send(&frame).unwrap();
+        }
+        assert_eq!(writer.queued(), 1);
+        assert_eq!(writer.get_ref().calls, 1);
+        let port = writer.into_inner().unwrap();
+        assert_eq!(port.bytes, expected);
+        assert_eq!(port.calls, 2);
+
+        // Short writes resume in the middle of a frame.
+        let port = Port {
+            limit: 7,
+            ..Port::default()
+        };
+        let mut writer = FrameWriter::with_mode(port, FlushPolicy::Manual, WriteMode::Vectored);
+        for _ in 0..4 {
+            writer.send(&frame).unwrap();
+        }
+        assert_eq!(writer.get_ref().calls, 0);
+        writer.flush().unwrap();
+        assert_eq!(writer.queued(), 0);
+        assert_eq!(writer.get_ref().bytes, expected);
+        assert_eq!(writer.writes(), (expected.len() as u64).div_ceil(7));
+
+        let mut writer = FrameWriter::new(Port::default(), FlushPolicy::Immediate);
+        writer.set_policy(FlushPolicy::Bytes(encoded.len() * 2));
+        writer.send(&frame).unwrap();
+        assert_eq!(writer.queued(), 1);
+    }
+
+    #[test]
+    fn bounded_queue_overflow_policies() {
+        use queue::{BoundedQueue, OverflowPolicy};
+        use std::sync::Arc;
+
+        let oldest = BoundedQueue::new(2, OverflowPolicy::DropOldest);
+        assert_eq!(oldest.push(1), None);
+        assert_eq!(oldest.push(2), None);
+        assert_eq!(oldest.push(3), Some(1));
+        assert_eq!(oldest.drain(), [2, 3]);
+        assert_eq!(oldest.dropped(), 1);
+
+        let newest = BoundedQueue::new(2, OverflowPolicy::DropNewest);
+        newest.push(1);
+        newest.push(2);
+        assert_eq!(newest.push(3), Some(3));
+        assert_eq!(newest.try_pop(), Some(1));
+        assert_eq!(newest.dropped(), 1);
+        assert_eq!(newest.pop_timeout(Duration::from_millis(1)), Some(2));
+        assert_eq!(newest.pop_timeout(Duration::from_millis(1)), None);
+
+        // The producer waits for the consumer instead of dropping.
+        let blocking = Arc::new(BoundedQueue::new(1, OverflowPolicy::Block));
+        let producer = {
+            let blocking = blocking.clone();
+            std::thread::spawn(move || {
+                for item in 0..100 {
+                    assert_eq!(blocking.push(item), None);
+                }
+            })
+        };
+        let received: Vec<_> = (0..100)
+            .map(|_| blocking.pop_timeout(Duration::from_secs(5)).unwrap())
+            .collect();
+        producer.join().unwrap();
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+        assert_eq!(blocking.dropped(), 0);
+        assert!(blocking.is_empty());
+    }
+
+    #[test]
+    fn frame_check_sequence_and_feed_slice() {
+        use frame::{CommandCode, MTFrame, MTHeader};
+
+        let bytes: Vec<u8> = (0..300u32).map(|x| (x * 37 + 11) as u8).collect();
+        for length in 0..bytes.len() {
+            let expected = bytes[..length].iter().fold(0, |acc, x| acc ^ x);
+            assert_eq!(
+                MTFrame::compute_frame_check_sequence(&bytes[..length]),
+                expected
+            );
+        }
+
+        let mut stream = vec![0x00, 0x42];
+        for length in [0, 1, 7, 8, 9, 100, 250].iter() {
+            let frame = MTFrame {
+                header: MTHeader {
+                    length: *length,
+                    command: CommandCode::from_cmd(0x61, 0x01).unwrap(),
+                },
+                extended_header: None,
+                payload: bytes[..*length as usize].to_vec(),
+            };
+            stream.extend(frame.encode_to_uart_transport_frame());
+        }
+        // A frame with a bad check sequence in the middle of the stream.
+        let mut corrupted = MTFrame {
+            header: MTHeader {
+                length: 2,
+                command: CommandCode::from_cmd(0x61, 0x01).unwrap(),
+            },
+            extended_header: None,
+            payload: vec![0x01, 0x02],
+        }
+        .encode_to_uart_transport_frame();
+        *corrupted.last_mut().unwrap() ^= 0xff;
+        stream.splice(2..2, corrupted);
+
+        let mut by_byte = Vec::new();
+        let mut parser = crate::parser::Parser::new();
+        for byte in stream.iter() {
+            by_byte.extend(parser.feed(*byte));
+        }
+
+        // Any split of the stream gives the same results.
+        for chunk_size in [1, 3, 64, stream.len()].iter() {
+            let mut parser = crate::parser::Parser::new();
+            let mut by_slice = Vec::new();
+            for chunk in stream.chunks(*chunk_size) {
+                by_slice.extend(parser.feed_slice(chunk));
+            }
+            assert_eq!(format!("{:?}", by_slice), format!("{:?}", by_byte));
+        }
+        assert_eq!(by_byte.iter().filter(|x| x.is_ok()).count(), 7);
+    }
+
+    #[test]
+    fn decode_any_never_panics() {
+        // xorshift, so failures can be replayed.
+        let mut state = 0x2545_f491u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        let mut decoded = 0;
+        for cmd in 0..=0xffffu32 {
+            for _ in 0..4 {
+                let length = (next() % 64) as usize;
+                let mut bytes = vec![length as u8, (cmd >> 8) as u8, cmd as u8];
+                bytes.extend((0..length).map(|_| next() as u8));
+                if let Ok(payload) = subsystem::decode_any(&bytes) {
+                    payload.into_mt_frame().encode_to_uart_transport_frame();
+                    decoded += 1;
+                }
+            }
+        }
+        assert!(decoded > 0);
+        assert!(subsystem::decode_any(&[]).is_err());
+    }
+
+    #[test]
+    fn payload_lengths_match_encoders() {
+        use subsystem::length::{expected_length, PayloadLength};
+        use test_util::{Arbitrary, Rng};
+
+        let mut rng = Rng::new(0x349);
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..50_000 {
+            let frame = subsystem::MTFramePayload::arbitrary(&mut rng).into_mt_frame();
+            let command = frame.header.command;
+            let expected = expected_length(command.subsystem, command.cmd_type, command.id)
+                .unwrap_or_else(|| panic!("no length for {:?}", command));
+            match expected {
+                PayloadLength::Exact(length) => assert_eq!(frame.payload.len(), length),
+                PayloadLength::AtLeast(length) => assert!(frame.payload.len() >= length),
+            }
+            seen.insert((command.subsystem, command.cmd_type, command.id));
+        }
+        assert_eq!(seen.len(), 116);
+
+        // MAC_POLL_IND with its last byte missing, and with one too many.
+        let data = [
+            0x2, 0xb1, 0xac, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0xfa, 0xff, 0x0,
+        ];
+        let decode = |payload: &[u8]| {
+            subsystem::MTFramePayload::try_decode(
+                &types::MTSubsystem::MAC,
+                &types::CommandType::AREQ,
+                types::MACCommandId::PollInd as u8,
+                payload,
+            )
+        };
+        let error = decode(&data[..11]).unwrap_err();
+        assert!(matches!(
+            error,
+            error::Error::LengthMismatch {
+                expected: PayloadLength::Exact(12),
+                actual: 11,
+                ..
+            }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "MAC AREQ 0x91 expects 12 bytes but has 11"
+        );
+        assert!(decode(&data).is_ok());
+        let mut longer = data.to_vec();
+        longer.push(0x00);
+        assert!(decode(&longer).is_err());
+
+        // Bytes appended by newer firmware are ignored when asked to.
+        let options = DecodeOptions {
+            trailing_bytes: true,
+            ..DecodeOptions::default()
+        };
+        let decoded = subsystem::MTFramePayload::try_decode_with(
+            &types::MTSubsystem::MAC,
+            &types::CommandType::AREQ,
+            types::MACCommandId::PollInd as u8,
+            &longer,
+            options,
+        )
+        .unwrap();
+        assert_eq!(decoded, decode(&data).unwrap());
+    }
+
+    #[cfg(feature = "sbl")]
+    #[test]
+    fn sbl_upgrades_flash() {
+        use crate::sbl::{self, Bootloader, SblError, SblStatus};
+        use std::collections::VecDeque;
+        use std::io::{self, Read, Write};
+
+        // ROM bootloader with 1 KiB sectors over 4 KiB of flash.
+        struct Rom {
+            flash: Vec<u8>,
+            to_host: VecDeque<u8>,
+            from_host: Vec<u8>,
+            synced: bool,
+            awaiting_ack: bool,
+            download: Option<(usize, usize)>,
+            status: u8,
+            resets: u32,
+        }
+
+        impl Rom {
+            fn reply(&mut self, data: &[u8]) {
+                self.to_host.extend(sbl::encode_packet(data).unwrap());
+                self.awaiting_ack = true;
+            }
+
+            fn run(&mut self, command: &[u8]) {
+                let u32_at = |offset: usize| {
+                    u32::from_be_bytes([
+                        command[offset],
+                        command[offset + 1],
+                        command[offset + 2],
+                        command[offset + 3],
+                    ]) as usize
+                };
+                self.to_host.extend([0x00, 0xcc].iter());
+                match command[0] {
+                    0x20 => (),
+                    0x21 => {
+                        self.download = Some((u32_at(1), u32_at(5)));
+                        self.status = 0x40;
+                    }
+                    0x23 => {
+                        let status = self.status;
+                        self.reply(&[status]);
+                    }
+                    0x24 => match self.download.as_mut() {
+                        Some((address, left)) if command.len() - 1 <= *left => {
+                            let data = &command[1..];
+                            self.flash[*address..*address + data.len()].copy_from_slice(data);
+                            *address += data.len();
+                            *left -= data.len();
+                            self.status = 0x40;
+                        }
+                        _ => self.status = 0x42,
+                    },
+                    0x25 => self.resets += 1,
+                    0x26 => {
+                        let address = u32_at(1);
+                        if address % 1024 != 0 || address >= self.flash.len() {
+                            self.status = 0x43;
+                        } else {
+                            self.flash[address..address + 1024].fill(0xff);
+                            self.status = 0x40;
+                        }
+                    }
+                    0x27 => {
+                        let (address, length) = (u32_at(1), u32_at(5));
+                        let crc = sbl::crc32(&self.flash[address..address + length]);
+                        self.reply(&crc.to_be_bytes());
+                    }
+                    0x28 => self.reply(&[0x2b, 0xb4, 0x10, 0x2f]),
+                    _ => self.status = 0x41,
+                }
+            }
+        }
+
+        impl Write for Rom {
+            fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+                self.from_host.extend_from_slice(buffer);
+                loop {
+                    if !self.synced {
+                        if self.from_host.starts_with(&[0x55, 0x55]) {
+                            self.from_host.drain(..2);
+                            self.synced = true;
+                            self.to_host.extend([0x00, 0xcc].iter());
+                        }
+                        return Ok(buffer.len());
+                    }
+                    if self.awaiting_ack {
+                        if self.from_host.len() < 2 {
+                            return Ok(buffer.len());
+                        }
+                        assert_eq!(self.from_host.drain(..2).collect::<Vec<_>>(), [0x00, 0xcc]);
+                        self.awaiting_ack = false;
+                    }
+                    let size = match self.from_host.first() {
+                        Some(size) if self.from_host.len() >= usize::from(*size) => {
+                            usize::from(*size)
+                        }
+                        _ => return Ok(buffer.len()),
+                    };
+                    let packet: Vec<u8> = self.from_host.drain(..size).collect();
+                    let sum = packet[2..].iter().fold(0u8, |acc, x| acc.wrapping_add(*x));
+                    if sum != packet[1] {
+                        self.to_host.extend([0x00, 0x33].iter());
+                        continue;
+                    }
+                    self.run(&packet[2..]);
+                }
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        impl Read for Rom {
+            fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+                match self.to_host.pop_front() {
+                    Some(byte) => {
+                        buffer[0] = byte;
+                        Ok(1)
+                    }
+                    None => Err(io::ErrorKind::TimedOut.into()),
+                }
+            }
+        }
+
+        let rom = Rom {
+            flash: vec![0x00; 4096],
+            to_host: VecDeque::new(),
+            from_host: Vec::new(),
+            synced: false,
+            awaiting_ack: false,
+            download: None,
+            status: 0x40,
+            resets: 0,
+        };
+        let mut bootloader = Bootloader::new(rom, 1024);
+        bootloader.enter().unwrap();
+        bootloader.ping().unwrap();
+        assert_eq!(bootloader.chip_id().unwrap(), 0x2bb4_102f);
+
+        // Spans three sectors, several SEND_DATA blocks and a padded tail.
+        let image: Vec<u8> = (0..1500u32).map(|x| (x * 7) as u8).collect();
+        bootloader.upgrade(1024, &image).unwrap();
+        let rom = bootloader.get_mut();
+        assert_eq!(&rom.flash[1024..1024 + 1500], &image[..]);
+        assert_eq!(&rom.flash[1024 + 1500..3072], &[0xff; 548][..]);
+        assert_eq!(&rom.flash[..1024], &[0x00; 1024][..]);
+        assert_eq!(rom.resets, 1);
+
+        rom.flash[2000] ^= 0xff;
+        assert!(matches!(
+            bootloader.verify(1024, &image),
+            Err(SblError::VerifyFailed { address: 1024, .. })
+        ));
+        assert!(matches!(
+            bootloader.erase(8192, 1),
+            Err(SblError::Status(SblStatus::InvalidAddress))
+        ));
+        assert!(matches!(
+            sbl::encode_packet(&[0x00; 254]),
+            Err(SblError::DataTooLong(254))
+        ));
+        assert_eq!(sbl::crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn serial_config_and_wakeup() {
+        use std::io::{self, Write};
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use subsystem::sys::sreq;
+        use transport::{FlowControl, FlushPolicy, FrameWriter, SerialConfig, SerialPort, Wakeup};
+
+        #[derive(Default)]
+        struct Port {
+            bytes: Vec<u8>,
+            config: Option<SerialConfig>,
+        }
+
+        impl Write for Port {
+            fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+                self.bytes.extend_from_slice(buffer);
+                Ok(buffer.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        impl SerialPort for Port {
+            fn configure(&mut self, config: &SerialConfig) -> io::Result<()> {
+                self.config = Some(*config);
+                Ok(())
+            }
+        }
+
+        assert!(SerialConfig::default().validate().is_ok());
+        assert!(SerialConfig::new(230400, FlowControl::None)
+            .validate()
+            .is_err());
+        assert!(SerialConfig::new(100000, FlowControl::RtsCts)
+            .validate()
+            .is_err());
+        let fast = SerialConfig::new(460800, FlowControl::RtsCts);
+        assert!(fast.validate().is_ok());
+        assert_eq!(
+            SerialConfig::default().transmit_time(1152),
+            Duration::from_millis(100)
+        );
+
+        static SLEPT_MS: AtomicU64 = AtomicU64::new(0);
+        let mut wakeup = Wakeup::new(
+            vec![0x00; 3],
+            Duration::from_millis(5),
+            Duration::from_secs(60),
+        );
+        wakeup.sleep = |duration| {
+            SLEPT_MS.fetch_add(duration.as_millis() as u64, Ordering::SeqCst);
+        };
+
+        let frame = sreq::PingReq {}.into_mt_frame();
+        let encoded = frame.encode_to_uart_transport_frame();
+        let mut writer = FrameWriter::new(Port::default(), FlushPolicy::Immediate);
+        writer.set_wakeup(Some(wakeup.clone()));
+
+        // Only the first frame follows an idle link.
+        writer.send(&frame).unwrap();
+        writer.send(&frame).unwrap();
+        let mut expected = vec![0x00; 3];
+        expected.extend(encoded.repeat(2));
+        assert_eq!(writer.get_ref().bytes, expected);
+        assert_eq!(SLEPT_MS.load(Ordering::SeqCst), 5);
+
+        wakeup.idle = Duration::ZERO;
+        writer.set_wakeup(Some(wakeup));
+        writer.send(&frame).unwrap();
+        expected.extend([0x00; 3].iter());
+        expected.extend(encoded.iter());
+        assert_eq!(writer.get_ref().bytes, expected);
+
+        // Queued frames go out at the old rate.
+        writer.set_policy(FlushPolicy::Manual);
+        writer.send(&frame).unwrap();
+        writer.configure(&fast).unwrap();
+        assert_eq!(writer.queued(), 0);
+        assert_eq!(writer.get_ref().config, Some(fast));
+        assert!(writer
+            .configure(&SerialConfig::new(230400, FlowControl::None))
+            .is_err());
+        assert_eq!(writer.get_ref().config, Some(fast));
+    }
+
+    #[test]
+    fn power_managed_port_handshakes() {
+        use std::io;
+        use subsystem::sys::sreq;
+        use transport::{FlushPolicy, FrameWriter, HandshakeLines, PowerManaged};
+
+        // The coprocessor answers MRDY after `wake_polls` reads of SRDY, and
+        // raises SRDY on its own while `sending`.
+        #[derive(Default)]
+        struct Lines {
+            mrdy: bool,
+            awake_polls: u32,
+            wake_polls: u32,
+            sending: bool,
+            edges: Vec<bool>,
+        }
+
+        impl HandshakeLines for Lines {
+            fn set_mrdy(&mut self, asserted: bool) -> io::Result<()> {
+                self.mrdy = asserted;
+                self.awake_polls = 0;
+                self.edges.push(asserted);
+                Ok(())
+            }
+
+            fn srdy(&mut self) -> io::Result<bool> {
+                if self.sending {
+                    return Ok(true);
+                }
+                self.awake_polls += 1;
+                Ok(self.mrdy && self.awake_polls > self.wake_polls)
+            }
+        }
+
+        let lines = Lines {
+            wake_polls: 3,
+            ..Lines::default()
+        };
+        let mut port = PowerManaged::new(Vec::new(), lines, Duration::from_millis(50));
+        port.set_sleep(|_| ());
+        let mut writer = FrameWriter::new(port, FlushPolicy::Frames(2));
+
+        // One handshake per batch.
+        let frame = sreq::PingReq {}.into_mt_frame();
+        for _ in 0..4 {
+            writer.send(&frame).unwrap();
+        }
+        let port = writer.get_mut();
+        assert_eq!(port.lines().edges, [true, false, true, false]);
+        assert_eq!(port.get_ref().len(), 4 * 5);
+
+        // A transfer started by the coprocessor.
+        port.lines().edges.clear();
+        assert!(!port.service().unwrap());
+        port.lines().sending = true;
+        assert!(port.service().unwrap());
+        assert!(port.service().unwrap());
+        port.lines().sending = false;
+        assert!(!port.service().unwrap());
+        assert_eq!(port.lines().edges, [true, false]);
+
+        // A coprocessor that never wakes fails the write and releases MRDY.
+        port.lines().edges.clear();
+        port.lines().wake_polls = u32::MAX;
+        let error = writer.send(&frame).and(writer.flush()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(writer.get_mut().lines().edges, [true, false]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn discover_ti_serial_ports() {
+        use std::fs;
+        use std::os::unix::fs::symlink;
+        use std::path::Path;
+
+        let root = std::env::temp_dir().join(format!("ti154-discover-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let sys = root.join("sys");
+        let write = |path: &Path, name: &str, value: &str| {
+            fs::create_dir_all(path).unwrap();
+            fs::write(path.join(name), value).unwrap();
+        };
+        let tty = |name: &str, device: &Path| {
+            let class = sys.join("class/tty").join(name);
+            fs::create_dir_all(&class).unwrap();
+            fs::create_dir_all(device).unwrap();
+            symlink(device, class.join("device")).unwrap();
+        };
+
+        // An XDS110 with its two interfaces, an unrelated USB serial adapter
+        // and a tty without a device.
+        let xds = sys.join("devices/usb1/1-1");
+        write(&xds, "idVendor", "0451\n");
+        write(&xds, "idProduct", "bef3\n");
+        write(&xds, "serial", "L1100ABC\n");
+        write(&xds.join("1-1:1.0"), "bInterfaceNumber", "00\n");
+        write(&xds.join("1-1:1.3"), "bInterfaceNumber", "03\n");
+        tty("ttyACM1", &xds.join("1-1:1.3"));
+        tty("ttyACM0", &xds.join("1-1:1.0"));
+
+        let other = sys.join("devices/usb1/1-2");
+        write(&other, "idVendor", "067b\n");
+        write(&other, "idProduct", "2303\n");
+        tty("ttyUSB0", &other.join("1-2:1.0/ttyUSB0"));
+        fs::create_dir_all(sys.join("class/tty/ttyS0")).unwrap();
+
+        let candidates = transport::discover_in(&sys, Path::new("/dev")).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let paths: Vec<_> = candidates.iter().map(|c| c.path.clone()).collect();
+        assert_eq!(
+            paths,
+            [Path::new("/dev/ttyACM0"), Path::new("/dev/ttyACM1")]
+        );
+        assert!(candidates[0].is_application_uart());
+        assert!(!candidates[1].is_application_uart());
+        assert_eq!(candidates[0].probe, "XDS110");
+        assert_eq!(candidates[0].serial_number.as_deref(), Some("L1100ABC"));
+        assert_eq!(transport::identify(0x0403, 0xa6d0), Some("XDS100v3"));
+        assert_eq!(transport::identify(0x067b, 0x2303), None);
+    }
+
+    #[cfg(feature = "serialport")]
+    #[test]
+    fn discover_ports_listed_by_serialport() {
+        use serialport::{SerialPortInfo, SerialPortType, UsbPortInfo};
+        use std::path::Path;
+
+        let usb = |name: &str, pid: u16, interface: u8| SerialPortInfo {
+            port_name: name.to_string(),
+            port_type: SerialPortType::UsbPort(UsbPortInfo {
+                vid: 0x0451,
+                pid,
+                serial_number: Some("L1100ABC".to_string()),
+                manufacturer: Some("Texas Instruments".to_string()),
+                product: Some("XDS110".to_string()),
+                interface: Some(interface),
+            }),
+        };
+        // Ports as listed on Windows: the two XDS110 interfaces, a USB
+        // device that is not a debug probe and a PCI port.
+        let ports = vec![
+            usb("COM7", 0xbef3, 3),
+            SerialPortInfo {
+                port_name: "COM1".to_string(),
+                port_type: SerialPortType::PciPort,
+            },
+            usb("COM6", 0xbef3, 0),
+            usb("COM3", 0x1234, 0),
+        ];
+
+        let candidates = transport::candidates_from(ports);
+        let paths: Vec<_> = candidates.iter().map(|c| c.path.clone()).collect();
+        assert_eq!(paths, [Path::new("COM6"), Path::new("COM7")]);
+        assert!(candidates[0].is_application_uart());
+        assert!(!candidates[1].is_application_uart());
+        assert_eq!(candidates[0].probe, "XDS110");
+        assert_eq!(candidates[0].serial_number.as_deref(), Some("L1100ABC"));
+    }
+
+    #[test]
+    fn tcp_transport_requests_over_a_bridge() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+        use subsystem::sys::{areq, srsp};
+
+        // A bridge answering the first SYS_PING after a reset indication and
+        // ignoring everything after it.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let bridge = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut parser = crate::parser::Parser::new();
+            let mut buffer = [0x00; 64];
+            let mut requests = 0;
+            loop {
+                let count = stream.read(&mut buffer).unwrap();
+                if count == 0 {
+                    return requests;
+                }
+                for _ in parser.feed_slice(&buffer[..count]) {
+                    requests += 1;
+                    if requests > 1 {
+                        continue;
+                    }
+                    let indication = areq::ResetInd {
+                        reason: types::ResetReason::Hardware,
+                        transport: types::TransportProtocolRevision::ExtendedRPCFrame,
+                        product: types::ProductIdCode::TI154Stack,
+                        major: 2,
+                        minor: 0,
+                        maint: 0,
+                    };
+                    let mut bytes = indication.into_mt_frame().encode_to_uart_transport_frame();
+                    bytes.insert(0, 0x00);
+                    let response = srsp::PingReq {
+                        capabilities: 0x0159,
+                    };
+                    bytes.extend(response.into_mt_frame().encode_to_uart_transport_frame());
+                    // Split mid-frame, as TCP may.
+                    stream.write_all(&bytes[..7]).unwrap();
+                    stream.flush().unwrap();
+                    std::thread::sleep(Duration::from_millis(5));
+                    stream.write_all(&bytes[7..]).unwrap();
+                }
+            }
+        });
+
+        let mut transport = tcp::TcpTransport::connect(address, Duration::from_secs(5)).unwrap();
+        assert_eq!(client::ping(&mut transport).unwrap(), 0x0159);
+        let indication = transport.receive(Duration::ZERO).unwrap().unwrap();
+        assert!(indication.header.command.is(
+            types::CommandType::AREQ,
+            types::MTSubsystem::SYS,
+            types::SYSCommandId::ResetInd
+        ));
+        assert!(transport
+            .receive(Duration::from_millis(1))
+            .unwrap()
+            .is_none());
+
+        transport.set_timeout(Duration::from_millis(20));
+        assert!(matches!(
+            client::ping(&mut transport),
+            Err(error::Error::Timeout { .. })
+        ));
+        drop(transport);
+        assert_eq!(bridge.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn data_req_longer_than_255_bytes() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+        use subsystem::mac::{sreq, srsp};
+        use subsystem::MTFramePayload;
+        use test_util::{Arbitrary, Rng};
+
+        let mut request = sreq::DataReq::arbitrary(&mut Rng::new(0x316));
+        request.data_payload = (0..400).map(|x| x as u8).collect();
+        request.data_length = 400;
+        let frame = request.clone().into_mt_frame();
+        assert!(frame.header.command.is_extended);
+        assert!(matches!(
+            frame.extended_header,
+            Some(frame::MTExtendedHeader::V2 { packet_length, .. })
+                if usize::from(packet_length) == frame.payload.len()
+        ));
+
+        // Written as the blocks of one extended frame.
+        let mut writer = transport::FrameWriter::new(Vec::new(), transport::FlushPolicy::Manual);
+        writer.send(&frame).unwrap();
+        assert_eq!(writer.queued(), 2);
+        let oversized = frame::MTFrame {
+            payload: vec![0x00; 0x10000],
+            ..frame.clone()
+        };
+        assert_eq!(
+            writer.send(&oversized).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+
+        // A bridge joining the blocks and confirming the request.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let bridge = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut parser = crate::parser::Parser::new();
+            let mut buffer = [0x00; 64];
+            loop {
+                let count = stream.read(&mut buffer).unwrap();
+                if let Some(frame) = parser.feed_slice(&buffer[..count]).pop() {
+                    let response = srsp::DataReq {
+                        status: types::Status::Success,
+                    };
+                    stream
+                        .write_all(&response.into_mt_frame().encode_to_uart_transport_frame())
+                        .unwrap();
+                    return frame.unwrap();
+                }
+            }
+        });
+
+        let mut transport = tcp::TcpTransport::connect(address, Duration::from_secs(5)).unwrap();
+        client::data_req(&mut transport, request.clone()).unwrap();
+        let received = bridge.join().unwrap();
+        assert_eq!(
+            MTFramePayload::try_from_frame(&received).unwrap(),
+            MTFramePayload::MAC_DataReq_SREQ(request)
+        );
+    }
+
+    #[test]
+    fn replay_recorded_session() {
+        use client::Client;
+        use replay::{Recorder, ReplayTransport, RequestMatch};
+        use subsystem::sys::{areq, sreq, srsp};
+
+        // A device answering pings, with a reset indication before the first.
+        struct Device {
+            pings: u16,
+        }
+
+        impl client::Client for Device {
+            fn request(&mut self, _: frame::MTFrame) -> Result<frame::MTFrame, error::Error> {
+                self.pings += 1;
+                Ok(srsp::PingReq {
+                    capabilities: self.pings,
+                }
+                .into_mt_frame())
+            }
+        }
+
+        let reset = areq::ResetInd {
+            reason: types::ResetReason::Hardware,
+            transport: types::TransportProtocolRevision::ExtendedRPCFrame,
+            product: types::ProductIdCode::TI154Stack,
+            major: 2,
+            minor: 0,
+            maint: 0,
+        }
+        .into_mt_frame();
+
+        let mut recorder = Recorder::new(Device { pings: 0 });
+        recorder.received(&reset);
+        assert_eq!(client::ping(&mut recorder).unwrap(), 1);
+        assert_eq!(client::ping(&mut recorder).unwrap(), 2);
+        let mut dump = Vec::new();
+        recorder.history().dump(&mut dump).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+        assert_eq!(dump.lines().count(), 5);
+
+        // The same calls get the same answers, without the device.
+        let mut replay = ReplayTransport::parse(&dump).unwrap();
+        let indication = replay.receive().unwrap();
+        assert_eq!(indication.payload, reset.payload);
+        assert_eq!(client::ping(&mut replay).unwrap(), 1);
+        assert!(!replay.is_finished());
+        assert_eq!(client::ping(&mut replay).unwrap(), 2);
+        assert!(replay.is_finished());
+        assert!(matches!(
+            client::ping(&mut replay),
+            Err(error::Error::Timeout { .. })
+        ));
+
+        // Another request than the recorded one.
+        let mut replay = ReplayTransport::from_history(recorder.history()).unwrap();
+        let version = sreq::VersionReq {}.into_mt_frame();
+        assert!(matches!(
+            replay.request(version),
+            Err(error::Error::ReplayMismatch { .. })
+        ));
+
+        let mut replay = ReplayTransport::from_history(recorder.history()).unwrap();
+        replay.set_matching(RequestMatch::Command);
+        let mut ping = sreq::PingReq {}.into_mt_frame();
+        ping.payload.push(0x00);
+        assert!(replay.request(ping).is_ok());
+        assert!(matches!(
+            ReplayTransport::parse("0.000000 TX fe zz"),
+            Err(error::Error::Io(std::io::ErrorKind::InvalidData))
+        ));
+    }
+
+    #[test]
+    #[cfg(all(feature = "sim", feature = "coordinator"))]
+    fn simulated_coprocessor_runs_join_and_data() {
+        use crate::sim::Coprocessor;
+        use coordinator::{Coordinator, CoordinatorConfig, CoordinatorEvent, JoinDecision};
+        use subsystem::mac::areq;
+        use subsystem::sys;
+        use types::{Address, ExtendedAddress, MACPIBAttributeId, ResetType, Status};
+
+        let mut coordinator = Coordinator::new(
+            Coprocessor::new(ExtendedAddress::from(0x00124b0000000001)),
+            CoordinatorConfig::default(),
+            |_: &areq::AssociateInd| JoinDecision::Accept,
+        );
+        coordinator.start().unwrap();
+        assert_eq!(
+            coordinator.client().pib(MACPIBAttributeId::PANId)[..2],
+            [0x34, 0x12]
+        );
+        let frame = coordinator.client().receive().unwrap();
+        assert!(matches!(
+            coordinator.handle(&frame),
+            Ok(CoordinatorEvent::Started)
+        ));
+
+        let device = ExtendedAddress::from(0x00124b00000000aa);
+        coordinator.client().join(device);
+        let frame = coordinator.client().receive().unwrap();
+        let short_address = match coordinator.handle(&frame).unwrap() {
+            CoordinatorEvent::Joined {
+                extended_address,
+                short_address,
+            } => {
+                assert_eq!(extended_address, device);
+                short_address
+            }
+            event => panic!("unexpected {:?}", event),
+        };
+        let frame = coordinator.client().receive().unwrap();
+        assert!(matches!(
+            coordinator.handle(&frame),
+            Ok(CoordinatorEvent::Other(
+                subsystem::MTFramePayload::MAC_CommStatusInd_AREQ(_)
+            ))
+        ));
+
+        let handle = coordinator
+            .send(Address::Addr16Bit(short_address), vec![0x68, 0x69])
+            .unwrap();
+        let frame = coordinator.client().receive().unwrap();
+        match coordinator.handle(&frame).unwrap() {
+            CoordinatorEvent::DataCnf(x) => {
+                assert_eq!(x.handle, handle);
+                assert_eq!(x.status, Status::Success);
+            }
+            event => panic!("unexpected {:?}", event),
+        }
+        let frame = coordinator.client().receive().unwrap();
+        match coordinator.handle(&frame).unwrap() {
+            CoordinatorEvent::Data(x) => {
+                assert_eq!(x.src_address, Address::Addr16Bit(short_address));
+                assert_eq!(x.data_payload, vec![0x68, 0x69]);
+            }
+            event => panic!("unexpected {:?}", event),
+        }
+
+        // Nobody answers at addresses that did not join.
+        let stranger = types::ShortAddress::from(0x0042);
+        coordinator
+            .send(Address::Addr16Bit(stranger), vec![0x00])
+            .unwrap();
+        let frame = coordinator.client().receive().unwrap();
+        let confirm = areq::DataCnf::try_decode(&frame.payload).unwrap();
+        assert_eq!(confirm.status, Status::NoAck);
+        assert!(coordinator.client().receive().is_none());
+
+        assert_eq!(client::ping(coordinator.client()).unwrap(), 0x0003);
+        let reset = sys::areq::ResetReq {
+            reset_type: ResetType::Soft,
+        };
+        coordinator.client().send(reset.into_mt_frame()).unwrap();
+        let frame = coordinator.client().receive().unwrap();
+        assert_eq!(frame.header.command.id, types::SYSCommandId::ResetInd as u8);
+        assert_eq!(
+            coordinator.client().pib(MACPIBAttributeId::PANId)[..2],
+            [0xff, 0xff]
+        );
+    }
+
+    #[test]
+    fn data_confirm_future_resolves_on_confirm() {
+        use confirm::Confirms;
+        use std::future::Future;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake, Waker};
+        use subsystem::mac::{areq, sreq};
+        use types::Status;
+
+        struct CountingWaker(AtomicUsize);
+
+        impl Wake for CountingWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let mut client = test_util::ScriptedClient::new();
+        let request = || sreq::DataReq {
+            dest_address: types::Address::Addr16Bit(types::ShortAddress::from(0x0001)),
+            dest_pan_id: types::PanId(0x1234),
+            src_address_mode: types::AddressMode::Addr16Bit,
+            handle: 0,
+            tx_option: types::TxOption::ACK,
+            channel: 0,
+            power: 0,
+            security: types::Security::none(),
+            include_fh_ies: 0,
+            data_length: 1,
+            ie_length: 0,
+            data_payload: vec![0x2a],
+            ie_payload: Vec::new(),
+        };
+        let confirm = |handle: u8, status: Status| {
+            areq::DataCnf {
+                status,
+                handle,
+                timestamp: 1000,
+                timestamp2: 0,
+                retries: 2,
+                link_quality: types::Lqi(0xff),
+                correlation: 0,
+                rssi: types::Rssi(-40),
+                frame_counter: 0,
+            }
+            .into_mt_frame()
+        };
+
+        let confirms = Confirms::new();
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut first = Box::pin(confirms.send_data(&mut client, request()).unwrap());
+        let mut second = Box::pin(confirms.send_data(&mut client, request()).unwrap());
+        assert_ne!(first.handle(), second.handle());
+        assert_eq!(confirms.pending(), 2);
+        assert!(first.as_mut().poll(&mut cx).is_pending());
+        assert!(second.as_mut().poll(&mut cx).is_pending());
+
+        // The SRSP alone does not resolve anything, the confirms do, in any
+        // order.
+        let second_handle = second.handle();
+        assert!(confirms
+            .handle(&confirm(second_handle, Status::NoAck))
+            .unwrap());
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert!(first.as_mut().poll(&mut cx).is_pending());
+        match second.as_mut().poll(&mut cx) {
+            Poll::Ready(x) => {
+                assert_eq!(x.handle, second_handle);
+                assert_eq!(x.status, Status::NoAck);
+                assert_eq!(x.retries, 2);
+            }
+            Poll::Pending => panic!("second confirm not resolved"),
+        }
+        assert!(!confirms
+            .handle(&confirm(second_handle, Status::Success))
+            .unwrap());
+
+        let first_handle = first.handle();
+        drop(first);
+        assert_eq!(confirms.pending(), 0);
+        assert!(confirms
+            .handle(&confirm(first_handle, Status::Success))
+            .unwrap());
+        assert!(!confirms
+            .handle(
+                &areq::StartCnf {
+                    status: Status::Success
+                }
+                .into_mt_frame()
+            )
+            .unwrap());
+
+        // A resolved future dropped after its handle went to a new request
+        // leaves that request alone.
+        let mut old = Box::pin(confirms.send_data(&mut client, request()).unwrap());
+        let handle = old.handle();
+        confirms.handle(&confirm(handle, Status::Success)).unwrap();
+        assert!(old.as_mut().poll(&mut cx).is_ready());
+        let mut newer = Vec::new();
+        while newer.last().map(|x: &confirm::DataConfirm| x.handle()) != Some(handle) {
+            newer.push(confirms.send_data(&mut client, request()).unwrap());
+        }
+        assert!(old.as_mut().poll(&mut cx).is_pending());
+        drop(old);
+        let mut reused = Box::pin(newer.pop().unwrap());
+        assert!(confirms.handle(&confirm(handle, Status::NoAck)).unwrap());
+        match reused.as_mut().poll(&mut cx) {
+            Poll::Ready(x) => assert_eq!(x.status, Status::NoAck),
+            Poll::Pending => panic!("request on the reused handle not resolved"),
+        }
+    }
+
+    #[test]
+    fn scheduler_limits_and_retries_per_destination() {
+        use scheduler::{DeliveryStats, Scheduler, SchedulerConfig, SchedulerEvent};
+        use std::time::{Duration, Instant};
+        use subsystem::mac::{areq, sreq};
+        use types::{Address, ShortAddress, Status};
+
+        let sent = |client: &test_util::ScriptedClient| {
+            client
+                .decoded(
+                    types::MTSubsystem::MAC,
+                    types::MACCommandId::DataReq as u8,
+                    sreq::DataReq::try_decode,
+                )
+                .into_iter()
+                .map(|x| (x.dest_address, x.handle))
+                .collect::<Vec<_>>()
+        };
+
+        let request = |address: u16| sreq::DataReq {
+            dest_address: Address::Addr16Bit(ShortAddress::from(address)),
+            dest_pan_id: types::PanId(0x1234),
+            src_address_mode: types::AddressMode::Addr16Bit,
+            handle: 0,
+            tx_option: types::TxOption::ACK,
+            channel: 0,
+            power: 0,
+            security: types::Security::none(),
+            include_fh_ies: 0,
+            data_length: 1,
+            ie_length: 0,
+            data_payload: vec![0x2a],
+            ie_payload: Vec::new(),
+        };
+        let confirm = |handle: u8, status: Status| areq::DataCnf {
+            status,
+            handle,
+            timestamp: 0,
+            timestamp2: 0,
+            retries: 0,
+            link_quality: types::Lqi(0xff),
+            correlation: 0,
+            rssi: types::Rssi(-40),
+            frame_counter: 0,
+        };
+        let a = Address::Addr16Bit(ShortAddress::from(0x0001));
+        let b = Address::Addr16Bit(ShortAddress::from(0x0002));
+
+        let now = Instant::now();
+        let mut client = test_util::ScriptedClient::new();
+        let mut scheduler = Scheduler::new(SchedulerConfig::default());
+        let first = scheduler.enqueue(request(0x0001), now);
+        let second = scheduler.enqueue(request(0x0001), now);
+        let other = scheduler.enqueue(request(0x0002), now);
+        assert_eq!(scheduler.len(), 3);
+
+        // One frame in flight per destination.
+        assert_eq!(scheduler.poll(&mut client, now).unwrap(), 2);
+        assert_eq!(scheduler.poll(&mut client, now).unwrap(), 0);
+        let handle_of = |client: &test_util::ScriptedClient, address: Address| {
+            sent(client)
+                .iter()
+                .rev()
+                .find(|x| x.0 == address)
+                .unwrap()
+                .1
+        };
+
+        // NoAck is retried after the backoff, ahead of the queued request.
+        let handle = handle_of(&client, a);
+        assert!(scheduler
+            .confirm(&confirm(handle, Status::NoAck), now)
+            .is_none());
+        assert_eq!(scheduler.poll(&mut client, now).unwrap(), 0);
+        let backoff = scheduler.config().retry.backoff_after(1);
+        assert_eq!(scheduler.next_deadline(), Some(now + backoff));
+        let later = now + backoff;
+        assert_eq!(scheduler.poll(&mut client, later).unwrap(), 1);
+        let handle = handle_of(&client, a);
+        match scheduler.confirm(&confirm(handle, Status::Success), later) {
+            Some(SchedulerEvent::Delivered {
+                id, destination, ..
+            }) => {
+                assert_eq!(id, first);
+                assert_eq!(destination, a);
+            }
+            event => panic!("unexpected {:?}", event),
+        }
+
+        // Statuses outside the retry list fail right away.
+        let handle = handle_of(&client, b);
+        match scheduler.confirm(&confirm(handle, Status::InvalidParameter), later) {
+            Some(SchedulerEvent::Failed { id, attempts, .. }) => {
+                assert_eq!(id, other);
+                assert_eq!(attempts, 1);
+            }
+            event => panic!("unexpected {:?}", event),
+        }
+
+        // The last attempt reports the failure.
+        let mut at = later;
+        let mut event = None;
+        while event.is_none() {
+            assert_eq!(scheduler.poll(&mut client, at).unwrap(), 1);
+            let handle = handle_of(&client, a);
+            event = scheduler.confirm(&confirm(handle, Status::ChannelAccessFailure), at);
+            at += Duration::from_secs(1);
+        }
+        match event {
+            Some(SchedulerEvent::Failed {
+                id,
+                attempts,
+                status,
+                ..
+            }) => {
+                assert_eq!(id, second);
+                assert_eq!(attempts, 3);
+                assert_eq!(status, Status::ChannelAccessFailure);
+            }
+            event => panic!("unexpected {:?}", event),
+        }
+        assert!(scheduler.is_empty());
+        assert_eq!(
+            scheduler.stats(&a),
+            Some(&DeliveryStats {
+                transmissions: 5,
+                retries: 3,
+                delivered: 1,
+                failed: 1,
+                in_flight: 0,
+                queued: 0,
+            })
+        );
+        assert_eq!(scheduler.stats(&b).unwrap().failed, 1);
+        assert!(scheduler
+            .confirm(&confirm(0x80, Status::Success), at)
+            .is_none());
+
+        // Idle destinations are kept for their stats until pruned.
+        scheduler.enqueue(request(0x0002), at);
+        assert_eq!(scheduler.prune(), 1);
+        assert!(scheduler.stats(&a).is_none());
+        assert!(scheduler.stats(&b).is_some());
+
+        // With every handle in flight the request stays queued.
+        let mut client = test_util::ScriptedClient::new();
+        let mut scheduler = Scheduler::new(SchedulerConfig {
+            max_in_flight: 300,
+            ..SchedulerConfig::default()
+        });
+        for _ in 0..257 {
+            scheduler.enqueue(request(0x0001), now);
+        }
+        assert!(matches!(
+            scheduler.poll(&mut client, now),
+            Err(error::Error::TableFull)
+        ));
+        assert_eq!(sent(&client).len(), 256);
+        assert_eq!(scheduler.len(), 257);
+        assert_eq!(scheduler.stats(&a).unwrap().queued, 1);
+        assert!(scheduler
+            .confirm(&confirm(sent(&client)[0].1, Status::Success), now)
+            .is_some());
+        assert_eq!(scheduler.poll(&mut client, now).unwrap(), 1);
+        assert_eq!(scheduler.stats(&a).unwrap().queued, 0);
+    }
+
+    #[test]
+    fn scheduler_drops_requests_the_coprocessor_refuses() {
+        use scheduler::{Scheduler, SchedulerConfig, SchedulerEvent};
+        use std::time::Instant;
+        use subsystem::mac::{sreq, srsp};
+        use types::{Address, MACCommandId, MTSubsystem, ShortAddress, Status};
+
+        let request = |address: u16, tag: u8| sreq::DataReq {
+            dest_address: Address::Addr16Bit(ShortAddress::from(address)),
+            dest_pan_id: types::PanId(0x1234),
+            src_address_mode: types::AddressMode::Addr16Bit,
+            handle: 0,
+            tx_option: types::TxOption::ACK,
+            channel: 0,
+            power: 0,
+            security: types::Security::none(),
+            include_fh_ies: 0,
+            data_length: 1,
+            ie_length: 0,
+            data_payload: vec![tag],
+            ie_payload: Vec::new(),
+        };
+        let a = Address::Addr16Bit(ShortAddress::from(0x0001));
+        let b = Address::Addr16Bit(ShortAddress::from(0x0002));
+
+        // The coprocessor refuses the first request and times out on the
+        // second.
+        let mut client = test_util::ScriptedClient::new();
+        client.respond(MTSubsystem::MAC, MACCommandId::DataReq as u8, |frame| {
+            let status = match sreq::DataReq::try_decode(&frame.payload)?.data_payload[0] {
+                1 => Status::InvalidParameter,
+                2 => {
+                    return Err(error::Error::Timeout {
+                        command: frame.header.command.clone(),
+                    })
+                }
+                _ => Status::Success,
+            };
+            Ok(srsp::DataReq { status }.into_mt_frame())
+        });
+
+        let now = Instant::now();
+        let mut scheduler = Scheduler::new(SchedulerConfig::default());
+        let refused = scheduler.enqueue(request(0x0001, 1), now);
+        scheduler.enqueue(request(0x0002, 2), now);
+        scheduler.enqueue(request(0x0001, 3), now);
+
+        // The refused request leaves the queue, the one that timed out stays.
+        assert!(matches!(
+            scheduler.poll(&mut client, now),
+            Err(error::Error::Timeout { .. })
+        ));
+        match scheduler.take_failed().as_slice() {
+            [SchedulerEvent::Failed {
+                id,
+                destination,
+                status,
+                attempts,
+            }] => {
+                assert_eq!(*id, refused);
+                assert_eq!(*destination, a);
+                assert_eq!(*status, Status::InvalidParameter);
+                assert_eq!(*attempts, 1);
+            }
+            events => panic!("unexpected {:?}", events),
+        }
+        assert!(scheduler.take_failed().is_empty());
+        let stats = scheduler.stats(&a).unwrap();
+        assert_eq!((stats.failed, stats.queued, stats.transmissions), (1, 1, 0));
+        assert_eq!(scheduler.stats(&b).unwrap().queued, 1);
+        assert_eq!(scheduler.len(), 2);
+
+        client.respond(MTSubsystem::MAC, MACCommandId::DataReq as u8, |_| {
+            Ok(srsp::DataReq {
+                status: Status::Success,
+            }
+            .into_mt_frame())
+        });
+        assert_eq!(scheduler.poll(&mut client, now).unwrap(), 2);
+        assert_eq!(scheduler.stats(&b).unwrap().transmissions, 1);
+    }
+
+    #[test]
+    fn scheduler_serves_higher_priorities_first() {
+        use scheduler::{Priority, Scheduler, SchedulerConfig};
+        use std::time::Instant;
+        use subsystem::mac::{areq, sreq};
+        use types::{Address, ShortAddress, Status};
+
+        let sent = |client: &test_util::ScriptedClient| {
+            client
+                .decoded(
+                    types::MTSubsystem::MAC,
+                    types::MACCommandId::DataReq as u8,
+                    sreq::DataReq::try_decode,
+                )
+                .into_iter()
+                .map(|x| (x.data_payload[0], x.handle))
+                .collect::<Vec<_>>()
+        };
+
+        let request = |address: u16, tag: u8| sreq::DataReq {
+            dest_address: Address::Addr16Bit(ShortAddress::from(address)),
+            dest_pan_id: types::PanId(0x1234),
+            src_address_mode: types::AddressMode::Addr16Bit,
+            handle: 0,
+            tx_option: types::TxOption::ACK,
+            channel: 0,
+            power: 0,
+            security: types::Security::none(),
+            include_fh_ies: 0,
+            data_length: 1,
+            ie_length: 0,
+            data_payload: vec![tag],
+            ie_payload: Vec::new(),
+        };
+        let confirm = |handle: u8, status: Status| areq::DataCnf {
+            status,
+            handle,
+            timestamp: 0,
+            timestamp2: 0,
+            retries: 0,
+            link_quality: types::Lqi(0xff),
+            correlation: 0,
+            rssi: types::Rssi(-40),
+            frame_counter: 0,
+        };
+
+        let now = Instant::now();
+        let mut client = test_util::ScriptedClient::new();
+        let mut scheduler = Scheduler::new(SchedulerConfig::default());
+        scheduler.enqueue_with_priority(request(0x0001, 1), Priority::Bulk, now);
+        scheduler.enqueue_with_priority(request(0x0001, 2), Priority::Bulk, now);
+        scheduler.enqueue_with_priority(request(0x0002, 3), Priority::Bulk, now);
+        scheduler.enqueue(request(0x0001, 4), now);
+        scheduler.enqueue_with_priority(request(0x0003, 5), Priority::Control, now);
+
+        // Across destinations the class decides, then the arrival.
+        assert_eq!(scheduler.poll(&mut client, now).unwrap(), 3);
+        let tags: Vec<_> = sent(&client).iter().map(|x| x.0).collect();
+        assert_eq!(tags, [5, 4, 3]);
+
+        // Within a destination, a control frame queued behind bulk data
+        // overtakes it.
+        scheduler.enqueue_with_priority(request(0x0001, 6), Priority::Control, now);
+        let handle = sent(&client)[1].1;
+        assert!(scheduler
+            .confirm(&confirm(handle, Status::Success), now)
+            .is_some());
+        assert_eq!(scheduler.poll(&mut client, now).unwrap(), 1);
+        assert_eq!(sent(&client).last().unwrap().0, 6);
+
+        // A bulk retry stays ahead of the other bulk request only.
+        let handle = sent(&client).last().unwrap().1;
+        scheduler.confirm(&confirm(handle, Status::Success), now);
+        assert_eq!(scheduler.poll(&mut client, now).unwrap(), 1);
+        let (tag, handle) = *sent(&client).last().unwrap();
+        assert_eq!(tag, 1);
+        assert!(scheduler
+            .confirm(&confirm(handle, Status::NoAck), now)
+            .is_none());
+        scheduler.enqueue(request(0x0001, 7), now);
+        let later = now + scheduler.config().retry.backoff_after(1);
+        for expected in [7, 1, 2] {
+            assert_eq!(scheduler.poll(&mut client, later).unwrap(), 1);
+            let (tag, handle) = *sent(&client).last().unwrap();
+            assert_eq!(tag, expected);
+            scheduler.confirm(&confirm(handle, Status::Success), later);
+        }
+        assert_eq!(scheduler.len(), 2);
+    }
+
+    #[test]
+    fn logged_client_keeps_recent_events() {
+        use history::{Event, Logged};
+        use subsystem::mac::{areq, sreq, srsp};
+        use types::{MACCommandId, MTSubsystem, Status};
+
+        // Every other request times out.
+        let mut flaky = test_util::ScriptedClient::new();
+        let mut requests = 0;
+        flaky.respond(
+            MTSubsystem::MAC,
+            MACCommandId::ResetReq as u8,
+            move |frame| {
+                requests += 1;
+                if requests % 2 == 0 {
+                    return Err(error::Error::Timeout {
+                        command: frame.header.command.clone(),
+                    });
+                }
+                Ok(srsp::ResetReq {
+                    status: Status::Success,
+                }
+                .into_mt_frame())
+            },
+        );
+
+        let mut client = Logged::new(flaky, 4);
+        let reset = || sreq::ResetReq { set_default: true }.into_mt_frame();
+        client::Client::request(&mut client, reset()).unwrap();
+        let start = std::time::Instant::now();
+        assert!(client::Client::request(&mut client, reset()).is_err());
+        client.received(
+            &areq::StartCnf {
+                status: Status::Success,
+            }
+            .into_mt_frame(),
+        );
+
+        // The first request fell out of the buffer.
+        let history = client.history();
+        assert_eq!(history.len(), 4);
+        assert!(matches!(
+            history.events().next().unwrap().event,
+            Event::Received(_)
+        ));
+        assert_eq!(history.since(start).count(), 3);
+        assert_eq!(
+            history
+                .command(MTSubsystem::MAC, MACCommandId::ResetReq as u8)
+                .count(),
+            3
+        );
+        let failure = history.failures().next().unwrap();
+        assert!(
+            matches!(&failure.event, Event::Failed { error, .. } if error.contains("timed out"))
+        );
+
+        let mut dump = Vec::new();
+        history.dump(&mut dump).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+        let lines: Vec<_> = dump.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].contains("RX  MAC SRSP ResetReq MAC_ResetReq_SRSP"));
+        assert!(lines[1].contains("TX  MAC SREQ ResetReq MAC_ResetReq_SREQ"));
+        assert!(lines[2].contains("ERR MAC SREQ ResetReq MAC SREQ 0x01 timed out"));
+        assert!(lines[3].contains("RX  MAC AREQ StartCnf MAC_StartCnf_AREQ"));
+    }
+
+    #[test]
+    fn status_metadata() {
+        use std::convert::TryFrom;
+        use types::{Status, StatusCategory};
+
+        assert_eq!(Status::try_from(0xe9).unwrap(), Status::NoAck);
+        assert_eq!(Status::NoAck.description(), "no acknowledgement received");
+        assert!(matches!(
+            Status::try_from(0x99),
+            Err(error::Error::InvalidStatus(0x99))
+        ));
+
+        assert_eq!(Status::Success.category(), StatusCategory::Success);
+        assert_eq!(Status::ChannelAccessFailure.category(), StatusCategory::Mac);
+        assert_eq!(Status::FHAPIOutSlot.category(), StatusCategory::Fh);
+        assert_eq!(Status::UnavailableKey.category(), StatusCategory::Security);
+        assert_eq!(Status::RPCCommandIdError.category(), StatusCategory::Rpc);
+        assert_eq!(Status::Unknown(0x99).category(), StatusCategory::Unknown);
+
+        // Every known value has its own description.
+        let descriptions: std::collections::HashSet<_> = (0..=u8::MAX)
+            .map(Status::from_raw)
+            .filter(|x| !x.is_unknown())
+            .map(|x| x.description())
+            .collect();
+        let known = (0..=u8::MAX)
+            .filter(|x| !Status::from_raw(*x).is_unknown())
+            .count();
+        assert_eq!(descriptions.len(), known);
+    }
+
+    #[test]
+    fn decode_values_without_a_cursor() {
+        use std::convert::TryFrom;
+        use types::{
+            Address, AddressMode, CapabilityInfo, ExtendedAddress, FHPIBAttributeId,
+            MACPIBAttributeId, PanId, ScanType, Security, ShortAddress,
+        };
+
+        assert_eq!(AddressMode::try_from(0x02).unwrap(), AddressMode::Addr16Bit);
+        assert!(matches!(
+            AddressMode::try_from(0x07),
+            Err(error::Error::InvalidAddressMode(0x07))
+        ));
+        assert_eq!(ScanType::try_from(0x01).unwrap(), ScanType::Active);
+        assert_eq!(
+            CapabilityInfo::try_from(0x80).unwrap(),
+            CapabilityInfo::ALLOCATE_ADDRESS
+        );
+        assert_eq!(
+            MACPIBAttributeId::try_from(0x53).unwrap(),
+            MACPIBAttributeId::ShortAddress
+        );
+        assert_eq!(
+            FHPIBAttributeId::try_from(0x2000).unwrap(),
+            FHPIBAttributeId::TrackParentEUI
+        );
+
+        assert_eq!(PanId::try_from(&[0x34, 0x12][..]).unwrap(), PanId(0x1234));
+        assert_eq!(
+            ShortAddress::try_from(&[0x01, 0x00][..]).unwrap(),
+            ShortAddress::from(0x0001)
+        );
+        let bytes = [0x03, 1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(
+            Address::try_from(&bytes[..]).unwrap(),
+            Address::Addr64Bit(ExtendedAddress::try_from(&bytes[1..]).unwrap())
+        );
+        assert!(matches!(
+            ShortAddress::try_from(&[0x01][..]),
+            Err(error::Error::NotEnoughBytes)
+        ));
+        assert!(matches!(
+            PanId::try_from(&[0x34, 0x12, 0x00][..]),
+            Err(error::Error::TrailingBytes(1))
+        ));
+
+        let mut buffer = Vec::new();
+        Security::none().encode_into(&mut buffer);
+        assert_eq!(Security::try_from(&buffer[..]).unwrap(), Security::none());
+    }
+
+    #[test]
+    fn values_are_comparable_and_hashable() {
+        use std::collections::{BTreeSet, HashSet};
+        use subsystem::mac::{areq, sreq};
+        use subsystem::MTFramePayload;
+        use types::{Address, ExtendedAddress, MACPIBAttributeId, ShortAddress, Status};
+
+        let mut pib = HashMap::new();
+        pib.insert(MACPIBAttributeId::ShortAddress, [0x00; 16]);
+        pib.insert(MACPIBAttributeId::PANId, [0x01; 16]);
+        assert_eq!(pib[&MACPIBAttributeId::PANId], [0x01; 16]);
+
+        let addresses: BTreeSet<_> = vec![
+            Address::Addr64Bit(ExtendedAddress::from(1)),
+            Address::Addr16Bit(ShortAddress::from(2)),
+            Address::Addr16Bit(ShortAddress::from(1)),
+            Address::None,
+        ]
+        .into_iter()
+        .collect();
+        let first = addresses.iter().next().unwrap();
+        assert_eq!(*first, Address::None);
+        assert!(ShortAddress::from(1) < ShortAddress::from(2));
+
+        // Fixed size payloads are Copy, the others Clone, and all of them
+        // compare and hash.
+        let confirm = areq::StartCnf {
+            status: Status::Success,
+        };
+        let copy = confirm;
+        assert_eq!(confirm, copy);
+        let request = sreq::ResetReq { set_default: true };
+        let frame = request.into_mt_frame();
+        let payloads: HashSet<_> = vec![
+            MTFramePayload::try_from_frame(&frame).unwrap(),
+            MTFramePayload::try_from_frame(&frame.clone()).unwrap(),
+            MTFramePayload::try_from_frame(&copy.into_mt_frame()).unwrap(),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(payloads.len(), 2);
+        assert_eq!(frame, request.into_mt_frame());
+    }
+
+    #[cfg(feature = "security-tables")]
+    #[test]
+    fn security_pib_entries_round_trip() {
+        use security::{
+            KeyDescriptor, KeyDeviceEntry, KeyEntry, KeyLookup, KeyTable, KeyUsage,
+            SecurityLevelEntry,
+        };
+        use types::SecurityPIBAttributeId::{KeyIdDeviceEntry, SecurityLevelEntry as LevelId};
+
+        let device = KeyDeviceEntry {
+            device_handle: 0x0102,
+            unique_device: true,
+            blacklisted: false,
+        };
+        let mut buffer = Vec::new();
+        device.encode_into(&mut buffer);
+        assert_eq!(buffer, [0x02, 0x01, 0x01, 0x00]);
+        assert_eq!(
+            KeyDeviceEntry::try_decode(&mut Reader::new(buffer.as_slice())).unwrap(),
+            device
+        );
+
+        let entry = KeyEntry {
+            key: [0xaa; 16],
+            frame_counter: 0x01020304,
+        };
+        let mut buffer = Vec::new();
+        entry.encode_into(&mut buffer);
+        assert_eq!(buffer[16..], [0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(
+            KeyEntry::try_decode(&mut Reader::new(buffer.as_slice())).unwrap(),
+            entry
+        );
+
+        let lookup = KeyLookup {
+            data: [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09],
+            size: 0x01,
+        };
+        let mut buffer = Vec::new();
+        lookup.encode_into(&mut buffer);
+        assert_eq!(
+            KeyLookup::try_decode(&mut Reader::new(buffer.as_slice())).unwrap(),
+            lookup
+        );
+        assert!(KeyLookup::try_decode(&mut Reader::new(&buffer[..9])).is_err());
+
+        let usage = KeyUsage {
+            frame_type: 0x03,
+            command_frame_id: 0x01,
+        };
+        let mut buffer = Vec::new();
+        usage.encode_into(&mut buffer);
+        assert_eq!(
+            KeyUsage::try_decode(&mut Reader::new(buffer.as_slice())).unwrap(),
+            usage
+        );
+
+        let level = SecurityLevelEntry {
+            frame_type: 0x01,
+            command_frame_id: 0x00,
+            security_minimum: 0x05,
+            override_minimum: true,
+        };
+        let request = level.set_request(2);
+        assert_eq!(request.attribute_id, LevelId);
+        assert_eq!(request.index1, 2);
+        assert_eq!(request.attribute_value, [0x01, 0x00, 0x05, 0x01]);
+        assert_eq!(
+            SecurityLevelEntry::try_decode(&mut Reader::new(request.attribute_value.as_slice()))
+                .unwrap(),
+            level
+        );
+
+        // Key device entries are written after the lookups, indexed by key.
+        let mut table = KeyTable::new(4);
+        let requests = table
+            .install(
+                3,
+                KeyDescriptor {
+                    key: entry.key,
+                    frame_counter: entry.frame_counter,
+                    lookups: vec![lookup],
+                    devices: vec![device, device],
+                    usages: vec![usage],
+                },
+            )
+            .unwrap();
+        let devices: Vec<_> = requests
+            .iter()
+            .filter(|x| x.attribute_id == KeyIdDeviceEntry)
+            .collect();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(requests[2].attribute_id, KeyIdDeviceEntry);
+        assert_eq!((devices[1].index1, devices[1].index2), (3, 1));
+        assert_eq!(table.get(3).unwrap().entry(), entry);
+    }
+
+    #[test]
+    fn reassembler_rejects_overlapping_fragments() {
+        use fragment::Reassembler;
+        use types::{Address, ShortAddress};
+
+        let start = Instant::now();
+        let source = Address::Addr16Bit(ShortAddress::from(0x0001));
+        let mut reassembler = Reassembler::new(Duration::from_secs(5));
+
+        // 24 bytes: 0..16 and 8..24 would add up to 32 with bytes 16..24 of
+        // the first one never received.
+        let mut first = vec![0xc0, 24, 0x00, 0x07];
+        first.extend([0x01; 16].iter());
+        let mut overlapping = vec![0xe0, 24, 0x00, 0x07, 1];
+        overlapping.extend([0x02; 16].iter());
+        assert_eq!(reassembler.receive(source, &first, start).unwrap(), None);
+        assert!(matches!(
+            reassembler.receive(source, &overlapping, start),
+            Err(error::Error::InvalidFragment)
+        ));
+        assert!(reassembler.is_empty());
+
+        // The same from the other side, with the last fragment first.
+        let mut last = vec![0xe0, 24, 0x00, 0x08, 2];
+        last.extend([0x03; 8].iter());
+        let mut second = vec![0xe0, 24, 0x00, 0x08, 1];
+        second.extend([0x02; 16].iter());
+        assert_eq!(reassembler.receive(source, &last, start).unwrap(), None);
+        assert!(reassembler.receive(source, &second, start).is_err());
+
+        // Fragments that only touch complete the payload.
+        let mut first = vec![0xc0, 24, 0x00, 0x09];
+        first.extend([0x01; 16].iter());
+        let mut last = vec![0xe0, 24, 0x00, 0x09, 2];
+        last.extend([0x03; 8].iter());
+        assert_eq!(reassembler.receive(source, &last, start).unwrap(), None);
+        let payload = reassembler.receive(source, &first, start).unwrap().unwrap();
+        assert_eq!(payload[..16], [0x01; 16]);
+        assert_eq!(payload[16..], [0x03; 8]);
+    }
+
+    #[test]
+    fn auto_baud_finds_the_coprocessor_rate() {
+        use std::io::{self, Read, Write};
+        use subsystem::sys::srsp;
+        use transport::{FlowControl, FlushPolicy, FrameWriter, SerialConfig, SerialPort, Wakeup};
+
+        // Answers SYS_PING only at 230400 baud, anything else is noise.
+        #[derive(Default)]
+        struct Port {
+            config: SerialConfig,
+            written: Vec<(u32, Vec<u8>)>,
+            pending: Vec<u8>,
+        }
+
+        impl Write for Port {
+            fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+                self.written.push((self.config.baud_rate, buffer.to_vec()));
+                if buffer.first() != Some(&0xfe) {
+                    return Ok(buffer.len());
+                }
+                if self.config.baud_rate == 230400 {
+                    self.pending.extend(
+                        srsp::PingReq {
+                            capabilities: 0x0003,
+                        }
+                        .into_mt_frame()
+                        .encode_to_uart_transport_frame(),
+                    );
+                } else {
+                    self.pending.extend([0xf0, 0x0f].iter());
+                }
+                Ok(buffer.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        impl Read for Port {
+            fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+                if self.pending.is_empty() {
+                    return Err(io::ErrorKind::TimedOut.into());
+                }
+                let count = self.pending.len().min(buffer.len());
+                buffer[..count].copy_from_slice(&self.pending[..count]);
+                self.pending.drain(..count);
+                Ok(count)
+            }
+        }
+
+        impl SerialPort for Port {
+            fn configure(&mut self, config: &SerialConfig) -> io::Result<()> {
+                self.config = *config;
+                Ok(())
+            }
+        }
+
+        let mut writer = FrameWriter::new(Port::default(), FlushPolicy::Immediate);
+        let mut wakeup = Wakeup::new(vec![0x00], Duration::ZERO, Duration::from_secs(60));
+        wakeup.sleep = |_| ();
+        writer.set_wakeup(Some(wakeup));
+
+        let config = writer
+            .auto_baud(
+                &[460800, 115200, 230400, 9600],
+                FlowControl::RtsCts,
+                Duration::from_millis(20),
+            )
+            .unwrap();
+        assert_eq!(config, SerialConfig::new(230400, FlowControl::RtsCts));
+        assert_eq!(writer.get_ref().config, config);
+
+        // Each rate got the preamble and a ping, the rate answered ends it.
+        let rates: Vec<_> = writer.get_ref().written.iter().map(|x| x.0).collect();
+        assert_eq!(rates, [460800, 460800, 115200, 115200, 230400, 230400]);
+        assert!(writer
+            .get_ref()
+            .written
+            .iter()
+            .step_by(2)
+            .all(|x| x.1 == [0x00]));
+
+        // Without flow control only the slower rates are tried.
+        let mut writer = FrameWriter::new(Port::default(), FlushPolicy::Manual);
+        let error = writer
+            .auto_baud(
+                &[460800, 230400, 115200],
+                FlowControl::None,
+                Duration::from_millis(20),
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        let rates: Vec<_> = writer.get_ref().written.iter().map(|x| x.0).collect();
+        assert_eq!(rates, [115200]);
+    }
+
+    #[test]
+    fn security_key_bindings_follow_device_removal() {
+        use security::{DeviceEntry, DeviceTable, KeyDescriptor, KeyDeviceEntry, KeyTable};
+        use types::SecurityPIBAttributeId::KeyIdDeviceEntry;
+
+        let device = |address: u16| DeviceEntry {
+            pan_id: types::PanId(0xabcd),
+            short_address: types::ShortAddress::from(address),
+            ext_address: types::ExtendedAddress::from(u64::from(address)),
+            frame_counter: 0,
+            exempt: false,
+        };
+        let binding = |device_handle: u16| KeyDeviceEntry {
+            device_handle,
+            unique_device: true,
+            blacklisted: false,
+        };
+
+        let mut devices = DeviceTable::new(3);
+        for address in 1..=3 {
+            devices.insert(device(address)).unwrap();
+        }
+        let mut keys = KeyTable::new(1);
+        keys.install(
+            0,
+            KeyDescriptor {
+                key: [0x11; 16],
+                frame_counter: 0,
+                lookups: vec![],
+                devices: vec![binding(0), binding(1), binding(2)],
+                usages: vec![],
+            },
+        )
+        .unwrap();
+
+        // Device 1 at index 0 goes away, device 3 moves from index 2 to 0.
+        let removal = devices.remove(&device(1).ext_address).unwrap();
+        let requests = keys.device_removed(&removal);
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|x| x.attribute_id == KeyIdDeviceEntry));
+
+        assert_eq!((requests[0].index1, requests[0].index2), (0, 0));
+        let mut cursor = Reader::new(&requests[0].attribute_value[..]);
+        assert_eq!(
+            KeyDeviceEntry::try_decode(&mut cursor).unwrap(),
+            KeyDeviceEntry::UNUSED
+        );
+
+        assert_eq!((requests[1].index1, requests[1].index2), (0, 2));
+        let mut cursor = Reader::new(&requests[1].attribute_value[..]);
+        assert_eq!(KeyDeviceEntry::try_decode(&mut cursor).unwrap(), binding(0));
+
+        // Every live binding still names the device it was made for.
+        let key = keys.get(0).unwrap();
+        assert_eq!(devices.get(key.devices[1].device_handle), Some(&device(2)));
+        assert_eq!(devices.get(key.devices[2].device_handle), Some(&device(3)));
+    }
+
+    #[test]
+    fn beacon_notify_ind_length_follows_encoding() {
+        use subsystem::mac::areq::{BeaconNotifyInd, StandardBeaconFrame};
+
+        let beacon = |sdu_length: usize| {
+            BeaconNotifyInd::StandardFrame(StandardBeaconFrame {
+                bsn: 0,
+                pan_descriptor: types::PanDescriptor {
+                    timestamp: 0,
+                    coord_address: types::Address::Addr16Bit(types::ShortAddress::from(0x0000)),
+                    pan_id: types::PanId(0x1234),
+                    superframe_spec: types::SuperframeSpec::from_u16(0xc0ff),
+                    logical_channel: 3,
+                    channel_page: 9,
+                    gts_permit: false,
+                    link_quality: 0xff,
+                    security_failure: false,
+                    security: types::Security::none(),
+                },
+                short_addr_list: vec![types::ShortAddress::from(0x0001)],
+                ext_addr_list: Vec::new(),
+                sdu: vec![0x00; sdu_length],
+            })
+        };
+
+        let frame = beacon(16).into_mt_frame();
+        assert_eq!(frame.header.length as usize, frame.payload.len());
+
+        let frame = beacon(255).into_mt_frame();
+        assert!(frame.header.command.is_extended);
+        assert!(matches!(
+            frame.extended_header,
+            Some(frame::MTExtendedHeader::V2 { packet_length, .. })
+                if packet_length as usize == frame.payload.len()
+        ));
+
+        // The SDU count is one byte, what does not fit it is left out.
+        let encoded = beacon(300).encode();
+        assert_eq!(encoded, beacon(255).encode());
+        assert_eq!(BeaconNotifyInd::try_decode(&encoded).unwrap(), beacon(255));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips_decoded_frames() {
+        use types::NetName;
+
+        let data = [0x6, 0x41, 0x80, 0x0, 0x3, 0x1, 0x2, 0x2, 0x0];
+        let frame = frame::MTFrame::try_decode(&mut Reader::new(&data[..])).unwrap();
+        let json = serde_json::to_string(&frame).unwrap();
+        let decoded: frame::MTFrame = serde_json::from_str(&json).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", frame));
+
+        let payload = subsystem::sys::areq::ResetInd::try_decode(frame.payload.as_slice()).unwrap();
+        let json = serde_json::to_string(&payload).unwrap();
+        assert!(json.contains("\"Hardware\""));
+        let decoded: subsystem::sys::areq::ResetInd = serde_json::from_str(&json).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", payload));
+
+        let name = NetName::new("wisun-fan").unwrap();
+        assert_eq!(serde_json::to_string(&name).unwrap(), "\"wisun-fan\"");
+        assert!(
+            serde_json::from_str::<NetName>("\"a-network-name-longer-than-32-bytes\"").is_err()
+        );
+    }
+
+    #[cfg(feature = "defmt")]
+    #[test]
+    fn defmt_formats_decoded_frames() {
+        // Logging needs a global logger, only check the impls are there.
+        fn format<T: defmt::Format>() {}
+
+        format::<frame::MTFrame>();
+        format::<subsystem::MTFramePayload>();
+        format::<types::Address>();
+        format::<types::NetName>();
+        format::<types::TxOption>();
+        format::<error::FhError>();
     }
 }
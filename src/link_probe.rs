@@ -0,0 +1,184 @@
+use crate::client::{self, Client};
+use crate::error::Error;
+use crate::frame::MTFrame;
+#[cfg(feature = "sys")]
+use crate::subsystem::sys;
+#[cfg(feature = "util")]
+use crate::subsystem::util;
+use crate::types::MTSubsystem;
+#[cfg(feature = "sys")]
+use crate::types::SYSCommandId;
+#[cfg(feature = "util")]
+use crate::types::UTILCommandId;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ProbeMethod {
+    // UTIL_LOOPBACK echoing the pattern, checks the payload makes it through.
+    #[cfg(feature = "util")]
+    Loopback,
+    // SYS_PING, the smallest round trip the coprocessor answers.
+    #[cfg(feature = "sys")]
+    Ping,
+}
+
+#[derive(Debug, Clone)]
+pub struct LinkProbeConfig {
+    pub method: ProbeMethod,
+    pub interval: Duration,
+    // Passed to `Client::request_with_timeout` for every probe.
+    pub timeout: Duration,
+    // Consecutive failed probes before the link counts as down.
+    pub max_failures: u32,
+    // Loopback payload, prefixed with a sequence number so a late echo of an
+    // earlier probe is not taken for the current one.
+    pub pattern: Vec<u8>,
+}
+
+impl Default for LinkProbeConfig {
+    fn default() -> Self {
+        LinkProbeConfig {
+            #[cfg(feature = "util")]
+            method: ProbeMethod::Loopback,
+            #[cfg(not(feature = "util"))]
+            method: ProbeMethod::Ping,
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(1),
+            max_failures: 3,
+            pattern: vec![0x55, 0xaa, 0x00, 0xff],
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct LinkHealth {
+    pub probes: u32,
+    pub failures: u32,
+    pub consecutive_failures: u32,
+    pub last_rtt: Option<Duration>,
+    pub min_rtt: Option<Duration>,
+    pub max_rtt: Option<Duration>,
+}
+
+pub type RttCallback = Box<dyn FnMut(Duration)>;
+pub type FailureCallback = Box<dyn FnMut(&Error, &LinkHealth)>;
+
+/// Supervises the serial link by sending a probe every `interval` and
+/// measuring its round trip. Meant for adapters that silently stop
+/// answering, the failure callback decides when to reopen or reset them.
+pub struct LinkProbe {
+    config: LinkProbeConfig,
+    health: LinkHealth,
+    sequence: u8,
+    next_probe: Option<Instant>,
+    on_rtt: Option<RttCallback>,
+    on_failure: Option<FailureCallback>,
+}
+
+impl LinkProbe {
+    pub fn new(config: LinkProbeConfig) -> LinkProbe {
+        LinkProbe {
+            config,
+            health: LinkHealth::default(),
+            sequence: 0,
+            next_probe: None,
+            on_rtt: None,
+            on_failure: None,
+        }
+    }
+
+    /// Called with the round trip time of every successful probe.
+    pub fn on_rtt<F: FnMut(Duration) + 'static>(&mut self, callback: F) {
+        self.on_rtt = Some(Box::new(callback));
+    }
+
+    /// Called for every failed probe, after the health counters are updated.
+    pub fn on_failure<F: FnMut(&Error, &LinkHealth) + 'static>(&mut self, callback: F) {
+        self.on_failure = Some(Box::new(callback));
+    }
+
+    pub fn health(&self) -> LinkHealth {
+        self.health
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.health.consecutive_failures < self.config.max_failures
+    }
+
+    /// Probes the link if `interval` elapsed since the last probe, the first
+    /// call always probes.
+    pub fn poll<C: Client>(
+        &mut self,
+        client: &mut C,
+        now: Instant,
+    ) -> Option<Result<Duration, Error>> {
+        match self.next_probe {
+            Some(next_probe) if now < next_probe => None,
+            _ => {
+                self.next_probe = Some(now + self.config.interval);
+                Some(self.probe(client))
+            }
+        }
+    }
+
+    pub fn probe<C: Client>(&mut self, client: &mut C) -> Result<Duration, Error> {
+        self.sequence = self.sequence.wrapping_add(1);
+        self.health.probes += 1;
+
+        let sent = Instant::now();
+        let result = self.round_trip(client).map(|_| sent.elapsed());
+
+        match &result {
+            Ok(rtt) => {
+                self.health.consecutive_failures = 0;
+                self.health.last_rtt = Some(*rtt);
+                self.health.min_rtt = Some(self.health.min_rtt.map_or(*rtt, |x| x.min(*rtt)));
+                self.health.max_rtt = Some(self.health.max_rtt.map_or(*rtt, |x| x.max(*rtt)));
+                if let Some(callback) = self.on_rtt.as_mut() {
+                    callback(*rtt);
+                }
+            }
+            Err(error) => {
+                self.health.failures += 1;
+                self.health.consecutive_failures += 1;
+                if let Some(callback) = self.on_failure.as_mut() {
+                    callback(error, &self.health);
+                }
+            }
+        }
+        result
+    }
+
+    fn round_trip<C: Client>(&mut self, client: &mut C) -> Result<(), Error> {
+        match self.config.method {
+            #[cfg(feature = "util")]
+            ProbeMethod::Loopback => {
+                let mut data = vec![self.sequence];
+                data.extend_from_slice(&self.config.pattern);
+                let request = util::sreq::Loopback {
+                    repeats: 0,
+                    interval: 0,
+                    data,
+                };
+                let expected = request.data.clone();
+                let frame = self.request(client, request.into_mt_frame())?;
+                client::expect_response(&frame, MTSubsystem::UTIL, UTILCommandId::Loopback as u8)?;
+                if util::srsp::Loopback::try_decode(&frame.payload)?.data != expected {
+                    return Err(Error::UnexpectedResponse(frame));
+                }
+                Ok(())
+            }
+            #[cfg(feature = "sys")]
+            ProbeMethod::Ping => {
+                let frame = self.request(client, sys::sreq::PingReq {}.into_mt_frame())?;
+                client::expect_response(&frame, MTSubsystem::SYS, SYSCommandId::PingReq as u8)?;
+                sys::srsp::PingReq::try_decode(&frame.payload)?;
+                Ok(())
+            }
+        }
+    }
+
+    fn request<C: Client>(&self, client: &mut C, frame: MTFrame) -> Result<MTFrame, Error> {
+        client.request_with_timeout(frame, self.config.timeout)
+    }
+}
@@ -0,0 +1,199 @@
+use crate::frame::MTFrame;
+use crate::subsystem::mac::{areq, sreq};
+use crate::types::{CommandType, MACCommandId, MTSubsystem};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// Values below 2^SUB_BUCKET_BITS are recorded exactly, larger values keep
+// SUB_BUCKET_BITS - 1 significant bits (around 6% worst-case error).
+const SUB_BUCKET_BITS: u32 = 5;
+const SUB_BUCKET_COUNT: u64 = 1 << SUB_BUCKET_BITS;
+const SUB_BUCKET_HALF_COUNT: u64 = SUB_BUCKET_COUNT / 2;
+
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    counts: Vec<u64>,
+    count: u64,
+    total: u64,
+    min: u64,
+    max: u64,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> LatencyHistogram {
+        LatencyHistogram::default()
+    }
+
+    pub fn record(&mut self, latency: Duration) {
+        let value = latency.as_micros().min(u64::MAX as u128) as u64;
+        let index = Self::bucket_index(value);
+
+        if self.counts.len() <= index {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+
+        self.min = if self.count == 0 {
+            value
+        } else {
+            self.min.min(value)
+        };
+        self.max = self.max.max(value);
+        self.total = self.total.saturating_add(value);
+        self.count += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        self.non_empty(self.min)
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.non_empty(self.max)
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        self.non_empty(self.total / self.count.max(1))
+    }
+
+    /// Returns the latency below which `percentile` percent of the recorded
+    /// samples fall, within the precision of the bucket the sample landed in.
+    pub fn value_at_percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let percentile = percentile.clamp(0.0, 100.0);
+        let target = ((percentile / 100.0) * self.count as f64).ceil().max(1.0) as u64;
+
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                let value = Self::bucket_highest_value(index).min(self.max);
+                return Some(Duration::from_micros(value));
+            }
+        }
+
+        self.max()
+    }
+
+    pub fn reset(&mut self) {
+        *self = LatencyHistogram::default();
+    }
+
+    fn non_empty(&self, micros: u64) -> Option<Duration> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(Duration::from_micros(micros))
+        }
+    }
+
+    fn bucket_index(value: u64) -> usize {
+        if value < SUB_BUCKET_COUNT {
+            return value as usize;
+        }
+
+        let magnitude = 63 - value.leading_zeros();
+        let shift = magnitude - (SUB_BUCKET_BITS - 1);
+        let top = value >> shift;
+        (SUB_BUCKET_COUNT + (shift as u64 - 1) * SUB_BUCKET_HALF_COUNT + top
+            - SUB_BUCKET_HALF_COUNT) as usize
+    }
+
+    fn bucket_highest_value(index: usize) -> u64 {
+        let index = index as u64;
+        if index < SUB_BUCKET_COUNT {
+            return index;
+        }
+
+        let offset = index - SUB_BUCKET_COUNT;
+        let shift = offset / SUB_BUCKET_HALF_COUNT + 1;
+        let top = SUB_BUCKET_HALF_COUNT + offset % SUB_BUCKET_HALF_COUNT;
+        (top << shift) + ((1 << shift) - 1)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    response_latencies: HashMap<(MTSubsystem, u8), LatencyHistogram>,
+    data_confirm_latency: LatencyHistogram,
+    pending_requests: HashMap<(MTSubsystem, u8), Instant>,
+    pending_data_requests: HashMap<u8, Instant>,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    pub fn frame_sent(&mut self, frame: &MTFrame, at: Instant) {
+        let command = &frame.header.command;
+        if command.cmd_type != CommandType::SREQ {
+            return;
+        }
+
+        self.pending_requests
+            .insert((command.subsystem, command.id), at);
+
+        if command.subsystem == MTSubsystem::MAC && command.id == MACCommandId::DataReq as u8 {
+            if let Ok(request) = sreq::DataReq::try_decode(&frame.payload) {
+                self.pending_data_requests.insert(request.handle, at);
+            }
+        }
+    }
+
+    pub fn frame_received(&mut self, frame: &MTFrame, at: Instant) {
+        let command = &frame.header.command;
+        match command.cmd_type {
+            CommandType::SRSP => {
+                let key = (command.subsystem, command.id);
+                if let Some(sent_at) = self.pending_requests.remove(&key) {
+                    self.response_latencies
+                        .entry(key)
+                        .or_default()
+                        .record(at.saturating_duration_since(sent_at));
+                }
+            }
+            CommandType::AREQ
+                if command.subsystem == MTSubsystem::MAC
+                    && command.id == MACCommandId::DataCnf as u8 =>
+            {
+                if let Ok(confirm) = areq::DataCnf::try_decode(&frame.payload) {
+                    if let Some(sent_at) = self.pending_data_requests.remove(&confirm.handle) {
+                        self.data_confirm_latency
+                            .record(at.saturating_duration_since(sent_at));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// SREQ to SRSP latency of the given command.
+    pub fn response_latency(&self, subsystem: MTSubsystem, id: u8) -> Option<&LatencyHistogram> {
+        self.response_latencies.get(&(subsystem, id))
+    }
+
+    pub fn response_latencies(&self) -> impl Iterator<Item = (MTSubsystem, u8, &LatencyHistogram)> {
+        self.response_latencies
+            .iter()
+            .map(|((subsystem, id), histogram)| (*subsystem, *id, histogram))
+    }
+
+    /// MAC_DATA_REQ to MAC_DATA_CNF latency, matched by MSDU handle.
+    pub fn data_confirm_latency(&self) -> &LatencyHistogram {
+        &self.data_confirm_latency
+    }
+
+    pub fn reset(&mut self) {
+        self.response_latencies.clear();
+        self.data_confirm_latency.reset();
+        self.pending_requests.clear();
+        self.pending_data_requests.clear();
+    }
+}
@@ -0,0 +1,108 @@
+use crate::frame::MTFrame;
+use crate::subsystem::mac::sreq::DataReq;
+use crate::types::{CommandType, MACCommandId, MTSubsystem, PhyId};
+use std::time::{Duration, Instant};
+
+// Long inter-frame spacing of IEEE 802.15.4, in symbols.
+const LIFS_SYMBOLS: u64 = 40;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct PhyTiming {
+    // Over the air data rate, in bits per second.
+    pub data_rate: u32,
+    // Bytes sent over the air on top of the MSDU: preamble, PHY and MAC headers.
+    pub overhead_bytes: u32,
+    pub inter_frame_spacing: Duration,
+}
+
+impl PhyTiming {
+    pub fn new(data_rate: u32, overhead_bytes: u32) -> PhyTiming {
+        let inter_frame_spacing =
+            Duration::from_micros(LIFS_SYMBOLS * 1_000_000 / u64::from(data_rate.max(1)));
+        PhyTiming {
+            data_rate,
+            overhead_bytes,
+            inter_frame_spacing,
+        }
+    }
+
+    pub fn for_phy(phy_id: PhyId) -> Option<PhyTiming> {
+        use PhyId::*;
+        let data_rate = match phy_id {
+            STD_US_915_PHY_1 | STD_ETSI_863_PHY_3 | GENERIC_CHINA_433_PHY_128 => 50_000,
+            GENERIC_US_LRM_915_PHY_129
+            | GENERIC_CHINA_LRM_433_PHY_130
+            | GENERIC_ETSI_LRM_863_PHY_131 => 5_000,
+            GENERIC_US_915_PHY_132 | GENERIC_ETSI_863_PHY_133 => 200_000,
+            _ => return None,
+        };
+        Some(PhyTiming::new(data_rate, 25))
+    }
+
+    pub fn air_time(&self, msdu_length: usize) -> Duration {
+        let bits = (msdu_length as u64 + u64::from(self.overhead_bytes)) * 8;
+        Duration::from_micros(bits * 1_000_000 / u64::from(self.data_rate.max(1)))
+    }
+}
+
+/// Spaces out data requests so the radio is not handed a new frame before the
+/// previous one could have left the air.
+#[derive(Debug)]
+pub struct Pacer {
+    timing: PhyTiming,
+    next_transmission: Option<Instant>,
+}
+
+impl Pacer {
+    pub fn new(timing: PhyTiming) -> Pacer {
+        Pacer {
+            timing,
+            next_transmission: None,
+        }
+    }
+
+    pub fn timing(&self) -> PhyTiming {
+        self.timing
+    }
+
+    pub fn set_timing(&mut self, timing: PhyTiming) {
+        self.timing = timing;
+    }
+
+    /// Time to wait before the next transmission may be handed to the radio.
+    pub fn delay(&self, now: Instant) -> Duration {
+        match self.next_transmission {
+            Some(next_transmission) => next_transmission.saturating_duration_since(now),
+            None => Duration::from_secs(0),
+        }
+    }
+
+    pub fn is_ready(&self, now: Instant) -> bool {
+        self.delay(now) == Duration::from_secs(0)
+    }
+
+    pub fn on_transmit(&mut self, msdu_length: usize, now: Instant) {
+        let start = match self.next_transmission {
+            Some(next_transmission) if next_transmission > now => next_transmission,
+            _ => now,
+        };
+        let busy = self.timing.air_time(msdu_length) + self.timing.inter_frame_spacing;
+        self.next_transmission = Some(start + busy);
+    }
+
+    /// Accounts for a frame sent to the device. Only data requests occupy the air.
+    pub fn on_frame_sent(&mut self, frame: &MTFrame, now: Instant) {
+        let command = &frame.header.command;
+        if command.cmd_type != CommandType::SREQ
+            || command.subsystem != MTSubsystem::MAC
+            || command.id != MACCommandId::DataReq as u8
+        {
+            return;
+        }
+
+        if let Ok(request) = DataReq::try_decode(&frame.payload) {
+            let length = request.data_payload.len() + request.ie_payload.len();
+            self.on_transmit(length, now);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::error::Error;
-use crate::frame::{MTFrame, MTHeader};
-use std::io::Cursor;
+use crate::frame::{MTFrame, MTHeader, Reassembler};
+use crate::reader::Reader;
+use alloc::vec::Vec;
 
 const START_OF_FRAME_TOKEN: u8 = 0xfe;
 
@@ -17,6 +18,13 @@ enum State {
 pub struct Parser {
     buffer: Vec<u8>,
     state: State,
+    reassembler: Reassembler,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Parser {
@@ -24,9 +32,12 @@ impl Parser {
         Parser {
             buffer: Vec::new(),
             state: State::WaitingStartOfFrame,
+            reassembler: Reassembler::new(),
         }
     }
 
+    /// Returns a frame once its last byte is fed. Blocks of an extended frame
+    /// are only returned once joined.
     pub fn feed(&mut self, new_byte: u8) -> Option<Result<MTFrame, Error>> {
         match self.state {
             State::WaitingStartOfFrame => {
@@ -56,21 +67,65 @@ impl Parser {
                     Err(Error::InvalidFrameCheckSequence(self.buffer.clone()))
                 };
 
-                self.reset();
-                return Some(result);
+                #[cfg(feature = "tracing")]
+                match &result {
+                    Ok(Some(frame)) => tracing::trace!(
+                        command = ?frame.header.command,
+                        length = frame.payload.len(),
+                        "frame received"
+                    ),
+                    Ok(None) => (),
+                    Err(error) => {
+                        tracing::debug!(%error, "decode failed: {:02x?}", self.buffer)
+                    }
+                }
+
+                self.buffer.clear();
+                self.state = State::WaitingStartOfFrame;
+                if result.is_err() {
+                    self.reassembler.reset();
+                }
+                return result.transpose();
             }
         }
 
         None
     }
 
+    /// Same as feeding every byte of `bytes`, but copies frame bodies at once.
+    pub fn feed_slice(&mut self, bytes: &[u8]) -> Vec<Result<MTFrame, Error>> {
+        let mut results = Vec::new();
+        let mut index = 0;
+        while index < bytes.len() {
+            if let (State::GatheringMTFrameBytes, Some(length)) = (&self.state, self.buffer.first())
+            {
+                // Leave the last byte of the frame to `feed`, which moves on
+                // to the frame check sequence.
+                let missing = MTHeader::size() + *length as usize - self.buffer.len();
+                let count = missing.saturating_sub(1).min(bytes.len() - index);
+                if count > 0 {
+                    self.buffer.extend_from_slice(&bytes[index..index + count]);
+                    index += count;
+                    continue;
+                }
+            }
+            if let Some(result) = self.feed(bytes[index]) {
+                results.push(result);
+            }
+            index += 1;
+        }
+        results
+    }
+
     pub fn reset(&mut self) {
         self.buffer.clear();
         self.state = State::WaitingStartOfFrame;
+        self.reassembler.reset();
     }
 
-    fn parse_frame(&self) -> Result<MTFrame, Error> {
-        let mut cursor = Cursor::new(self.buffer.as_slice());
-        MTFrame::try_decode(&mut cursor)
+    fn parse_frame(&mut self) -> Result<Option<MTFrame>, Error> {
+        let mut cursor = Reader::new(self.buffer.as_slice());
+        let frame = MTFrame::try_decode(&mut cursor)?;
+        self.reassembler.push(frame)
     }
 }
@@ -0,0 +1,253 @@
+#[cfg(any(feature = "fh", feature = "security-tables"))]
+use crate::client::Confirm;
+use crate::client::{self, Client};
+use crate::error::Error;
+#[cfg(any(feature = "fh", feature = "security-tables"))]
+use crate::subsystem::mac::{sreq, srsp};
+#[cfg(feature = "security-tables")]
+use crate::types::SecurityPIBAttributeId;
+use crate::types::{ExtendedAddress, MACPIBAttributeId, PanId, PhyId, PibAttribute, ShortAddress};
+#[cfg(feature = "fh")]
+use crate::types::{FHPIBAttributeId, FhSchedule, NetName};
+#[cfg(any(feature = "fh", feature = "security-tables"))]
+use crate::types::{MACCommandId, MTSubsystem};
+use alloc::boxed::Box;
+#[cfg(feature = "fh")]
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum PibWrite {
+    Mac(MACPIBAttributeId, [u8; 16]),
+    #[cfg(feature = "fh")]
+    Fh(FHPIBAttributeId, Vec<u8>),
+    #[cfg(feature = "security-tables")]
+    Security {
+        attribute_id: SecurityPIBAttributeId,
+        index1: u16,
+        index2: u16,
+        value: Vec<u8>,
+    },
+}
+
+impl PibWrite {
+    pub fn attribute(&self) -> PibAttribute {
+        match self {
+            PibWrite::Mac(attribute_id, _) => PibAttribute::Mac(*attribute_id),
+            #[cfg(feature = "fh")]
+            PibWrite::Fh(attribute_id, _) => PibAttribute::Fh(*attribute_id),
+            #[cfg(feature = "security-tables")]
+            PibWrite::Security { attribute_id, .. } => PibAttribute::Security(*attribute_id),
+        }
+    }
+
+    pub fn apply<C: Client>(&self, client: &mut C) -> Result<(), Error> {
+        match self {
+            PibWrite::Mac(attribute_id, value) => {
+                client::set_mac_pib(client, *attribute_id, *value)
+            }
+            #[cfg(feature = "fh")]
+            PibWrite::Fh(attribute_id, data) => {
+                let request = sreq::FHSetReq {
+                    attribute_id: *attribute_id,
+                    data: data.clone(),
+                };
+                let frame = client.request(request.into_mt_frame())?;
+                client::expect_response(&frame, MTSubsystem::MAC, MACCommandId::FHSetReq as u8)?;
+                srsp::FHSetReq::try_decode(&frame.payload)?.check()?;
+                Ok(())
+            }
+            #[cfg(feature = "security-tables")]
+            PibWrite::Security {
+                attribute_id,
+                index1,
+                index2,
+                value,
+            } => {
+                let request = sreq::SecuritySetReq {
+                    attribute_id: *attribute_id,
+                    index1: *index1,
+                    index2: *index2,
+                    attribute_value: value.clone(),
+                };
+                let frame = client.request(request.into_mt_frame())?;
+                let id = MACCommandId::SecuritySetReq as u8;
+                client::expect_response(&frame, MTSubsystem::MAC, id)?;
+                srsp::SecuritySetReq::try_decode(&frame.payload)?.check()?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// PIB writes collected for bring-up, e.g.
+/// `PibConfig::new().pan_id(pan_id).channel(0).rx_on_idle(true)`, and applied
+/// in order with `apply`.
+#[derive(Debug, Default, Clone)]
+pub struct PibConfig {
+    writes: Vec<PibWrite>,
+}
+
+impl PibConfig {
+    pub fn new() -> PibConfig {
+        PibConfig::default()
+    }
+
+    /// Sets a MAC attribute, `value` is truncated to 16 bytes.
+    pub fn mac(mut self, attribute_id: MACPIBAttributeId, value: &[u8]) -> PibConfig {
+        let mut attribute_value = [0x00; 16];
+        let length = value.len().min(attribute_value.len());
+        attribute_value[..length].copy_from_slice(&value[..length]);
+        self.writes
+            .push(PibWrite::Mac(attribute_id, attribute_value));
+        self
+    }
+
+    pub fn pan_id(self, pan_id: PanId) -> PibConfig {
+        self.mac(MACPIBAttributeId::PANId, &pan_id.u16().to_le_bytes())
+    }
+
+    pub fn short_address(self, address: ShortAddress) -> PibConfig {
+        self.mac(
+            MACPIBAttributeId::ShortAddress,
+            &address.u16().to_le_bytes(),
+        )
+    }
+
+    pub fn extended_address(self, address: ExtendedAddress) -> PibConfig {
+        self.mac(
+            MACPIBAttributeId::ExtendedAddress,
+            &address.u64().to_le_bytes(),
+        )
+    }
+
+    pub fn channel(self, logical_channel: u8) -> PibConfig {
+        self.mac(MACPIBAttributeId::LogicalChannel, &[logical_channel])
+    }
+
+    pub fn channel_page(self, channel_page: u8) -> PibConfig {
+        self.mac(MACPIBAttributeId::ChannelPage, &[channel_page])
+    }
+
+    pub fn phy_id(self, phy_id: PhyId) -> PibConfig {
+        self.mac(MACPIBAttributeId::PhyCurrentDescriptorId, &[phy_id.u8()])
+    }
+
+    pub fn transmit_power(self, dbm: i8) -> PibConfig {
+        self.mac(MACPIBAttributeId::PhyTransmitPowerSigned, &[dbm as u8])
+    }
+
+    pub fn rx_on_idle(self, enabled: bool) -> PibConfig {
+        self.mac(MACPIBAttributeId::RxOnWhenIdle, &[enabled as u8])
+    }
+
+    pub fn association_permit(self, enabled: bool) -> PibConfig {
+        self.mac(MACPIBAttributeId::AssociationPermit, &[enabled as u8])
+    }
+
+    pub fn security_enabled(self, enabled: bool) -> PibConfig {
+        self.mac(MACPIBAttributeId::SecurityEnabled, &[enabled as u8])
+    }
+
+    pub fn beacon_payload(self, payload: &[u8]) -> PibConfig {
+        self.mac(
+            MACPIBAttributeId::BeaconPayloadLength,
+            &[payload.len() as u8],
+        )
+        .mac(MACPIBAttributeId::BeaconPayload, payload)
+    }
+
+    #[cfg(feature = "fh")]
+    pub fn fh(mut self, attribute_id: FHPIBAttributeId, data: Vec<u8>) -> PibConfig {
+        self.writes.push(PibWrite::Fh(attribute_id, data));
+        self
+    }
+
+    #[cfg(feature = "fh")]
+    pub fn net_name(self, name: &NetName) -> PibConfig {
+        let request = sreq::FHSetReq::net_name(name);
+        self.fh(request.attribute_id, request.data)
+    }
+
+    /// Writes the fixed channels before the channel functions so the
+    /// coprocessor never runs a fixed schedule on a stale channel. Does not
+    /// validate `schedule`, see `client::write_schedule`.
+    #[cfg(feature = "fh")]
+    pub fn fh_schedule(self, schedule: &FhSchedule) -> PibConfig {
+        let mut config = self
+            .fh(
+                FHPIBAttributeId::UCDwellInterval,
+                vec![schedule.uc_dwell_interval],
+            )
+            .fh(
+                FHPIBAttributeId::BCInterval,
+                schedule.bc_interval.to_le_bytes().to_vec(),
+            )
+            .fh(
+                FHPIBAttributeId::BCDwellInterval,
+                vec![schedule.bc_dwell_interval],
+            );
+        if let Some(channel) = schedule.uc_fixed_channel {
+            config = config.fh(
+                FHPIBAttributeId::UCFixedChannel,
+                channel.to_le_bytes().to_vec(),
+            );
+        }
+        if let Some(channel) = schedule.bc_fixed_channel {
+            config = config.fh(
+                FHPIBAttributeId::BCFixedChannel,
+                channel.to_le_bytes().to_vec(),
+            );
+        }
+        config
+            .fh(
+                FHPIBAttributeId::UCChannelFunction,
+                vec![schedule.uc_channel_function as u8],
+            )
+            .fh(
+                FHPIBAttributeId::BCChannelFunction,
+                vec![schedule.bc_channel_function as u8],
+            )
+    }
+
+    #[cfg(feature = "security-tables")]
+    pub fn security(
+        mut self,
+        attribute_id: SecurityPIBAttributeId,
+        index1: u16,
+        index2: u16,
+        value: Vec<u8>,
+    ) -> PibConfig {
+        self.writes.push(PibWrite::Security {
+            attribute_id,
+            index1,
+            index2,
+            value,
+        });
+        self
+    }
+
+    pub fn writes(&self) -> &[PibWrite] {
+        &self.writes
+    }
+
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Writes every attribute in order and stops at the first failure,
+    /// reported as `Error::PibWriteFailed` naming the attribute.
+    pub fn apply<C: Client>(&self, client: &mut C) -> Result<(), Error> {
+        for write in self.writes.iter() {
+            write.apply(client).map_err(|error| Error::PibWriteFailed {
+                attribute: write.attribute(),
+                kind: Box::new(error),
+            })?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use crate::client::{get_mac_pib, set_mac_pib, Client};
+use crate::error::Error;
+use crate::types::MACPIBAttributeId;
+use alloc::vec::Vec;
+
+/// Sets MAC PIB attributes for as long as the guard lives and writes the
+/// previous values back when it is dropped.
+pub struct PibOverride<'a, C: Client> {
+    client: &'a mut C,
+    saved: Vec<(MACPIBAttributeId, [u8; 16])>,
+}
+
+impl<'a, C: Client> PibOverride<'a, C> {
+    pub fn new(
+        client: &'a mut C,
+        overrides: &[(MACPIBAttributeId, [u8; 16])],
+    ) -> Result<Self, Error> {
+        let mut guard = PibOverride {
+            client,
+            saved: Vec::new(),
+        };
+
+        // On failure the guard is dropped here, restoring what was already set.
+        for (attribute_id, value) in overrides.iter() {
+            let previous = get_mac_pib(guard.client, *attribute_id)?;
+            set_mac_pib(guard.client, *attribute_id, *value)?;
+            guard.saved.push((*attribute_id, previous));
+        }
+
+        Ok(guard)
+    }
+
+    pub fn client(&mut self) -> &mut C {
+        self.client
+    }
+
+    /// Restores the previous values, reporting the first failure.
+    pub fn restore(mut self) -> Result<(), Error> {
+        self.restore_saved()
+    }
+
+    fn restore_saved(&mut self) -> Result<(), Error> {
+        let mut result = Ok(());
+        while let Some((attribute_id, value)) = self.saved.pop() {
+            let restored = set_mac_pib(self.client, attribute_id, value);
+            if result.is_ok() {
+                result = restored;
+            }
+        }
+        result
+    }
+}
+
+impl<'a, C: Client> Drop for PibOverride<'a, C> {
+    fn drop(&mut self) {
+        let _ = self.restore_saved();
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum OverflowPolicy {
+    // Make room by dropping the item queued first.
+    DropOldest,
+    // Drop the item being pushed.
+    DropNewest,
+    // Wait for the consumer to make room. Only use it when the consumer runs
+    // on another thread.
+    Block,
+}
+
+#[derive(Debug)]
+struct State<T> {
+    items: VecDeque<T>,
+    dropped: u64,
+}
+
+/// Bounded queue between the thread reading the serial port and the one
+/// consuming indications, so a slow consumer costs dropped indications or a
+/// stalled reader, as `policy` says, instead of unbounded memory. Share it
+/// with an `Arc`.
+#[derive(Debug)]
+pub struct BoundedQueue<T> {
+    capacity: usize,
+    policy: OverflowPolicy,
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T> BoundedQueue<T> {
+    /// `capacity` is at least 1.
+    pub fn new(capacity: usize, policy: OverflowPolicy) -> BoundedQueue<T> {
+        BoundedQueue {
+            capacity: capacity.max(1),
+            policy,
+            state: Mutex::new(State {
+                items: VecDeque::new(),
+                dropped: 0,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().items.is_empty()
+    }
+
+    /// Items dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.lock().dropped
+    }
+
+    /// Queues `item` and returns the one dropped to make room, if any.
+    pub fn push(&self, item: T) -> Option<T> {
+        let mut state = self.lock();
+        let mut dropped = None;
+        if state.items.len() >= self.capacity {
+            match self.policy {
+                OverflowPolicy::DropOldest => {
+                    dropped = state.items.pop_front();
+                    state.dropped += 1;
+                }
+                OverflowPolicy::DropNewest => {
+                    state.dropped += 1;
+                    return Some(item);
+                }
+                OverflowPolicy::Block => {
+                    while state.items.len() >= self.capacity {
+                        state = self
+                            .not_full
+                            .wait(state)
+                            .unwrap_or_else(|error| error.into_inner());
+                    }
+                }
+            }
+        }
+        state.items.push_back(item);
+        self.not_empty.notify_one();
+        dropped
+    }
+
+    pub fn try_pop(&self) -> Option<T> {
+        let item = self.lock().items.pop_front();
+        if item.is_some() {
+            self.not_full.notify_one();
+        }
+        item
+    }
+
+    /// Waits up to `timeout` for an item.
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<T> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.lock();
+        loop {
+            if let Some(item) = state.items.pop_front() {
+                self.not_full.notify_one();
+                return Some(item);
+            }
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left == Duration::ZERO {
+                return None;
+            }
+            state = self
+                .not_empty
+                .wait_timeout(state, left)
+                .map(|(state, _)| state)
+                .unwrap_or_else(|error| error.into_inner().0);
+        }
+    }
+
+    /// Takes everything queued.
+    pub fn drain(&self) -> Vec<T> {
+        let items: Vec<T> = self.lock().items.drain(..).collect();
+        if !items.is_empty() {
+            self.not_full.notify_all();
+        }
+        items
+    }
+
+    // A panic while holding the lock cannot leave the queue inconsistent, so
+    // poisoning is ignored.
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
@@ -0,0 +1,117 @@
+use crate::error::Error;
+use alloc::vec::Vec;
+
+/// How forgiving decoding is. The default is strict, as firmware values the
+/// crate does not know about may change the meaning of a frame.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct DecodeOptions {
+    // Status, PIB attribute and frame type values the crate has no name for
+    // decode into their `Unknown` variants instead of failing the frame.
+    pub unknown_values: bool,
+    // Payloads of fixed size commands may be longer than expected, as newer
+    // firmware may append fields. The extra bytes are ignored.
+    pub trailing_bytes: bool,
+}
+
+impl DecodeOptions {
+    pub fn lenient() -> DecodeOptions {
+        DecodeOptions {
+            unknown_values: true,
+            trailing_bytes: true,
+        }
+    }
+}
+
+/// Fallible reads over a byte buffer. Every accessor returns
+/// `Error::NotEnoughBytes` when the buffer is too short, so decoding a
+/// truncated payload fails instead of panicking.
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    buffer: &'a [u8],
+    position: usize,
+    options: DecodeOptions,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buffer: &'a [u8]) -> Reader<'a> {
+        Reader::with_options(buffer, DecodeOptions::default())
+    }
+
+    pub fn with_options(buffer: &'a [u8], options: DecodeOptions) -> Reader<'a> {
+        Reader {
+            buffer,
+            position: 0,
+            options,
+        }
+    }
+
+    pub fn options(&self) -> DecodeOptions {
+        self.options
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buffer.len().saturating_sub(self.position)
+    }
+
+    /// Borrows the next `count` bytes without copying them.
+    pub fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], Error> {
+        if self.remaining() < count {
+            return Err(Error::NotEnoughBytes);
+        }
+        let bytes = &self.buffer[self.position..self.position + count];
+        self.position += count;
+        Ok(bytes)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_i8(&mut self) -> Result<i8, Error> {
+        Ok(self.read_bytes(1)?[0] as i8)
+    }
+
+    pub fn read_u16_le(&mut self) -> Result<u16, Error> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn read_u32_le(&mut self) -> Result<u32, Error> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn skip(&mut self, count: usize) -> Result<(), Error> {
+        self.read_bytes(count).map(|_| ())
+    }
+
+    pub fn read_exact(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        buffer.copy_from_slice(self.read_bytes(buffer.len())?);
+        Ok(())
+    }
+
+    pub fn read_to_end(&mut self, buffer: &mut Vec<u8>) -> Result<usize, Error> {
+        let bytes = self.read_bytes(self.remaining())?;
+        buffer.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    /// Runs `decode` and tags a failure with the field name and the offset
+    /// the field starts at. Errors already tagged by a nested decoder are
+    /// passed through, they point closer to the offending byte.
+    pub(crate) fn field<T>(
+        &mut self,
+        field: &'static str,
+        decode: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let offset = self.position;
+        decode(self).map_err(|error| match error {
+            Error::Decode { .. } => error,
+            kind => Error::field(field, offset, kind),
+        })
+    }
+}
@@ -0,0 +1,210 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::frame::MTFrame;
+use crate::history::{Direction, FrameHistory};
+use crate::parser::Parser;
+use crate::types::CommandType;
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Records every frame exchanged through `client`, to be saved and replayed
+/// by `ReplayTransport`. Frames the application reads outside requests,
+/// such as indications, are added with `received`.
+pub struct Recorder<C> {
+    client: C,
+    history: FrameHistory,
+}
+
+impl<C: Client> Recorder<C> {
+    pub fn new(client: C) -> Recorder<C> {
+        Recorder {
+            client,
+            history: FrameHistory::unbounded(),
+        }
+    }
+
+    pub fn client(&mut self) -> &mut C {
+        &mut self.client
+    }
+
+    pub fn history(&self) -> &FrameHistory {
+        &self.history
+    }
+
+    pub fn into_inner(self) -> (C, FrameHistory) {
+        (self.client, self.history)
+    }
+
+    pub fn received(&mut self, frame: &MTFrame) {
+        self.history.frame_received(frame, Instant::now());
+    }
+
+    /// Writes the session in the `FrameHistory::dump` format.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.history.dump_to_file(path)
+    }
+
+    fn record(&mut self, result: &Result<MTFrame, Error>) {
+        match result {
+            Ok(frame) | Err(Error::UnexpectedResponse(frame)) => self.received(frame),
+            Err(_) => (),
+        }
+    }
+}
+
+impl<C: Client> Client for Recorder<C> {
+    fn request(&mut self, frame: MTFrame) -> Result<MTFrame, Error> {
+        self.history.frame_sent(&frame, Instant::now());
+        let result = self.client.request(frame);
+        self.record(&result);
+        result
+    }
+
+    fn request_with_timeout(
+        &mut self,
+        frame: MTFrame,
+        timeout: Duration,
+    ) -> Result<MTFrame, Error> {
+        self.history.frame_sent(&frame, Instant::now());
+        let result = self.client.request_with_timeout(frame, timeout);
+        self.record(&result);
+        result
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum RequestMatch {
+    // Requests must repeat the recorded bytes.
+    Bytes,
+    // Only the command code has to match, for sessions where handles or
+    // payloads vary from run to run.
+    Command,
+}
+
+/// Plays the device side of a recorded session. Each request is checked
+/// against the next recorded one and answered with the frames recorded
+/// after it: indications are queued for `receive` and the SRSP is returned.
+pub struct ReplayTransport {
+    script: VecDeque<(Direction, MTFrame)>,
+    indications: VecDeque<MTFrame>,
+    matching: RequestMatch,
+}
+
+impl ReplayTransport {
+    pub fn new(frames: Vec<(Direction, MTFrame)>) -> ReplayTransport {
+        let mut replay = ReplayTransport {
+            script: frames.into(),
+            indications: VecDeque::new(),
+            matching: RequestMatch::Bytes,
+        };
+        replay.take_indications();
+        replay
+    }
+
+    pub fn from_history(history: &FrameHistory) -> Result<ReplayTransport, Error> {
+        let frames = history
+            .frames()
+            .map(|frame| Ok((frame.direction, decode(&frame.bytes)?)))
+            .collect::<Result<_, Error>>()?;
+        Ok(ReplayTransport::new(frames))
+    }
+
+    /// Reads a session saved by `Recorder::save` or `FrameHistory::dump`.
+    pub fn parse(dump: &str) -> Result<ReplayTransport, Error> {
+        let mut frames = Vec::new();
+        for line in dump.lines().filter(|line| !line.trim().is_empty()) {
+            let mut fields = line.split_whitespace().skip(1);
+            let direction = match fields.next() {
+                Some("TX") => Direction::Tx,
+                Some("RX") => Direction::Rx,
+                _ => return Err(Error::Io(io::ErrorKind::InvalidData)),
+            };
+            let bytes = fields
+                .map(|byte| u8::from_str_radix(byte, 16))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| Error::Io(io::ErrorKind::InvalidData))?;
+            frames.push((direction, decode(&bytes)?));
+        }
+        Ok(ReplayTransport::new(frames))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ReplayTransport, Error> {
+        ReplayTransport::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn set_matching(&mut self, matching: RequestMatch) {
+        self.matching = matching;
+    }
+
+    /// Next frame the device sent outside a request.
+    pub fn receive(&mut self) -> Option<MTFrame> {
+        self.indications.pop_front()
+    }
+
+    /// Whether every recorded frame was played.
+    pub fn is_finished(&self) -> bool {
+        self.script.is_empty() && self.indications.is_empty()
+    }
+
+    // Received frames not answering a request are delivered right away.
+    fn take_indications(&mut self) {
+        while let Some((Direction::Rx, _)) = self.script.front() {
+            if let Some((_, frame)) = self.script.pop_front() {
+                self.indications.push_back(frame);
+            }
+        }
+    }
+
+    fn matches(&self, expected: &MTFrame, actual: &MTFrame) -> bool {
+        match self.matching {
+            RequestMatch::Bytes => {
+                expected.encode_to_uart_transport_frame() == actual.encode_to_uart_transport_frame()
+            }
+            RequestMatch::Command => expected.header.command == actual.header.command,
+        }
+    }
+}
+
+impl Client for ReplayTransport {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(command = ?frame.header.command))
+    )]
+    fn request(&mut self, frame: MTFrame) -> Result<MTFrame, Error> {
+        let command = frame.header.command.clone();
+        let expected = match self.script.pop_front() {
+            Some((Direction::Tx, expected)) => expected,
+            _ => return Err(Error::Timeout { command }),
+        };
+        if !self.matches(&expected, &frame) {
+            return Err(Error::ReplayMismatch {
+                expected: expected.encode_to_uart_transport_frame(),
+                actual: frame.encode_to_uart_transport_frame(),
+            });
+        }
+
+        let mut response = None;
+        while let Some((Direction::Rx, _)) = self.script.front() {
+            if let Some((_, frame)) = self.script.pop_front() {
+                if response.is_none() && frame.header.command.cmd_type == CommandType::SRSP {
+                    response = Some(frame);
+                } else {
+                    self.indications.push_back(frame);
+                }
+            }
+        }
+        response.ok_or(Error::Timeout { command })
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<MTFrame, Error> {
+    let mut parser = Parser::new();
+    parser
+        .feed_slice(bytes)
+        .into_iter()
+        .next()
+        .unwrap_or(Err(Error::NotEnoughBytes))
+}
@@ -0,0 +1,299 @@
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+// Serial bootloader of the CC13xx/CC26xx ROM. Packets are a size byte
+// counting itself and the checksum, a checksum summing the data bytes, then
+// the data. Every packet is acknowledged with 0x00 followed by ACK or NACK.
+const SYNC: [u8; 2] = [0x55, 0x55];
+const ACK: u8 = 0xcc;
+const NACK: u8 = 0x33;
+
+/// Largest data block `SEND_DATA` takes.
+pub const MAX_DATA_SIZE: usize = 252;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum SblCommand {
+    Ping = 0x20,
+    Download = 0x21,
+    GetStatus = 0x23,
+    SendData = 0x24,
+    Reset = 0x25,
+    SectorErase = 0x26,
+    Crc32 = 0x27,
+    GetChipId = 0x28,
+    BankErase = 0x2c,
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+pub enum SblStatus {
+    Success = 0x40,
+    UnknownCommand = 0x41,
+    InvalidCommand = 0x42,
+    InvalidAddress = 0x43,
+    FlashFail = 0x44,
+}
+
+#[derive(Debug)]
+pub enum SblError {
+    Io(io::Error),
+    // The bootloader rejected the packet, usually a checksum error.
+    Nack,
+    // The command was acknowledged but failed, as reported by GET_STATUS.
+    Status(SblStatus),
+    // A reply that does not follow the protocol.
+    InvalidResponse(Vec<u8>),
+    DataTooLong(usize),
+    // The CRC of the flash after programming differs from the image's.
+    VerifyFailed {
+        address: u32,
+        expected: u32,
+        actual: u32,
+    },
+}
+
+impl From<io::Error> for SblError {
+    fn from(error: io::Error) -> Self {
+        SblError::Io(error)
+    }
+}
+
+impl fmt::Display for SblError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SblError::Io(error) => write!(f, "bootloader i/o: {}", error),
+            SblError::Nack => write!(f, "bootloader answered NACK"),
+            SblError::Status(status) => write!(f, "bootloader status {:?}", status),
+            SblError::InvalidResponse(bytes) => {
+                write!(f, "invalid bootloader response {:02x?}", bytes)
+            }
+            SblError::DataTooLong(length) => {
+                write!(f, "{} bytes do not fit a bootloader packet", length)
+            }
+            SblError::VerifyFailed {
+                address,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "CRC at 0x{:08x} is 0x{:08x}, expected 0x{:08x}",
+                address, actual, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SblError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SblError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Frames `data` as a bootloader packet.
+pub fn encode_packet(data: &[u8]) -> Result<Vec<u8>, SblError> {
+    if data.len() > usize::from(u8::MAX) - 2 {
+        return Err(SblError::DataTooLong(data.len()));
+    }
+    let mut packet = vec![data.len() as u8 + 2, checksum(data)];
+    packet.extend_from_slice(data);
+    Ok(packet)
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |acc, x| acc.wrapping_add(*x))
+}
+
+/// CRC-32 as computed by the `CRC32` command (IEEE 802.3, reflected).
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(0xffff_ffffu32, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+/// Talks to the ROM bootloader over `port`, which has to be opened at a
+/// baud rate the bootloader can detect and should time reads out. The chip
+/// only runs the bootloader when the flash image is invalid or when the
+/// backdoor pin is held at reset, bringing it there is up to the board.
+#[derive(Debug)]
+pub struct Bootloader<P: Read + Write> {
+    port: P,
+    sector_size: u32,
+}
+
+impl<P: Read + Write> Bootloader<P> {
+    /// `sector_size` is the flash erase unit, 4 KiB on CC13x0 and 8 KiB on
+    /// CC13x2.
+    pub fn new(port: P, sector_size: u32) -> Bootloader<P> {
+        Bootloader { port, sector_size }
+    }
+
+    pub fn get_mut(&mut self) -> &mut P {
+        &mut self.port
+    }
+
+    pub fn into_inner(self) -> P {
+        self.port
+    }
+
+    /// Sends the auto baud sequence, the first thing the bootloader expects.
+    pub fn enter(&mut self) -> Result<(), SblError> {
+        self.port.write_all(&SYNC)?;
+        self.port.flush()?;
+        self.read_ack()
+    }
+
+    pub fn ping(&mut self) -> Result<(), SblError> {
+        self.command(SblCommand::Ping, &[])
+    }
+
+    pub fn chip_id(&mut self) -> Result<u32, SblError> {
+        self.command(SblCommand::GetChipId, &[])?;
+        self.read_u32()
+    }
+
+    /// Status of the last command.
+    pub fn status(&mut self) -> Result<SblStatus, SblError> {
+        self.command(SblCommand::GetStatus, &[])?;
+        let response = self.read_packet()?;
+        match response[..] {
+            [value] => SblStatus::from_u8(value).ok_or(SblError::InvalidResponse(response)),
+            _ => Err(SblError::InvalidResponse(response)),
+        }
+    }
+
+    /// Erases the sectors covering `length` bytes from `address`.
+    pub fn erase(&mut self, address: u32, length: usize) -> Result<(), SblError> {
+        let start = address / self.sector_size * self.sector_size;
+        let end = address as u64 + length as u64;
+        let mut sector = u64::from(start);
+        while sector < end {
+            self.checked(SblCommand::SectorErase, &(sector as u32).to_be_bytes())?;
+            sector += u64::from(self.sector_size);
+        }
+        Ok(())
+    }
+
+    /// Erases the whole flash, CCFG included.
+    pub fn erase_bank(&mut self) -> Result<(), SblError> {
+        self.checked(SblCommand::BankErase, &[])
+    }
+
+    /// Writes `image` at `address`, which must have been erased. The image
+    /// is padded with 0xff to a multiple of 4 bytes.
+    pub fn program(&mut self, address: u32, image: &[u8]) -> Result<(), SblError> {
+        let mut image = image.to_vec();
+        image.resize(image.len().div_ceil(4) * 4, 0xff);
+
+        let mut download = address.to_be_bytes().to_vec();
+        download.extend_from_slice(&(image.len() as u32).to_be_bytes());
+        self.checked(SblCommand::Download, &download)?;
+
+        for block in image.chunks(MAX_DATA_SIZE) {
+            self.checked(SblCommand::SendData, block)?;
+        }
+        Ok(())
+    }
+
+    /// Compares the CRC of the flash at `address` with the one of `image`.
+    pub fn verify(&mut self, address: u32, image: &[u8]) -> Result<(), SblError> {
+        let mut arguments = address.to_be_bytes().to_vec();
+        arguments.extend_from_slice(&(image.len() as u32).to_be_bytes());
+        arguments.extend_from_slice(&0u32.to_be_bytes());
+        self.command(SblCommand::Crc32, &arguments)?;
+
+        let actual = self.read_u32()?;
+        let expected = crc32(image);
+        if actual != expected {
+            return Err(SblError::VerifyFailed {
+                address,
+                expected,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Starts the flashed image.
+    pub fn reset(&mut self) -> Result<(), SblError> {
+        self.command(SblCommand::Reset, &[])
+    }
+
+    /// Erase, program, verify and reset, the usual field upgrade.
+    pub fn upgrade(&mut self, address: u32, image: &[u8]) -> Result<(), SblError> {
+        self.erase(address, image.len())?;
+        self.program(address, image)?;
+        self.verify(address, image)?;
+        self.reset()
+    }
+
+    // Commands changing the flash only report failures through GET_STATUS.
+    fn checked(&mut self, command: SblCommand, arguments: &[u8]) -> Result<(), SblError> {
+        self.command(command, arguments)?;
+        match self.status()? {
+            SblStatus::Success => Ok(()),
+            status => Err(SblError::Status(status)),
+        }
+    }
+
+    fn command(&mut self, command: SblCommand, arguments: &[u8]) -> Result<(), SblError> {
+        let mut data = vec![command as u8];
+        data.extend_from_slice(arguments);
+        self.port.write_all(&encode_packet(&data)?)?;
+        self.port.flush()?;
+        self.read_ack()
+    }
+
+    fn read_ack(&mut self) -> Result<(), SblError> {
+        match self.read_non_zero()? {
+            ACK => Ok(()),
+            NACK => Err(SblError::Nack),
+            byte => Err(SblError::InvalidResponse(vec![byte])),
+        }
+    }
+
+    // Responses are packets too, and have to be acknowledged.
+    fn read_packet(&mut self) -> Result<Vec<u8>, SblError> {
+        let size = self.read_non_zero()?;
+        if size < 2 {
+            return Err(SblError::InvalidResponse(vec![size]));
+        }
+        let mut header_checksum = [0x00];
+        self.port.read_exact(&mut header_checksum)?;
+        let mut data = vec![0x00; usize::from(size) - 2];
+        self.port.read_exact(&mut data)?;
+
+        let valid = checksum(&data) == header_checksum[0];
+        self.port
+            .write_all(&[0x00, if valid { ACK } else { NACK }])?;
+        self.port.flush()?;
+        if !valid {
+            return Err(SblError::InvalidResponse(data));
+        }
+        Ok(data)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, SblError> {
+        let response = self.read_packet()?;
+        match response[..] {
+            [a, b, c, d] => Ok(u32::from_be_bytes([a, b, c, d])),
+            _ => Err(SblError::InvalidResponse(response)),
+        }
+    }
+
+    // The bootloader pads its replies with zeros.
+    fn read_non_zero(&mut self) -> Result<u8, SblError> {
+        let mut byte = [0x00];
+        loop {
+            self.port.read_exact(&mut byte)?;
+            if byte[0] != 0x00 {
+                return Ok(byte[0]);
+            }
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use crate::client::{self, Client, HandleAllocator, RetryPolicy};
+use crate::error::Error;
+use crate::subsystem::mac::{areq, sreq};
+use crate::types::{Address, Status};
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+#[derive(Debug, Clone)]
+pub struct SchedulerConfig {
+    // Data requests a destination may have waiting for their confirm.
+    pub max_in_flight: usize,
+    // Transmissions per request and the backoff between them. The SRSP
+    // timeouts are left to the client.
+    pub retry: RetryPolicy,
+    // Confirm statuses worth another transmission.
+    pub retry_on: Vec<Status>,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        SchedulerConfig {
+            max_in_flight: 1,
+            retry: RetryPolicy::default(),
+            retry_on: vec![Status::NoAck, Status::ChannelAccessFailure],
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct DeliveryStats {
+    // Data requests handed to the coprocessor, retries included.
+    pub transmissions: u64,
+    pub retries: u64,
+    pub delivered: u64,
+    pub failed: u64,
+    pub in_flight: usize,
+    pub queued: usize,
+}
+
+// Classes in increasing order of precedence.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub enum Priority {
+    // Uploads and other traffic that can wait.
+    Bulk,
+    #[default]
+    Normal,
+    // EAPOL, Wi-SUN async frames and other control traffic.
+    Control,
+}
+
+#[derive(Debug)]
+pub enum SchedulerEvent {
+    Delivered {
+        id: u64,
+        destination: Address,
+        confirm: areq::DataCnf,
+    },
+    // The last attempt failed or the status is not worth a retry.
+    Failed {
+        id: u64,
+        destination: Address,
+        status: Status,
+        attempts: u32,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Queued {
+    id: u64,
+    priority: Priority,
+    request: sreq::DataReq,
+    attempts: u32,
+    not_before: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Destination {
+    // Ordered by priority, then by arrival.
+    queue: VecDeque<Queued>,
+    stats: DeliveryStats,
+}
+
+impl Destination {
+    // Retries go ahead of the requests of their class, new requests after.
+    fn insert(&mut self, queued: Queued, retry: bool) {
+        let index = self
+            .queue
+            .iter()
+            .position(|x| {
+                if retry {
+                    x.priority <= queued.priority
+                } else {
+                    x.priority < queued.priority
+                }
+            })
+            .unwrap_or(self.queue.len());
+        self.queue.insert(index, queued);
+    }
+
+    fn due(&self, now: Instant, max_in_flight: usize) -> Option<&Queued> {
+        self.queue
+            .front()
+            .filter(|x| self.stats.in_flight < max_in_flight && x.not_before <= now)
+    }
+}
+
+/// Outbound data scheduler. Requests are queued per destination and sent
+/// by `poll` with a free MSDU handle, at most `max_in_flight` at a time for
+/// each destination and higher priority classes first. Confirms passed to
+/// `confirm` release the destination and failed transmissions are queued
+/// again, ahead of the other requests of their class, after the policy's
+/// backoff.
+#[derive(Debug)]
+pub struct Scheduler {
+    config: SchedulerConfig,
+    destinations: HashMap<Address, Destination>,
+    handles: HandleAllocator<Queued>,
+    next_id: u64,
+    // Requests the coprocessor refused in `poll`, see `take_failed`.
+    failed: Vec<SchedulerEvent>,
+}
+
+impl Scheduler {
+    pub fn new(config: SchedulerConfig) -> Scheduler {
+        Scheduler {
+            config,
+            destinations: HashMap::new(),
+            handles: HandleAllocator::new(),
+            next_id: 0,
+            failed: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &SchedulerConfig {
+        &self.config
+    }
+
+    /// Queues `request` with `Priority::Normal` and returns the id its
+    /// event will carry. The handle of the request is replaced when it is
+    /// sent.
+    pub fn enqueue(&mut self, request: sreq::DataReq, now: Instant) -> u64 {
+        self.enqueue_with_priority(request, Priority::Normal, now)
+    }
+
+    pub fn enqueue_with_priority(
+        &mut self,
+        request: sreq::DataReq,
+        priority: Priority,
+        now: Instant,
+    ) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        let destination = self.destinations.entry(request.dest_address).or_default();
+        destination.stats.queued += 1;
+        destination.insert(
+            Queued {
+                id,
+                priority,
+                request,
+                attempts: 0,
+                not_before: now,
+            },
+            false,
+        );
+        id
+    }
+
+    /// Sends the queued requests that are due and whose destination has
+    /// room, returning how many were sent. The highest class goes first,
+    /// across destinations, then the oldest request. A request the client
+    /// fails to send, with `Error::Timeout` or `Error::Io`, stays at the head
+    /// of its queue and the error is returned. One the coprocessor refuses,
+    /// e.g. with a failed SRSP status, leaves the queue and is reported as
+    /// `SchedulerEvent::Failed` by `take_failed`.
+    pub fn poll<C: Client>(&mut self, client: &mut C, now: Instant) -> Result<usize, Error> {
+        let max_in_flight = self.config.max_in_flight;
+        let mut sent = 0;
+        loop {
+            let next = self
+                .destinations
+                .iter()
+                .filter_map(|(address, x)| Some((x.due(now, max_in_flight)?, address)))
+                .max_by_key(|(queued, _)| (queued.priority, core::cmp::Reverse(queued.id)))
+                .map(|(_, address)| *address);
+            let destination = match next {
+                Some(address) => self.destinations.get_mut(&address),
+                None => None,
+            };
+            let destination = match destination {
+                Some(destination) => destination,
+                None => return Ok(sent),
+            };
+            // The request leaves the queue only once it has a handle.
+            let mut queued = match destination.queue.front() {
+                Some(queued) => queued.clone(),
+                None => return Ok(sent),
+            };
+            queued.attempts += 1;
+            let mut request = queued.request.clone();
+            let handle = self.handles.allocate(queued)?;
+            destination.queue.pop_front();
+            request.handle = handle;
+            if let Err(error) = client::data_req(client, request) {
+                let mut queued = match self.handles.release(handle) {
+                    Some(queued) => queued,
+                    None => return Err(error),
+                };
+                if is_transport_error(&error) {
+                    queued.attempts -= 1;
+                    destination.queue.push_front(queued);
+                    return Err(error);
+                }
+                destination.stats.queued -= 1;
+                destination.stats.failed += 1;
+                self.failed.push(SchedulerEvent::Failed {
+                    id: queued.id,
+                    destination: queued.request.dest_address,
+                    status: rejection_status(&error),
+                    attempts: queued.attempts,
+                });
+                continue;
+            }
+            destination.stats.transmissions += 1;
+            destination.stats.queued -= 1;
+            destination.stats.in_flight += 1;
+            sent += 1;
+        }
+    }
+
+    /// Handles the confirm of a request sent by `poll`. Returns None for
+    /// other handles and for failures that are retried.
+    pub fn confirm(&mut self, confirm: &areq::DataCnf, now: Instant) -> Option<SchedulerEvent> {
+        let mut queued = self.handles.confirm(confirm)?;
+        let address = queued.request.dest_address;
+        let destination = self.destinations.entry(address).or_default();
+        destination.stats.in_flight -= 1;
+
+        if confirm.status == Status::Success {
+            destination.stats.delivered += 1;
+            return Some(SchedulerEvent::Delivered {
+                id: queued.id,
+                destination: address,
+                confirm: *confirm,
+            });
+        }
+        if self.config.retry_on.contains(&confirm.status)
+            && queued.attempts < self.config.retry.attempts
+        {
+            queued.not_before = now + self.config.retry.backoff_after(queued.attempts);
+            destination.stats.retries += 1;
+            destination.stats.queued += 1;
+            destination.insert(queued, true);
+            return None;
+        }
+        destination.stats.failed += 1;
+        Some(SchedulerEvent::Failed {
+            id: queued.id,
+            destination: address,
+            status: confirm.status,
+            attempts: queued.attempts,
+        })
+    }
+
+    /// Takes the failures of the requests refused since the last call.
+    pub fn take_failed(&mut self) -> Vec<SchedulerEvent> {
+        core::mem::take(&mut self.failed)
+    }
+
+    /// Earliest time a queued request becomes due, for sleeping until the
+    /// next `poll`. Destinations without room are left out.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.destinations
+            .values()
+            .filter(|x| x.stats.in_flight < self.config.max_in_flight)
+            .filter_map(|x| x.queue.front().map(|queued| queued.not_before))
+            .min()
+    }
+
+    pub fn stats(&self, destination: &Address) -> Option<&DeliveryStats> {
+        self.destinations.get(destination).map(|x| &x.stats)
+    }
+
+    pub fn all_stats(&self) -> impl Iterator<Item = (&Address, &DeliveryStats)> {
+        self.destinations
+            .iter()
+            .map(|(address, x)| (address, &x.stats))
+    }
+
+    /// Drops the destinations with nothing queued or in flight, and their
+    /// stats, returning how many were dropped. Destinations are kept
+    /// otherwise, so their stats outlive their requests.
+    pub fn prune(&mut self) -> usize {
+        let before = self.destinations.len();
+        self.destinations
+            .retain(|_, x| !x.queue.is_empty() || x.stats.in_flight > 0);
+        before - self.destinations.len()
+    }
+
+    /// Requests queued or waiting for their confirm.
+    pub fn len(&self) -> usize {
+        self.destinations
+            .values()
+            .map(|x| x.stats.queued + x.stats.in_flight)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// The request may not have reached the coprocessor, worth sending again.
+fn is_transport_error(error: &Error) -> bool {
+    matches!(error, Error::Timeout { .. } | Error::Io(_))
+}
+
+// Status reported for a request refused before its transmission.
+fn rejection_status(error: &Error) -> Status {
+    match error {
+        Error::CommandFailed(status) => *status,
+        Error::PayloadTooLarge(_) => Status::FrameTooLong,
+        _ => Status::InvalidParameter,
+    }
+}
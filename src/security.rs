@@ -0,0 +1,720 @@
+use crate::error::Error;
+use crate::frame::MTFrame;
+use crate::reader::Reader;
+use crate::subsystem::mac::sreq::{AddDeviceReq, SecurityGetReq, SecuritySetReq, WriteKeyReq};
+use crate::subsystem::mac::srsp;
+use crate::subsystem::MTFramePayload;
+use crate::types::{ExtendedAddress, PanId, SecurityPIBAttributeId, ShortAddress};
+use crate::writer::BufMut;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+const BUNDLE_MAGIC: &[u8; 8] = b"TI154SEC";
+const BUNDLE_VERSION: u8 = 1;
+
+/// Protects exported bundles. The crate does not ship any cipher, the
+/// application provides one matching its key management.
+pub trait BundleCipher {
+    fn encrypt(&mut self, plaintext: &[u8]) -> Vec<u8>;
+    fn decrypt(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+/// Host side copy of the MAC security tables, kept up to date from the
+/// requests sent to the device.
+#[derive(Debug, Default, Clone)]
+pub struct SecurityManager {
+    keys: BTreeMap<u16, WriteKeyReq>,
+    devices: Vec<AddDeviceReq>,
+    security_levels: BTreeMap<(u16, u16), SecuritySetReq>,
+    frame_counter: Option<u32>,
+}
+
+impl SecurityManager {
+    pub fn new() -> SecurityManager {
+        SecurityManager::default()
+    }
+
+    pub fn observe(&mut self, payload: &MTFramePayload) {
+        use MTFramePayload::*;
+        match payload {
+            MAC_WriteKeyReq_SREQ(x) => {
+                self.keys.insert(x.index, *x);
+            }
+            MAC_DeleteKeyReq_SREQ(x) => {
+                self.keys.remove(&u16::from(x.index));
+            }
+            MAC_AddDeviceReq_SREQ(x) => {
+                self.devices.retain(|device| device.ext_addr != x.ext_addr);
+                self.devices.push(*x);
+            }
+            MAC_DeleteDeviceReq_SREQ(x) => {
+                self.devices.retain(|device| device.ext_addr != x.ext_addr);
+            }
+            MAC_DeleteAllDevicesReq_SREQ(_) => self.devices.clear(),
+            MAC_SecuritySetReq_SREQ(x) => match x.attribute_id {
+                SecurityPIBAttributeId::SecurityLevelEntry => {
+                    self.security_levels.insert((x.index1, x.index2), x.clone());
+                }
+                SecurityPIBAttributeId::FrameCounter => {
+                    let mut cursor = Reader::new(x.attribute_value.as_slice());
+                    if let Ok(frame_counter) = cursor.read_u32_le() {
+                        self.frame_counter = Some(frame_counter);
+                    }
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &WriteKeyReq> {
+        self.keys.values()
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = &AddDeviceReq> {
+        self.devices.iter()
+    }
+
+    pub fn security_levels(&self) -> impl Iterator<Item = &SecuritySetReq> {
+        self.security_levels.values()
+    }
+
+    pub fn frame_counter(&self) -> Option<u32> {
+        self.frame_counter
+    }
+
+    /// Frame counters advance on the device without host requests, refresh
+    /// them before exporting so the replacement never reuses a counter.
+    pub fn set_frame_counter(&mut self, frame_counter: u32) {
+        self.frame_counter = Some(frame_counter);
+    }
+
+    pub fn set_key_frame_counter(&mut self, index: u16, frame_counter: u32) {
+        if let Some(key) = self.keys.get_mut(&index) {
+            key.frame_counter = frame_counter;
+        }
+    }
+
+    /// Fails with `TableFull` when a table has more records than the bundle
+    /// can count, and `PayloadTooLarge` when a record does not fit its
+    /// length.
+    pub fn export<C: BundleCipher>(&self, cipher: &mut C) -> Result<Vec<u8>, Error> {
+        let mut buffer = Vec::new();
+        buffer.extend(BUNDLE_MAGIC.iter());
+        buffer.put_u8(BUNDLE_VERSION);
+
+        match self.frame_counter {
+            Some(frame_counter) => {
+                buffer.put_u8(1);
+                buffer.put_u32_le(frame_counter);
+            }
+            None => buffer.put_u8(0),
+        }
+
+        encode_records(&mut buffer, self.keys.values().map(|x| x.encode()))?;
+        encode_records(&mut buffer, self.devices.iter().map(|x| x.encode()))?;
+        encode_records(
+            &mut buffer,
+            self.security_levels.values().map(|x| x.encode()),
+        )?;
+
+        Ok(cipher.encrypt(&buffer))
+    }
+
+    pub fn import<C: BundleCipher>(bundle: &[u8], cipher: &mut C) -> Result<Self, Error> {
+        let plaintext = cipher.decrypt(bundle)?;
+        let mut cursor = Reader::new(plaintext.as_slice());
+
+        let mut magic = [0x00; 8];
+        cursor.read_exact(&mut magic)?;
+        let version = cursor.read_u8()?;
+        if &magic != BUNDLE_MAGIC || version != BUNDLE_VERSION {
+            return Err(Error::InvalidSecurityBundle);
+        }
+
+        let frame_counter = match cursor.read_u8()? {
+            0 => None,
+            _ => Some(cursor.read_u32_le()?),
+        };
+
+        let mut manager = SecurityManager {
+            frame_counter,
+            ..SecurityManager::default()
+        };
+
+        for record in decode_records(&mut cursor)? {
+            let key = WriteKeyReq::try_decode(&record)?;
+            manager.keys.insert(key.index, key);
+        }
+
+        for record in decode_records(&mut cursor)? {
+            manager.devices.push(AddDeviceReq::try_decode(&record)?);
+        }
+
+        for record in decode_records(&mut cursor)? {
+            let level = SecuritySetReq::try_decode(&record)?;
+            manager
+                .security_levels
+                .insert((level.index1, level.index2), level);
+        }
+
+        Ok(manager)
+    }
+
+    /// Requests that write the tables into a freshly reset device.
+    pub fn restore_frames(&self) -> Vec<MTFrame> {
+        let mut frames = Vec::new();
+
+        if let Some(frame_counter) = self.frame_counter {
+            let mut attribute_value = Vec::new();
+            attribute_value.put_u32_le(frame_counter);
+            let request = SecuritySetReq {
+                attribute_id: SecurityPIBAttributeId::FrameCounter,
+                index1: 0,
+                index2: 0,
+                attribute_value,
+            };
+            frames.push(request.into_mt_frame());
+        }
+
+        frames.extend(self.keys.values().cloned().map(WriteKeyReq::into_mt_frame));
+        frames.extend(
+            self.devices
+                .iter()
+                .cloned()
+                .map(AddDeviceReq::into_mt_frame),
+        );
+        frames.extend(
+            self.security_levels
+                .values()
+                .cloned()
+                .map(SecuritySetReq::into_mt_frame),
+        );
+
+        frames
+    }
+}
+
+// Value of the DeviceEntry security PIB attribute.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct DeviceEntry {
+    pub pan_id: PanId,
+    pub short_address: ShortAddress,
+    pub ext_address: ExtendedAddress,
+    pub frame_counter: u32,
+    pub exempt: bool,
+}
+
+impl DeviceEntry {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let pan_id = PanId::try_decode(cursor)?;
+        let short_address = ShortAddress::try_decode(cursor)?;
+        let ext_address = ExtendedAddress::try_decode(cursor)?;
+        let frame_counter = cursor.read_u32_le()?;
+        let exempt = cursor.read_u8()? != 0;
+        Ok(DeviceEntry {
+            pan_id,
+            short_address,
+            ext_address,
+            frame_counter,
+            exempt,
+        })
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        self.pan_id.encode_into(buffer);
+        self.short_address.encode_into(buffer);
+        self.ext_address.encode_into(buffer);
+        buffer.put_u32_le(self.frame_counter);
+        buffer.put_u8(u8::from(self.exempt));
+    }
+}
+
+/// Host side mirror of the device table. Every change returns the security
+/// PIB writes that apply it to the coprocessor.
+///
+/// Entries are kept at indexes `0..len`, matching DeviceTableEntries. A
+/// removal moves the last entry into the freed index, pass the returned
+/// `DeviceRemoval` to `KeyTable::device_removed` so key bindings follow it.
+#[derive(Debug, Clone)]
+pub struct DeviceTable {
+    capacity: usize,
+    entries: Vec<DeviceEntry>,
+}
+
+impl DeviceTable {
+    pub fn new(capacity: usize) -> DeviceTable {
+        DeviceTable {
+            capacity,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, index: u16) -> Option<&DeviceEntry> {
+        self.entries.get(index as usize)
+    }
+
+    pub fn index_of(&self, ext_address: &ExtendedAddress) -> Option<u16> {
+        self.entries
+            .iter()
+            .zip(0..=u16::MAX)
+            .find(|(entry, _)| entry.ext_address == *ext_address)
+            .map(|(_, index)| index)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &DeviceEntry> {
+        self.entries.iter()
+    }
+
+    /// Adds a device, or updates it in place when its extended address is
+    /// already in the table. DeviceTableEntries is a u16, so past
+    /// `u16::MAX` devices the table is full whatever its capacity.
+    pub fn insert(&mut self, entry: DeviceEntry) -> Result<Vec<SecuritySetReq>, Error> {
+        if let Some(index) = self.index_of(&entry.ext_address) {
+            self.entries[usize::from(index)] = entry;
+            return Ok(vec![device_entry_request(index, &entry)]);
+        }
+
+        if self.entries.len() >= self.capacity {
+            return Err(Error::TableFull);
+        }
+        let index = u16::try_from(self.entries.len()).map_err(|_| Error::TableFull)?;
+        let entries = index.checked_add(1).ok_or(Error::TableFull)?;
+
+        self.entries.push(entry);
+        Ok(vec![
+            device_entry_request(index, &entry),
+            DeviceTable::entries_request(entries),
+        ])
+    }
+
+    pub fn remove(&mut self, ext_address: &ExtendedAddress) -> Option<DeviceRemoval> {
+        let index = self.index_of(ext_address)?;
+        self.entries.swap_remove(usize::from(index));
+        // The index of the former last entry, which `insert` kept in a u16.
+        let entries = u16::try_from(self.entries.len()).ok()?;
+
+        let mut requests = Vec::new();
+        let mut moved_from = None;
+        if let Some(moved) = self.entries.get(usize::from(index)) {
+            requests.push(device_entry_request(index, moved));
+            moved_from = Some(entries);
+        }
+        requests.push(DeviceTable::entries_request(entries));
+        Some(DeviceRemoval {
+            index,
+            moved_from,
+            requests,
+        })
+    }
+
+    pub fn clear(&mut self) -> Vec<SecuritySetReq> {
+        self.entries.clear();
+        vec![DeviceTable::entries_request(0)]
+    }
+
+    /// Writes for the whole table, e.g. after a coprocessor reset.
+    pub fn restore_requests(&self) -> Vec<SecuritySetReq> {
+        let mut requests = Vec::new();
+        let mut entries = 0;
+        for (entry, count) in self.entries.iter().zip(1..=u16::MAX) {
+            requests.push(device_entry_request(count - 1, entry));
+            entries = count;
+        }
+        requests.push(DeviceTable::entries_request(entries));
+        requests
+    }
+
+    fn entries_request(entries: u16) -> SecuritySetReq {
+        let mut attribute_value = Vec::new();
+        attribute_value.put_u16_le(entries);
+        SecuritySetReq {
+            attribute_id: SecurityPIBAttributeId::DeviceTableEntries,
+            index1: 0,
+            index2: 0,
+            attribute_value,
+        }
+    }
+}
+
+/// Outcome of `DeviceTable::remove`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DeviceRemoval {
+    // Index the device was removed from.
+    pub index: u16,
+    // Former index of the last device, now moved into `index`. `None` when
+    // the removed device was the last one.
+    pub moved_from: Option<u16>,
+    pub requests: Vec<SecuritySetReq>,
+}
+
+// Value of the KeyIdLookupEntry attribute. A `size` of 0 means the first 5
+// bytes of `data` are used, 1 means all 9.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct KeyLookup {
+    pub data: [u8; 9],
+    pub size: u8,
+}
+
+impl KeyLookup {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let mut data = [0x00; 9];
+        cursor.read_exact(&mut data)?;
+        let size = cursor.read_u8()?;
+        Ok(KeyLookup { data, size })
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.extend(self.data.iter());
+        buffer.put_u8(self.size);
+    }
+}
+
+// Value of the KeyIdDeviceEntry attribute, a device the key is used with.
+// `device_handle` is the index of the device in the device table.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct KeyDeviceEntry {
+    pub device_handle: u16,
+    // The key is used with this device only.
+    pub unique_device: bool,
+    // The device exhausted the frame counter of a unique key.
+    pub blacklisted: bool,
+}
+
+impl KeyDeviceEntry {
+    // Row left behind by a removed device, bound to no device table index.
+    pub const UNUSED: KeyDeviceEntry = KeyDeviceEntry {
+        device_handle: 0xffff,
+        unique_device: false,
+        blacklisted: true,
+    };
+
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let device_handle = cursor.read_u16_le()?;
+        let unique_device = cursor.read_u8()? != 0;
+        let blacklisted = cursor.read_u8()? != 0;
+        Ok(KeyDeviceEntry {
+            device_handle,
+            unique_device,
+            blacklisted,
+        })
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u16_le(self.device_handle);
+        buffer.put_u8(u8::from(self.unique_device));
+        buffer.put_u8(u8::from(self.blacklisted));
+    }
+}
+
+// Value of the KeyIdUsageEntry attribute, the frames a key may protect.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct KeyUsage {
+    pub frame_type: u8,
+    pub command_frame_id: u8,
+}
+
+impl KeyUsage {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let frame_type = cursor.read_u8()?;
+        let command_frame_id = cursor.read_u8()?;
+        Ok(KeyUsage {
+            frame_type,
+            command_frame_id,
+        })
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u8(self.frame_type);
+        buffer.put_u8(self.command_frame_id);
+    }
+}
+
+// Value of the KeyEntry attribute, the key material and its outgoing frame
+// counter.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct KeyEntry {
+    pub key: [u8; 16],
+    pub frame_counter: u32,
+}
+
+impl KeyEntry {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let mut key = [0x00; 16];
+        cursor.read_exact(&mut key)?;
+        let frame_counter = cursor.read_u32_le()?;
+        Ok(KeyEntry { key, frame_counter })
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.extend(self.key.iter());
+        buffer.put_u32_le(self.frame_counter);
+    }
+}
+
+// Value of the SecurityLevelEntry attribute, the minimum security level of
+// a frame type. Devices marked exempt may use a lower one when
+// `override_minimum` is set.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct SecurityLevelEntry {
+    pub frame_type: u8,
+    pub command_frame_id: u8,
+    pub security_minimum: u8,
+    pub override_minimum: bool,
+}
+
+impl SecurityLevelEntry {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let frame_type = cursor.read_u8()?;
+        let command_frame_id = cursor.read_u8()?;
+        let security_minimum = cursor.read_u8()?;
+        let override_minimum = cursor.read_u8()? != 0;
+        Ok(SecurityLevelEntry {
+            frame_type,
+            command_frame_id,
+            security_minimum,
+            override_minimum,
+        })
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u8(self.frame_type);
+        buffer.put_u8(self.command_frame_id);
+        buffer.put_u8(self.security_minimum);
+        buffer.put_u8(u8::from(self.override_minimum));
+    }
+
+    /// Write of the entry at `index` of the security level table.
+    pub fn set_request(&self, index: u16) -> SecuritySetReq {
+        set_request(SecurityPIBAttributeId::SecurityLevelEntry, index, 0, |x| {
+            self.encode_into(x)
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct KeyDescriptor {
+    pub key: [u8; 16],
+    pub frame_counter: u32,
+    pub lookups: Vec<KeyLookup>,
+    pub devices: Vec<KeyDeviceEntry>,
+    pub usages: Vec<KeyUsage>,
+}
+
+impl KeyDescriptor {
+    pub fn entry(&self) -> KeyEntry {
+        KeyEntry {
+            key: self.key,
+            frame_counter: self.frame_counter,
+        }
+    }
+}
+
+/// Host side mirror of the key table. Installing a key returns the security
+/// PIB writes for the key, its lookup, device and usage descriptors.
+#[derive(Debug, Clone)]
+pub struct KeyTable {
+    capacity: u16,
+    keys: BTreeMap<u16, KeyDescriptor>,
+}
+
+impl KeyTable {
+    pub fn new(capacity: u16) -> KeyTable {
+        KeyTable {
+            capacity,
+            keys: BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&self, index: u16) -> Option<&KeyDescriptor> {
+        self.keys.get(&index)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = (u16, &KeyDescriptor)> {
+        self.keys.iter().map(|(index, key)| (*index, key))
+    }
+
+    /// Installs `key` at `index`, replacing the key already there. Each of
+    /// its descriptor lists is indexed by a u16, longer ones fail with
+    /// `TableFull`.
+    pub fn install(
+        &mut self,
+        index: u16,
+        key: KeyDescriptor,
+    ) -> Result<Vec<SecuritySetReq>, Error> {
+        const MAX_DESCRIPTORS: usize = u16::MAX as usize + 1;
+        if index >= self.capacity
+            || key.lookups.len() > MAX_DESCRIPTORS
+            || key.devices.len() > MAX_DESCRIPTORS
+            || key.usages.len() > MAX_DESCRIPTORS
+        {
+            return Err(Error::TableFull);
+        }
+
+        let requests = key_requests(index, &key);
+        self.keys.insert(index, key);
+        Ok(requests
+            .into_iter()
+            .chain(core::iter::once(self.entries_request()))
+            .collect())
+    }
+
+    /// Reads the outgoing frame counter of a key. Rotation is due before it
+    /// wraps, feed the response to `update_frame_counter`.
+    pub fn frame_counter_request(&self, index: u16) -> SecurityGetReq {
+        SecurityGetReq {
+            attribute_id: SecurityPIBAttributeId::KeyEntry,
+            index1: index,
+            index2: 0,
+        }
+    }
+
+    pub fn update_frame_counter(
+        &mut self,
+        index: u16,
+        response: &srsp::SecurityGetReq,
+    ) -> Result<u32, Error> {
+        response
+            .status
+            .into_result()
+            .map_err(Error::CommandFailed)?;
+        let mut cursor = Reader::new(response.data.as_slice());
+        let frame_counter = KeyEntry::try_decode(&mut cursor)?.frame_counter;
+        if let Some(key) = self.keys.get_mut(&index) {
+            key.frame_counter = frame_counter;
+        }
+        Ok(frame_counter)
+    }
+
+    /// Follows a device table removal. Rows bound to the removed device are
+    /// replaced by `KeyDeviceEntry::UNUSED` and rows bound to the moved device
+    /// are pointed at its new index. Returns the writes for the changed rows.
+    pub fn device_removed(&mut self, removal: &DeviceRemoval) -> Vec<SecuritySetReq> {
+        let mut requests = Vec::new();
+        for (index, key) in self.keys.iter_mut() {
+            for (device, i) in key.devices.iter_mut().zip(0..=u16::MAX) {
+                if device.device_handle == removal.index {
+                    *device = KeyDeviceEntry::UNUSED;
+                } else if Some(device.device_handle) == removal.moved_from {
+                    device.device_handle = removal.index;
+                } else {
+                    continue;
+                }
+                requests.push(set_request(
+                    SecurityPIBAttributeId::KeyIdDeviceEntry,
+                    *index,
+                    i,
+                    |x| device.encode_into(x),
+                ));
+            }
+        }
+        requests
+    }
+
+    /// Writes for the whole table, e.g. after a coprocessor reset.
+    pub fn restore_requests(&self) -> Vec<SecuritySetReq> {
+        let mut requests: Vec<_> = self
+            .keys
+            .iter()
+            .flat_map(|(index, key)| key_requests(*index, key))
+            .collect();
+        requests.push(self.entries_request());
+        requests
+    }
+
+    fn entries_request(&self) -> SecuritySetReq {
+        let entries = self.keys.keys().next_back().map_or(0, |index| index + 1);
+        let mut attribute_value = Vec::new();
+        attribute_value.put_u16_le(entries);
+        SecuritySetReq {
+            attribute_id: SecurityPIBAttributeId::KeyTableEntries,
+            index1: 0,
+            index2: 0,
+            attribute_value,
+        }
+    }
+}
+
+fn key_requests(index: u16, key: &KeyDescriptor) -> Vec<SecuritySetReq> {
+    let entry = key.entry();
+    let mut requests = vec![set_request(
+        SecurityPIBAttributeId::KeyEntry,
+        index,
+        0,
+        |x| entry.encode_into(x),
+    )];
+    requests.extend(key.lookups.iter().zip(0..=u16::MAX).map(|(lookup, i)| {
+        set_request(SecurityPIBAttributeId::KeyIdLookupEntry, index, i, |x| {
+            lookup.encode_into(x)
+        })
+    }));
+    requests.extend(key.devices.iter().zip(0..=u16::MAX).map(|(device, i)| {
+        set_request(SecurityPIBAttributeId::KeyIdDeviceEntry, index, i, |x| {
+            device.encode_into(x)
+        })
+    }));
+    requests.extend(key.usages.iter().zip(0..=u16::MAX).map(|(usage, i)| {
+        set_request(SecurityPIBAttributeId::KeyIdUsageEntry, index, i, |x| {
+            usage.encode_into(x)
+        })
+    }));
+    requests
+}
+
+fn device_entry_request(index: u16, entry: &DeviceEntry) -> SecuritySetReq {
+    set_request(SecurityPIBAttributeId::DeviceEntry, index, 0, |x| {
+        entry.encode_into(x)
+    })
+}
+
+fn set_request<F: FnOnce(&mut Vec<u8>)>(
+    attribute_id: SecurityPIBAttributeId,
+    index1: u16,
+    index2: u16,
+    encode: F,
+) -> SecuritySetReq {
+    let mut attribute_value = Vec::new();
+    encode(&mut attribute_value);
+    SecuritySetReq {
+        attribute_id,
+        index1,
+        index2,
+        attribute_value,
+    }
+}
+
+fn encode_records<I: ExactSizeIterator<Item = Vec<u8>>>(
+    buffer: &mut Vec<u8>,
+    records: I,
+) -> Result<(), Error> {
+    let count = u16::try_from(records.len()).map_err(|_| Error::TableFull)?;
+    buffer.put_u16_le(count);
+    for record in records {
+        let length =
+            u16::try_from(record.len()).map_err(|_| Error::PayloadTooLarge(record.len()))?;
+        buffer.put_u16_le(length);
+        buffer.extend(record);
+    }
+    Ok(())
+}
+
+fn decode_records(cursor: &mut Reader) -> Result<Vec<Vec<u8>>, Error> {
+    let count = cursor.read_u16_le()?;
+    let mut records = Vec::new();
+    for _ in 0..count {
+        let mut record = vec![0x00; cursor.read_u16_le()? as usize];
+        cursor.read_exact(&mut record)?;
+        records.push(record);
+    }
+    Ok(records)
+}
@@ -0,0 +1,268 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::frame::MTFrame;
+use crate::subsystem::mac::{areq, sreq, srsp};
+use crate::subsystem::rpc::MTCommandError;
+use crate::subsystem::sys;
+use crate::subsystem::MTFramePayload;
+use crate::types::{
+    Address, AssociationStatus, CapabilityInfo, CommEventReason, ErrorCode, ExtendedAddress, Lqi,
+    MACPIBAttributeId, PanId, ProductIdCode, ResetReason, Rssi, Security, ShortAddress, Status,
+    TransportProtocolRevision,
+};
+use std::collections::{HashMap, VecDeque};
+
+// SYS and MAC, as reported by SYS_PING.
+const CAPABILITIES: u16 = 0x0003;
+
+/// Software model of a 15.4 coprocessor, for running association and data
+/// flows without hardware. Requests are answered with the SRSP the stack
+/// would send and the indications and confirms they cause are queued for
+/// `receive`. The radio is not modelled: data sent to a peer is confirmed
+/// and echoed back by it, data sent anywhere else fails with `NoAck`.
+pub struct Coprocessor {
+    extended_address: ExtendedAddress,
+    pib: HashMap<MACPIBAttributeId, [u8; 16]>,
+    peers: Vec<Address>,
+    // Short addresses handed out to devices associating with `AssociateReq`.
+    next_address: u16,
+    indications: VecDeque<MTFrame>,
+}
+
+impl Coprocessor {
+    pub fn new(extended_address: ExtendedAddress) -> Coprocessor {
+        let mut coprocessor = Coprocessor {
+            extended_address,
+            pib: HashMap::new(),
+            peers: Vec::new(),
+            next_address: 0x0001,
+            indications: VecDeque::new(),
+        };
+        coprocessor.reset_pib();
+        coprocessor
+    }
+
+    /// Delivers an AREQ, such as `SYS_RESET_REQ`, to the coprocessor.
+    pub fn send(&mut self, frame: MTFrame) -> Result<(), Error> {
+        if let MTFramePayload::SYS_ResetReq_AREQ(_) = MTFramePayload::try_from_frame(&frame)? {
+            self.reset_pib();
+            self.peers.clear();
+            self.indications.clear();
+            self.indicate(
+                sys::areq::ResetInd {
+                    reason: ResetReason::HostRequest,
+                    transport: TransportProtocolRevision::ExtendedRPCFrame,
+                    product: ProductIdCode::TI154Stack,
+                    major: 2,
+                    minor: 0,
+                    maint: 0,
+                }
+                .into_mt_frame(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Next frame the coprocessor sent outside a request.
+    pub fn receive(&mut self) -> Option<MTFrame> {
+        self.indications.pop_front()
+    }
+
+    /// Has a virtual device ask to join, as a coordinator would see it.
+    pub fn join(&mut self, extended_address: ExtendedAddress) {
+        self.indicate(
+            areq::AssociateInd {
+                extended_address,
+                capabilities: CapabilityInfo::ALLOCATE_ADDRESS,
+                security: Security::none(),
+            }
+            .into_mt_frame(),
+        );
+    }
+
+    /// Value of a PIB attribute, as written with `SetReq`.
+    pub fn pib(&self, attribute_id: MACPIBAttributeId) -> [u8; 16] {
+        self.pib.get(&attribute_id).cloned().unwrap_or_default()
+    }
+
+    fn indicate(&mut self, frame: MTFrame) {
+        self.indications.push_back(frame);
+    }
+
+    fn reset_pib(&mut self) {
+        let mut extended_address = [0x00; 16];
+        for (to, from) in extended_address
+            .iter_mut()
+            .zip(self.extended_address.address.iter().rev())
+        {
+            *to = *from;
+        }
+        self.pib.clear();
+        self.pib
+            .insert(MACPIBAttributeId::ExtendedAddress, extended_address);
+        self.set_u16(MACPIBAttributeId::ShortAddress, 0xffff);
+        self.set_u16(MACPIBAttributeId::PANId, 0xffff);
+    }
+
+    fn get_u16(&self, attribute_id: MACPIBAttributeId) -> u16 {
+        let value = self.pib(attribute_id);
+        u16::from_le_bytes([value[0], value[1]])
+    }
+
+    fn set_u16(&mut self, attribute_id: MACPIBAttributeId, value: u16) {
+        let mut attribute_value = [0x00; 16];
+        attribute_value[..2].copy_from_slice(&value.to_le_bytes());
+        self.pib.insert(attribute_id, attribute_value);
+    }
+
+    fn on_associate_req(&mut self, request: sreq::AssociateReq) -> MTFrame {
+        let short_address = ShortAddress::from(self.next_address);
+        self.next_address = self.next_address.wrapping_add(1);
+        self.set_u16(MACPIBAttributeId::ShortAddress, short_address.u16());
+        self.set_u16(MACPIBAttributeId::PANId, request.coord_pan_id.u16());
+        self.peers.push(request.coord_address);
+        self.indicate(
+            areq::AssociateCnf {
+                status: Status::Success,
+                short_address,
+                security: Security::none(),
+            }
+            .into_mt_frame(),
+        );
+        srsp::AssociateReq {
+            status: Status::Success,
+        }
+        .into_mt_frame()
+    }
+
+    fn on_associate_rsp(&mut self, response: sreq::AssociateRsp) -> MTFrame {
+        if response.assoc_status == AssociationStatus::Successful {
+            self.peers
+                .push(Address::Addr16Bit(response.assoc_short_address));
+            self.peers
+                .push(Address::Addr64Bit(response.extended_address));
+        }
+        self.indicate(
+            areq::CommStatusInd {
+                status: Status::Success,
+                src_addr: Address::Addr64Bit(self.extended_address),
+                dst_addr: Address::Addr64Bit(response.extended_address),
+                device_pan_id: PanId(self.get_u16(MACPIBAttributeId::PANId)),
+                reason: CommEventReason::AssociateRsp,
+                security: Security::none(),
+            }
+            .into_mt_frame(),
+        );
+        srsp::AssociateRsp {
+            status: Status::Success,
+        }
+        .into_mt_frame()
+    }
+
+    fn on_data_req(&mut self, request: sreq::DataReq) -> MTFrame {
+        let delivered = self.peers.contains(&request.dest_address);
+        self.indicate(
+            areq::DataCnf {
+                status: if delivered {
+                    Status::Success
+                } else {
+                    Status::NoAck
+                },
+                handle: request.handle,
+                timestamp: 0,
+                timestamp2: 0,
+                retries: 0,
+                link_quality: Lqi(0xff),
+                correlation: 0,
+                rssi: Rssi(-40),
+                frame_counter: 0,
+            }
+            .into_mt_frame(),
+        );
+        if delivered {
+            let pan_id = PanId(self.get_u16(MACPIBAttributeId::PANId));
+            self.indicate(
+                areq::DataInd {
+                    src_address: request.dest_address,
+                    dest_address: Address::Addr16Bit(ShortAddress::from(
+                        self.get_u16(MACPIBAttributeId::ShortAddress),
+                    )),
+                    timestamp: 0,
+                    timestamp2: 0,
+                    src_pan_id: pan_id,
+                    dest_pan_id: pan_id,
+                    link_quality: Lqi(0xff),
+                    correlation: 0,
+                    rssi: Rssi(-40),
+                    dsn: 0,
+                    security: Security::none(),
+                    frame_counter: 0,
+                    data_length: request.data_length,
+                    ie_length: 0,
+                    data_payload: request.data_payload,
+                    ie_payload: Vec::new(),
+                }
+                .into_mt_frame(),
+            );
+        }
+        srsp::DataReq {
+            status: Status::Success,
+        }
+        .into_mt_frame()
+    }
+}
+
+impl Client for Coprocessor {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(command = ?frame.header.command))
+    )]
+    fn request(&mut self, frame: MTFrame) -> Result<MTFrame, Error> {
+        use MTFramePayload::*;
+        let status = Status::Success;
+        let response = match MTFramePayload::try_from_frame(&frame)? {
+            SYS_PingReq_SREQ(_) => sys::srsp::PingReq {
+                capabilities: CAPABILITIES,
+            }
+            .into_mt_frame(),
+            SYS_VersionReq_SREQ(_) => sys::srsp::VersionReq {
+                transport: TransportProtocolRevision::ExtendedRPCFrame,
+                product: ProductIdCode::TI154Stack,
+                major: 2,
+                minor: 0,
+                maint: 0,
+            }
+            .into_mt_frame(),
+            MAC_ResetReq_SREQ(x) => {
+                if x.set_default {
+                    self.reset_pib();
+                    self.peers.clear();
+                }
+                srsp::ResetReq { status }.into_mt_frame()
+            }
+            MAC_GetReq_SREQ(x) => srsp::GetReq {
+                status,
+                data: self.pib(x.attribute_id),
+            }
+            .into_mt_frame(),
+            MAC_SetReq_SREQ(x) => {
+                self.pib.insert(x.attribute_id, x.attribute_value);
+                srsp::SetReq { status }.into_mt_frame()
+            }
+            MAC_StartReq_SREQ(x) => {
+                self.set_u16(MACPIBAttributeId::PANId, x.pan_id.u16());
+                self.indicate(areq::StartCnf { status }.into_mt_frame());
+                srsp::StartReq { status }.into_mt_frame()
+            }
+            MAC_AssociateReq_SREQ(x) => self.on_associate_req(x),
+            MAC_AssociateRsp_SREQ(x) => self.on_associate_rsp(x),
+            MAC_DataReq_SREQ(x) => self.on_data_req(x),
+            _ => MTCommandError {
+                error_code: ErrorCode::InvalidCommandId,
+                command: frame.header.command.clone(),
+            }
+            .into_mt_frame(),
+        };
+        Ok(response)
+    }
+}
@@ -0,0 +1,382 @@
+use crate::error::Error;
+use crate::fragment::{self, Reassembler, DISPATCH_MASK, FRAG1, FRAG1_HEADER_SIZE};
+use crate::reader::Reader;
+use crate::subsystem::mac::areq::DataInd;
+use crate::types::Address;
+use std::time::{Duration, Instant};
+
+// Dispatch values from RFC 4944 and RFC 6282.
+const IPV6: u8 = 0x41;
+const IPHC: u8 = 0x60;
+const IPHC_MASK: u8 = 0xe0;
+
+const IPV6_HEADER_SIZE: usize = 40;
+const LINK_LOCAL_PREFIX: [u8; 8] = [0xfe, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+// Interface identifier built from a short address, 0000:00ff:fe00:XXXX.
+const SHORT_IID_PREFIX: [u8; 6] = [0x00, 0x00, 0x00, 0xff, 0xfe, 0x00];
+
+fn link_local_iid(address: &Address) -> Option<[u8; 8]> {
+    let mut iid = [0x00; 8];
+    match address {
+        Address::Addr64Bit(address) => {
+            iid.copy_from_slice(&address.address);
+            // The universal/local bit is inverted in the EUI-64 based IID.
+            iid[0] ^= 0x02;
+        }
+        Address::Addr16Bit(address) => {
+            iid[..6].copy_from_slice(&SHORT_IID_PREFIX);
+            iid[6..].copy_from_slice(&address.u16().to_be_bytes());
+        }
+        Address::None => return None,
+    }
+    Some(iid)
+}
+
+fn compress_unicast(address: &[u8], link_address: &Address, inline: &mut Vec<u8>) -> u8 {
+    if address[..8] != LINK_LOCAL_PREFIX {
+        inline.extend(address.iter());
+        return 0b00;
+    }
+    let iid = &address[8..];
+    if link_local_iid(link_address).is_some_and(|x| x[..] == *iid) {
+        0b11
+    } else if iid[..6] == SHORT_IID_PREFIX {
+        inline.extend(iid[6..].iter());
+        0b10
+    } else {
+        inline.extend(iid.iter());
+        0b01
+    }
+}
+
+fn compress_multicast(address: &[u8], inline: &mut Vec<u8>) -> u8 {
+    let zeros_until = |end: usize| address[2..end].iter().all(|byte| *byte == 0x00);
+    if address[1] == 0x02 && zeros_until(15) {
+        inline.push(address[15]);
+        0b11
+    } else if zeros_until(13) {
+        inline.push(address[1]);
+        inline.extend(address[13..].iter());
+        0b10
+    } else if zeros_until(11) {
+        inline.push(address[1]);
+        inline.extend(address[11..].iter());
+        0b01
+    } else {
+        inline.extend(address.iter());
+        0b00
+    }
+}
+
+/// Compresses the header of the IPv6 `packet` with IPHC, the payload follows
+/// unchanged. Only link-local addresses are compressed, there are no shared
+/// contexts, and the next header is always carried inline.
+pub fn compress(packet: &[u8], source: &Address, destination: &Address) -> Result<Vec<u8>, Error> {
+    if packet.len() < IPV6_HEADER_SIZE || packet[0] >> 4 != 6 {
+        return Err(Error::InvalidIpv6Packet);
+    }
+    let payload_length = usize::from(u16::from_be_bytes([packet[4], packet[5]]));
+    if payload_length != packet.len() - IPV6_HEADER_SIZE {
+        return Err(Error::InvalidIpv6Packet);
+    }
+
+    let traffic_class = (packet[0] & 0x0f) << 4 | packet[1] >> 4;
+    let dscp = traffic_class >> 2;
+    let ecn = traffic_class & 0x03;
+    let flow_label = u32::from_be_bytes([0x00, packet[1] & 0x0f, packet[2], packet[3]]);
+
+    let mut header = vec![IPHC, 0x00];
+    let mut inline = Vec::new();
+
+    let tf = match (dscp, flow_label) {
+        (0, 0) if ecn == 0 => 0b11,
+        (_, 0) => {
+            inline.push(ecn << 6 | dscp);
+            0b10
+        }
+        (0, _) => {
+            inline.push(ecn << 6 | packet[1] & 0x0f);
+            inline.extend(packet[2..4].iter());
+            0b01
+        }
+        _ => {
+            inline.push(ecn << 6 | dscp);
+            inline.push(packet[1] & 0x0f);
+            inline.extend(packet[2..4].iter());
+            0b00
+        }
+    };
+    header[0] |= tf << 3;
+
+    inline.push(packet[6]);
+
+    header[0] |= match packet[7] {
+        1 => 0b01,
+        64 => 0b10,
+        255 => 0b11,
+        hop_limit => {
+            inline.push(hop_limit);
+            0b00
+        }
+    };
+
+    header[1] |= compress_unicast(&packet[8..24], source, &mut inline) << 4;
+
+    let destination_address = &packet[24..IPV6_HEADER_SIZE];
+    header[1] |= match destination_address[0] {
+        0xff => 0x08 | compress_multicast(destination_address, &mut inline),
+        _ => compress_unicast(destination_address, destination, &mut inline),
+    };
+
+    header.extend(inline);
+    header.extend(packet[IPV6_HEADER_SIZE..].iter());
+    Ok(header)
+}
+
+fn decompress_unicast(
+    cursor: &mut Reader,
+    mode: u8,
+    link_address: &Address,
+) -> Result<[u8; 16], Error> {
+    let mut address = [0x00; 16];
+    if mode == 0b00 {
+        cursor.read_exact(&mut address)?;
+        return Ok(address);
+    }
+
+    address[..8].copy_from_slice(&LINK_LOCAL_PREFIX);
+    match mode {
+        0b01 => cursor.read_exact(&mut address[8..])?,
+        0b10 => {
+            address[8..14].copy_from_slice(&SHORT_IID_PREFIX);
+            cursor.read_exact(&mut address[14..])?;
+        }
+        _ => {
+            let iid = link_local_iid(link_address).ok_or(Error::InvalidIpv6Packet)?;
+            address[8..].copy_from_slice(&iid);
+        }
+    }
+    Ok(address)
+}
+
+fn decompress_multicast(cursor: &mut Reader, mode: u8) -> Result<[u8; 16], Error> {
+    let mut address = [0x00; 16];
+    address[0] = 0xff;
+    match mode {
+        0b00 => cursor.read_exact(&mut address)?,
+        0b01 => {
+            address[1] = cursor.read_u8()?;
+            cursor.read_exact(&mut address[11..])?;
+        }
+        0b10 => {
+            address[1] = cursor.read_u8()?;
+            cursor.read_exact(&mut address[13..])?;
+        }
+        _ => {
+            address[1] = 0x02;
+            address[15] = cursor.read_u8()?;
+        }
+    }
+    Ok(address)
+}
+
+// Rebuilds the IPv6 header from the IPHC header at the cursor, with a zero
+// payload length for the caller to fill in.
+fn decompress_header(
+    cursor: &mut Reader,
+    source: &Address,
+    destination: &Address,
+) -> Result<[u8; IPV6_HEADER_SIZE], Error> {
+    let first = cursor.read_u8()?;
+    let second = cursor.read_u8()?;
+    if first & IPHC_MASK != IPHC {
+        return Err(Error::InvalidIpv6Packet);
+    }
+    // Context based compression and compressed next headers are not
+    // supported.
+    let stateful =
+        second & 0x80 != 0 || (second & 0x40 != 0 && second & 0x30 != 0) || second & 0x04 != 0;
+    if stateful || first & 0x04 != 0 {
+        return Err(Error::NotImplemented);
+    }
+
+    let (mut ecn, mut dscp, mut flow_label) = (0, 0, [0x00; 3]);
+    match (first >> 3) & 0b11 {
+        0b00 => {
+            let byte = cursor.read_u8()?;
+            ecn = byte >> 6;
+            dscp = byte & 0x3f;
+            cursor.read_exact(&mut flow_label)?;
+        }
+        0b01 => {
+            cursor.read_exact(&mut flow_label)?;
+            ecn = flow_label[0] >> 6;
+        }
+        0b10 => {
+            let byte = cursor.read_u8()?;
+            ecn = byte >> 6;
+            dscp = byte & 0x3f;
+        }
+        _ => (),
+    }
+    let traffic_class = dscp << 2 | ecn;
+
+    let next_header = cursor.read_u8()?;
+    let hop_limit = match first & 0b11 {
+        0b00 => cursor.read_u8()?,
+        0b01 => 1,
+        0b10 => 64,
+        _ => 255,
+    };
+
+    // SAC with SAM 00 is the unspecified address.
+    let source_address = match second & 0x40 {
+        0 => decompress_unicast(cursor, (second >> 4) & 0b11, source)?,
+        _ => [0x00; 16],
+    };
+    let destination_address = match second & 0x08 {
+        0 => decompress_unicast(cursor, second & 0b11, destination)?,
+        _ => decompress_multicast(cursor, second & 0b11)?,
+    };
+
+    let mut header = [0x00; IPV6_HEADER_SIZE];
+    header[0] = 0x60 | traffic_class >> 4;
+    header[1] = traffic_class << 4 | flow_label[0] & 0x0f;
+    header[2..4].copy_from_slice(&flow_label[1..]);
+    header[6] = next_header;
+    header[7] = hop_limit;
+    header[8..24].copy_from_slice(&source_address);
+    header[24..].copy_from_slice(&destination_address);
+    Ok(header)
+}
+
+/// Expands an IPHC compressed MSDU back into the IPv6 packet.
+pub fn decompress(msdu: &[u8], source: &Address, destination: &Address) -> Result<Vec<u8>, Error> {
+    let mut cursor = Reader::new(msdu);
+    let mut packet = decompress_header(&mut cursor, source, destination)?.to_vec();
+    let payload = &msdu[cursor.position()..];
+    packet[4..6].copy_from_slice(&(payload.len() as u16).to_be_bytes());
+    packet.extend(payload.iter());
+    Ok(packet)
+}
+
+/// Carries IPv6 packets over data requests and indications, compressing
+/// their headers and fragmenting the ones larger than `mtu`.
+#[derive(Debug)]
+pub struct SixLowPan {
+    mtu: usize,
+    next_tag: u16,
+    reassembler: Reassembler,
+}
+
+impl SixLowPan {
+    /// Incomplete packets are dropped by `poll` after `timeout`.
+    pub fn new(mtu: usize, timeout: Duration) -> SixLowPan {
+        SixLowPan {
+            mtu,
+            next_tag: 0,
+            reassembler: Reassembler::new(timeout),
+        }
+    }
+
+    /// Returns the MSDUs to send `packet` from `source` to `destination`.
+    pub fn send(
+        &mut self,
+        packet: &[u8],
+        source: &Address,
+        destination: &Address,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let compressed = compress(packet, source, destination)?;
+        if compressed.len() <= self.mtu {
+            return Ok(vec![compressed]);
+        }
+        if packet.len() > fragment::MAX_PAYLOAD_SIZE {
+            return Err(Error::PayloadTooLarge(packet.len()));
+        }
+
+        // Offsets count bytes of the uncompressed packet, so the compressed
+        // header plus the first chunk has to end on an 8 byte boundary once
+        // expanded.
+        let header_size = compressed.len() - (packet.len() - IPV6_HEADER_SIZE);
+        let room = self.mtu.saturating_sub(FRAG1_HEADER_SIZE + header_size);
+        let first_end = (IPV6_HEADER_SIZE + room) / 8 * 8;
+        let next_size = self.mtu.saturating_sub(fragment::FRAGN_HEADER_SIZE) / 8 * 8;
+        if first_end <= IPV6_HEADER_SIZE || next_size == 0 {
+            return Err(Error::PayloadTooLarge(packet.len()));
+        }
+
+        let tag = self.next_tag;
+        self.next_tag = self.next_tag.wrapping_add(1);
+        let size = packet.len() as u16;
+
+        let mut first = fragment::frag1_header(size, tag);
+        first.extend(compressed[..header_size].iter());
+        first.extend(packet[IPV6_HEADER_SIZE..first_end].iter());
+
+        let mut fragments = vec![first];
+        let mut offset = first_end;
+        while offset < packet.len() {
+            let end = (offset + next_size).min(packet.len());
+            let mut fragment = fragment::fragn_header(size, tag, offset);
+            fragment.extend(packet[offset..end].iter());
+            fragments.push(fragment);
+            offset = end;
+        }
+        Ok(fragments)
+    }
+
+    pub fn on_data_ind(
+        &mut self,
+        indication: &DataInd,
+        now: Instant,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.receive(
+            &indication.src_address,
+            &indication.dest_address,
+            &indication.data_payload,
+            now,
+        )
+    }
+
+    /// Returns the IPv6 packet once `msdu` completes it.
+    pub fn receive(
+        &mut self,
+        source: &Address,
+        destination: &Address,
+        msdu: &[u8],
+        now: Instant,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        match msdu.first() {
+            Some(&IPV6) => Ok(Some(msdu[1..].to_vec())),
+            Some(byte) if byte & IPHC_MASK == IPHC => {
+                decompress(msdu, source, destination).map(Some)
+            }
+            Some(byte) if byte & DISPATCH_MASK == FRAG1 => {
+                // Expand the header so the reassembler only sees offsets into
+                // the uncompressed packet.
+                if msdu.len() < FRAG1_HEADER_SIZE {
+                    return Err(Error::InvalidFragment);
+                }
+                let size = usize::from(u16::from_be_bytes([msdu[0] & 0x07, msdu[1]]));
+                let payload_length = size
+                    .checked_sub(IPV6_HEADER_SIZE)
+                    .ok_or(Error::InvalidFragment)?;
+                let mut cursor = Reader::new(&msdu[FRAG1_HEADER_SIZE..]);
+                let mut header = decompress_header(&mut cursor, source, destination)?;
+                header[4..6].copy_from_slice(&(payload_length as u16).to_be_bytes());
+
+                let mut expanded = msdu[..FRAG1_HEADER_SIZE].to_vec();
+                expanded.extend(header.iter());
+                expanded.extend(msdu[FRAG1_HEADER_SIZE + cursor.position()..].iter());
+                self.reassembler.receive(*source, &expanded, now)
+            }
+            _ if fragment::is_fragment(msdu) => self.reassembler.receive(*source, msdu, now),
+            _ => Err(Error::InvalidIpv6Packet),
+        }
+    }
+
+    /// Drops the packets that timed out and returns their source and tag.
+    pub fn poll(&mut self, now: Instant) -> Vec<(Address, u16)> {
+        self.reassembler.poll(now)
+    }
+}
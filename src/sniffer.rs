@@ -0,0 +1,122 @@
+use crate::client::{self, Client};
+use crate::error::Error;
+use crate::frame::MTFrame;
+use crate::subsystem::mac::areq;
+use crate::subsystem::MTFramePayload;
+use crate::types::{Address, Lqi, MACPIBAttributeId, PanId, PhyId, Rssi};
+use alloc::vec::Vec;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct SnifferConfig {
+    pub logical_channel: u8,
+    pub channel_page: u8,
+    pub phy_id: PhyId,
+}
+
+/// A frame heard while in promiscuous mode, with the radio metadata the
+/// coprocessor attached to it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SniffedFrame {
+    pub logical_channel: u8,
+    pub timestamp: u32,
+    pub timestamp2: u16,
+    pub rssi: Rssi,
+    pub link_quality: Lqi,
+    pub dsn: u8,
+    pub src_address: Address,
+    pub src_pan_id: PanId,
+    pub dest_address: Address,
+    pub dest_pan_id: PanId,
+    pub payload: Vec<u8>,
+}
+
+/// Puts the MAC in promiscuous mode on a fixed channel so every frame on the
+/// air is reported as a data indication.
+pub struct Sniffer<C> {
+    client: C,
+    config: SnifferConfig,
+}
+
+impl<C: Client> Sniffer<C> {
+    pub fn new(client: C, config: SnifferConfig) -> Sniffer<C> {
+        Sniffer { client, config }
+    }
+
+    pub fn client(&mut self) -> &mut C {
+        &mut self.client
+    }
+
+    pub fn config(&self) -> &SnifferConfig {
+        &self.config
+    }
+
+    /// Tunes the radio and turns promiscuous mode on.
+    pub fn start(&mut self) -> Result<(), Error> {
+        let SnifferConfig {
+            logical_channel,
+            channel_page,
+            phy_id,
+        } = self.config;
+        self.set(MACPIBAttributeId::PhyCurrentDescriptorId, phy_id.u8())?;
+        self.set(MACPIBAttributeId::ChannelPage, channel_page)?;
+        self.set(MACPIBAttributeId::LogicalChannel, logical_channel)?;
+        self.set(MACPIBAttributeId::RxOnWhenIdle, 1)?;
+        self.set(MACPIBAttributeId::PromiscuousMode, 1)
+    }
+
+    pub fn stop(&mut self) -> Result<(), Error> {
+        self.set(MACPIBAttributeId::PromiscuousMode, 0)?;
+        self.set(MACPIBAttributeId::RxOnWhenIdle, 0)
+    }
+
+    /// Moves to another channel without leaving promiscuous mode.
+    pub fn set_channel(&mut self, logical_channel: u8) -> Result<(), Error> {
+        self.set(MACPIBAttributeId::LogicalChannel, logical_channel)?;
+        self.config.logical_channel = logical_channel;
+        Ok(())
+    }
+
+    /// Returns the sniffed frame carried by `frame`, `None` for anything that
+    /// is not a data indication.
+    pub fn handle(&self, frame: &MTFrame) -> Result<Option<SniffedFrame>, Error> {
+        match MTFramePayload::try_from_frame(frame)? {
+            MTFramePayload::MAC_DataInd_AREQ(indication) => Ok(Some(self.sniffed(indication))),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn frames<'a, I>(
+        &'a self,
+        frames: I,
+    ) -> impl Iterator<Item = Result<SniffedFrame, Error>> + 'a
+    where
+        I: IntoIterator<Item = MTFrame>,
+        I::IntoIter: 'a,
+    {
+        frames
+            .into_iter()
+            .filter_map(move |frame| self.handle(&frame).transpose())
+    }
+
+    fn sniffed(&self, indication: areq::DataInd) -> SniffedFrame {
+        SniffedFrame {
+            logical_channel: self.config.logical_channel,
+            timestamp: indication.timestamp,
+            timestamp2: indication.timestamp2,
+            rssi: indication.rssi,
+            link_quality: indication.link_quality,
+            dsn: indication.dsn,
+            src_address: indication.src_address,
+            src_pan_id: indication.src_pan_id,
+            dest_address: indication.dest_address,
+            dest_pan_id: indication.dest_pan_id,
+            payload: indication.data_payload,
+        }
+    }
+
+    fn set(&mut self, attribute_id: MACPIBAttributeId, value: u8) -> Result<(), Error> {
+        let mut attribute_value = [0x00; 16];
+        attribute_value[0] = value;
+        client::set_mac_pib(&mut self.client, attribute_id, attribute_value)
+    }
+}
@@ -0,0 +1,117 @@
+use crate::error::Error;
+use crate::frame::MTFrame;
+use crate::types::MTSubsystem;
+
+const SUBSYSTEM_COUNT: usize = MTSubsystem::APP as usize + 1;
+
+/// Counters at a point in time, see `Stats::snapshot`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct Snapshot {
+    pub frames_sent: u64,
+    pub frames_received: u64,
+    pub checksum_failures: u64,
+    // Times the parser lost the frame boundary and had to skip bytes.
+    pub resyncs: u64,
+    pub discarded_bytes: u64,
+    pub timeouts: u64,
+    pub decode_errors: u64,
+    sent: [u64; SUBSYSTEM_COUNT],
+    received: [u64; SUBSYSTEM_COUNT],
+}
+
+impl Snapshot {
+    pub fn sent(&self, subsystem: MTSubsystem) -> u64 {
+        self.sent[subsystem as usize]
+    }
+
+    pub fn received(&self, subsystem: MTSubsystem) -> u64 {
+        self.received[subsystem as usize]
+    }
+
+    /// What was counted between `earlier` and this snapshot.
+    pub fn since(&self, earlier: &Snapshot) -> Snapshot {
+        let mut delta = Snapshot {
+            frames_sent: self.frames_sent.saturating_sub(earlier.frames_sent),
+            frames_received: self.frames_received.saturating_sub(earlier.frames_received),
+            checksum_failures: self
+                .checksum_failures
+                .saturating_sub(earlier.checksum_failures),
+            resyncs: self.resyncs.saturating_sub(earlier.resyncs),
+            discarded_bytes: self.discarded_bytes.saturating_sub(earlier.discarded_bytes),
+            timeouts: self.timeouts.saturating_sub(earlier.timeouts),
+            decode_errors: self.decode_errors.saturating_sub(earlier.decode_errors),
+            ..Snapshot::default()
+        };
+        for index in 0..SUBSYSTEM_COUNT {
+            delta.sent[index] = self.sent[index].saturating_sub(earlier.sent[index]);
+            delta.received[index] = self.received[index].saturating_sub(earlier.received[index]);
+        }
+        delta
+    }
+}
+
+/// Frame level counters for health monitoring. Feed it the frames written to
+/// the coprocessor and whatever the parser and client return.
+#[derive(Debug, Default)]
+pub struct Stats {
+    counters: Snapshot,
+    resyncing: bool,
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats::default()
+    }
+
+    pub fn frame_sent(&mut self, frame: &MTFrame) {
+        self.counters.frames_sent += 1;
+        self.counters.sent[frame.header.command.subsystem as usize] += 1;
+    }
+
+    pub fn frame_received(&mut self, frame: &MTFrame) {
+        self.resyncing = false;
+        self.counters.frames_received += 1;
+        self.counters.received[frame.header.command.subsystem as usize] += 1;
+    }
+
+    /// Counts the errors that say something about the link. Command failures
+    /// and other application level errors are ignored.
+    pub fn error(&mut self, error: &Error) {
+        match error {
+            Error::InvalidStartOfFrame(_) => {
+                if !self.resyncing {
+                    self.resyncing = true;
+                    self.counters.resyncs += 1;
+                }
+                self.counters.discarded_bytes += 1;
+                return;
+            }
+            Error::InvalidFrameCheckSequence(_) => self.counters.checksum_failures += 1,
+            Error::Timeout { .. } => self.counters.timeouts += 1,
+            Error::CommandFailed(_)
+            | Error::UnexpectedResponse(_)
+            | Error::NotAssociated
+            | Error::NotImplemented
+            | Error::TableFull
+            | Error::DeviceReset(_) => (),
+            _ => self.counters.decode_errors += 1,
+        }
+        self.resyncing = false;
+    }
+
+    /// Records a parser or client result.
+    pub fn record(&mut self, result: &Result<MTFrame, Error>) {
+        match result {
+            Ok(frame) => self.frame_received(frame),
+            Err(error) => self.error(error),
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        self.counters
+    }
+
+    pub fn reset(&mut self) {
+        *self = Stats::default();
+    }
+}
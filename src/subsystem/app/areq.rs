@@ -0,0 +1,74 @@
+use crate::error::Error;
+use crate::frame::{CommandCode, MTFrame};
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
+use crate::types::*;
+use crate::writer::BufMut;
+use alloc::vec::Vec;
+
+/// Payload sent up by application code running on the coprocessor.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AppMsgInd {
+    pub app_endpoint: u8,
+    pub src_address: ShortAddress,
+    pub src_endpoint: u8,
+    pub cluster_id: u16,
+    pub data_length: u8,
+    pub data: Vec<u8>,
+}
+
+impl AppMsgInd {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let app_endpoint = cursor.field("app_endpoint", Reader::read_u8)?;
+        let src_address = cursor.field("src_address", ShortAddress::try_decode)?;
+        let src_endpoint = cursor.field("src_endpoint", Reader::read_u8)?;
+        let cluster_id = cursor.field("cluster_id", Reader::read_u16_le)?;
+        let data_length = cursor.field("data_length", Reader::read_u8)?;
+
+        let mut data = Vec::new();
+        cursor.field("data", |cursor| cursor.read_to_end(&mut data))?;
+
+        Ok(AppMsgInd {
+            app_endpoint,
+            src_address,
+            src_endpoint,
+            cluster_id,
+            data_length,
+            data,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.encode_into(&mut buffer);
+        buffer
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u8(self.app_endpoint);
+        self.src_address.encode_into(buffer);
+        buffer.put_u8(self.src_endpoint);
+        buffer.put_u16_le(self.cluster_id);
+        buffer.put_u8(self.data_length);
+        buffer.extend(self.data.iter());
+    }
+
+    pub fn into_mt_frame(self) -> MTFrame {
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::AREQ,
+                subsystem: MTSubsystem::APP,
+                id: APPCommandId::AppMsg as u8,
+            },
+            self.encode(),
+        )
+    }
+}
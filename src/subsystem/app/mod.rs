@@ -0,0 +1,38 @@
+pub mod areq;
+pub mod sreq;
+pub mod srsp;
+
+use crate::error::Error;
+use crate::reader::DecodeOptions;
+use crate::subsystem::MTFramePayload;
+use crate::types::CommandType;
+use num_traits::FromPrimitive;
+
+pub fn try_decode(cmd_type: &CommandType, id: u8, buffer: &[u8]) -> Result<MTFramePayload, Error> {
+    try_decode_with(cmd_type, id, buffer, DecodeOptions::default())
+}
+
+pub fn try_decode_with(
+    cmd_type: &CommandType,
+    id: u8,
+    buffer: &[u8],
+    options: DecodeOptions,
+) -> Result<MTFramePayload, Error> {
+    use crate::types::APPCommandId::*;
+    use MTFramePayload::*;
+
+    let id = FromPrimitive::from_u8(id).ok_or(Error::InvalidCommandId(id))?;
+
+    match cmd_type {
+        CommandType::POLL => Err(Error::NotImplemented),
+        CommandType::SREQ => match id {
+            AppMsg => sreq::AppMsgReq::try_decode_with(buffer, options).map(APP_AppMsgReq_SREQ),
+        },
+        CommandType::AREQ => match id {
+            AppMsg => areq::AppMsgInd::try_decode_with(buffer, options).map(APP_AppMsgInd_AREQ),
+        },
+        CommandType::SRSP => match id {
+            AppMsg => srsp::AppMsgReq::try_decode_with(buffer, options).map(APP_AppMsgReq_SRSP),
+        },
+    }
+}
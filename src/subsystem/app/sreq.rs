@@ -0,0 +1,92 @@
+use crate::error::Error;
+use crate::frame::{CommandCode, MTFrame};
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
+use crate::types::*;
+use crate::writer::BufMut;
+use alloc::vec::Vec;
+
+/// Hands an opaque payload to application code running on the coprocessor.
+/// The endpoints and cluster are not interpreted by the MT layer.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AppMsgReq {
+    pub app_endpoint: u8,
+    pub dest_address: ShortAddress,
+    pub dest_endpoint: u8,
+    pub cluster_id: u16,
+    pub data_length: u8,
+    pub data: Vec<u8>,
+}
+
+impl AppMsgReq {
+    pub fn new(
+        app_endpoint: u8,
+        dest_address: ShortAddress,
+        dest_endpoint: u8,
+        cluster_id: u16,
+        data: Vec<u8>,
+    ) -> AppMsgReq {
+        AppMsgReq {
+            app_endpoint,
+            dest_address,
+            dest_endpoint,
+            cluster_id,
+            data_length: data.len() as u8,
+            data,
+        }
+    }
+
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let app_endpoint = cursor.field("app_endpoint", Reader::read_u8)?;
+        let dest_address = cursor.field("dest_address", ShortAddress::try_decode)?;
+        let dest_endpoint = cursor.field("dest_endpoint", Reader::read_u8)?;
+        let cluster_id = cursor.field("cluster_id", Reader::read_u16_le)?;
+        let data_length = cursor.field("data_length", Reader::read_u8)?;
+
+        let mut data = Vec::new();
+        cursor.field("data", |cursor| cursor.read_to_end(&mut data))?;
+
+        Ok(AppMsgReq {
+            app_endpoint,
+            dest_address,
+            dest_endpoint,
+            cluster_id,
+            data_length,
+            data,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.encode_into(&mut buffer);
+        buffer
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u8(self.app_endpoint);
+        self.dest_address.encode_into(buffer);
+        buffer.put_u8(self.dest_endpoint);
+        buffer.put_u16_le(self.cluster_id);
+        buffer.put_u8(self.data_length);
+        buffer.extend(self.data.iter());
+    }
+
+    pub fn into_mt_frame(self) -> MTFrame {
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::SREQ,
+                subsystem: MTSubsystem::APP,
+                id: APPCommandId::AppMsg as u8,
+            },
+            self.encode(),
+        )
+    }
+}
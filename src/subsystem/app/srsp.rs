@@ -0,0 +1,51 @@
+use crate::error::Error;
+use crate::frame::{CommandCode, MTFrame, MTHeader};
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
+use crate::types::*;
+use alloc::vec::Vec;
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AppMsgReq {
+    pub status: Status,
+}
+
+impl AppMsgReq {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        Ok(AppMsgReq { status })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.encode_into(&mut buffer);
+        buffer
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        self.status.encode_into(buffer);
+    }
+
+    pub fn into_mt_frame(self) -> MTFrame {
+        MTFrame {
+            header: MTHeader {
+                length: 0x01,
+                command: CommandCode {
+                    is_extended: false,
+                    cmd_type: CommandType::SRSP,
+                    subsystem: MTSubsystem::APP,
+                    id: APPCommandId::AppMsg as u8,
+                },
+            },
+            extended_header: None,
+            payload: self.encode(),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use crate::reader::DecodeOptions;
+use crate::types::CommandType::{self, AREQ, SREQ, SRSP};
+use crate::types::MTSubsystem::{self, APP, MAC, RPC, SYS, UTIL};
+use crate::types::{APPCommandId, MACCommandId, RPCCommandId, SYSCommandId, UTILCommandId};
+use core::fmt;
+use PayloadLength::{AtLeast, Exact};
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PayloadLength {
+    // Commands made only of fixed size fields.
+    Exact(usize),
+    // Commands ending in a list or buffer, with the size of everything else.
+    AtLeast(usize),
+}
+
+impl PayloadLength {
+    pub fn accepts(&self, length: usize) -> bool {
+        self.accepts_with(length, DecodeOptions::default())
+    }
+
+    pub fn accepts_with(&self, length: usize, options: DecodeOptions) -> bool {
+        match self {
+            Exact(expected) if options.trailing_bytes => length >= *expected,
+            Exact(expected) => length == *expected,
+            AtLeast(minimum) => length >= *minimum,
+        }
+    }
+}
+
+impl fmt::Display for PayloadLength {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Exact(length) => write!(f, "{} bytes", length),
+            AtLeast(length) => write!(f, "at least {} bytes", length),
+        }
+    }
+}
+
+// Payload size of every command the crate decodes, checked before any field
+// is read so a host and firmware disagreeing on a layout fail with
+// `Error::LengthMismatch` instead of decoding misaligned fields. Keep it in
+// step with the decoders, `payload_lengths_match_encoders` checks it against
+// the encoded size of random payloads.
+const PAYLOAD_LENGTHS: &[(MTSubsystem, CommandType, u8, PayloadLength)] = &[
+    // MAC
+    (MAC, SREQ, MACCommandId::ResetReq as u8, Exact(1)),
+    (MAC, SREQ, MACCommandId::Init as u8, Exact(0)),
+    (MAC, SREQ, MACCommandId::StartReq as u8, AtLeast(42)),
+    (MAC, SREQ, MACCommandId::SyncReq as u8, Exact(4)),
+    (MAC, SREQ, MACCommandId::DataReq as u8, AtLeast(35)),
+    (MAC, SREQ, MACCommandId::AssociateReq as u8, Exact(26)),
+    (MAC, SREQ, MACCommandId::DisassociateReq as u8, Exact(24)),
+    (MAC, SREQ, MACCommandId::GetReq as u8, Exact(1)),
+    (MAC, SREQ, MACCommandId::SetReq as u8, Exact(17)),
+    (MAC, SREQ, MACCommandId::ScanReq as u8, Exact(40)),
+    (MAC, SREQ, MACCommandId::PollReq as u8, Exact(22)),
+    (MAC, SREQ, MACCommandId::PurgeReq as u8, Exact(1)),
+    (MAC, SREQ, MACCommandId::SetRxGainReq as u8, Exact(1)),
+    (MAC, SREQ, MACCommandId::SecurityGetReq as u8, Exact(5)),
+    (MAC, SREQ, MACCommandId::SecuritySetReq as u8, AtLeast(5)),
+    (MAC, SREQ, MACCommandId::UpdatePANIdReq as u8, Exact(2)),
+    (MAC, SREQ, MACCommandId::AddDeviceReq as u8, Exact(29)),
+    (MAC, SREQ, MACCommandId::DeleteDeviceReq as u8, Exact(8)),
+    (MAC, SREQ, MACCommandId::DeleteAllDevicesReq as u8, Exact(0)),
+    (MAC, SREQ, MACCommandId::DeleteKeyReq as u8, Exact(1)),
+    (MAC, SREQ, MACCommandId::ReadKeyReq as u8, Exact(1)),
+    (MAC, SREQ, MACCommandId::WriteKeyReq as u8, Exact(33)),
+    (MAC, SREQ, MACCommandId::FHEnableReq as u8, Exact(0)),
+    (MAC, SREQ, MACCommandId::FHStartReq as u8, Exact(0)),
+    (MAC, SREQ, MACCommandId::FHGetReq as u8, Exact(2)),
+    (MAC, SREQ, MACCommandId::FHSetReq as u8, AtLeast(2)),
+    (MAC, SREQ, MACCommandId::WSAsyncReq as u8, Exact(30)),
+    (MAC, SREQ, MACCommandId::SrcMatchEnableReq as u8, Exact(2)),
+    (
+        MAC,
+        SREQ,
+        MACCommandId::SrcMatchAddEntryReq as u8,
+        Exact(11),
+    ),
+    (
+        MAC,
+        SREQ,
+        MACCommandId::SrcMatchDeleteEntryReq as u8,
+        Exact(11),
+    ),
+    (
+        MAC,
+        SREQ,
+        MACCommandId::SrcMatchAckAllPendingReq as u8,
+        Exact(1),
+    ),
+    (MAC, SREQ, MACCommandId::AssociateRsp as u8, Exact(22)),
+    (MAC, SREQ, MACCommandId::OrphanRsp as u8, Exact(22)),
+    (MAC, SRSP, MACCommandId::ResetReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::Init as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::StartReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::SyncReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::DataReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::AssociateReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::DisassociateReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::GetReq as u8, Exact(17)),
+    (MAC, SRSP, MACCommandId::SetReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::ScanReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::PollReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::PurgeReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::SetRxGainReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::SecurityGetReq as u8, AtLeast(5)),
+    (MAC, SRSP, MACCommandId::SecuritySetReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::UpdatePANIdReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::AddDeviceReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::DeleteDeviceReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::DeleteAllDevicesReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::DeleteKeyReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::ReadKeyReq as u8, Exact(5)),
+    (MAC, SRSP, MACCommandId::WriteKeyReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::FHEnableReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::FHStartReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::FHGetReq as u8, AtLeast(1)),
+    (MAC, SRSP, MACCommandId::FHSetReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::WSAsyncReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::SrcMatchEnableReq as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::SrcMatchAddEntryReq as u8, Exact(1)),
+    (
+        MAC,
+        SRSP,
+        MACCommandId::SrcMatchDeleteEntryReq as u8,
+        Exact(1),
+    ),
+    (
+        MAC,
+        SRSP,
+        MACCommandId::SrcMatchAckAllPendingReq as u8,
+        Exact(1),
+    ),
+    (MAC, SRSP, MACCommandId::AssociateRsp as u8, Exact(1)),
+    (MAC, SRSP, MACCommandId::OrphanRsp as u8, Exact(1)),
+    (MAC, AREQ, MACCommandId::SyncLossInd as u8, Exact(17)),
+    (MAC, AREQ, MACCommandId::AssociateInd as u8, Exact(20)),
+    (MAC, AREQ, MACCommandId::AssociateCnf as u8, Exact(14)),
+    (MAC, AREQ, MACCommandId::BeaconNotifyInd as u8, AtLeast(10)),
+    (MAC, AREQ, MACCommandId::DataCnf as u8, Exact(16)),
+    (MAC, AREQ, MACCommandId::DataInd as u8, AtLeast(51)),
+    (MAC, AREQ, MACCommandId::DisassociateInd as u8, Exact(20)),
+    (MAC, AREQ, MACCommandId::DisassociateCnf as u8, Exact(12)),
+    (MAC, AREQ, MACCommandId::OrphanInd as u8, Exact(19)),
+    (MAC, AREQ, MACCommandId::PollCnf as u8, Exact(2)),
+    (MAC, AREQ, MACCommandId::ScanCnf as u8, AtLeast(22)),
+    (MAC, AREQ, MACCommandId::CommStatusInd as u8, Exact(33)),
+    (MAC, AREQ, MACCommandId::StartCnf as u8, Exact(1)),
+    (MAC, AREQ, MACCommandId::PurgeCnf as u8, Exact(2)),
+    (MAC, AREQ, MACCommandId::PollInd as u8, Exact(12)),
+    (MAC, AREQ, MACCommandId::WSAsyncCnf as u8, Exact(1)),
+    (MAC, AREQ, MACCommandId::WSAsyncInd as u8, AtLeast(52)),
+    // RPC
+    (RPC, SRSP, RPCCommandId::MTCommandError as u8, Exact(3)),
+    // SYS
+    (SYS, SREQ, SYSCommandId::PingReq as u8, Exact(0)),
+    (SYS, SREQ, SYSCommandId::VersionReq as u8, Exact(0)),
+    (SYS, SREQ, SYSCommandId::NVCreateReq as u8, Exact(9)),
+    (SYS, SREQ, SYSCommandId::NVDeleteReq as u8, Exact(5)),
+    (SYS, SREQ, SYSCommandId::NVLengthReq as u8, Exact(5)),
+    (SYS, SREQ, SYSCommandId::NVReadReq as u8, Exact(8)),
+    (SYS, SREQ, SYSCommandId::NVWriteReq as u8, AtLeast(8)),
+    (SYS, SREQ, SYSCommandId::NVUpdateReq as u8, AtLeast(6)),
+    (SYS, SREQ, SYSCommandId::NVCompactReq as u8, Exact(2)),
+    (SYS, SRSP, SYSCommandId::PingReq as u8, Exact(2)),
+    (SYS, SRSP, SYSCommandId::VersionReq as u8, Exact(5)),
+    (SYS, SRSP, SYSCommandId::NVCreateReq as u8, Exact(1)),
+    (SYS, SRSP, SYSCommandId::NVDeleteReq as u8, Exact(1)),
+    (SYS, SRSP, SYSCommandId::NVLengthReq as u8, Exact(4)),
+    (SYS, SRSP, SYSCommandId::NVReadReq as u8, AtLeast(2)),
+    (SYS, SRSP, SYSCommandId::NVWriteReq as u8, Exact(1)),
+    (SYS, SRSP, SYSCommandId::NVUpdateReq as u8, Exact(1)),
+    (SYS, SRSP, SYSCommandId::NVCompactReq as u8, Exact(1)),
+    (SYS, AREQ, SYSCommandId::ResetReq as u8, Exact(1)),
+    (SYS, AREQ, SYSCommandId::ResetInd as u8, Exact(6)),
+    // UTIL
+    (UTIL, SREQ, UTILCommandId::CallbackSubCmd as u8, Exact(5)),
+    (UTIL, SREQ, UTILCommandId::Loopback as u8, AtLeast(5)),
+    (UTIL, SREQ, UTILCommandId::Random as u8, Exact(0)),
+    (UTIL, SREQ, UTILCommandId::GetExtAddr as u8, Exact(1)),
+    (UTIL, SRSP, UTILCommandId::CallbackSubCmd as u8, Exact(5)),
+    (UTIL, SRSP, UTILCommandId::Loopback as u8, AtLeast(5)),
+    (UTIL, SRSP, UTILCommandId::Random as u8, Exact(2)),
+    (UTIL, SRSP, UTILCommandId::GetExtAddr as u8, Exact(9)),
+    (UTIL, AREQ, UTILCommandId::Loopback as u8, AtLeast(5)),
+    // APP
+    (APP, SREQ, APPCommandId::AppMsg as u8, AtLeast(7)),
+    (APP, SRSP, APPCommandId::AppMsg as u8, Exact(1)),
+    (APP, AREQ, APPCommandId::AppMsg as u8, AtLeast(7)),
+];
+
+/// Expected payload size of a command, `None` for commands the crate does
+/// not know.
+pub fn expected_length(
+    subsystem: MTSubsystem,
+    cmd_type: CommandType,
+    id: u8,
+) -> Option<PayloadLength> {
+    PAYLOAD_LENGTHS
+        .iter()
+        .find(|entry| entry.0 == subsystem && entry.1 == cmd_type && entry.2 == id)
+        .map(|entry| entry.3)
+}
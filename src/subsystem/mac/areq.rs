@@ -1,35 +1,44 @@
 use crate::error::Error;
 use crate::frame::{CommandCode, MTFrame, MTHeader};
+use crate::ie::PayloadIe;
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
 use crate::types::*;
-use bytes::{Buf, BufMut};
-use std::io::Cursor;
-use std::io::Read;
+use crate::writer::BufMut;
+use alloc::vec;
+use alloc::vec::Vec;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DataCnf {
     pub status: Status,
     pub handle: u8,
     pub timestamp: u32,
     pub timestamp2: u16,
     pub retries: u8,
-    pub link_quality: u8,
+    pub link_quality: Lqi,
     pub correlation: u8,
-    pub rssi: u8,
+    pub rssi: Rssi,
     pub frame_counter: u32,
 }
 
 impl DataCnf {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
-        let handle = cursor.get_u8();
-        let timestamp = cursor.get_u32_le();
-        let timestamp2 = cursor.get_u16_le();
-        let retries = cursor.get_u8();
-        let link_quality = cursor.get_u8();
-        let correlation = cursor.get_u8();
-        let rssi = cursor.get_u8();
-        let frame_counter = cursor.get_u32_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        let handle = cursor.field("handle", Reader::read_u8)?;
+        let timestamp = cursor.field("timestamp", Reader::read_u32_le)?;
+        let timestamp2 = cursor.field("timestamp2", Reader::read_u16_le)?;
+        let retries = cursor.field("retries", Reader::read_u8)?;
+        let link_quality = cursor.field("link_quality", Lqi::try_decode)?;
+        let correlation = cursor.field("correlation", Reader::read_u8)?;
+        let rssi = cursor.field("rssi", Rssi::try_decode)?;
+        let frame_counter = cursor.field("frame_counter", Reader::read_u32_le)?;
 
         Ok(DataCnf {
             status,
@@ -56,9 +65,9 @@ impl DataCnf {
         buffer.put_u32_le(self.timestamp);
         buffer.put_u16_le(self.timestamp2);
         buffer.put_u8(self.retries);
-        buffer.put_u8(self.link_quality);
+        self.link_quality.encode_into(buffer);
         buffer.put_u8(self.correlation);
-        buffer.put_u8(self.rssi);
+        self.rssi.encode_into(buffer);
         buffer.put_u32_le(self.frame_counter);
     }
 
@@ -79,22 +88,21 @@ impl DataCnf {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DataInd {
     pub src_address: Address,
     pub dest_address: Address,
     pub timestamp: u32,
     pub timestamp2: u16,
-    pub src_pan_id: u16,
-    pub dest_pan_id: u16,
-    pub link_quality: u8,
+    pub src_pan_id: PanId,
+    pub dest_pan_id: PanId,
+    pub link_quality: Lqi,
     pub correlation: u8,
-    pub rssi: i8,
+    pub rssi: Rssi,
     pub dsn: u8,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub security: Security,
     pub frame_counter: u32,
     pub data_length: u16,
     pub ie_length: u16,
@@ -104,36 +112,112 @@ pub struct DataInd {
 
 impl DataInd {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let src_address = Address::try_decode(&mut cursor)?;
-        let dest_address = Address::try_decode(&mut cursor)?;
-        let timestamp = cursor.get_u32_le();
-        let timestamp2 = cursor.get_u16_le();
-        let src_pan_id = cursor.get_u16_le();
-        let dest_pan_id = cursor.get_u16_le();
-        let link_quality = cursor.get_u8();
-        let correlation = cursor.get_u8();
-        let rssi = cursor.get_i8();
-        let dsn = cursor.get_u8();
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
-        let frame_counter = cursor.get_u32_le();
-        let data_length = cursor.get_u16_le();
-        let ie_length = cursor.get_u16_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        DataIndRef::try_decode_with(buffer, options).map(DataIndRef::into_owned)
+    }
+
+    pub fn payload_ies(&self) -> Result<Vec<PayloadIe>, Error> {
+        PayloadIe::try_decode_list(&self.ie_payload)
+    }
+
+    pub fn to_ref(&self) -> DataIndRef<'_> {
+        DataIndRef {
+            src_address: self.src_address,
+            dest_address: self.dest_address,
+            timestamp: self.timestamp,
+            timestamp2: self.timestamp2,
+            src_pan_id: self.src_pan_id,
+            dest_pan_id: self.dest_pan_id,
+            link_quality: self.link_quality,
+            correlation: self.correlation,
+            rssi: self.rssi,
+            dsn: self.dsn,
+            security: self.security,
+            frame_counter: self.frame_counter,
+            data_length: self.data_length,
+            ie_length: self.ie_length,
+            data_payload: &self.data_payload,
+            ie_payload: &self.ie_payload,
+        }
+    }
 
-        let mut data_payload = vec![0x00; data_length as usize];
-        cursor
-            .read_exact(&mut data_payload)
-            .map_err(|_| Error::NotEnoughBytes)?;
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.encode_into(&mut buffer);
+        buffer
+    }
 
-        let mut ie_payload = vec![0x00; ie_length as usize];
-        cursor
-            .read_exact(&mut ie_payload)
-            .map_err(|_| Error::NotEnoughBytes)?;
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        self.to_ref().encode_into(buffer);
+    }
 
-        Ok(DataInd {
+    pub fn into_mt_frame(self) -> MTFrame {
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::AREQ,
+                subsystem: MTSubsystem::MAC,
+                id: MACCommandId::DataInd as u8,
+            },
+            self.encode(),
+        )
+    }
+}
+
+// Borrowed form of DataInd, the payloads point into the decoded buffer.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DataIndRef<'a> {
+    pub src_address: Address,
+    pub dest_address: Address,
+    pub timestamp: u32,
+    pub timestamp2: u16,
+    pub src_pan_id: PanId,
+    pub dest_pan_id: PanId,
+    pub link_quality: Lqi,
+    pub correlation: u8,
+    pub rssi: Rssi,
+    pub dsn: u8,
+    pub security: Security,
+    pub frame_counter: u32,
+    pub data_length: u16,
+    pub ie_length: u16,
+    pub data_payload: &'a [u8],
+    pub ie_payload: &'a [u8],
+}
+
+impl<'a> DataIndRef<'a> {
+    pub fn try_decode(buffer: &'a [u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &'a [u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let src_address = cursor.field("src_address", Address::try_decode)?;
+        let dest_address = cursor.field("dest_address", Address::try_decode)?;
+        let timestamp = cursor.field("timestamp", Reader::read_u32_le)?;
+        let timestamp2 = cursor.field("timestamp2", Reader::read_u16_le)?;
+        let src_pan_id = cursor.field("src_pan_id", PanId::try_decode)?;
+        let dest_pan_id = cursor.field("dest_pan_id", PanId::try_decode)?;
+        let link_quality = cursor.field("link_quality", Lqi::try_decode)?;
+        let correlation = cursor.field("correlation", Reader::read_u8)?;
+        let rssi = cursor.field("rssi", Rssi::try_decode)?;
+        let dsn = cursor.field("dsn", Reader::read_u8)?;
+        let security = cursor.field("security", Security::try_decode)?;
+        let frame_counter = cursor.field("frame_counter", Reader::read_u32_le)?;
+        let data_length = cursor.field("data_length", Reader::read_u16_le)?;
+        let ie_length = cursor.field("ie_length", Reader::read_u16_le)?;
+        let data_payload = cursor.field("data_payload", |cursor| {
+            cursor.read_bytes(data_length as usize)
+        })?;
+        let ie_payload =
+            cursor.field("ie_payload", |cursor| cursor.read_bytes(ie_length as usize))?;
+
+        Ok(DataIndRef {
             src_address,
             dest_address,
             timestamp,
@@ -144,10 +228,7 @@ impl DataInd {
             correlation,
             rssi,
             dsn,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
             frame_counter,
             data_length,
             ie_length,
@@ -156,10 +237,25 @@ impl DataInd {
         })
     }
 
-    pub fn encode(&self) -> Vec<u8> {
-        let mut buffer = Vec::new();
-        self.encode_into(&mut buffer);
-        buffer
+    pub fn into_owned(self) -> DataInd {
+        DataInd {
+            src_address: self.src_address,
+            dest_address: self.dest_address,
+            timestamp: self.timestamp,
+            timestamp2: self.timestamp2,
+            src_pan_id: self.src_pan_id,
+            dest_pan_id: self.dest_pan_id,
+            link_quality: self.link_quality,
+            correlation: self.correlation,
+            rssi: self.rssi,
+            dsn: self.dsn,
+            security: self.security,
+            frame_counter: self.frame_counter,
+            data_length: self.data_length,
+            ie_length: self.ie_length,
+            data_payload: self.data_payload.to_vec(),
+            ie_payload: self.ie_payload.to_vec(),
+        }
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -167,41 +263,24 @@ impl DataInd {
         self.dest_address.encode_into(buffer);
         buffer.put_u32_le(self.timestamp);
         buffer.put_u16_le(self.timestamp2);
-        buffer.put_u16_le(self.src_pan_id);
-        buffer.put_u16_le(self.dest_pan_id);
-        buffer.put_u8(self.link_quality);
+        self.src_pan_id.encode_into(buffer);
+        self.dest_pan_id.encode_into(buffer);
+        self.link_quality.encode_into(buffer);
         buffer.put_u8(self.correlation);
-        buffer.put_i8(self.rssi);
+        self.rssi.encode_into(buffer);
         buffer.put_u8(self.dsn);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.security.encode_into(buffer);
         buffer.put_u32_le(self.frame_counter);
         buffer.put_u16_le(self.data_length);
         buffer.put_u16_le(self.ie_length);
-        buffer.extend(self.data_payload.iter());
-        buffer.extend(self.ie_payload.iter());
-    }
-
-    pub fn into_mt_frame(self) -> MTFrame {
-        MTFrame {
-            header: MTHeader {
-                length: (0x33 + self.data_payload.len() + self.ie_payload.len()) as u8,
-                command: CommandCode {
-                    is_extended: false,
-                    cmd_type: CommandType::AREQ,
-                    subsystem: MTSubsystem::MAC,
-                    id: MACCommandId::DataInd as u8,
-                },
-            },
-            extended_header: None,
-            payload: self.encode(),
-        }
+        buffer.extend_from_slice(self.data_payload);
+        buffer.extend_from_slice(self.ie_payload);
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PurgeCnf {
     pub status: Status,
     pub handle: u8,
@@ -209,9 +288,13 @@ pub struct PurgeCnf {
 
 impl PurgeCnf {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
-        let handle = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        let handle = cursor.field("handle", Reader::read_u8)?;
         Ok(PurgeCnf { status, handle })
     }
 
@@ -243,22 +326,22 @@ impl PurgeCnf {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "wisun-ie")]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WSAsyncInd {
     pub src_address: Address,
     pub dest_address: Address,
     pub timestamp: u32,
     pub timestamp2: u16,
-    pub src_pan_id: u16,
-    pub dest_pan_id: u16,
-    pub link_quality: u8,
+    pub src_pan_id: PanId,
+    pub dest_pan_id: PanId,
+    pub link_quality: Lqi,
     pub correlation: u8,
-    pub rssi: u8,
+    pub rssi: Rssi,
     pub dsn: u8,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub security: Security,
     pub frame_counter: u32,
     pub frame_type: WiSUNAsyncFrameType,
     pub data_length: u16,
@@ -267,37 +350,37 @@ pub struct WSAsyncInd {
     pub ie_payload: Vec<u8>,
 }
 
+#[cfg(feature = "wisun-ie")]
 impl WSAsyncInd {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let src_address = Address::try_decode(&mut cursor)?;
-        let dest_address = Address::try_decode(&mut cursor)?;
-        let timestamp = cursor.get_u32_le();
-        let timestamp2 = cursor.get_u16_le();
-        let src_pan_id = cursor.get_u16_le();
-        let dest_pan_id = cursor.get_u16_le();
-        let link_quality = cursor.get_u8();
-        let correlation = cursor.get_u8();
-        let rssi = cursor.get_u8();
-        let dsn = cursor.get_u8();
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
-        let frame_counter = cursor.get_u32_le();
-        let frame_type = WiSUNAsyncFrameType::try_decode(&mut cursor)?;
-        let data_length = cursor.get_u16_le();
-        let ie_length = cursor.get_u16_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let src_address = cursor.field("src_address", Address::try_decode)?;
+        let dest_address = cursor.field("dest_address", Address::try_decode)?;
+        let timestamp = cursor.field("timestamp", Reader::read_u32_le)?;
+        let timestamp2 = cursor.field("timestamp2", Reader::read_u16_le)?;
+        let src_pan_id = cursor.field("src_pan_id", PanId::try_decode)?;
+        let dest_pan_id = cursor.field("dest_pan_id", PanId::try_decode)?;
+        let link_quality = cursor.field("link_quality", Lqi::try_decode)?;
+        let correlation = cursor.field("correlation", Reader::read_u8)?;
+        let rssi = cursor.field("rssi", Rssi::try_decode)?;
+        let dsn = cursor.field("dsn", Reader::read_u8)?;
+        let security = cursor.field("security", Security::try_decode)?;
+        let frame_counter = cursor.field("frame_counter", Reader::read_u32_le)?;
+        let frame_type = cursor.field("frame_type", WiSUNAsyncFrameType::try_decode)?;
+        let data_length = cursor.field("data_length", Reader::read_u16_le)?;
+        let ie_length = cursor.field("ie_length", Reader::read_u16_le)?;
 
         let mut data_payload = vec![0x00; data_length as usize];
-        cursor
-            .read_exact(&mut data_payload)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("data_payload", |cursor| {
+            cursor.read_exact(&mut data_payload)
+        })?;
 
-        let mut ie_payload = vec![0x00; data_length as usize];
-        cursor
-            .read_exact(&mut ie_payload)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        let mut ie_payload = vec![0x00; ie_length as usize];
+        cursor.field("ie_payload", |cursor| cursor.read_exact(&mut ie_payload))?;
 
         Ok(WSAsyncInd {
             src_address,
@@ -310,10 +393,7 @@ impl WSAsyncInd {
             correlation,
             rssi,
             dsn,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
             frame_counter,
             frame_type,
             data_length,
@@ -323,6 +403,10 @@ impl WSAsyncInd {
         })
     }
 
+    pub fn payload_ies(&self) -> Result<Vec<PayloadIe>, Error> {
+        PayloadIe::try_decode_list(&self.ie_payload)
+    }
+
     pub fn encode(&self) -> Vec<u8> {
         let mut buffer = Vec::new();
         self.encode_into(&mut buffer);
@@ -334,16 +418,13 @@ impl WSAsyncInd {
         self.dest_address.encode_into(buffer);
         buffer.put_u32_le(self.timestamp);
         buffer.put_u16_le(self.timestamp2);
-        buffer.put_u16_le(self.src_pan_id);
-        buffer.put_u16_le(self.dest_pan_id);
-        buffer.put_u8(self.link_quality);
+        self.src_pan_id.encode_into(buffer);
+        self.dest_pan_id.encode_into(buffer);
+        self.link_quality.encode_into(buffer);
         buffer.put_u8(self.correlation);
-        buffer.put_u8(self.rssi);
+        self.rssi.encode_into(buffer);
         buffer.put_u8(self.dsn);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.security.encode_into(buffer);
         buffer.put_u32_le(self.frame_counter);
         self.frame_type.encode_into(buffer);
         buffer.put_u16_le(self.data_length);
@@ -353,47 +434,43 @@ impl WSAsyncInd {
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
-        MTFrame {
-            header: MTHeader {
-                length: (0x34 + self.data_payload.len() + self.ie_payload.len()) as u8,
-                command: CommandCode {
-                    is_extended: false,
-                    cmd_type: CommandType::AREQ,
-                    subsystem: MTSubsystem::MAC,
-                    id: MACCommandId::WSAsyncInd as u8,
-                },
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::AREQ,
+                subsystem: MTSubsystem::MAC,
+                id: MACCommandId::WSAsyncInd as u8,
             },
-            extended_header: None,
-            payload: self.encode(),
-        }
+            self.encode(),
+        )
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SyncLossInd {
     pub status: Status,
-    pub pan_id: u16,
+    pub pan_id: PanId,
     pub logical_channel: u8,
     pub channel_page: u8,
     pub phy_id: PhyId,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub security: Security,
 }
 
 impl SyncLossInd {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
-        let pan_id = cursor.get_u16_le();
-        let logical_channel = cursor.get_u8();
-        let channel_page = cursor.get_u8();
-        let phy_id = PhyId::try_decode(&mut cursor)?;
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        let pan_id = cursor.field("pan_id", PanId::try_decode)?;
+        let logical_channel = cursor.field("logical_channel", Reader::read_u8)?;
+        let channel_page = cursor.field("channel_page", Reader::read_u8)?;
+        let phy_id = cursor.field("phy_id", PhyId::try_decode)?;
+        let security = cursor.field("security", Security::try_decode)?;
 
         Ok(SyncLossInd {
             status,
@@ -401,10 +478,7 @@ impl SyncLossInd {
             logical_channel,
             channel_page,
             phy_id,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
         })
     }
 
@@ -416,14 +490,11 @@ impl SyncLossInd {
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         self.status.encode_into(buffer);
-        buffer.put_u16_le(self.pan_id);
+        self.pan_id.encode_into(buffer);
         buffer.put_u8(self.logical_channel);
         buffer.put_u8(self.channel_page);
         self.phy_id.encode_into(buffer);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.security.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
@@ -443,33 +514,30 @@ impl SyncLossInd {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AssociateInd {
     pub extended_address: ExtendedAddress,
-    pub capabilities: u8,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub capabilities: CapabilityInfo,
+    pub security: Security,
 }
 
 impl AssociateInd {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let extended_address = ExtendedAddress::try_decode(&mut cursor)?;
-        let capabilities = cursor.get_u8();
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let extended_address = cursor.field("extended_address", ExtendedAddress::try_decode)?;
+        let capabilities = cursor.field("capabilities", CapabilityInfo::try_decode)?;
+        let security = cursor.field("security", Security::try_decode)?;
 
         Ok(AssociateInd {
             extended_address,
             capabilities,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
         })
     }
 
@@ -481,11 +549,8 @@ impl AssociateInd {
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         self.extended_address.encode_into(buffer);
-        buffer.put_u8(self.capabilities);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.capabilities.encode_into(buffer);
+        self.security.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
@@ -505,33 +570,30 @@ impl AssociateInd {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AssociateCnf {
     pub status: Status,
     pub short_address: ShortAddress,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub security: Security,
 }
 
 impl AssociateCnf {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
-        let short_address = ShortAddress::try_decode(&mut cursor)?;
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        let short_address = cursor.field("short_address", ShortAddress::try_decode)?;
+        let security = cursor.field("security", Security::try_decode)?;
 
         Ok(AssociateCnf {
             status,
             short_address,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
         })
     }
 
@@ -544,10 +606,7 @@ impl AssociateCnf {
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         self.status.encode_into(buffer);
         self.short_address.encode_into(buffer);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.security.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
@@ -567,7 +626,9 @@ impl AssociateCnf {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BeaconNotifyInd {
     StandardFrame(StandardBeaconFrame),
     EnhancedFrame(EnhancedBeaconFrame),
@@ -575,15 +636,25 @@ pub enum BeaconNotifyInd {
 
 impl BeaconNotifyInd {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
         use BeaconNotifyInd::{EnhancedFrame, StandardFrame};
 
-        let beacon_type = cursor.get_u8();
+        let beacon_type = cursor.field("beacon_type", Reader::read_u8)?;
 
         let beacon_frame = match beacon_type {
             0 => StandardFrame(StandardBeaconFrame::try_decode(&mut cursor)?),
             1 => EnhancedFrame(EnhancedBeaconFrame::try_decode(&mut cursor)?),
-            _ => return Err(Error::InvalidBeaconType(beacon_type)),
+            _ => {
+                return Err(Error::field(
+                    "beacon_type",
+                    0,
+                    Error::InvalidBeaconType(beacon_type),
+                ))
+            }
         };
 
         Ok(beacon_frame)
@@ -609,113 +680,56 @@ impl BeaconNotifyInd {
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
-        use BeaconNotifyInd::*;
-        let length = match self {
-            StandardFrame(ref frame) => {
-                0x26 + (2 * frame.short_addr_list.len() + 8 * frame.ext_addr_list.len()) as u8
-                    + frame.sdu_length
-            }
-            EnhancedFrame(_) => 0x0a,
-        };
-        MTFrame {
-            header: MTHeader {
-                length,
-                command: CommandCode {
-                    is_extended: false,
-                    cmd_type: CommandType::AREQ,
-                    subsystem: MTSubsystem::MAC,
-                    id: MACCommandId::BeaconNotifyInd as u8,
-                },
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::AREQ,
+                subsystem: MTSubsystem::MAC,
+                id: MACCommandId::BeaconNotifyInd as u8,
             },
-            extended_header: None,
-            payload: self.encode(),
-        }
+            self.encode(),
+        )
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StandardBeaconFrame {
     pub bsn: u8,
-    pub timestamp: u32,
-    pub coord_address_mode: AddressMode,
-    pub coord_extended_address: ExtendedAddress,
-    pub pan_id: u16,
-    pub superframe_spec: u16,
-    pub logical_channel: u8,
-    pub channel_page: u8,
-    pub gts_permit: bool,
-    pub link_quality: u8,
-    pub security_failure: bool,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
-    pub short_addrs: u8,
-    pub ext_addrs: u8,
-    pub sdu_length: u8,
+    pub pan_descriptor: PanDescriptor,
     pub short_addr_list: Vec<ShortAddress>,
     pub ext_addr_list: Vec<ExtendedAddress>,
-    pub nsdu: Vec<u8>,
+    pub sdu: Vec<u8>,
 }
 
 impl StandardBeaconFrame {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let bsn = cursor.get_u8();
-        let timestamp = cursor.get_u32_le();
-        let coord_address_mode = AddressMode::try_decode(cursor)?;
-        let coord_extended_address = ExtendedAddress::try_decode(cursor)?;
-        let pan_id = cursor.get_u16_le();
-        let superframe_spec = cursor.get_u16_le();
-        let logical_channel = cursor.get_u8();
-        let channel_page = cursor.get_u8();
-        let gts_permit = cursor.get_u8() != 0;
-        let link_quality = cursor.get_u8();
-        let security_failure = cursor.get_u8() != 0;
-        let key_source = KeySource::try_decode(cursor)?;
-        let security_level = SecurityLevel::try_decode(cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(cursor)?;
-        let key_index = cursor.get_u8();
-        let short_addrs = cursor.get_u8();
-        let ext_addrs = cursor.get_u8();
-        let sdu_length = cursor.get_u8();
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let bsn = cursor.field("bsn", Reader::read_u8)?;
+        let pan_descriptor = cursor.field("pan_descriptor", PanDescriptor::try_decode)?;
+        let short_addrs = cursor.field("short_addrs", Reader::read_u8)?;
+        let ext_addrs = cursor.field("ext_addrs", Reader::read_u8)?;
+        let sdu_length = cursor.field("sdu_length", Reader::read_u8)?;
 
         let mut short_addr_list = Vec::new();
         for _ in 0..short_addrs {
-            short_addr_list.push(ShortAddress::try_decode(cursor)?);
+            short_addr_list.push(cursor.field("short_addr_list", ShortAddress::try_decode)?);
         }
 
         let mut ext_addr_list = Vec::new();
         for _ in 0..ext_addrs {
-            ext_addr_list.push(ExtendedAddress::try_decode(cursor)?);
+            ext_addr_list.push(cursor.field("ext_addr_list", ExtendedAddress::try_decode)?);
         }
 
-        let mut nsdu = vec![0x00; sdu_length as usize];
-        cursor
-            .read_exact(&mut nsdu)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        let mut sdu = vec![0x00; sdu_length as usize];
+        cursor.field("sdu", |cursor| cursor.read_exact(&mut sdu))?;
 
         Ok(StandardBeaconFrame {
             bsn,
-            timestamp,
-            coord_address_mode,
-            coord_extended_address,
-            pan_id,
-            superframe_spec,
-            logical_channel,
-            channel_page,
-            gts_permit,
-            link_quality,
-            security_failure,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
-            short_addrs,
-            ext_addrs,
-            sdu_length,
+            pan_descriptor,
             short_addr_list,
             ext_addr_list,
-            nsdu,
+            sdu,
         })
     }
 
@@ -725,70 +739,45 @@ impl StandardBeaconFrame {
         buffer
     }
 
+    /// The address lists and the SDU are counted in one byte each, so at most
+    /// 255 entries of each are written and the counts always match what
+    /// follows them.
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        let short_addr_list = &self.short_addr_list[..self.short_addr_list.len().min(0xff)];
+        let ext_addr_list = &self.ext_addr_list[..self.ext_addr_list.len().min(0xff)];
+        let sdu = &self.sdu[..self.sdu.len().min(0xff)];
+
         buffer.put_u8(self.bsn);
-        buffer.put_u32_le(self.timestamp);
-        self.coord_address_mode.encode_into(buffer);
-        self.coord_extended_address.encode_into(buffer);
-        buffer.put_u16_le(self.pan_id);
-        buffer.put_u16_le(self.superframe_spec);
-        buffer.put_u8(self.logical_channel);
-        buffer.put_u8(self.channel_page);
-        buffer.put_u8(if self.gts_permit { 1 } else { 0 });
-        buffer.put_u8(self.link_quality);
-        buffer.put_u8(if self.security_failure { 1 } else { 0 });
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
-        buffer.put_u8(self.short_addrs);
-        buffer.put_u8(self.ext_addrs);
-        buffer.put_u8(self.sdu_length);
-
-        for short_address in self.short_addr_list.iter() {
+        self.pan_descriptor.encode_into(buffer);
+        buffer.put_u8(short_addr_list.len() as u8);
+        buffer.put_u8(ext_addr_list.len() as u8);
+        buffer.put_u8(sdu.len() as u8);
+
+        for short_address in short_addr_list.iter() {
             short_address.encode_into(buffer);
         }
 
-        for extended_address in self.ext_addr_list.iter() {
+        for extended_address in ext_addr_list.iter() {
             extended_address.encode_into(buffer);
         }
 
-        buffer.extend(self.nsdu.iter());
+        buffer.extend(sdu.iter());
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EnhancedBeaconFrame {
     pub bsn: u8,
-    pub beacon_order: u8,
-    pub super_frame_order: u8,
-    pub final_cap_slot: u8,
-    pub enh_beacon_order: u8,
-    pub ofs_time_slot: u8,
-    pub cap_back_off: u8,
-    pub non_beacon_order: u16,
+    pub coexistence: CoexistenceSpec,
 }
 
 impl EnhancedBeaconFrame {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let bsn = cursor.get_u8();
-        let beacon_order = cursor.get_u8();
-        let super_frame_order = cursor.get_u8();
-        let final_cap_slot = cursor.get_u8();
-        let enh_beacon_order = cursor.get_u8();
-        let ofs_time_slot = cursor.get_u8();
-        let cap_back_off = cursor.get_u8();
-        let non_beacon_order = cursor.get_u16_le();
-        Ok(EnhancedBeaconFrame {
-            bsn,
-            beacon_order,
-            super_frame_order,
-            final_cap_slot,
-            enh_beacon_order,
-            ofs_time_slot,
-            cap_back_off,
-            non_beacon_order,
-        })
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let bsn = cursor.field("bsn", Reader::read_u8)?;
+        let coexistence = cursor.field("coexistence", CoexistenceSpec::try_decode)?;
+        Ok(EnhancedBeaconFrame { bsn, coexistence })
     }
 
     pub fn encode(&self) -> Vec<u8> {
@@ -799,43 +788,35 @@ impl EnhancedBeaconFrame {
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         buffer.put_u8(self.bsn);
-        buffer.put_u8(self.beacon_order);
-        buffer.put_u8(self.super_frame_order);
-        buffer.put_u8(self.final_cap_slot);
-        buffer.put_u8(self.enh_beacon_order);
-        buffer.put_u8(self.ofs_time_slot);
-        buffer.put_u8(self.cap_back_off);
-        buffer.put_u16_le(self.non_beacon_order);
+        self.coexistence.encode_into(buffer);
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DisassociateInd {
     pub extended_address: ExtendedAddress,
     pub disassociate_reason: DisassociateReason,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub security: Security,
 }
 
 impl DisassociateInd {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let extended_address = ExtendedAddress::try_decode(&mut cursor)?;
-        let disassociate_reason = DisassociateReason::try_decode(&mut cursor)?;
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let extended_address = cursor.field("extended_address", ExtendedAddress::try_decode)?;
+        let disassociate_reason =
+            cursor.field("disassociate_reason", DisassociateReason::try_decode)?;
+        let security = cursor.field("security", Security::try_decode)?;
 
         Ok(DisassociateInd {
             extended_address,
             disassociate_reason,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
         })
     }
 
@@ -848,10 +829,7 @@ impl DisassociateInd {
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         self.extended_address.encode_into(buffer);
         self.disassociate_reason.encode_into(buffer);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.security.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
@@ -871,19 +849,25 @@ impl DisassociateInd {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DisassociateCnf {
     pub status: Status,
     pub device_addr: Address,
-    pub device_pan_id: u16,
+    pub device_pan_id: PanId,
 }
 
 impl DisassociateCnf {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
-        let device_addr = Address::try_decode(&mut cursor)?;
-        let device_pan_id = cursor.get_u16_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        let device_addr = cursor.field("device_addr", Address::try_decode)?;
+        let device_pan_id = cursor.field("device_pan_id", PanId::try_decode)?;
 
         Ok(DisassociateCnf {
             status,
@@ -901,7 +885,7 @@ impl DisassociateCnf {
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         self.status.encode_into(buffer);
         self.device_addr.encode_into(buffer);
-        buffer.put_u16_le(self.device_pan_id);
+        self.device_pan_id.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
@@ -921,29 +905,26 @@ impl DisassociateCnf {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrphanInd {
     pub extended_address: ExtendedAddress,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub security: Security,
 }
 
 impl OrphanInd {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let extended_address = ExtendedAddress::try_decode(&mut cursor)?;
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let extended_address = cursor.field("extended_address", ExtendedAddress::try_decode)?;
+        let security = cursor.field("security", Security::try_decode)?;
         Ok(OrphanInd {
             extended_address,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
         })
     }
 
@@ -955,10 +936,7 @@ impl OrphanInd {
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         self.extended_address.encode_into(buffer);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.security.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
@@ -978,7 +956,9 @@ impl OrphanInd {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PollCnf {
     pub status: Status,
     pub frame_pending: bool,
@@ -986,9 +966,13 @@ pub struct PollCnf {
 
 impl PollCnf {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
-        let frame_pending = cursor.get_u8() != 0;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        let frame_pending = cursor.field("frame_pending", Reader::read_u8)? != 0;
         Ok(PollCnf {
             status,
             frame_pending,
@@ -1023,19 +1007,25 @@ impl PollCnf {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PollInd {
     pub dev_addr: Address,
-    pub pan_id: u16,
+    pub pan_id: PanId,
     pub no_response: bool,
 }
 
 impl PollInd {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let dev_addr = Address::try_decode(&mut cursor)?;
-        let pan_id = cursor.get_u16_le();
-        let no_response = cursor.get_u8() != 0;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let dev_addr = cursor.field("dev_addr", Address::try_decode)?;
+        let pan_id = cursor.field("pan_id", PanId::try_decode)?;
+        let no_response = cursor.field("no_response", Reader::read_u8)? != 0;
         Ok(PollInd {
             dev_addr,
             pan_id,
@@ -1051,7 +1041,7 @@ impl PollInd {
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         self.dev_addr.encode_into(buffer);
-        buffer.put_u16_le(self.pan_id);
+        self.pan_id.encode_into(buffer);
         buffer.put_u8(if self.no_response { 1 } else { 0 });
     }
 
@@ -1072,31 +1062,35 @@ impl PollInd {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScanCnf {
     pub status: Status,
     pub scan_type: ScanType,
     pub channel_page: u8,
     pub phy_id: PhyId,
-    pub unscanned_channels: ChannelsBitMap,
+    pub unscanned_channels: ChannelMask,
     pub result_list_count: u8,
     pub result_list: Vec<u8>,
 }
 
 impl ScanCnf {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
-        let scan_type = ScanType::try_decode(&mut cursor)?;
-        let channel_page = cursor.get_u8();
-        let phy_id = PhyId::try_decode(&mut cursor)?;
-        let unscanned_channels = ChannelsBitMap::try_decode(&mut cursor)?;
-        let result_list_count = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        let scan_type = cursor.field("scan_type", ScanType::try_decode)?;
+        let channel_page = cursor.field("channel_page", Reader::read_u8)?;
+        let phy_id = cursor.field("phy_id", PhyId::try_decode)?;
+        let unscanned_channels = cursor.field("unscanned_channels", ChannelMask::try_decode)?;
+        let result_list_count = cursor.field("result_list_count", Reader::read_u8)?;
 
         let mut result_list = Vec::new();
-        cursor
-            .read_to_end(&mut result_list)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("result_list", |cursor| cursor.read_to_end(&mut result_list))?;
 
         Ok(ScanCnf {
             status,
@@ -1109,6 +1103,34 @@ impl ScanCnf {
         })
     }
 
+    /// Energy measured on each channel of an energy detect scan, `None` for
+    /// other scans. The result list follows the order of the channels that
+    /// were actually scanned, i.e. `requested` minus the unscanned ones.
+    pub fn energy_levels(&self, requested: &ChannelMask) -> Option<Vec<(Channel, EnergyLevel)>> {
+        if self.scan_type != ScanType::EnergyDetect {
+            return None;
+        }
+        let scanned = requested
+            .channels()
+            .filter(|channel| !self.unscanned_channels.is_enabled(*channel))
+            .map(Channel);
+        let levels = self.result_list.iter().map(|level| EnergyLevel(*level));
+        Some(scanned.zip(levels).collect())
+    }
+
+    /// Up to `count` scanned channels, quietest first.
+    pub fn best_channels(&self, requested: &ChannelMask, count: usize) -> Option<Vec<Channel>> {
+        let mut levels = self.energy_levels(requested)?;
+        levels.sort_by_key(|(channel, level)| (*level, *channel));
+        Some(
+            levels
+                .into_iter()
+                .take(count)
+                .map(|(channel, _)| channel)
+                .collect(),
+        )
+    }
+
     pub fn encode(&self) -> Vec<u8> {
         let mut buffer = Vec::new();
         self.encode_into(&mut buffer);
@@ -1126,47 +1148,43 @@ impl ScanCnf {
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
-        MTFrame {
-            header: MTHeader {
-                length: 0x0c + self.result_list.len() as u8,
-                command: CommandCode {
-                    is_extended: false,
-                    cmd_type: CommandType::AREQ,
-                    subsystem: MTSubsystem::MAC,
-                    id: MACCommandId::ScanCnf as u8,
-                },
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::AREQ,
+                subsystem: MTSubsystem::MAC,
+                id: MACCommandId::ScanCnf as u8,
             },
-            extended_header: None,
-            payload: self.encode(),
-        }
+            self.encode(),
+        )
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CommStatusInd {
     pub status: Status,
     pub src_addr: Address,
     pub dst_addr: Address,
-    pub device_pan_id: u16,
+    pub device_pan_id: PanId,
     pub reason: CommEventReason,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub security: Security,
 }
 
 impl CommStatusInd {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
-        let src_addr = Address::try_decode(&mut cursor)?;
-        let dst_addr = Address::try_decode(&mut cursor)?;
-        let device_pan_id = cursor.get_u16_le();
-        let reason = CommEventReason::try_decode(&mut cursor)?;
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        let src_addr = cursor.field("src_addr", Address::try_decode)?;
+        let dst_addr = cursor.field("dst_addr", Address::try_decode)?;
+        let device_pan_id = cursor.field("device_pan_id", PanId::try_decode)?;
+        let reason = cursor.field("reason", CommEventReason::try_decode)?;
+        let security = cursor.field("security", Security::try_decode)?;
 
         Ok(CommStatusInd {
             status,
@@ -1174,10 +1192,7 @@ impl CommStatusInd {
             dst_addr,
             device_pan_id,
             reason,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
         })
     }
 
@@ -1191,12 +1206,9 @@ impl CommStatusInd {
         self.status.encode_into(buffer);
         self.src_addr.encode_into(buffer);
         self.dst_addr.encode_into(buffer);
-        buffer.put_u16_le(self.device_pan_id);
+        self.device_pan_id.encode_into(buffer);
         self.reason.encode_into(buffer);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.security.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
@@ -1216,15 +1228,21 @@ impl CommStatusInd {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StartCnf {
     pub status: Status,
 }
 
 impl StartCnf {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(StartCnf { status })
     }
 
@@ -1255,15 +1273,23 @@ impl StartCnf {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "wisun-ie")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WSAsyncCnf {
     pub status: Status,
 }
 
+#[cfg(feature = "wisun-ie")]
 impl WSAsyncCnf {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(WSAsyncCnf { status })
     }
 
@@ -1,15 +1,25 @@
 use crate::error::Error;
 use crate::frame::{CommandCode, MTFrame, MTHeader};
+use crate::ie::{HeaderIe, PayloadIe};
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
 use crate::types::*;
-use bytes::{Buf, BufMut};
-use std::io::Cursor;
-use std::io::Read;
-
-#[derive(Debug)]
+use crate::writer::BufMut;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Init {}
 
 impl Init {
-    pub fn try_decode(_: &[u8]) -> Result<Self, Error> {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(_: &[u8], _: DecodeOptions) -> Result<Self, Error> {
         Ok(Init {})
     }
 
@@ -38,19 +48,18 @@ impl Init {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DataReq {
     pub dest_address: Address,
-    pub dest_pan_id: u16,
+    pub dest_pan_id: PanId,
     pub src_address_mode: AddressMode,
     pub handle: u8,
     pub tx_option: TxOption,
     pub channel: u8,
     pub power: u8,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub security: Security,
     pub include_fh_ies: u32,
     pub data_length: u16,
     pub ie_length: u16,
@@ -60,31 +69,30 @@ pub struct DataReq {
 
 impl DataReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let dest_address = Address::try_decode(&mut cursor)?;
-        let dest_pan_id = cursor.get_u16_le();
-        let src_address_mode = AddressMode::try_decode(&mut cursor)?;
-        let handle = cursor.get_u8();
-        let tx_option = TxOption::try_decode(&mut cursor)?;
-        let channel = cursor.get_u8();
-        let power = cursor.get_u8();
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
-        let include_fh_ies = cursor.get_u32_le();
-        let data_length = cursor.get_u16_le();
-        let ie_length = cursor.get_u16_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let dest_address = cursor.field("dest_address", Address::try_decode)?;
+        let dest_pan_id = cursor.field("dest_pan_id", PanId::try_decode)?;
+        let src_address_mode = cursor.field("src_address_mode", AddressMode::try_decode)?;
+        let handle = cursor.field("handle", Reader::read_u8)?;
+        let tx_option = cursor.field("tx_option", TxOption::try_decode)?;
+        let channel = cursor.field("channel", Reader::read_u8)?;
+        let power = cursor.field("power", Reader::read_u8)?;
+        let security = cursor.field("security", Security::try_decode)?;
+        let include_fh_ies = cursor.field("include_fh_ies", Reader::read_u32_le)?;
+        let data_length = cursor.field("data_length", Reader::read_u16_le)?;
+        let ie_length = cursor.field("ie_length", Reader::read_u16_le)?;
 
         let mut data_payload = vec![0x00; data_length as usize];
-        cursor
-            .read_exact(&mut data_payload)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("data_payload", |cursor| {
+            cursor.read_exact(&mut data_payload)
+        })?;
 
-        let mut ie_payload = vec![0x00; data_length as usize];
-        cursor
-            .read_exact(&mut ie_payload)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        let mut ie_payload = vec![0x00; ie_length as usize];
+        cursor.field("ie_payload", |cursor| cursor.read_exact(&mut ie_payload))?;
 
         Ok(DataReq {
             dest_address,
@@ -94,10 +102,7 @@ impl DataReq {
             tx_option,
             channel,
             power,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
             include_fh_ies,
             data_length,
             ie_length,
@@ -106,6 +111,54 @@ impl DataReq {
         })
     }
 
+    /// Replaces the IE payload, keeping `ie_length` in sync.
+    pub fn set_payload_ies(&mut self, ies: &[PayloadIe]) -> Result<(), Error> {
+        self.set_ies(&[], ies)
+    }
+
+    /// Replaces the IE payload with header IEs followed by payload IEs. A
+    /// header termination IE is inserted between them when both are present.
+    /// Fails with `PayloadTooLarge`, leaving the request unchanged, when an IE
+    /// does not fit its descriptor or the whole does not fit `ie_length`.
+    pub fn set_ies(
+        &mut self,
+        header_ies: &[HeaderIe],
+        payload_ies: &[PayloadIe],
+    ) -> Result<(), Error> {
+        let mut ie_payload = Vec::new();
+        for ie in header_ies {
+            ie.encode_into(&mut ie_payload)?;
+        }
+        if !header_ies.is_empty() && !payload_ies.is_empty() {
+            let termination = HeaderIe {
+                element_id: HeaderIe::HT1,
+                content: Vec::new(),
+            };
+            termination.encode_into(&mut ie_payload)?;
+        }
+        for ie in payload_ies {
+            ie.encode_into(&mut ie_payload)?;
+        }
+        self.ie_length = u16::try_from(ie_payload.len())
+            .map_err(|_| Error::PayloadTooLarge(ie_payload.len()))?;
+        self.ie_payload = ie_payload;
+        Ok(())
+    }
+
+    /// Appends IEs that are already encoded, e.g. an EAPOL relay's WH-IEs.
+    pub fn push_raw_ies(&mut self, ies: &[u8]) {
+        self.ie_payload.extend_from_slice(ies);
+        self.ie_length = self.ie_payload.len() as u16;
+    }
+
+    pub fn fh_ies(&self) -> FHIncludeIe {
+        FHIncludeIe::from_bits_truncate(self.include_fh_ies)
+    }
+
+    pub fn set_fh_ies(&mut self, ies: FHIncludeIe) {
+        self.include_fh_ies = ies.bits();
+    }
+
     pub fn encode(&self) -> Vec<u8> {
         let mut buffer = Vec::new();
         self.encode_into(&mut buffer);
@@ -114,16 +167,13 @@ impl DataReq {
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         self.dest_address.encode_into(buffer);
-        buffer.put_u16_le(self.dest_pan_id);
+        self.dest_pan_id.encode_into(buffer);
         self.src_address_mode.encode_into(buffer);
         buffer.put_u8(self.handle);
         self.tx_option.encode_into(buffer);
         buffer.put_u8(self.channel);
         buffer.put_u8(self.power);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.security.encode_into(buffer);
         buffer.put_u32_le(self.include_fh_ies);
         buffer.put_u16_le(self.data_length);
         buffer.put_u16_le(self.ie_length);
@@ -132,31 +182,33 @@ impl DataReq {
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
-        MTFrame {
-            header: MTHeader {
-                length: (0x23 + self.data_payload.len() + self.ie_payload.len()) as u8,
-                command: CommandCode {
-                    is_extended: false,
-                    cmd_type: CommandType::SREQ,
-                    subsystem: MTSubsystem::MAC,
-                    id: MACCommandId::DataReq as u8,
-                },
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::SREQ,
+                subsystem: MTSubsystem::MAC,
+                id: MACCommandId::DataReq as u8,
             },
-            extended_header: None,
-            payload: self.encode(),
-        }
+            self.encode(),
+        )
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PurgeReq {
     pub handle: u8,
 }
 
 impl PurgeReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let handle = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let handle = cursor.field("handle", Reader::read_u8)?;
         Ok(PurgeReq { handle })
     }
 
@@ -187,33 +239,33 @@ impl PurgeReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AssociateReq {
     pub logical_channel: u8,
     pub channel_page: u8,
     pub phy_id: u8,
     pub coord_address: Address,
-    pub coord_pan_id: u16,
-    pub capability_info: u8,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub coord_pan_id: PanId,
+    pub capability_info: CapabilityInfo,
+    pub security: Security,
 }
 
 impl AssociateReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let logical_channel = cursor.get_u8();
-        let channel_page = cursor.get_u8();
-        let phy_id = cursor.get_u8();
-        let coord_address = Address::try_decode(&mut cursor)?;
-        let coord_pan_id = cursor.get_u16_le();
-        let capability_info = cursor.get_u8();
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let logical_channel = cursor.field("logical_channel", Reader::read_u8)?;
+        let channel_page = cursor.field("channel_page", Reader::read_u8)?;
+        let phy_id = cursor.field("phy_id", Reader::read_u8)?;
+        let coord_address = cursor.field("coord_address", Address::try_decode)?;
+        let coord_pan_id = cursor.field("coord_pan_id", PanId::try_decode)?;
+        let capability_info = cursor.field("capability_info", CapabilityInfo::try_decode)?;
+        let security = cursor.field("security", Security::try_decode)?;
         Ok(AssociateReq {
             logical_channel,
             channel_page,
@@ -221,10 +273,7 @@ impl AssociateReq {
             coord_address,
             coord_pan_id,
             capability_info,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
         })
     }
 
@@ -239,12 +288,9 @@ impl AssociateReq {
         buffer.put_u8(self.channel_page);
         buffer.put_u8(self.phy_id);
         self.coord_address.encode_into(buffer);
-        buffer.put_u16_le(self.coord_pan_id);
-        buffer.put_u8(self.capability_info);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.coord_pan_id.encode_into(buffer);
+        self.capability_info.encode_into(buffer);
+        self.security.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
@@ -264,35 +310,32 @@ impl AssociateReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AssociateRsp {
     pub extended_address: ExtendedAddress,
     pub assoc_short_address: ShortAddress,
     pub assoc_status: AssociationStatus,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub security: Security,
 }
 
 impl AssociateRsp {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let extended_address = ExtendedAddress::try_decode(&mut cursor)?;
-        let assoc_short_address = ShortAddress::try_decode(&mut cursor)?;
-        let assoc_status = AssociationStatus::try_decode(&mut cursor)?;
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let extended_address = cursor.field("extended_address", ExtendedAddress::try_decode)?;
+        let assoc_short_address = cursor.field("assoc_short_address", ShortAddress::try_decode)?;
+        let assoc_status = cursor.field("assoc_status", AssociationStatus::try_decode)?;
+        let security = cursor.field("security", Security::try_decode)?;
         Ok(AssociateRsp {
             extended_address,
             assoc_short_address,
             assoc_status,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
         })
     }
 
@@ -306,10 +349,7 @@ impl AssociateRsp {
         self.extended_address.encode_into(buffer);
         self.assoc_short_address.encode_into(buffer);
         self.assoc_status.encode_into(buffer);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.security.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
@@ -329,38 +369,36 @@ impl AssociateRsp {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DisassociateReq {
     pub device_address: Address,
-    pub device_pan_id: u16,
+    pub device_pan_id: PanId,
     pub disassociate_reason: DisassociateReason,
     pub tx_indirect: bool,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub security: Security,
 }
 
 impl DisassociateReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let device_address = Address::try_decode(&mut cursor)?;
-        let device_pan_id = cursor.get_u16_le();
-        let disassociate_reason = DisassociateReason::try_decode(&mut cursor)?;
-        let tx_indirect = cursor.get_u8() == 0;
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let device_address = cursor.field("device_address", Address::try_decode)?;
+        let device_pan_id = cursor.field("device_pan_id", PanId::try_decode)?;
+        let disassociate_reason =
+            cursor.field("disassociate_reason", DisassociateReason::try_decode)?;
+        let tx_indirect = cursor.field("tx_indirect", Reader::read_u8)? != 0;
+        let security = cursor.field("security", Security::try_decode)?;
         Ok(DisassociateReq {
             device_address,
             device_pan_id,
             disassociate_reason,
             tx_indirect,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
         })
     }
 
@@ -372,13 +410,10 @@ impl DisassociateReq {
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         self.device_address.encode_into(buffer);
-        buffer.put_u16_le(self.device_pan_id);
+        self.device_pan_id.encode_into(buffer);
         self.disassociate_reason.encode_into(buffer);
         buffer.put_u8(if self.tx_indirect { 1 } else { 0 });
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.security.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
@@ -398,15 +433,21 @@ impl DisassociateReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetReq {
     pub attribute_id: MACPIBAttributeId,
 }
 
 impl GetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let attribute_id = MACPIBAttributeId::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let attribute_id = cursor.field("attribute_id", MACPIBAttributeId::try_decode)?;
         Ok(GetReq { attribute_id })
     }
 
@@ -437,7 +478,9 @@ impl GetReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetReq {
     pub attribute_id: MACPIBAttributeId,
     pub attribute_value: [u8; 16],
@@ -445,13 +488,17 @@ pub struct SetReq {
 
 impl SetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let attribute_id = MACPIBAttributeId::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let attribute_id = cursor.field("attribute_id", MACPIBAttributeId::try_decode)?;
 
         let mut attribute_value: [u8; 16] = Default::default();
-        cursor
-            .read_exact(&mut attribute_value)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("attribute_value", |cursor| {
+            cursor.read_exact(&mut attribute_value)
+        })?;
         attribute_value.reverse();
 
         Ok(SetReq {
@@ -488,19 +535,27 @@ impl SetReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SecurityGetReq {
     pub attribute_id: SecurityPIBAttributeId,
     pub index1: u16,
     pub index2: u16,
 }
 
+#[cfg(feature = "security-tables")]
 impl SecurityGetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let attribute_id = SecurityPIBAttributeId::try_decode(&mut cursor)?;
-        let index1 = cursor.get_u16_le();
-        let index2 = cursor.get_u16_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let attribute_id = cursor.field("attribute_id", SecurityPIBAttributeId::try_decode)?;
+        let index1 = cursor.field("index1", Reader::read_u16_le)?;
+        let index2 = cursor.field("index2", Reader::read_u16_le)?;
 
         Ok(SecurityGetReq {
             attribute_id,
@@ -538,7 +593,10 @@ impl SecurityGetReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SecuritySetReq {
     pub attribute_id: SecurityPIBAttributeId,
     pub index1: u16,
@@ -546,17 +604,22 @@ pub struct SecuritySetReq {
     pub attribute_value: Vec<u8>,
 }
 
+#[cfg(feature = "security-tables")]
 impl SecuritySetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let attribute_id = SecurityPIBAttributeId::try_decode(&mut cursor)?;
-        let index1 = cursor.get_u16_le();
-        let index2 = cursor.get_u16_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let attribute_id = cursor.field("attribute_id", SecurityPIBAttributeId::try_decode)?;
+        let index1 = cursor.field("index1", Reader::read_u16_le)?;
+        let index2 = cursor.field("index2", Reader::read_u16_le)?;
 
         let mut attribute_value = Vec::new();
-        cursor
-            .read_to_end(&mut attribute_value)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("attribute_value", |cursor| {
+            cursor.read_to_end(&mut attribute_value)
+        })?;
 
         Ok(SecuritySetReq {
             attribute_id,
@@ -580,31 +643,33 @@ impl SecuritySetReq {
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
-        MTFrame {
-            header: MTHeader {
-                length: 0x05 + self.attribute_value.len() as u8,
-                command: CommandCode {
-                    is_extended: false,
-                    cmd_type: CommandType::SREQ,
-                    subsystem: MTSubsystem::MAC,
-                    id: MACCommandId::SecuritySetReq as u8,
-                },
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::SREQ,
+                subsystem: MTSubsystem::MAC,
+                id: MACCommandId::SecuritySetReq as u8,
             },
-            extended_header: None,
-            payload: self.encode(),
-        }
+            self.encode(),
+        )
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UpdatePANIdReq {
-    pub pan_id: u16,
+    pub pan_id: PanId,
 }
 
 impl UpdatePANIdReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let pan_id = cursor.get_u16_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let pan_id = cursor.field("pan_id", PanId::try_decode)?;
         Ok(UpdatePANIdReq { pan_id })
     }
 
@@ -615,7 +680,7 @@ impl UpdatePANIdReq {
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
-        buffer.put_u16_le(self.pan_id);
+        self.pan_id.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
@@ -635,9 +700,12 @@ impl UpdatePANIdReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AddDeviceReq {
-    pub pan_id: u16,
+    pub pan_id: PanId,
     pub short_addr: ShortAddress,
     pub ext_addr: ExtendedAddress,
     pub frame_counter: u32,
@@ -648,22 +716,25 @@ pub struct AddDeviceReq {
     pub lookup_data: [u8; 9],
 }
 
+#[cfg(feature = "security-tables")]
 impl AddDeviceReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let pan_id = cursor.get_u16_le();
-        let short_addr = ShortAddress::try_decode(&mut cursor)?;
-        let ext_addr = ExtendedAddress::try_decode(&mut cursor)?;
-        let frame_counter = cursor.get_u32_le();
-        let exempt = cursor.get_u8() != 0;
-        let unique = cursor.get_u8() != 0;
-        let duplicate = cursor.get_u8() != 0;
-        let data_size = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let pan_id = cursor.field("pan_id", PanId::try_decode)?;
+        let short_addr = cursor.field("short_addr", ShortAddress::try_decode)?;
+        let ext_addr = cursor.field("ext_addr", ExtendedAddress::try_decode)?;
+        let frame_counter = cursor.field("frame_counter", Reader::read_u32_le)?;
+        let exempt = cursor.field("exempt", Reader::read_u8)? != 0;
+        let unique = cursor.field("unique", Reader::read_u8)? != 0;
+        let duplicate = cursor.field("duplicate", Reader::read_u8)? != 0;
+        let data_size = cursor.field("data_size", Reader::read_u8)?;
 
         let mut lookup_data: [u8; 9] = Default::default();
-        cursor
-            .read_exact(&mut lookup_data)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("lookup_data", |cursor| cursor.read_exact(&mut lookup_data))?;
 
         Ok(AddDeviceReq {
             pan_id,
@@ -685,7 +756,7 @@ impl AddDeviceReq {
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
-        buffer.put_u16_le(self.pan_id);
+        self.pan_id.encode_into(buffer);
         self.short_addr.encode_into(buffer);
         self.ext_addr.encode_into(buffer);
         buffer.put_u32_le(self.frame_counter);
@@ -713,15 +784,23 @@ impl AddDeviceReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeleteDeviceReq {
     pub ext_addr: ExtendedAddress,
 }
 
+#[cfg(feature = "security-tables")]
 impl DeleteDeviceReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let ext_addr = ExtendedAddress::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let ext_addr = cursor.field("ext_addr", ExtendedAddress::try_decode)?;
         Ok(DeleteDeviceReq { ext_addr })
     }
 
@@ -752,11 +831,19 @@ impl DeleteDeviceReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeleteAllDevicesReq {}
 
+#[cfg(feature = "security-tables")]
 impl DeleteAllDevicesReq {
-    pub fn try_decode(_: &[u8]) -> Result<Self, Error> {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(_: &[u8], _: DecodeOptions) -> Result<Self, Error> {
         Ok(DeleteAllDevicesReq {})
     }
 
@@ -785,15 +872,23 @@ impl DeleteAllDevicesReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeleteKeyReq {
     pub index: u8,
 }
 
+#[cfg(feature = "security-tables")]
 impl DeleteKeyReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let index = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let index = cursor.field("index", Reader::read_u8)?;
         Ok(DeleteKeyReq { index })
     }
 
@@ -824,15 +919,23 @@ impl DeleteKeyReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReadKeyReq {
     pub index: u8,
 }
 
+#[cfg(feature = "security-tables")]
 impl ReadKeyReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let index = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let index = cursor.field("index", Reader::read_u8)?;
         Ok(ReadKeyReq { index })
     }
 
@@ -863,7 +966,10 @@ impl ReadKeyReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WriteKeyReq {
     pub new: bool,
     pub index: u16,
@@ -873,24 +979,25 @@ pub struct WriteKeyReq {
     pub lookup_data: [u8; 9],
 }
 
+#[cfg(feature = "security-tables")]
 impl WriteKeyReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let new = cursor.get_u8() != 0;
-        let index = cursor.get_u16_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let new = cursor.field("new", Reader::read_u8)? != 0;
+        let index = cursor.field("index", Reader::read_u16_le)?;
 
         let mut key: [u8; 16] = Default::default();
-        cursor
-            .read_exact(&mut key)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("key", |cursor| cursor.read_exact(&mut key))?;
 
-        let frame_counter = cursor.get_u32_le();
-        let data_size = cursor.get_u8();
+        let frame_counter = cursor.field("frame_counter", Reader::read_u32_le)?;
+        let data_size = cursor.field("data_size", Reader::read_u8)?;
 
         let mut lookup_data: [u8; 9] = Default::default();
-        cursor
-            .read_exact(&mut lookup_data)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("lookup_data", |cursor| cursor.read_exact(&mut lookup_data))?;
 
         Ok(WriteKeyReq {
             new,
@@ -934,35 +1041,32 @@ impl WriteKeyReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrphanRsp {
     pub extended_address: ExtendedAddress,
     pub assoc_short_address: ShortAddress,
     pub associated_member: bool,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub security: Security,
 }
 
 impl OrphanRsp {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let extended_address = ExtendedAddress::try_decode(&mut cursor)?;
-        let assoc_short_address = ShortAddress::try_decode(&mut cursor)?;
-        let associated_member = cursor.get_u8() != 0;
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let extended_address = cursor.field("extended_address", ExtendedAddress::try_decode)?;
+        let assoc_short_address = cursor.field("assoc_short_address", ShortAddress::try_decode)?;
+        let associated_member = cursor.field("associated_member", Reader::read_u8)? != 0;
+        let security = cursor.field("security", Security::try_decode)?;
         Ok(OrphanRsp {
             extended_address,
             assoc_short_address,
             associated_member,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
         })
     }
 
@@ -976,10 +1080,7 @@ impl OrphanRsp {
         self.extended_address.encode_into(buffer);
         self.assoc_short_address.encode_into(buffer);
         buffer.put_u8(if self.associated_member { 1 } else { 0 });
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.security.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
@@ -999,32 +1100,29 @@ impl OrphanRsp {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PollReq {
     pub coord_address: Address,
-    pub coord_pan_id: u16,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
+    pub coord_pan_id: PanId,
+    pub security: Security,
 }
 
 impl PollReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let coord_address = Address::try_decode(&mut cursor)?;
-        let coord_pan_id = cursor.get_u16_le();
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let coord_address = cursor.field("coord_address", Address::try_decode)?;
+        let coord_pan_id = cursor.field("coord_pan_id", PanId::try_decode)?;
+        let security = cursor.field("security", Security::try_decode)?;
         Ok(PollReq {
             coord_address,
             coord_pan_id,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
         })
     }
 
@@ -1036,11 +1134,8 @@ impl PollReq {
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         self.coord_address.encode_into(buffer);
-        buffer.put_u16_le(self.coord_pan_id);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.coord_pan_id.encode_into(buffer);
+        self.security.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
@@ -1060,15 +1155,21 @@ impl PollReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResetReq {
     pub set_default: bool,
 }
 
 impl ResetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let set_default = cursor.get_u8() != 0;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let set_default = cursor.field("set_default", Reader::read_u8)? != 0;
         Ok(ResetReq { set_default })
     }
 
@@ -1099,7 +1200,9 @@ impl ResetReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScanReq {
     pub scan_type: ScanType,
     pub scan_duration: u8,
@@ -1112,32 +1215,30 @@ pub struct ScanReq {
     pub mpm_scan: MPMScan,
     pub mpm_type: MPMType,
     pub mpm_duration: u16,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
-    pub channels: ChannelsBitMap,
+    pub security: Security,
+    pub channels: ChannelMask,
 }
 
 impl ScanReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let scan_type = ScanType::try_decode(&mut cursor)?;
-        let scan_duration = cursor.get_u8();
-        let channel_page = cursor.get_u8();
-        let phy_id = PhyId::try_decode(&mut cursor)?;
-        let max_results = cursor.get_u8();
-        let permit_join = PermitJoin::try_decode(&mut cursor)?;
-        let link_quality = cursor.get_u8();
-        let rsp_filter = cursor.get_u8();
-        let mpm_scan = MPMScan::try_decode(&mut cursor)?;
-        let mpm_type = MPMType::try_decode(&mut cursor)?;
-        let mpm_duration = cursor.get_u16_le();
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
-        let channels = ChannelsBitMap::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let scan_type = cursor.field("scan_type", ScanType::try_decode)?;
+        let scan_duration = cursor.field("scan_duration", Reader::read_u8)?;
+        let channel_page = cursor.field("channel_page", Reader::read_u8)?;
+        let phy_id = cursor.field("phy_id", PhyId::try_decode)?;
+        let max_results = cursor.field("max_results", Reader::read_u8)?;
+        let permit_join = cursor.field("permit_join", PermitJoin::try_decode)?;
+        let link_quality = cursor.field("link_quality", Reader::read_u8)?;
+        let rsp_filter = cursor.field("rsp_filter", Reader::read_u8)?;
+        let mpm_scan = cursor.field("mpm_scan", MPMScan::try_decode)?;
+        let mpm_type = cursor.field("mpm_type", MPMType::try_decode)?;
+        let mpm_duration = cursor.field("mpm_duration", Reader::read_u16_le)?;
+        let security = cursor.field("security", Security::try_decode)?;
+        let channels = cursor.field("channels", ChannelMask::try_decode)?;
         Ok(ScanReq {
             scan_type,
             scan_duration,
@@ -1150,10 +1251,7 @@ impl ScanReq {
             mpm_scan,
             mpm_type,
             mpm_duration,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
             channels,
         })
     }
@@ -1176,17 +1274,14 @@ impl ScanReq {
         self.mpm_scan.encode_into(buffer);
         self.mpm_type.encode_into(buffer);
         buffer.put_u16_le(self.mpm_duration);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.security.encode_into(buffer);
         self.channels.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
         MTFrame {
             header: MTHeader {
-                length: 0x1b,
+                length: 0x28,
                 command: CommandCode {
                     is_extended: false,
                     cmd_type: CommandType::SREQ,
@@ -1200,10 +1295,12 @@ impl ScanReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StartReq {
     pub start_time: u32,
-    pub pan_id: u16,
+    pub pan_id: PanId,
     pub logical_channel: u8,
     pub channel_page: u8,
     pub phy_id: PhyId,
@@ -1212,14 +1309,8 @@ pub struct StartReq {
     pub pan_coordinator: bool,
     pub battery_life_ext: bool,
     pub coord_realignment: bool,
-    pub realign_key_source: KeySource,
-    pub realign_security_level: SecurityLevel,
-    pub realign_key_id_mode: KeyIdMode,
-    pub realign_key_index: u8,
-    pub beacon_key_source: KeySource,
-    pub beacon_security_level: SecurityLevel,
-    pub beacon_key_id_mode: KeyIdMode,
-    pub beacon_key_index: u8,
+    pub realign_security: Security,
+    pub beacon_security: Security,
     pub start_fh: bool,
     pub enh_beacon_order: u8,
     pub ofs_time_slot: u8,
@@ -1230,35 +1321,31 @@ pub struct StartReq {
 
 impl StartReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let start_time = cursor.get_u32_le();
-        let pan_id = cursor.get_u16_le();
-        let logical_channel = cursor.get_u8();
-        let channel_page = cursor.get_u8();
-        let phy_id = PhyId::try_decode(&mut cursor)?;
-        let beacon_order = cursor.get_u8();
-        let super_frame_order = cursor.get_u8();
-        let pan_coordinator = cursor.get_u8() != 0;
-        let battery_life_ext = cursor.get_u8() != 0;
-        let coord_realignment = cursor.get_u8() != 0;
-        let realign_key_source = KeySource::try_decode(&mut cursor)?;
-        let realign_security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let realign_key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let realign_key_index = cursor.get_u8();
-        let beacon_key_source = KeySource::try_decode(&mut cursor)?;
-        let beacon_security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let beacon_key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let beacon_key_index = cursor.get_u8();
-        let start_fh = cursor.get_u8() != 0;
-        let enh_beacon_order = cursor.get_u8();
-        let ofs_time_slot = cursor.get_u8();
-        let non_beacon_order = cursor.get_u16_le();
-        let num_ies = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let start_time = cursor.field("start_time", Reader::read_u32_le)?;
+        let pan_id = cursor.field("pan_id", PanId::try_decode)?;
+        let logical_channel = cursor.field("logical_channel", Reader::read_u8)?;
+        let channel_page = cursor.field("channel_page", Reader::read_u8)?;
+        let phy_id = cursor.field("phy_id", PhyId::try_decode)?;
+        let beacon_order = cursor.field("beacon_order", Reader::read_u8)?;
+        let super_frame_order = cursor.field("super_frame_order", Reader::read_u8)?;
+        let pan_coordinator = cursor.field("pan_coordinator", Reader::read_u8)? != 0;
+        let battery_life_ext = cursor.field("battery_life_ext", Reader::read_u8)? != 0;
+        let coord_realignment = cursor.field("coord_realignment", Reader::read_u8)? != 0;
+        let realign_security = cursor.field("realign_security", Security::try_decode)?;
+        let beacon_security = cursor.field("beacon_security", Security::try_decode)?;
+        let start_fh = cursor.field("start_fh", Reader::read_u8)? != 0;
+        let enh_beacon_order = cursor.field("enh_beacon_order", Reader::read_u8)?;
+        let ofs_time_slot = cursor.field("ofs_time_slot", Reader::read_u8)?;
+        let non_beacon_order = cursor.field("non_beacon_order", Reader::read_u16_le)?;
+        let num_ies = cursor.field("num_ies", Reader::read_u8)?;
 
         let mut ie_id_list = vec![0x00; num_ies as usize];
-        cursor
-            .read_exact(&mut ie_id_list)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("ie_id_list", |cursor| cursor.read_exact(&mut ie_id_list))?;
 
         Ok(StartReq {
             start_time,
@@ -1271,14 +1358,8 @@ impl StartReq {
             pan_coordinator,
             battery_life_ext,
             coord_realignment,
-            realign_key_source,
-            realign_security_level,
-            realign_key_id_mode,
-            realign_key_index,
-            beacon_key_source,
-            beacon_security_level,
-            beacon_key_id_mode,
-            beacon_key_index,
+            realign_security,
+            beacon_security,
             start_fh,
             enh_beacon_order,
             ofs_time_slot,
@@ -1296,7 +1377,7 @@ impl StartReq {
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         buffer.put_u32_le(self.start_time);
-        buffer.put_u16_le(self.pan_id);
+        self.pan_id.encode_into(buffer);
         buffer.put_u8(self.logical_channel);
         buffer.put_u8(self.channel_page);
         self.phy_id.encode_into(buffer);
@@ -1305,14 +1386,8 @@ impl StartReq {
         buffer.put_u8(if self.pan_coordinator { 1 } else { 0 });
         buffer.put_u8(if self.battery_life_ext { 1 } else { 0 });
         buffer.put_u8(if self.coord_realignment { 1 } else { 0 });
-        self.realign_key_source.encode_into(buffer);
-        self.realign_security_level.encode_into(buffer);
-        self.realign_key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.realign_key_index);
-        self.beacon_key_source.encode_into(buffer);
-        self.beacon_security_level.encode_into(buffer);
-        self.beacon_key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.beacon_key_index);
+        self.realign_security.encode_into(buffer);
+        self.beacon_security.encode_into(buffer);
         buffer.put_u8(if self.start_fh { 1 } else { 0 });
         buffer.put_u8(self.enh_beacon_order);
         buffer.put_u8(self.ofs_time_slot);
@@ -1322,23 +1397,21 @@ impl StartReq {
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
-        MTFrame {
-            header: MTHeader {
-                length: 0x2a + self.ie_id_list.len() as u8,
-                command: CommandCode {
-                    is_extended: false,
-                    cmd_type: CommandType::SREQ,
-                    subsystem: MTSubsystem::MAC,
-                    id: MACCommandId::StartReq as u8,
-                },
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::SREQ,
+                subsystem: MTSubsystem::MAC,
+                id: MACCommandId::StartReq as u8,
             },
-            extended_header: None,
-            payload: self.encode(),
-        }
+            self.encode(),
+        )
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SyncReq {
     pub logical_channel: u8,
     pub channel_page: u8,
@@ -1348,11 +1421,15 @@ pub struct SyncReq {
 
 impl SyncReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let logical_channel = cursor.get_u8();
-        let channel_page = cursor.get_u8();
-        let track_beacon = cursor.get_u8() != 0;
-        let phy_id = PhyId::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let logical_channel = cursor.field("logical_channel", Reader::read_u8)?;
+        let channel_page = cursor.field("channel_page", Reader::read_u8)?;
+        let track_beacon = cursor.field("track_beacon", Reader::read_u8)? != 0;
+        let phy_id = cursor.field("phy_id", PhyId::try_decode)?;
         Ok(SyncReq {
             logical_channel,
             channel_page,
@@ -1391,15 +1468,21 @@ impl SyncReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetRxGainReq {
     pub mode: bool,
 }
 
 impl SetRxGainReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let mode = cursor.get_u8() != 0;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let mode = cursor.field("mode", Reader::read_u8)? != 0;
         Ok(SetRxGainReq { mode })
     }
 
@@ -1430,38 +1513,67 @@ impl SetRxGainReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "wisun-ie")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WSAsyncReq {
     pub operation: WiSUNAsyncOperation,
     pub frame_type: WiSUNAsyncFrameType,
-    pub key_source: KeySource,
-    pub security_level: SecurityLevel,
-    pub key_id_mode: KeyIdMode,
-    pub key_index: u8,
-    pub channels: ChannelsBitMap,
+    pub security: Security,
+    pub channels: ChannelMask,
 }
 
+#[cfg(feature = "wisun-ie")]
 impl WSAsyncReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let operation = WiSUNAsyncOperation::try_decode(&mut cursor)?;
-        let frame_type = WiSUNAsyncFrameType::try_decode(&mut cursor)?;
-        let key_source = KeySource::try_decode(&mut cursor)?;
-        let security_level = SecurityLevel::try_decode(&mut cursor)?;
-        let key_id_mode = KeyIdMode::try_decode(&mut cursor)?;
-        let key_index = cursor.get_u8();
-        let channels = ChannelsBitMap::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let operation = cursor.field("operation", WiSUNAsyncOperation::try_decode)?;
+        let frame_type = cursor.field("frame_type", WiSUNAsyncFrameType::try_decode)?;
+        let security = cursor.field("security", Security::try_decode)?;
+        let channels = cursor.field("channels", ChannelMask::try_decode)?;
         Ok(WSAsyncReq {
             operation,
             frame_type,
-            key_source,
-            security_level,
-            key_id_mode,
-            key_index,
+            security,
             channels,
         })
     }
 
+    /// Checks what the coprocessor would reject with `InvalidParameter`:
+    /// only PAN advertisement and configuration frames are sent
+    /// asynchronously, and a start needs at least one channel, all of them
+    /// within the PHY's band and none excluded. Stop requests ignore the
+    /// channel list.
+    pub fn validate(&self, phy_id: PhyId, excluded: &ChannelMask) -> Result<(), Error> {
+        use WiSUNAsyncFrameType::*;
+        if self.operation == WiSUNAsyncOperation::Stop {
+            return Ok(());
+        }
+        if !matches!(
+            self.frame_type,
+            PANAdvert | PANAdvertSOL | PANConfig | PANConfigSOL
+        ) {
+            return Err(Error::UnsupportedAsyncFrameType(self.frame_type));
+        }
+        if self.channels.is_empty() {
+            return Err(Error::NoChannels);
+        }
+        if let Some(count) = phy_id.channel_count() {
+            if let Some(channel) = self.channels.channels().find(|channel| *channel >= count) {
+                return Err(Error::ChannelOutOfRange(channel));
+            }
+        }
+        match self.channels.intersection(excluded).channels().next() {
+            Some(channel) => Err(Error::ExcludedChannel(channel)),
+            None => Ok(()),
+        }
+    }
+
     pub fn encode(&self) -> Vec<u8> {
         let mut buffer = Vec::new();
         self.encode_into(&mut buffer);
@@ -1471,17 +1583,14 @@ impl WSAsyncReq {
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         self.operation.encode_into(buffer);
         self.frame_type.encode_into(buffer);
-        self.key_source.encode_into(buffer);
-        self.security_level.encode_into(buffer);
-        self.key_id_mode.encode_into(buffer);
-        buffer.put_u8(self.key_index);
+        self.security.encode_into(buffer);
         self.channels.encode_into(buffer);
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
         MTFrame {
             header: MTHeader {
-                length: 0x26,
+                length: 0x1e,
                 command: CommandCode {
                     is_extended: false,
                     cmd_type: CommandType::SREQ,
@@ -1495,11 +1604,19 @@ impl WSAsyncReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "fh")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FHEnableReq {}
 
+#[cfg(feature = "fh")]
 impl FHEnableReq {
-    pub fn try_decode(_: &[u8]) -> Result<Self, Error> {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(_: &[u8], _: DecodeOptions) -> Result<Self, Error> {
         Ok(FHEnableReq {})
     }
 
@@ -1528,11 +1645,19 @@ impl FHEnableReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "fh")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FHStartReq {}
 
+#[cfg(feature = "fh")]
 impl FHStartReq {
-    pub fn try_decode(_: &[u8]) -> Result<Self, Error> {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(_: &[u8], _: DecodeOptions) -> Result<Self, Error> {
         Ok(FHStartReq {})
     }
 
@@ -1561,15 +1686,23 @@ impl FHStartReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "fh")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FHGetReq {
     pub attribute_id: FHPIBAttributeId,
 }
 
+#[cfg(feature = "fh")]
 impl FHGetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let attribute_id = FHPIBAttributeId::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let attribute_id = cursor.field("attribute_id", FHPIBAttributeId::try_decode)?;
         Ok(FHGetReq { attribute_id })
     }
 
@@ -1600,25 +1733,50 @@ impl FHGetReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "fh")]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FHSetReq {
     pub attribute_id: FHPIBAttributeId,
     pub data: Vec<u8>,
 }
 
+#[cfg(feature = "fh")]
 impl FHSetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let attribute_id = FHPIBAttributeId::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let attribute_id = cursor.field("attribute_id", FHPIBAttributeId::try_decode)?;
 
         let mut data = Vec::new();
-        cursor
-            .read_to_end(&mut data)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("data", |cursor| cursor.read_to_end(&mut data))?;
 
         Ok(FHSetReq { attribute_id, data })
     }
 
+    pub fn net_name(name: &NetName) -> FHSetReq {
+        let mut data = Vec::new();
+        name.encode_into(&mut data);
+        FHSetReq {
+            attribute_id: FHPIBAttributeId::NetName,
+            data,
+        }
+    }
+
+    /// Returns `None` for indexes past GTK3.
+    pub fn gtk_hash(index: u8, hash: GtkHash) -> Option<FHSetReq> {
+        let mut data = Vec::new();
+        hash.encode_into(&mut data);
+        Some(FHSetReq {
+            attribute_id: GtkHash::attribute_id(index)?,
+            data,
+        })
+    }
+
     pub fn encode(&self) -> Vec<u8> {
         let mut buffer = Vec::new();
         self.encode_into(&mut buffer);
@@ -1630,15 +1788,203 @@ impl FHSetReq {
         buffer.extend(self.data.iter());
     }
 
+    pub fn into_mt_frame(self) -> MTFrame {
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::SREQ,
+                subsystem: MTSubsystem::MAC,
+                id: MACCommandId::FHSetReq as u8,
+            },
+            self.encode(),
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SrcMatchEnableReq {
+    pub address_type: AddressMode,
+    pub num_entries: u8,
+}
+
+impl SrcMatchEnableReq {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let address_type = cursor.field("address_type", AddressMode::try_decode)?;
+        let num_entries = cursor.field("num_entries", Reader::read_u8)?;
+        Ok(SrcMatchEnableReq {
+            address_type,
+            num_entries,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.encode_into(&mut buffer);
+        buffer
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        self.address_type.encode_into(buffer);
+        buffer.put_u8(self.num_entries);
+    }
+
+    pub fn into_mt_frame(self) -> MTFrame {
+        MTFrame {
+            header: MTHeader {
+                length: 0x02,
+                command: CommandCode {
+                    is_extended: false,
+                    cmd_type: CommandType::SREQ,
+                    subsystem: MTSubsystem::MAC,
+                    id: MACCommandId::SrcMatchEnableReq as u8,
+                },
+            },
+            extended_header: None,
+            payload: self.encode(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SrcMatchAddEntryReq {
+    pub address: Address,
+    pub pan_id: PanId,
+}
+
+impl SrcMatchAddEntryReq {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let address = cursor.field("address", Address::try_decode)?;
+        let pan_id = cursor.field("pan_id", PanId::try_decode)?;
+        Ok(SrcMatchAddEntryReq { address, pan_id })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.encode_into(&mut buffer);
+        buffer
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        self.address.encode_into(buffer);
+        self.pan_id.encode_into(buffer);
+    }
+
     pub fn into_mt_frame(self) -> MTFrame {
         MTFrame {
             header: MTHeader {
-                length: 0x02 + self.data.len() as u8,
+                length: 0x0b,
+                command: CommandCode {
+                    is_extended: false,
+                    cmd_type: CommandType::SREQ,
+                    subsystem: MTSubsystem::MAC,
+                    id: MACCommandId::SrcMatchAddEntryReq as u8,
+                },
+            },
+            extended_header: None,
+            payload: self.encode(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SrcMatchDeleteEntryReq {
+    pub address: Address,
+    pub pan_id: PanId,
+}
+
+impl SrcMatchDeleteEntryReq {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let address = cursor.field("address", Address::try_decode)?;
+        let pan_id = cursor.field("pan_id", PanId::try_decode)?;
+        Ok(SrcMatchDeleteEntryReq { address, pan_id })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.encode_into(&mut buffer);
+        buffer
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        self.address.encode_into(buffer);
+        self.pan_id.encode_into(buffer);
+    }
+
+    pub fn into_mt_frame(self) -> MTFrame {
+        MTFrame {
+            header: MTHeader {
+                length: 0x0b,
+                command: CommandCode {
+                    is_extended: false,
+                    cmd_type: CommandType::SREQ,
+                    subsystem: MTSubsystem::MAC,
+                    id: MACCommandId::SrcMatchDeleteEntryReq as u8,
+                },
+            },
+            extended_header: None,
+            payload: self.encode(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SrcMatchAckAllPendingReq {
+    pub enabled: bool,
+}
+
+impl SrcMatchAckAllPendingReq {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let enabled = cursor.field("enabled", Reader::read_u8)? != 0;
+        Ok(SrcMatchAckAllPendingReq { enabled })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.encode_into(&mut buffer);
+        buffer
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u8(if self.enabled { 1 } else { 0 });
+    }
+
+    pub fn into_mt_frame(self) -> MTFrame {
+        MTFrame {
+            header: MTHeader {
+                length: 0x01,
                 command: CommandCode {
                     is_extended: false,
                     cmd_type: CommandType::SREQ,
                     subsystem: MTSubsystem::MAC,
-                    id: MACCommandId::FHSetReq as u8,
+                    id: MACCommandId::SrcMatchAckAllPendingReq as u8,
                 },
             },
             extended_header: None,
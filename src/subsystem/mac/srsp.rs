@@ -1,19 +1,27 @@
 use crate::error::Error;
 use crate::frame::{CommandCode, MTFrame, MTHeader};
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
 use crate::types::*;
-use bytes::{Buf, BufMut};
-use std::io::Cursor;
-use std::io::Read;
+#[cfg(feature = "security-tables")]
+use crate::writer::BufMut;
+use alloc::vec::Vec;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Init {
     pub status: Status,
 }
 
 impl Init {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(Init { status })
     }
 
@@ -44,15 +52,21 @@ impl Init {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DataReq {
     pub status: Status,
 }
 
 impl DataReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(DataReq { status })
     }
 
@@ -83,15 +97,21 @@ impl DataReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PurgeReq {
     pub status: Status,
 }
 
 impl PurgeReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(PurgeReq { status })
     }
 
@@ -122,15 +142,21 @@ impl PurgeReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AssociateReq {
     pub status: Status,
 }
 
 impl AssociateReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(AssociateReq { status })
     }
 
@@ -161,15 +187,21 @@ impl AssociateReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AssociateRsp {
     pub status: Status,
 }
 
 impl AssociateRsp {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(AssociateRsp { status })
     }
 
@@ -200,15 +232,21 @@ impl AssociateRsp {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DisassociateReq {
     pub status: Status,
 }
 
 impl DisassociateReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(DisassociateReq { status })
     }
 
@@ -239,7 +277,9 @@ impl DisassociateReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetReq {
     pub status: Status,
     pub data: [u8; 16],
@@ -247,13 +287,15 @@ pub struct GetReq {
 
 impl GetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
 
         let mut data: [u8; 16] = Default::default();
-        cursor
-            .read_exact(&mut data)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("data", |cursor| cursor.read_exact(&mut data))?;
         data.reverse();
 
         Ok(GetReq { status, data })
@@ -287,15 +329,21 @@ impl GetReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetReq {
     pub status: Status,
 }
 
 impl SetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(SetReq { status })
     }
 
@@ -326,7 +374,10 @@ impl SetReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SecurityGetReq {
     pub status: Status,
     pub index1: u16,
@@ -334,17 +385,20 @@ pub struct SecurityGetReq {
     pub data: Vec<u8>,
 }
 
+#[cfg(feature = "security-tables")]
 impl SecurityGetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
-        let index1 = cursor.get_u16_le();
-        let index2 = cursor.get_u16_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        let index1 = cursor.field("index1", Reader::read_u16_le)?;
+        let index2 = cursor.field("index2", Reader::read_u16_le)?;
 
         let mut data = Vec::new();
-        cursor
-            .read_to_end(&mut data)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("data", |cursor| cursor.read_to_end(&mut data))?;
 
         Ok(SecurityGetReq {
             status,
@@ -368,31 +422,35 @@ impl SecurityGetReq {
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
-        MTFrame {
-            header: MTHeader {
-                length: 0x05 + self.data.len() as u8,
-                command: CommandCode {
-                    is_extended: false,
-                    cmd_type: CommandType::SRSP,
-                    subsystem: MTSubsystem::MAC,
-                    id: MACCommandId::SecurityGetReq as u8,
-                },
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::SRSP,
+                subsystem: MTSubsystem::MAC,
+                id: MACCommandId::SecurityGetReq as u8,
             },
-            extended_header: None,
-            payload: self.encode(),
-        }
+            self.encode(),
+        )
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SecuritySetReq {
     pub status: Status,
 }
 
+#[cfg(feature = "security-tables")]
 impl SecuritySetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(SecuritySetReq { status })
     }
 
@@ -423,15 +481,21 @@ impl SecuritySetReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UpdatePANIdReq {
     pub status: Status,
 }
 
 impl UpdatePANIdReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(UpdatePANIdReq { status })
     }
 
@@ -462,15 +526,23 @@ impl UpdatePANIdReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AddDeviceReq {
     pub status: Status,
 }
 
+#[cfg(feature = "security-tables")]
 impl AddDeviceReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(AddDeviceReq { status })
     }
 
@@ -501,15 +573,23 @@ impl AddDeviceReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeleteDeviceReq {
     pub status: Status,
 }
 
+#[cfg(feature = "security-tables")]
 impl DeleteDeviceReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(DeleteDeviceReq { status })
     }
 
@@ -540,15 +620,23 @@ impl DeleteDeviceReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeleteAllDevicesReq {
     pub status: Status,
 }
 
+#[cfg(feature = "security-tables")]
 impl DeleteAllDevicesReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(DeleteAllDevicesReq { status })
     }
 
@@ -579,15 +667,23 @@ impl DeleteAllDevicesReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeleteKeyReq {
     pub status: Status,
 }
 
+#[cfg(feature = "security-tables")]
 impl DeleteKeyReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(DeleteKeyReq { status })
     }
 
@@ -618,17 +714,25 @@ impl DeleteKeyReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReadKeyReq {
     pub status: Status,
     pub frame_counter: u32,
 }
 
+#[cfg(feature = "security-tables")]
 impl ReadKeyReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
-        let frame_counter = cursor.get_u32_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        let frame_counter = cursor.field("frame_counter", Reader::read_u32_le)?;
         Ok(ReadKeyReq {
             status,
             frame_counter,
@@ -663,15 +767,23 @@ impl ReadKeyReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "security-tables")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WriteKeyReq {
     pub status: Status,
 }
 
+#[cfg(feature = "security-tables")]
 impl WriteKeyReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(WriteKeyReq { status })
     }
 
@@ -702,15 +814,21 @@ impl WriteKeyReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrphanRsp {
     pub status: Status,
 }
 
 impl OrphanRsp {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(OrphanRsp { status })
     }
 
@@ -741,15 +859,21 @@ impl OrphanRsp {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PollReq {
     pub status: Status,
 }
 
 impl PollReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(PollReq { status })
     }
 
@@ -780,15 +904,21 @@ impl PollReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResetReq {
     pub status: Status,
 }
 
 impl ResetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(ResetReq { status })
     }
 
@@ -819,15 +949,21 @@ impl ResetReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScanReq {
     pub status: Status,
 }
 
 impl ScanReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(ScanReq { status })
     }
 
@@ -858,15 +994,21 @@ impl ScanReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StartReq {
     pub status: Status,
 }
 
 impl StartReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(StartReq { status })
     }
 
@@ -897,15 +1039,21 @@ impl StartReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SyncReq {
     pub status: Status,
 }
 
 impl SyncReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(SyncReq { status })
     }
 
@@ -936,15 +1084,21 @@ impl SyncReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SetRxGainReq {
     pub status: Status,
 }
 
 impl SetRxGainReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(SetRxGainReq { status })
     }
 
@@ -975,15 +1129,23 @@ impl SetRxGainReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "wisun-ie")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WSAsyncReq {
     pub status: Status,
 }
 
+#[cfg(feature = "wisun-ie")]
 impl WSAsyncReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(WSAsyncReq { status })
     }
 
@@ -1014,15 +1176,23 @@ impl WSAsyncReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "fh")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FHEnableReq {
     pub status: Status,
 }
 
+#[cfg(feature = "fh")]
 impl FHEnableReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(FHEnableReq { status })
     }
 
@@ -1053,15 +1223,23 @@ impl FHEnableReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "fh")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FHStartReq {
     pub status: Status,
 }
 
+#[cfg(feature = "fh")]
 impl FHStartReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(FHStartReq { status })
     }
 
@@ -1092,25 +1270,44 @@ impl FHStartReq {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "fh")]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FHGetReq {
     pub status: Status,
     pub data: Vec<u8>,
 }
 
+#[cfg(feature = "fh")]
 impl FHGetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
 
         let mut data = Vec::new();
-        cursor
-            .read_to_end(&mut data)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("data", |cursor| cursor.read_to_end(&mut data))?;
 
         Ok(FHGetReq { status, data })
     }
 
+    pub fn net_name(&self) -> Result<NetName, Error> {
+        NetName::try_decode(&mut Reader::new(&self.data[..]))
+    }
+
+    pub fn gtk_hash(&self) -> Result<GtkHash, Error> {
+        GtkHash::try_decode(&mut Reader::new(&self.data[..]))
+    }
+
+    /// Value of `UCExcludedChannels` or `BCExcludedChannels`.
+    pub fn channel_mask(&self) -> Result<ChannelMask, Error> {
+        ChannelMask::try_decode(&mut Reader::new(&self.data[..]))
+    }
+
     pub fn encode(&self) -> Vec<u8> {
         let mut buffer = Vec::new();
         self.encode_into(&mut buffer);
@@ -1122,15 +1319,58 @@ impl FHGetReq {
         buffer.extend(self.data.iter());
     }
 
+    pub fn into_mt_frame(self) -> MTFrame {
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::SRSP,
+                subsystem: MTSubsystem::MAC,
+                id: MACCommandId::FHGetReq as u8,
+            },
+            self.encode(),
+        )
+    }
+}
+
+#[cfg(feature = "fh")]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FHSetReq {
+    pub status: Status,
+}
+
+#[cfg(feature = "fh")]
+impl FHSetReq {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        Ok(FHSetReq { status })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.encode_into(&mut buffer);
+        buffer
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        self.status.encode_into(buffer);
+    }
+
     pub fn into_mt_frame(self) -> MTFrame {
         MTFrame {
             header: MTHeader {
-                length: 0x01 + self.data.len() as u8,
+                length: 0x01,
                 command: CommandCode {
                     is_extended: false,
                     cmd_type: CommandType::SRSP,
                     subsystem: MTSubsystem::MAC,
-                    id: MACCommandId::FHGetReq as u8,
+                    id: MACCommandId::FHSetReq as u8,
                 },
             },
             extended_header: None,
@@ -1139,16 +1379,22 @@ impl FHGetReq {
     }
 }
 
-#[derive(Debug)]
-pub struct FHSetReq {
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SrcMatchEnableReq {
     pub status: Status,
 }
 
-impl FHSetReq {
+impl SrcMatchEnableReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
-        Ok(FHSetReq { status })
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        Ok(SrcMatchEnableReq { status })
     }
 
     pub fn encode(&self) -> Vec<u8> {
@@ -1169,7 +1415,142 @@ impl FHSetReq {
                     is_extended: false,
                     cmd_type: CommandType::SRSP,
                     subsystem: MTSubsystem::MAC,
-                    id: MACCommandId::FHSetReq as u8,
+                    id: MACCommandId::SrcMatchEnableReq as u8,
+                },
+            },
+            extended_header: None,
+            payload: self.encode(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SrcMatchAddEntryReq {
+    pub status: Status,
+}
+
+impl SrcMatchAddEntryReq {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        Ok(SrcMatchAddEntryReq { status })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.encode_into(&mut buffer);
+        buffer
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        self.status.encode_into(buffer);
+    }
+
+    pub fn into_mt_frame(self) -> MTFrame {
+        MTFrame {
+            header: MTHeader {
+                length: 0x01,
+                command: CommandCode {
+                    is_extended: false,
+                    cmd_type: CommandType::SRSP,
+                    subsystem: MTSubsystem::MAC,
+                    id: MACCommandId::SrcMatchAddEntryReq as u8,
+                },
+            },
+            extended_header: None,
+            payload: self.encode(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SrcMatchDeleteEntryReq {
+    pub status: Status,
+}
+
+impl SrcMatchDeleteEntryReq {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        Ok(SrcMatchDeleteEntryReq { status })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.encode_into(&mut buffer);
+        buffer
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        self.status.encode_into(buffer);
+    }
+
+    pub fn into_mt_frame(self) -> MTFrame {
+        MTFrame {
+            header: MTHeader {
+                length: 0x01,
+                command: CommandCode {
+                    is_extended: false,
+                    cmd_type: CommandType::SRSP,
+                    subsystem: MTSubsystem::MAC,
+                    id: MACCommandId::SrcMatchDeleteEntryReq as u8,
+                },
+            },
+            extended_header: None,
+            payload: self.encode(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SrcMatchAckAllPendingReq {
+    pub status: Status,
+}
+
+impl SrcMatchAckAllPendingReq {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        Ok(SrcMatchAckAllPendingReq { status })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.encode_into(&mut buffer);
+        buffer
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        self.status.encode_into(buffer);
+    }
+
+    pub fn into_mt_frame(self) -> MTFrame {
+        MTFrame {
+            header: MTHeader {
+                length: 0x01,
+                command: CommandCode {
+                    is_extended: false,
+                    cmd_type: CommandType::SRSP,
+                    subsystem: MTSubsystem::MAC,
+                    id: MACCommandId::SrcMatchAckAllPendingReq as u8,
                 },
             },
             extended_header: None,
@@ -1,132 +1,307 @@
+#[cfg(feature = "app")]
+pub mod app;
+pub mod length;
+#[cfg(feature = "mac")]
 pub mod mac;
 pub mod rpc;
+#[cfg(feature = "sys")]
 pub mod sys;
+#[cfg(feature = "util")]
 pub mod util;
 
 use crate::error::Error;
+use crate::frame::{CommandCode, MTFrame};
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
 use crate::types::{CommandType, MTSubsystem};
+use alloc::vec::Vec;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(non_camel_case_types)]
 pub enum MTFramePayload {
     // MAC
+    #[cfg(feature = "mac")]
     MAC_DataCnf_AREQ(mac::areq::DataCnf),
+    #[cfg(feature = "mac")]
     MAC_DataInd_AREQ(mac::areq::DataInd),
+    #[cfg(feature = "mac")]
     MAC_PurgeCnf_AREQ(mac::areq::PurgeCnf),
+    #[cfg(feature = "wisun-ie")]
     MAC_WSAsyncInd_AREQ(mac::areq::WSAsyncInd),
+    #[cfg(feature = "mac")]
     MAC_SyncLossInd_AREQ(mac::areq::SyncLossInd),
+    #[cfg(feature = "mac")]
     MAC_AssociateInd_AREQ(mac::areq::AssociateInd),
+    #[cfg(feature = "mac")]
     MAC_AssociateCnf_AREQ(mac::areq::AssociateCnf),
+    #[cfg(feature = "mac")]
     MAC_BeaconNotifyInd_AREQ(mac::areq::BeaconNotifyInd),
+    #[cfg(feature = "mac")]
     MAC_DisassociateInd_AREQ(mac::areq::DisassociateInd),
+    #[cfg(feature = "mac")]
     MAC_DisassociateCnf_AREQ(mac::areq::DisassociateCnf),
+    #[cfg(feature = "mac")]
     MAC_OrphanInd_AREQ(mac::areq::OrphanInd),
+    #[cfg(feature = "mac")]
     MAC_PollCnf_AREQ(mac::areq::PollCnf),
+    #[cfg(feature = "mac")]
     MAC_PollInd_AREQ(mac::areq::PollInd),
+    #[cfg(feature = "mac")]
     MAC_ScanCnf_AREQ(mac::areq::ScanCnf),
+    #[cfg(feature = "mac")]
     MAC_CommStatusInd_AREQ(mac::areq::CommStatusInd),
+    #[cfg(feature = "mac")]
     MAC_StartCnf_AREQ(mac::areq::StartCnf),
+    #[cfg(feature = "wisun-ie")]
     MAC_WSAsyncCnf_AREQ(mac::areq::WSAsyncCnf),
 
+    #[cfg(feature = "mac")]
     MAC_Init_SREQ(mac::sreq::Init),
+    #[cfg(feature = "mac")]
     MAC_DataReq_SREQ(mac::sreq::DataReq),
+    #[cfg(feature = "mac")]
     MAC_PurgeReq_SREQ(mac::sreq::PurgeReq),
+    #[cfg(feature = "mac")]
     MAC_AssociateReq_SREQ(mac::sreq::AssociateReq),
+    #[cfg(feature = "mac")]
     MAC_AssociateRsp_SREQ(mac::sreq::AssociateRsp),
+    #[cfg(feature = "mac")]
     MAC_DisassociateReq_SREQ(mac::sreq::DisassociateReq),
+    #[cfg(feature = "mac")]
     MAC_GetReq_SREQ(mac::sreq::GetReq),
+    #[cfg(feature = "mac")]
     MAC_SetReq_SREQ(mac::sreq::SetReq),
+    #[cfg(feature = "security-tables")]
     MAC_SecurityGetReq_SREQ(mac::sreq::SecurityGetReq),
+    #[cfg(feature = "security-tables")]
     MAC_SecuritySetReq_SREQ(mac::sreq::SecuritySetReq),
+    #[cfg(feature = "mac")]
     MAC_UpdatePANIdReq_SREQ(mac::sreq::UpdatePANIdReq),
+    #[cfg(feature = "security-tables")]
     MAC_AddDeviceReq_SREQ(mac::sreq::AddDeviceReq),
+    #[cfg(feature = "security-tables")]
     MAC_DeleteDeviceReq_SREQ(mac::sreq::DeleteDeviceReq),
+    #[cfg(feature = "security-tables")]
     MAC_DeleteAllDevicesReq_SREQ(mac::sreq::DeleteAllDevicesReq),
+    #[cfg(feature = "security-tables")]
     MAC_DeleteKeyReq_SREQ(mac::sreq::DeleteKeyReq),
+    #[cfg(feature = "security-tables")]
     MAC_ReadKeyReq_SREQ(mac::sreq::ReadKeyReq),
+    #[cfg(feature = "security-tables")]
     MAC_WriteKeyReq_SREQ(mac::sreq::WriteKeyReq),
+    #[cfg(feature = "mac")]
     MAC_OrphanRsp_SREQ(mac::sreq::OrphanRsp),
+    #[cfg(feature = "mac")]
     MAC_PollReq_SREQ(mac::sreq::PollReq),
+    #[cfg(feature = "mac")]
     MAC_ResetReq_SREQ(mac::sreq::ResetReq),
+    #[cfg(feature = "mac")]
     MAC_ScanReq_SREQ(mac::sreq::ScanReq),
+    #[cfg(feature = "mac")]
     MAC_StartReq_SREQ(mac::sreq::StartReq),
+    #[cfg(feature = "mac")]
     MAC_SyncReq_SREQ(mac::sreq::SyncReq),
+    #[cfg(feature = "mac")]
     MAC_SetRxGainReq_SREQ(mac::sreq::SetRxGainReq),
+    #[cfg(feature = "wisun-ie")]
     MAC_WSAsyncReq_SREQ(mac::sreq::WSAsyncReq),
+    #[cfg(feature = "fh")]
     MAC_FHEnableReq_SREQ(mac::sreq::FHEnableReq),
+    #[cfg(feature = "fh")]
     MAC_FHStartReq_SREQ(mac::sreq::FHStartReq),
+    #[cfg(feature = "fh")]
     MAC_FHGetReq_SREQ(mac::sreq::FHGetReq),
+    #[cfg(feature = "fh")]
     MAC_FHSetReq_SREQ(mac::sreq::FHSetReq),
+    #[cfg(feature = "mac")]
+    MAC_SrcMatchEnableReq_SREQ(mac::sreq::SrcMatchEnableReq),
+    #[cfg(feature = "mac")]
+    MAC_SrcMatchAddEntryReq_SREQ(mac::sreq::SrcMatchAddEntryReq),
+    #[cfg(feature = "mac")]
+    MAC_SrcMatchDeleteEntryReq_SREQ(mac::sreq::SrcMatchDeleteEntryReq),
+    #[cfg(feature = "mac")]
+    MAC_SrcMatchAckAllPendingReq_SREQ(mac::sreq::SrcMatchAckAllPendingReq),
 
+    #[cfg(feature = "mac")]
     MAC_Init_SRSP(mac::srsp::Init),
+    #[cfg(feature = "mac")]
     MAC_DataReq_SRSP(mac::srsp::DataReq),
+    #[cfg(feature = "mac")]
     MAC_PurgeReq_SRSP(mac::srsp::PurgeReq),
+    #[cfg(feature = "mac")]
     MAC_AssociateReq_SRSP(mac::srsp::AssociateReq),
+    #[cfg(feature = "mac")]
     MAC_AssociateRsp_SRSP(mac::srsp::AssociateRsp),
+    #[cfg(feature = "mac")]
     MAC_DisassociateReq_SRSP(mac::srsp::DisassociateReq),
+    #[cfg(feature = "mac")]
     MAC_GetReq_SRSP(mac::srsp::GetReq),
+    #[cfg(feature = "mac")]
     MAC_SetReq_SRSP(mac::srsp::SetReq),
+    #[cfg(feature = "security-tables")]
     MAC_SecurityGetReq_SRSP(mac::srsp::SecurityGetReq),
+    #[cfg(feature = "security-tables")]
     MAC_SecuritySetReq_SRSP(mac::srsp::SecuritySetReq),
+    #[cfg(feature = "mac")]
     MAC_UpdatePANIdReq_SRSP(mac::srsp::UpdatePANIdReq),
+    #[cfg(feature = "security-tables")]
     MAC_AddDeviceReq_SRSP(mac::srsp::AddDeviceReq),
+    #[cfg(feature = "security-tables")]
     MAC_DeleteDeviceReq_SRSP(mac::srsp::DeleteDeviceReq),
+    #[cfg(feature = "security-tables")]
     MAC_DeleteAllDevicesReq_SRSP(mac::srsp::DeleteAllDevicesReq),
+    #[cfg(feature = "security-tables")]
     MAC_DeleteKeyReq_SRSP(mac::srsp::DeleteKeyReq),
+    #[cfg(feature = "security-tables")]
     MAC_ReadKeyReq_SRSP(mac::srsp::ReadKeyReq),
+    #[cfg(feature = "security-tables")]
     MAC_WriteKeyReq_SRSP(mac::srsp::WriteKeyReq),
+    #[cfg(feature = "mac")]
     MAC_OrphanRsp_SRSP(mac::srsp::OrphanRsp),
+    #[cfg(feature = "mac")]
     MAC_PollReq_SRSP(mac::srsp::PollReq),
+    #[cfg(feature = "mac")]
     MAC_ResetReq_SRSP(mac::srsp::ResetReq),
+    #[cfg(feature = "mac")]
     MAC_ScanReq_SRSP(mac::srsp::ScanReq),
+    #[cfg(feature = "mac")]
     MAC_StartReq_SRSP(mac::srsp::StartReq),
+    #[cfg(feature = "mac")]
     MAC_SyncReq_SRSP(mac::srsp::SyncReq),
+    #[cfg(feature = "mac")]
     MAC_SetRxGainReq_SRSP(mac::srsp::SetRxGainReq),
+    #[cfg(feature = "wisun-ie")]
     MAC_WSAsyncReq_SRSP(mac::srsp::WSAsyncReq),
+    #[cfg(feature = "fh")]
     MAC_FHEnableReq_SRSP(mac::srsp::FHEnableReq),
+    #[cfg(feature = "fh")]
     MAC_FHStartReq_SRSP(mac::srsp::FHStartReq),
+    #[cfg(feature = "fh")]
     MAC_FHGetReq_SRSP(mac::srsp::FHGetReq),
+    #[cfg(feature = "fh")]
     MAC_FHSetReq_SRSP(mac::srsp::FHSetReq),
+    #[cfg(feature = "mac")]
+    MAC_SrcMatchEnableReq_SRSP(mac::srsp::SrcMatchEnableReq),
+    #[cfg(feature = "mac")]
+    MAC_SrcMatchAddEntryReq_SRSP(mac::srsp::SrcMatchAddEntryReq),
+    #[cfg(feature = "mac")]
+    MAC_SrcMatchDeleteEntryReq_SRSP(mac::srsp::SrcMatchDeleteEntryReq),
+    #[cfg(feature = "mac")]
+    MAC_SrcMatchAckAllPendingReq_SRSP(mac::srsp::SrcMatchAckAllPendingReq),
 
     // RPC
     RPC_MTCommandError(rpc::MTCommandError),
 
     // SYS
+    #[cfg(feature = "sys")]
     SYS_ResetReq_AREQ(sys::areq::ResetReq),
+    #[cfg(feature = "sys")]
     SYS_ResetInd_AREQ(sys::areq::ResetInd),
 
+    #[cfg(feature = "sys")]
     SYS_PingReq_SREQ(sys::sreq::PingReq),
+    #[cfg(feature = "sys")]
     SYS_VersionReq_SREQ(sys::sreq::VersionReq),
+    #[cfg(feature = "sys")]
     SYS_NVCreateReq_SREQ(sys::sreq::NVCreateReq),
+    #[cfg(feature = "sys")]
     SYS_NVDeleteReq_SREQ(sys::sreq::NVDeleteReq),
+    #[cfg(feature = "sys")]
     SYS_NVLengthReq_SREQ(sys::sreq::NVLengthReq),
+    #[cfg(feature = "sys")]
     SYS_NVReadReq_SREQ(sys::sreq::NVReadReq),
+    #[cfg(feature = "sys")]
     SYS_NVWriteReq_SREQ(sys::sreq::NVWriteReq),
+    #[cfg(feature = "sys")]
     SYS_NVUpdateReq_SREQ(sys::sreq::NVUpdateReq),
+    #[cfg(feature = "sys")]
     SYS_NVCompactReq_SREQ(sys::sreq::NVCompactReq),
 
+    #[cfg(feature = "sys")]
     SYS_PingReq_SRSP(sys::srsp::PingReq),
+    #[cfg(feature = "sys")]
     SYS_VersionReq_SRSP(sys::srsp::VersionReq),
+    #[cfg(feature = "sys")]
     SYS_NVCreateReq_SRSP(sys::srsp::NVCreateReq),
+    #[cfg(feature = "sys")]
     SYS_NVDeleteReq_SRSP(sys::srsp::NVDeleteReq),
+    #[cfg(feature = "sys")]
     SYS_NVLengthReq_SRSP(sys::srsp::NVLengthReq),
+    #[cfg(feature = "sys")]
     SYS_NVReadReq_SRSP(sys::srsp::NVReadReq),
+    #[cfg(feature = "sys")]
     SYS_NVWriteReq_SRSP(sys::srsp::NVWriteReq),
+    #[cfg(feature = "sys")]
     SYS_NVUpdateReq_SRSP(sys::srsp::NVUpdateReq),
+    #[cfg(feature = "sys")]
     SYS_NVCompactReq_SRSP(sys::srsp::NVCompactReq),
 
     // UTIL
+    #[cfg(feature = "util")]
     UTIL_Loopback_AREQ(util::areq::Loopback),
 
+    #[cfg(feature = "util")]
     UTIL_CallbackSubCmd_SREQ(util::sreq::CallbackSubCmd),
+    #[cfg(feature = "util")]
     UTIL_GetExtAddr_SREQ(util::sreq::GetExtAddr),
+    #[cfg(feature = "util")]
     UTIL_Loopback_SREQ(util::sreq::Loopback),
+    #[cfg(feature = "util")]
     UTIL_Random_SREQ(util::sreq::Random),
 
+    #[cfg(feature = "util")]
     UTIL_CallbackSubCmd_SRSP(util::srsp::CallbackSubCmd),
+    #[cfg(feature = "util")]
     UTIL_GetExtAddr_SRSP(util::srsp::GetExtAddr),
+    #[cfg(feature = "util")]
     UTIL_Loopback_SRSP(util::srsp::Loopback),
+    #[cfg(feature = "util")]
     UTIL_Random_SRSP(util::srsp::Random),
+
+    // APP
+    #[cfg(feature = "app")]
+    APP_AppMsgInd_AREQ(app::areq::AppMsgInd),
+    #[cfg(feature = "app")]
+    APP_AppMsgReq_SREQ(app::sreq::AppMsgReq),
+    #[cfg(feature = "app")]
+    APP_AppMsgReq_SRSP(app::srsp::AppMsgReq),
+}
+
+/// Decodes an MT frame, without the UART transport bytes, down to its
+/// payload. Any input, however malformed, gives an error instead of a panic,
+/// which makes it the entry point for fuzzing the decoders.
+pub fn decode_any(bytes: &[u8]) -> Result<MTFramePayload, Error> {
+    let frame = MTFrame::try_decode(&mut Reader::new(bytes))?;
+    MTFramePayload::try_from_frame(&frame)
+}
+
+// Run before the decoders, which on their own would read whatever fields fit.
+// Left out of `try_decode_unaudited` so `debug::annotate` can still decode
+// prefixes field by field.
+fn check_length(
+    subsystem: &MTSubsystem,
+    cmd_type: &CommandType,
+    id: u8,
+    buffer: &[u8],
+    options: DecodeOptions,
+) -> Result<(), Error> {
+    match length::expected_length(*subsystem, *cmd_type, id) {
+        Some(expected) if !expected.accepts_with(buffer.len(), options) => {
+            Err(Error::LengthMismatch {
+                command: CommandCode {
+                    is_extended: false,
+                    cmd_type: *cmd_type,
+                    subsystem: *subsystem,
+                    id,
+                },
+                expected,
+                actual: buffer.len(),
+            })
+        }
+        _ => Ok(()),
+    }
 }
 
 impl MTFramePayload {
@@ -135,12 +310,79 @@ impl MTFramePayload {
         cmd_type: &CommandType,
         id: u8,
         buffer: &[u8],
+    ) -> Result<Self, Error> {
+        Self::try_decode_with(subsystem, cmd_type, id, buffer, DecodeOptions::default())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(buffer, options), fields(length = buffer.len()))
+    )]
+    pub fn try_decode_with(
+        subsystem: &MTSubsystem,
+        cmd_type: &CommandType,
+        id: u8,
+        buffer: &[u8],
+        options: DecodeOptions,
+    ) -> Result<Self, Error> {
+        let payload = check_length(subsystem, cmd_type, id, buffer, options)
+            .and_then(|_| Self::try_decode_unaudited(subsystem, cmd_type, id, buffer, options))
+            .map_err(|error| {
+                let error = error.with_command(CommandCode {
+                    is_extended: false,
+                    cmd_type: *cmd_type,
+                    subsystem: *subsystem,
+                    id,
+                });
+
+                #[cfg(feature = "tracing")]
+                tracing::debug!(%error, "decode failed: {:02x?}", buffer);
+
+                error
+            })?;
+
+        #[cfg(feature = "audit")]
+        crate::audit::audit(subsystem, cmd_type, id, buffer, &payload);
+
+        Ok(payload)
+    }
+
+    /// Decodes the payload of a frame using the command code in its header.
+    pub fn try_from_frame(frame: &MTFrame) -> Result<Self, Error> {
+        Self::try_from_frame_with(frame, DecodeOptions::default())
+    }
+
+    pub fn try_from_frame_with(frame: &MTFrame, options: DecodeOptions) -> Result<Self, Error> {
+        let command = &frame.header.command;
+        Self::try_decode_with(
+            &command.subsystem,
+            &command.cmd_type,
+            command.id,
+            &frame.payload,
+            options,
+        )
+    }
+
+    pub(crate) fn try_decode_unaudited(
+        subsystem: &MTSubsystem,
+        cmd_type: &CommandType,
+        id: u8,
+        buffer: &[u8],
+        options: DecodeOptions,
     ) -> Result<Self, Error> {
         match subsystem {
-            MTSubsystem::MAC => mac::try_decode(cmd_type, id, buffer),
-            MTSubsystem::RPC => rpc::try_decode(cmd_type, id, buffer),
-            MTSubsystem::SYS => sys::try_decode(cmd_type, id, buffer),
-            MTSubsystem::UTIL => util::try_decode(cmd_type, id, buffer),
+            #[cfg(feature = "mac")]
+            MTSubsystem::MAC => mac::try_decode_with(cmd_type, id, buffer, options),
+            MTSubsystem::RPC => rpc::try_decode_with(cmd_type, id, buffer, options),
+            #[cfg(feature = "sys")]
+            MTSubsystem::SYS => sys::try_decode_with(cmd_type, id, buffer, options),
+            #[cfg(feature = "util")]
+            MTSubsystem::UTIL => util::try_decode_with(cmd_type, id, buffer, options),
+            #[cfg(feature = "app")]
+            MTSubsystem::APP => app::try_decode_with(cmd_type, id, buffer, options),
+            // Subsystems left out of the build.
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::NotImplemented),
         }
     }
 
@@ -148,123 +390,504 @@ impl MTFramePayload {
         use MTFramePayload::*;
         match self {
             // MAC
+            #[cfg(feature = "mac")]
             MAC_DataCnf_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_DataInd_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_PurgeCnf_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "wisun-ie")]
             MAC_WSAsyncInd_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_SyncLossInd_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_AssociateInd_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_AssociateCnf_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_BeaconNotifyInd_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_DisassociateInd_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_DisassociateCnf_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_OrphanInd_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_PollCnf_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_PollInd_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_ScanCnf_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_CommStatusInd_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_StartCnf_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "wisun-ie")]
             MAC_WSAsyncCnf_AREQ(x) => x.encode_into(buffer),
 
+            #[cfg(feature = "mac")]
             MAC_Init_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_DataReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_PurgeReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_AssociateReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_AssociateRsp_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_DisassociateReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_GetReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_SetReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_SecurityGetReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_SecuritySetReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_UpdatePANIdReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_AddDeviceReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_DeleteDeviceReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_DeleteAllDevicesReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_DeleteKeyReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_ReadKeyReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_WriteKeyReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_OrphanRsp_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_PollReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_ResetReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_ScanReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_StartReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_SyncReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_SetRxGainReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "wisun-ie")]
             MAC_WSAsyncReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "fh")]
             MAC_FHEnableReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "fh")]
             MAC_FHStartReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "fh")]
             MAC_FHGetReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "fh")]
             MAC_FHSetReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchEnableReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchAddEntryReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchDeleteEntryReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchAckAllPendingReq_SREQ(x) => x.encode_into(buffer),
 
+            #[cfg(feature = "mac")]
             MAC_Init_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_DataReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_PurgeReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_AssociateReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_AssociateRsp_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_DisassociateReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_GetReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_SetReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_SecurityGetReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_SecuritySetReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_UpdatePANIdReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_AddDeviceReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_DeleteDeviceReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_DeleteAllDevicesReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_DeleteKeyReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_ReadKeyReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "security-tables")]
             MAC_WriteKeyReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_OrphanRsp_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_PollReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_ResetReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_ScanReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_StartReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_SyncReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
             MAC_SetRxGainReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "wisun-ie")]
             MAC_WSAsyncReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "fh")]
             MAC_FHEnableReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "fh")]
             MAC_FHStartReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "fh")]
             MAC_FHGetReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "fh")]
             MAC_FHSetReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchEnableReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchAddEntryReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchDeleteEntryReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchAckAllPendingReq_SRSP(x) => x.encode_into(buffer),
 
             // RPC
             RPC_MTCommandError(x) => x.encode_into(buffer),
 
             // SYS
+            #[cfg(feature = "sys")]
             SYS_ResetReq_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_ResetInd_AREQ(x) => x.encode_into(buffer),
 
+            #[cfg(feature = "sys")]
             SYS_PingReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_VersionReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVCreateReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVDeleteReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVLengthReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVReadReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVWriteReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVUpdateReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVCompactReq_SREQ(x) => x.encode_into(buffer),
 
+            #[cfg(feature = "sys")]
             SYS_PingReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_VersionReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVCreateReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVDeleteReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVLengthReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVReadReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVWriteReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVUpdateReq_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "sys")]
             SYS_NVCompactReq_SRSP(x) => x.encode_into(buffer),
 
             // UTIL
+            #[cfg(feature = "util")]
             UTIL_Loopback_AREQ(x) => x.encode_into(buffer),
 
+            #[cfg(feature = "util")]
             UTIL_CallbackSubCmd_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "util")]
             UTIL_GetExtAddr_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "util")]
             UTIL_Loopback_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "util")]
             UTIL_Random_SREQ(x) => x.encode_into(buffer),
 
+            #[cfg(feature = "util")]
             UTIL_CallbackSubCmd_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "util")]
             UTIL_GetExtAddr_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "util")]
             UTIL_Loopback_SRSP(x) => x.encode_into(buffer),
+            #[cfg(feature = "util")]
             UTIL_Random_SRSP(x) => x.encode_into(buffer),
+
+            // APP
+            #[cfg(feature = "app")]
+            APP_AppMsgInd_AREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "app")]
+            APP_AppMsgReq_SREQ(x) => x.encode_into(buffer),
+            #[cfg(feature = "app")]
+            APP_AppMsgReq_SRSP(x) => x.encode_into(buffer),
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn into_mt_frame(self) -> MTFrame {
+        use MTFramePayload::*;
+        match self {
+            // MAC
+            #[cfg(feature = "mac")]
+            MAC_DataCnf_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_DataInd_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_PurgeCnf_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "wisun-ie")]
+            MAC_WSAsyncInd_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SyncLossInd_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_AssociateInd_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_AssociateCnf_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_BeaconNotifyInd_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_DisassociateInd_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_DisassociateCnf_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_OrphanInd_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_PollCnf_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_PollInd_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_ScanCnf_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_CommStatusInd_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_StartCnf_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "wisun-ie")]
+            MAC_WSAsyncCnf_AREQ(x) => x.into_mt_frame(),
+
+            #[cfg(feature = "mac")]
+            MAC_Init_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_DataReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_PurgeReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_AssociateReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_AssociateRsp_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_DisassociateReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_GetReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SetReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_SecurityGetReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_SecuritySetReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_UpdatePANIdReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_AddDeviceReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_DeleteDeviceReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_DeleteAllDevicesReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_DeleteKeyReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_ReadKeyReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_WriteKeyReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_OrphanRsp_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_PollReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_ResetReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_ScanReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_StartReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SyncReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SetRxGainReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "wisun-ie")]
+            MAC_WSAsyncReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "fh")]
+            MAC_FHEnableReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "fh")]
+            MAC_FHStartReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "fh")]
+            MAC_FHGetReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "fh")]
+            MAC_FHSetReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchEnableReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchAddEntryReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchDeleteEntryReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchAckAllPendingReq_SREQ(x) => x.into_mt_frame(),
+
+            #[cfg(feature = "mac")]
+            MAC_Init_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_DataReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_PurgeReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_AssociateReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_AssociateRsp_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_DisassociateReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_GetReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SetReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_SecurityGetReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_SecuritySetReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_UpdatePANIdReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_AddDeviceReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_DeleteDeviceReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_DeleteAllDevicesReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_DeleteKeyReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_ReadKeyReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "security-tables")]
+            MAC_WriteKeyReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_OrphanRsp_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_PollReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_ResetReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_ScanReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_StartReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SyncReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SetRxGainReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "wisun-ie")]
+            MAC_WSAsyncReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "fh")]
+            MAC_FHEnableReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "fh")]
+            MAC_FHStartReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "fh")]
+            MAC_FHGetReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "fh")]
+            MAC_FHSetReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchEnableReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchAddEntryReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchDeleteEntryReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "mac")]
+            MAC_SrcMatchAckAllPendingReq_SRSP(x) => x.into_mt_frame(),
+
+            // RPC
+            RPC_MTCommandError(x) => x.into_mt_frame(),
+
+            // SYS
+            #[cfg(feature = "sys")]
+            SYS_ResetReq_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_ResetInd_AREQ(x) => x.into_mt_frame(),
+
+            #[cfg(feature = "sys")]
+            SYS_PingReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_VersionReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVCreateReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVDeleteReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVLengthReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVReadReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVWriteReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVUpdateReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVCompactReq_SREQ(x) => x.into_mt_frame(),
+
+            #[cfg(feature = "sys")]
+            SYS_PingReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_VersionReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVCreateReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVDeleteReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVLengthReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVReadReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVWriteReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVUpdateReq_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "sys")]
+            SYS_NVCompactReq_SRSP(x) => x.into_mt_frame(),
+
+            // UTIL
+            #[cfg(feature = "util")]
+            UTIL_Loopback_AREQ(x) => x.into_mt_frame(),
+
+            #[cfg(feature = "util")]
+            UTIL_CallbackSubCmd_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "util")]
+            UTIL_GetExtAddr_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "util")]
+            UTIL_Loopback_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "util")]
+            UTIL_Random_SREQ(x) => x.into_mt_frame(),
+
+            #[cfg(feature = "util")]
+            UTIL_CallbackSubCmd_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "util")]
+            UTIL_GetExtAddr_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "util")]
+            UTIL_Loopback_SRSP(x) => x.into_mt_frame(),
+            #[cfg(feature = "util")]
+            UTIL_Random_SRSP(x) => x.into_mt_frame(),
+
+            // APP
+            #[cfg(feature = "app")]
+            APP_AppMsgInd_AREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "app")]
+            APP_AppMsgReq_SREQ(x) => x.into_mt_frame(),
+            #[cfg(feature = "app")]
+            APP_AppMsgReq_SRSP(x) => x.into_mt_frame(),
         }
     }
 }
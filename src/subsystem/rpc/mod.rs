@@ -1,11 +1,22 @@
 use crate::error::Error;
 use crate::frame::{CommandCode, MTFrame, MTHeader};
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
 use crate::subsystem::MTFramePayload;
 use crate::types::{CommandType, ErrorCode, MTSubsystem, RPCCommandId};
+use alloc::vec::Vec;
 use num_traits::FromPrimitive;
-use std::io::Cursor;
 
 pub fn try_decode(cmd_type: &CommandType, id: u8, buffer: &[u8]) -> Result<MTFramePayload, Error> {
+    try_decode_with(cmd_type, id, buffer, DecodeOptions::default())
+}
+
+pub fn try_decode_with(
+    cmd_type: &CommandType,
+    id: u8,
+    buffer: &[u8],
+    options: DecodeOptions,
+) -> Result<MTFramePayload, Error> {
     use MTFramePayload::*;
 
     let id = FromPrimitive::from_u8(id).ok_or(Error::InvalidCommandId(id))?;
@@ -16,13 +27,15 @@ pub fn try_decode(cmd_type: &CommandType, id: u8, buffer: &[u8]) -> Result<MTFra
         CommandType::AREQ => Err(Error::NotImplemented),
         CommandType::SRSP => match id {
             RPCCommandId::MTCommandError => {
-                MTCommandError::try_decode(buffer).map(|x| RPC_MTCommandError(x))
+                MTCommandError::try_decode_with(buffer, options).map(RPC_MTCommandError)
             }
         },
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MTCommandError {
     pub error_code: ErrorCode,
     pub command: CommandCode,
@@ -30,9 +43,13 @@ pub struct MTCommandError {
 
 impl MTCommandError {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let error_code = ErrorCode::try_decode(&mut cursor)?;
-        let command = CommandCode::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let error_code = cursor.field("error_code", ErrorCode::try_decode)?;
+        let command = cursor.field("command", CommandCode::try_decode)?;
         Ok(MTCommandError {
             error_code,
             command,
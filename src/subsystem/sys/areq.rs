@@ -1,18 +1,26 @@
 use crate::error::Error;
 use crate::frame::{CommandCode, MTFrame, MTHeader};
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
 use crate::types::*;
-use bytes::{Buf, BufMut};
-use std::io::Cursor;
+use crate::writer::BufMut;
+use alloc::vec::Vec;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResetReq {
     pub reset_type: ResetType,
 }
 
 impl ResetReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let reset_type = ResetType::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let reset_type = cursor.field("reset_type", ResetType::try_decode)?;
         Ok(ResetReq { reset_type })
     }
 
@@ -43,7 +51,9 @@ impl ResetReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResetInd {
     pub reason: ResetReason,
     pub transport: TransportProtocolRevision,
@@ -55,13 +65,17 @@ pub struct ResetInd {
 
 impl ResetInd {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let reason = ResetReason::try_decode(&mut cursor)?;
-        let transport = TransportProtocolRevision::try_decode(&mut cursor)?;
-        let product = ProductIdCode::try_decode(&mut cursor)?;
-        let major = cursor.get_u8();
-        let minor = cursor.get_u8();
-        let maint = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let reason = cursor.field("reason", ResetReason::try_decode)?;
+        let transport = cursor.field("transport", TransportProtocolRevision::try_decode)?;
+        let product = cursor.field("product", ProductIdCode::try_decode)?;
+        let major = cursor.field("major", Reader::read_u8)?;
+        let minor = cursor.field("minor", Reader::read_u8)?;
+        let maint = cursor.field("maint", Reader::read_u8)?;
         Ok(ResetInd {
             reason,
             transport,
@@ -1,15 +1,22 @@
 use crate::error::Error;
 use crate::frame::{CommandCode, MTFrame, MTHeader};
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
 use crate::types::{CommandType, MTSubsystem, SYSCommandId};
-use bytes::{Buf, BufMut};
-use std::io::Cursor;
-use std::io::Read;
+use crate::writer::BufMut;
+use alloc::vec::Vec;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PingReq {}
 
 impl PingReq {
-    pub fn try_decode(_: &[u8]) -> Result<Self, Error> {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(_: &[u8], _: DecodeOptions) -> Result<Self, Error> {
         Ok(PingReq {})
     }
 
@@ -38,11 +45,17 @@ impl PingReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VersionReq {}
 
 impl VersionReq {
-    pub fn try_decode(_: &[u8]) -> Result<Self, Error> {
+    pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(_: &[u8], _: DecodeOptions) -> Result<Self, Error> {
         Ok(VersionReq {})
     }
 
@@ -71,7 +84,9 @@ impl VersionReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVCreateReq {
     pub sys_id: u8,
     pub item_id: u16,
@@ -81,11 +96,15 @@ pub struct NVCreateReq {
 
 impl NVCreateReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let sys_id = cursor.get_u8();
-        let item_id = cursor.get_u16_le();
-        let sub_id = cursor.get_u16_le();
-        let length = cursor.get_u32_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let sys_id = cursor.field("sys_id", Reader::read_u8)?;
+        let item_id = cursor.field("item_id", Reader::read_u16_le)?;
+        let sub_id = cursor.field("sub_id", Reader::read_u16_le)?;
+        let length = cursor.field("length", Reader::read_u32_le)?;
         Ok(NVCreateReq {
             sys_id,
             item_id,
@@ -124,7 +143,9 @@ impl NVCreateReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVDeleteReq {
     pub sys_id: u8,
     pub item_id: u16,
@@ -133,10 +154,14 @@ pub struct NVDeleteReq {
 
 impl NVDeleteReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let sys_id = cursor.get_u8();
-        let item_id = cursor.get_u16_le();
-        let sub_id = cursor.get_u16_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let sys_id = cursor.field("sys_id", Reader::read_u8)?;
+        let item_id = cursor.field("item_id", Reader::read_u16_le)?;
+        let sub_id = cursor.field("sub_id", Reader::read_u16_le)?;
         Ok(NVDeleteReq {
             sys_id,
             item_id,
@@ -173,7 +198,9 @@ impl NVDeleteReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVLengthReq {
     pub sys_id: u8,
     pub item_id: u16,
@@ -182,10 +209,14 @@ pub struct NVLengthReq {
 
 impl NVLengthReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let sys_id = cursor.get_u8();
-        let item_id = cursor.get_u16_le();
-        let sub_id = cursor.get_u16_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let sys_id = cursor.field("sys_id", Reader::read_u8)?;
+        let item_id = cursor.field("item_id", Reader::read_u16_le)?;
+        let sub_id = cursor.field("sub_id", Reader::read_u16_le)?;
         Ok(NVLengthReq {
             sys_id,
             item_id,
@@ -222,7 +253,9 @@ impl NVLengthReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVReadReq {
     pub sys_id: u8,
     pub item_id: u16,
@@ -233,12 +266,16 @@ pub struct NVReadReq {
 
 impl NVReadReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let sys_id = cursor.get_u8();
-        let item_id = cursor.get_u16_le();
-        let sub_id = cursor.get_u16_le();
-        let offset = cursor.get_u16_le();
-        let length = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let sys_id = cursor.field("sys_id", Reader::read_u8)?;
+        let item_id = cursor.field("item_id", Reader::read_u16_le)?;
+        let sub_id = cursor.field("sub_id", Reader::read_u16_le)?;
+        let offset = cursor.field("offset", Reader::read_u16_le)?;
+        let length = cursor.field("length", Reader::read_u8)?;
         Ok(NVReadReq {
             sys_id,
             item_id,
@@ -279,7 +316,9 @@ impl NVReadReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVWriteReq {
     pub sys_id: u8,
     pub item_id: u16,
@@ -291,17 +330,19 @@ pub struct NVWriteReq {
 
 impl NVWriteReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let sys_id = cursor.get_u8();
-        let item_id = cursor.get_u16_le();
-        let sub_id = cursor.get_u16_le();
-        let offset = cursor.get_u16_le();
-        let length = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let sys_id = cursor.field("sys_id", Reader::read_u8)?;
+        let item_id = cursor.field("item_id", Reader::read_u16_le)?;
+        let sub_id = cursor.field("sub_id", Reader::read_u16_le)?;
+        let offset = cursor.field("offset", Reader::read_u16_le)?;
+        let length = cursor.field("length", Reader::read_u8)?;
 
         let mut data = Vec::new();
-        cursor
-            .read_to_end(&mut data)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("data", |cursor| cursor.read_to_end(&mut data))?;
 
         Ok(NVWriteReq {
             sys_id,
@@ -329,23 +370,21 @@ impl NVWriteReq {
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
-        MTFrame {
-            header: MTHeader {
-                length: 0x08 + self.data.len() as u8,
-                command: CommandCode {
-                    is_extended: false,
-                    cmd_type: CommandType::SREQ,
-                    subsystem: MTSubsystem::SYS,
-                    id: SYSCommandId::NVWriteReq as u8,
-                },
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::SREQ,
+                subsystem: MTSubsystem::SYS,
+                id: SYSCommandId::NVWriteReq as u8,
             },
-            extended_header: None,
-            payload: self.encode(),
-        }
+            self.encode(),
+        )
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVUpdateReq {
     pub sys_id: u8,
     pub item_id: u16,
@@ -356,16 +395,18 @@ pub struct NVUpdateReq {
 
 impl NVUpdateReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let sys_id = cursor.get_u8();
-        let item_id = cursor.get_u16_le();
-        let sub_id = cursor.get_u16_le();
-        let length = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let sys_id = cursor.field("sys_id", Reader::read_u8)?;
+        let item_id = cursor.field("item_id", Reader::read_u16_le)?;
+        let sub_id = cursor.field("sub_id", Reader::read_u16_le)?;
+        let length = cursor.field("length", Reader::read_u8)?;
 
         let mut data = Vec::new();
-        cursor
-            .read_to_end(&mut data)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("data", |cursor| cursor.read_to_end(&mut data))?;
 
         Ok(NVUpdateReq {
             sys_id,
@@ -391,31 +432,33 @@ impl NVUpdateReq {
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
-        MTFrame {
-            header: MTHeader {
-                length: 0x06 + self.data.len() as u8,
-                command: CommandCode {
-                    is_extended: false,
-                    cmd_type: CommandType::SREQ,
-                    subsystem: MTSubsystem::SYS,
-                    id: SYSCommandId::NVUpdateReq as u8,
-                },
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::SREQ,
+                subsystem: MTSubsystem::SYS,
+                id: SYSCommandId::NVUpdateReq as u8,
             },
-            extended_header: None,
-            payload: self.encode(),
-        }
+            self.encode(),
+        )
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVCompactReq {
     pub threshold: u16,
 }
 
 impl NVCompactReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let threshold = cursor.get_u16_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let threshold = cursor.field("threshold", Reader::read_u16_le)?;
         Ok(NVCompactReq { threshold })
     }
 
@@ -1,19 +1,26 @@
 use crate::error::Error;
 use crate::frame::{CommandCode, MTFrame, MTHeader};
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
 use crate::types::*;
-use bytes::{Buf, BufMut};
-use std::io::Cursor;
-use std::io::Read;
+use crate::writer::BufMut;
+use alloc::vec::Vec;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PingReq {
     pub capabilities: u16,
 }
 
 impl PingReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let capabilities = cursor.get_u16_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let capabilities = cursor.field("capabilities", Reader::read_u16_le)?;
         Ok(PingReq { capabilities })
     }
 
@@ -30,7 +37,7 @@ impl PingReq {
     pub fn into_mt_frame(self) -> MTFrame {
         MTFrame {
             header: MTHeader {
-                length: 0x01,
+                length: 0x02,
                 command: CommandCode {
                     is_extended: false,
                     cmd_type: CommandType::SRSP,
@@ -44,7 +51,9 @@ impl PingReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VersionReq {
     pub transport: TransportProtocolRevision,
     pub product: ProductIdCode,
@@ -55,12 +64,16 @@ pub struct VersionReq {
 
 impl VersionReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let transport = TransportProtocolRevision::try_decode(&mut cursor)?;
-        let product = ProductIdCode::try_decode(&mut cursor)?;
-        let major = cursor.get_u8();
-        let minor = cursor.get_u8();
-        let maint = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let transport = cursor.field("transport", TransportProtocolRevision::try_decode)?;
+        let product = cursor.field("product", ProductIdCode::try_decode)?;
+        let major = cursor.field("major", Reader::read_u8)?;
+        let minor = cursor.field("minor", Reader::read_u8)?;
+        let maint = cursor.field("maint", Reader::read_u8)?;
         Ok(VersionReq {
             transport,
             product,
@@ -101,15 +114,21 @@ impl VersionReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVCreateReq {
     pub status: Status,
 }
 
 impl NVCreateReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(NVCreateReq { status })
     }
 
@@ -140,15 +159,21 @@ impl NVCreateReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVDeleteReq {
     pub status: Status,
 }
 
 impl NVDeleteReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(NVDeleteReq { status })
     }
 
@@ -179,15 +204,21 @@ impl NVDeleteReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVLengthReq {
     pub length: u32,
 }
 
 impl NVLengthReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let length = cursor.get_u32_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let length = cursor.field("length", Reader::read_u32_le)?;
         Ok(NVLengthReq { length })
     }
 
@@ -218,7 +249,9 @@ impl NVLengthReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVReadReq {
     pub status: Status,
     pub length: u8,
@@ -227,14 +260,16 @@ pub struct NVReadReq {
 
 impl NVReadReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
-        let length = cursor.get_u8();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
+        let length = cursor.field("length", Reader::read_u8)?;
 
         let mut data = Vec::new();
-        cursor
-            .read_to_end(&mut data)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("data", |cursor| cursor.read_to_end(&mut data))?;
 
         Ok(NVReadReq {
             status,
@@ -256,31 +291,33 @@ impl NVReadReq {
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
-        MTFrame {
-            header: MTHeader {
-                length: 0x02 + self.data.len() as u8,
-                command: CommandCode {
-                    is_extended: false,
-                    cmd_type: CommandType::SRSP,
-                    subsystem: MTSubsystem::SYS,
-                    id: SYSCommandId::NVReadReq as u8,
-                },
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::SRSP,
+                subsystem: MTSubsystem::SYS,
+                id: SYSCommandId::NVReadReq as u8,
             },
-            extended_header: None,
-            payload: self.encode(),
-        }
+            self.encode(),
+        )
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVWriteReq {
     pub status: Status,
 }
 
 impl NVWriteReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(NVWriteReq { status })
     }
 
@@ -311,15 +348,21 @@ impl NVWriteReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVUpdateReq {
     pub status: Status,
 }
 
 impl NVUpdateReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(NVUpdateReq { status })
     }
 
@@ -350,15 +393,21 @@ impl NVUpdateReq {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NVCompactReq {
     pub status: Status,
 }
 
 impl NVCompactReq {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let status = Status::try_decode(&mut cursor)?;
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let status = cursor.field("status", Status::try_decode)?;
         Ok(NVCompactReq { status })
     }
 
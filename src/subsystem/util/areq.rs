@@ -1,11 +1,14 @@
 use crate::error::Error;
-use crate::frame::{CommandCode, MTFrame, MTHeader};
+use crate::frame::{CommandCode, MTFrame};
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
 use crate::types::{CommandType, MTSubsystem, UTILCommandId};
-use bytes::{Buf, BufMut};
-use std::io::Cursor;
-use std::io::Read;
+use crate::writer::BufMut;
+use alloc::vec::Vec;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Loopback {
     pub repeats: u8,
     pub interval: u32,
@@ -14,14 +17,16 @@ pub struct Loopback {
 
 impl Loopback {
     pub fn try_decode(buffer: &[u8]) -> Result<Self, Error> {
-        let mut cursor = Cursor::new(buffer);
-        let repeats = cursor.get_u8();
-        let interval = cursor.get_u32_le();
+        Self::try_decode_with(buffer, DecodeOptions::default())
+    }
+
+    pub fn try_decode_with(buffer: &[u8], options: DecodeOptions) -> Result<Self, Error> {
+        let mut cursor = Reader::with_options(buffer, options);
+        let repeats = cursor.field("repeats", Reader::read_u8)?;
+        let interval = cursor.field("interval", Reader::read_u32_le)?;
 
         let mut data = Vec::new();
-        cursor
-            .read_to_end(&mut data)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.field("data", |cursor| cursor.read_to_end(&mut data))?;
 
         Ok(Loopback {
             repeats,
@@ -43,18 +48,14 @@ impl Loopback {
     }
 
     pub fn into_mt_frame(self) -> MTFrame {
-        MTFrame {
-            header: MTHeader {
-                length: 0x05 + self.data.len() as u8,
-                command: CommandCode {
-                    is_extended: false,
-                    cmd_type: CommandType::AREQ,
-                    subsystem: MTSubsystem::UTIL,
-                    id: UTILCommandId::Loopback as u8,
-                },
+        MTFrame::new(
+            CommandCode {
+                is_extended: false,
+                cmd_type: CommandType::AREQ,
+                subsystem: MTSubsystem::UTIL,
+                id: UTILCommandId::Loopback as u8,
             },
-            extended_header: None,
-            payload: self.encode(),
-        }
+            self.encode(),
+        )
     }
 }
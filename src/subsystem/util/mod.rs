@@ -3,11 +3,21 @@ pub mod sreq;
 pub mod srsp;
 
 use crate::error::Error;
+use crate::reader::DecodeOptions;
 use crate::subsystem::MTFramePayload;
 use crate::types::CommandType;
 use num_traits::FromPrimitive;
 
 pub fn try_decode(cmd_type: &CommandType, id: u8, buffer: &[u8]) -> Result<MTFramePayload, Error> {
+    try_decode_with(cmd_type, id, buffer, DecodeOptions::default())
+}
+
+pub fn try_decode_with(
+    cmd_type: &CommandType,
+    id: u8,
+    buffer: &[u8],
+    options: DecodeOptions,
+) -> Result<MTFramePayload, Error> {
     use crate::types::UTILCommandId::*;
     use MTFramePayload::*;
 
@@ -17,23 +27,27 @@ pub fn try_decode(cmd_type: &CommandType, id: u8, buffer: &[u8]) -> Result<MTFra
         CommandType::POLL => Err(Error::NotImplemented),
         CommandType::SREQ => match id {
             CallbackSubCmd => {
-                sreq::CallbackSubCmd::try_decode(buffer).map(|x| UTIL_CallbackSubCmd_SREQ(x))
+                sreq::CallbackSubCmd::try_decode_with(buffer, options).map(UTIL_CallbackSubCmd_SREQ)
             }
-            GetExtAddr => sreq::GetExtAddr::try_decode(buffer).map(|x| UTIL_GetExtAddr_SREQ(x)),
-            Loopback => sreq::Loopback::try_decode(buffer).map(|x| UTIL_Loopback_SREQ(x)),
-            Random => sreq::Random::try_decode(buffer).map(|x| UTIL_Random_SREQ(x)),
+            GetExtAddr => {
+                sreq::GetExtAddr::try_decode_with(buffer, options).map(UTIL_GetExtAddr_SREQ)
+            }
+            Loopback => sreq::Loopback::try_decode_with(buffer, options).map(UTIL_Loopback_SREQ),
+            Random => sreq::Random::try_decode_with(buffer, options).map(UTIL_Random_SREQ),
         },
         CommandType::AREQ => match id {
-            Loopback => areq::Loopback::try_decode(buffer).map(|x| UTIL_Loopback_AREQ(x)),
+            Loopback => areq::Loopback::try_decode_with(buffer, options).map(UTIL_Loopback_AREQ),
             _ => Err(Error::NotImplemented),
         },
         CommandType::SRSP => match id {
             CallbackSubCmd => {
-                srsp::CallbackSubCmd::try_decode(buffer).map(|x| UTIL_CallbackSubCmd_SRSP(x))
+                srsp::CallbackSubCmd::try_decode_with(buffer, options).map(UTIL_CallbackSubCmd_SRSP)
+            }
+            GetExtAddr => {
+                srsp::GetExtAddr::try_decode_with(buffer, options).map(UTIL_GetExtAddr_SRSP)
             }
-            GetExtAddr => srsp::GetExtAddr::try_decode(buffer).map(|x| UTIL_GetExtAddr_SRSP(x)),
-            Loopback => srsp::Loopback::try_decode(buffer).map(|x| UTIL_Loopback_SRSP(x)),
-            Random => srsp::Random::try_decode(buffer).map(|x| UTIL_Random_SRSP(x)),
+            Loopback => srsp::Loopback::try_decode_with(buffer, options).map(UTIL_Loopback_SRSP),
+            Random => srsp::Random::try_decode_with(buffer, options).map(UTIL_Random_SRSP),
         },
     }
 }
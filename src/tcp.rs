@@ -0,0 +1,159 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::frame::{MTExtendedHeader, MTFrame};
+use crate::parser::Parser;
+use crate::types::CommandType;
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+/// Client for a coprocessor whose UART is bridged to TCP, as with socat or
+/// ser2net. The byte stream is the same UART transport framing. AREQs
+/// arriving while a request waits for its SRSP are kept for `receive`.
+pub struct TcpTransport {
+    stream: TcpStream,
+    parser: Parser,
+    // Decoded frames not handed out yet.
+    frames: VecDeque<MTFrame>,
+    timeout: Duration,
+}
+
+impl TcpTransport {
+    /// `timeout` bounds connecting and every request.
+    pub fn connect<A: ToSocketAddrs>(address: A, timeout: Duration) -> io::Result<TcpTransport> {
+        let mut last_error = io::Error::from(io::ErrorKind::AddrNotAvailable);
+        for address in address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, timeout) {
+                Ok(stream) => return TcpTransport::from_stream(stream, timeout),
+                Err(error) => last_error = error,
+            }
+        }
+        Err(last_error)
+    }
+
+    pub fn from_stream(stream: TcpStream, timeout: Duration) -> io::Result<TcpTransport> {
+        // Frames are small and latency bound.
+        stream.set_nodelay(true)?;
+        Ok(TcpTransport {
+            stream,
+            parser: Parser::new(),
+            frames: VecDeque::new(),
+            timeout,
+        })
+    }
+
+    pub fn get_ref(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Sends a frame without waiting for a response, as for AREQs. Payloads
+    /// longer than 255 bytes go out as the blocks of an extended frame.
+    pub fn send(&mut self, frame: &MTFrame) -> Result<(), Error> {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            command = ?frame.header.command,
+            length = frame.payload.len(),
+            "frame sent"
+        );
+
+        let stack_id = frame
+            .extended_header
+            .as_ref()
+            .map_or(0, MTExtendedHeader::stack_id);
+        for block in frame.clone().into_blocks(stack_id)? {
+            self.stream
+                .write_all(&block.encode_to_uart_transport_frame())?;
+        }
+        Ok(())
+    }
+
+    /// Returns the next frame received outside a request, waiting up to
+    /// `timeout` for one.
+    pub fn receive(&mut self, timeout: Duration) -> Result<Option<MTFrame>, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(frame) = self.frames.pop_front() {
+                return Ok(Some(frame));
+            }
+            if !self.read(deadline)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    // Reads what arrives before `deadline` into `frames`. Returns false on
+    // timeout.
+    fn read(&mut self, deadline: Instant) -> Result<bool, Error> {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left == Duration::ZERO {
+            return Ok(false);
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        let mut buffer = [0x00; 256];
+        let count = match self.stream.read(&mut buffer) {
+            Ok(0) => return Err(Error::Io(io::ErrorKind::UnexpectedEof)),
+            Ok(count) => count,
+            Err(error)
+                if error.kind() == io::ErrorKind::WouldBlock
+                    || error.kind() == io::ErrorKind::TimedOut =>
+            {
+                return Ok(false)
+            }
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => return Ok(true),
+            Err(error) => return Err(error.into()),
+        };
+        // Line noise and corrupted frames are dropped, the request times out
+        // if its response was among them.
+        self.frames.extend(
+            self.parser
+                .feed_slice(&buffer[..count])
+                .into_iter()
+                .flatten(),
+        );
+        Ok(true)
+    }
+}
+
+impl Client for TcpTransport {
+    fn request(&mut self, frame: MTFrame) -> Result<MTFrame, Error> {
+        let timeout = self.timeout;
+        self.request_with_timeout(frame, timeout)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(command = ?frame.header.command))
+    )]
+    fn request_with_timeout(
+        &mut self,
+        frame: MTFrame,
+        timeout: Duration,
+    ) -> Result<MTFrame, Error> {
+        let command = frame.header.command.clone();
+        // Responses to requests that timed out earlier.
+        self.frames
+            .retain(|frame| frame.header.command.cmd_type != CommandType::SRSP);
+        self.send(&frame)?;
+        let deadline = Instant::now() + timeout;
+        let mut checked = 0;
+        loop {
+            let position = self
+                .frames
+                .iter()
+                .skip(checked)
+                .position(|frame| frame.header.command.cmd_type == CommandType::SRSP);
+            if let Some(position) = position {
+                return Ok(self.frames.remove(checked + position).unwrap());
+            }
+            checked = self.frames.len();
+            if !self.read(deadline)? {
+                return Err(Error::Timeout { command });
+            }
+        }
+    }
+}
@@ -0,0 +1,968 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::frame::{CommandCode, MTFrame, MTHeader};
+use crate::subsystem::{app, mac, rpc, sys, util, MTFramePayload};
+use crate::types::*;
+use crate::Reader;
+use num_traits::FromPrimitive;
+use std::collections::HashMap;
+
+// Small xorshift generator so the property tests are reproducible from a seed
+// without pulling in a random number crate.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng {
+            state: (seed ^ 0x9e37_79b9_7f4a_7c15) | 1,
+        }
+    }
+
+    pub fn u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    pub fn u32(&mut self) -> u32 {
+        (self.u64() >> 32) as u32
+    }
+
+    pub fn u16(&mut self) -> u16 {
+        (self.u64() >> 48) as u16
+    }
+
+    pub fn u8(&mut self) -> u8 {
+        (self.u64() >> 56) as u8
+    }
+
+    pub fn bool(&mut self) -> bool {
+        self.u64() >> 63 != 0
+    }
+
+    // Uniform enough for picking variants and lengths.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.u64() % bound as u64) as usize
+    }
+
+    pub fn bytes(&mut self, max_len: usize) -> Vec<u8> {
+        let len = self.below(max_len + 1);
+        (0..len).map(|_| self.u8()).collect()
+    }
+}
+
+/// Generates random values that the decoders accept.
+pub trait Arbitrary: Sized {
+    fn arbitrary(rng: &mut Rng) -> Self;
+}
+
+impl Arbitrary for u8 {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        rng.u8()
+    }
+}
+
+impl Arbitrary for i8 {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        rng.u8() as i8
+    }
+}
+
+impl Arbitrary for u16 {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        rng.u16()
+    }
+}
+
+impl Arbitrary for u32 {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        rng.u32()
+    }
+}
+
+impl Arbitrary for bool {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        rng.bool()
+    }
+}
+
+impl<const N: usize> Arbitrary for [u8; N] {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let mut value = [0x00; N];
+        value.iter_mut().for_each(|byte| *byte = rng.u8());
+        value
+    }
+}
+
+impl Arbitrary for Vec<u8> {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        rng.bytes(16)
+    }
+}
+
+macro_rules! arbitrary_enum {
+    ($from:ident, $rand:ident, $($name:ident),* $(,)?) => {
+        $(
+            impl Arbitrary for $name {
+                fn arbitrary(rng: &mut Rng) -> Self {
+                    loop {
+                        if let Some(value) = $name::$from(rng.$rand()) {
+                            return value;
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+arbitrary_enum!(
+    from_u8,
+    u8,
+    CommandType,
+    MTSubsystem,
+    Status,
+    AddressMode,
+    SecurityLevel,
+    KeyIdMode,
+    WiSUNAsyncFrameType,
+    AssociationStatus,
+    DisassociateReason,
+    MACPIBAttributeId,
+    SecurityPIBAttributeId,
+    ScanType,
+    PermitJoin,
+    MPMScan,
+    MPMType,
+    WiSUNAsyncOperation,
+    CommEventReason,
+    ResetType,
+    TransportProtocolRevision,
+    ProductIdCode,
+    ResetReason,
+    SubsystemId,
+    ExtendedAddressType,
+    ErrorCode,
+);
+
+// Attribute ids live in a narrow range of the u16 space.
+impl Arbitrary for FHPIBAttributeId {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        loop {
+            if let Some(value) = FHPIBAttributeId::from_u16(0x2000 | u16::from(rng.u8() & 0x1f)) {
+                return value;
+            }
+        }
+    }
+}
+
+impl Arbitrary for PhyId {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PhyId::from_u8(rng.u8())
+    }
+}
+
+impl Arbitrary for TxOption {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        TxOption::from_bits_truncate(rng.u8())
+    }
+}
+
+impl Arbitrary for CapabilityInfo {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        CapabilityInfo::from_bits_truncate(rng.u8())
+    }
+}
+
+impl Arbitrary for SuperframeSpec {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        SuperframeSpec::from_u16(rng.u16() & !(1 << 13))
+    }
+}
+
+impl Arbitrary for ChannelMask {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ChannelMask::from_bytes(Arbitrary::arbitrary(rng))
+    }
+}
+
+impl Arbitrary for PanId {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        PanId(rng.u16())
+    }
+}
+
+impl Arbitrary for Lqi {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        Lqi(rng.u8())
+    }
+}
+
+impl Arbitrary for Rssi {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        Rssi(Arbitrary::arbitrary(rng))
+    }
+}
+
+impl Arbitrary for Address {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        match rng.below(3) {
+            0 => Address::None,
+            1 => Address::Addr16Bit(Arbitrary::arbitrary(rng)),
+            _ => Address::Addr64Bit(Arbitrary::arbitrary(rng)),
+        }
+    }
+}
+
+impl Arbitrary for CommandCode {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        CommandCode {
+            is_extended: rng.bool(),
+            cmd_type: Arbitrary::arbitrary(rng),
+            subsystem: Arbitrary::arbitrary(rng),
+            id: rng.u8(),
+        }
+    }
+}
+
+macro_rules! arbitrary_struct {
+    ($($name:ident)::+ { $($field:ident),* $(,)? }) => {
+        impl Arbitrary for $($name)::+ {
+            #[allow(unused_variables)]
+            fn arbitrary(rng: &mut Rng) -> Self {
+                $($name)::+ {
+                    $($field: Arbitrary::arbitrary(rng),)*
+                }
+            }
+        }
+    };
+}
+
+arbitrary_struct!(ShortAddress { address });
+arbitrary_struct!(ExtendedAddress { address });
+arbitrary_struct!(KeySource { key });
+arbitrary_struct!(Security {
+    key_source,
+    security_level,
+    key_id_mode,
+    key_index
+});
+arbitrary_struct!(PanDescriptor {
+    timestamp,
+    coord_address,
+    pan_id,
+    superframe_spec,
+    logical_channel,
+    channel_page,
+    gts_permit,
+    link_quality,
+    security_failure,
+    security
+});
+arbitrary_struct!(CoexistenceSpec {
+    beacon_order,
+    super_frame_order,
+    final_cap_slot,
+    enh_beacon_order,
+    ofs_time_slot,
+    cap_back_off,
+    non_beacon_order
+});
+
+arbitrary_struct!(mac::areq::DataCnf {
+    status,
+    handle,
+    timestamp,
+    timestamp2,
+    retries,
+    link_quality,
+    correlation,
+    rssi,
+    frame_counter
+});
+arbitrary_struct!(mac::areq::PurgeCnf { status, handle });
+arbitrary_struct!(mac::areq::SyncLossInd {
+    status,
+    pan_id,
+    logical_channel,
+    channel_page,
+    phy_id,
+    security
+});
+arbitrary_struct!(mac::areq::AssociateInd {
+    extended_address,
+    capabilities,
+    security
+});
+arbitrary_struct!(mac::areq::AssociateCnf {
+    status,
+    short_address,
+    security
+});
+arbitrary_struct!(mac::areq::EnhancedBeaconFrame { bsn, coexistence });
+arbitrary_struct!(mac::areq::DisassociateInd {
+    extended_address,
+    disassociate_reason,
+    security
+});
+arbitrary_struct!(mac::areq::DisassociateCnf {
+    status,
+    device_addr,
+    device_pan_id
+});
+arbitrary_struct!(mac::areq::OrphanInd {
+    extended_address,
+    security
+});
+arbitrary_struct!(mac::areq::PollCnf {
+    status,
+    frame_pending
+});
+arbitrary_struct!(mac::areq::PollInd {
+    dev_addr,
+    pan_id,
+    no_response
+});
+arbitrary_struct!(mac::areq::ScanCnf {
+    status,
+    scan_type,
+    channel_page,
+    phy_id,
+    unscanned_channels,
+    result_list_count,
+    result_list
+});
+arbitrary_struct!(mac::areq::CommStatusInd {
+    status,
+    src_addr,
+    dst_addr,
+    device_pan_id,
+    reason,
+    security
+});
+arbitrary_struct!(mac::areq::StartCnf { status });
+arbitrary_struct!(mac::areq::WSAsyncCnf { status });
+arbitrary_struct!(mac::sreq::Init {});
+arbitrary_struct!(mac::sreq::PurgeReq { handle });
+arbitrary_struct!(mac::sreq::AssociateReq {
+    logical_channel,
+    channel_page,
+    phy_id,
+    coord_address,
+    coord_pan_id,
+    capability_info,
+    security
+});
+arbitrary_struct!(mac::sreq::AssociateRsp {
+    extended_address,
+    assoc_short_address,
+    assoc_status,
+    security
+});
+arbitrary_struct!(mac::sreq::DisassociateReq {
+    device_address,
+    device_pan_id,
+    disassociate_reason,
+    tx_indirect,
+    security
+});
+arbitrary_struct!(mac::sreq::GetReq { attribute_id });
+arbitrary_struct!(mac::sreq::SetReq {
+    attribute_id,
+    attribute_value
+});
+arbitrary_struct!(mac::sreq::SecurityGetReq {
+    attribute_id,
+    index1,
+    index2
+});
+arbitrary_struct!(mac::sreq::SecuritySetReq {
+    attribute_id,
+    index1,
+    index2,
+    attribute_value
+});
+arbitrary_struct!(mac::sreq::UpdatePANIdReq { pan_id });
+arbitrary_struct!(mac::sreq::AddDeviceReq {
+    pan_id,
+    short_addr,
+    ext_addr,
+    frame_counter,
+    exempt,
+    unique,
+    duplicate,
+    data_size,
+    lookup_data
+});
+arbitrary_struct!(mac::sreq::DeleteDeviceReq { ext_addr });
+arbitrary_struct!(mac::sreq::DeleteAllDevicesReq {});
+arbitrary_struct!(mac::sreq::DeleteKeyReq { index });
+arbitrary_struct!(mac::sreq::ReadKeyReq { index });
+arbitrary_struct!(mac::sreq::WriteKeyReq {
+    new,
+    index,
+    key,
+    frame_counter,
+    data_size,
+    lookup_data
+});
+arbitrary_struct!(mac::sreq::OrphanRsp {
+    extended_address,
+    assoc_short_address,
+    associated_member,
+    security
+});
+arbitrary_struct!(mac::sreq::PollReq {
+    coord_address,
+    coord_pan_id,
+    security
+});
+arbitrary_struct!(mac::sreq::ResetReq { set_default });
+arbitrary_struct!(mac::sreq::ScanReq {
+    scan_type,
+    scan_duration,
+    channel_page,
+    phy_id,
+    max_results,
+    permit_join,
+    link_quality,
+    rsp_filter,
+    mpm_scan,
+    mpm_type,
+    mpm_duration,
+    security,
+    channels
+});
+arbitrary_struct!(mac::sreq::SyncReq {
+    logical_channel,
+    channel_page,
+    track_beacon,
+    phy_id
+});
+arbitrary_struct!(mac::sreq::SetRxGainReq { mode });
+arbitrary_struct!(mac::sreq::WSAsyncReq {
+    operation,
+    frame_type,
+    security,
+    channels
+});
+arbitrary_struct!(mac::sreq::FHEnableReq {});
+arbitrary_struct!(mac::sreq::FHStartReq {});
+arbitrary_struct!(mac::sreq::FHGetReq { attribute_id });
+arbitrary_struct!(mac::sreq::FHSetReq { attribute_id, data });
+arbitrary_struct!(mac::sreq::SrcMatchEnableReq {
+    address_type,
+    num_entries
+});
+arbitrary_struct!(mac::sreq::SrcMatchAddEntryReq { address, pan_id });
+arbitrary_struct!(mac::sreq::SrcMatchDeleteEntryReq { address, pan_id });
+arbitrary_struct!(mac::sreq::SrcMatchAckAllPendingReq { enabled });
+arbitrary_struct!(mac::srsp::Init { status });
+arbitrary_struct!(mac::srsp::DataReq { status });
+arbitrary_struct!(mac::srsp::PurgeReq { status });
+arbitrary_struct!(mac::srsp::AssociateReq { status });
+arbitrary_struct!(mac::srsp::AssociateRsp { status });
+arbitrary_struct!(mac::srsp::DisassociateReq { status });
+arbitrary_struct!(mac::srsp::GetReq { status, data });
+arbitrary_struct!(mac::srsp::SetReq { status });
+arbitrary_struct!(mac::srsp::SecurityGetReq {
+    status,
+    index1,
+    index2,
+    data
+});
+arbitrary_struct!(mac::srsp::SecuritySetReq { status });
+arbitrary_struct!(mac::srsp::UpdatePANIdReq { status });
+arbitrary_struct!(mac::srsp::AddDeviceReq { status });
+arbitrary_struct!(mac::srsp::DeleteDeviceReq { status });
+arbitrary_struct!(mac::srsp::DeleteAllDevicesReq { status });
+arbitrary_struct!(mac::srsp::DeleteKeyReq { status });
+arbitrary_struct!(mac::srsp::ReadKeyReq {
+    status,
+    frame_counter
+});
+arbitrary_struct!(mac::srsp::WriteKeyReq { status });
+arbitrary_struct!(mac::srsp::OrphanRsp { status });
+arbitrary_struct!(mac::srsp::PollReq { status });
+arbitrary_struct!(mac::srsp::ResetReq { status });
+arbitrary_struct!(mac::srsp::ScanReq { status });
+arbitrary_struct!(mac::srsp::StartReq { status });
+arbitrary_struct!(mac::srsp::SyncReq { status });
+arbitrary_struct!(mac::srsp::SetRxGainReq { status });
+arbitrary_struct!(mac::srsp::WSAsyncReq { status });
+arbitrary_struct!(mac::srsp::FHEnableReq { status });
+arbitrary_struct!(mac::srsp::FHStartReq { status });
+arbitrary_struct!(mac::srsp::FHGetReq { status, data });
+arbitrary_struct!(mac::srsp::FHSetReq { status });
+arbitrary_struct!(mac::srsp::SrcMatchEnableReq { status });
+arbitrary_struct!(mac::srsp::SrcMatchAddEntryReq { status });
+arbitrary_struct!(mac::srsp::SrcMatchDeleteEntryReq { status });
+arbitrary_struct!(mac::srsp::SrcMatchAckAllPendingReq { status });
+arbitrary_struct!(rpc::MTCommandError {
+    error_code,
+    command
+});
+arbitrary_struct!(sys::areq::ResetReq { reset_type });
+arbitrary_struct!(sys::areq::ResetInd {
+    reason,
+    transport,
+    product,
+    major,
+    minor,
+    maint
+});
+arbitrary_struct!(sys::sreq::PingReq {});
+arbitrary_struct!(sys::sreq::VersionReq {});
+arbitrary_struct!(sys::sreq::NVCreateReq {
+    sys_id,
+    item_id,
+    sub_id,
+    length
+});
+arbitrary_struct!(sys::sreq::NVDeleteReq {
+    sys_id,
+    item_id,
+    sub_id
+});
+arbitrary_struct!(sys::sreq::NVLengthReq {
+    sys_id,
+    item_id,
+    sub_id
+});
+arbitrary_struct!(sys::sreq::NVReadReq {
+    sys_id,
+    item_id,
+    sub_id,
+    offset,
+    length
+});
+arbitrary_struct!(sys::sreq::NVWriteReq {
+    sys_id,
+    item_id,
+    sub_id,
+    offset,
+    length,
+    data
+});
+arbitrary_struct!(sys::sreq::NVUpdateReq {
+    sys_id,
+    item_id,
+    sub_id,
+    length,
+    data
+});
+arbitrary_struct!(sys::sreq::NVCompactReq { threshold });
+arbitrary_struct!(sys::srsp::PingReq { capabilities });
+arbitrary_struct!(sys::srsp::VersionReq {
+    transport,
+    product,
+    major,
+    minor,
+    maint
+});
+arbitrary_struct!(sys::srsp::NVCreateReq { status });
+arbitrary_struct!(sys::srsp::NVDeleteReq { status });
+arbitrary_struct!(sys::srsp::NVLengthReq { length });
+arbitrary_struct!(sys::srsp::NVReadReq {
+    status,
+    length,
+    data
+});
+arbitrary_struct!(sys::srsp::NVWriteReq { status });
+arbitrary_struct!(sys::srsp::NVUpdateReq { status });
+arbitrary_struct!(sys::srsp::NVCompactReq { status });
+arbitrary_struct!(util::areq::Loopback {
+    repeats,
+    interval,
+    data
+});
+arbitrary_struct!(util::sreq::CallbackSubCmd {
+    subsystem_id,
+    enables
+});
+arbitrary_struct!(util::sreq::GetExtAddr { address_type });
+arbitrary_struct!(util::sreq::Loopback {
+    repeats,
+    interval,
+    data
+});
+arbitrary_struct!(util::sreq::Random {});
+arbitrary_struct!(util::srsp::CallbackSubCmd { status, enables });
+arbitrary_struct!(util::srsp::GetExtAddr {
+    address_type,
+    ext_address
+});
+arbitrary_struct!(util::srsp::Loopback {
+    repeats,
+    interval,
+    data
+});
+arbitrary_struct!(util::srsp::Random { number });
+arbitrary_struct!(app::areq::AppMsgInd {
+    app_endpoint,
+    src_address,
+    src_endpoint,
+    cluster_id,
+    data_length,
+    data
+});
+arbitrary_struct!(app::sreq::AppMsgReq {
+    app_endpoint,
+    dest_address,
+    dest_endpoint,
+    cluster_id,
+    data_length,
+    data
+});
+arbitrary_struct!(app::srsp::AppMsgReq { status });
+
+// The commands below carry length fields that have to agree with the
+// payloads following them.
+
+impl Arbitrary for mac::areq::DataInd {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let data_payload = rng.bytes(32);
+        let ie_payload = rng.bytes(16);
+        mac::areq::DataInd {
+            src_address: Arbitrary::arbitrary(rng),
+            dest_address: Arbitrary::arbitrary(rng),
+            timestamp: rng.u32(),
+            timestamp2: rng.u16(),
+            src_pan_id: PanId(rng.u16()),
+            dest_pan_id: PanId(rng.u16()),
+            link_quality: Arbitrary::arbitrary(rng),
+            correlation: rng.u8(),
+            rssi: Arbitrary::arbitrary(rng),
+            dsn: rng.u8(),
+            security: Arbitrary::arbitrary(rng),
+            frame_counter: rng.u32(),
+            data_length: data_payload.len() as u16,
+            ie_length: ie_payload.len() as u16,
+            data_payload,
+            ie_payload,
+        }
+    }
+}
+
+impl Arbitrary for mac::areq::WSAsyncInd {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let data_payload = rng.bytes(32);
+        let ie_payload = rng.bytes(16);
+        mac::areq::WSAsyncInd {
+            src_address: Arbitrary::arbitrary(rng),
+            dest_address: Arbitrary::arbitrary(rng),
+            timestamp: rng.u32(),
+            timestamp2: rng.u16(),
+            src_pan_id: PanId(rng.u16()),
+            dest_pan_id: PanId(rng.u16()),
+            link_quality: Arbitrary::arbitrary(rng),
+            correlation: rng.u8(),
+            rssi: Arbitrary::arbitrary(rng),
+            dsn: rng.u8(),
+            security: Arbitrary::arbitrary(rng),
+            frame_counter: rng.u32(),
+            frame_type: Arbitrary::arbitrary(rng),
+            data_length: data_payload.len() as u16,
+            ie_length: ie_payload.len() as u16,
+            data_payload,
+            ie_payload,
+        }
+    }
+}
+
+impl Arbitrary for mac::sreq::DataReq {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let data_payload = rng.bytes(32);
+        let ie_payload = rng.bytes(16);
+        mac::sreq::DataReq {
+            dest_address: Arbitrary::arbitrary(rng),
+            dest_pan_id: PanId(rng.u16()),
+            src_address_mode: Arbitrary::arbitrary(rng),
+            handle: rng.u8(),
+            tx_option: Arbitrary::arbitrary(rng),
+            channel: rng.u8(),
+            power: rng.u8(),
+            security: Arbitrary::arbitrary(rng),
+            include_fh_ies: rng.u32(),
+            data_length: data_payload.len() as u16,
+            ie_length: ie_payload.len() as u16,
+            data_payload,
+            ie_payload,
+        }
+    }
+}
+
+impl Arbitrary for mac::sreq::StartReq {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        let ie_id_list = rng.bytes(8);
+        mac::sreq::StartReq {
+            start_time: rng.u32(),
+            pan_id: PanId(rng.u16()),
+            logical_channel: rng.u8(),
+            channel_page: rng.u8(),
+            phy_id: Arbitrary::arbitrary(rng),
+            beacon_order: rng.u8(),
+            super_frame_order: rng.u8(),
+            pan_coordinator: rng.bool(),
+            battery_life_ext: rng.bool(),
+            coord_realignment: rng.bool(),
+            realign_security: Arbitrary::arbitrary(rng),
+            beacon_security: Arbitrary::arbitrary(rng),
+            start_fh: rng.bool(),
+            enh_beacon_order: rng.u8(),
+            ofs_time_slot: rng.u8(),
+            non_beacon_order: rng.u16(),
+            num_ies: ie_id_list.len() as u8,
+            ie_id_list,
+        }
+    }
+}
+
+impl Arbitrary for mac::areq::StandardBeaconFrame {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        mac::areq::StandardBeaconFrame {
+            bsn: rng.u8(),
+            pan_descriptor: Arbitrary::arbitrary(rng),
+            short_addr_list: (0..rng.below(4))
+                .map(|_| Arbitrary::arbitrary(rng))
+                .collect(),
+            ext_addr_list: (0..rng.below(4))
+                .map(|_| Arbitrary::arbitrary(rng))
+                .collect(),
+            sdu: rng.bytes(16),
+        }
+    }
+}
+
+impl Arbitrary for mac::areq::BeaconNotifyInd {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        if rng.bool() {
+            mac::areq::BeaconNotifyInd::StandardFrame(Arbitrary::arbitrary(rng))
+        } else {
+            mac::areq::BeaconNotifyInd::EnhancedFrame(Arbitrary::arbitrary(rng))
+        }
+    }
+}
+
+impl Arbitrary for MTFramePayload {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        use MTFramePayload::*;
+        match rng.below(116) {
+            0 => MAC_DataCnf_AREQ(Arbitrary::arbitrary(rng)),
+            1 => MAC_DataInd_AREQ(Arbitrary::arbitrary(rng)),
+            2 => MAC_PurgeCnf_AREQ(Arbitrary::arbitrary(rng)),
+            3 => MAC_WSAsyncInd_AREQ(Arbitrary::arbitrary(rng)),
+            4 => MAC_SyncLossInd_AREQ(Arbitrary::arbitrary(rng)),
+            5 => MAC_AssociateInd_AREQ(Arbitrary::arbitrary(rng)),
+            6 => MAC_AssociateCnf_AREQ(Arbitrary::arbitrary(rng)),
+            7 => MAC_BeaconNotifyInd_AREQ(Arbitrary::arbitrary(rng)),
+            8 => MAC_DisassociateInd_AREQ(Arbitrary::arbitrary(rng)),
+            9 => MAC_DisassociateCnf_AREQ(Arbitrary::arbitrary(rng)),
+            10 => MAC_OrphanInd_AREQ(Arbitrary::arbitrary(rng)),
+            11 => MAC_PollCnf_AREQ(Arbitrary::arbitrary(rng)),
+            12 => MAC_PollInd_AREQ(Arbitrary::arbitrary(rng)),
+            13 => MAC_ScanCnf_AREQ(Arbitrary::arbitrary(rng)),
+            14 => MAC_CommStatusInd_AREQ(Arbitrary::arbitrary(rng)),
+            15 => MAC_StartCnf_AREQ(Arbitrary::arbitrary(rng)),
+            16 => MAC_WSAsyncCnf_AREQ(Arbitrary::arbitrary(rng)),
+            17 => MAC_Init_SREQ(Arbitrary::arbitrary(rng)),
+            18 => MAC_DataReq_SREQ(Arbitrary::arbitrary(rng)),
+            19 => MAC_PurgeReq_SREQ(Arbitrary::arbitrary(rng)),
+            20 => MAC_AssociateReq_SREQ(Arbitrary::arbitrary(rng)),
+            21 => MAC_AssociateRsp_SREQ(Arbitrary::arbitrary(rng)),
+            22 => MAC_DisassociateReq_SREQ(Arbitrary::arbitrary(rng)),
+            23 => MAC_GetReq_SREQ(Arbitrary::arbitrary(rng)),
+            24 => MAC_SetReq_SREQ(Arbitrary::arbitrary(rng)),
+            25 => MAC_SecurityGetReq_SREQ(Arbitrary::arbitrary(rng)),
+            26 => MAC_SecuritySetReq_SREQ(Arbitrary::arbitrary(rng)),
+            27 => MAC_UpdatePANIdReq_SREQ(Arbitrary::arbitrary(rng)),
+            28 => MAC_AddDeviceReq_SREQ(Arbitrary::arbitrary(rng)),
+            29 => MAC_DeleteDeviceReq_SREQ(Arbitrary::arbitrary(rng)),
+            30 => MAC_DeleteAllDevicesReq_SREQ(Arbitrary::arbitrary(rng)),
+            31 => MAC_DeleteKeyReq_SREQ(Arbitrary::arbitrary(rng)),
+            32 => MAC_ReadKeyReq_SREQ(Arbitrary::arbitrary(rng)),
+            33 => MAC_WriteKeyReq_SREQ(Arbitrary::arbitrary(rng)),
+            34 => MAC_OrphanRsp_SREQ(Arbitrary::arbitrary(rng)),
+            35 => MAC_PollReq_SREQ(Arbitrary::arbitrary(rng)),
+            36 => MAC_ResetReq_SREQ(Arbitrary::arbitrary(rng)),
+            37 => MAC_ScanReq_SREQ(Arbitrary::arbitrary(rng)),
+            38 => MAC_StartReq_SREQ(Arbitrary::arbitrary(rng)),
+            39 => MAC_SyncReq_SREQ(Arbitrary::arbitrary(rng)),
+            40 => MAC_SetRxGainReq_SREQ(Arbitrary::arbitrary(rng)),
+            41 => MAC_WSAsyncReq_SREQ(Arbitrary::arbitrary(rng)),
+            42 => MAC_FHEnableReq_SREQ(Arbitrary::arbitrary(rng)),
+            43 => MAC_FHStartReq_SREQ(Arbitrary::arbitrary(rng)),
+            44 => MAC_FHGetReq_SREQ(Arbitrary::arbitrary(rng)),
+            45 => MAC_FHSetReq_SREQ(Arbitrary::arbitrary(rng)),
+            46 => MAC_SrcMatchEnableReq_SREQ(Arbitrary::arbitrary(rng)),
+            47 => MAC_SrcMatchAddEntryReq_SREQ(Arbitrary::arbitrary(rng)),
+            48 => MAC_SrcMatchDeleteEntryReq_SREQ(Arbitrary::arbitrary(rng)),
+            49 => MAC_SrcMatchAckAllPendingReq_SREQ(Arbitrary::arbitrary(rng)),
+            50 => MAC_Init_SRSP(Arbitrary::arbitrary(rng)),
+            51 => MAC_DataReq_SRSP(Arbitrary::arbitrary(rng)),
+            52 => MAC_PurgeReq_SRSP(Arbitrary::arbitrary(rng)),
+            53 => MAC_AssociateReq_SRSP(Arbitrary::arbitrary(rng)),
+            54 => MAC_AssociateRsp_SRSP(Arbitrary::arbitrary(rng)),
+            55 => MAC_DisassociateReq_SRSP(Arbitrary::arbitrary(rng)),
+            56 => MAC_GetReq_SRSP(Arbitrary::arbitrary(rng)),
+            57 => MAC_SetReq_SRSP(Arbitrary::arbitrary(rng)),
+            58 => MAC_SecurityGetReq_SRSP(Arbitrary::arbitrary(rng)),
+            59 => MAC_SecuritySetReq_SRSP(Arbitrary::arbitrary(rng)),
+            60 => MAC_UpdatePANIdReq_SRSP(Arbitrary::arbitrary(rng)),
+            61 => MAC_AddDeviceReq_SRSP(Arbitrary::arbitrary(rng)),
+            62 => MAC_DeleteDeviceReq_SRSP(Arbitrary::arbitrary(rng)),
+            63 => MAC_DeleteAllDevicesReq_SRSP(Arbitrary::arbitrary(rng)),
+            64 => MAC_DeleteKeyReq_SRSP(Arbitrary::arbitrary(rng)),
+            65 => MAC_ReadKeyReq_SRSP(Arbitrary::arbitrary(rng)),
+            66 => MAC_WriteKeyReq_SRSP(Arbitrary::arbitrary(rng)),
+            67 => MAC_OrphanRsp_SRSP(Arbitrary::arbitrary(rng)),
+            68 => MAC_PollReq_SRSP(Arbitrary::arbitrary(rng)),
+            69 => MAC_ResetReq_SRSP(Arbitrary::arbitrary(rng)),
+            70 => MAC_ScanReq_SRSP(Arbitrary::arbitrary(rng)),
+            71 => MAC_StartReq_SRSP(Arbitrary::arbitrary(rng)),
+            72 => MAC_SyncReq_SRSP(Arbitrary::arbitrary(rng)),
+            73 => MAC_SetRxGainReq_SRSP(Arbitrary::arbitrary(rng)),
+            74 => MAC_WSAsyncReq_SRSP(Arbitrary::arbitrary(rng)),
+            75 => MAC_FHEnableReq_SRSP(Arbitrary::arbitrary(rng)),
+            76 => MAC_FHStartReq_SRSP(Arbitrary::arbitrary(rng)),
+            77 => MAC_FHGetReq_SRSP(Arbitrary::arbitrary(rng)),
+            78 => MAC_FHSetReq_SRSP(Arbitrary::arbitrary(rng)),
+            79 => MAC_SrcMatchEnableReq_SRSP(Arbitrary::arbitrary(rng)),
+            80 => MAC_SrcMatchAddEntryReq_SRSP(Arbitrary::arbitrary(rng)),
+            81 => MAC_SrcMatchDeleteEntryReq_SRSP(Arbitrary::arbitrary(rng)),
+            82 => MAC_SrcMatchAckAllPendingReq_SRSP(Arbitrary::arbitrary(rng)),
+            83 => RPC_MTCommandError(Arbitrary::arbitrary(rng)),
+            84 => SYS_ResetReq_AREQ(Arbitrary::arbitrary(rng)),
+            85 => SYS_ResetInd_AREQ(Arbitrary::arbitrary(rng)),
+            86 => SYS_PingReq_SREQ(Arbitrary::arbitrary(rng)),
+            87 => SYS_VersionReq_SREQ(Arbitrary::arbitrary(rng)),
+            88 => SYS_NVCreateReq_SREQ(Arbitrary::arbitrary(rng)),
+            89 => SYS_NVDeleteReq_SREQ(Arbitrary::arbitrary(rng)),
+            90 => SYS_NVLengthReq_SREQ(Arbitrary::arbitrary(rng)),
+            91 => SYS_NVReadReq_SREQ(Arbitrary::arbitrary(rng)),
+            92 => SYS_NVWriteReq_SREQ(Arbitrary::arbitrary(rng)),
+            93 => SYS_NVUpdateReq_SREQ(Arbitrary::arbitrary(rng)),
+            94 => SYS_NVCompactReq_SREQ(Arbitrary::arbitrary(rng)),
+            95 => SYS_PingReq_SRSP(Arbitrary::arbitrary(rng)),
+            96 => SYS_VersionReq_SRSP(Arbitrary::arbitrary(rng)),
+            97 => SYS_NVCreateReq_SRSP(Arbitrary::arbitrary(rng)),
+            98 => SYS_NVDeleteReq_SRSP(Arbitrary::arbitrary(rng)),
+            99 => SYS_NVLengthReq_SRSP(Arbitrary::arbitrary(rng)),
+            100 => SYS_NVReadReq_SRSP(Arbitrary::arbitrary(rng)),
+            101 => SYS_NVWriteReq_SRSP(Arbitrary::arbitrary(rng)),
+            102 => SYS_NVUpdateReq_SRSP(Arbitrary::arbitrary(rng)),
+            103 => SYS_NVCompactReq_SRSP(Arbitrary::arbitrary(rng)),
+            104 => UTIL_Loopback_AREQ(Arbitrary::arbitrary(rng)),
+            105 => UTIL_CallbackSubCmd_SREQ(Arbitrary::arbitrary(rng)),
+            106 => UTIL_GetExtAddr_SREQ(Arbitrary::arbitrary(rng)),
+            107 => UTIL_Loopback_SREQ(Arbitrary::arbitrary(rng)),
+            108 => UTIL_Random_SREQ(Arbitrary::arbitrary(rng)),
+            109 => UTIL_CallbackSubCmd_SRSP(Arbitrary::arbitrary(rng)),
+            110 => UTIL_GetExtAddr_SRSP(Arbitrary::arbitrary(rng)),
+            111 => UTIL_Loopback_SRSP(Arbitrary::arbitrary(rng)),
+            112 => UTIL_Random_SRSP(Arbitrary::arbitrary(rng)),
+            113 => APP_AppMsgInd_AREQ(Arbitrary::arbitrary(rng)),
+            114 => APP_AppMsgReq_SREQ(Arbitrary::arbitrary(rng)),
+            115 => APP_AppMsgReq_SRSP(Arbitrary::arbitrary(rng)),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Encodes `payload` into a frame, decodes it back and panics if anything
+/// changed along the way.
+pub fn assert_round_trip(payload: MTFramePayload) {
+    let expected = format!("{:?}", payload);
+    let frame = payload.into_mt_frame();
+    assert_eq!(
+        frame.header.length as usize,
+        frame.payload.len(),
+        "header length of {}",
+        expected
+    );
+
+    let mut buffer = Vec::new();
+    frame.encode_into(&mut buffer);
+    let decoded = MTFrame::try_decode(&mut Reader::new(&buffer[..]))
+        .unwrap_or_else(|error| panic!("{:?} decoding frame of {}", error, expected));
+    let decoded = MTFramePayload::try_from_frame(&decoded)
+        .unwrap_or_else(|error| panic!("{:?} decoding payload of {}", error, expected));
+
+    assert_eq!(format!("{:?}", decoded), expected);
+}
+
+type Responder = Box<dyn FnMut(&MTFrame) -> Result<MTFrame, Error>>;
+
+/// Client standing in for the coprocessor in tests. Every request is
+/// recorded and answered by the responder scripted for its command, or by a
+/// `Success` status SRSP of the same command when none is.
+#[derive(Default)]
+pub struct ScriptedClient {
+    requests: Vec<MTFrame>,
+    responders: HashMap<(MTSubsystem, u8), Responder>,
+}
+
+impl ScriptedClient {
+    pub fn new() -> ScriptedClient {
+        ScriptedClient::default()
+    }
+
+    /// Answers the `id` command of `subsystem` with `responder` from now on,
+    /// replacing the one scripted before.
+    pub fn respond<F>(&mut self, subsystem: MTSubsystem, id: u8, responder: F)
+    where
+        F: FnMut(&MTFrame) -> Result<MTFrame, Error> + 'static,
+    {
+        self.responders.insert((subsystem, id), Box::new(responder));
+    }
+
+    pub fn requests(&self) -> &[MTFrame] {
+        &self.requests
+    }
+
+    pub fn command_ids(&self) -> Vec<u8> {
+        self.requests.iter().map(|x| x.header.command.id).collect()
+    }
+
+    /// Decodes the recorded payloads of the `id` command of `subsystem`.
+    pub fn decoded<T>(
+        &self,
+        subsystem: MTSubsystem,
+        id: u8,
+        decode: fn(&[u8]) -> Result<T, Error>,
+    ) -> Vec<T> {
+        self.requests
+            .iter()
+            .filter(|x| x.header.command.subsystem == subsystem && x.header.command.id == id)
+            .map(|x| decode(&x.payload).unwrap())
+            .collect()
+    }
+}
+
+impl Client for ScriptedClient {
+    fn request(&mut self, frame: MTFrame) -> Result<MTFrame, Error> {
+        let command = frame.header.command.clone();
+        self.requests.push(frame);
+        if let Some(responder) = self.responders.get_mut(&(command.subsystem, command.id)) {
+            return responder(self.requests.last().unwrap());
+        }
+
+        let mut payload = Vec::new();
+        Status::Success.encode_into(&mut payload);
+        Ok(MTFrame {
+            header: MTHeader {
+                length: payload.len() as u8,
+                command: CommandCode {
+                    cmd_type: CommandType::SRSP,
+                    ..command
+                },
+            },
+            extended_header: None,
+            payload,
+        })
+    }
+}
@@ -0,0 +1,128 @@
+use crate::subsystem::mac::areq::{DataCnf, DataInd};
+use crate::subsystem::mac::sreq::DataReq;
+use crate::types::{Address, Status, TxOption};
+use std::collections::HashMap;
+
+#[derive(Debug, Copy, Clone)]
+pub struct TpcConfig {
+    // Transmit power limits, in dBm.
+    pub min_power: i8,
+    pub max_power: i8,
+    // Power used for neighbors without feedback yet.
+    pub initial_power: i8,
+    // Received signal level the neighbor should see, in dBm.
+    pub target_rssi: i8,
+    // No adjustment is made while the feedback stays this close to the target.
+    pub hysteresis: u8,
+    pub step: u8,
+}
+
+impl Default for TpcConfig {
+    fn default() -> Self {
+        TpcConfig {
+            min_power: -10,
+            max_power: 14,
+            initial_power: 14,
+            target_rssi: -80,
+            hysteresis: 6,
+            step: 2,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct TxReport {
+    pub handle: u8,
+    pub destination: Address,
+    pub power: i8,
+    pub status: Option<Status>,
+}
+
+/// Chooses a transmit power per destination from the signal level of the
+/// frames and acknowledgements received from it.
+#[derive(Debug)]
+pub struct TransmitPowerControl {
+    config: TpcConfig,
+    powers: HashMap<Address, i8>,
+    pending: HashMap<u8, TxReport>,
+}
+
+impl TransmitPowerControl {
+    pub fn new(config: TpcConfig) -> TransmitPowerControl {
+        TransmitPowerControl {
+            config,
+            powers: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn power(&self, destination: &Address) -> i8 {
+        let power = self
+            .powers
+            .get(destination)
+            .copied()
+            .unwrap_or(self.config.initial_power);
+        self.clamp(power)
+    }
+
+    /// Sets the power of the request. PWR_CHAN makes the device use the channel
+    /// of the request as well, so it has to hold the operating channel.
+    pub fn apply(&mut self, request: &mut DataReq) -> TxReport {
+        let power = self.power(&request.dest_address);
+        request.tx_option.insert(TxOption::PWR_CHAN);
+        request.power = power as u8;
+
+        let report = TxReport {
+            handle: request.handle,
+            destination: request.dest_address,
+            power,
+            status: None,
+        };
+        self.pending.insert(request.handle, report);
+        report
+    }
+
+    pub fn on_data_cnf(&mut self, confirm: &DataCnf) -> Option<TxReport> {
+        let mut report = self.pending.remove(&confirm.handle)?;
+        report.status = Some(confirm.status);
+
+        match confirm.status {
+            Status::Success => self.feedback(report.destination, confirm.rssi.dbm()),
+            Status::NoAck => self.adjust(report.destination, i16::from(self.config.step)),
+            _ => (),
+        }
+
+        Some(report)
+    }
+
+    pub fn on_data_ind(&mut self, indication: &DataInd) {
+        if indication.src_address != Address::None {
+            self.feedback(indication.src_address, indication.rssi.dbm());
+        }
+    }
+
+    pub fn forget(&mut self, destination: &Address) {
+        self.powers.remove(destination);
+    }
+
+    fn feedback(&mut self, neighbor: Address, rssi: i8) {
+        let error = i16::from(rssi) - i16::from(self.config.target_rssi);
+        let hysteresis = i16::from(self.config.hysteresis);
+        let step = i16::from(self.config.step);
+        if error > hysteresis {
+            self.adjust(neighbor, -step);
+        } else if error < -hysteresis {
+            self.adjust(neighbor, step);
+        }
+    }
+
+    fn adjust(&mut self, neighbor: Address, delta: i16) {
+        let power = i16::from(self.power(&neighbor)) + delta;
+        let power = self.clamp(power.clamp(i16::from(i8::MIN), i16::from(i8::MAX)) as i8);
+        self.powers.insert(neighbor, power);
+    }
+
+    fn clamp(&self, power: i8) -> i8 {
+        power.max(self.config.min_power).min(self.config.max_power)
+    }
+}
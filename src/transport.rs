@@ -0,0 +1,663 @@
+use crate::frame::{CommandCode, MTExtendedHeader, MTFrame, MTHeader};
+use crate::parser::Parser;
+use crate::types::{CommandType, MTSubsystem, SYSCommandId};
+use std::fs;
+use std::io::{self, IoSlice, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Baud rates the CC13xx UART driver accepts for the MT link.
+pub const STANDARD_BAUD_RATES: [u32; 7] = [9600, 19200, 38400, 57600, 115200, 230400, 460800];
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum FlowControl {
+    None,
+    // Hardware handshake on the RTS and CTS lines, needed from 230400 baud
+    // on so the coprocessor can hold the host off while its buffers drain.
+    RtsCts,
+}
+
+/// Line settings of the serial port, always 8 data bits, no parity and one
+/// stop bit.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct SerialConfig {
+    pub baud_rate: u32,
+    pub flow_control: FlowControl,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        SerialConfig {
+            baud_rate: 115200,
+            flow_control: FlowControl::None,
+        }
+    }
+}
+
+impl SerialConfig {
+    pub fn new(baud_rate: u32, flow_control: FlowControl) -> SerialConfig {
+        SerialConfig {
+            baud_rate,
+            flow_control,
+        }
+    }
+
+    pub fn validate(&self) -> io::Result<()> {
+        if !STANDARD_BAUD_RATES.contains(&self.baud_rate) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported baud rate {}", self.baud_rate),
+            ));
+        }
+        if self.baud_rate > 115200 && self.flow_control == FlowControl::None {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} baud needs RTS/CTS flow control", self.baud_rate),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Time on the wire for `bytes` bytes, ten bits each. Useful to scale
+    /// request timeouts with the frame size.
+    pub fn transmit_time(&self, bytes: usize) -> Duration {
+        Duration::from_micros(bytes as u64 * 10 * 1_000_000 / u64::from(self.baud_rate.max(1)))
+    }
+}
+
+/// Serial ports able to change their line settings, implemented for
+/// whatever serial library the application uses.
+pub trait SerialPort: Write {
+    fn configure(&mut self, config: &SerialConfig) -> io::Result<()>;
+}
+
+/// Bytes sent ahead of a frame when the link has been idle, for coprocessors
+/// that sleep between transfers and drop whatever arrives while they wake
+/// up. `preamble` is what the coprocessor is configured to wake on, often
+/// a few 0x00 bytes, and `settle` the time it needs before reading frames.
+#[derive(Debug, Clone)]
+pub struct Wakeup {
+    pub preamble: Vec<u8>,
+    pub settle: Duration,
+    // The preamble is only sent when nothing was written for this long.
+    pub idle: Duration,
+    pub sleep: fn(Duration),
+}
+
+impl Wakeup {
+    pub fn new(preamble: Vec<u8>, settle: Duration, idle: Duration) -> Wakeup {
+        Wakeup {
+            preamble,
+            settle,
+            idle,
+            sleep: std::thread::sleep,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum FlushPolicy {
+    // Every frame is written as soon as it is queued.
+    Immediate,
+    // Written once this many frames are queued.
+    Frames(usize),
+    // Written once at least this many bytes are queued.
+    Bytes(usize),
+    // Written only by `flush`.
+    Manual,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum WriteMode {
+    // Frames are copied into one buffer and written with `write_all`.
+    Coalesced,
+    // Frames are passed to `write_vectored` without copying.
+    Vectored,
+}
+
+/// Queues UART transport frames and writes them in batches, so streaming
+/// many small requests costs one write call per batch instead of one per
+/// frame. Frames still queued when the writer is dropped are lost, flush it
+/// first.
+#[derive(Debug)]
+pub struct FrameWriter<W: Write> {
+    inner: W,
+    policy: FlushPolicy,
+    mode: WriteMode,
+    frames: Vec<Vec<u8>>,
+    queued_bytes: usize,
+    writes: u64,
+    wakeup: Option<Wakeup>,
+    last_write: Option<Instant>,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(inner: W, policy: FlushPolicy) -> FrameWriter<W> {
+        FrameWriter::with_mode(inner, policy, WriteMode::Coalesced)
+    }
+
+    pub fn with_mode(inner: W, policy: FlushPolicy, mode: WriteMode) -> FrameWriter<W> {
+        FrameWriter {
+            inner,
+            policy,
+            mode,
+            frames: Vec::new(),
+            queued_bytes: 0,
+            writes: 0,
+            wakeup: None,
+            last_write: None,
+        }
+    }
+
+    pub fn set_wakeup(&mut self, wakeup: Option<Wakeup>) {
+        self.wakeup = wakeup;
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    pub fn policy(&self) -> FlushPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: FlushPolicy) {
+        self.policy = policy;
+    }
+
+    /// Frames waiting for the next flush.
+    pub fn queued(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Write calls made on the inner writer so far.
+    pub fn writes(&self) -> u64 {
+        self.writes
+    }
+
+    /// Queues `frame` and flushes when the policy says so. Payloads longer
+    /// than 255 bytes are queued as the blocks of an extended frame, those
+    /// `into_blocks` refuses fail with `InvalidInput`.
+    pub fn send(&mut self, frame: &MTFrame) -> io::Result<()> {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            command = ?frame.header.command,
+            length = frame.payload.len(),
+            "frame sent"
+        );
+
+        let stack_id = frame
+            .extended_header
+            .as_ref()
+            .map_or(0, MTExtendedHeader::stack_id);
+        let blocks = frame
+            .clone()
+            .into_blocks(stack_id)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+        for block in blocks {
+            let bytes = block.encode_to_uart_transport_frame();
+            self.queued_bytes += bytes.len();
+            self.frames.push(bytes);
+        }
+
+        let due = match self.policy {
+            FlushPolicy::Immediate => true,
+            FlushPolicy::Frames(count) => self.frames.len() >= count,
+            FlushPolicy::Bytes(count) => self.queued_bytes >= count,
+            FlushPolicy::Manual => false,
+        };
+        if due {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes every queued frame and flushes the inner writer. After an
+    /// error, vectored writes keep the frames not written yet queued. A failed
+    /// coalesced write drops the batch, as `write_all` does not tell how much
+    /// of it went out.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if !self.frames.is_empty() {
+            self.wake()?;
+            let result = match self.mode {
+                WriteMode::Coalesced => self.write_coalesced(),
+                WriteMode::Vectored => self.write_vectored(),
+            };
+            self.last_write = Some(Instant::now());
+            result?;
+        }
+        self.inner.flush()
+    }
+
+    /// Flushes and returns the inner writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush()?;
+        Ok(self.inner)
+    }
+
+    fn wake(&mut self) -> io::Result<()> {
+        let last_write = self.last_write;
+        let wakeup = match &self.wakeup {
+            Some(wakeup) if last_write.map_or(true, |last| last.elapsed() >= wakeup.idle) => wakeup,
+            _ => return Ok(()),
+        };
+        self.writes += 1;
+        self.inner.write_all(&wakeup.preamble)?;
+        self.inner.flush()?;
+        (wakeup.sleep)(wakeup.settle);
+        Ok(())
+    }
+
+    fn write_coalesced(&mut self) -> io::Result<()> {
+        let buffer = self.frames.concat();
+        self.frames.clear();
+        self.queued_bytes = 0;
+        self.writes += 1;
+        self.inner.write_all(&buffer)
+    }
+
+    fn write_vectored(&mut self) -> io::Result<()> {
+        // Index of the first frame not fully written and the bytes of it
+        // already written.
+        let (mut first, mut offset) = (0, 0);
+        let result = loop {
+            if first == self.frames.len() {
+                break Ok(());
+            }
+            let slices: Vec<_> = std::iter::once(&self.frames[first][offset..])
+                .chain(self.frames[first + 1..].iter().map(|frame| &frame[..]))
+                .map(IoSlice::new)
+                .collect();
+            self.writes += 1;
+            let mut written = match self.inner.write_vectored(&slices) {
+                Ok(0) => break Err(io::Error::from(io::ErrorKind::WriteZero)),
+                Ok(written) => written,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => break Err(error),
+            };
+            while written > 0 {
+                let left = self.frames[first].len() - offset;
+                if written < left {
+                    offset += written;
+                    break;
+                }
+                written -= left;
+                first += 1;
+                offset = 0;
+            }
+        };
+
+        self.frames.drain(..first);
+        if let Some(frame) = self.frames.first_mut() {
+            frame.drain(..offset);
+        }
+        self.queued_bytes = self.frames.iter().map(Vec::len).sum();
+        result
+    }
+}
+
+impl<W: SerialPort> FrameWriter<W> {
+    /// Writes the queued frames with the old settings, then applies `config`.
+    pub fn configure(&mut self, config: &SerialConfig) -> io::Result<()> {
+        config.validate()?;
+        self.flush()?;
+        self.inner.configure(config)
+    }
+}
+
+impl<W: SerialPort + Read> FrameWriter<W> {
+    /// Finds the baud rate the coprocessor listens at by sending SYS_PING at
+    /// each of `rates` in turn, with the wake preamble if one is set, and
+    /// waiting up to `timeout` for the response. The port is left at the
+    /// first rate answered, which is returned. Rates `flow_control` is not
+    /// valid for are skipped. Bytes read while probing are dropped.
+    pub fn auto_baud(
+        &mut self,
+        rates: &[u32],
+        flow_control: FlowControl,
+        timeout: Duration,
+    ) -> io::Result<SerialConfig> {
+        let ping = MTFrame {
+            header: MTHeader {
+                length: 0,
+                command: CommandCode {
+                    is_extended: false,
+                    cmd_type: CommandType::SREQ,
+                    subsystem: MTSubsystem::SYS,
+                    id: SYSCommandId::PingReq as u8,
+                },
+            },
+            extended_header: None,
+            payload: Vec::new(),
+        };
+        for rate in rates.iter() {
+            let config = SerialConfig::new(*rate, flow_control);
+            if config.validate().is_err() {
+                continue;
+            }
+            self.configure(&config)?;
+            // The link may have been silent at this rate for any time.
+            self.last_write = None;
+            self.send(&ping)?;
+            self.flush()?;
+            if self.wait_for_ping(timeout)? {
+                return Ok(config);
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no answer to SYS_PING at any baud rate",
+        ))
+    }
+
+    fn wait_for_ping(&mut self, timeout: Duration) -> io::Result<bool> {
+        let deadline = Instant::now() + timeout;
+        let mut parser = Parser::new();
+        let mut buffer = [0x00; 64];
+        while Instant::now() < deadline {
+            let read = match self.inner.read(&mut buffer) {
+                Ok(read) => read,
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::TimedOut
+                            | io::ErrorKind::WouldBlock
+                            | io::ErrorKind::Interrupted
+                    ) =>
+                {
+                    0
+                }
+                Err(error) => return Err(error),
+            };
+            let answered = parser.feed_slice(&buffer[..read]).iter().any(|frame| {
+                frame.as_ref().is_ok_and(|frame| {
+                    frame.header.command.is(
+                        CommandType::SRSP,
+                        MTSubsystem::SYS,
+                        SYSCommandId::PingReq as u8,
+                    )
+                })
+            });
+            if answered {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// The MRDY and SRDY lines of the NPI power saving handshake, implemented
+/// over the GPIO library of the host. Both lines are active low, `true`
+/// below means asserted.
+pub trait HandshakeLines {
+    fn set_mrdy(&mut self, asserted: bool) -> io::Result<()>;
+    fn srdy(&mut self) -> io::Result<bool>;
+}
+
+/// Serial port of a coprocessor that sleeps between transfers. Before
+/// writing, MRDY is asserted and the coprocessor wakes and asserts SRDY once
+/// it listens. `flush` ends the transfer by releasing MRDY, so wrap it in a
+/// `FrameWriter` to handshake once per batch. Transfers started by the
+/// coprocessor are handled by calling `service` while waiting for data.
+#[derive(Debug)]
+pub struct PowerManaged<P, L: HandshakeLines> {
+    port: P,
+    lines: L,
+    timeout: Duration,
+    sleep: fn(Duration),
+    mrdy: bool,
+    // MRDY is asserted for a transfer the coprocessor started.
+    reading: bool,
+}
+
+impl<P, L: HandshakeLines> PowerManaged<P, L> {
+    /// `timeout` bounds the wait for SRDY.
+    pub fn new(port: P, lines: L, timeout: Duration) -> PowerManaged<P, L> {
+        PowerManaged {
+            port,
+            lines,
+            timeout,
+            sleep: std::thread::sleep,
+            mrdy: false,
+            reading: false,
+        }
+    }
+
+    pub fn set_sleep(&mut self, sleep: fn(Duration)) {
+        self.sleep = sleep;
+    }
+
+    pub fn get_ref(&self) -> &P {
+        &self.port
+    }
+
+    pub fn get_mut(&mut self) -> &mut P {
+        &mut self.port
+    }
+
+    pub fn lines(&mut self) -> &mut L {
+        &mut self.lines
+    }
+
+    pub fn into_inner(self) -> (P, L) {
+        (self.port, self.lines)
+    }
+
+    /// Answers SRDY raised by the coprocessor by asserting MRDY, and releases
+    /// MRDY again once the coprocessor lowers SRDY at the end of its
+    /// transfer. Returns whether a transfer from the coprocessor is underway.
+    pub fn service(&mut self) -> io::Result<bool> {
+        let srdy = self.lines.srdy()?;
+        if srdy && !self.mrdy {
+            self.set_mrdy(true)?;
+            self.reading = true;
+        } else if !srdy && self.reading {
+            self.set_mrdy(false)?;
+            self.reading = false;
+        }
+        Ok(self.reading)
+    }
+
+    fn set_mrdy(&mut self, asserted: bool) -> io::Result<()> {
+        self.lines.set_mrdy(asserted)?;
+        self.mrdy = asserted;
+        Ok(())
+    }
+
+    fn wake(&mut self) -> io::Result<()> {
+        if self.mrdy {
+            return Ok(());
+        }
+        self.set_mrdy(true)?;
+        let start = Instant::now();
+        while !self.lines.srdy()? {
+            if start.elapsed() >= self.timeout {
+                self.set_mrdy(false)?;
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "coprocessor did not assert SRDY",
+                ));
+            }
+            (self.sleep)(Duration::from_millis(1));
+        }
+        Ok(())
+    }
+}
+
+impl<P: Write, L: HandshakeLines> Write for PowerManaged<P, L> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.wake()?;
+        self.port.write(buffer)
+    }
+
+    fn write_vectored(&mut self, buffers: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.wake()?;
+        self.port.write_vectored(buffers)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.port.flush();
+        if self.mrdy && !self.reading {
+            self.set_mrdy(false)?;
+        }
+        result
+    }
+}
+
+impl<P: SerialPort, L: HandshakeLines> SerialPort for PowerManaged<P, L> {
+    fn configure(&mut self, config: &SerialConfig) -> io::Result<()> {
+        self.port.configure(config)
+    }
+}
+
+impl<P: Read, L: HandshakeLines> Read for PowerManaged<P, L> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.port.read(buffer)
+    }
+}
+
+pub const TI_VENDOR_ID: u16 = 0x0451;
+const FTDI_VENDOR_ID: u16 = 0x0403;
+
+// Debug probes found on the LaunchPads that carry a CC13xx or CC26xx. The
+// XDS110 has two serial interfaces: the first is the application UART the
+// coprocessor talks on, the other is an auxiliary data port.
+const KNOWN_DEVICES: [(u16, u16, &str); 3] = [
+    (TI_VENDOR_ID, 0xbef3, "XDS110"),
+    (TI_VENDOR_ID, 0xbef4, "XDS110 (CMSIS-DAP)"),
+    (FTDI_VENDOR_ID, 0xa6d0, "XDS100v3"),
+];
+
+/// Serial port with a likely coprocessor behind it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PortCandidate {
+    pub path: PathBuf,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub interface: Option<u8>,
+    // Name of the debug probe, e.g. "XDS110".
+    pub probe: &'static str,
+    pub serial_number: Option<String>,
+}
+
+impl PortCandidate {
+    /// Whether this is the application UART rather than an auxiliary port.
+    pub fn is_application_uart(&self) -> bool {
+        self.interface.map_or(true, |interface| interface == 0)
+    }
+}
+
+/// Name of the TI debug probe with these USB ids, if it is one. For ports
+/// listed by other means, as on Windows and macOS.
+pub fn identify(vendor_id: u16, product_id: u16) -> Option<&'static str> {
+    KNOWN_DEVICES
+        .iter()
+        .find(|(vendor, product, _)| *vendor == vendor_id && *product == product_id)
+        .map(|(_, _, name)| *name)
+}
+
+/// Lists the serial ports behind TI debug probes, application UARTs first.
+/// Each may then be opened and checked with `client::ping`. With the
+/// `serialport` feature the ports come from `serialport::available_ports`,
+/// falling back to sysfs on Linux when it fails. Without it only Linux can
+/// be enumerated, elsewhere this fails with `ErrorKind::Unsupported` and
+/// `identify` filters the ports listed by the application.
+pub fn discover() -> io::Result<Vec<PortCandidate>> {
+    #[cfg(feature = "serialport")]
+    match serialport::available_ports() {
+        Ok(ports) => return Ok(candidates_from(ports)),
+        Err(error) if !cfg!(target_os = "linux") => return Err(error.into()),
+        Err(_) => (),
+    }
+
+    if cfg!(target_os = "linux") {
+        discover_in(Path::new("/sys"), Path::new("/dev"))
+    } else {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// `discover` over a sysfs tree mounted at `sys`, with device nodes in `dev`.
+pub fn discover_in(sys: &Path, dev: &Path) -> io::Result<Vec<PortCandidate>> {
+    let mut candidates = Vec::new();
+    for entry in fs::read_dir(sys.join("class/tty"))? {
+        let entry = entry?;
+        // Only ttys with a USB device behind them.
+        let device = match fs::canonicalize(entry.path().join("device")) {
+            Ok(device) => device,
+            Err(_) => continue,
+        };
+        let usb = match device
+            .ancestors()
+            .find(|path| path.join("idVendor").is_file())
+        {
+            Some(usb) => usb,
+            None => continue,
+        };
+        let (vendor_id, product_id) = match (read_hex(usb, "idVendor"), read_hex(usb, "idProduct"))
+        {
+            (Some(vendor_id), Some(product_id)) => (vendor_id, product_id),
+            _ => continue,
+        };
+        let probe = match identify(vendor_id, product_id) {
+            Some(probe) => probe,
+            None => continue,
+        };
+        let interface = device
+            .ancestors()
+            .take_while(|path| *path != usb)
+            .find_map(|path| read_hex(path, "bInterfaceNumber"))
+            .map(|interface| interface as u8);
+        candidates.push(PortCandidate {
+            path: dev.join(entry.file_name()),
+            vendor_id,
+            product_id,
+            interface,
+            probe,
+            serial_number: read_attribute(usb, "serial"),
+        });
+    }
+    sort_candidates(&mut candidates);
+    Ok(candidates)
+}
+
+/// The ports among `ports` behind TI debug probes, application UARTs first.
+/// On macOS serialport reports the data interface of a CDC port rather than
+/// the communication one, so `is_application_uart` may not hold there.
+#[cfg(feature = "serialport")]
+pub fn candidates_from(ports: Vec<serialport::SerialPortInfo>) -> Vec<PortCandidate> {
+    let mut candidates: Vec<_> = ports
+        .into_iter()
+        .filter_map(|port| match port.port_type {
+            serialport::SerialPortType::UsbPort(usb) => Some(PortCandidate {
+                path: PathBuf::from(port.port_name),
+                vendor_id: usb.vid,
+                product_id: usb.pid,
+                interface: usb.interface,
+                probe: identify(usb.vid, usb.pid)?,
+                serial_number: usb.serial_number,
+            }),
+            _ => None,
+        })
+        .collect();
+    sort_candidates(&mut candidates);
+    candidates
+}
+
+fn sort_candidates(candidates: &mut [PortCandidate]) {
+    candidates.sort_by_key(|candidate| (!candidate.is_application_uart(), candidate.path.clone()));
+}
+
+fn read_attribute(path: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(path.join(name))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+fn read_hex(path: &Path, name: &str) -> Option<u16> {
+    u16::from_str_radix(&read_attribute(path, name)?, 16).ok()
+}
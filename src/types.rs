@@ -1,12 +1,126 @@
 use crate::error::Error;
-use bytes::{Buf, BufMut};
+use crate::reader::DecodeOptions;
+use crate::reader::Reader;
+use crate::writer::BufMut;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::iter::FromIterator;
+use core::ops::RangeInclusive;
+use core::str::FromStr;
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
-use std::io::Cursor;
-use std::io::Read;
+
+// Enums whose value set grows with new coprocessor firmware. A value the
+// crate has no name for is a decode error unless the cursor's options allow
+// unknown values, then it decodes into `Unknown` and encodes back unchanged.
+macro_rules! open_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident: $repr:ident {
+            $($variant:ident = $value:expr,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub enum $name {
+            $($variant,)*
+            Unknown($repr),
+        }
+
+        impl $name {
+            pub fn from_raw(value: $repr) -> $name {
+                match value {
+                    $(x if x == $value => $name::$variant,)*
+                    value => $name::Unknown(value),
+                }
+            }
+
+            pub fn raw(&self) -> $repr {
+                match *self {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(value) => value,
+                }
+            }
+
+            pub fn is_unknown(&self) -> bool {
+                matches!(self, $name::Unknown(_))
+            }
+
+            fn from_decoded(
+                value: $repr,
+                options: DecodeOptions,
+                error: fn($repr) -> Error,
+            ) -> Result<$name, Error> {
+                match $name::from_raw(value) {
+                    $name::Unknown(value) if !options.unknown_values => Err(error(value)),
+                    decoded => Ok(decoded),
+                }
+            }
+        }
+
+        impl num_traits::FromPrimitive for $name {
+            fn from_i64(value: i64) -> Option<$name> {
+                <$repr as TryFrom<_>>::try_from(value)
+                    .ok()
+                    .map($name::from_raw)
+                    .filter(|decoded| !decoded.is_unknown())
+            }
+
+            fn from_u64(value: u64) -> Option<$name> {
+                <$repr as TryFrom<_>>::try_from(value)
+                    .ok()
+                    .map($name::from_raw)
+                    .filter(|decoded| !decoded.is_unknown())
+            }
+        }
+    };
+}
+
+// Decodes a fixed size value from a slice holding that value and nothing
+// else, the cursor based `try_decode` being the one payloads use.
+macro_rules! impl_try_from_slice {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl TryFrom<&[u8]> for $name {
+                type Error = Error;
+
+                fn try_from(bytes: &[u8]) -> Result<$name, Error> {
+                    let mut cursor = Reader::new(bytes);
+                    let value = $name::try_decode(&mut cursor)?;
+                    match cursor.remaining() {
+                        0 => Ok(value),
+                        extra => Err(Error::TrailingBytes(extra)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_slice!(
+    PanId,
+    ShortAddress,
+    ExtendedAddress,
+    Address,
+    KeySource,
+    Security,
+    PanDescriptor,
+    SuperframeSpec,
+    CoexistenceSpec,
+    GtkHash,
+    NetName,
+    ChannelMask,
+);
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MACCommandId {
     DataCnf = 0x84,
     DataInd = 0x85,
@@ -54,16 +168,24 @@ pub enum MACCommandId {
     FHStartReq = 0x41,
     FHGetReq = 0x42,
     FHSetReq = 0x43,
+    SrcMatchEnableReq = 0x45,
+    SrcMatchAddEntryReq = 0x46,
+    SrcMatchDeleteEntryReq = 0x47,
+    SrcMatchAckAllPendingReq = 0x49,
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RPCCommandId {
     MTCommandError = 0x00,
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SYSCommandId {
     ResetReq = 0x00,
     ResetInd = 0x80,
@@ -79,7 +201,9 @@ pub enum SYSCommandId {
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UTILCommandId {
     Loopback = 0x10,
     CallbackSubCmd = 0x06,
@@ -87,7 +211,86 @@ pub enum UTILCommandId {
     Random = 0x12,
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+#[allow(non_camel_case_types)]
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum APPCommandId {
+    AppMsg = 0x00,
+}
+
+// CMD1 of the MT header. The same id means different commands in different
+// subsystems, the typed views below pick the subsystem explicitly.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CommandId(pub u8);
+
+impl CommandId {
+    pub fn u8(&self) -> u8 {
+        self.0
+    }
+
+    pub fn mac(&self) -> Option<MACCommandId> {
+        FromPrimitive::from_u8(self.0)
+    }
+
+    pub fn sys(&self) -> Option<SYSCommandId> {
+        FromPrimitive::from_u8(self.0)
+    }
+
+    pub fn util(&self) -> Option<UTILCommandId> {
+        FromPrimitive::from_u8(self.0)
+    }
+
+    pub fn rpc(&self) -> Option<RPCCommandId> {
+        FromPrimitive::from_u8(self.0)
+    }
+
+    pub fn app(&self) -> Option<APPCommandId> {
+        FromPrimitive::from_u8(self.0)
+    }
+}
+
+impl From<u8> for CommandId {
+    fn from(id: u8) -> Self {
+        CommandId(id)
+    }
+}
+
+impl From<MACCommandId> for CommandId {
+    fn from(id: MACCommandId) -> Self {
+        CommandId(id as u8)
+    }
+}
+
+impl From<SYSCommandId> for CommandId {
+    fn from(id: SYSCommandId) -> Self {
+        CommandId(id as u8)
+    }
+}
+
+impl From<UTILCommandId> for CommandId {
+    fn from(id: UTILCommandId) -> Self {
+        CommandId(id as u8)
+    }
+}
+
+impl From<RPCCommandId> for CommandId {
+    fn from(id: RPCCommandId) -> Self {
+        CommandId(id as u8)
+    }
+}
+
+impl From<APPCommandId> for CommandId {
+    fn from(id: APPCommandId) -> Self {
+        CommandId(id as u8)
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CommandType {
     POLL = 0,
     SREQ = 1,
@@ -95,7 +298,9 @@ pub enum CommandType {
     SRSP = 3,
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MTExtendedHeaderStatus {
     Success = 0,                           // Success
     ResendLastFrame = 1,                   // Request - resend last frame
@@ -109,9 +314,8 @@ pub enum MTExtendedHeaderStatus {
 }
 
 impl MTExtendedHeaderStatus {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidExtendedHeaderStatus(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        MTExtendedHeaderStatus::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -119,104 +323,257 @@ impl MTExtendedHeaderStatus {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for MTExtendedHeaderStatus {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<MTExtendedHeaderStatus, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidExtendedHeaderStatus(value))
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MTSubsystem {
     RPC = 0,
     SYS = 1,
     MAC = 2,
+    NWK = 3,
+    AF = 4,
+    ZDO = 5,
+    SAPI = 6,
     UTIL = 7,
-}
-
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
-pub enum Status {
-    Success = 0x00,
-    Unsupported = 0x18,
-    BadState = 0x19,
-    NoResources = 0x1A,
-    RPCCommandSubsystemError = 0x25,
-    RPCCommandIdError = 0x26,
-    RPCCommandLengthError = 0x27,
-    RPCCommandUnsupportedType = 0x28,
-    FHAPIError = 0x61,
-    FHAPINotSupportedIE = 0x62,
-    FHAPINotInAsync = 0x63,
-    FHAPINoEntryInTheNeighbor = 0x64,
-    FHAPIOutSlot = 0x65,
-    FHAPIInvalidAddress = 0x66,
-    FHAPIInvalidFormat = 0x67,
-    FHAPINotSupportedPIB = 0x68,
-    FHAPIReadOnlyPIB = 0x69,
-    FHAPIInvalidParamPIB = 0x6A,
-    FHAPIInvalidFrameType = 0x6B,
-    FHAPIExpiredNode = 0x6C,
-    CounterError = 0xDB,
-    ImproperKeyType = 0xDC,
-    ImproperSecurityLevel = 0xDD,
-    UnsupportedLegacy = 0xDE,
-    UnsupportedSecurity = 0xDF,
-    BeaconLoss = 0xE0,
-    ChannelAccessFailure = 0xE1,
-    Denied = 0xE2,
-    DisableTRXFailure = 0xE3,
-    SecurityError = 0xE4,
-    FrameTooLong = 0xE5,
-    InvalidGTS = 0xE6,
-    InvalidHandle = 0xE7,
-    InvalidParameter = 0xE8,
-    NoAck = 0xE9,
-    NoBeacon = 0xEA,
-    NoData = 0xEB,
-    NoShortAddress = 0xEC,
-    OutOfCAP = 0xED,
-    PANIdConflict = 0xEE,
-    Realignment = 0xEF,
-    TransactionExpired = 0xF0,
-    TransactionOverflow = 0xF1,
-    TxActive = 0xF2,
-    UnavailableKey = 0xF3,
-    UnsupportedAttribute = 0xF4,
-    InvalidAddress = 0xF5,
-    OnTimeTooLong = 0xF6,
-    PastTime = 0xF7,
-    TrackingOff = 0xF8,
-    InvalidIndex = 0xF9,
-    LimitReached = 0xFA,
-    ReadOnly = 0xFB,
-    ScanInProgress = 0xFC,
-    SuperframeOverlap = 0xFD,
-    AutoAckPendingAllOn = 0xFE,
-    AutoAckPendingAllOff = 0xFF,
+    DEBUG = 8,
+    APP = 9,
+}
+
+open_enum! {
+    pub enum Status: u8 {
+        Success = 0x00,
+        Unsupported = 0x18,
+        BadState = 0x19,
+        NoResources = 0x1A,
+        RPCCommandSubsystemError = 0x25,
+        RPCCommandIdError = 0x26,
+        RPCCommandLengthError = 0x27,
+        RPCCommandUnsupportedType = 0x28,
+        FHAPIError = 0x61,
+        FHAPINotSupportedIE = 0x62,
+        FHAPINotInAsync = 0x63,
+        FHAPINoEntryInTheNeighbor = 0x64,
+        FHAPIOutSlot = 0x65,
+        FHAPIInvalidAddress = 0x66,
+        FHAPIInvalidFormat = 0x67,
+        FHAPINotSupportedPIB = 0x68,
+        FHAPIReadOnlyPIB = 0x69,
+        FHAPIInvalidParamPIB = 0x6A,
+        FHAPIInvalidFrameType = 0x6B,
+        FHAPIExpiredNode = 0x6C,
+        CounterError = 0xDB,
+        ImproperKeyType = 0xDC,
+        ImproperSecurityLevel = 0xDD,
+        UnsupportedLegacy = 0xDE,
+        UnsupportedSecurity = 0xDF,
+        BeaconLoss = 0xE0,
+        ChannelAccessFailure = 0xE1,
+        Denied = 0xE2,
+        DisableTRXFailure = 0xE3,
+        SecurityError = 0xE4,
+        FrameTooLong = 0xE5,
+        InvalidGTS = 0xE6,
+        InvalidHandle = 0xE7,
+        InvalidParameter = 0xE8,
+        NoAck = 0xE9,
+        NoBeacon = 0xEA,
+        NoData = 0xEB,
+        NoShortAddress = 0xEC,
+        OutOfCAP = 0xED,
+        PANIdConflict = 0xEE,
+        Realignment = 0xEF,
+        TransactionExpired = 0xF0,
+        TransactionOverflow = 0xF1,
+        TxActive = 0xF2,
+        UnavailableKey = 0xF3,
+        UnsupportedAttribute = 0xF4,
+        InvalidAddress = 0xF5,
+        OnTimeTooLong = 0xF6,
+        PastTime = 0xF7,
+        TrackingOff = 0xF8,
+        InvalidIndex = 0xF9,
+        LimitReached = 0xFA,
+        ReadOnly = 0xFB,
+        ScanInProgress = 0xFC,
+        SuperframeOverlap = 0xFD,
+        AutoAckPendingAllOn = 0xFE,
+        AutoAckPendingAllOff = 0xFF,
+    }
 }
 
 impl Status {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidStatus(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let value = cursor.read_u8()?;
+        Status::from_decoded(value, cursor.options(), Error::InvalidStatus)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
-        buffer.put_u8(*self as u8);
+        buffer.put_u8(self.raw());
     }
 
     pub fn to_result(self) -> Result<(), Status> {
-        if self == Status::Success {
+        self.into_result()
+    }
+
+    pub fn is_success(&self) -> bool {
+        *self == Status::Success
+    }
+
+    pub fn into_result(self) -> Result<(), Status> {
+        if self.is_success() {
             Ok(())
         } else {
             Err(self)
         }
     }
+
+    pub fn is_security_error(&self) -> bool {
+        matches!(
+            self,
+            Status::CounterError
+                | Status::ImproperKeyType
+                | Status::ImproperSecurityLevel
+                | Status::UnsupportedLegacy
+                | Status::UnsupportedSecurity
+                | Status::SecurityError
+                | Status::UnavailableKey
+        )
+    }
+
+    // Errors reported by the frequency hopping API, 0x61 to 0x6C.
+    pub fn is_fh_error(&self) -> bool {
+        (Status::FHAPIError.raw()..=Status::FHAPIExpiredNode.raw()).contains(&self.raw())
+    }
+
+    pub fn is_rpc_error(&self) -> bool {
+        matches!(
+            self,
+            Status::RPCCommandSubsystemError
+                | Status::RPCCommandIdError
+                | Status::RPCCommandLengthError
+                | Status::RPCCommandUnsupportedType
+        )
+    }
+
+    pub fn category(&self) -> StatusCategory {
+        match self {
+            Status::Success => StatusCategory::Success,
+            Status::Unknown(_) => StatusCategory::Unknown,
+            x if x.is_rpc_error() => StatusCategory::Rpc,
+            x if x.is_fh_error() => StatusCategory::Fh,
+            x if x.is_security_error() => StatusCategory::Security,
+            _ => StatusCategory::Mac,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Status::Success => "operation successful",
+            Status::Unsupported => "operation not supported in the current configuration",
+            Status::BadState => "operation not allowed in the current state",
+            Status::NoResources => "no resources available for the operation",
+            Status::RPCCommandSubsystemError => "unknown command subsystem",
+            Status::RPCCommandIdError => "unknown command id",
+            Status::RPCCommandLengthError => "invalid command length",
+            Status::RPCCommandUnsupportedType => "unsupported command type",
+            Status::FHAPIError => "frequency hopping error",
+            Status::FHAPINotSupportedIE => "information element not supported",
+            Status::FHAPINotInAsync => "not in an asynchronous operation",
+            Status::FHAPINoEntryInTheNeighbor => "destination not in the neighbor table",
+            Status::FHAPIOutSlot => "outside of the unicast slot",
+            Status::FHAPIInvalidAddress => "invalid frequency hopping address",
+            Status::FHAPIInvalidFormat => "invalid information element format",
+            Status::FHAPINotSupportedPIB => "frequency hopping PIB attribute not supported",
+            Status::FHAPIReadOnlyPIB => "frequency hopping PIB attribute is read only",
+            Status::FHAPIInvalidParamPIB => "invalid frequency hopping PIB parameter",
+            Status::FHAPIInvalidFrameType => "invalid frame type",
+            Status::FHAPIExpiredNode => "neighbor entry expired",
+            Status::CounterError => "frame counter of the received frame is invalid",
+            Status::ImproperKeyType => "key not allowed for the frame type",
+            Status::ImproperSecurityLevel => "security level below the required minimum",
+            Status::UnsupportedLegacy => "frame secured with unsupported 802.15.4-2003 security",
+            Status::UnsupportedSecurity => "security level not supported",
+            Status::BeaconLoss => "beacons from the coordinator were lost",
+            Status::ChannelAccessFailure => "channel busy, CSMA-CA failed",
+            Status::Denied => "GTS request denied by the coordinator",
+            Status::DisableTRXFailure => "failed to disable the transceiver",
+            Status::SecurityError => "failed to secure or unsecure the frame",
+            Status::FrameTooLong => "frame too long",
+            Status::InvalidGTS => "invalid GTS",
+            Status::InvalidHandle => "no transaction with this MSDU handle",
+            Status::InvalidParameter => "invalid parameter",
+            Status::NoAck => "no acknowledgement received",
+            Status::NoBeacon => "no beacon received",
+            Status::NoData => "no data received after a data request",
+            Status::NoShortAddress => "no short address allocated",
+            Status::OutOfCAP => "transaction does not fit the contention access period",
+            Status::PANIdConflict => "PAN identifier conflict detected",
+            Status::Realignment => "coordinator realignment received",
+            Status::TransactionExpired => "indirect transaction expired before being polled",
+            Status::TransactionOverflow => "no room for the indirect transaction",
+            Status::TxActive => "transmitter busy",
+            Status::UnavailableKey => "no key for the frame",
+            Status::UnsupportedAttribute => "PIB attribute not supported",
+            Status::InvalidAddress => "invalid address",
+            Status::OnTimeTooLong => "receiver on time too long",
+            Status::PastTime => "requested time already passed",
+            Status::TrackingOff => "not tracking beacons",
+            Status::InvalidIndex => "table index out of range",
+            Status::LimitReached => "scan stopped at the PAN descriptor limit",
+            Status::ReadOnly => "PIB attribute is read only",
+            Status::ScanInProgress => "a scan is already in progress",
+            Status::SuperframeOverlap => "superframe overlaps the one of the coordinator",
+            Status::AutoAckPendingAllOn => "pending bit set on every acknowledgement",
+            Status::AutoAckPendingAllOff => "pending bit not set on acknowledgements",
+            Status::Unknown(_) => "unknown status",
+        }
+    }
+}
+
+// Decodes without a cursor, e.g. a status from a log. Unknown values are
+// refused as when decoding a frame.
+impl TryFrom<u8> for Status {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Status, Error> {
+        Status::from_decoded(value, DecodeOptions::default(), Error::InvalidStatus)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StatusCategory {
+    Success,
+    // Failures of the MAC layer and of the stack API.
+    Mac,
+    Fh,
+    Security,
+    // The coprocessor rejected the MT command itself.
+    Rpc,
+    Unknown,
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AddressMode {
+    AddrNone = 0x00,
     Addr16Bit = 0x02,
     Addr64Bit = 0x03,
 }
 
 impl AddressMode {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidAddressMode(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        AddressMode::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -224,34 +581,127 @@ impl AddressMode {
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for AddressMode {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<AddressMode, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidAddressMode(value))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PanId(pub u16);
+
+impl PanId {
+    // Accepted by every PAN, never the identifier of a network.
+    pub const BROADCAST: PanId = PanId(0xffff);
+
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        Ok(PanId(cursor.read_u16_le()?))
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u16_le(self.0);
+    }
+
+    pub fn u16(&self) -> u16 {
+        self.0
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        *self == PanId::BROADCAST
+    }
+
+    /// Whether a network can use this identifier.
+    pub fn is_valid(&self) -> bool {
+        !self.is_broadcast()
+    }
+}
+
+impl From<u16> for PanId {
+    fn from(pan_id: u16) -> Self {
+        PanId(pan_id)
+    }
+}
+
+impl From<PanId> for u16 {
+    fn from(pan_id: PanId) -> Self {
+        pan_id.0
+    }
+}
+
+impl fmt::Display for PanId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x{:04x}", self.0)
+    }
+}
+
+impl fmt::LowerHex for PanId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShortAddress {
     pub address: u16,
 }
 
 impl ShortAddress {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
         Ok(ShortAddress {
-            address: cursor.get_u16_le(),
+            address: cursor.read_u16_le()?,
         })
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         buffer.put_u16_le(self.address);
     }
+
+    pub fn u16(&self) -> u16 {
+        self.address
+    }
+}
+
+impl From<u16> for ShortAddress {
+    fn from(address: u16) -> Self {
+        ShortAddress { address }
+    }
+}
+
+impl From<ShortAddress> for u16 {
+    fn from(address: ShortAddress) -> Self {
+        address.address
+    }
+}
+
+impl fmt::Display for ShortAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_colon_separated(f, &self.address.to_be_bytes())
+    }
+}
+
+impl fmt::LowerHex for ShortAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.address, f)
+    }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExtendedAddress {
     pub address: [u8; 8],
 }
 
 impl ExtendedAddress {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
         let mut address: [u8; 8] = Default::default();
-        cursor
-            .read_exact(&mut address)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.read_exact(&mut address)?;
         address.reverse();
         Ok(ExtendedAddress { address })
     }
@@ -259,22 +709,102 @@ impl ExtendedAddress {
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         buffer.extend(self.address.iter().rev());
     }
+
+    // The address is kept most significant byte first, the wire order is reversed.
+    pub fn u64(&self) -> u64 {
+        u64::from_be_bytes(self.address)
+    }
+}
+
+impl From<u64> for ExtendedAddress {
+    fn from(address: u64) -> Self {
+        ExtendedAddress {
+            address: address.to_be_bytes(),
+        }
+    }
+}
+
+impl From<ExtendedAddress> for u64 {
+    fn from(address: ExtendedAddress) -> Self {
+        address.u64()
+    }
+}
+
+impl fmt::Display for ExtendedAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_colon_separated(f, &self.address)
+    }
+}
+
+impl fmt::LowerHex for ExtendedAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.u64(), f)
+    }
+}
+
+// Accepts 00:12:4b:00:0b:c9:bd:80 and 00124b000bc9bd80, optionally with a 0x prefix.
+impl FromStr for ExtendedAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidExtendedAddress(s.to_string());
+        let hex = s.trim();
+        let hex = hex
+            .strip_prefix("0x")
+            .or_else(|| hex.strip_prefix("0X"))
+            .unwrap_or(hex);
+
+        let digits: String = if hex.contains(':') {
+            let octets: Vec<&str> = hex.split(':').collect();
+            if octets.len() != 8 || octets.iter().any(|octet| octet.len() != 2) {
+                return Err(invalid());
+            }
+            octets.concat()
+        } else {
+            hex.to_string()
+        };
+
+        if digits.len() != 16 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        u64::from_str_radix(&digits, 16)
+            .map(ExtendedAddress::from)
+            .map_err(|_| invalid())
+    }
+}
+
+fn write_colon_separated(f: &mut fmt::Formatter, bytes: &[u8]) -> fmt::Result {
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 {
+            f.write_str(":")?;
+        }
+        write!(f, "{:02x}", byte)?;
+    }
+    Ok(())
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Address {
+    None,
     Addr16Bit(ShortAddress),
     Addr64Bit(ExtendedAddress),
 }
 
 impl Address {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let address_mode = AddressMode::try_decode(Read::by_ref(cursor))?;
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let address_mode = AddressMode::try_decode(cursor)?;
 
         let address = match address_mode {
+            AddressMode::AddrNone => {
+                cursor.skip(8)?;
+                Address::None
+            }
             AddressMode::Addr16Bit => {
                 let address = Address::Addr16Bit(ShortAddress::try_decode(cursor)?);
-                std::io::BufRead::consume(cursor, 6);
+                cursor.skip(6)?;
                 address
             }
             AddressMode::Addr64Bit => Address::Addr64Bit(ExtendedAddress::try_decode(cursor)?),
@@ -285,6 +815,10 @@ impl Address {
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         match self {
+            Address::None => {
+                buffer.put_u8(AddressMode::AddrNone as u8);
+                buffer.extend_from_slice(&[0x00; 8]);
+            }
             Address::Addr16Bit(address) => {
                 buffer.put_u8(AddressMode::Addr16Bit as u8);
                 address.encode_into(buffer);
@@ -298,7 +832,41 @@ impl Address {
     }
 }
 
+impl From<ShortAddress> for Address {
+    fn from(address: ShortAddress) -> Self {
+        Address::Addr16Bit(address)
+    }
+}
+
+impl From<ExtendedAddress> for Address {
+    fn from(address: ExtendedAddress) -> Self {
+        Address::Addr64Bit(address)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Address::None => f.write_str("none"),
+            Address::Addr16Bit(address) => fmt::Display::fmt(address, f),
+            Address::Addr64Bit(address) => fmt::Display::fmt(address, f),
+        }
+    }
+}
+
+impl fmt::LowerHex for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Address::None => f.write_str("none"),
+            Address::Addr16Bit(address) => fmt::LowerHex::fmt(address, f),
+            Address::Addr64Bit(address) => fmt::LowerHex::fmt(address, f),
+        }
+    }
+}
+
 bitflags! {
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
     pub struct TxOption: u8 {
         // Non-acknowledged transmission.
         const NOACK = 0x00;
@@ -333,17 +901,89 @@ bitflags! {
 }
 
 impl TxOption {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        TxOption::try_from(cursor.read_u8()?)
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u8(self.bits);
+    }
+}
+
+impl TryFrom<u8> for TxOption {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<TxOption, Error> {
         TxOption::from_bits(value).ok_or(Error::InvalidTxOption(value))
     }
+}
+
+bitflags! {
+    // Wi-SUN IEs the coprocessor generates and appends to a data request,
+    // the IncludeFhIEs field of MAC_DATA_REQ.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct FHIncludeIe: u32 {
+        // Header IEs.
+        const UTT = 0x0000_0002;
+        const RSL = 0x0000_0004;
+        const BT = 0x0000_0008;
+
+        // Payload IEs nested in the WP-IE.
+        const US = 0x0001_0000;
+        const BS = 0x0002_0000;
+        const PAN = 0x0004_0000;
+        const NETNAME = 0x0008_0000;
+        const PAN_VERSION = 0x0010_0000;
+        const GTK_HASH = 0x0020_0000;
+    }
+}
+
+bitflags! {
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct CapabilityInfo: u8 {
+        // The device is capable of becoming a PAN coordinator.
+        const ALTERNATE_PAN_COORDINATOR = 0x01;
+
+        // The device is a full-function device (FFD).
+        const FFD = 0x02;
+
+        // The device is mains powered.
+        const MAINS_POWERED = 0x04;
+
+        // The receiver is enabled during idle periods.
+        const RX_ON_WHEN_IDLE = 0x08;
+
+        // The device is capable of sending and receiving secured frames.
+        const SECURITY = 0x40;
+
+        // The device requests the coordinator to allocate a short address.
+        const ALLOCATE_ADDRESS = 0x80;
+    }
+}
+
+impl CapabilityInfo {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        CapabilityInfo::try_from(cursor.read_u8()?)
+    }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         buffer.put_u8(self.bits);
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for CapabilityInfo {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<CapabilityInfo, Error> {
+        CapabilityInfo::from_bits(value).ok_or(Error::InvalidCapabilityInfo(value))
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SecurityLevel {
     NoSecurity = 0x00,
     MIC32Auth = 0x01,
@@ -356,9 +996,8 @@ pub enum SecurityLevel {
 }
 
 impl SecurityLevel {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidSecurityLevel(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        SecurityLevel::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -366,7 +1005,17 @@ impl SecurityLevel {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for SecurityLevel {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<SecurityLevel, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidSecurityLevel(value))
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KeyIdMode {
     NotUsed = 0x00,
     Key1ByteIndex = 0x01,
@@ -375,9 +1024,8 @@ pub enum KeyIdMode {
 }
 
 impl KeyIdMode {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidKeyIdMode(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        KeyIdMode::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -385,17 +1033,25 @@ impl KeyIdMode {
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for KeyIdMode {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<KeyIdMode, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidKeyIdMode(value))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeySource {
     pub key: [u8; 8],
 }
 
 impl KeySource {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
         let mut key: [u8; 8] = Default::default();
-        cursor
-            .read_exact(&mut key)
-            .map_err(|_| Error::NotEnoughBytes)?;
+        cursor.read_exact(&mut key)?;
         Ok(KeySource { key })
     }
 
@@ -404,120 +1060,279 @@ impl KeySource {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
-pub enum WiSUNAsyncFrameType {
-    PANAdvert = 0x00,
-    PANAdvertSOL = 0x01,
-    PANConfig = 0x02,
-    PANConfigSOL = 0x03,
-    Data = 0x04,
-    Ack = 0x05,
-    EAPOL = 0x06,
-    Invalid = 0xFF,
+// Security parameters closing most MAC requests and indications.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Security {
+    pub key_source: KeySource,
+    pub security_level: SecurityLevel,
+    pub key_id_mode: KeyIdMode,
+    pub key_index: u8,
 }
 
-impl WiSUNAsyncFrameType {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidFrameType(value))
+impl Security {
+    pub fn none() -> Security {
+        Security {
+            key_source: KeySource { key: [0x00; 8] },
+            security_level: SecurityLevel::NoSecurity,
+            key_id_mode: KeyIdMode::NotUsed,
+            key_index: 0x00,
+        }
     }
 
-    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
-        buffer.put_u8(*self as u8);
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let key_source = KeySource::try_decode(cursor)?;
+        let security_level = SecurityLevel::try_decode(cursor)?;
+        let key_id_mode = KeyIdMode::try_decode(cursor)?;
+        let key_index = cursor.read_u8()?;
+        Ok(Security {
+            key_source,
+            security_level,
+            key_id_mode,
+            key_index,
+        })
     }
-}
-
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
-pub enum AssociationStatus {
-    Successful = 0x00,
-    PANAtCapacity = 0x01,
-    PANAccessDenied = 0x02,
-}
 
-impl AssociationStatus {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidAssociationStatus(value))
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        self.key_source.encode_into(buffer);
+        self.security_level.encode_into(buffer);
+        self.key_id_mode.encode_into(buffer);
+        buffer.put_u8(self.key_index);
+    }
+}
+
+impl Default for Security {
+    fn default() -> Self {
+        Security::none()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PanDescriptor {
+    pub timestamp: u32,
+    pub coord_address: Address,
+    pub pan_id: PanId,
+    pub superframe_spec: SuperframeSpec,
+    pub logical_channel: u8,
+    pub channel_page: u8,
+    pub gts_permit: bool,
+    pub link_quality: u8,
+    pub security_failure: bool,
+    pub security: Security,
+}
+
+impl PanDescriptor {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let timestamp = cursor.read_u32_le()?;
+        let coord_address = Address::try_decode(cursor)?;
+        let pan_id = PanId::try_decode(cursor)?;
+        let superframe_spec = SuperframeSpec::try_decode(cursor)?;
+        let logical_channel = cursor.read_u8()?;
+        let channel_page = cursor.read_u8()?;
+        let gts_permit = cursor.read_u8()? != 0;
+        let link_quality = cursor.read_u8()?;
+        let security_failure = cursor.read_u8()? != 0;
+        let security = Security::try_decode(cursor)?;
+        Ok(PanDescriptor {
+            timestamp,
+            coord_address,
+            pan_id,
+            superframe_spec,
+            logical_channel,
+            channel_page,
+            gts_permit,
+            link_quality,
+            security_failure,
+            security,
+        })
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
-        buffer.put_u8(*self as u8);
+        buffer.put_u32_le(self.timestamp);
+        self.coord_address.encode_into(buffer);
+        self.pan_id.encode_into(buffer);
+        self.superframe_spec.encode_into(buffer);
+        buffer.put_u8(self.logical_channel);
+        buffer.put_u8(self.channel_page);
+        buffer.put_u8(if self.gts_permit { 1 } else { 0 });
+        buffer.put_u8(self.link_quality);
+        buffer.put_u8(if self.security_failure { 1 } else { 0 });
+        self.security.encode_into(buffer);
+    }
+}
+
+// Superframe specification field of IEEE 802.15.4 beacons. Bit 13 is reserved.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuperframeSpec {
+    pub beacon_order: u8,
+    pub superframe_order: u8,
+    pub final_cap_slot: u8,
+    pub battery_life_extension: bool,
+    pub pan_coordinator: bool,
+    pub association_permit: bool,
+}
+
+impl SuperframeSpec {
+    pub fn from_u16(value: u16) -> SuperframeSpec {
+        SuperframeSpec {
+            beacon_order: (value & 0x0f) as u8,
+            superframe_order: ((value >> 4) & 0x0f) as u8,
+            final_cap_slot: ((value >> 8) & 0x0f) as u8,
+            battery_life_extension: value & (1 << 12) != 0,
+            pan_coordinator: value & (1 << 14) != 0,
+            association_permit: value & (1 << 15) != 0,
+        }
     }
-}
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
-pub enum DisassociateReason {
-    Reserved = 0x00,
-    CoorWishesDevLeave = 0x01,
-    DevWishesLeave = 0x02,
-}
+    pub fn to_u16(&self) -> u16 {
+        u16::from(self.beacon_order & 0x0f)
+            | u16::from(self.superframe_order & 0x0f) << 4
+            | u16::from(self.final_cap_slot & 0x0f) << 8
+            | u16::from(self.battery_life_extension) << 12
+            | u16::from(self.pan_coordinator) << 14
+            | u16::from(self.association_permit) << 15
+    }
 
-impl DisassociateReason {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidDisassociationReason(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        Ok(SuperframeSpec::from_u16(cursor.read_u16_le()?))
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
-        buffer.put_u8(*self as u8);
+        buffer.put_u16_le(self.to_u16());
+    }
+
+    // Beacon order 15 means the coordinator does not send periodic beacons.
+    pub fn is_beacon_enabled(&self) -> bool {
+        self.beacon_order < 15
+    }
+}
+
+// Contents of the MPM coexistence specification IE carried by enhanced beacons.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoexistenceSpec {
+    pub beacon_order: u8,
+    pub super_frame_order: u8,
+    pub final_cap_slot: u8,
+    pub enh_beacon_order: u8,
+    pub ofs_time_slot: u8,
+    pub cap_back_off: u8,
+    pub non_beacon_order: u16,
+}
+
+impl CoexistenceSpec {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let beacon_order = cursor.read_u8()?;
+        let super_frame_order = cursor.read_u8()?;
+        let final_cap_slot = cursor.read_u8()?;
+        let enh_beacon_order = cursor.read_u8()?;
+        let ofs_time_slot = cursor.read_u8()?;
+        let cap_back_off = cursor.read_u8()?;
+        let non_beacon_order = cursor.read_u16_le()?;
+        Ok(CoexistenceSpec {
+            beacon_order,
+            super_frame_order,
+            final_cap_slot,
+            enh_beacon_order,
+            ofs_time_slot,
+            cap_back_off,
+            non_beacon_order,
+        })
     }
-}
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
-pub enum MACPIBAttributeId {
-    AckWaitDuration = 0x40,
-    AssociationPermit = 0x41,
-    AutoRequest = 0x42,
-    BattLifeExt = 0x43,
-    BattLeftExtPeriods = 0x44,
-    BeaconPayload = 0x45,
-    BeaconPayloadLength = 0x46,
-    BeaconOrder = 0x47,
-    BeaconTxTime = 0x48,
-    BSN = 0x49,
-    CoordExtendedAddress = 0x4A,
-    CoordShortAddress = 0x4B,
-    DSN = 0x4C,
-    GTSPermit = 0x4D,
-    MaxCSMABackoffs = 0x4E,
-    MinBE = 0x4F,
-    PANId = 0x50,
-    PromiscuousMode = 0x51,
-    RxOnWhenIdle = 0x52,
-    ShortAddress = 0x53,
-    SuperframeOrder = 0x54,
-    TransactionPersistenceTime = 0x55,
-    AssociatedPANCoord = 0x56,
-    MaxBE = 0x57,
-    FrameTotalWaitTime = 0x58,
-    MaxFrameRetries = 0x59,
-    ResponseWaitTime = 0x5A,
-    SyncSymbolOffset = 0x5B,
-    TimestampSupported = 0x5C,
-    SecurityEnabled = 0x5D,
-    EBSN = 0x5E,
-    EBeaconOrder = 0x5F,
-    EBeaconOrderNBPAN = 0x60,
-    OffsetTimeslot = 0x61,
-    IncludeMPMIE = 0x62,
-    PhyFSKPreambleLen = 0x63,
-    PhyMRFSKSFD = 0x64,
-    PhyTransmitPowerSigned = 0xE0,
-    LogicalChannel = 0xE1,
-    ExtendedAddress = 0xE2,
-    AltBE = 0xE3,
-    DeviceBeaconOrder = 0xE4,
-    RF4CEPowerSavings = 0xE5,
-    FrameVersionSupport = 0xE6,
-    ChannelPage = 0xE7,
-    PhyCurrentDescriptorId = 0xE8,
-    FCSType = 0xE9,
-}
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u8(self.beacon_order);
+        buffer.put_u8(self.super_frame_order);
+        buffer.put_u8(self.final_cap_slot);
+        buffer.put_u8(self.enh_beacon_order);
+        buffer.put_u8(self.ofs_time_slot);
+        buffer.put_u8(self.cap_back_off);
+        buffer.put_u16_le(self.non_beacon_order);
+    }
 
-impl MACPIBAttributeId {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidMACPIBAttributeId(value))
+    // Beacon order 15 means the coordinator does not send periodic beacons.
+    pub fn is_beacon_enabled(&self) -> bool {
+        self.beacon_order < 15
+    }
+}
+
+open_enum! {
+    pub enum WiSUNAsyncFrameType: u8 {
+        PANAdvert = 0x00,
+        PANAdvertSOL = 0x01,
+        PANConfig = 0x02,
+        PANConfigSOL = 0x03,
+        Data = 0x04,
+        Ack = 0x05,
+        EAPOL = 0x06,
+        Invalid = 0xFF,
+    }
+}
+
+impl WiSUNAsyncFrameType {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let value = cursor.read_u8()?;
+        WiSUNAsyncFrameType::from_decoded(value, cursor.options(), Error::InvalidFrameType)
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u8(self.raw());
+    }
+}
+
+impl TryFrom<u8> for WiSUNAsyncFrameType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<WiSUNAsyncFrameType, Error> {
+        WiSUNAsyncFrameType::from_decoded(value, DecodeOptions::default(), Error::InvalidFrameType)
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AssociationStatus {
+    Successful = 0x00,
+    PANAtCapacity = 0x01,
+    PANAccessDenied = 0x02,
+}
+
+impl AssociationStatus {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        AssociationStatus::try_from(cursor.read_u8()?)
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u8(*self as u8);
+    }
+}
+
+impl TryFrom<u8> for AssociationStatus {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<AssociationStatus, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidAssociationStatus(value))
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DisassociateReason {
+    Reserved = 0x00,
+    CoorWishesDevLeave = 0x01,
+    DevWishesLeave = 0x02,
+}
+
+impl DisassociateReason {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        DisassociateReason::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -525,73 +1340,371 @@ impl MACPIBAttributeId {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
-pub enum FHPIBAttributeId {
-    TrackParentEUI = 0x2000,
-    BCInterval = 0x2001,
-    UCExcludedChannels = 0x2002,
-    BCExcludedChannels = 0x2003,
-    UCDwellInterval = 0x2004,
-    BCDwellInterval = 0x2005,
-    ClockDrift = 0x2006,
-    TimingAccuracy = 0x2007,
-    UCChannelFunction = 0x2008,
-    BCChannelFunction = 0x2009,
-    UseParentBSIE = 0x200A,
-    BrocastSchedId = 0x200B,
-    UCFixedChannel = 0x200C,
-    BCFixedChannel = 0x200D,
-    PANSize = 0x200E,
-    RoutingCost = 0x200F,
-    RoutingMethod = 0x2010,
-    EAPOLReady = 0x2011,
-    FANTPSVersion = 0x2012,
-    NetName = 0x2013,
-    PANVersion = 0x2014,
-    GTK0Hash = 0x2015,
-    GTK1Hash = 0x2016,
-    GTK2Hash = 0x2017,
-    GTK3Hash = 0x2018,
-    NeighborValidTime = 0x2019,
+impl TryFrom<u8> for DisassociateReason {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<DisassociateReason, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidDisassociationReason(value))
+    }
+}
+
+open_enum! {
+    pub enum MACPIBAttributeId: u8 {
+        AckWaitDuration = 0x40,
+        AssociationPermit = 0x41,
+        AutoRequest = 0x42,
+        BattLifeExt = 0x43,
+        BattLeftExtPeriods = 0x44,
+        BeaconPayload = 0x45,
+        BeaconPayloadLength = 0x46,
+        BeaconOrder = 0x47,
+        BeaconTxTime = 0x48,
+        BSN = 0x49,
+        CoordExtendedAddress = 0x4A,
+        CoordShortAddress = 0x4B,
+        DSN = 0x4C,
+        GTSPermit = 0x4D,
+        MaxCSMABackoffs = 0x4E,
+        MinBE = 0x4F,
+        PANId = 0x50,
+        PromiscuousMode = 0x51,
+        RxOnWhenIdle = 0x52,
+        ShortAddress = 0x53,
+        SuperframeOrder = 0x54,
+        TransactionPersistenceTime = 0x55,
+        AssociatedPANCoord = 0x56,
+        MaxBE = 0x57,
+        FrameTotalWaitTime = 0x58,
+        MaxFrameRetries = 0x59,
+        ResponseWaitTime = 0x5A,
+        SyncSymbolOffset = 0x5B,
+        TimestampSupported = 0x5C,
+        SecurityEnabled = 0x5D,
+        EBSN = 0x5E,
+        EBeaconOrder = 0x5F,
+        EBeaconOrderNBPAN = 0x60,
+        OffsetTimeslot = 0x61,
+        IncludeMPMIE = 0x62,
+        PhyFSKPreambleLen = 0x63,
+        PhyMRFSKSFD = 0x64,
+        PhyTransmitPowerSigned = 0xE0,
+        LogicalChannel = 0xE1,
+        ExtendedAddress = 0xE2,
+        AltBE = 0xE3,
+        DeviceBeaconOrder = 0xE4,
+        RF4CEPowerSavings = 0xE5,
+        FrameVersionSupport = 0xE6,
+        ChannelPage = 0xE7,
+        PhyCurrentDescriptorId = 0xE8,
+        FCSType = 0xE9,
+    }
+}
+
+impl MACPIBAttributeId {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let value = cursor.read_u8()?;
+        MACPIBAttributeId::from_decoded(value, cursor.options(), Error::InvalidMACPIBAttributeId)
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u8(self.raw());
+    }
+}
+
+impl TryFrom<u8> for MACPIBAttributeId {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<MACPIBAttributeId, Error> {
+        MACPIBAttributeId::from_decoded(
+            value,
+            DecodeOptions::default(),
+            Error::InvalidMACPIBAttributeId,
+        )
+    }
+}
+
+open_enum! {
+    pub enum FHPIBAttributeId: u16 {
+        TrackParentEUI = 0x2000,
+        BCInterval = 0x2001,
+        UCExcludedChannels = 0x2002,
+        BCExcludedChannels = 0x2003,
+        UCDwellInterval = 0x2004,
+        BCDwellInterval = 0x2005,
+        ClockDrift = 0x2006,
+        TimingAccuracy = 0x2007,
+        UCChannelFunction = 0x2008,
+        BCChannelFunction = 0x2009,
+        UseParentBSIE = 0x200A,
+        BrocastSchedId = 0x200B,
+        UCFixedChannel = 0x200C,
+        BCFixedChannel = 0x200D,
+        PANSize = 0x200E,
+        RoutingCost = 0x200F,
+        RoutingMethod = 0x2010,
+        EAPOLReady = 0x2011,
+        FANTPSVersion = 0x2012,
+        NetName = 0x2013,
+        PANVersion = 0x2014,
+        GTK0Hash = 0x2015,
+        GTK1Hash = 0x2016,
+        GTK2Hash = 0x2017,
+        GTK3Hash = 0x2018,
+        NeighborValidTime = 0x2019,
+    }
 }
 
 impl FHPIBAttributeId {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u16_le();
-        FromPrimitive::from_u16(value).ok_or(Error::InvalidFHPIBAttributeId(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let value = cursor.read_u16_le()?;
+        FHPIBAttributeId::from_decoded(value, cursor.options(), Error::InvalidFHPIBAttributeId)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
-        buffer.put_u16_le(*self as u16);
+        buffer.put_u16_le(self.raw());
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
-pub enum SecurityPIBAttributeId {
-    KeyTable = 0x71,
-    KeyTableEntries = 0x81,
-    DeviceTableEntries = 0x82,
-    SecurityLevelTableEntries = 0x83,
-    FrameCounter = 0x84,
-    AutoRequestSecurityLevel = 0x85,
-    AutoRequestKeyIdMode = 0x86,
-    AutoRequestKeySource = 0x87,
-    AutoRequestKeyIndex = 0x88,
-    DefaultKeySource = 0x89,
-    PANCoordExtendedAddress = 0x8A,
-    PANCoordShortAddress = 0x8B,
-    KeyIdLookupEntry = 0xD0,
-    KeyIdDeviceEntry = 0xD1,
-    KeyIdUsageEntry = 0xD2,
-    KeyEntry = 0xD3,
-    DeviceEntry = 0xD4,
-    SecurityLevelEntry = 0xD5,
+impl TryFrom<u16> for FHPIBAttributeId {
+    type Error = Error;
+
+    fn try_from(value: u16) -> Result<FHPIBAttributeId, Error> {
+        FHPIBAttributeId::from_decoded(
+            value,
+            DecodeOptions::default(),
+            Error::InvalidFHPIBAttributeId,
+        )
+    }
 }
 
-impl SecurityPIBAttributeId {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidSecurityPIBAttributeId(value))
+// Received signal strength in dBm, reported as a signed byte.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rssi(pub i8);
+
+impl Rssi {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        Ok(Rssi(cursor.read_i8()?))
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_i8(self.0);
+    }
+
+    pub fn dbm(&self) -> i8 {
+        self.0
+    }
+}
+
+impl From<i8> for Rssi {
+    fn from(dbm: i8) -> Self {
+        Rssi(dbm)
+    }
+}
+
+impl fmt::Display for Rssi {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} dBm", self.0)
+    }
+}
+
+// Link quality indicator, from 0x00 for the weakest usable link to 0xff for
+// the strongest.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lqi(pub u8);
+
+impl Lqi {
+    // The TI MAC derives the LQI linearly from the RSSI between the receiver
+    // sensitivity and its saturation level.
+    pub const MIN_DBM: i8 = -100;
+    pub const MAX_DBM: i8 = -10;
+
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        Ok(Lqi(cursor.read_u8()?))
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u8(self.0);
+    }
+
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+
+    pub fn percent(&self) -> u8 {
+        (u16::from(self.0) * 100 / 0xff) as u8
+    }
+
+    /// Signal strength this LQI stands for.
+    pub fn to_rssi(&self) -> Rssi {
+        let span = i16::from(Lqi::MAX_DBM) - i16::from(Lqi::MIN_DBM);
+        Rssi((i16::from(Lqi::MIN_DBM) + i16::from(self.0) * span / 0xff) as i8)
+    }
+
+    pub fn from_rssi(rssi: Rssi) -> Lqi {
+        let dbm = i16::from(rssi.0).clamp(i16::from(Lqi::MIN_DBM), i16::from(Lqi::MAX_DBM));
+        let span = i16::from(Lqi::MAX_DBM) - i16::from(Lqi::MIN_DBM);
+        Lqi(((dbm - i16::from(Lqi::MIN_DBM)) * 0xff / span) as u8)
+    }
+
+    /// Chip correlation of the received frame mapped onto 0 to 100, the radio
+    /// reports about 50 for the worst and 110 for the best frames.
+    pub fn correlation_quality(correlation: u8) -> u8 {
+        (u16::from(correlation.clamp(50, 110) - 50) * 100 / 60) as u8
+    }
+}
+
+impl From<u8> for Lqi {
+    fn from(value: u8) -> Self {
+        Lqi(value)
+    }
+}
+
+impl fmt::Display for Lqi {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LQI {}", self.0)
+    }
+}
+
+// Energy measured on a channel during an energy detect scan, higher means
+// busier.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnergyLevel(pub u8);
+
+impl EnergyLevel {
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+}
+
+impl From<u8> for EnergyLevel {
+    fn from(value: u8) -> Self {
+        EnergyLevel(value)
+    }
+}
+
+impl fmt::Display for EnergyLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ED {}", self.0)
+    }
+}
+
+// Value of the FH GTK0Hash to GTK3Hash attributes, the lowest 8 bytes of the
+// SHA-256 of each group temporal key.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GtkHash(pub [u8; 8]);
+
+impl GtkHash {
+    pub fn attribute_id(index: u8) -> Option<FHPIBAttributeId> {
+        match index {
+            0 => Some(FHPIBAttributeId::GTK0Hash),
+            1 => Some(FHPIBAttributeId::GTK1Hash),
+            2 => Some(FHPIBAttributeId::GTK2Hash),
+            3 => Some(FHPIBAttributeId::GTK3Hash),
+            _ => None,
+        }
+    }
+
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let mut hash = [0x00; 8];
+        cursor.read_exact(&mut hash)?;
+        Ok(GtkHash(hash))
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.extend(self.0.iter());
+    }
+}
+
+// Value of the FH NetName attribute, a string of up to 32 bytes padded with
+// zeros on the wire.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct NetName {
+    name: String,
+}
+
+impl NetName {
+    pub const SIZE: usize = 32;
+
+    pub fn new(name: &str) -> Result<NetName, Error> {
+        if name.len() > NetName::SIZE || name.contains('\0') {
+            return Err(Error::InvalidNetName(name.as_bytes().to_vec()));
+        }
+        Ok(NetName {
+            name: name.to_string(),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let mut bytes = [0x00; NetName::SIZE];
+        cursor.read_exact(&mut bytes)?;
+        let length = bytes
+            .iter()
+            .position(|byte| *byte == 0x00)
+            .unwrap_or(NetName::SIZE);
+        if bytes[length..].iter().any(|byte| *byte != 0x00) {
+            return Err(Error::InvalidNetName(bytes.to_vec()));
+        }
+        match core::str::from_utf8(&bytes[..length]) {
+            Ok(name) => NetName::new(name),
+            Err(_) => Err(Error::InvalidNetName(bytes.to_vec())),
+        }
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.extend(self.name.as_bytes());
+        buffer.resize(buffer.len() + NetName::SIZE - self.name.len(), 0x00);
+    }
+}
+
+impl TryFrom<String> for NetName {
+    type Error = Error;
+
+    fn try_from(name: String) -> Result<Self, Error> {
+        NetName::new(&name)
+    }
+}
+
+impl From<NetName> for String {
+    fn from(name: NetName) -> String {
+        name.name
+    }
+}
+
+impl fmt::Display for NetName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+// Value of the FH UCChannelFunction and BCChannelFunction attributes.
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChannelFunction {
+    Fixed = 0,
+    TR51CF = 1,
+    DH1CF = 2,
+    VendorDefined = 3,
+}
+
+impl ChannelFunction {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        ChannelFunction::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -599,7 +1712,151 @@ impl SecurityPIBAttributeId {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for ChannelFunction {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<ChannelFunction, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidEnumValue {
+            type_name: "ChannelFunction",
+            value: value.into(),
+        })
+    }
+}
+
+// Unicast and broadcast schedules as set through the FH PIB. Intervals are
+// in milliseconds, fixed channels are only used by `ChannelFunction::Fixed`.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FhSchedule {
+    pub uc_dwell_interval: u8,
+    pub uc_channel_function: ChannelFunction,
+    pub uc_fixed_channel: Option<u16>,
+    pub bc_interval: u32,
+    pub bc_dwell_interval: u8,
+    pub bc_channel_function: ChannelFunction,
+    pub bc_fixed_channel: Option<u16>,
+}
+
+impl FhSchedule {
+    /// Checks the fields against each other, the coprocessor only checks
+    /// them one by one.
+    pub fn validate(&self) -> Result<(), Error> {
+        match (self.uc_channel_function, self.uc_fixed_channel) {
+            (ChannelFunction::Fixed, None) => {
+                return Err(Error::InvalidFhSchedule("unicast fixed channel missing"))
+            }
+            (ChannelFunction::Fixed, Some(_)) => (),
+            (_, Some(_)) => {
+                return Err(Error::InvalidFhSchedule(
+                    "unicast fixed channel set while hopping",
+                ))
+            }
+            (_, None) if self.uc_dwell_interval == 0 => {
+                return Err(Error::InvalidFhSchedule("unicast dwell interval is zero"))
+            }
+            (_, None) => (),
+        }
+        match (self.bc_channel_function, self.bc_fixed_channel) {
+            (ChannelFunction::Fixed, None) => {
+                return Err(Error::InvalidFhSchedule("broadcast fixed channel missing"))
+            }
+            (ChannelFunction::Fixed, Some(_)) => (),
+            (_, Some(_)) => {
+                return Err(Error::InvalidFhSchedule(
+                    "broadcast fixed channel set while hopping",
+                ))
+            }
+            (_, None) => (),
+        }
+        // A zero broadcast interval disables the broadcast schedule.
+        if self.bc_interval != 0 {
+            if self.bc_dwell_interval == 0 {
+                return Err(Error::InvalidFhSchedule("broadcast dwell interval is zero"));
+            }
+            if u32::from(self.bc_dwell_interval) > self.bc_interval {
+                return Err(Error::InvalidFhSchedule(
+                    "broadcast dwell interval exceeds the broadcast interval",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+open_enum! {
+    pub enum SecurityPIBAttributeId: u8 {
+        KeyTable = 0x71,
+        KeyTableEntries = 0x81,
+        DeviceTableEntries = 0x82,
+        SecurityLevelTableEntries = 0x83,
+        FrameCounter = 0x84,
+        AutoRequestSecurityLevel = 0x85,
+        AutoRequestKeyIdMode = 0x86,
+        AutoRequestKeySource = 0x87,
+        AutoRequestKeyIndex = 0x88,
+        DefaultKeySource = 0x89,
+        PANCoordExtendedAddress = 0x8A,
+        PANCoordShortAddress = 0x8B,
+        KeyIdLookupEntry = 0xD0,
+        KeyIdDeviceEntry = 0xD1,
+        KeyIdUsageEntry = 0xD2,
+        KeyEntry = 0xD3,
+        DeviceEntry = 0xD4,
+        SecurityLevelEntry = 0xD5,
+    }
+}
+
+impl SecurityPIBAttributeId {
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let value = cursor.read_u8()?;
+        SecurityPIBAttributeId::from_decoded(
+            value,
+            cursor.options(),
+            Error::InvalidSecurityPIBAttributeId,
+        )
+    }
+
+    pub fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.put_u8(self.raw());
+    }
+}
+
+impl TryFrom<u8> for SecurityPIBAttributeId {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<SecurityPIBAttributeId, Error> {
+        SecurityPIBAttributeId::from_decoded(
+            value,
+            DecodeOptions::default(),
+            Error::InvalidSecurityPIBAttributeId,
+        )
+    }
+}
+
+// An attribute of any of the MAC's PIBs.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PibAttribute {
+    Mac(MACPIBAttributeId),
+    Fh(FHPIBAttributeId),
+    Security(SecurityPIBAttributeId),
+}
+
+impl fmt::Display for PibAttribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PibAttribute::Mac(attribute_id) => write!(f, "MAC PIB {:?}", attribute_id),
+            PibAttribute::Fh(attribute_id) => write!(f, "FH PIB {:?}", attribute_id),
+            PibAttribute::Security(attribute_id) => write!(f, "security PIB {:?}", attribute_id),
+        }
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ScanType {
     EnergyDetect = 0x00,
     Active = 0x01,
@@ -609,9 +1866,8 @@ pub enum ScanType {
 }
 
 impl ScanType {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidScanType(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        ScanType::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -619,43 +1875,138 @@ impl ScanType {
     }
 }
 
+impl TryFrom<u8> for ScanType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<ScanType, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidScanType(value))
+    }
+}
+
 #[allow(non_camel_case_types)]
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PhyId {
-    STD_US_915_PHY_1 = 0x01,
-    STD_ETSI_863_PHY_3 = 0x03,
-    GENERIC_CHINA_433_PHY_128 = 128,
-    GENERIC_US_LRM_915_PHY_129 = 129,
-    GENERIC_CHINA_LRM_433_PHY_130 = 130,
-    GENERIC_ETSI_LRM_863_PHY_131 = 131,
-    GENERIC_US_915_PHY_132 = 132,
-    GENERIC_ETSI_863_PHY_133 = 133,
-    PHY_ID_NONE = 0,
-    MRFSK_GENERIC_PHY_ID_BEGIN = 0x04,
-    MRFSK_GENERIC_PHY_ID_END = 0x06,
+    STD_US_915_PHY_1,
+    STD_ETSI_863_PHY_3,
+    GENERIC_CHINA_433_PHY_128,
+    GENERIC_US_LRM_915_PHY_129,
+    GENERIC_CHINA_LRM_433_PHY_130,
+    GENERIC_ETSI_LRM_863_PHY_131,
+    GENERIC_US_915_PHY_132,
+    GENERIC_ETSI_863_PHY_133,
+    PHY_ID_NONE,
+    MRFSK_GENERIC_PHY_ID_BEGIN,
+    MRFSK_GENERIC_PHY_ID_END,
+    // Ids without a name here, e.g. the 920 MHz Japan PHYs or custom PHYs
+    // added to the coprocessor's RF configuration.
+    Other(u8),
 }
 
 impl PhyId {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidPhyId(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        Ok(PhyId::from_u8(cursor.read_u8()?))
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
-        buffer.put_u8(*self as u8);
+        buffer.put_u8(self.u8());
+    }
+
+    pub fn from_u8(value: u8) -> PhyId {
+        use PhyId::*;
+        match value {
+            0x00 => PHY_ID_NONE,
+            0x01 => STD_US_915_PHY_1,
+            0x03 => STD_ETSI_863_PHY_3,
+            0x04 => MRFSK_GENERIC_PHY_ID_BEGIN,
+            0x06 => MRFSK_GENERIC_PHY_ID_END,
+            128 => GENERIC_CHINA_433_PHY_128,
+            129 => GENERIC_US_LRM_915_PHY_129,
+            130 => GENERIC_CHINA_LRM_433_PHY_130,
+            131 => GENERIC_ETSI_LRM_863_PHY_131,
+            132 => GENERIC_US_915_PHY_132,
+            133 => GENERIC_ETSI_863_PHY_133,
+            value => Other(value),
+        }
+    }
+
+    pub fn u8(&self) -> u8 {
+        use PhyId::*;
+        match *self {
+            PHY_ID_NONE => 0x00,
+            STD_US_915_PHY_1 => 0x01,
+            STD_ETSI_863_PHY_3 => 0x03,
+            MRFSK_GENERIC_PHY_ID_BEGIN => 0x04,
+            MRFSK_GENERIC_PHY_ID_END => 0x06,
+            GENERIC_CHINA_433_PHY_128 => 128,
+            GENERIC_US_LRM_915_PHY_129 => 129,
+            GENERIC_CHINA_LRM_433_PHY_130 => 130,
+            GENERIC_ETSI_LRM_863_PHY_131 => 131,
+            GENERIC_US_915_PHY_132 => 132,
+            GENERIC_ETSI_863_PHY_133 => 133,
+            Other(value) => value,
+        }
+    }
+
+    // Ids 1 to 127 are PHYs from the IEEE 802.15.4 standard, generic PHYs
+    // defined by TI start at 128.
+    pub fn is_standard(&self) -> bool {
+        (0x01..0x80).contains(&self.u8())
+    }
+
+    pub fn is_generic(&self) -> bool {
+        self.u8() >= 0x80
+    }
+
+    pub fn is_long_range(&self) -> bool {
+        use PhyId::*;
+        matches!(
+            self,
+            GENERIC_US_LRM_915_PHY_129
+                | GENERIC_CHINA_LRM_433_PHY_130
+                | GENERIC_ETSI_LRM_863_PHY_131
+        )
+    }
+
+    /// Channels the PHY defines, `None` for PHYs whose band plan is not
+    /// known here.
+    pub fn channel_count(&self) -> Option<u8> {
+        use PhyId::*;
+        match self {
+            STD_US_915_PHY_1 | GENERIC_US_LRM_915_PHY_129 => Some(129),
+            STD_ETSI_863_PHY_3 | GENERIC_ETSI_LRM_863_PHY_131 => Some(34),
+            GENERIC_CHINA_433_PHY_128 | GENERIC_CHINA_LRM_433_PHY_130 => Some(7),
+            GENERIC_US_915_PHY_132 => Some(64),
+            GENERIC_ETSI_863_PHY_133 => Some(17),
+            _ => None,
+        }
+    }
+}
+
+impl From<u8> for PhyId {
+    fn from(value: u8) -> Self {
+        PhyId::from_u8(value)
+    }
+}
+
+impl From<PhyId> for u8 {
+    fn from(phy_id: PhyId) -> Self {
+        phy_id.u8()
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PermitJoin {
     AllBeaconRequests = 0x00,
     OnlyIfPermitJoinIsEnabled = 0x01,
 }
 
 impl PermitJoin {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidPhyId(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        PermitJoin::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -663,16 +2014,25 @@ impl PermitJoin {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for PermitJoin {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<PermitJoin, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidPermitJoin(value))
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MPMScan {
     Disabled = 0x00,
     Enabled = 0x01,
 }
 
 impl MPMScan {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidPhyId(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        MPMScan::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -680,16 +2040,25 @@ impl MPMScan {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for MPMScan {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<MPMScan, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidMPMScan(value))
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MPMType {
     BPAN = 0x01,  // Beacon Enabled
     NBPAN = 0x02, // Non-beacon Enabled
 }
 
 impl MPMType {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidPhyId(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        MPMType::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -697,16 +2066,25 @@ impl MPMType {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for MPMType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<MPMType, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidMPMType(value))
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WiSUNAsyncOperation {
     Start = 0x00,
     Stop = 0x01,
 }
 
 impl WiSUNAsyncOperation {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidPhyId(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        WiSUNAsyncOperation::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -714,7 +2092,17 @@ impl WiSUNAsyncOperation {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for WiSUNAsyncOperation {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<WiSUNAsyncOperation, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidWiSUNAsyncOperation(value))
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CommEventReason {
     AssociateRsp = 0x00, // Event sent in response to MAC_AssociateRsp().
     OrphanRsp = 0x01,    // Event sent in response to MAC_OrphanRsp().
@@ -722,9 +2110,8 @@ pub enum CommEventReason {
 }
 
 impl CommEventReason {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidPhyId(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        CommEventReason::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -732,16 +2119,25 @@ impl CommEventReason {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for CommEventReason {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<CommEventReason, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidCommEventReason(value))
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ResetType {
     Hard = 0,
     Soft = 1,
 }
 
 impl ResetType {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidPhyId(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        ResetType::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -749,16 +2145,28 @@ impl ResetType {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for ResetType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<ResetType, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidEnumValue {
+            type_name: "ResetType",
+            value: value.into(),
+        })
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TransportProtocolRevision {
     StandardRPCFrame = 2, // Standard RPC frame, no fragmentation
     ExtendedRPCFrame = 3, // Extended RPC frame, fragmentation
 }
 
 impl TransportProtocolRevision {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidPhyId(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        TransportProtocolRevision::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -766,16 +2174,28 @@ impl TransportProtocolRevision {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for TransportProtocolRevision {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<TransportProtocolRevision, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidEnumValue {
+            type_name: "TransportProtocolRevision",
+            value: value.into(),
+        })
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ProductIdCode {
     ZStack = 0,
     TI154Stack = 1,
 }
 
 impl ProductIdCode {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidPhyId(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        ProductIdCode::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -783,7 +2203,20 @@ impl ProductIdCode {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for ProductIdCode {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<ProductIdCode, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidEnumValue {
+            type_name: "ProductIdCode",
+            value: value.into(),
+        })
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ResetReason {
     Hardware = 0,
     HostRequest = 1,
@@ -793,9 +2226,8 @@ pub enum ResetReason {
 }
 
 impl ResetReason {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidPhyId(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        ResetReason::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -803,7 +2235,20 @@ impl ResetReason {
     }
 }
 
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+impl TryFrom<u8> for ResetReason {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<ResetReason, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidEnumValue {
+            type_name: "ResetReason",
+            value: value.into(),
+        })
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SubsystemId {
     Sys = 0x01,
     MAC = 0x02,
@@ -812,9 +2257,8 @@ pub enum SubsystemId {
 }
 
 impl SubsystemId {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidPhyId(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        SubsystemId::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -822,8 +2266,21 @@ impl SubsystemId {
     }
 }
 
+impl TryFrom<u8> for SubsystemId {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<SubsystemId, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidEnumValue {
+            type_name: "SubsystemId",
+            value: value.into(),
+        })
+    }
+}
+
 #[allow(non_camel_case_types)]
-#[derive(Debug, FromPrimitive, PartialEq, Copy, Clone)]
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExtendedAddressType {
     DEVICE_MAC_PIB = 0x00,
     DEVICE_PRIMARY = 0x01,
@@ -832,9 +2289,8 @@ pub enum ExtendedAddressType {
 }
 
 impl ExtendedAddressType {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidPhyId(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        ExtendedAddressType::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
@@ -842,27 +2298,175 @@ impl ExtendedAddressType {
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
-pub struct ChannelsBitMap {
-    pub channels: [u8; 17],
+impl TryFrom<u8> for ExtendedAddressType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<ExtendedAddressType, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidEnumValue {
+            type_name: "ExtendedAddressType",
+            value: value.into(),
+        })
+    }
+}
+
+// Logical channel number within the band plan of the current PHY.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Channel(pub u8);
+
+impl Channel {
+    pub fn u8(&self) -> u8 {
+        self.0
+    }
+
+    /// Center frequency in Hz, `None` for channels outside the PHY's band
+    /// plan or PHYs whose plan is not known here.
+    pub fn center_frequency(&self, phy: PhyId) -> Option<u32> {
+        use PhyId::*;
+        let (first, spacing) = match phy {
+            STD_US_915_PHY_1 | GENERIC_US_LRM_915_PHY_129 => (902_200_000, 200_000),
+            STD_ETSI_863_PHY_3 | GENERIC_ETSI_LRM_863_PHY_131 => (863_125_000, 200_000),
+            GENERIC_CHINA_433_PHY_128 | GENERIC_CHINA_LRM_433_PHY_130 => (433_300_000, 200_000),
+            GENERIC_US_915_PHY_132 => (902_400_000, 400_000),
+            GENERIC_ETSI_863_PHY_133 => (863_225_000, 400_000),
+            _ => return None,
+        };
+        if self.0 >= phy.channel_count()? {
+            return None;
+        }
+        Some(first + spacing * u32::from(self.0))
+    }
+}
+
+impl From<u8> for Channel {
+    fn from(channel: u8) -> Self {
+        Channel(channel)
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "channel {}", self.0)
+    }
+}
+
+// Bit n of the bitmap, counting from the least significant bit of the first
+// byte on the wire, enables channel n.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChannelMask {
+    bits: [u8; ChannelMask::SIZE],
 }
 
-impl ChannelsBitMap {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let mut channels: [u8; 17] = Default::default();
-        cursor
-            .read_exact(&mut channels)
-            .map_err(|_| Error::NotEnoughBytes)?;
-        channels.reverse();
-        Ok(ChannelsBitMap { channels })
+impl ChannelMask {
+    pub const SIZE: usize = 17;
+    pub const CHANNELS: usize = ChannelMask::SIZE * 8;
+
+    pub fn new() -> ChannelMask {
+        ChannelMask::default()
+    }
+
+    pub fn all() -> ChannelMask {
+        ChannelMask {
+            bits: [0xff; ChannelMask::SIZE],
+        }
+    }
+
+    pub fn from_bytes(bits: [u8; ChannelMask::SIZE]) -> ChannelMask {
+        ChannelMask { bits }
+    }
+
+    pub fn to_bytes(&self) -> [u8; ChannelMask::SIZE] {
+        self.bits
+    }
+
+    pub fn enable(&mut self, channel: u8) -> &mut Self {
+        if let Some(byte) = self.bits.get_mut(channel as usize / 8) {
+            *byte |= 1 << (channel % 8);
+        }
+        self
+    }
+
+    pub fn disable(&mut self, channel: u8) -> &mut Self {
+        if let Some(byte) = self.bits.get_mut(channel as usize / 8) {
+            *byte &= !(1 << (channel % 8));
+        }
+        self
+    }
+
+    pub fn enable_range(&mut self, channels: RangeInclusive<u8>) -> &mut Self {
+        for channel in channels {
+            self.enable(channel);
+        }
+        self
+    }
+
+    pub fn disable_range(&mut self, channels: RangeInclusive<u8>) -> &mut Self {
+        for channel in channels {
+            self.disable(channel);
+        }
+        self
+    }
+
+    pub fn is_enabled(&self, channel: u8) -> bool {
+        match self.bits.get(channel as usize / 8) {
+            Some(byte) => byte & (1 << (channel % 8)) != 0,
+            None => false,
+        }
+    }
+
+    pub fn channels(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..ChannelMask::CHANNELS)
+            .map(|channel| channel as u8)
+            .filter(move |channel| self.is_enabled(*channel))
+    }
+
+    pub fn count(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|byte| *byte == 0)
+    }
+
+    /// Channels enabled in both masks.
+    pub fn intersection(&self, other: &ChannelMask) -> ChannelMask {
+        let mut bits = self.bits;
+        for (byte, other) in bits.iter_mut().zip(other.bits.iter()) {
+            *byte &= other;
+        }
+        ChannelMask { bits }
+    }
+
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        let mut bits = [0x00; ChannelMask::SIZE];
+        cursor.read_exact(&mut bits)?;
+        Ok(ChannelMask { bits })
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
-        buffer.extend(self.channels.iter().rev());
+        buffer.extend(self.bits.iter());
     }
 }
 
-#[derive(Debug, FromPrimitive, Copy, Clone)]
+impl FromIterator<u8> for ChannelMask {
+    fn from_iter<I: IntoIterator<Item = u8>>(channels: I) -> Self {
+        let mut mask = ChannelMask::new();
+        for channel in channels {
+            mask.enable(channel);
+        }
+        mask
+    }
+}
+
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ErrorCode {
     InvalidSubsystem = 0x01,
     InvalidCommandId = 0x02,
@@ -873,12 +2477,19 @@ pub enum ErrorCode {
 }
 
 impl ErrorCode {
-    pub fn try_decode(cursor: &mut Cursor<&[u8]>) -> Result<Self, Error> {
-        let value = cursor.get_u8();
-        FromPrimitive::from_u8(value).ok_or(Error::InvalidErrorCode(value))
+    pub fn try_decode(cursor: &mut Reader) -> Result<Self, Error> {
+        ErrorCode::try_from(cursor.read_u8()?)
     }
 
     pub fn encode_into(&self, buffer: &mut Vec<u8>) {
         buffer.put_u8(*self as u8);
     }
 }
+
+impl TryFrom<u8> for ErrorCode {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<ErrorCode, Error> {
+        FromPrimitive::from_u8(value).ok_or(Error::InvalidErrorCode(value))
+    }
+}
@@ -0,0 +1,129 @@
+#[cfg(feature = "sys")]
+use crate::frame::MTFrame;
+#[cfg(feature = "sys")]
+use crate::subsystem::sys::areq::ResetReq;
+#[cfg(feature = "sys")]
+use crate::types::ResetType;
+
+pub trait BoardControl {
+    type Error;
+
+    fn hard_reset(&mut self) -> Result<(), Self::Error>;
+    fn reflash(&mut self) -> Result<(), Self::Error>;
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum RecoveryAction {
+    Retry,
+    SoftReset,
+    HardReset,
+    Reflash,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct RecoveryStep {
+    pub action: RecoveryAction,
+    // 1-based count of consecutive failures handled with this action.
+    pub attempt: u32,
+    // Set on the first failure handled with a more drastic action.
+    pub escalated: bool,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct WatchdogConfig {
+    pub retries: u32,
+    pub soft_resets: u32,
+    pub hard_resets: u32,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        WatchdogConfig {
+            retries: 3,
+            soft_resets: 2,
+            hard_resets: 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Watchdog {
+    config: WatchdogConfig,
+    consecutive_failures: u32,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Watchdog::new(WatchdogConfig::default())
+    }
+}
+
+impl Watchdog {
+    pub fn new(config: WatchdogConfig) -> Watchdog {
+        Watchdog {
+            config,
+            consecutive_failures: 0,
+        }
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    pub fn report_success(&mut self) {
+        self.consecutive_failures = 0;
+    }
+
+    /// Returns the action that should be taken to recover from the failure.
+    /// Once the re-flash stage is reached the escalation starts over.
+    pub fn report_failure(&mut self) -> RecoveryStep {
+        self.consecutive_failures += 1;
+
+        let stages = [
+            (RecoveryAction::Retry, self.config.retries),
+            (RecoveryAction::SoftReset, self.config.soft_resets),
+            (RecoveryAction::HardReset, self.config.hard_resets),
+        ];
+
+        let mut remaining = self.consecutive_failures;
+        let mut escalated = false;
+        for (action, threshold) in stages.iter() {
+            if remaining <= *threshold {
+                return RecoveryStep {
+                    action: *action,
+                    attempt: remaining,
+                    escalated: escalated && remaining == 1,
+                };
+            }
+            remaining -= threshold;
+            escalated = true;
+        }
+
+        self.consecutive_failures = 0;
+        RecoveryStep {
+            action: RecoveryAction::Reflash,
+            attempt: 1,
+            escalated: true,
+        }
+    }
+
+    /// Reports a failure and performs board level recovery actions. Retries
+    /// and soft resets are left to the caller, see `soft_reset_frame`.
+    pub fn recover<B: BoardControl>(&mut self, board: &mut B) -> Result<RecoveryStep, B::Error> {
+        let step = self.report_failure();
+        match step.action {
+            RecoveryAction::HardReset => board.hard_reset()?,
+            RecoveryAction::Reflash => board.reflash()?,
+            RecoveryAction::Retry | RecoveryAction::SoftReset => (),
+        }
+        Ok(step)
+    }
+}
+
+#[cfg(feature = "sys")]
+pub fn soft_reset_frame() -> MTFrame {
+    ResetReq {
+        reset_type: ResetType::Soft,
+    }
+    .into_mt_frame()
+}
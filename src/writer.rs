@@ -0,0 +1,29 @@
+use alloc::vec::Vec;
+
+// Little-endian writers, named after the `bytes` methods they replace. Some
+// are only needed by the MAC commands.
+#[cfg_attr(not(feature = "mac"), allow(dead_code))]
+pub trait BufMut {
+    fn put_u8(&mut self, value: u8);
+    fn put_i8(&mut self, value: i8);
+    fn put_u16_le(&mut self, value: u16);
+    fn put_u32_le(&mut self, value: u32);
+}
+
+impl BufMut for Vec<u8> {
+    fn put_u8(&mut self, value: u8) {
+        self.push(value);
+    }
+
+    fn put_i8(&mut self, value: i8) {
+        self.push(value as u8);
+    }
+
+    fn put_u16_le(&mut self, value: u16) {
+        self.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_u32_le(&mut self, value: u32) {
+        self.extend_from_slice(&value.to_le_bytes());
+    }
+}